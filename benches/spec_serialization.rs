@@ -0,0 +1,145 @@
+//! Benchmarks for serializing (and parsing) large specs, and for building the
+//! references to their definitions.
+//!
+//! Run with `cargo bench --features v2` (along with `simd-json` for comparing
+//! the parsers).
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use paperclip::v2::models::{DefaultApiRaw, DefaultSchema, ResolvableApi};
+use paperclip::v2::schema::definition_reference;
+use serde_json::{json, Value};
+
+/// Returns a spec with the given number of definitions (each with a list
+/// and a get operation).
+fn spec(definitions: usize) -> DefaultApiRaw {
+    let mut paths = serde_json::Map::new();
+    let mut defs = serde_json::Map::new();
+    for i in 0..definitions {
+        let name = format!("Model{}", i);
+        defs.insert(
+            name.clone(),
+            json!({
+                "type": "object",
+                "description": "Some model in the spec.",
+                "properties": {
+                    "id": { "type": "integer", "format": "int64" },
+                    "name": { "type": "string", "description": "Name of the model." },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "parent": { "$ref": format!("#/definitions/Model{}", i / 2) },
+                },
+                "required": ["id", "name"],
+            }),
+        );
+        paths.insert(
+            format!("/models{}", i),
+            json!({
+                "get": {
+                    "operationId": format!("listModels{}", i),
+                    "parameters": [
+                        { "in": "query", "name": "limit", "type": "integer" },
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "OK",
+                            "schema": { "type": "array", "items": { "$ref": format!("#/definitions/{}", name) } },
+                        },
+                    },
+                },
+            }),
+        );
+        paths.insert(
+            format!("/models{}/{{id}}", i),
+            json!({
+                "get": {
+                    "operationId": format!("getModel{}", i),
+                    "parameters": [
+                        { "in": "path", "name": "id", "type": "integer", "required": true },
+                    ],
+                    "responses": {
+                        "200": { "description": "OK", "schema": { "$ref": format!("#/definitions/{}", name) } },
+                    },
+                },
+            }),
+        );
+    }
+
+    serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": { "title": "Models", "version": "1.0" },
+        "definitions": defs,
+        "paths": paths,
+    }))
+    .expect("invalid spec")
+}
+
+fn serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    for size in [100, 1000] {
+        let spec = spec(size);
+        let bytes = serde_json::to_vec(&spec).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("to_vec", size), &spec, |b, spec| {
+            b.iter(|| serde_json::to_vec(spec).unwrap())
+        });
+        // This is what's done for serving specs with overlays (or hashes).
+        group.bench_with_input(BenchmarkId::new("to_value", size), &spec, |b, spec| {
+            b.iter(|| serde_json::to_vec(&serde_json::to_value(spec).unwrap()).unwrap())
+        });
+        let value = serde_json::to_value(&spec).unwrap();
+        group.bench_with_input(BenchmarkId::new("sha256", size), &value, |b, value| {
+            b.iter(|| paperclip::integrity::spec_sha256(value))
+        });
+    }
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for size in [100, 1000] {
+        let bytes = serde_json::to_vec(&spec(size)).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("value", size), &bytes, |b, bytes| {
+            b.iter(|| paperclip::v2::parse_json::<Value>(bytes).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("from_slice", size), &bytes, |b, bytes| {
+            b.iter(|| paperclip::v2::from_slice::<DefaultSchema>(bytes).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("from_reader", size), &bytes, |b, bytes| {
+            b.iter(|| {
+                let api: ResolvableApi<DefaultSchema> =
+                    paperclip::v2::from_reader(std::io::Cursor::new(bytes)).unwrap();
+                api
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn references(c: &mut Criterion) {
+    let mut group = c.benchmark_group("references");
+    let names: Vec<_> = (0..1000).map(|i| format!("Model{}", i)).collect();
+    group.throughput(Throughput::Elements(names.len() as u64));
+    // References are allocated once with the exact capacity.
+    group.bench_function("definition_reference", |b| {
+        b.iter(|| {
+            let refs: Vec<_> = names.iter().map(|n| definition_reference(n)).collect();
+            black_box(refs)
+        })
+    });
+    group.bench_function("formatted", |b| {
+        b.iter(|| {
+            let refs: Vec<_> = names
+                .iter()
+                .map(|n| format!("#/definitions/{}", n))
+                .collect();
+            black_box(refs)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, serialize, parse, references);
+criterion_main!(benches);
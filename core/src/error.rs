@@ -36,3 +36,25 @@ pub enum ValidationError {
     )]
     InvalidParameterType(String, String, Option<DataType>, ParameterIn),
 }
+
+/// Errors related to querying specs.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// The given expression is neither a JSON pointer nor a (supported) JSONPath expression.
+    #[error("Invalid query expression: {:?}", _0)]
+    InvalidExpression(String),
+    /// Failed to convert between the matching nodes and model structs.
+    #[error("JSON error: {}", _0)]
+    Json(serde_json::Error),
+}
+
+/// Errors related to building URLs of operations.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum RouteError {
+    /// The spec doesn't have an operation with the given ID.
+    #[error("Unknown operation {:?}", _0)]
+    UnknownOperation(String),
+    /// No value was given for the path parameter (in the given operation).
+    #[error("Missing value for path parameter {:?} of operation {:?}", _1, _0)]
+    MissingParameter(String, String),
+}
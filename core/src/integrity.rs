@@ -0,0 +1,140 @@
+//! Integrity metadata for specs.
+//!
+//! The SHA-256 hash of the canonicalized spec is embedded in the `x-spec-sha256`
+//! extension, so that consumers can check whether the spec served by (say) a gateway
+//! matches the one produced in CI. The canonical form is the compact JSON encoding
+//! with sorted keys (excluding the extension itself), so the hash doesn't depend on
+//! the format of the document or the order of its fields.
+//!
+//! ```rust
+//! use paperclip_core::integrity;
+//!
+//! let mut spec = serde_json::json!({"swagger": "2.0", "paths": {}});
+//! let hash = integrity::embed_sha256(&mut spec);
+//! assert_eq!(spec[integrity::SPEC_SHA256_EXTENSION], hash);
+//! assert_eq!(integrity::verify_sha256(&spec), Some(true));
+//!
+//! spec["paths"]["/pets"] = serde_json::json!({});
+//! assert_eq!(integrity::verify_sha256(&spec), Some(false));
+//! ```
+//!
+//! Each definition can also be fingerprinted (with the same hash of its canonical
+//! form), so that consumers can detect changes to the models they depend on without
+//! diffing the whole spec. Note that referenced definitions have fingerprints of
+//! their own, i.e., changing a definition doesn't change the fingerprints of the
+//! definitions referring to it.
+//!
+//! ```rust
+//! use paperclip_core::integrity;
+//! use serde_json::json;
+//!
+//! let pet = json!({"type": "object", "properties": {"name": {"type": "string"}}});
+//! let spec = json!({"definitions": {"Pet": pet}});
+//! let fingerprints = integrity::definition_fingerprints(&spec);
+//! assert_eq!(fingerprints["Pet"], integrity::fingerprint(&pet));
+//! ```
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Extension for the SHA-256 hash (hex-encoded) of the canonicalized spec.
+pub const SPEC_SHA256_EXTENSION: &str = "x-spec-sha256";
+
+/// Returns the canonical (compact JSON) encoding of the value, with sorted keys.
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Array(list) => {
+            out.push('[');
+            for (i, v) in list.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                write_canonical(v, out);
+            }
+
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                out.push_str(&Value::String(k.clone()).to_string());
+                out.push(':');
+                write_canonical(v, out);
+            }
+
+            out.push('}');
+        }
+        v => out.push_str(&v.to_string()),
+    }
+}
+
+/// Returns the SHA-256 hash (hex-encoded) of the canonicalized spec (ignoring
+/// any hash already embedded in it).
+pub fn spec_sha256(spec: &Value) -> String {
+    match spec {
+        Value::Object(map) if map.contains_key(SPEC_SHA256_EXTENSION) => {
+            let mut spec = map.clone();
+            spec.remove(SPEC_SHA256_EXTENSION);
+            fingerprint(&Value::Object(spec))
+        }
+        _ => fingerprint(spec),
+    }
+}
+
+/// Returns the SHA-256 hash (hex-encoded) of the canonicalized value.
+pub fn fingerprint(value: &Value) -> String {
+    let digest = Sha256::digest(canonicalize(value).as_bytes());
+    digest.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+/// Returns the fingerprints of the definitions in the spec (`definitions` in v2
+/// and `components.schemas` in v3).
+pub fn definition_fingerprints(spec: &Value) -> BTreeMap<String, String> {
+    let definitions = match spec.get("definitions") {
+        Some(d) => d,
+        None => &spec["components"]["schemas"],
+    };
+
+    definitions
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, schema)| (name.clone(), fingerprint(schema)))
+        .collect()
+}
+
+/// Embeds the hash of the spec in the `x-spec-sha256` extension (replacing the
+/// existing hash, if any) and returns it.
+pub fn embed_sha256(spec: &mut Value) -> String {
+    let hash = spec_sha256(spec);
+    if let Value::Object(map) = spec {
+        map.insert(SPEC_SHA256_EXTENSION.into(), hash.clone().into());
+    }
+
+    hash
+}
+
+/// Checks whether the hash embedded in the spec matches its content. Returns `None`
+/// if the spec doesn't have a hash.
+pub fn verify_sha256(spec: &Value) -> Option<bool> {
+    let hash = spec.get(SPEC_SHA256_EXTENSION)?.as_str()?;
+    Some(hash == spec_sha256(spec))
+}
@@ -12,10 +12,13 @@ extern crate serde;
 
 mod error;
 pub mod im;
+pub mod integrity;
+pub mod query;
+pub mod routes;
 pub mod util;
 #[cfg(feature = "v2")]
 pub mod v2;
 #[cfg(feature = "v3")]
 pub mod v3;
 
-pub use self::error::ValidationError;
+pub use self::error::{QueryError, RouteError, ValidationError};
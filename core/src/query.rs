@@ -0,0 +1,389 @@
+//! Selecting nodes in specs through [JSON Pointers](https://tools.ietf.org/html/rfc6901)
+//! or (a subset of) [JSONPath](https://goessner.net/articles/JsonPath/).
+//!
+//! Queries operate on the JSON representation of a spec, so they work for both
+//! `DefaultApiRaw` and v3 documents. Matches can be converted back into the model
+//! structs through [`Query::select_as`](struct.Query.html#method.select_as) and
+//! [`Query::select_from`](struct.Query.html#method.select_from).
+//!
+//! ```rust
+//! use paperclip_core::query::Query;
+//! use serde_json::json;
+//!
+//! let spec = json!({
+//!     "paths": {
+//!         "/pets": { "get": { "operationId": "listPets" } },
+//!         "/pets/{id}": { "get": { "operationId": "getPet" } }
+//!     }
+//! });
+//!
+//! let query = Query::parse("$.paths.*.get.operationId").unwrap();
+//! assert_eq!(query.select(&spec), vec!["listPets", "getPet"]);
+//!
+//! let query = Query::parse("/paths/~1pets~1{id}/get").unwrap();
+//! assert_eq!(query.select(&spec)[0]["operationId"], "getPet");
+//! ```
+
+use crate::error::QueryError;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use std::fmt::{self, Display};
+
+/// Parsed query expression.
+#[derive(Clone, Debug)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+/// Segment in a query expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    /// Child with the given name.
+    Key(String),
+    /// Array item at the given index.
+    Index(usize),
+    /// Reference token from a JSON pointer (could be either a key or an index).
+    Token(String),
+    /// All children of an object or array.
+    Wildcard,
+}
+
+/// Step into a child node.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Location {
+    Key(String),
+    Index(usize),
+}
+
+/// Concrete location of a node in a document. This is displayed as a JSON pointer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodePath(pub Vec<Location>);
+
+impl Query {
+    /// Parses the given expression. Expressions starting with `$` are treated as JSONPath
+    /// and everything else as JSON pointers.
+    ///
+    /// Only a subset of JSONPath is supported for now: the root (`$`), child names
+    /// (`.name`, `['name']`), array indices (`[0]`) and wildcards (`.*`, `[*]`).
+    pub fn parse(expr: &str) -> Result<Self, QueryError> {
+        let expr = expr.trim();
+        let segments = if expr.starts_with('$') {
+            parse_json_path(expr)
+        } else {
+            parse_json_pointer(expr)
+        };
+
+        segments
+            .ok_or_else(|| QueryError::InvalidExpression(expr.into()))
+            .map(|segments| Query { segments })
+    }
+
+    /// Returns the locations of all nodes matching this query.
+    pub fn locate(&self, doc: &Value) -> Vec<NodePath> {
+        let mut paths = vec![];
+        self.walk(doc, 0, &mut vec![], &mut |path, _| {
+            paths.push(NodePath(path.to_vec()))
+        });
+        paths
+    }
+
+    /// Returns all nodes matching this query.
+    pub fn select<'a>(&self, doc: &'a Value) -> Vec<&'a Value> {
+        let mut nodes = vec![];
+        self.walk(doc, 0, &mut vec![], &mut |_, node| nodes.push(node));
+        nodes
+    }
+
+    /// Returns all nodes matching this query, converted to the given type
+    /// (for example, `DefaultOperationRaw` or `openapiv3::Operation`).
+    pub fn select_as<T>(&self, doc: &Value) -> Result<Vec<T>, QueryError>
+    where
+        T: DeserializeOwned,
+    {
+        self.select(doc)
+            .into_iter()
+            .map(|v| T::deserialize(v).map_err(QueryError::Json))
+            .collect()
+    }
+
+    /// Same as [`select_as`](#method.select_as), but for a spec model
+    /// (for example, `DefaultApiRaw` or `openapiv3::OpenAPI`).
+    pub fn select_from<S, T>(&self, spec: &S) -> Result<Vec<T>, QueryError>
+    where
+        S: Serialize,
+        T: DeserializeOwned,
+    {
+        let doc = serde_json::to_value(spec).map_err(QueryError::Json)?;
+        self.select_as(&doc)
+    }
+
+    fn walk<'a, F>(&self, node: &'a Value, idx: usize, current: &mut Vec<Location>, f: &mut F)
+    where
+        F: FnMut(&[Location], &'a Value),
+    {
+        let segment = match self.segments.get(idx) {
+            Some(s) => s,
+            None => return f(current, node),
+        };
+
+        let mut visit = |loc: Location, child: &'a Value, f: &mut F| {
+            current.push(loc);
+            self.walk(child, idx + 1, current, f);
+            current.pop();
+        };
+
+        match (segment, node) {
+            (Segment::Key(k), Value::Object(map)) | (Segment::Token(k), Value::Object(map)) => {
+                if let Some(child) = map.get(k) {
+                    visit(Location::Key(k.clone()), child, f);
+                }
+            }
+            (Segment::Index(i), Value::Array(list)) => {
+                if let Some(child) = list.get(*i) {
+                    visit(Location::Index(*i), child, f);
+                }
+            }
+            (Segment::Token(t), Value::Array(list)) => {
+                if let Some((i, child)) = t.parse().ok().and_then(|i| list.get(i).map(|c| (i, c))) {
+                    visit(Location::Index(i), child, f);
+                }
+            }
+            (Segment::Wildcard, Value::Object(map)) => {
+                for (k, child) in map {
+                    visit(Location::Key(k.clone()), child, f);
+                }
+            }
+            (Segment::Wildcard, Value::Array(list)) => {
+                for (i, child) in list.iter().enumerate() {
+                    visit(Location::Index(i), child, f);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl NodePath {
+    /// Returns a reference to the node at this location (if any).
+    pub fn get<'a>(&self, mut node: &'a Value) -> Option<&'a Value> {
+        for loc in &self.0 {
+            node = match loc {
+                Location::Key(k) => node.get(k.as_str())?,
+                Location::Index(i) => node.get(*i)?,
+            };
+        }
+
+        Some(node)
+    }
+
+    /// Returns a mutable reference to the node at this location (if any).
+    pub fn get_mut<'a>(&self, mut node: &'a mut Value) -> Option<&'a mut Value> {
+        for loc in &self.0 {
+            node = match loc {
+                Location::Key(k) => node.get_mut(k.as_str())?,
+                Location::Index(i) => node.get_mut(*i)?,
+            };
+        }
+
+        Some(node)
+    }
+
+    /// Removes the node at this location from its parent and returns it.
+    /// The root node cannot be removed.
+    pub fn remove(&self, root: &mut Value) -> Option<Value> {
+        let (last, parent) = self.0.split_last()?;
+        let parent = NodePath(parent.to_vec());
+        match (parent.get_mut(root)?, last) {
+            (Value::Object(map), Location::Key(k)) => map.remove(k),
+            (Value::Array(list), Location::Index(i)) if *i < list.len() => Some(list.remove(*i)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for loc in &self.0 {
+            match loc {
+                Location::Key(k) => write!(f, "/{}", k.replace('~', "~0").replace('/', "~1"))?,
+                Location::Index(i) => write!(f, "/{}", i)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a JSON pointer (`""` refers to the whole document).
+fn parse_json_pointer(expr: &str) -> Option<Vec<Segment>> {
+    let expr = expr.strip_prefix('#').unwrap_or(expr);
+    if expr.is_empty() {
+        return Some(vec![]);
+    }
+
+    let tokens = expr.strip_prefix('/')?.split('/');
+    Some(
+        tokens
+            .map(|t| Segment::Token(t.replace("~1", "/").replace("~0", "~")))
+            .collect(),
+    )
+}
+
+/// Parses the supported subset of JSONPath.
+fn parse_json_path(expr: &str) -> Option<Vec<Segment>> {
+    let mut rest = expr.strip_prefix('$')?;
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(&['.', '['][..]).unwrap_or(r.len());
+            segments.push(match &r[..end] {
+                "" => return None,
+                "*" => Segment::Wildcard,
+                name => Segment::Key(name.into()),
+            });
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = match r.chars().next() {
+                Some(q @ '\'') | Some(q @ '"') => {
+                    let close = r[1..].find(q)? + 1;
+                    segments.push(Segment::Key(r[1..close].into()));
+                    close + 1
+                }
+                _ => {
+                    let close = r.find(']')?;
+                    segments.push(match r[..close].trim() {
+                        "*" => Segment::Wildcard,
+                        i => Segment::Index(i.parse().ok()?),
+                    });
+                    close
+                }
+            };
+
+            rest = r[end..].strip_prefix(']')?;
+        } else {
+            return None;
+        }
+    }
+
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec() -> Value {
+        json!({
+            "paths": {
+                "/pets": {
+                    "get": { "operationId": "listPets", "tags": ["pets", "public"] },
+                    "post": { "operationId": "addPet" }
+                },
+                "/pets/{id}": { "get": { "operationId": "getPet" } }
+            },
+            "a~b": { "c": 1 }
+        })
+    }
+
+    #[test]
+    fn test_json_path_selection() {
+        let spec = spec();
+        let query = Query::parse("$.paths['/pets'].get.operationId").unwrap();
+        assert_eq!(query.select(&spec), vec!["listPets"]);
+
+        let query = Query::parse("$.paths[\"/pets\"].get.tags[1]").unwrap();
+        assert_eq!(query.select(&spec), vec!["public"]);
+
+        let query = Query::parse("$").unwrap();
+        assert_eq!(query.select(&spec), vec![&spec]);
+
+        // Missing children (and indices out of bounds) don't match anything.
+        let query = Query::parse("$.paths['/pets'].get.tags[2]").unwrap();
+        assert!(query.select(&spec).is_empty());
+        let query = Query::parse("$.paths.missing").unwrap();
+        assert!(query.select(&spec).is_empty());
+    }
+
+    #[test]
+    fn test_json_pointer_selection() {
+        let spec = spec();
+        let query = Query::parse("/paths/~1pets~1{id}/get/operationId").unwrap();
+        assert_eq!(query.select(&spec), vec!["getPet"]);
+
+        let query = Query::parse("#/a~0b/c").unwrap();
+        assert_eq!(query.select(&spec), vec![1]);
+
+        // Tokens are indices for arrays.
+        let query = Query::parse("/paths/~1pets/get/tags/0").unwrap();
+        assert_eq!(query.select(&spec), vec!["pets"]);
+
+        let query = Query::parse("").unwrap();
+        assert_eq!(query.select(&spec), vec![&spec]);
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let spec = spec();
+        let query = Query::parse("$.paths.*.*.operationId").unwrap();
+        assert_eq!(query.select(&spec), vec!["listPets", "addPet", "getPet"]);
+
+        let query = Query::parse("$.paths['/pets'].get.tags[*]").unwrap();
+        assert_eq!(query.select(&spec), vec!["pets", "public"]);
+
+        // Wildcards don't match scalars.
+        let query = Query::parse("$.paths.*.get.operationId.*").unwrap();
+        assert!(query.select(&spec).is_empty());
+    }
+
+    #[test]
+    fn test_locations() {
+        let mut spec = spec();
+        let query = Query::parse("$.paths.*.get").unwrap();
+        let paths = query.locate(&spec);
+        assert_eq!(
+            paths.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+            vec!["/paths/~1pets/get", "/paths/~1pets~1{id}/get"]
+        );
+        assert_eq!(paths[1].get(&spec).unwrap()["operationId"], "getPet");
+
+        let removed = paths[0].remove(&mut spec).unwrap();
+        assert_eq!(removed["operationId"], "listPets");
+        assert!(paths[0].get(&spec).is_none());
+        assert!(NodePath::default().remove(&mut spec).is_none());
+    }
+
+    #[test]
+    fn test_select_as() {
+        let query = Query::parse("$.paths.*.get.operationId").unwrap();
+        let ids: Vec<String> = query.select_as(&spec()).unwrap();
+        assert_eq!(ids, vec!["listPets", "getPet"]);
+
+        let query = Query::parse("$.paths.*.get").unwrap();
+        assert!(matches!(
+            query.select_as::<String>(&spec()),
+            Err(QueryError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expr in &[
+            "paths/pets",
+            "$.",
+            "$..paths",
+            "$paths",
+            "$[abc]",
+            "$[0",
+            "$['paths'",
+            "$['paths']x",
+        ] {
+            match Query::parse(expr) {
+                Err(QueryError::InvalidExpression(e)) => assert_eq!(&e, expr),
+                other => panic!("expected {:?} to be invalid, found {:?}", expr, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Reverse routing (building the URLs of operations from their IDs).
+//!
+//! Services can construct links (or `Location` headers) from the IDs of documented
+//! operations rather than hard-coding their paths, so that those links can't drift
+//! away from the spec. Path parameters in the templates are replaced with the given
+//! (percent-encoded) values, and the values of other parameters end up in the query.
+//!
+//! ```rust
+//! use paperclip_core::routes::OperationUrls;
+//! use serde_json::json;
+//!
+//! let urls = OperationUrls::from_spec(&json!({
+//!     "basePath": "/api",
+//!     "paths": {
+//!         "/users/{id}": {
+//!             "get": { "operationId": "getUser" }
+//!         }
+//!     }
+//! }));
+//!
+//! assert_eq!(urls.operation_url("getUser", &[("id", "42")]).unwrap(), "/api/users/42");
+//! assert_eq!(
+//!     urls.operation_url("getUser", &[("id", "a b"), ("fields", "name")]).unwrap(),
+//!     "/api/users/a%20b?fields=name"
+//! );
+//! assert!(urls.operation_url("getUser", &[]).is_err());
+//! ```
+//!
+//! Hypermedia links to operations can be built with [`Links`](struct.Links.html), either
+//! as [RFC 8288](https://tools.ietf.org/html/rfc8288) `Link` headers or as embedded
+//! (HAL-style) `_links` objects:
+//!
+//! ```rust
+//! # use paperclip_core::routes::OperationUrls;
+//! # use serde_json::json;
+//! # let urls = OperationUrls::from_spec(&json!({
+//! #     "paths": {
+//! #         "/users/{id}": { "get": { "operationId": "getUser" } },
+//! #         "/users/{id}/pets": { "get": { "operationId": "listPets" } }
+//! #     }
+//! # }));
+//! let links = urls
+//!     .links()
+//!     .link("self", "getUser", &[("id", "42")])?
+//!     .link("pets", "listPets", &[("id", "42"), ("limit", "10")])?;
+//!
+//! assert_eq!(
+//!     links.header_value(),
+//!     r#"</users/42>; rel="self", </users/42/pets?limit=10>; rel="pets""#
+//! );
+//! assert_eq!(links.to_json(), json!({
+//!     "self": { "href": "/users/42" },
+//!     "pets": { "href": "/users/42/pets?limit=10" }
+//! }));
+//! # Ok::<(), paperclip_core::RouteError>(())
+//! ```
+
+use crate::error::RouteError;
+use serde_json::{Map, Value};
+
+use std::collections::BTreeMap;
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Path templates of the operations in a spec (by their IDs).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationUrls {
+    templates: BTreeMap<String, String>,
+}
+
+impl OperationUrls {
+    /// Collects the operations (with IDs) in the given spec.
+    ///
+    /// Paths are prefixed with the `basePath` of v2 specs.
+    pub fn from_spec(spec: &Value) -> Self {
+        let base = spec["basePath"]
+            .as_str()
+            .unwrap_or("")
+            .trim_end_matches('/');
+        let mut templates = BTreeMap::new();
+        for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+            for method in METHODS {
+                if let Some(id) = item[*method]["operationId"].as_str() {
+                    templates.insert(id.into(), format!("{}{}", base, path));
+                }
+            }
+        }
+
+        OperationUrls { templates }
+    }
+
+    /// Returns the path template of the operation with the given ID (if any).
+    pub fn template(&self, operation_id: &str) -> Option<&str> {
+        self.templates.get(operation_id).map(String::as_str)
+    }
+
+    /// Returns an iterator over the IDs of operations and their path templates.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.templates
+            .iter()
+            .map(|(id, path)| (id.as_str(), path.as_str()))
+    }
+
+    /// Returns the URL (path and query) of the operation with the given ID, using the
+    /// given values for its path parameters. Values of other parameters are added
+    /// to the query (in the given order).
+    pub fn operation_url(
+        &self,
+        operation_id: &str,
+        params: &[(&str, &str)],
+    ) -> Result<String, RouteError> {
+        let template = self
+            .template(operation_id)
+            .ok_or_else(|| RouteError::UnknownOperation(operation_id.into()))?;
+        expand(template, params)
+            .map_err(|name| RouteError::MissingParameter(operation_id.into(), name))
+    }
+}
+
+impl OperationUrls {
+    /// Returns a builder for links to the operations.
+    pub fn links(&self) -> Links<'_> {
+        Links {
+            urls: self,
+            links: vec![],
+        }
+    }
+}
+
+/// Builder for hypermedia links to operations (by their IDs).
+#[derive(Clone, Debug)]
+pub struct Links<'a> {
+    urls: &'a OperationUrls,
+    links: Vec<(String, String)>,
+}
+
+impl<'a> Links<'a> {
+    /// Adds a link (with the given relation type) to the operation with the given ID,
+    /// using the given parameters (see [`OperationUrls::operation_url`](struct.OperationUrls.html#method.operation_url)).
+    pub fn link(
+        mut self,
+        rel: &str,
+        operation_id: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Self, RouteError> {
+        let href = self.urls.operation_url(operation_id, params)?;
+        self.links.push((rel.into(), href));
+        Ok(self)
+    }
+
+    /// Returns the relation types and the targets of the links (in the order they were added).
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.links
+            .iter()
+            .map(|(rel, href)| (rel.as_str(), href.as_str()))
+    }
+
+    /// Returns the value of the `Link` header (RFC 8288) for the links.
+    pub fn header_value(&self) -> String {
+        self.links
+            .iter()
+            .map(|(rel, href)| format!("<{}>; rel={:?}", href, rel))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Returns the links as a (HAL-style) `_links` object, which maps the relation
+    /// types to link objects (or arrays of them, for relation types used multiple times).
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        for (rel, href) in &self.links {
+            let link = serde_json::json!({ "href": href });
+            match map.get_mut(rel) {
+                Some(Value::Array(list)) => list.push(link),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), link]),
+                None => {
+                    map.insert(rel.clone(), link);
+                }
+            }
+        }
+
+        Value::Object(map)
+    }
+}
+
+/// Replaces the path parameters in the template with the given values, adding the
+/// remaining ones to the query. Returns the name of the missing parameter (if any).
+fn expand(template: &str, params: &[(&str, &str)]) -> Result<String, String> {
+    let mut url = String::new();
+    let mut used = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(i) => start + i,
+            None => break,
+        };
+
+        let name = &rest[start + 1..end];
+        let value = params
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+            .ok_or_else(|| name.to_owned())?;
+        url.push_str(&rest[..start]);
+        url.push_str(&encode(value));
+        used.push(name);
+        rest = &rest[end + 1..];
+    }
+
+    url.push_str(rest);
+    let query = params
+        .iter()
+        .filter(|(n, _)| !used.contains(n))
+        .map(|(n, v)| format!("{}={}", encode(n), encode(v)))
+        .collect::<Vec<_>>();
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    Ok(url)
+}
+
+/// Percent-encodes everything other than the unreserved characters (RFC 3986).
+fn encode(value: &str) -> String {
+    value.bytes().fold(String::new(), |mut s, b| {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            s.push(b as char);
+        } else {
+            s.push_str(&format!("%{:02X}", b));
+        }
+
+        s
+    })
+}
@@ -3,6 +3,7 @@ use std::{
     marker::Unpin,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Unstabilized [`Ready`](https://doc.rust-lang.org/nightly/std/future/struct.Ready.html) future.
@@ -23,3 +24,47 @@ impl<T> Future for Ready<T> {
 pub fn ready<T>(t: T) -> Ready<T> {
     Ready(Some(t))
 }
+
+/// Applies the given [JSON merge patch](https://tools.ietf.org/html/rfc7386) to the target value.
+pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let patch = match patch {
+        serde_json::Value::Object(p) => p,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = serde_json::Value::Object(Default::default());
+    }
+
+    let map = target.as_object_mut().expect("expected object");
+    for (key, value) in patch {
+        if value.is_null() {
+            map.remove(key);
+        } else {
+            merge_patch(
+                map.entry(key.as_str()).or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
+}
+
+/// Parses a duration (like the `x-timeout` of operations) from a number with an optional
+/// unit (`ms`, `s`, `m` or `h`) such as `"30s"` or `"500ms"`. Numbers without units are
+/// in seconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let idx = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(idx);
+    let num = num.parse::<u64>().ok()?;
+    match unit.trim() {
+        "ms" => Some(Duration::from_millis(num)),
+        "" | "s" => Some(Duration::from_secs(num)),
+        "m" => num.checked_mul(60).map(Duration::from_secs),
+        "h" => num.checked_mul(3600).map(Duration::from_secs),
+        _ => None,
+    }
+}
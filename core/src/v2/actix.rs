@@ -1,32 +1,49 @@
-#[cfg(feature = "actix-multipart")]
-use super::schema::TypedData;
 use super::{
+    filter::{self, Filter, SortKey, FIELDS_PARAMETER, FILTER_PARAMETER, SORT_PARAMETER},
     models::{
-        DefaultOperationRaw, DefaultSchemaRaw, Either, Items, Parameter, ParameterIn, Response,
-        SecurityScheme,
+        CookieParameter, DataType, DefaultOperationRaw, DefaultSchemaRaw, Either,
+        Header as ResponseHeader, Items, MediaRange, Parameter, ParameterIn, Response,
+        SecurityScheme, JSON_MIME,
     },
     schema::{Apiv2Errors, Apiv2Operation, Apiv2Schema},
+    validate::coerce_parameter,
 };
+#[cfg(feature = "actix-multipart")]
+use super::{models::DataTypeFormat, schema::TypedData};
 
-#[cfg(feature = "actix3")]
+#[cfg(feature = "v3")]
+use crate::v3::{
+    native::SchemaMap,
+    openapiv3::{ReferenceOr, Schema as V3Schema},
+};
+#[cfg(any(feature = "actix3", feature = "actix4"))]
 use actix_web::web::ReqData;
 
+use crate::util::{ready, Ready};
 use actix_web::{
-    http::StatusCode,
+    body::BoxBody,
+    error::ErrorBadRequest,
+    http::{header::COOKIE, StatusCode},
     web::{Bytes, Data, Form, Json, Path, Payload, Query},
-    HttpRequest, HttpResponse, Responder,
+    Error, FromRequest, HttpRequest, HttpResponse, Responder,
 };
 
+use futures_core::Stream;
 use pin_project::pin_project;
 
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 #[cfg(feature = "serde_qs")]
 use serde_qs::actix::QsQuery;
 
 use std::{
-    collections::BTreeMap,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     future::Future,
+    iter::FromIterator,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
     pin::Pin,
     task::{Context, Poll},
 };
@@ -34,6 +51,11 @@ use std::{
 /// Actix-specific trait for indicating that this entity can modify an operation
 /// and/or update the global map of definitions.
 pub trait OperationModifier: Apiv2Schema + Sized {
+    /// Media type of the body handled by this entity (if any). This is added to the
+    /// `consumes` of operations using this as an extractor and to the `produces`
+    /// of operations returning this, unless they've been specified explicitly.
+    const MEDIA_TYPE: Option<&'static str> = None;
+
     /// Update the parameters list in the given operation (if needed).
     fn update_parameter(_op: &mut DefaultOperationRaw) {}
 
@@ -45,6 +67,25 @@ pub trait OperationModifier: Apiv2Schema + Sized {
         update_definitions_from_schema_type::<Self>(map);
     }
 
+    /// Update the map of (OpenAPI v3) schemas (if needed).
+    ///
+    /// By default, this adds the v3 schemas of this entity if it updates the
+    /// definitions, along with the conversions of definitions which don't have
+    /// a v3 schema (like the ones of errors).
+    #[cfg(feature = "v3")]
+    fn update_schemas_v3(map: &mut SchemaMap) {
+        let mut definitions = BTreeMap::new();
+        Self::update_definitions(&mut definitions);
+        if definitions.is_empty() {
+            return;
+        }
+
+        Self::add_schemas_v3(map);
+        for (name, schema) in definitions {
+            map.entry(name).or_insert_with(|| schema.into());
+        }
+    }
+
     /// Update the security map in the given operation (if needed).
     fn update_security(op: &mut DefaultOperationRaw) {
         update_security::<Self>(op);
@@ -62,6 +103,8 @@ impl<T> OperationModifier for T
 where
     T: Apiv2Schema,
 {
+    default const MEDIA_TYPE: Option<&'static str> = None;
+
     default fn update_parameter(_op: &mut DefaultOperationRaw) {}
 
     default fn update_response(_op: &mut DefaultOperationRaw) {}
@@ -83,8 +126,21 @@ impl<T> OperationModifier for Option<T>
 where
     T: OperationModifier,
 {
+    const MEDIA_TYPE: Option<&'static str> = T::MEDIA_TYPE;
+
     fn update_parameter(op: &mut DefaultOperationRaw) {
+        // Parameters from optional extractors (like `Option<Query<T>>`) aren't required.
+        let idx = op.parameters.len();
+        let cookies = op.cookies.len();
         T::update_parameter(op);
+        op.parameters[idx..]
+            .iter_mut()
+            .filter_map(Either::right_mut)
+            .filter(|p| p.in_ != ParameterIn::Path)
+            .for_each(|p| p.required = false);
+        op.cookies[cookies..]
+            .iter_mut()
+            .for_each(|c| c.required = false);
     }
 
     fn update_response(op: &mut DefaultOperationRaw) {
@@ -109,6 +165,8 @@ impl<T, E> OperationModifier for Result<T, E>
 where
     T: OperationModifier,
 {
+    default const MEDIA_TYPE: Option<&'static str> = T::MEDIA_TYPE;
+
     default fn update_parameter(op: &mut DefaultOperationRaw) {
         T::update_parameter(op);
     }
@@ -131,6 +189,8 @@ where
     T: OperationModifier,
     E: Apiv2Errors,
 {
+    const MEDIA_TYPE: Option<&'static str> = T::MEDIA_TYPE;
+
     fn update_parameter(op: &mut DefaultOperationRaw) {
         T::update_parameter(op);
     }
@@ -151,15 +211,21 @@ where
 }
 
 // We don't know what we should do with these abstractions
-// as they could be anything.
+// as they could be anything. Application data and request-local data
+// (usually inserted by middlewares) don't show up in the spec, so these
+// don't require `T: Apiv2Schema` and never touch the operation or definitions.
 impl<T> Apiv2Schema for Data<T> {}
 #[cfg(not(feature = "nightly"))]
-impl<T> OperationModifier for Data<T> {}
-#[cfg(feature = "actix3")]
+impl<T> OperationModifier for Data<T> {
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+#[cfg(any(feature = "actix3", feature = "actix4"))]
 impl<T: std::clone::Clone> Apiv2Schema for ReqData<T> {}
 #[cfg(not(feature = "nightly"))]
-#[cfg(feature = "actix3")]
-impl<T: std::clone::Clone> OperationModifier for ReqData<T> {}
+#[cfg(any(feature = "actix3", feature = "actix4"))]
+impl<T: std::clone::Clone> OperationModifier for ReqData<T> {
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
 
 macro_rules! impl_empty({ $($ty:ty),+ } => {
     $(
@@ -169,19 +235,26 @@ macro_rules! impl_empty({ $($ty:ty),+ } => {
     )+
 });
 
-impl Apiv2Operation for HttpResponse {
-    fn operation() -> DefaultOperationRaw {
-        Default::default()
-    }
+macro_rules! impl_empty_operation({ $($ty:ty),+ } => {
+    $(
+        impl Apiv2Operation for $ty {
+            fn operation() -> DefaultOperationRaw {
+                Default::default()
+            }
 
-    fn security_definitions() -> BTreeMap<String, SecurityScheme> {
-        Default::default()
-    }
+            fn security_definitions() -> BTreeMap<String, SecurityScheme> {
+                Default::default()
+            }
 
-    fn definitions() -> BTreeMap<String, DefaultSchemaRaw> {
-        Default::default()
-    }
-}
+            fn definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+                Default::default()
+            }
+        }
+    )+
+});
+
+// Handlers returning (ready) responses are allowed, but they're not documented.
+impl_empty_operation!(HttpResponse, Ready<HttpResponse>);
 
 impl_empty!(HttpRequest, HttpResponse, Bytes, Payload);
 
@@ -235,6 +308,14 @@ impl<T> Apiv2Schema for Json<T> {
     default fn raw_schema() -> DefaultSchemaRaw {
         Default::default()
     }
+
+    #[cfg(feature = "v3")]
+    default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        Self::raw_schema().into()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn add_schemas_v3(_map: &mut SchemaMap) {}
 }
 
 /// JSON needs specialization because it updates the global definitions.
@@ -244,12 +325,24 @@ impl<T: Apiv2Schema> Apiv2Schema for Json<T> {
     fn raw_schema() -> DefaultSchemaRaw {
         T::raw_schema()
     }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 impl<T> OperationModifier for Json<T>
 where
     T: Apiv2Schema,
 {
+    const MEDIA_TYPE: Option<&'static str> = Some("application/json");
+
     fn update_parameter(op: &mut DefaultOperationRaw) {
         op.parameters.push(Either::Right(Parameter {
             description: None,
@@ -284,6 +377,8 @@ where
 
 #[cfg(feature = "actix-multipart")]
 impl OperationModifier for actix_multipart::Multipart {
+    const MEDIA_TYPE: Option<&'static str> = Some("multipart/form-data");
+
     fn update_parameter(op: &mut DefaultOperationRaw) {
         op.parameters.push(Either::Right(Parameter {
             description: None,
@@ -297,17 +392,233 @@ impl OperationModifier for actix_multipart::Multipart {
     }
 }
 
+/// Multipart form extractor, whose parts are documented as the `formData` parameters
+/// for the properties of `T` (which is only used for the spec). Fields of
+/// [`FilePart`](struct.FilePart.html) are documented as files.
+///
+/// ```rust,ignore
+/// #[derive(Apiv2Schema)]
+/// struct Upload {
+///     /// Name of the pet.
+///     name: String,
+///     photo: FilePart,
+/// }
+///
+/// #[api_v2_operation]
+/// async fn upload(mut form: MultipartForm<Upload>) -> Result<HttpResponse, Error> {
+///     while let Some(field) = form.try_next().await? {
+///         // ...
+///     }
+///     // ...
+/// }
+/// ```
+#[cfg(feature = "actix-multipart")]
+pub struct MultipartForm<T> {
+    inner: actix_multipart::Multipart,
+    _form: std::marker::PhantomData<T>,
+}
+
+#[cfg(feature = "actix-multipart")]
+impl<T> MultipartForm<T> {
+    /// Unwraps the stream of fields.
+    pub fn into_inner(self) -> actix_multipart::Multipart {
+        self.inner
+    }
+}
+
+#[cfg(feature = "actix-multipart")]
+impl<T> Deref for MultipartForm<T> {
+    type Target = actix_multipart::Multipart;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "actix-multipart")]
+impl<T> DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "actix-multipart")]
+impl<T> FromRequest for MultipartForm<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(Ok(MultipartForm {
+            inner: actix_multipart::Multipart::new(req.headers(), payload.take()),
+            _form: std::marker::PhantomData,
+        }))
+    }
+}
+
+#[cfg(feature = "actix-multipart")]
+impl<T: Apiv2Schema> Apiv2Schema for MultipartForm<T> {}
+
+#[cfg(feature = "actix-multipart")]
+impl<T: Apiv2Schema> OperationModifier for MultipartForm<T> {
+    const MEDIA_TYPE: Option<&'static str> = Some("multipart/form-data");
+
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        add_struct_parameters::<T>(op, ParameterIn::FormData);
+    }
+
+    // The form is only used for its properties.
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+/// Marker for the file parts of [`MultipartForm`](struct.MultipartForm.html)s, which
+/// are documented as files.
+#[cfg(feature = "actix-multipart")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FilePart;
+
+#[cfg(feature = "actix-multipart")]
+impl TypedData for FilePart {
+    fn data_type() -> DataType {
+        DataType::File
+    }
+
+    fn format() -> Option<DataTypeFormat> {
+        Some(DataTypeFormat::Binary)
+    }
+}
+
 #[cfg(feature = "actix-session")]
 impl OperationModifier for actix_session::Session {
     fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
 }
 
+#[cfg(feature = "actix-identity")]
+impl OperationModifier for actix_identity::Identity {
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+/// Cookie API key (named `sessionCookie`) for the `id` cookie, which `actix-session`
+/// and `actix-identity` use by default. This is the security scheme documented by
+/// [`SessionAuth`](struct.SessionAuth.html) unless another one is specified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionCookie;
+
+impl Apiv2Schema for SessionCookie {
+    const NAME: Option<&'static str> = Some("sessionCookie");
+
+    fn security_scheme() -> Option<SecurityScheme> {
+        Some(SecurityScheme {
+            type_: "apiKey".into(),
+            name: Some("id".into()),
+            in_: Some("cookie".into()),
+            flow: None,
+            auth_url: None,
+            token_url: None,
+            scopes: BTreeMap::new(),
+            description: Some("Session cookie".into()),
+        })
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl OperationModifier for SessionCookie {}
+
+/// Wrapper for session extractors (like `actix_session::Session` and
+/// `actix_identity::Identity`), which documents the security scheme `S` (a cookie
+/// API key deriving `Apiv2Security`, or [`SessionCookie`](struct.SessionCookie.html))
+/// for the operations taking it. The v2 spec doesn't allow API keys in cookies,
+/// but they become cookie API keys in v3 specs.
+pub struct SessionAuth<T, S = SessionCookie> {
+    inner: T,
+    scheme: PhantomData<S>,
+}
+
+impl<T, S> SessionAuth<T, S> {
+    /// Unwraps the session extractor.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, S> Deref for SessionAuth<T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T, S> DerefMut for SessionAuth<T, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T, S> fmt::Debug for SessionAuth<T, S>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SessionAuth").field(&self.inner).finish()
+    }
+}
+
+impl<T: FromRequest, S> FromRequest for SessionAuth<T, S> {
+    type Error = T::Error;
+    type Future = SessionAuthFuture<T::Future, S>;
+
+    fn from_request(req: &HttpRequest, payload: &mut actix_web::dev::Payload) -> Self::Future {
+        SessionAuthFuture {
+            inner: T::from_request(req, payload),
+            scheme: PhantomData,
+        }
+    }
+}
+
+/// Future extracting a [`SessionAuth`](struct.SessionAuth.html).
+#[pin_project]
+pub struct SessionAuthFuture<F, S> {
+    #[pin]
+    inner: F,
+    scheme: PhantomData<S>,
+}
+
+impl<F, T, E, S> Future for SessionAuthFuture<F, S>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = Result<SessionAuth<T, S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx).map(|result| {
+            result.map(|inner| SessionAuth {
+                inner,
+                scheme: PhantomData,
+            })
+        })
+    }
+}
+
+impl<T, S> Apiv2Schema for SessionAuth<T, S> {}
+
+impl<T, S: Apiv2Schema> OperationModifier for SessionAuth<T, S> {
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+
+    fn update_security(op: &mut DefaultOperationRaw) {
+        update_security::<S>(op);
+    }
+
+    fn update_security_definitions(map: &mut BTreeMap<String, SecurityScheme>) {
+        update_security_definitions::<S>(map);
+    }
+}
+
 #[cfg(feature = "actix-files")]
 impl OperationModifier for actix_files::NamedFile {
     fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
 }
 
-macro_rules! impl_param_extractor ({ $ty:ty => $container:ident } => {
+macro_rules! impl_param_extractor ({ $ty:ty => $container:ident $(, $media:expr)? } => {
     #[cfg(feature = "nightly")]
     impl<T> Apiv2Schema for $ty {
         default const NAME: Option<&'static str> = None;
@@ -315,12 +626,22 @@ macro_rules! impl_param_extractor ({ $ty:ty => $container:ident } => {
         default fn raw_schema() -> DefaultSchemaRaw {
             Default::default()
         }
+
+        #[cfg(feature = "v3")]
+        default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+            Self::raw_schema().into()
+        }
+
+        #[cfg(feature = "v3")]
+        default fn add_schemas_v3(_map: &mut SchemaMap) {}
     }
 
     #[cfg(not(feature = "nightly"))]
     impl<T: Apiv2Schema> Apiv2Schema for $ty {}
 
     impl<T: Apiv2Schema> OperationModifier for $ty {
+        $(const MEDIA_TYPE: Option<&'static str> = Some($media);)?
+
         fn update_parameter(op: &mut DefaultOperationRaw) {
             let def = T::raw_schema();
             // If there aren't any properties and if it's a path parameter,
@@ -333,24 +654,13 @@ macro_rules! impl_param_extractor ({ $ty:ty => $container:ident } => {
                     data_type: def.data_type,
                     format: def.format,
                     enum_: def.enum_,
+                    pattern: def.pattern,
                     description: def.description,
                     ..Default::default()
                 }));
             }
-            for (k, v) in def.properties {
-                op.parameters.push(Either::Right(Parameter {
-                    in_: ParameterIn::$container,
-                    required: def.required.contains(&k),
-                    data_type: v.data_type,
-                    format: v.format,
-                    enum_: v.enum_,
-                    description: v.description,
-                    collection_format: None, // this defaults to csv
-                    items: v.items.as_deref().map(map_schema_to_items),
-                    name: k,
-                    ..Default::default()
-                }));
-            }
+            // Fields can still be documented in other locations with `#[openapi(in = "...")]`.
+            add_struct_parameters::<T>(op, ParameterIn::$container);
         }
 
         // These don't require updating definitions, as we use them only
@@ -359,6 +669,64 @@ macro_rules! impl_param_extractor ({ $ty:ty => $container:ident } => {
     }
 });
 
+/// Adds the parameter to the operation, unless a parameter with the same name and
+/// location already exists (for example, when multiple extractors share some fields).
+fn add_parameter(op: &mut DefaultOperationRaw, param: Parameter<DefaultSchemaRaw>) {
+    let exists = op
+        .parameters
+        .iter()
+        .filter_map(Either::right)
+        .any(|p| p.name == param.name && p.in_ == param.in_);
+    if !exists {
+        op.parameters.push(Either::Right(param));
+    }
+}
+
+/// Adds the properties of the struct as parameters of the operation, in the locations
+/// given by `#[openapi(in = "...")]` on their fields (or the given default location).
+/// This is used by the parameter extractors and structs marked with `#[openapi(parameters)]`.
+#[doc(hidden)]
+pub fn add_struct_parameters<T: Apiv2Schema>(op: &mut DefaultOperationRaw, default: ParameterIn) {
+    let def = T::raw_schema();
+    let locations = T::parameter_locations();
+    for (k, v) in def.properties {
+        let in_ = locations.get(&k).copied().unwrap_or(default);
+        add_parameter(
+            op,
+            Parameter {
+                in_,
+                // Path parameters are always required.
+                required: in_ == ParameterIn::Path || def.required.contains(&k),
+                data_type: v.data_type,
+                format: v.format,
+                enum_: v.enum_,
+                pattern: v.pattern,
+                description: v.description,
+                collection_format: None, // this defaults to csv
+                items: v.items.as_deref().map(map_schema_to_items),
+                name: k,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Returns the media types inferred from the extractors (or responders) of an operation.
+/// JSON is the default media type for operations, so it's listed only if there are others.
+#[doc(hidden)]
+pub fn infer_media_types(media_types: &[Option<&'static str>]) -> Option<BTreeSet<MediaRange>> {
+    let set: BTreeSet<_> = media_types
+        .iter()
+        .flatten()
+        .map(|m| MediaRange(m.parse().expect("parsing mime")))
+        .collect();
+    if set.is_empty() || (set.len() == 1 && set.contains(&*JSON_MIME)) {
+        None
+    } else {
+        Some(set)
+    }
+}
+
 fn map_schema_to_items(schema: &DefaultSchemaRaw) -> Items {
     Items {
         data_type: schema.data_type,
@@ -381,140 +749,627 @@ impl<T: Apiv2Schema> Apiv2Schema for Form<T> {
     fn raw_schema() -> DefaultSchemaRaw {
         T::raw_schema()
     }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 impl_param_extractor!(Path<T> => Path);
 impl_param_extractor!(Query<T> => Query);
-impl_param_extractor!(Form<T> => FormData);
+impl_param_extractor!(Form<T> => FormData, "application/x-www-form-urlencoded");
 #[cfg(feature = "serde_qs")]
 impl_param_extractor!(QsQuery<T> => Query);
-
-macro_rules! impl_path_tuple ({ $($ty:ident),+ } => {
-    #[cfg(feature = "nightly")]
-    impl<$($ty,)+> Apiv2Schema for Path<($($ty,)+)> {}
-
-    #[cfg(not(feature = "nightly"))]
-    impl<$($ty: Apiv2Schema,)+> Apiv2Schema for Path<($($ty,)+)> {}
-
-    impl<$($ty,)+> OperationModifier for Path<($($ty,)+)>
-        where $($ty: Apiv2Schema,)+
-    {
-        fn update_parameter(op: &mut DefaultOperationRaw) {
-            $(
-                let def = $ty::raw_schema();
-                if def.properties.is_empty() {
-                    op.parameters.push(Either::Right(Parameter {
-                        // NOTE: We're setting empty name, because we don't know
-                        // the name in this context. We'll get it when we add services.
-                        name: String::new(),
-                        in_: ParameterIn::Path,
-                        required: true,
-                        data_type: def.data_type,
-                        format: def.format,
-                        enum_: def.enum_,
-                        description: def.description,
-                        ..Default::default()
-                    }));
-                }
-                for (k, v) in def.properties {
-                    op.parameters.push(Either::Right(Parameter {
-                        in_: ParameterIn::Path,
-                        required: def.required.contains(&k),
-                        data_type: v.data_type,
-                        format: v.format,
-                        enum_: v.enum_,
-                        description: v.description,
-                        collection_format: None, // this defaults to csv
-                        items: v.items.as_deref().map(map_schema_to_items),
-                        name: k,
-                        ..Default::default()
-                    }));
-                }
-            )+
-        }
+impl_param_extractor!(Header<T> => Header);
+
+/// Query extractor for arbitrary (string) parameters, for search-style endpoints
+/// with open-ended filters. Operations using this are marked with the `x-dynamic-query`
+/// extension in v2 specs and get a free-form query object in v3 specs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryMap(pub HashMap<String, String>);
+
+impl QueryMap {
+    /// Unwraps the map of query parameters.
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
     }
-});
-
-impl_path_tuple!(A);
-impl_path_tuple!(A, B);
-impl_path_tuple!(A, B, C);
-impl_path_tuple!(A, B, C, D);
-impl_path_tuple!(A, B, C, D, E);
-
-/// Wrapper for wrapping over `impl Responder` thingies (to avoid breakage).
-pub struct ResponderWrapper<T>(pub T);
+}
 
-#[cfg(feature = "nightly")]
-impl<T: Responder> Apiv2Schema for ResponderWrapper<T> {
-    default const NAME: Option<&'static str> = None;
+impl Deref for QueryMap {
+    type Target = HashMap<String, String>;
 
-    default fn raw_schema() -> DefaultSchemaRaw {
-        DefaultSchemaRaw::default()
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-#[cfg(not(feature = "nightly"))]
-impl<T: Responder> Apiv2Schema for ResponderWrapper<T> {}
-
-#[cfg(not(feature = "nightly"))]
-impl<T: Responder> OperationModifier for ResponderWrapper<T> {}
-
-impl<T: Responder> Responder for ResponderWrapper<T> {
-    #[inline]
-    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
-        self.0.respond_to(req)
+impl DerefMut for QueryMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
 }
 
-/// Wrapper for all response types from handlers. This holds the actual value
-/// returned by the handler and a unit struct (autogenerated by the plugin) which
-/// is used for generating operation information.
-#[pin_project]
-pub struct ResponseWrapper<T, H>(#[pin] pub T, pub H);
+impl FromRequest for QueryMap {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
 
-impl<T: Responder, H> Responder for ResponseWrapper<T, H> {
-    #[inline]
-    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
-        self.0.respond_to(req)
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(
+            Query::<HashMap<String, String>>::from_query(req.query_string())
+                .map(|q| QueryMap(q.into_inner()))
+                .map_err(Into::into),
+        )
     }
 }
 
-impl<F, T, H> Future for ResponseWrapper<F, H>
-where
-    F: Future<Output = T>,
-    T: OperationModifier + Responder,
-    H: Apiv2Operation,
-{
-    type Output = T;
+impl Apiv2Schema for QueryMap {}
 
-    #[inline]
-    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
-        let this = self.as_mut().project();
-        this.0.poll(ctx)
+impl OperationModifier for QueryMap {
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        op.dynamic_query = true;
     }
+
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
 }
 
-impl<F, T, H> Apiv2Operation for ResponseWrapper<F, H>
-where
-    F: Future<Output = T>,
-    T: OperationModifier + Responder,
-    H: Apiv2Operation,
-{
-    fn operation() -> DefaultOperationRaw {
-        H::operation()
+macro_rules! impl_list_param ({ $ty:ident => $item:ty, $param:expr, $parse:path } => {
+    impl<T> $ty<T> {
+        /// Unwraps the parsed values of the parameter.
+        pub fn into_inner(self) -> Vec<$item> {
+            self.values
+        }
     }
 
-    fn security_definitions() -> BTreeMap<String, SecurityScheme> {
-        H::security_definitions()
+    impl<T> Clone for $ty<T> {
+        fn clone(&self) -> Self {
+            $ty {
+                values: self.values.clone(),
+                _marker: PhantomData,
+            }
+        }
     }
 
-    fn definitions() -> BTreeMap<String, DefaultSchemaRaw> {
-        H::definitions()
+    impl<T> fmt::Debug for $ty<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple(stringify!($ty)).field(&self.values).finish()
+        }
     }
-}
 
-/// Given the schema type, recursively update the map of definitions.
-fn update_definitions_from_schema_type<T>(map: &mut BTreeMap<String, DefaultSchemaRaw>)
+    impl<T> Deref for $ty<T> {
+        type Target = [$item];
+
+        fn deref(&self) -> &Self::Target {
+            &self.values
+        }
+    }
+
+    impl<T: Apiv2Schema> FromRequest for $ty<T> {
+        type Error = Error;
+        type Future = Ready<Result<Self, Error>>;
+
+        fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+            let query = match Query::<HashMap<String, String>>::from_query(req.query_string()) {
+                Ok(q) => q.into_inner(),
+                Err(e) => return ready(Err(e.into())),
+            };
+
+            let expr = query.get($param).map(String::as_str);
+            ready(
+                $parse(expr.unwrap_or_default(), &T::raw_schema())
+                    .map(|values| $ty {
+                        values,
+                        _marker: PhantomData,
+                    })
+                    .map_err(ErrorBadRequest),
+            )
+        }
+    }
+
+    #[cfg(feature = "nightly")]
+    impl<T> Apiv2Schema for $ty<T> {
+        default const NAME: Option<&'static str> = None;
+
+        default fn raw_schema() -> DefaultSchemaRaw {
+            Default::default()
+        }
+
+        #[cfg(feature = "v3")]
+        default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+            Self::raw_schema().into()
+        }
+
+        #[cfg(feature = "v3")]
+        default fn add_schemas_v3(_map: &mut SchemaMap) {}
+    }
+
+    #[cfg(not(feature = "nightly"))]
+    impl<T: Apiv2Schema> Apiv2Schema for $ty<T> {}
+});
+
+/// Query extractor for the `filter` parameter of list endpoints, which is parsed
+/// against the fields of `T` (see the [`filter`](../filter/index.html) module for the
+/// grammar). The grammar and the fields which can be filtered are documented in the
+/// description of the parameter. Invalid expressions (including unknown fields) are
+/// rejected with `400 Bad Request`.
+pub struct FilterParam<T> {
+    values: Vec<Filter>,
+    _marker: PhantomData<T>,
+}
+
+impl_list_param!(FilterParam => Filter, FILTER_PARAMETER, filter::parse_filters);
+
+impl<T: Serialize> FilterParam<T> {
+    /// Checks whether the given item matches all the clauses of the filter.
+    pub fn matches(&self, item: &T) -> bool {
+        let item = serde_json::to_value(item).unwrap_or_default();
+        self.values.iter().all(|f| f.matches(&item))
+    }
+}
+
+impl<T: Apiv2Schema> OperationModifier for FilterParam<T> {
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        add_parameter(
+            op,
+            Parameter {
+                in_: ParameterIn::Query,
+                name: FILTER_PARAMETER.into(),
+                data_type: Some(DataType::String),
+                description: Some(filter::describe_filters(&T::raw_schema())),
+                ..Default::default()
+            },
+        );
+    }
+
+    // Like the other parameter extractors, this only uses the properties of `T`.
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+/// Query extractor for the `sort` parameter of list endpoints, which takes comma-separated
+/// (scalar) fields of `T`, with `-` prefixes for descending order (like `-age,name`). The
+/// allowed values are documented as the `enum` of the parameter, and unknown fields are
+/// rejected with `400 Bad Request`.
+pub struct SortParam<T> {
+    values: Vec<SortKey>,
+    _marker: PhantomData<T>,
+}
+
+impl_list_param!(SortParam => SortKey, SORT_PARAMETER, filter::parse_sort);
+
+impl<T: Serialize> SortParam<T> {
+    /// Compares the given items by the keys (in order).
+    pub fn compare(&self, a: &T, b: &T) -> Ordering {
+        let a = serde_json::to_value(a).unwrap_or_default();
+        let b = serde_json::to_value(b).unwrap_or_default();
+        self.compare_values(&a, &b)
+    }
+
+    /// Sorts the given items (stably) by the keys.
+    pub fn sort(&self, items: &mut Vec<T>) {
+        if self.values.is_empty() {
+            return;
+        }
+
+        let mut pairs = items
+            .drain(..)
+            .map(|i| (serde_json::to_value(&i).unwrap_or_default(), i))
+            .collect::<Vec<_>>();
+        pairs.sort_by(|(a, _), (b, _)| self.compare_values(a, b));
+        items.extend(pairs.into_iter().map(|(_, i)| i));
+    }
+
+    fn compare_values(&self, a: &Value, b: &Value) -> Ordering {
+        self.values
+            .iter()
+            .map(|k| k.compare(a, b))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<T: Apiv2Schema> OperationModifier for SortParam<T> {
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        add_parameter(
+            op,
+            Parameter {
+                in_: ParameterIn::Query,
+                name: SORT_PARAMETER.into(),
+                description: Some(
+                    "Fields to sort by (comma-separated, with `-` prefixes for descending order)."
+                        .into(),
+                ),
+                data_type: Some(DataType::Array),
+                items: Some(Items {
+                    data_type: Some(DataType::String),
+                    enum_: filter::sort_values(&T::raw_schema()),
+                    ..Default::default()
+                }),
+                collection_format: None, // this defaults to csv
+                ..Default::default()
+            },
+        );
+    }
+
+    // Like the other parameter extractors, this only uses the properties of `T`.
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+/// Query extractor for the `fields` parameter of list (or get) endpoints, which takes
+/// comma-separated fields of `T` to be included in the response. The allowed values are
+/// documented as the `enum` of the parameter, and unknown fields are rejected with
+/// `400 Bad Request`. No fields means that all fields are included.
+pub struct FieldsParam<T> {
+    values: Vec<String>,
+    _marker: PhantomData<T>,
+}
+
+impl_list_param!(FieldsParam => String, FIELDS_PARAMETER, filter::parse_fields);
+
+impl<T: Serialize> FieldsParam<T> {
+    /// Serializes the given item, keeping only the selected fields (if any).
+    pub fn project(&self, item: &T) -> Value {
+        let mut value = serde_json::to_value(item).unwrap_or_default();
+        if let (false, Value::Object(map)) = (self.values.is_empty(), &mut value) {
+            map.retain(|k, _| self.values.contains(k));
+        }
+
+        value
+    }
+}
+
+impl<T: Apiv2Schema> OperationModifier for FieldsParam<T> {
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        add_parameter(
+            op,
+            Parameter {
+                in_: ParameterIn::Query,
+                name: FIELDS_PARAMETER.into(),
+                description: Some(
+                    "Fields to include in the response (comma-separated). All fields are \
+                     included by default."
+                        .into(),
+                ),
+                data_type: Some(DataType::Array),
+                items: Some(Items {
+                    data_type: Some(DataType::String),
+                    enum_: T::raw_schema()
+                        .properties
+                        .keys()
+                        .map(|k| Value::from(k.as_str()))
+                        .collect(),
+                    ..Default::default()
+                }),
+                collection_format: None, // this defaults to csv
+                ..Default::default()
+            },
+        );
+    }
+
+    // Like the other parameter extractors, this only uses the properties of `T`.
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+/// Extractor for header parameters, like `Query` for headers. The fields of `T` (by their
+/// serialized names, like `X-Request-Id`) are looked up in the headers of the request
+/// regardless of case, and their values are converted to the documented types of the fields.
+/// Requests with missing (required) or invalid headers are rejected with `400 Bad Request`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header<T>(pub T);
+
+impl<T> Header<T> {
+    /// Unwraps the header values.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Header<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned + Apiv2Schema> FromRequest for Header<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let mut op = DefaultOperationRaw::default();
+        add_struct_parameters::<T>(&mut op, ParameterIn::Header);
+
+        let mut values = serde_json::Map::new();
+        let params = op.parameters.iter().filter_map(Either::right);
+        for param in params.filter(|p| p.in_ == ParameterIn::Header) {
+            let raw = req
+                .headers()
+                .get_all(param.name.as_str())
+                .filter_map(|v| v.to_str().ok())
+                .collect::<Vec<_>>();
+            if raw.is_empty() {
+                continue;
+            }
+
+            match coerce_parameter(param, &raw) {
+                Ok(v) => {
+                    values.insert(param.name.clone(), v);
+                }
+                Err(_) => {
+                    let msg = format!("invalid value for header {:?}", param.name);
+                    return ready(Err(ErrorBadRequest(msg)));
+                }
+            }
+        }
+
+        ready(
+            serde_json::from_value(Value::Object(values))
+                .map(Header)
+                .map_err(|e| ErrorBadRequest(format!("invalid headers: {}", e))),
+        )
+    }
+}
+
+/// Extractor for cookies, like `Header` for cookies. The fields of `T` (by their serialized
+/// names) are looked up in the `Cookie` header of the request, and their values are converted
+/// to the documented types of the fields. Since v2 doesn't support cookie parameters, they're
+/// documented in the `x-cookies` extension of the operation (and as `cookie` parameters in v3).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cookie<T>(pub T);
+
+impl<T> Cookie<T> {
+    /// Unwraps the cookie values.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Cookie<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Cookie<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned + Apiv2Schema> FromRequest for Cookie<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let mut op = DefaultOperationRaw::default();
+        Self::update_parameter(&mut op);
+
+        let pairs = req
+            .headers()
+            .get_all(COOKIE)
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|c| c.split_once('='))
+            .map(|(k, v)| (k.trim(), v.trim().trim_matches('"')))
+            .collect::<Vec<_>>();
+
+        let mut values = serde_json::Map::new();
+        for cookie in &op.cookies {
+            let raw = match pairs.iter().find(|(k, _)| *k == cookie.name) {
+                Some((_, v)) => *v,
+                None => continue,
+            };
+
+            match coerce_parameter(&cookie.to_parameter(), &[raw]) {
+                Ok(v) => {
+                    values.insert(cookie.name.clone(), v);
+                }
+                Err(_) => {
+                    let msg = format!("invalid value for cookie {:?}", cookie.name);
+                    return ready(Err(ErrorBadRequest(msg)));
+                }
+            }
+        }
+
+        ready(
+            serde_json::from_value(Value::Object(values))
+                .map(Cookie)
+                .map_err(|e| ErrorBadRequest(format!("invalid cookies: {}", e))),
+        )
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T> Apiv2Schema for Cookie<T> {
+    default const NAME: Option<&'static str> = None;
+
+    default fn raw_schema() -> DefaultSchemaRaw {
+        Default::default()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        Self::raw_schema().into()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn add_schemas_v3(_map: &mut SchemaMap) {}
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T: Apiv2Schema> Apiv2Schema for Cookie<T> {}
+
+impl<T: Apiv2Schema> OperationModifier for Cookie<T> {
+    fn update_parameter(op: &mut DefaultOperationRaw) {
+        let def = T::raw_schema();
+        for (name, schema) in def.properties {
+            if op.cookies.iter().any(|c| c.name == name) {
+                continue;
+            }
+
+            op.cookies.push(CookieParameter {
+                required: def.required.contains(&name),
+                description: schema.description,
+                data_type: schema.data_type,
+                format: schema.format,
+                pattern: schema.pattern,
+                enum_: schema.enum_,
+                name,
+            });
+        }
+    }
+
+    // Like the other parameter extractors, this only uses the properties of `T`.
+    fn update_definitions(_map: &mut BTreeMap<String, DefaultSchemaRaw>) {}
+}
+
+macro_rules! impl_path_tuple ({ $($ty:ident),+ } => {
+    #[cfg(feature = "nightly")]
+    impl<$($ty,)+> Apiv2Schema for Path<($($ty,)+)> {}
+
+    #[cfg(not(feature = "nightly"))]
+    impl<$($ty: Apiv2Schema,)+> Apiv2Schema for Path<($($ty,)+)> {}
+
+    impl<$($ty,)+> OperationModifier for Path<($($ty,)+)>
+        where $($ty: Apiv2Schema,)+
+    {
+        fn update_parameter(op: &mut DefaultOperationRaw) {
+            $(
+                let def = $ty::raw_schema();
+                if def.properties.is_empty() {
+                    op.parameters.push(Either::Right(Parameter {
+                        // NOTE: We're setting empty name, because we don't know
+                        // the name in this context. We'll get it when we add services.
+                        name: String::new(),
+                        in_: ParameterIn::Path,
+                        required: true,
+                        data_type: def.data_type,
+                        format: def.format,
+                        enum_: def.enum_,
+                        description: def.description,
+                        ..Default::default()
+                    }));
+                }
+                add_struct_parameters::<$ty>(op, ParameterIn::Path);
+            )+
+        }
+    }
+});
+
+impl_path_tuple!(A);
+impl_path_tuple!(A, B);
+impl_path_tuple!(A, B, C);
+impl_path_tuple!(A, B, C, D);
+impl_path_tuple!(A, B, C, D, E);
+
+/// Wrapper for wrapping over `impl Responder` thingies (to avoid breakage).
+pub struct ResponderWrapper<T>(pub T);
+
+#[cfg(feature = "nightly")]
+impl<T: Responder> Apiv2Schema for ResponderWrapper<T> {
+    default const NAME: Option<&'static str> = None;
+
+    default fn raw_schema() -> DefaultSchemaRaw {
+        DefaultSchemaRaw::default()
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T: Responder> Apiv2Schema for ResponderWrapper<T> {}
+
+#[cfg(not(feature = "nightly"))]
+impl<T: Responder> OperationModifier for ResponderWrapper<T> {}
+
+impl<T: Responder> Responder for ResponderWrapper<T> {
+    type Body = T::Body;
+
+    #[inline]
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        self.0.respond_to(req)
+    }
+}
+
+/// Wrapper for all response types from handlers. This holds the actual value
+/// returned by the handler and a unit struct (autogenerated by the plugin) which
+/// is used for generating operation information.
+#[pin_project]
+pub struct ResponseWrapper<T, H>(#[pin] pub T, pub H);
+
+impl<T: Responder, H> Responder for ResponseWrapper<T, H> {
+    type Body = T::Body;
+
+    #[inline]
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        self.0.respond_to(req)
+    }
+}
+
+impl<F, T, H> Future for ResponseWrapper<F, H>
+where
+    F: Future<Output = T>,
+    T: OperationModifier + Responder,
+    H: Apiv2Operation,
+{
+    type Output = T;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.as_mut().project();
+        this.0.poll(ctx)
+    }
+}
+
+impl<F, T, H> Apiv2Operation for ResponseWrapper<F, H>
+where
+    F: Future<Output = T>,
+    T: OperationModifier + Responder,
+    H: Apiv2Operation,
+{
+    fn operation() -> DefaultOperationRaw {
+        H::operation()
+    }
+
+    fn security_definitions() -> BTreeMap<String, SecurityScheme> {
+        H::security_definitions()
+    }
+
+    fn definitions() -> BTreeMap<String, DefaultSchemaRaw> {
+        H::definitions()
+    }
+
+    #[cfg(feature = "v3")]
+    fn schemas_v3() -> SchemaMap {
+        H::schemas_v3()
+    }
+
+    fn is_visible() -> bool {
+        H::is_visible()
+    }
+
+    fn max_body_size() -> Option<usize> {
+        H::max_body_size()
+    }
+
+    fn handler() -> Option<&'static str> {
+        H::handler()
+    }
+}
+
+/// Given the schema type, recursively update the map of definitions.
+fn update_definitions_from_schema_type<T>(map: &mut BTreeMap<String, DefaultSchemaRaw>)
 where
     T: Apiv2Schema,
 {
@@ -528,7 +1383,7 @@ where
             continue;
         } else if let Some(n) = schema.name.take() {
             schema.remove_refs();
-            map.insert(n, schema);
+            map.insert(n.into(), schema);
         }
 
         break;
@@ -586,6 +1441,8 @@ macro_rules! json_with_status {
         where
             T: Serialize + Apiv2Schema,
         {
+            type Body = BoxBody;
+
             fn respond_to(self, _: &HttpRequest) -> HttpResponse {
                 let status: StatusCode = $status;
                 let body = match serde_json::to_string(&self.0) {
@@ -611,12 +1468,24 @@ macro_rules! json_with_status {
             fn raw_schema() -> DefaultSchemaRaw {
                 T::raw_schema()
             }
+
+            #[cfg(feature = "v3")]
+            fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+                T::raw_schema_v3()
+            }
+
+            #[cfg(feature = "v3")]
+            fn add_schemas_v3(map: &mut SchemaMap) {
+                T::add_schemas_v3(map);
+            }
         }
 
         impl<T> OperationModifier for $name<T>
         where
             T: Serialize + Apiv2Schema,
         {
+            const MEDIA_TYPE: Option<&'static str> = Some("application/json");
+
             fn update_response(op: &mut DefaultOperationRaw) {
                 let status: StatusCode = $status;
                 op.responses.insert(
@@ -639,6 +1508,318 @@ macro_rules! json_with_status {
 json_with_status!(CreatedJson => StatusCode::CREATED);
 json_with_status!(AcceptedJson => StatusCode::ACCEPTED);
 
+/// Reference to the status of a long-running operation (like a job), for responding
+/// with [`Accepted`](Accepted). The status can be polled at its URL through the (`GET`)
+/// operation with the given ID.
+pub trait StatusRef: Serialize + Apiv2Schema {
+    /// ID of the operation for polling the status.
+    const STATUS_OPERATION: &'static str;
+
+    /// URL of the status.
+    fn status_url(&self) -> String;
+}
+
+/// Responder for long-running operations, which responds with `202 Accepted` and the
+/// status reference (as JSON), along with its URL in the `Location` and `Operation-Location`
+/// headers. The response is documented with those headers and the ID of the operation for
+/// polling the status (in the `x-status-operation` extension, or as a link in v3 specs).
+pub struct Accepted<T: StatusRef>(pub T);
+
+impl<T> fmt::Debug for Accepted<T>
+where
+    T: fmt::Debug + StatusRef,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Accepted: {:?}", self.0)
+    }
+}
+
+impl<T: StatusRef> Responder for Accepted<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let body = match serde_json::to_string(&self.0) {
+            Ok(body) => body,
+            Err(_e) => {
+                return HttpResponse::build(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body("Internal Server Error".to_string())
+            }
+        };
+
+        let url = self.0.status_url();
+        HttpResponse::build(StatusCode::ACCEPTED)
+            .insert_header(("Location", url.as_str()))
+            .insert_header(("Operation-Location", url.as_str()))
+            .content_type("application/json")
+            .body(body)
+    }
+}
+
+impl<T: StatusRef> Apiv2Schema for Accepted<T> {
+    const NAME: Option<&'static str> = T::NAME;
+
+    fn raw_schema() -> DefaultSchemaRaw {
+        T::raw_schema()
+    }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
+}
+
+impl<T: StatusRef> OperationModifier for Accepted<T> {
+    const MEDIA_TYPE: Option<&'static str> = Some("application/json");
+
+    fn update_response(op: &mut DefaultOperationRaw) {
+        let status = StatusCode::ACCEPTED;
+        let url_header = |description: &str| ResponseHeader {
+            description: Some(description.into()),
+            data_type: Some(DataType::String),
+            ..Default::default()
+        };
+
+        let mut headers = BTreeMap::new();
+        headers.insert("Location".into(), url_header("URL of the status."));
+        headers.insert(
+            "Operation-Location".into(),
+            url_header("URL of the status (same as `Location`)."),
+        );
+        op.responses.insert(
+            status.as_str().into(),
+            Either::Right(Response {
+                description: status.canonical_reason().map(ToString::to_string),
+                schema: Some({
+                    let mut def = T::schema_with_ref();
+                    def.retain_ref();
+                    def
+                }),
+                headers,
+                status_operation: Some(T::STATUS_OPERATION.into()),
+                ..Default::default()
+            }),
+        );
+    }
+}
+
+/// Returns the name of the definition of the given schema (if any).
+fn definition_name<T: Apiv2Schema>() -> Option<String> {
+    T::NAME
+        .map(String::from)
+        .or_else(|| T::raw_schema().name.map(String::from))
+}
+
+/// Returns an array schema with the given schema for its items.
+fn array_of(items: DefaultSchemaRaw) -> DefaultSchemaRaw {
+    DefaultSchemaRaw {
+        data_type: Some(DataType::Array),
+        items: Some(items.into()),
+        ..Default::default()
+    }
+}
+
+/// Envelope for bulk requests (like `web::Json<Batch<Pet>>` for creating pets in bulk),
+/// which is an object with the `items` of the batch. Its schema is defined as
+/// `{T}Batch` (like `PetBatch`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Batch<T> {
+    /// Items of the batch.
+    pub items: Vec<T>,
+}
+
+impl<T> Batch<T> {
+    /// Returns the items of the batch.
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T> Deref for Batch<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.items
+    }
+}
+
+impl<T> DerefMut for Batch<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.items
+    }
+}
+
+impl<T: Apiv2Schema> Apiv2Schema for Batch<T> {
+    fn raw_schema() -> DefaultSchemaRaw {
+        let mut schema = DefaultSchemaRaw {
+            name: definition_name::<T>().map(|n| (n + "Batch").into()),
+            data_type: Some(DataType::Object),
+            ..Default::default()
+        };
+        schema
+            .properties
+            .insert("items".into(), array_of(T::schema_with_ref()).into());
+        schema.required.insert("items".into());
+        schema
+    }
+}
+
+/// Result of an item in a [`BatchResult`](BatchResult), which has the status (code)
+/// of the item, along with its data (if it succeeded) or its error (if it failed).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchItem<T, E> {
+    /// Status code of the item.
+    pub status: u16,
+    /// Data of the item (if it succeeded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    /// Error of the item (if it failed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<E>,
+}
+
+impl<T, E> BatchItem<T, E> {
+    /// Result of an item which succeeded with the given status.
+    pub fn ok(status: StatusCode, data: T) -> Self {
+        BatchItem {
+            status: status.as_u16(),
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    /// Result of an item which failed with the given status.
+    pub fn err(status: StatusCode, error: E) -> Self {
+        BatchItem {
+            status: status.as_u16(),
+            data: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Responder for bulk requests, which responds with `207 Multi-Status` and the results
+/// of the items (as JSON, in the order of the items of the request). Its schema is defined
+/// as `{T}{E}BatchResult` (like `PetErrorBatchResult`).
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchResult<T, E> {
+    /// Results of the items.
+    pub items: Vec<BatchItem<T, E>>,
+}
+
+impl<T, E> Default for BatchResult<T, E> {
+    fn default() -> Self {
+        BatchResult { items: vec![] }
+    }
+}
+
+impl<T, E> BatchResult<T, E> {
+    /// Creates an empty result.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the result of the next item.
+    pub fn push(&mut self, item: BatchItem<T, E>) {
+        self.items.push(item);
+    }
+}
+
+impl<T, E> FromIterator<BatchItem<T, E>> for BatchResult<T, E> {
+    fn from_iter<I: IntoIterator<Item = BatchItem<T, E>>>(iter: I) -> Self {
+        BatchResult {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: Serialize, E: Serialize> Responder for BatchResult<T, E> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let body = match serde_json::to_string(&self) {
+            Ok(body) => body,
+            Err(_e) => {
+                return HttpResponse::build(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body("Internal Server Error".to_string())
+            }
+        };
+
+        HttpResponse::build(StatusCode::MULTI_STATUS)
+            .content_type("application/json")
+            .body(body)
+    }
+}
+
+impl<T: Apiv2Schema, E: Apiv2Schema> Apiv2Schema for BatchResult<T, E> {
+    fn raw_schema() -> DefaultSchemaRaw {
+        let name = [definition_name::<T>(), definition_name::<E>()]
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<String>();
+        let mut item = DefaultSchemaRaw {
+            data_type: Some(DataType::Object),
+            ..Default::default()
+        };
+        item.properties.insert(
+            "status".into(),
+            DefaultSchemaRaw {
+                data_type: Some(DataType::Integer),
+                description: Some("Status code of the item.".into()),
+                ..Default::default()
+            }
+            .into(),
+        );
+        item.properties
+            .insert("data".into(), T::schema_with_ref().into());
+        item.properties
+            .insert("error".into(), E::schema_with_ref().into());
+        item.required.insert("status".into());
+
+        let mut schema = DefaultSchemaRaw {
+            name: Some(name)
+                .filter(|n| !n.is_empty())
+                .map(|n| (n + "BatchResult").into()),
+            data_type: Some(DataType::Object),
+            ..Default::default()
+        };
+        schema
+            .properties
+            .insert("items".into(), array_of(item).into());
+        schema.required.insert("items".into());
+        schema
+    }
+}
+
+impl<T, E> OperationModifier for BatchResult<T, E>
+where
+    T: Serialize + Apiv2Schema,
+    E: Serialize + Apiv2Schema,
+{
+    const MEDIA_TYPE: Option<&'static str> = Some("application/json");
+
+    fn update_response(op: &mut DefaultOperationRaw) {
+        let status = StatusCode::MULTI_STATUS;
+        op.responses.insert(
+            status.as_str().into(),
+            Either::Right(Response {
+                description: status.canonical_reason().map(ToString::to_string),
+                schema: Some({
+                    let mut def = Self::schema_with_ref();
+                    def.retain_ref();
+                    def
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+}
+
 #[derive(Debug)]
 pub struct NoContent;
 
@@ -649,6 +1830,8 @@ impl fmt::Display for NoContent {
 }
 
 impl Responder for NoContent {
+    type Body = BoxBody;
+
     fn respond_to(self, _: &HttpRequest) -> HttpResponse {
         HttpResponse::build(StatusCode::NO_CONTENT)
             .content_type("application/json")
@@ -671,3 +1854,349 @@ impl OperationModifier for NoContent {
         );
     }
 }
+
+/// Media types which [`Negotiate`](Negotiate) can respond with, in the order
+/// of preference (when the client accepts several of them equally).
+const NEGOTIATED_MEDIA_TYPES: &[&str] = &["application/json", "application/yaml", "text/csv"];
+
+/// Responder which serializes the value as JSON, YAML or CSV (with `200 OK`)
+/// based on the `Accept` header of the request, and responds with
+/// `406 Not Acceptable` if none of those are acceptable.
+///
+/// All three media types are added to the `produces` of the operation (unless
+/// it's been specified explicitly). CSV bodies are documented as strings, since
+/// rows are flattened to text.
+pub struct Negotiate<T: Serialize + Apiv2Schema>(pub T);
+
+impl<T> fmt::Debug for Negotiate<T>
+where
+    T: fmt::Debug + Serialize + Apiv2Schema,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Negotiate: {:?}", self.0)
+    }
+}
+
+impl<T> Responder for Negotiate<T>
+where
+    T: Serialize + Apiv2Schema,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let accept = req
+            .headers()
+            .get(actix_web::http::header::ACCEPT)
+            .and_then(|v| v.to_str().ok());
+        let media_type = match negotiate_media_type(accept) {
+            Some(m) => m,
+            None => return HttpResponse::build(StatusCode::NOT_ACCEPTABLE).finish(),
+        };
+
+        let body = match media_type {
+            "application/yaml" => serde_yaml::to_string(&self.0).map_err(|_| ()),
+            "text/csv" => serde_json::to_value(&self.0)
+                .map(|v| to_csv(&v))
+                .map_err(|_| ()),
+            _ => serde_json::to_string(&self.0).map_err(|_| ()),
+        };
+
+        match body {
+            Ok(body) => HttpResponse::build(StatusCode::OK)
+                .content_type(media_type)
+                .body(body),
+            Err(()) => HttpResponse::build(StatusCode::INTERNAL_SERVER_ERROR)
+                .body("Internal Server Error".to_string()),
+        }
+    }
+}
+
+impl<T> Apiv2Schema for Negotiate<T>
+where
+    T: Serialize + Apiv2Schema,
+{
+    const NAME: Option<&'static str> = T::NAME;
+
+    fn raw_schema() -> DefaultSchemaRaw {
+        T::raw_schema()
+    }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
+}
+
+impl<T> OperationModifier for Negotiate<T>
+where
+    T: Serialize + Apiv2Schema,
+{
+    fn update_response(op: &mut DefaultOperationRaw) {
+        let status = StatusCode::OK;
+        let mut content_schemas = BTreeMap::new();
+        content_schemas.insert(
+            "text/csv".into(),
+            DefaultSchemaRaw {
+                data_type: Some(DataType::String),
+                ..Default::default()
+            },
+        );
+
+        op.responses.insert(
+            status.as_str().into(),
+            Either::Right(Response {
+                description: status.canonical_reason().map(ToString::to_string),
+                schema: Some({
+                    let mut def = T::schema_with_ref();
+                    def.retain_ref();
+                    def
+                }),
+                content_schemas,
+                ..Default::default()
+            }),
+        );
+
+        if op.produces.is_none() {
+            op.produces = Some(
+                NEGOTIATED_MEDIA_TYPES
+                    .iter()
+                    .map(|m| MediaRange(m.parse().expect("parsing mime")))
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Picks the media type (among the ones supported by `Negotiate`) with the
+/// highest quality in the given `Accept` header. Missing or empty headers
+/// accept anything.
+fn negotiate_media_type(accept: Option<&str>) -> Option<&'static str> {
+    let accept = match accept {
+        Some(a) if !a.trim().is_empty() => a,
+        _ => return Some(NEGOTIATED_MEDIA_TYPES[0]),
+    };
+
+    let mut best: Option<(f32, &'static str)> = None;
+    for range in accept.split(',') {
+        let mut parts = range.split(';');
+        let range = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        let quality = parts
+            .filter_map(|p| {
+                let mut kv = p.splitn(2, '=');
+                match (kv.next().map(str::trim), kv.next()) {
+                    (Some("q"), Some(q)) => q.trim().parse::<f32>().ok(),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(1.0);
+        if quality <= 0.0 || best.map(|(q, _)| quality <= q).unwrap_or(false) {
+            continue;
+        }
+
+        let found = NEGOTIATED_MEDIA_TYPES
+            .iter()
+            .find(|m| match range.as_str() {
+                "*/*" => true,
+                "application/x-yaml" | "text/yaml" => **m == "application/yaml",
+                r if r.ends_with("/*") => m.starts_with(&r[..r.len() - 1]),
+                r => **m == r,
+            });
+        if let Some(m) = found {
+            best = Some((quality, *m));
+        }
+    }
+
+    best.map(|(_, m)| m)
+}
+
+/// Writes the value as CSV. Arrays of objects have a header row (with the keys
+/// of the first object) and a row for each object. Nested values are written
+/// as JSON.
+fn to_csv(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    fn quote(field: &str) -> String {
+        if field.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.into()
+        }
+    }
+
+    fn field(value: Option<&Value>) -> String {
+        match value {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => quote(s),
+            Some(v @ Value::Array(_)) | Some(v @ Value::Object(_)) => quote(&v.to_string()),
+            Some(v) => v.to_string(),
+        }
+    }
+
+    let rows = match value {
+        Value::Array(rows) => rows.iter().collect::<Vec<_>>(),
+        v => vec![v],
+    };
+
+    let mut csv = String::new();
+    let columns = match rows.first() {
+        Some(Value::Object(first)) => first.keys().cloned().collect::<Vec<_>>(),
+        _ => vec![],
+    };
+    if !columns.is_empty() {
+        let header = columns.iter().map(|c| quote(c)).collect::<Vec<_>>();
+        csv.push_str(&header.join(","));
+        csv.push_str("\r\n");
+    }
+
+    for row in rows {
+        let line = match row {
+            Value::Object(map) if !columns.is_empty() => columns
+                .iter()
+                .map(|c| field(map.get(c)))
+                .collect::<Vec<_>>()
+                .join(","),
+            Value::Array(values) => values
+                .iter()
+                .map(|v| field(Some(v)))
+                .collect::<Vec<_>>()
+                .join(","),
+            v => field(Some(v)),
+        };
+        csv.push_str(&line);
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+macro_rules! json_stream {
+    ($(#[$attr:meta])* $name:ident => $media:expr, $frame:expr) => {
+        $(#[$attr])*
+        pub struct $name<S>(pub S);
+
+        impl<S> fmt::Debug for $name<S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(stringify!($name))
+            }
+        }
+
+        impl<S, T, E> Responder for $name<S>
+        where
+            S: Stream<Item = Result<T, E>> + 'static,
+            T: Serialize,
+            E: Into<Box<dyn std::error::Error>> + 'static,
+        {
+            type Body = BoxBody;
+
+            fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+                HttpResponse::build(StatusCode::OK)
+                    .content_type($media)
+                    .streaming(FramedStream {
+                        stream: self.0,
+                        frame: $frame,
+                    })
+            }
+        }
+
+        impl<S, T, E> Apiv2Schema for $name<S>
+        where
+            S: Stream<Item = Result<T, E>>,
+            T: Serialize + Apiv2Schema,
+        {
+            const NAME: Option<&'static str> = T::NAME;
+
+            fn raw_schema() -> DefaultSchemaRaw {
+                T::raw_schema()
+            }
+
+            #[cfg(feature = "v3")]
+            fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+                T::raw_schema_v3()
+            }
+
+            #[cfg(feature = "v3")]
+            fn add_schemas_v3(map: &mut SchemaMap) {
+                T::add_schemas_v3(map);
+            }
+        }
+
+        impl<S, T, E> OperationModifier for $name<S>
+        where
+            S: Stream<Item = Result<T, E>>,
+            T: Serialize + Apiv2Schema,
+        {
+            const MEDIA_TYPE: Option<&'static str> = Some($media);
+
+            fn update_response(op: &mut DefaultOperationRaw) {
+                let status = StatusCode::OK;
+                op.responses.insert(
+                    status.as_str().into(),
+                    Either::Right(Response {
+                        description: status.canonical_reason().map(ToString::to_string),
+                        schema: Some({
+                            let mut def = T::schema_with_ref();
+                            def.retain_ref();
+                            def
+                        }),
+                        ..Default::default()
+                    }),
+                );
+            }
+        }
+    };
+}
+
+json_stream!(
+    /// Responder which streams the items of the given stream as
+    /// [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html)
+    /// (with each item serialized as JSON in the `data` of an event).
+    ///
+    /// The schema of the response is the schema of the items, and `text/event-stream`
+    /// is added to the `produces` of the operation (unless it's been specified explicitly).
+    EventStream => "text/event-stream",
+    |json| [&b"data: "[..], &json[..], &b"\n\n"[..]].concat()
+);
+json_stream!(
+    /// Responder which streams the items of the given stream as
+    /// [newline-delimited JSON](http://ndjson.org/).
+    ///
+    /// The schema of the response is the schema of the items, and `application/x-ndjson`
+    /// is added to the `produces` of the operation (unless it's been specified explicitly).
+    NdJson => "application/x-ndjson",
+    |json| [&json[..], &b"\n"[..]].concat()
+);
+
+/// Stream of the items (serialized as JSON) of the given stream, each of which is
+/// framed by the given function.
+#[pin_project]
+struct FramedStream<S> {
+    #[pin]
+    stream: S,
+    frame: fn(Vec<u8>) -> Vec<u8>,
+}
+
+impl<S, T, E> Stream for FramedStream<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    T: Serialize,
+    E: Into<Box<dyn std::error::Error>>,
+{
+    type Item = Result<Bytes, Box<dyn std::error::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let frame = *this.frame;
+        this.stream.poll_next(cx).map(|item| {
+            item.map(|result| match result {
+                Ok(item) => Ok(Bytes::from(frame(serde_json::to_vec(&item)?))),
+                Err(e) => Err(e.into()),
+            })
+        })
+    }
+}
@@ -0,0 +1,555 @@
+//! Builders for constructing spec fragments at runtime.
+//!
+//! These are useful for documenting routes which are only known at runtime
+//! (plugins, tenant-specific endpoints, etc.) and hence cannot be annotated
+//! with the macros.
+//!
+//! ```rust
+//! use paperclip_core::v2::builder::{OperationBuilder, ParameterBuilder, SchemaBuilder};
+//! use paperclip_core::v2::models::{DataType, DefaultApiRaw, HttpMethod};
+//!
+//! let pet = SchemaBuilder::object()
+//!     .name("Pet")
+//!     .required_property("id", SchemaBuilder::new(DataType::Integer))
+//!     .property("name", SchemaBuilder::new(DataType::String));
+//!
+//! let mut api = DefaultApiRaw::default();
+//! OperationBuilder::new()
+//!     .operation_id("getPet")
+//!     .summary("Fetch a pet")
+//!     .parameter(ParameterBuilder::path("id").data_type(DataType::Integer))
+//!     .response(200, "Pet with the given ID", Some(pet))
+//!     .add_to(&mut api, "/pets/{id}", HttpMethod::Get);
+//!
+//! let op = &api.paths["/pets/{id}"].methods[&HttpMethod::Get];
+//! assert_eq!(op.parameters[0].name, "id");
+//! assert!(api.definitions.contains_key("Pet"));
+//! ```
+
+use super::{
+    models::{
+        DataType, DataTypeFormat, DefaultApiRaw, DefaultOperationRaw, DefaultParameterRaw,
+        DefaultResponseRaw, DefaultSchemaRaw, Either, ExternalDocs, Header, HttpMethod, MediaRange,
+        ParameterIn,
+    },
+    schema::{definition_reference, Apiv2Schema},
+};
+use mime::Mime;
+
+use std::collections::BTreeMap;
+
+/// Builder for schema objects.
+///
+/// Named schemas (and schemas obtained from `Apiv2Schema` types) are collected
+/// as definitions and referenced wherever they're used in an operation.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaBuilder {
+    schema: DefaultSchemaRaw,
+    definitions: BTreeMap<String, DefaultSchemaRaw>,
+}
+
+impl SchemaBuilder {
+    /// Creates a schema for the given data type.
+    pub fn new(data_type: DataType) -> Self {
+        let mut builder = SchemaBuilder::default();
+        builder.schema.data_type = Some(data_type);
+        builder
+    }
+
+    /// Creates an object schema.
+    pub fn object() -> Self {
+        Self::new(DataType::Object)
+    }
+
+    /// Creates an array schema with the given item schema.
+    pub fn array(items: SchemaBuilder) -> Self {
+        let mut builder = Self::new(DataType::Array);
+        builder.schema.items = Some(Box::new(builder.absorb(items)));
+        builder
+    }
+
+    /// Creates a map (object with arbitrary keys) with the given value schema.
+    pub fn map(values: SchemaBuilder) -> Self {
+        let mut builder = Self::object();
+        builder.schema.extra_props = Some(Either::Right(Box::new(builder.absorb(values))));
+        builder
+    }
+
+    /// Creates a schema for the given type (as it would've been documented by the macros).
+    pub fn of<T: Apiv2Schema>() -> Self {
+        let mut builder = SchemaBuilder {
+            schema: T::schema_with_ref(),
+            definitions: BTreeMap::new(),
+        };
+
+        collect_definition(&mut builder.schema, &mut builder.definitions);
+        builder
+    }
+
+    /// Creates a reference to some definition in the spec.
+    pub fn reference(name: &str) -> Self {
+        let mut builder = SchemaBuilder::default();
+        builder.schema.reference = Some(definition_reference(name));
+        builder
+    }
+
+    /// Sets the name for this schema. Named schemas are added to the definitions
+    /// and referenced wherever they're used.
+    pub fn name(mut self, name: &str) -> Self {
+        self.schema.name = Some(name.to_owned().into());
+        self
+    }
+
+    /// Sets the format of this schema.
+    pub fn format(mut self, format: DataTypeFormat) -> Self {
+        self.schema.format = Some(format);
+        self
+    }
+
+    /// Sets the title of this schema.
+    pub fn title(mut self, title: &str) -> Self {
+        self.schema.title = Some(title.into());
+        self
+    }
+
+    /// Sets the description of this schema.
+    pub fn description(mut self, description: &str) -> Self {
+        self.schema.description = Some(description.into());
+        self
+    }
+
+    /// Sets the example for this schema.
+    pub fn example(mut self, example: &str) -> Self {
+        self.schema.example = Some(example.into());
+        self
+    }
+
+    /// Sets the values allowed for this schema.
+    pub fn enum_variants<I>(mut self, variants: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<serde_json::Value>,
+    {
+        self.schema.enum_ = variants.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds an optional property to this object.
+    pub fn property(mut self, name: &str, schema: SchemaBuilder) -> Self {
+        let schema = self.absorb(schema);
+        self.schema.properties.insert(name.into(), Box::new(schema));
+        self
+    }
+
+    /// Adds a required property to this object.
+    pub fn required_property(mut self, name: &str, schema: SchemaBuilder) -> Self {
+        self.schema.required.insert(name.into());
+        self.property(name, schema)
+    }
+
+    /// Returns the schema along with the definitions collected so far.
+    /// If this schema is named, then it's moved to the definitions and
+    /// a reference to it is returned instead.
+    pub fn build(mut self) -> (DefaultSchemaRaw, BTreeMap<String, DefaultSchemaRaw>) {
+        if let Some(name) = self.schema.name.take() {
+            let reference = SchemaBuilder::reference(&name).schema;
+            self.definitions
+                .insert(name.into(), std::mem::replace(&mut self.schema, reference));
+        }
+
+        (self.schema, self.definitions)
+    }
+
+    /// Collects the definitions from the given builder and returns its schema.
+    fn absorb(&mut self, other: SchemaBuilder) -> DefaultSchemaRaw {
+        let (schema, defs) = other.build();
+        self.definitions.extend(defs);
+        schema
+    }
+}
+
+/// Moves the named schema (which could be nested in arrays and maps) to the definitions
+/// and leaves a reference in its place.
+fn collect_definition(schema: &mut DefaultSchemaRaw, map: &mut BTreeMap<String, DefaultSchemaRaw>) {
+    if let Some(s) = schema.items.as_mut() {
+        collect_definition(s, map);
+    } else if let Some(Either::Right(s)) = schema.extra_props.as_mut() {
+        collect_definition(s, map);
+    } else if let Some(n) = schema.name.take() {
+        let mut def = schema.clone();
+        def.remove_refs();
+        map.insert(n.into(), def);
+        schema.retain_ref();
+    }
+}
+
+/// Builder for parameter objects.
+#[derive(Clone, Debug)]
+pub struct ParameterBuilder {
+    param: DefaultParameterRaw,
+    definitions: BTreeMap<String, DefaultSchemaRaw>,
+}
+
+impl ParameterBuilder {
+    /// Creates a parameter with the given name and location. Path parameters
+    /// are always required.
+    pub fn new(name: &str, in_: ParameterIn) -> Self {
+        ParameterBuilder {
+            param: DefaultParameterRaw {
+                name: name.into(),
+                in_,
+                required: in_ == ParameterIn::Path,
+                data_type: Some(DataType::String),
+                ..Default::default()
+            },
+            definitions: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a path parameter.
+    pub fn path(name: &str) -> Self {
+        Self::new(name, ParameterIn::Path)
+    }
+
+    /// Creates a query parameter.
+    pub fn query(name: &str) -> Self {
+        Self::new(name, ParameterIn::Query)
+    }
+
+    /// Creates a header parameter.
+    pub fn header(name: &str) -> Self {
+        Self::new(name, ParameterIn::Header)
+    }
+
+    /// Creates a (required) body parameter with the given schema.
+    pub fn body(schema: SchemaBuilder) -> Self {
+        let mut builder = Self::new("body", ParameterIn::Body);
+        builder.param.data_type = None;
+        builder.param.required = true;
+        builder.schema(schema)
+    }
+
+    /// Sets the description of this parameter.
+    pub fn description(mut self, description: &str) -> Self {
+        self.param.description = Some(description.into());
+        self
+    }
+
+    /// Sets whether this parameter is required.
+    pub fn required(mut self, required: bool) -> Self {
+        self.param.required = required;
+        self
+    }
+
+    /// Sets the data type of this (non-body) parameter.
+    pub fn data_type(mut self, data_type: DataType) -> Self {
+        self.param.data_type = Some(data_type);
+        self
+    }
+
+    /// Sets the format of this (non-body) parameter.
+    pub fn format(mut self, format: DataTypeFormat) -> Self {
+        self.param.format = Some(format);
+        self
+    }
+
+    /// Sets the default value of this parameter.
+    pub fn default_value<V: Into<serde_json::Value>>(mut self, value: V) -> Self {
+        self.param.default = Some(value.into());
+        self
+    }
+
+    /// Sets the schema of this (body) parameter.
+    pub fn schema(mut self, schema: SchemaBuilder) -> Self {
+        let (schema, defs) = schema.build();
+        self.param.schema = Some(schema);
+        self.definitions.extend(defs);
+        self
+    }
+
+    /// Returns the parameter along with the definitions used by it.
+    pub fn build(self) -> (DefaultParameterRaw, BTreeMap<String, DefaultSchemaRaw>) {
+        (self.param, self.definitions)
+    }
+}
+
+/// Builder for operation objects.
+#[derive(Clone, Debug, Default)]
+pub struct OperationBuilder {
+    op: DefaultOperationRaw,
+    definitions: BTreeMap<String, DefaultSchemaRaw>,
+}
+
+impl OperationBuilder {
+    /// Creates an empty operation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the ID of this operation.
+    pub fn operation_id(mut self, id: &str) -> Self {
+        self.op.operation_id = Some(id.into());
+        self
+    }
+
+    /// Sets the summary of this operation.
+    pub fn summary(mut self, summary: &str) -> Self {
+        self.op.summary = Some(summary.into());
+        self
+    }
+
+    /// Sets the description of this operation.
+    pub fn description(mut self, description: &str) -> Self {
+        self.op.description = Some(description.into());
+        self
+    }
+
+    /// Adds a tag to this operation.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.op.tags.push(tag.into());
+        self
+    }
+
+    /// Links this operation to the external documentation at the given URL.
+    pub fn external_docs(mut self, url: &str, description: Option<&str>) -> Self {
+        self.op.external_docs = Some(ExternalDocs::new(url, description));
+        self
+    }
+
+    /// Marks this operation as deprecated.
+    pub fn deprecated(mut self) -> Self {
+        self.op.deprecated = true;
+        self
+    }
+
+    /// Adds a media range consumed by this operation.
+    pub fn consumes(mut self, mime: Mime) -> Self {
+        self.op
+            .consumes
+            .get_or_insert_with(Default::default)
+            .insert(MediaRange(mime));
+        self
+    }
+
+    /// Adds a media range produced by this operation.
+    pub fn produces(mut self, mime: Mime) -> Self {
+        self.op
+            .produces
+            .get_or_insert_with(Default::default)
+            .insert(MediaRange(mime));
+        self
+    }
+
+    /// Adds a security requirement (name of the security definition along
+    /// with the scopes) to this operation.
+    pub fn security(mut self, name: &str, scopes: &[&str]) -> Self {
+        let mut map = BTreeMap::new();
+        map.insert(name.into(), scopes.iter().map(|&s| s.into()).collect());
+        self.op.security.push(map);
+        self
+    }
+
+    /// Adds a parameter to this operation.
+    pub fn parameter(mut self, param: ParameterBuilder) -> Self {
+        let (param, defs) = param.build();
+        self.op.parameters.push(Either::Right(param));
+        self.definitions.extend(defs);
+        self
+    }
+
+    /// Adds a response (with an optional schema) for the given status code.
+    pub fn response(
+        mut self,
+        status: u16,
+        description: &str,
+        schema: Option<SchemaBuilder>,
+    ) -> Self {
+        let schema = schema.map(|s| {
+            let (schema, defs) = s.build();
+            self.definitions.extend(defs);
+            schema
+        });
+
+        self.op.responses.insert(
+            status.to_string(),
+            Either::Right(DefaultResponseRaw {
+                description: Some(description.into()),
+                schema,
+                ..Default::default()
+            }),
+        );
+        self
+    }
+
+    /// Documents a header (of the given type) in the response with the given status code,
+    /// which should have been added before.
+    pub fn response_header(
+        mut self,
+        status: u16,
+        name: &str,
+        data_type: DataType,
+        description: &str,
+    ) -> Self {
+        let header = Header {
+            description: Some(description.into()),
+            data_type: Some(data_type),
+            ..Default::default()
+        };
+        self.op
+            .add_response_header(Some(&status.to_string()), name, header);
+        self
+    }
+
+    /// Returns the operation along with the definitions used by it.
+    pub fn build(self) -> (DefaultOperationRaw, BTreeMap<String, DefaultSchemaRaw>) {
+        (self.op, self.definitions)
+    }
+
+    /// Adds this operation (and its definitions) to the given spec.
+    pub fn add_to(self, api: &mut DefaultApiRaw, path: &str, method: HttpMethod) {
+        let (op, defs) = self.build();
+        api.definitions.extend(defs);
+        api.paths
+            .entry(path.into())
+            .or_default()
+            .methods
+            .insert(method, op);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parameter() {
+        let (param, defs) = ParameterBuilder::query("limit")
+            .description("Maximum number of pets.")
+            .data_type(DataType::Integer)
+            .format(DataTypeFormat::Int32)
+            .default_value(20)
+            .build();
+        assert!(defs.is_empty());
+        assert_eq!(
+            serde_json::to_value(&param).unwrap(),
+            json!({
+                "in": "query",
+                "name": "limit",
+                "description": "Maximum number of pets.",
+                "type": "integer",
+                "format": "int32",
+                "default": 20
+            })
+        );
+
+        let (param, _) = ParameterBuilder::path("id").build();
+        assert_eq!(
+            serde_json::to_value(&param).unwrap(),
+            json!({ "in": "path", "name": "id", "required": true, "type": "string" })
+        );
+    }
+
+    #[test]
+    fn test_body_parameter_with_definition() {
+        let pet = SchemaBuilder::object()
+            .name("Pet")
+            .required_property("name", SchemaBuilder::new(DataType::String));
+        let (param, defs) = ParameterBuilder::body(pet).build();
+        assert_eq!(
+            serde_json::to_value(&param).unwrap(),
+            json!({
+                "in": "body",
+                "name": "body",
+                "required": true,
+                "schema": { "$ref": "#/definitions/Pet" }
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&defs).unwrap(),
+            json!({
+                "Pet": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_response() {
+        let (op, defs) = OperationBuilder::new()
+            .response(
+                200,
+                "Pets",
+                Some(SchemaBuilder::array(SchemaBuilder::reference("Pet"))),
+            )
+            .response_header(200, "X-Total-Count", DataType::Integer, "Number of pets.")
+            .response(404, "Not found", None)
+            .build();
+        assert!(defs.is_empty());
+        assert_eq!(
+            serde_json::to_value(&op.responses).unwrap(),
+            json!({
+                "200": {
+                    "description": "Pets",
+                    "schema": { "type": "array", "items": { "$ref": "#/definitions/Pet" } },
+                    "headers": {
+                        "X-Total-Count": { "description": "Number of pets.", "type": "integer" }
+                    }
+                },
+                "404": { "description": "Not found" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_operation() {
+        let mut api = DefaultApiRaw::default();
+        OperationBuilder::new()
+            .operation_id("addPet")
+            .summary("Add a pet")
+            .tag("pets")
+            .deprecated()
+            .consumes(mime::APPLICATION_JSON)
+            .produces(mime::APPLICATION_JSON)
+            .security("api_key", &[])
+            .parameter(ParameterBuilder::header("X-Tenant").required(true))
+            .parameter(ParameterBuilder::body(SchemaBuilder::object().name("Pet")))
+            .response(201, "Created", Some(SchemaBuilder::reference("Pet")))
+            .add_to(&mut api, "/pets", HttpMethod::Post);
+
+        assert!(api.definitions.contains_key("Pet"));
+        assert_eq!(
+            serde_json::to_value(&api.paths["/pets"].methods[&HttpMethod::Post]).unwrap(),
+            json!({
+                "operationId": "addPet",
+                "summary": "Add a pet",
+                "tags": ["pets"],
+                "deprecated": true,
+                "consumes": ["application/json"],
+                "produces": ["application/json"],
+                "security": [{ "api_key": [] }],
+                "parameters": [
+                    { "in": "header", "name": "X-Tenant", "required": true, "type": "string" },
+                    {
+                        "in": "body",
+                        "name": "body",
+                        "required": true,
+                        "schema": { "$ref": "#/definitions/Pet" }
+                    }
+                ],
+                "responses": {
+                    "201": { "description": "Created", "schema": { "$ref": "#/definitions/Pet" } }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_reference() {
+        let (schema, _) = SchemaBuilder::reference("Pet").build();
+        assert_eq!(schema.reference.as_deref(), Some("#/definitions/Pet"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Generic types for composing schemas out of existing ones (similar to the
+//! utility types in TypeScript), so that variants of the same model (for
+//! requests, responses, updates, etc.) don't need hand-copied structs.
+//!
+//! Fields can also be omitted from derived schemas through
+//! `#[openapi(omit(fields = "..."))]`, which generates a sibling struct.
+
+use super::{models::DataType, models::DefaultSchemaRaw, schema::Apiv2Schema};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use std::{fmt, marker::PhantomData};
+
+/// Object with the fields of both the objects, which is (de)serialized by
+/// flattening them. Its schema (named `{A}And{B}`) has the properties of both.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Merged<A, B> {
+    #[serde(flatten)]
+    pub first: A,
+    #[serde(flatten)]
+    pub second: B,
+}
+
+impl<A: Apiv2Schema, B: Apiv2Schema> Apiv2Schema for Merged<A, B> {
+    fn raw_schema() -> DefaultSchemaRaw {
+        let mut schema = A::raw_schema();
+        let other = B::raw_schema();
+        schema.name = match (schema.name.take(), other.name) {
+            (Some(a), Some(b)) => Some(format!("{}And{}", a, b).into()),
+            _ => None,
+        };
+        schema.data_type = Some(DataType::Object);
+        schema.description = None;
+        schema.properties.extend(other.properties);
+        schema.required.extend(other.required);
+        schema.reference = None;
+        schema
+    }
+}
+
+/// Object with some (or none) of the fields of `T`, for updating values of
+/// `T` (say, in `PATCH` requests). Its schema (named `Partial{T}`) has the
+/// properties of `T`, none of which are required.
+pub struct Partial<T> {
+    fields: Map<String, Value>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Partial<T> {
+    /// Returns the value of the given field (if it's been set).
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields.get(field)
+    }
+
+    /// Returns the fields which have been set.
+    pub fn fields(&self) -> &Map<String, Value> {
+        &self.fields
+    }
+
+    /// Updates the given value with the fields (as a
+    /// [JSON merge patch](https://tools.ietf.org/html/rfc7386), so `null`
+    /// unsets optional fields).
+    pub fn apply(&self, target: &mut T) -> Result<(), serde_json::Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut value = serde_json::to_value(&*target)?;
+        crate::util::merge_patch(&mut value, &Value::Object(self.fields.clone()));
+        *target = serde_json::from_value(value)?;
+        Ok(())
+    }
+}
+
+impl<T> From<Map<String, Value>> for Partial<T> {
+    fn from(fields: Map<String, Value>) -> Self {
+        Partial {
+            fields,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Partial<T> {
+    fn clone(&self) -> Self {
+        self.fields.clone().into()
+    }
+}
+
+impl<T> Default for Partial<T> {
+    fn default() -> Self {
+        Map::new().into()
+    }
+}
+
+impl<T> fmt::Debug for Partial<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Partial").field(&self.fields).finish()
+    }
+}
+
+impl<T> Serialize for Partial<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fields.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Partial<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Map::deserialize(deserializer).map(Into::into)
+    }
+}
+
+impl<T: Apiv2Schema> Apiv2Schema for Partial<T> {
+    fn raw_schema() -> DefaultSchemaRaw {
+        let mut schema = T::raw_schema();
+        let name = schema.name.take();
+        schema.name = T::NAME
+            .or(name.as_deref())
+            .map(|n| format!("Partial{}", n).into());
+        schema.required.clear();
+        schema.reference = None;
+        schema
+    }
+}
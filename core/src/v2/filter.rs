@@ -0,0 +1,350 @@
+//! Filter, sort and field selection expressions for list endpoints.
+//!
+//! Filters are comma-separated `field:op:value` clauses (all of which have to match),
+//! like `status:eq:sold,age:gt:3`. The fields are the (serialized) names of the scalar
+//! properties of a schema, and the values are converted to the types of those fields.
+//! The operators are `eq`, `ne`, `gt`, `ge`, `lt`, `le` and `in` (which takes values
+//! separated by `|`, like `status:in:sold|pending`).
+//!
+//! Sort expressions are comma-separated fields (with `-` prefixes for descending order,
+//! like `-age,name`), and field selections are comma-separated property names.
+//!
+//! ```rust
+//! use paperclip_core::v2::{filter, models::DefaultSchemaRaw};
+//! use serde_json::json;
+//!
+//! let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "age": { "type": "integer" },
+//!         "status": { "type": "string" }
+//!     }
+//! }))
+//! .unwrap();
+//!
+//! let filters = filter::parse_filters("status:in:sold|pending,age:gt:3", &schema).unwrap();
+//! assert!(filters.iter().all(|f| f.matches(&json!({ "status": "sold", "age": 5 }))));
+//! assert!(!filters[1].matches(&json!({ "status": "sold", "age": 2 })));
+//! assert!(filter::parse_filters("owner:eq:me", &schema).is_err());
+//!
+//! let keys = filter::parse_sort("-age", &schema).unwrap();
+//! assert!(keys[0].descending);
+//! assert_eq!(filter::parse_fields("status", &schema).unwrap(), vec!["status"]);
+//! ```
+
+use super::{
+    models::{DataType, DefaultSchemaRaw, Parameter},
+    validate::coerce_parameter,
+};
+use serde_json::Value;
+use thiserror::Error;
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// Name of the query parameter with the filter expression.
+pub const FILTER_PARAMETER: &str = "filter";
+/// Name of the query parameter with the sort expression.
+pub const SORT_PARAMETER: &str = "sort";
+/// Name of the query parameter with the selected fields.
+pub const FIELDS_PARAMETER: &str = "fields";
+
+/// Comparison operator in a filter clause.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    In,
+}
+
+impl FilterOp {
+    /// All the operators (in the order they're documented).
+    pub const ALL: [FilterOp; 7] = [
+        FilterOp::Eq,
+        FilterOp::Ne,
+        FilterOp::Gt,
+        FilterOp::Ge,
+        FilterOp::Lt,
+        FilterOp::Le,
+        FilterOp::In,
+    ];
+
+    /// Returns the name of this operator in filter expressions.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterOp::Eq => "eq",
+            FilterOp::Ne => "ne",
+            FilterOp::Gt => "gt",
+            FilterOp::Ge => "ge",
+            FilterOp::Lt => "lt",
+            FilterOp::Le => "le",
+            FilterOp::In => "in",
+        }
+    }
+
+    /// Returns whether this operator compares the order of values.
+    fn is_ordering(self) -> bool {
+        matches!(
+            self,
+            FilterOp::Gt | FilterOp::Ge | FilterOp::Lt | FilterOp::Le
+        )
+    }
+}
+
+impl fmt::Display for FilterOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FilterOp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FilterOp::ALL
+            .iter()
+            .copied()
+            .find(|op| op.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// Clause in a filter expression. For `in` clauses, the value is an array.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    /// (Serialized) name of the field.
+    pub field: String,
+    /// The operator.
+    pub op: FilterOp,
+    /// Value (of the type of the field) compared against.
+    pub value: Value,
+}
+
+impl Filter {
+    /// Checks whether the given (serialized) item matches this clause. Missing fields
+    /// are taken as `null`, which only matches `ne` clauses.
+    pub fn matches(&self, item: &Value) -> bool {
+        let actual = &item[self.field.as_str()];
+        match self.op {
+            FilterOp::Eq => compare(actual, &self.value) == Some(Ordering::Equal),
+            FilterOp::Ne => compare(actual, &self.value) != Some(Ordering::Equal),
+            FilterOp::Gt => compare(actual, &self.value) == Some(Ordering::Greater),
+            FilterOp::Ge => matches!(
+                compare(actual, &self.value),
+                Some(Ordering::Greater) | Some(Ordering::Equal)
+            ),
+            FilterOp::Lt => compare(actual, &self.value) == Some(Ordering::Less),
+            FilterOp::Le => matches!(
+                compare(actual, &self.value),
+                Some(Ordering::Less) | Some(Ordering::Equal)
+            ),
+            FilterOp::In => self
+                .value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|v| compare(actual, v) == Some(Ordering::Equal)),
+        }
+    }
+}
+
+/// Key in a sort expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    /// (Serialized) name of the field.
+    pub field: String,
+    /// Whether the items are sorted in descending order.
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// Compares the given (serialized) items by this key. Missing fields (and `null`s)
+    /// come before other values in ascending order.
+    pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        let (a, b) = (&a[self.field.as_str()], &b[self.field.as_str()]);
+        let ord = match (a, b) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => Ordering::Less,
+            (_, Value::Null) => Ordering::Greater,
+            _ => compare(a, b).unwrap_or(Ordering::Equal),
+        };
+
+        if self.descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+}
+
+/// Errors in filter, sort and field selection expressions.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum FilterError {
+    /// The clause isn't of the form `field:op:value`.
+    #[error("invalid filter clause {:?} (expected `field:op:value`)", _0)]
+    InvalidClause(String),
+    /// The field doesn't exist (or it can't be used in the expression).
+    #[error("unknown field {:?}", _0)]
+    UnknownField(String),
+    /// The operator doesn't exist or it can't be used with the type of the field.
+    #[error("unsupported operator {:?} for filter field {:?}", _1, _0)]
+    UnsupportedOperator(String, String),
+    /// The value can't be converted to the type of the field (or it's not one of its values).
+    #[error("invalid value {:?} for filter field {:?}", _1, _0)]
+    InvalidValue(String, String),
+}
+
+/// Parses the given filter expression against the properties of the given schema.
+/// Empty expressions (and empty clauses) are ignored.
+pub fn parse_filters(expr: &str, schema: &DefaultSchemaRaw) -> Result<Vec<Filter>, FilterError> {
+    let mut filters = vec![];
+    for clause in expr.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let mut parts = clause.splitn(3, ':');
+        let (field, op, raw) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(f), Some(op), Some(v)) => (f, op, v),
+            _ => return Err(FilterError::InvalidClause(clause.into())),
+        };
+
+        let prop = schema
+            .properties
+            .get(field)
+            .filter(|p| is_filterable(p))
+            .ok_or_else(|| FilterError::UnknownField(field.into()))?;
+        let op = op
+            .parse::<FilterOp>()
+            .ok()
+            .filter(|op| !(op.is_ordering() && prop.data_type == Some(DataType::Boolean)))
+            .ok_or_else(|| FilterError::UnsupportedOperator(field.into(), op.into()))?;
+
+        let value = if op == FilterOp::In {
+            let values = raw.split('|').map(|v| coerce_value(field, prop, v));
+            Value::Array(values.collect::<Result<_, _>>()?)
+        } else {
+            coerce_value(field, prop, raw)?
+        };
+
+        filters.push(Filter {
+            field: field.into(),
+            op,
+            value,
+        });
+    }
+
+    Ok(filters)
+}
+
+/// Parses the given sort expression against the properties of the given schema.
+pub fn parse_sort(expr: &str, schema: &DefaultSchemaRaw) -> Result<Vec<SortKey>, FilterError> {
+    let mut keys = vec![];
+    for key in expr.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        let (field, descending) = match key.strip_prefix('-') {
+            Some(f) => (f, true),
+            None => (key.trim_start_matches('+'), false),
+        };
+
+        if !filterable_fields(schema).contains(&field) {
+            return Err(FilterError::UnknownField(field.into()));
+        }
+
+        keys.push(SortKey {
+            field: field.into(),
+            descending,
+        });
+    }
+
+    Ok(keys)
+}
+
+/// Parses the given field selection against the properties of the given schema.
+pub fn parse_fields(expr: &str, schema: &DefaultSchemaRaw) -> Result<Vec<String>, FilterError> {
+    let mut fields = vec![];
+    for field in expr.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !schema.properties.contains_key(field) {
+            return Err(FilterError::UnknownField(field.into()));
+        }
+
+        if !fields.iter().any(|f| f == field) {
+            fields.push(field.into());
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Returns the values allowed in sort expressions for the given schema (the fields
+/// which can be filtered, with and without the `-` prefix).
+pub fn sort_values(schema: &DefaultSchemaRaw) -> Vec<Value> {
+    filterable_fields(schema)
+        .into_iter()
+        .flat_map(|f| vec![Value::from(f), Value::from(format!("-{}", f))])
+        .collect()
+}
+
+/// Returns the (serialized) names of the properties of the given schema which can be
+/// filtered or sorted (i.e., the ones with scalar types).
+pub fn filterable_fields(schema: &DefaultSchemaRaw) -> Vec<&str> {
+    schema
+        .properties
+        .iter()
+        .filter(|(_, p)| is_filterable(p))
+        .map(|(k, _)| k.as_str())
+        .collect()
+}
+
+/// Returns the description of the filter parameter for the given schema, which
+/// documents the grammar along with the fields which can be filtered.
+pub fn describe_filters(schema: &DefaultSchemaRaw) -> String {
+    let ops = FilterOp::ALL
+        .iter()
+        .map(|op| format!("`{}`", op))
+        .collect::<Vec<_>>();
+    let fields = filterable_fields(schema)
+        .into_iter()
+        .map(|f| format!("`{}`", f))
+        .collect::<Vec<_>>();
+    format!(
+        "Filter expression of comma-separated `field:op:value` clauses, all of which have \
+         to match (like `field:eq:value`). Operators: {} (with values separated by `|`). \
+         Fields: {}.",
+        ops.join(", "),
+        fields.join(", ")
+    )
+}
+
+fn is_filterable(schema: &DefaultSchemaRaw) -> bool {
+    matches!(
+        schema.data_type,
+        Some(DataType::String)
+            | Some(DataType::Integer)
+            | Some(DataType::Number)
+            | Some(DataType::Boolean)
+    )
+}
+
+/// Converts the raw value to the type of the given property (checking its `enum` if any).
+fn coerce_value(field: &str, prop: &DefaultSchemaRaw, raw: &str) -> Result<Value, FilterError> {
+    let param = Parameter {
+        data_type: prop.data_type,
+        format: prop.format.clone(),
+        ..Default::default()
+    };
+    coerce_parameter(&param, &[raw])
+        .ok()
+        .filter(|v| prop.enum_.is_empty() || prop.enum_.contains(v))
+        .ok_or_else(|| FilterError::InvalidValue(field.into(), raw.into()))
+}
+
+/// Compares values of the same type (numbers are compared regardless of their
+/// representation). Values of different types aren't comparable.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
@@ -0,0 +1,259 @@
+//! Bridges for types implementing [`schemars::JsonSchema`](https://docs.rs/schemars)
+//! (with the `schemars` feature) or [`utoipa::ToSchema`](https://docs.rs/utoipa) (with
+//! the `utoipa` feature), so that types which already derive those (for other tooling,
+//! or while migrating between the two) don't need to derive `Apiv2Schema` as well.
+//!
+//! ```ignore
+//! #[derive(Deserialize, Serialize, JsonSchema)]
+//! struct Pet {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize, Serialize, ToSchema)]
+//! struct Owner {
+//!     name: String,
+//! }
+//!
+//! paperclip::v2::impl_apiv2_schema_for_json_schema!(Pet);
+//! paperclip::v2::impl_apiv2_schema_for_to_schema!(Owner);
+//! ```
+//!
+//! JSON schemas (and OpenAPI v3 schemas) are more expressive than v2 schemas, so
+//! constructs which can't be expressed in v2 are approximated: `null` is dropped from
+//! types (i.e., nullable values are documented as their non-null types), compositions
+//! of a single schema (other than `null`) are replaced by the schema, and other
+//! compositions, tuples and unknown formats are dropped.
+
+use super::models::{DefaultSchemaRaw, Either};
+use super::schema::definition_reference;
+#[cfg(feature = "schemars")]
+use ::schemars::{gen::SchemaSettings, schema::RootSchema, JsonSchema};
+#[cfg(feature = "utoipa")]
+use ::utoipa::ToSchema;
+use serde_json::{Map, Value};
+
+/// Prefixes of references to definitions in JSON schemas and OpenAPI v3 schemas.
+const REF_PREFIXES: &[&str] = &["#/definitions/", "#/components/schemas/"];
+
+/// Formats which exist in v2 (other formats are dropped).
+const FORMATS: &[&str] = &[
+    "int32",
+    "int64",
+    "float",
+    "double",
+    "byte",
+    "binary",
+    "date",
+    "date-time",
+    "password",
+    "url",
+    "uuid",
+];
+
+/// Returns the (v2) schema of the given type, named after its JSON schema.
+#[cfg(feature = "schemars")]
+pub fn schema_for<T: JsonSchema>() -> DefaultSchemaRaw {
+    let settings = SchemaSettings::draft07().with(|s| {
+        s.definitions_path = "#/definitions/".into();
+        s.option_nullable = false;
+        s.option_add_null_type = false;
+    });
+
+    let mut schema = from_json_schema(&settings.into_generator().into_root_schema_for::<T>());
+    if T::is_referenceable() {
+        schema.name = Some(T::schema_name().into());
+    }
+
+    schema
+}
+
+/// Converts the given (root) JSON schema to a v2 schema. The definitions it refers
+/// to are inlined (and named after the definitions), like the ones in the schemas
+/// of types deriving `Apiv2Schema`, except for recursive references.
+#[cfg(feature = "schemars")]
+pub fn from_json_schema(root: &RootSchema) -> DefaultSchemaRaw {
+    let definitions = serde_json::to_value(&root.definitions).unwrap_or_default();
+    let definitions = definitions.as_object().cloned().unwrap_or_default();
+    let mut schema = serde_json::to_value(&root.schema).unwrap_or_default();
+    // The title of the root is its name, so recursive references refer to the root.
+    let mut stack = vec![];
+    if let Some(Value::String(title)) = schema.as_object_mut().and_then(|m| m.remove("title")) {
+        stack.push(title);
+    }
+
+    convert(&schema, &definitions, &mut stack)
+}
+
+/// Returns the (v2) schema of the given type, named after its `utoipa` schema.
+#[cfg(feature = "utoipa")]
+pub fn to_schema_for<T: ToSchema>() -> DefaultSchemaRaw {
+    let mut schemas = vec![];
+    T::schemas(&mut schemas);
+    let definitions = schemas
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or_default()))
+        .collect();
+
+    // References to the type itself are recursive.
+    let name = T::name().into_owned();
+    let schema = serde_json::to_value(T::schema()).unwrap_or_default();
+    let mut schema = convert(&schema, &definitions, &mut vec![name.clone()]);
+    schema.name = Some(name.into());
+    schema
+}
+
+/// Converts the given JSON schema (or OpenAPI v3 schema) to a v2 schema, inlining
+/// the given definitions it refers to (through `#/definitions/...` or
+/// `#/components/schemas/...`). Recursive references are made to refer to v2 definitions.
+pub fn from_json_value(schema: &Value, definitions: &Map<String, Value>) -> DefaultSchemaRaw {
+    convert(schema, definitions, &mut vec![])
+}
+
+/// Converts the given JSON schema, inlining the definitions which aren't being
+/// converted already (i.e., in the given stack).
+fn convert(
+    value: &Value,
+    definitions: &Map<String, Value>,
+    stack: &mut Vec<String>,
+) -> DefaultSchemaRaw {
+    // Boolean schemas (i.e., anything or nothing) don't have any properties.
+    let mut map = match value {
+        Value::Object(m) => m.clone(),
+        _ => return DefaultSchemaRaw::default(),
+    };
+
+    if let Some(Value::String(reference)) = map.get("$ref") {
+        let name = REF_PREFIXES.iter().find_map(|p| reference.strip_prefix(p));
+        let reference = match name {
+            Some(name) => match definitions.get(name) {
+                Some(def) if !stack.iter().any(|n| n == name) => {
+                    stack.push(name.into());
+                    let mut schema = convert(def, definitions, stack);
+                    stack.pop();
+                    schema.name = Some(name.to_owned().into());
+                    return schema;
+                }
+                _ => definition_reference(name),
+            },
+            // External schemas.
+            None => reference.clone().into(),
+        };
+
+        return DefaultSchemaRaw {
+            reference: Some(reference),
+            ..Default::default()
+        };
+    }
+
+    for key in &["allOf", "anyOf", "oneOf"] {
+        let schemas = match map.remove(*key) {
+            Some(Value::Array(s)) => s,
+            _ => continue,
+        };
+
+        let schemas: Vec<_> = schemas
+            .iter()
+            .filter(|s| s.get("type") != Some(&Value::from("null")))
+            .collect();
+        match schemas.as_slice() {
+            [schema] => {
+                let mut schema = convert(schema, definitions, stack);
+                if let Some(Value::String(d)) = map.get("description") {
+                    schema.description = Some(d.clone());
+                }
+
+                return schema;
+            }
+            // Enums whose (unit) variants have descriptions are compositions of enums.
+            _ if !schemas.is_empty() && schemas.iter().all(|s| s.get("enum").is_some()) => {
+                let values = schemas
+                    .iter()
+                    .filter_map(|s| s["enum"].as_array())
+                    .flatten()
+                    .cloned()
+                    .collect();
+                map.insert("type".into(), schemas[0]["type"].clone());
+                map.insert("enum".into(), Value::Array(values));
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(Value::Array(types)) = map.get("type") {
+        let mut types = types.iter().filter(|t| *t != "null");
+        match (types.next().cloned(), types.next()) {
+            (Some(t), None) => map.insert("type".into(), t),
+            _ => map.remove("type"),
+        };
+    }
+
+    if let Some(value) = map.remove("const") {
+        map.insert("enum".into(), Value::Array(vec![value]));
+    }
+
+    if matches!(map.get("format"), Some(Value::String(f)) if !FORMATS.contains(&f.as_str())) {
+        map.remove("format");
+    }
+
+    let properties = map.remove("properties");
+    let items = map.remove("items");
+    let extra_props = map.remove("additionalProperties");
+    // Examples can be of any type (instead of strings).
+    map.remove("examples");
+
+    let mut schema: DefaultSchemaRaw =
+        serde_json::from_value(Value::Object(map)).unwrap_or_default();
+    if let Some(Value::Object(properties)) = properties {
+        for (name, value) in &properties {
+            let property = convert(value, definitions, stack);
+            schema.properties.insert(name.clone(), property.into());
+        }
+    }
+
+    // Tuples (i.e., arrays of schemas) can't be described in v2.
+    if let Some(items @ Value::Object(_)) = items {
+        schema.items = Some(convert(&items, definitions, stack).into());
+    }
+
+    schema.extra_props = match extra_props {
+        Some(Value::Bool(b)) => Some(Either::Left(b)),
+        Some(value @ Value::Object(_)) => {
+            Some(Either::Right(convert(&value, definitions, stack).into()))
+        }
+        _ => None,
+    };
+
+    schema
+}
+
+/// Implements `Apiv2Schema` for the given types (which implement `schemars::JsonSchema`)
+/// through their JSON schemas (see [`schema_for`](crate::v2::json_schema::schema_for)).
+#[cfg(feature = "schemars")]
+#[macro_export]
+macro_rules! impl_apiv2_schema_for_json_schema {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::v2::schema::Apiv2Schema for $ty {
+                fn raw_schema() -> $crate::v2::models::DefaultSchemaRaw {
+                    $crate::v2::json_schema::schema_for::<$ty>()
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `Apiv2Schema` for the given types (which implement `utoipa::ToSchema`)
+/// through their `utoipa` schemas (see [`to_schema_for`](crate::v2::json_schema::to_schema_for)).
+#[cfg(feature = "utoipa")]
+#[macro_export]
+macro_rules! impl_apiv2_schema_for_to_schema {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $crate::v2::schema::Apiv2Schema for $ty {
+                fn raw_schema() -> $crate::v2::models::DefaultSchemaRaw {
+                    $crate::v2::json_schema::to_schema_for::<$ty>()
+                }
+            }
+        )+
+    };
+}
@@ -3,16 +3,30 @@
 
 #[cfg(feature = "actix4")]
 mod actix;
+pub mod builder;
+pub mod compose;
 mod extensions;
+pub mod filter;
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+pub mod json_schema;
 pub mod models;
 #[cfg(feature = "codegen")]
 mod resolver;
 pub mod schema;
+pub mod string_type;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod validate;
 
 #[cfg(feature = "actix-base")]
 pub use self::actix::{
-    AcceptedJson, CreatedJson, NoContent, OperationModifier, ResponderWrapper, ResponseWrapper,
+    add_struct_parameters, infer_media_types, Accepted, AcceptedJson, Batch, BatchItem,
+    BatchResult, Cookie, CreatedJson, EventStream, FieldsParam, FilterParam, Header, NdJson,
+    Negotiate, NoContent, OperationModifier, QueryMap, ResponderWrapper, ResponseWrapper,
+    SessionAuth, SessionAuthFuture, SessionCookie, SortParam, StatusRef,
 };
+#[cfg(all(feature = "actix-base", feature = "actix-multipart"))]
+pub use self::actix::{FilePart, MultipartForm};
 
 pub use self::{
     models::{DefaultSchema, ResolvableApi},
@@ -25,6 +39,17 @@ use self::resolver::Resolver;
 #[cfg(feature = "codegen")]
 use crate::error::ValidationError;
 
+/// Progress of resolving an API schema (reported by `ResolvableApi::resolve_with_progress`).
+#[cfg(feature = "codegen")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveProgress {
+    /// Number of paths whose operations have been resolved (out of the total).
+    Paths { done: usize, total: usize },
+    /// Number of definitions which have been resolved (out of the total, which
+    /// includes the anonymous definitions found in the operations).
+    Definitions { done: usize, total: usize },
+}
+
 #[cfg(feature = "codegen")]
 impl<S: Schema + Default> ResolvableApi<S> {
     /// Consumes this API schema, resolves the references and returns
@@ -34,13 +59,26 @@ impl<S: Schema + Default> ResolvableApi<S> {
     /// substitutes the referenced IDs with the pointer to schema objects
     /// and returns the resolved object or an error if it encountered one.
     pub fn resolve(self) -> Result<ResolvableApi<S>, ValidationError> {
+        self.resolve_with_progress(|_| ())
+    }
+
+    /// Same as `resolve`, but this also reports the progress (after resolving
+    /// each path and definition) to the given function, which is useful for
+    /// very large specs.
+    pub fn resolve_with_progress<F>(
+        self,
+        mut progress: F,
+    ) -> Result<ResolvableApi<S>, ValidationError>
+    where
+        F: FnMut(ResolveProgress),
+    {
         let mut resolver = Resolver::from((
             self.definitions,
             self.paths,
             self.parameters,
             self.responses,
         ));
-        resolver.resolve()?;
+        resolver.resolve(&mut progress)?;
         Ok(ResolvableApi {
             swagger: self.swagger,
             info: self.info,
@@ -60,6 +98,8 @@ impl<S: Schema + Default> ResolvableApi<S> {
             security: self.security,
             security_definitions: self.security_definitions,
             tags: self.tags,
+            webhooks: self.webhooks,
+            servers: self.servers,
         })
     }
 }
@@ -14,11 +14,16 @@ use regex::{Captures, Regex};
 use actix_web::http::Method;
 
 use parking_lot::RwLock;
+use serde::Serialize;
 use std::{
     borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
+    fs::File,
+    io::{self, BufWriter, Write},
+    mem,
     ops::{Deref, DerefMut},
+    path::Path,
     sync::Arc,
 };
 
@@ -178,6 +183,21 @@ pub struct Api<P, R, S> {
         skip_serializing_if = "BTreeMap::is_empty"
     )]
     pub support_crates: BTreeMap<String, String>,
+    /// Extension for webhooks (outgoing requests made by the API), which are
+    /// emitted in the `webhooks` section of OpenAPI 3.1 specs.
+    ///
+    /// The key is the name of the webhook (for example, `order.created`).
+    #[serde(
+        default = "BTreeMap::new",
+        rename = "x-webhooks",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub webhooks: BTreeMap<String, PathItem<P, R>>,
+    /// Extension for the servers of the API (as in OpenAPI v3), for APIs with
+    /// multiple base URLs or with variables in their base URLs. These take
+    /// precedence over `host` and `basePath` in the v3 spec and in codegen.
+    #[serde(default, rename = "x-servers", skip_serializing_if = "Vec::is_empty")]
+    pub servers: Vec<Server>,
     /// This field is set manually, because we don't know the format in which
     /// the spec was provided and we need to use this as the fallback encoding.
     #[serde(skip)]
@@ -208,6 +228,26 @@ impl SpecFormat {
             SpecFormat::Yaml => &*YAML_MIME,
         }
     }
+
+    /// The format for the given file path (YAML for `.yaml` and `.yml` files, JSON otherwise).
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => SpecFormat::Yaml,
+            _ => SpecFormat::Json,
+        }
+    }
+
+    /// Writes the given value (pretty-printed if it's JSON) in this format.
+    pub fn write<T: Serialize, W: Write>(self, value: &T, mut writer: W) -> io::Result<()> {
+        match self {
+            SpecFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, value)?;
+                writeln!(writer)
+            }
+            SpecFormat::Yaml => serde_yaml::to_writer(writer, value)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
+        }
+    }
 }
 
 impl<P, R, S> Api<P, R, S> {
@@ -221,6 +261,189 @@ impl<P, R, S> Api<P, R, S> {
     }
 }
 
+impl DefaultApiRaw {
+    /// Writes this spec in the given format, so that it can be committed along with
+    /// the code (say, for generating clients) or fed to the code generator.
+    pub fn write_to<W: Write>(&self, writer: W, format: SpecFormat) -> io::Result<()> {
+        format.write(self, writer)
+    }
+
+    /// Writes this spec to the given file (in YAML if it has a `.yaml` or `.yml`
+    /// extension, and JSON otherwise).
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_to(&mut file, SpecFormat::from_path(path))?;
+        file.flush()
+    }
+
+    /// Removes the definitions which aren't referred to by the operations (or the
+    /// global parameters and responses) in this spec, either directly or through
+    /// other definitions.
+    pub fn retain_reachable_definitions(&mut self) {
+        let mut refs = vec![];
+        let roots = (&self.paths, &self.parameters, &self.responses);
+        collect_definition_refs(
+            &serde_json::to_value(roots).expect("serializing spec"),
+            &mut refs,
+        );
+
+        let mut reachable = BTreeSet::new();
+        while let Some(name) = refs.pop() {
+            if let Some(schema) = self.definitions.get(&name) {
+                if reachable.insert(name) {
+                    let value = serde_json::to_value(schema).expect("serializing schema");
+                    collect_definition_refs(&value, &mut refs);
+                }
+            }
+        }
+
+        self.definitions.retain(|name, _| reachable.contains(name));
+    }
+
+    /// Retains the operations for which the given function (called with their paths,
+    /// methods and operations) returns `true`, along with the definitions and tags
+    /// used by them. Paths without operations are removed.
+    pub fn retain_operations<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str, HttpMethod, &DefaultOperationRaw) -> bool,
+    {
+        for (path, item) in self.paths.iter_mut() {
+            item.methods.retain(|method, op| f(path, *method, op));
+        }
+
+        self.paths.retain(|_, item| !item.methods.is_empty());
+        let tags = self
+            .paths
+            .values()
+            .flat_map(|item| item.methods.values())
+            .flat_map(|op| op.tags.iter())
+            .collect::<BTreeSet<_>>();
+        self.tags.retain(|tag| tags.contains(&tag.name));
+        self.retain_reachable_definitions();
+    }
+
+    /// Sets the IDs of the operations which don't have one from the paths of their
+    /// handlers (in the given map of paths, methods and handlers) using the given strategy.
+    /// Handlers serving several operations get the (lowercase) methods of those operations
+    /// appended to their IDs (like `get_pet_get`).
+    pub fn generate_operation_ids(
+        &mut self,
+        strategy: OperationIdStrategy,
+        handlers: &BTreeMap<String, BTreeMap<HttpMethod, &str>>,
+    ) {
+        let handler = |path: &str, method: &HttpMethod| {
+            handlers
+                .get(path)
+                .and_then(|methods| methods.get(method))
+                .copied()
+        };
+
+        let mut counts = BTreeMap::new();
+        for (path, item) in &self.paths {
+            for (method, op) in &item.methods {
+                if let (None, Some(h)) = (&op.operation_id, handler(path, method)) {
+                    *counts.entry(h).or_insert(0) += 1;
+                }
+            }
+        }
+
+        for (path, item) in &mut self.paths {
+            for (method, op) in &mut item.methods {
+                let h = match (&op.operation_id, handler(path, method)) {
+                    (None, Some(h)) => h,
+                    _ => continue,
+                };
+
+                let id = strategy.operation_id(h);
+                op.operation_id = Some(if counts[h] > 1 {
+                    format!("{}_{}", id, method.to_string().to_lowercase())
+                } else {
+                    id
+                });
+            }
+        }
+    }
+
+    /// Returns the operation IDs used by more than one operation, along with the paths
+    /// and methods of those operations.
+    pub fn duplicate_operation_ids(&self) -> BTreeMap<String, Vec<(String, HttpMethod)>> {
+        let mut ids = BTreeMap::<_, Vec<_>>::new();
+        for (path, item) in &self.paths {
+            for (method, op) in &item.methods {
+                if let Some(id) = &op.operation_id {
+                    ids.entry(id.clone())
+                        .or_default()
+                        .push((path.clone(), *method));
+                }
+            }
+        }
+
+        ids.retain(|_, ops| ops.len() > 1);
+        ids
+    }
+
+    /// Removes the given (templated) path prefix (like `/t/{tenant_id}`) from the paths
+    /// starting with it, along with the path parameters in the prefix, so that those
+    /// operations are documented relative to the prefix (say, for publishing the API
+    /// of a single tenant). Other paths are left alone.
+    pub fn strip_path_prefix(&mut self, prefix: &str) {
+        let prefix = prefix.trim_end_matches('/');
+        let mut names = vec![];
+        Self::path_parameters_map(prefix, |p| {
+            names.push(p.to_owned());
+            ":".into()
+        });
+
+        let is_prefix_param = |p: &Either<Reference, DefaultParameterRaw>| match p {
+            Either::Right(p) => p.in_ == ParameterIn::Path && names.contains(&p.name),
+            Either::Left(_) => false,
+        };
+
+        for (path, mut item) in mem::take(&mut self.paths) {
+            let path = match path.strip_prefix(prefix) {
+                Some("") => "/".to_owned(),
+                Some(p) if p.starts_with('/') => p.to_owned(),
+                _ => {
+                    self.paths.insert(path, item);
+                    continue;
+                }
+            };
+
+            item.parameters.retain(|p| !is_prefix_param(p));
+            for op in item.methods.values_mut() {
+                op.parameters.retain(|p| !is_prefix_param(p));
+            }
+
+            let existing = self.paths.entry(path).or_insert_with(Default::default);
+            existing.methods.extend(item.methods);
+            existing.parameters.extend(item.parameters);
+        }
+    }
+}
+
+/// Collects the names of the definitions referred to (through `$ref`) in the value.
+fn collect_definition_refs(value: &serde_json::Value, refs: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    serde_json::Value::String(r) if key == "$ref" => {
+                        if let Some(name) = r.strip_prefix("#/definitions/") {
+                            refs.push(name.into());
+                        }
+                    }
+                    _ => collect_definition_refs(value, refs),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            values.iter().for_each(|v| collect_definition_refs(v, refs))
+        }
+        _ => (),
+    }
+}
+
 use crate as paperclip; // hack for proc macro
 
 /// Default schema if your schema doesn't have any custom fields.
@@ -230,6 +453,15 @@ use crate as paperclip; // hack for proc macro
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DefaultSchema;
 
+impl DefaultSchemaRaw {
+    /// Returns the SHA-256 hash (hex-encoded) of the canonicalized (JSON) schema,
+    /// which only changes when the schema itself changes (see `paperclip_core::integrity`).
+    pub fn fingerprint(&self) -> String {
+        let value = serde_json::to_value(self).expect("serializing schema");
+        crate::integrity::fingerprint(&value)
+    }
+}
+
 /// Info object.
 ///
 /// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#infoObject
@@ -294,7 +526,7 @@ pub struct SecurityScheme {
 
 impl SecurityScheme {
     /// Adds or updates this definition to the map of security definitions.
-    pub fn update_definitions(mut self, name: &str, map: &mut BTreeMap<String, SecurityScheme>) {
+    pub fn update_definitions(self, name: &str, map: &mut BTreeMap<String, SecurityScheme>) {
         if let Some(existing) = map.get_mut(name) {
             existing.name = existing.name.take().or(self.name);
             if !self.type_.is_empty() {
@@ -304,8 +536,18 @@ impl SecurityScheme {
             existing.flow = existing.flow.take().or(self.flow);
             existing.auth_url = existing.auth_url.take().or(self.auth_url);
             existing.token_url = existing.token_url.take().or(self.token_url);
-            existing.scopes.append(&mut self.scopes);
-            existing.description = existing.description.take().or(self.description);
+            for (scope, description) in self.scopes {
+                let existing = existing.scopes.entry(scope.clone()).or_default();
+                // Scopes without descriptions default to their names.
+                if existing.is_empty() || (*existing == scope && !description.is_empty()) {
+                    *existing = description;
+                }
+            }
+            existing.description = existing
+                .description
+                .take()
+                .filter(|d| !d.is_empty())
+                .or(self.description);
             return;
         }
 
@@ -336,6 +578,61 @@ pub struct Tag {
     pub external_docs: Option<ExternalDocs>,
 }
 
+impl Tag {
+    /// Appends the given tags to the list, whilst filling the missing properties
+    /// of the tags already in the list (with the same names).
+    pub fn append_list(tags: Vec<Tag>, list: &mut Vec<Tag>) {
+        for tag in tags {
+            match list.iter_mut().find(|t| t.name == tag.name) {
+                Some(existing) => {
+                    existing.description = existing.description.take().or(tag.description);
+                    existing.external_docs = existing.external_docs.take().or(tag.external_docs);
+                }
+                None => list.push(tag),
+            }
+        }
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(name: &str) -> Self {
+        name.to_owned().into()
+    }
+}
+
+impl From<String> for Tag {
+    fn from(name: String) -> Self {
+        Tag {
+            name,
+            ..Default::default()
+        }
+    }
+}
+
+/// Server object (from OpenAPI v3).
+///
+/// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.3.md#server-object
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Server {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, ServerVariable>,
+}
+
+/// Server variable object (from OpenAPI v3), for substituting `{name}` in server URLs.
+///
+/// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.3.md#server-variable-object
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ServerVariable {
+    #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub enum_: Vec<String>,
+    pub default: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
 /// External Documentation object.
 ///
 /// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#external-documentation-object
@@ -346,6 +643,16 @@ pub struct ExternalDocs {
     pub url: String,
 }
 
+impl ExternalDocs {
+    /// Creates the documentation object for the given URL (with an optional description).
+    pub fn new(url: &str, description: Option<&str>) -> Self {
+        ExternalDocs {
+            description: description.map(Into::into),
+            url: url.into(),
+        }
+    }
+}
+
 /// Path item that can be traversed and resolved for codegen.
 pub type ResolvablePathItem<S> = PathItem<ResolvableParameter<S>, ResolvableResponse<S>>;
 
@@ -468,6 +775,44 @@ pub struct Parameter<S> {
     pub enum_: Vec<serde_json::Value>,
 }
 
+/// Cookie parameter of an operation (in its `x-cookies` extension), since v2 doesn't
+/// support `cookie` parameters. These become `cookie` parameters in v3 specs.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CookieParameter {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<DataType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<DataTypeFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    pub enum_: Vec<serde_json::Value>,
+}
+
+impl CookieParameter {
+    /// Returns this cookie as a (header) parameter, for converting its values and schema.
+    #[cfg(feature = "actix-base")]
+    pub(crate) fn to_parameter(&self) -> DefaultParameterRaw {
+        Parameter {
+            name: self.name.clone(),
+            in_: ParameterIn::Header,
+            description: self.description.clone(),
+            required: self.required,
+            data_type: self.data_type,
+            format: self.format.clone(),
+            pattern: self.pattern.clone(),
+            enum_: self.enum_.clone(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Items object.
 ///
 /// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#itemsObject
@@ -536,10 +881,12 @@ where
             }
         }
 
-        // Non-body parameters must be primitives or an array - they can't have objects.
+        // Non-body parameters must be primitives or an array - they can't have objects
+        // (except for query parameters, which can be serialized as `deepObject`).
         let mut is_invalid = false;
         match self.data_type {
             Some(dt) if dt.is_primitive() => (),
+            Some(DataType::Object) if self.in_ == ParameterIn::Query => (),
             Some(DataType::Array) => {
                 let mut inner = self.items.as_ref();
                 loop {
@@ -609,6 +956,39 @@ pub enum CollectionFormat {
     Multi,
 }
 
+/// Strategy for generating the IDs of operations from the paths of their handlers
+/// (like `my_crate::pets::get_pet`).
+#[derive(Clone, Copy, Debug)]
+pub enum OperationIdStrategy {
+    /// Name of the handler (like `get_pet`).
+    FnName,
+    /// Module path of the handler (without the crate name) along with its name,
+    /// joined with underscores (like `pets_get_pet`).
+    ModulePath,
+    /// The given function, which is called with the path of the handler.
+    Custom(fn(&str) -> String),
+}
+
+impl OperationIdStrategy {
+    /// Returns the operation ID for the handler with the given path.
+    pub fn operation_id(self, handler: &str) -> String {
+        match self {
+            OperationIdStrategy::FnName => handler.rsplit("::").next().unwrap_or(handler).into(),
+            OperationIdStrategy::ModulePath => {
+                let mut segments = handler.split("::");
+                let krate = segments.next();
+                let segments = segments.collect::<Vec<_>>();
+                if segments.is_empty() {
+                    krate.unwrap_or(handler).into()
+                } else {
+                    segments.join("_")
+                }
+            }
+            OperationIdStrategy::Custom(f) => f(handler),
+        }
+    }
+}
+
 /// Operation that can be traversed and resolved for codegen.
 pub type ResolvableOperation<S> = Operation<ResolvableParameter<S>, ResolvableResponse<S>>;
 
@@ -646,33 +1026,232 @@ pub struct Operation<P, R> {
     pub deprecated: bool,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_docs: Option<ExternalDocs>,
+    /// Extension for indicating that this operation accepts arbitrary (string)
+    /// query parameters in addition to the documented ones.
+    #[serde(default, rename = "x-dynamic-query", skip_serializing_if = "is_false")]
+    pub dynamic_query: bool,
+    /// Extension for the cookies read by this operation.
+    #[serde(default, rename = "x-cookies", skip_serializing_if = "Vec::is_empty")]
+    pub cookies: Vec<CookieParameter>,
+    /// Extension for the roles (any of which is) required for calling this operation.
+    #[serde(
+        default,
+        rename = "x-required-roles",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub required_roles: Vec<String>,
+    /// Other vendor extensions (`x-*` fields) of this operation.
+    #[serde(flatten, deserialize_with = "deserialize_extensions")]
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 impl<S> Operation<Parameter<S>, Response<S>> {
+    /// Documents the `Cache-Control` and `ETag` headers in the successful responses
+    /// of this operation, based on its `x-cache` extension (if any).
+    pub fn add_cache_headers(&mut self) {
+        let cache = match self.extensions.get("x-cache") {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut headers = vec![];
+        if let Some(directives) = cache.get("cacheControl").and_then(|v| v.as_str()) {
+            headers.push((
+                "Cache-Control",
+                format!("Caching directives (`{}`).", directives),
+            ));
+        }
+
+        if cache.get("etag").and_then(|v| v.as_bool()) == Some(true) {
+            headers.push(("ETag", "Identifier for the version of the resource.".into()));
+        }
+
+        for (name, description) in headers {
+            let header = Header {
+                description: Some(description),
+                data_type: Some(DataType::String),
+                ..Default::default()
+            };
+            self.add_response_header(None, name, header);
+        }
+    }
+
+    /// Adds the given example of the body to the successful responses (with schemas)
+    /// of this operation, for the media types it produces (or JSON, if it doesn't
+    /// specify them). Examples which have already been added are left alone.
+    pub fn add_response_example(&mut self, example: serde_json::Value) {
+        let media_types = match &self.produces {
+            Some(p) => p.iter().map(|m| m.0.to_string()).collect(),
+            None => vec![SpecFormat::Json.mime().0.to_string()],
+        };
+
+        let responses = self
+            .responses
+            .iter_mut()
+            .filter(|(code, _)| code.starts_with('2'))
+            .filter_map(|(_, r)| r.right_mut())
+            .filter(|r| r.schema.is_some());
+        for response in responses {
+            for media in &media_types {
+                response
+                    .examples
+                    .entry(media.clone())
+                    .or_insert_with(|| example.clone());
+            }
+        }
+    }
+
+    /// Documents the given header in the response with the given status code, or in
+    /// all the successful responses of this operation (if there's no status code).
+    /// Headers which have already been documented in a response are left alone.
+    pub fn add_response_header(&mut self, status: Option<&str>, name: &str, header: Header) {
+        let responses = self
+            .responses
+            .iter_mut()
+            .filter(|(code, _)| match status {
+                Some(s) => *code == s,
+                None => code.starts_with('2'),
+            })
+            .filter_map(|(_, r)| r.right_mut());
+        for response in responses {
+            response
+                .headers
+                .entry(name.into())
+                .or_insert_with(|| header.clone());
+        }
+    }
+
     /// Overwrites the names of parameters in this operation using the
     /// given path template.
+    ///
+    /// Path parameters which already have a name from the template (for example,
+    /// those from the fields of a struct) keep their name (and hence their description),
+    /// and they're sorted in the order of the template.
     pub fn set_parameter_names_from_path_template(&mut self, path: &str) {
-        let mut names = vec![];
+        let mut template = vec![];
         Api::<(), (), ()>::path_parameters_map(path, |p| {
-            names.push(p.to_owned());
+            template.push(p.to_owned());
             ":".into()
         });
 
-        for p in self
+        let (known, unknown): (Vec<_>, Vec<_>) = self
             .parameters
             .iter_mut()
             .filter(|p| p.in_ == ParameterIn::Path)
-            .rev()
-        {
+            .partition(|p| template.contains(&p.name));
+        let mut names = template.clone();
+        names.retain(|n| !known.iter().any(|p| p.name == *n));
+
+        for p in unknown.into_iter().rev() {
             if let Some(n) = names.pop() {
                 p.name = n;
             } else {
                 break;
             }
         }
+
+        let position =
+            |p: &Either<Reference, Parameter<S>>| template.iter().position(|n| *n == p.name);
+        let is_path = |p: &Either<Reference, Parameter<S>>| p.in_ == ParameterIn::Path;
+        if !self
+            .parameters
+            .iter()
+            .filter(|p| is_path(p))
+            .all(|p| position(p).is_some())
+        {
+            return;
+        }
+
+        // Other parameters stay where they are.
+        let slots: Vec<_> = self.parameters.iter().map(is_path).collect();
+        let (mut path_params, others): (Vec<_>, Vec<_>) =
+            self.parameters.drain(..).partition(is_path);
+        path_params.sort_by_key(position);
+
+        let (mut path_params, mut others) = (path_params.into_iter(), others.into_iter());
+        self.parameters = slots
+            .into_iter()
+            .filter_map(|p| if p { path_params.next() } else { others.next() })
+            .collect();
     }
 }
 
+impl DefaultOperationRaw {
+    /// Documents the (raw) body of the requests to this operation in the given media type
+    /// (like `text/csv`), for handlers reading the body on their own (say, through
+    /// `web::Bytes` or `web::Payload`). The body replaces the documented one (if any), and
+    /// the media type is added to the ones consumed by this operation.
+    ///
+    /// **NOTE:** This panics if the media type is invalid.
+    pub fn set_request_body(&mut self, media_type: &str, description: Option<&str>) {
+        self.parameters
+            .retain(|p| !matches!(p, Either::Right(p) if p.in_ == ParameterIn::Body));
+        self.parameters.push(Either::Right(Parameter {
+            description: description.map(String::from),
+            in_: ParameterIn::Body,
+            name: "body".into(),
+            required: true,
+            schema: Some(raw_body_schema(media_type, false)),
+            ..Default::default()
+        }));
+        add_media_type(&mut self.consumes, media_type);
+    }
+
+    /// Documents the (raw) body of the response with the given status code in the given
+    /// media type (like `application/pdf`), for handlers building the responses on their own
+    /// (say, through `HttpResponse`). The response is added if it's not documented, and the
+    /// media type is added to the ones produced by this operation.
+    ///
+    /// **NOTE:** This panics if the media type is invalid.
+    pub fn set_response_body(&mut self, status: &str, media_type: &str, description: &str) {
+        let response = self
+            .responses
+            .entry(status.into())
+            .or_insert_with(|| Either::Right(Default::default()));
+        if let Either::Right(response) = response {
+            response.description = Some(description.into());
+            response.schema = Some(raw_body_schema(media_type, true));
+        }
+
+        add_media_type(&mut self.produces, media_type);
+    }
+}
+
+/// Returns the schema of raw bodies in the given media type, which are strings for text
+/// (and JSON or XML) and binary data otherwise (files in responses).
+fn raw_body_schema(media_type: &str, response: bool) -> DefaultSchemaRaw {
+    let mime = media_type
+        .parse::<mime::Mime>()
+        .expect("invalid media type");
+    let subtype = mime.suffix().unwrap_or_else(|| mime.subtype());
+    let is_text = mime.type_() == mime::TEXT || subtype == mime::JSON || subtype == mime::XML;
+    match (is_text, response) {
+        (true, _) => DefaultSchemaRaw {
+            data_type: Some(DataType::String),
+            ..Default::default()
+        },
+        (false, true) => DefaultSchemaRaw {
+            data_type: Some(DataType::File),
+            ..Default::default()
+        },
+        (false, false) => DefaultSchemaRaw {
+            data_type: Some(DataType::String),
+            format: Some(DataTypeFormat::Binary),
+            ..Default::default()
+        },
+    }
+}
+
+/// Adds the given media type to the (consumed or produced) media types.
+fn add_media_type(media_types: &mut Option<BTreeSet<MediaRange>>, media_type: &str) {
+    let media_type = MediaRange(media_type.parse().expect("invalid media type"));
+    media_types
+        .get_or_insert_with(Default::default)
+        .insert(media_type);
+}
+
 /// Reference object.
 ///
 /// https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#referenceObject
@@ -709,6 +1288,24 @@ pub struct Response<S> {
     pub schema: Option<S>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub headers: BTreeMap<String, Header>,
+    /// Examples of the body (by their media types).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub examples: BTreeMap<String, serde_json::Value>,
+    /// Schemas for media types (in `produces`) whose body differs from `schema`.
+    #[serde(
+        default = "BTreeMap::new",
+        rename = "x-content-schemas",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub content_schemas: BTreeMap<String, S>,
+    /// Extension for the ID of the operation for polling the status of a long-running
+    /// operation (in `202 Accepted` responses).
+    #[serde(
+        default,
+        rename = "x-status-operation",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub status_operation: Option<String>,
 }
 
 /// Header object.
@@ -965,3 +1562,17 @@ impl Default for ParameterIn {
 fn is_false(val: &bool) -> bool {
     !*val
 }
+
+/// Keeps only the vendor extensions among the unknown fields of an object.
+#[doc(hidden)]
+pub fn deserialize_extensions<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut map: BTreeMap<String, serde_json::Value> =
+        serde::Deserialize::deserialize(deserializer)?;
+    map.retain(|k, _| k.starts_with("x-"));
+    Ok(map)
+}
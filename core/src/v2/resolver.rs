@@ -3,7 +3,7 @@ use super::{
         Either, HttpMethod, Reference, Resolvable, ResolvableParameter, ResolvablePathItem,
         ResolvableResponse,
     },
-    Schema,
+    ResolveProgress, Schema,
 };
 use crate::error::ValidationError;
 use heck::CamelCase;
@@ -68,16 +68,21 @@ impl<S> Resolver<S>
 where
     S: Schema + Default,
 {
-    /// Visit definitions and resolve them!
-    pub fn resolve(&mut self) -> Result<(), ValidationError> {
+    /// Visit definitions and resolve them (reporting the progress along the way)!
+    pub fn resolve(
+        &mut self,
+        progress: &mut dyn FnMut(ResolveProgress),
+    ) -> Result<(), ValidationError> {
         // Resolve path operations first. We may encounter anonymous
         // definitions along the way, which we'll insert into `self.defs`
         // and we'll have to resolve them anyway.
         let mut paths = mem::replace(&mut self.paths, BTreeMap::new());
-        paths.iter_mut().try_for_each(|(path, map)| {
+        let total = paths.len();
+        for (i, (path, map)) in paths.iter_mut().enumerate() {
             log::trace!("Checking path: {}", path);
-            self.resolve_operations(path, map)
-        })?;
+            self.resolve_operations(path, map)?;
+            progress(ResolveProgress::Paths { done: i + 1, total });
+        }
         self.paths = paths;
 
         // Set the names of all schemas.
@@ -85,9 +90,11 @@ where
             schema.write().set_name(name);
         }
 
-        for (name, schema) in &self.defs {
+        let total = self.defs.len();
+        for (i, (name, schema)) in self.defs.iter().enumerate() {
             log::trace!("Entering: {}", name);
             self.resolve_definitions_no_root_ref(schema)?;
+            progress(ResolveProgress::Definitions { done: i + 1, total });
 
             for def in self.cyclic_defs.borrow_mut().drain(..) {
                 log::debug!(
@@ -197,6 +204,9 @@ where
                     path,
                     "Response",
                 )?;
+                for schema in response.content_schemas.values_mut() {
+                    self.resolve_definitions(schema)?;
+                }
             }
         }
 
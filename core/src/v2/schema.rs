@@ -1,11 +1,20 @@
 //! Traits used for code and spec generation.
 
 use super::models::{
-    DataType, DataTypeFormat, DefaultOperationRaw, DefaultSchemaRaw, Either, Resolvable,
-    SecurityScheme,
+    DataType, DataTypeFormat, DefaultOperationRaw, DefaultSchemaRaw, Either, ParameterIn,
+    Resolvable, SecurityScheme,
 };
 
-use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "v3")]
+use crate::v3::{
+    native::{self, SchemaMap},
+    openapiv3::{ReferenceOr, Schema as V3Schema},
+};
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+};
 
 /// Interface for the [`Schema`](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/2.0.md#schemaObject) object.
 ///
@@ -194,6 +203,8 @@ impl_type_simple!(
 );
 #[cfg(feature = "actix-session")]
 impl_type_simple!(actix_session::Session);
+#[cfg(feature = "actix-identity")]
+impl_type_simple!(actix_identity::Identity);
 #[cfg(feature = "actix-files")]
 impl_type_simple!(
     actix_files::NamedFile,
@@ -223,6 +234,15 @@ impl_type_simple!(url::Url, DataType::String, DataTypeFormat::Url);
 #[cfg(feature = "uuid")]
 impl_type_simple!(uuid::Uuid, DataType::String, DataTypeFormat::Uuid);
 
+// Well-known types of protobuf are (de)serialized with their canonical JSON mappings
+// when protobuf models are exposed through JSON transcoding.
+#[cfg(feature = "prost-types")]
+impl_type_simple!(
+    prost_types::Timestamp,
+    DataType::String,
+    DataTypeFormat::DateTime
+);
+
 #[cfg(feature = "chrono")]
 impl<T: chrono::offset::TimeZone> TypedData for chrono::DateTime<T> {
     fn data_type() -> DataType {
@@ -282,10 +302,8 @@ pub trait Apiv2Schema {
     /// so it won't affect the incoming requests at all.
     fn schema_with_ref() -> DefaultSchemaRaw {
         let mut def = Self::raw_schema();
-        if let Some(n) = Self::NAME {
-            def.reference = Some(String::from("#/definitions/") + n);
-        } else if let Some(n) = def.name.as_ref() {
-            def.reference = Some(String::from("#/definitions/") + n);
+        if let Some(n) = Self::NAME.or(def.name.as_deref()) {
+            def.reference = Some(definition_reference(n));
         }
         if !Self::DESCRIPTION.is_empty() {
             def.description = Some(Self::DESCRIPTION.to_owned());
@@ -298,6 +316,63 @@ pub trait Apiv2Schema {
     fn security_scheme() -> Option<SecurityScheme> {
         None
     }
+
+    /// Returns the locations of the properties which should be documented as
+    /// parameters in some other location (through `#[openapi(in = "...")]` on
+    /// the fields of derived structs).
+    fn parameter_locations() -> BTreeMap<String, ParameterIn> {
+        Default::default()
+    }
+
+    /// Returns the OpenAPI v3 schema for this object.
+    ///
+    /// By default, this is converted from the v2 schema (with the definitions it
+    /// refers to inlined), but implementors can emit constructs which don't exist
+    /// in v2 (like `nullable` and `oneOf`).
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        let mut schema = Self::raw_schema();
+        schema.remove_refs();
+        schema.into()
+    }
+
+    /// Returns a reference to the v3 schema of this object (if it's named),
+    /// or the schema itself.
+    #[cfg(feature = "v3")]
+    fn schema_with_ref_v3() -> ReferenceOr<V3Schema> {
+        match schema_name::<Self>() {
+            Some(n) => native::reference(&n),
+            None => Self::raw_schema_v3(),
+        }
+    }
+
+    /// Adds the (named) v3 schemas used by this object to the given map.
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        if let Some(n) = schema_name::<Self>() {
+            if !map.contains_key(&n) {
+                map.insert(n, Self::raw_schema_v3());
+            }
+        }
+    }
+}
+
+/// Returns the (local) reference to the definition with the given name, which is
+/// allocated once with the exact capacity (references are repeated throughout specs).
+pub fn definition_reference(name: &str) -> Cow<'static, str> {
+    const PREFIX: &str = "#/definitions/";
+    let mut reference = String::with_capacity(PREFIX.len() + name.len());
+    reference.push_str(PREFIX);
+    reference.push_str(name);
+    Cow::Owned(reference)
+}
+
+/// Returns the name to which the definition of the given schema is mapped (if any).
+#[cfg(feature = "v3")]
+fn schema_name<T: Apiv2Schema + ?Sized>() -> Option<String> {
+    T::NAME
+        .map(String::from)
+        .or_else(|| T::raw_schema().name.map(String::from))
 }
 
 impl Apiv2Schema for () {}
@@ -314,6 +389,54 @@ impl<T: TypedData> Apiv2Schema for T {
     }
 }
 
+/// Durations are strings with the seconds (and up to 9 fractional digits) suffixed
+/// with `s`, like `"1.5s"`.
+#[cfg(feature = "prost-types")]
+impl Apiv2Schema for prost_types::Duration {
+    fn raw_schema() -> DefaultSchemaRaw {
+        DefaultSchemaRaw {
+            data_type: Some(DataType::String),
+            pattern: Some(r"^-?\d+(\.\d{1,9})?s$".into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Structs are arbitrary JSON objects.
+#[cfg(feature = "prost-types")]
+impl Apiv2Schema for prost_types::Struct {
+    fn raw_schema() -> DefaultSchemaRaw {
+        DefaultSchemaRaw {
+            data_type: Some(DataType::Object),
+            extra_props: Some(Either::Left(true)),
+            ..Default::default()
+        }
+    }
+}
+
+/// `Any` messages are JSON objects with the URL of their type (in `@type`), along
+/// with the fields of the message (or its JSON mapping in `value`).
+#[cfg(feature = "prost-types")]
+impl Apiv2Schema for prost_types::Any {
+    fn raw_schema() -> DefaultSchemaRaw {
+        let mut schema = DefaultSchemaRaw {
+            data_type: Some(DataType::Object),
+            extra_props: Some(Either::Left(true)),
+            ..Default::default()
+        };
+        schema.properties.insert(
+            "@type".into(),
+            DefaultSchemaRaw {
+                data_type: Some(DataType::String),
+                ..Default::default()
+            }
+            .into(),
+        );
+        schema.required.insert("@type".into());
+        schema
+    }
+}
+
 #[cfg(feature = "nightly")]
 impl<T> Apiv2Schema for Option<T> {
     default const NAME: Option<&'static str> = None;
@@ -326,6 +449,23 @@ impl<T> Apiv2Schema for Option<T> {
     default fn security_scheme() -> Option<SecurityScheme> {
         None
     }
+
+    default fn parameter_locations() -> BTreeMap<String, ParameterIn> {
+        Default::default()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        Self::raw_schema().into()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn schema_with_ref_v3() -> ReferenceOr<V3Schema> {
+        Self::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn add_schemas_v3(_map: &mut SchemaMap) {}
 }
 
 impl<T: Apiv2Schema> Apiv2Schema for Option<T> {
@@ -339,6 +479,25 @@ impl<T: Apiv2Schema> Apiv2Schema for Option<T> {
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
+
+    fn parameter_locations() -> BTreeMap<String, ParameterIn> {
+        T::parameter_locations()
+    }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        native::nullable(T::raw_schema_v3())
+    }
+
+    #[cfg(feature = "v3")]
+    fn schema_with_ref_v3() -> ReferenceOr<V3Schema> {
+        native::nullable(T::schema_with_ref_v3())
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -352,6 +511,14 @@ impl<T, E> Apiv2Schema for Result<T, E> {
     default fn security_scheme() -> Option<SecurityScheme> {
         Default::default()
     }
+
+    #[cfg(feature = "v3")]
+    default fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        Self::raw_schema().into()
+    }
+
+    #[cfg(feature = "v3")]
+    default fn add_schemas_v3(_map: &mut SchemaMap) {}
 }
 
 impl<T: Apiv2Schema, E> Apiv2Schema for Result<T, E> {
@@ -364,6 +531,16 @@ impl<T: Apiv2Schema, E> Apiv2Schema for Result<T, E> {
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 impl<T: Apiv2Schema + Clone> Apiv2Schema for std::borrow::Cow<'_, T> {
@@ -376,12 +553,32 @@ impl<T: Apiv2Schema + Clone> Apiv2Schema for std::borrow::Cow<'_, T> {
     fn security_scheme() -> Option<SecurityScheme> {
         T::security_scheme()
     }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        T::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 impl<'a, T: Apiv2Schema> Apiv2Schema for &'a [T] {
     fn raw_schema() -> DefaultSchemaRaw {
         Vec::<T>::raw_schema()
     }
+
+    #[cfg(feature = "v3")]
+    fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+        Vec::<T>::raw_schema_v3()
+    }
+
+    #[cfg(feature = "v3")]
+    fn add_schemas_v3(map: &mut SchemaMap) {
+        T::add_schemas_v3(map);
+    }
 }
 
 macro_rules! impl_schema_array {
@@ -394,6 +591,16 @@ macro_rules! impl_schema_array {
                     ..Default::default()
                 }
             }
+
+            #[cfg(feature = "v3")]
+            fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+                native::array(T::schema_with_ref_v3())
+            }
+
+            #[cfg(feature = "v3")]
+            fn add_schemas_v3(map: &mut SchemaMap) {
+                T::add_schemas_v3(map);
+            }
         }
     };
 }
@@ -408,6 +615,16 @@ macro_rules! impl_schema_map {
                     ..Default::default()
                 }
             }
+
+            #[cfg(feature = "v3")]
+            fn raw_schema_v3() -> ReferenceOr<V3Schema> {
+                native::map(V::schema_with_ref_v3())
+            }
+
+            #[cfg(feature = "v3")]
+            fn add_schemas_v3(map: &mut SchemaMap) {
+                V::add_schemas_v3(map);
+            }
         }
     };
 }
@@ -473,6 +690,33 @@ pub trait Apiv2Operation {
 
     /// Returns the definitions used by this operation.
     fn definitions() -> BTreeMap<String, DefaultSchemaRaw>;
+
+    /// Returns the (OpenAPI v3) schemas used by this operation. By default,
+    /// these are converted from the definitions.
+    #[cfg(feature = "v3")]
+    fn schemas_v3() -> SchemaMap {
+        Self::definitions()
+            .into_iter()
+            .map(|(name, schema)| (name, schema.into()))
+            .collect()
+    }
+
+    /// Returns whether this operation (and its definitions) should show up in the spec.
+    fn is_visible() -> bool {
+        true
+    }
+
+    /// Returns the maximum size (in bytes) of request bodies accepted by this
+    /// operation, if it's limited.
+    fn max_body_size() -> Option<usize> {
+        None
+    }
+
+    /// Returns the path of the handler for this operation (like `my_crate::pets::get_pet`),
+    /// which is used for generating its ID.
+    fn handler() -> Option<&'static str> {
+        None
+    }
 }
 
 /// Represents a OpenAPI v2 error convertible. This is auto-implemented by
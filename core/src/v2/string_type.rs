@@ -0,0 +1,146 @@
+//! Newtypes for strings matching a pattern (like IDs with prefixes).
+//!
+//! [`api_string_type!`](crate::api_string_type) generates the newtype along with its
+//! serde impls (values are validated against the pattern when they're deserialized)
+//! and its `Apiv2Schema` (a string with the pattern, and the format if it's given).
+//!
+//! ```ignore
+//! paperclip::v2::api_string_type!(
+//!     /// ID of an order.
+//!     pub OrderId, pattern = "^ord_[a-z0-9]{12}$"
+//! );
+//!
+//! paperclip::v2::api_string_type!(pub ApiKey, pattern = "^[A-Za-z0-9]{32}$", format = Password);
+//!
+//! let id: OrderId = "ord_0123456789ab".parse()?;
+//! assert_eq!(id.as_str(), "ord_0123456789ab");
+//! assert!(OrderId::new("order-1").is_err());
+//! ```
+
+use thiserror::Error;
+
+#[doc(hidden)]
+pub use once_cell::sync::Lazy;
+#[doc(hidden)]
+pub use regex::Regex;
+#[doc(hidden)]
+pub use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Error for values which don't match the pattern of their string type.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("{:?} doesn't match the pattern {:?}", value, pattern)]
+pub struct PatternMismatch {
+    /// The value.
+    pub value: String,
+    /// Pattern of the string type.
+    pub pattern: &'static str,
+}
+
+/// Generates a newtype (with the given name and visibility) for strings matching the
+/// given pattern, along with its serde impls and `Apiv2Schema`. Values are validated
+/// (through `new`, `FromStr` or deserialization) against the pattern. The format of the
+/// schema (a variant of `DataTypeFormat`) can be given through `format = ...`.
+#[macro_export]
+macro_rules! api_string_type {
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident, pattern = $pattern:literal $(, format = $format:ident)? $(,)?
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name(String);
+
+        impl $name {
+            /// Pattern of the values.
+            pub const PATTERN: &'static str = $pattern;
+
+            /// Creates a new value, failing if it doesn't match the pattern.
+            pub fn new<S>(value: S) -> Result<Self, $crate::v2::string_type::PatternMismatch>
+            where
+                S: Into<String>,
+            {
+                static REGEX: $crate::v2::string_type::Lazy<$crate::v2::string_type::Regex> =
+                    $crate::v2::string_type::Lazy::new(|| {
+                        $crate::v2::string_type::Regex::new($pattern).expect("invalid pattern")
+                    });
+
+                let value = value.into();
+                if REGEX.is_match(&value) {
+                    Ok($name(value))
+                } else {
+                    Err($crate::v2::string_type::PatternMismatch {
+                        value,
+                        pattern: $pattern,
+                    })
+                }
+            }
+
+            /// Returns the value as a string slice.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Returns the underlying string.
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::v2::string_type::PatternMismatch;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::new(s)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                value.0
+            }
+        }
+
+        impl $crate::v2::string_type::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: $crate::v2::string_type::Serializer,
+            {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> $crate::v2::string_type::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: $crate::v2::string_type::Deserializer<'de>,
+            {
+                let value =
+                    <String as $crate::v2::string_type::Deserialize>::deserialize(deserializer)?;
+                $name::new(value).map_err(<D::Error as $crate::v2::string_type::DeError>::custom)
+            }
+        }
+
+        impl $crate::v2::schema::Apiv2Schema for $name {
+            fn raw_schema() -> $crate::v2::models::DefaultSchemaRaw {
+                $crate::v2::models::DefaultSchemaRaw {
+                    data_type: Some($crate::v2::models::DataType::String),
+                    $(format: Some($crate::v2::models::DataTypeFormat::$format),)?
+                    pattern: Some($pattern.into()),
+                    ..Default::default()
+                }
+            }
+        }
+    };
+}
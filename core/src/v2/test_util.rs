@@ -0,0 +1,307 @@
+//! [proptest](https://docs.rs/proptest) strategies for generating random (valid) specs.
+//!
+//! These are useful for testing code which consumes or transforms specs (for example,
+//! the v2 to v3 conversion) against a wide range of documents.
+//!
+//! ```rust
+//! use paperclip_core::v2::test_util;
+//! use proptest::test_runner::TestRunner;
+//!
+//! TestRunner::default()
+//!     .run(&test_util::api(), |api| {
+//!         assert!(api.paths.keys().all(|p| p.starts_with('/')));
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+//!
+//! All references in the generated specs point to existing definitions, path
+//! parameters match the path templates and parameter names are unique for
+//! each location.
+
+use super::models::{
+    CollectionFormat, DataType, DataTypeFormat, DefaultApiRaw, DefaultOperationRaw,
+    DefaultParameterRaw, DefaultPathItemRaw, DefaultResponseRaw, DefaultSchemaRaw, Either,
+    HttpMethod, Info, Items, ParameterIn,
+};
+use super::schema::definition_reference;
+use proptest::{collection, option, prelude::*, sample, strategy::Union};
+
+use std::collections::BTreeMap;
+
+/// Names for definitions, properties, parameters and tags.
+pub fn name() -> impl Strategy<Value = String> {
+    "[a-z][a-zA-Z0-9]{0,7}"
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z ]{0,15}"
+}
+
+/// Primitive data types along with a (valid) format.
+pub fn primitive() -> impl Strategy<Value = (DataType, Option<DataTypeFormat>)> {
+    use DataTypeFormat::*;
+
+    prop_oneof![
+        sample::select(vec![None, Some(Int32), Some(Int64)]).prop_map(|f| (DataType::Integer, f)),
+        sample::select(vec![None, Some(Float), Some(Double)]).prop_map(|f| (DataType::Number, f)),
+        sample::select(vec![
+            None,
+            Some(Byte),
+            Some(Date),
+            Some(DateTime),
+            Some(Password)
+        ])
+        .prop_map(|f| (DataType::String, f)),
+        Just((DataType::Boolean, None)),
+    ]
+}
+
+/// Schemas (nested objects and arrays) which could refer to the given definitions.
+pub fn schema(definitions: Vec<String>) -> BoxedStrategy<DefaultSchemaRaw> {
+    let primitive = primitive().prop_map(|(data_type, format)| DefaultSchemaRaw {
+        data_type: Some(data_type),
+        format,
+        ..Default::default()
+    });
+
+    let leaf = if definitions.is_empty() {
+        primitive.boxed()
+    } else {
+        let reference = sample::select(definitions).prop_map(|n| DefaultSchemaRaw {
+            reference: Some(definition_reference(&n)),
+            ..Default::default()
+        });
+        prop_oneof![3 => primitive, 1 => reference].boxed()
+    };
+
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|items| DefaultSchemaRaw {
+                data_type: Some(DataType::Array),
+                items: Some(Box::new(items)),
+                ..Default::default()
+            }),
+            (
+                collection::btree_map(name(), inner, 1..4),
+                option::of(text())
+            )
+                .prop_flat_map(|(props, description)| {
+                    let names = props.keys().cloned().collect::<Vec<_>>();
+                    let required = sample::subsequence(names.clone(), 0..=names.len());
+                    (Just(props), Just(description), required)
+                })
+                .prop_map(|(props, description, required)| DefaultSchemaRaw {
+                    data_type: Some(DataType::Object),
+                    description,
+                    properties: props.into_iter().map(|(k, v)| (k, Box::new(v))).collect(),
+                    required: required.into_iter().collect(),
+                    ..Default::default()
+                }),
+        ]
+    })
+    .boxed()
+}
+
+/// Non-body parameters (primitives or arrays of primitives) for the given location.
+pub fn parameter(name: String, in_: ParameterIn) -> impl Strategy<Value = DefaultParameterRaw> {
+    let mut formats = vec![
+        CollectionFormat::Csv,
+        CollectionFormat::Ssv,
+        CollectionFormat::Tsv,
+        CollectionFormat::Pipes,
+    ];
+    if in_ == ParameterIn::Query || in_ == ParameterIn::FormData {
+        formats.push(CollectionFormat::Multi);
+    }
+
+    let items = (primitive(), sample::select(formats)).prop_map(|((data_type, format), cf)| {
+        let items = Items {
+            data_type: Some(data_type),
+            format,
+            ..Default::default()
+        };
+        (DataType::Array, None, Some(Box::new(items)), Some(cf))
+    });
+    let mut kinds = vec![
+        primitive().prop_map(|(t, f)| (t, f, None, None)).boxed(),
+        items.boxed(),
+    ];
+    if in_ == ParameterIn::FormData {
+        kinds.push(Just((DataType::File, None, None, None)).boxed());
+    }
+
+    let required = if in_ == ParameterIn::Path {
+        Just(true).boxed()
+    } else {
+        any::<bool>().boxed()
+    };
+
+    (Union::new(kinds), required, option::of(text())).prop_map(
+        move |((data_type, format, items, cf), required, description)| DefaultParameterRaw {
+            name: name.clone(),
+            in_,
+            required,
+            description,
+            data_type: Some(data_type),
+            format,
+            items: items.map(|i| *i),
+            collection_format: cf,
+            ..Default::default()
+        },
+    )
+}
+
+/// Responses with optional schemas (which could refer to the given definitions).
+pub fn response(definitions: Vec<String>) -> impl Strategy<Value = DefaultResponseRaw> {
+    (text(), option::of(schema(definitions))).prop_map(|(description, schema)| DefaultResponseRaw {
+        description: Some(description),
+        schema,
+        ..Default::default()
+    })
+}
+
+/// Operations for a path with the given parameters. Operations either have
+/// a body parameter or form data parameters (but not both).
+pub fn operation(
+    definitions: Vec<String>,
+    path_params: Vec<String>,
+) -> impl Strategy<Value = DefaultOperationRaw> {
+    let params_in = |in_, prefix: &'static str, max| {
+        collection::btree_set(name(), 0..max).prop_flat_map(move |names| {
+            names
+                .into_iter()
+                .map(|n| parameter(String::from(prefix) + &n, in_))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let path = path_params
+        .into_iter()
+        .map(|n| parameter(n, ParameterIn::Path))
+        .collect::<Vec<_>>();
+    let body = (schema(definitions.clone()), any::<bool>()).prop_map(|(schema, required)| {
+        vec![DefaultParameterRaw {
+            name: "body".into(),
+            in_: ParameterIn::Body,
+            required,
+            schema: Some(schema),
+            ..Default::default()
+        }]
+    });
+    let payload = prop_oneof![Just(vec![]), body, params_in(ParameterIn::FormData, "", 4),];
+
+    let codes = vec!["200", "201", "204", "400", "404", "default"];
+    let responses = collection::btree_map(
+        sample::select(codes).prop_map(String::from),
+        response(definitions),
+        1..4,
+    );
+
+    let meta = (
+        option::of(name()),
+        option::of(text()),
+        collection::vec(name(), 0..2),
+        any::<bool>(),
+    );
+
+    (
+        path,
+        params_in(ParameterIn::Query, "", 3),
+        params_in(ParameterIn::Header, "X-", 2),
+        payload,
+        responses,
+        meta,
+    )
+        .prop_map(
+            |(path, query, header, payload, responses, (id, summary, tags, deprecated))| {
+                DefaultOperationRaw {
+                    operation_id: id,
+                    summary,
+                    tags,
+                    deprecated,
+                    parameters: path
+                        .into_iter()
+                        .chain(query)
+                        .chain(header)
+                        .chain(payload)
+                        .map(Either::Right)
+                        .collect(),
+                    responses: responses
+                        .into_iter()
+                        .map(|(k, v)| (k, Either::Right(v)))
+                        .collect(),
+                    ..Default::default()
+                }
+            },
+        )
+}
+
+/// Path templates along with the operations for that path.
+pub fn path_item(definitions: Vec<String>) -> impl Strategy<Value = (String, DefaultPathItemRaw)> {
+    let methods = vec![
+        HttpMethod::Get,
+        HttpMethod::Put,
+        HttpMethod::Post,
+        HttpMethod::Delete,
+        HttpMethod::Patch,
+    ];
+
+    collection::vec((name(), any::<bool>()), 1..4).prop_flat_map(move |segments| {
+        let mut path = String::new();
+        let mut params = vec![];
+        for (segment, is_param) in segments {
+            if is_param && !params.contains(&segment) {
+                path.push_str(&format!("/{{{}}}", segment));
+                params.push(segment);
+            } else {
+                path.push('/');
+                path.push_str(&segment);
+            }
+        }
+
+        let ops = collection::btree_map(
+            sample::select(methods.clone()),
+            operation(definitions.clone(), params),
+            1..3,
+        );
+        (Just(path), ops).prop_map(|(path, methods)| {
+            (
+                path,
+                DefaultPathItemRaw {
+                    methods,
+                    ..Default::default()
+                },
+            )
+        })
+    })
+}
+
+/// Specs with definitions and paths.
+pub fn api() -> impl Strategy<Value = DefaultApiRaw> {
+    collection::btree_set(name(), 0..4).prop_flat_map(|names| {
+        let names = names.into_iter().collect::<Vec<_>>();
+        let definitions = names
+            .iter()
+            .map(|n| (Just(n.clone()), schema(names.clone())))
+            .collect::<Vec<_>>();
+
+        (
+            definitions,
+            collection::vec(path_item(names), 1..4),
+            option::of("/[a-z]{1,8}"),
+            text(),
+        )
+            .prop_map(|(definitions, paths, base_path, title)| DefaultApiRaw {
+                info: Info {
+                    title,
+                    version: "1.0".into(),
+                    ..Default::default()
+                },
+                base_path,
+                definitions: definitions.into_iter().collect(),
+                paths: paths.into_iter().collect::<BTreeMap<_, _>>(),
+                ..Default::default()
+            })
+    })
+}
@@ -0,0 +1,1051 @@
+//! Validation of JSON values against schemas.
+//!
+//! This checks types, required properties, additional properties, enums, references
+//! (to the given definitions), the numeric, length and item constraints, patterns and
+//! some formats (`int32`, `date`, `date-time` and `uuid`). Errors are collected for all
+//! the offending values (along with their JSON pointers) rather than the first one,
+//! and it's the foundation for checking requests and responses against the spec.
+//!
+//! ```rust
+//! use paperclip_core::v2::{models::DefaultSchemaRaw, validate::Validator};
+//! use serde_json::json;
+//!
+//! let schema: DefaultSchemaRaw = serde_json::from_value(json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "id": { "type": "integer", "minimum": 1 },
+//!         "name": { "type": "string", "maxLength": 16 }
+//!     },
+//!     "required": ["id", "name"]
+//! })).unwrap();
+//!
+//! let definitions = Default::default();
+//! let validator = Validator::new(&definitions);
+//! assert!(validator.validate(&schema, &json!({"id": 1, "name": "Milo"})).is_ok());
+//!
+//! let errors = validator.validate(&schema, &json!({"id": "1"})).unwrap_err();
+//! assert_eq!(errors[0].to_string(), "/: missing required property \"name\"");
+//! assert_eq!(errors[1].to_string(), "/id: expected integer, found string");
+//!
+//! let errors = validator.validate(&schema, &json!({"id": 0, "name": "Milo"})).unwrap_err();
+//! assert_eq!(errors[0].to_string(), "/id: 0 is below the minimum 1");
+//! ```
+//!
+//! ## Parameter coercion
+//!
+//! Query, path, header and form parameters arrive as strings. By default, they're
+//! validated as strings (i.e., only arrays are split, and the enums, lengths, patterns
+//! and the `date`, `date-time` and `uuid` formats are checked). With
+//! [`coerce_parameters`](struct.Validator.html#method.coerce_parameters), they're
+//! coerced to their documented `type` (and `format`) before they're validated:
+//!
+//! | Type | Format | Accepted inputs |
+//! |------|--------|-----------------|
+//! | `boolean` | | `true`, `false`, `1` and `0` |
+//! | `integer` | `int32`, `int64` | decimal integers (within the range of the format) |
+//! | `number` | `float`, `double` | decimal numbers |
+//! | `string` | `date` | ISO 8601 dates (`2021-04-13`) |
+//! | `string` | `date-time` | RFC 3339 date-times (`2021-04-13T10:00:00Z`, the offset is optional) |
+//! | `array` | | values separated according to the `collectionFormat`, each coerced to the `items` type |
+//!
+//! ```rust
+//! use paperclip_core::v2::{models::DefaultParameterRaw, validate::Validator};
+//! use serde_json::json;
+//!
+//! let param: DefaultParameterRaw = serde_json::from_value(json!({
+//!     "in": "query",
+//!     "name": "ids",
+//!     "type": "array",
+//!     "items": { "type": "integer", "format": "int32", "minimum": 1 },
+//!     "collectionFormat": "pipes"
+//! })).unwrap();
+//!
+//! let definitions = Default::default();
+//! let validator = Validator::new(&definitions);
+//! assert_eq!(validator.validate_parameter(&param, &["1|x"]).unwrap(), json!(["1", "x"]));
+//!
+//! let validator = Validator::new(&definitions).coerce_parameters(true);
+//! assert_eq!(validator.validate_parameter(&param, &["1|2"]).unwrap(), json!([1, 2]));
+//!
+//! let errors = validator.validate_parameter(&param, &["0|x"]).unwrap_err();
+//! assert_eq!(errors[0].to_string(), "/1: expected integer, found \"x\"");
+//! ```
+
+use super::models::{
+    CollectionFormat, DataType, DataTypeFormat, DefaultParameterRaw, DefaultSchemaRaw, Either,
+    Items,
+};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::Regex;
+use serde_json::Value;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+};
+
+static DATE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").expect("date regex"));
+// The offset is optional, because naive date-times are also documented as `date-time`.
+static DATE_TIME_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})?$")
+        .expect("date-time regex")
+});
+static UUID_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[0-9a-fA-F]{8}-([0-9a-fA-F]{4}-){3}[0-9a-fA-F]{12}$").expect("uuid regex")
+});
+/// Compiled `pattern`s of schemas (`None` for invalid ones), so that each of them
+/// is only compiled once rather than for every value.
+static PATTERNS: Lazy<RwLock<HashMap<String, Option<Regex>>>> = Lazy::new(Default::default);
+
+/// Value (at the given JSON pointer) which doesn't conform to its schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    /// JSON pointer to the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Validates values against schemas, resolving references using the given definitions.
+pub struct Validator<'a> {
+    definitions: &'a BTreeMap<String, DefaultSchemaRaw>,
+    coerce_parameters: bool,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(definitions: &'a BTreeMap<String, DefaultSchemaRaw>) -> Self {
+        Validator {
+            definitions,
+            coerce_parameters: false,
+        }
+    }
+
+    /// Sets whether the values of non-body parameters are coerced to their documented
+    /// types before they're validated (off by default).
+    pub fn coerce_parameters(mut self, coerce: bool) -> Self {
+        self.coerce_parameters = coerce;
+        self
+    }
+
+    /// Validates the value against the schema and returns all the errors (if any).
+    ///
+    /// Non-required properties are allowed to be `null` (like `None` in `Option` fields).
+    pub fn validate(
+        &self,
+        schema: &DefaultSchemaRaw,
+        value: &Value,
+    ) -> Result<(), Vec<SchemaError>> {
+        let mut errors = vec![];
+        self.check(schema, value, "", &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validates the (string) values of a non-body parameter against its constraints.
+    /// If coercion is enabled, they're coerced to the documented type first (see
+    /// [`coerce_parameter`](fn.coerce_parameter.html)). Otherwise, they're validated
+    /// as strings (and arrays of strings).
+    ///
+    /// Missing parameters are `null` (and only an error if they're required).
+    pub fn validate_parameter(
+        &self,
+        param: &DefaultParameterRaw,
+        values: &[&str],
+    ) -> Result<Value, Vec<SchemaError>> {
+        if values.is_empty() {
+            if param.required {
+                return Err(vec![SchemaError {
+                    path: "".into(),
+                    message: format!("missing required parameter {:?}", param.name),
+                }]);
+            }
+
+            return Ok(Value::Null);
+        }
+
+        if self.coerce_parameters {
+            let value = coerce_parameter(param, values)?;
+            self.validate(&parameter_schema(param), &value)?;
+            return Ok(value);
+        }
+
+        let value = split_parameter(param, values);
+        self.validate(&string_schema(parameter_schema(param)), &value)?;
+        Ok(value)
+    }
+
+    fn check(
+        &self,
+        schema: &DefaultSchemaRaw,
+        value: &Value,
+        path: &str,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        if let Some(reference) = &schema.reference {
+            // External schemas aren't available here (bundle the spec to check them).
+            if !reference.starts_with('#') {
+                return;
+            }
+
+            let name = reference.trim_start_matches("#/definitions/");
+            match self.definitions.get(name) {
+                Some(schema) => self.check(schema, value, path, errors),
+                None => errors.push(SchemaError {
+                    path: path.into(),
+                    message: format!("unresolved reference {:?}", reference),
+                }),
+            }
+
+            return;
+        }
+
+        errors.extend(
+            constraint_errors(schema, value)
+                .into_iter()
+                .map(|message| SchemaError {
+                    path: path.into(),
+                    message,
+                }),
+        );
+        if matches!(schema.data_type, Some(t) if !matches_type(t, value)) {
+            return;
+        }
+
+        match value {
+            Value::Object(map) => {
+                for (name, value) in map {
+                    let path = format!("{}/{}", path, escape(name));
+                    if let Some(prop) = schema.properties.get(name) {
+                        if !value.is_null() || schema.required.contains(name) {
+                            self.check(prop, value, &path, errors);
+                        }
+
+                        continue;
+                    }
+
+                    match &schema.extra_props {
+                        Some(Either::Left(false)) => errors.push(SchemaError {
+                            path,
+                            message: "unexpected property".into(),
+                        }),
+                        Some(Either::Right(s)) => self.check(s, value, &path, errors),
+                        _ => (),
+                    }
+                }
+            }
+            Value::Array(list) => {
+                if let Some(items) = &schema.items {
+                    for (i, value) in list.iter().enumerate() {
+                        self.check(items, value, &format!("{}/{}", path, i), errors);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Converts the (string) values of a non-body parameter to a JSON value of its
+/// documented type.
+///
+/// Arrays are split according to the collection format (`csv` by default), and
+/// `multi` arrays take all the values, whereas other types only use the first one.
+pub fn coerce_parameter(
+    param: &DefaultParameterRaw,
+    values: &[&str],
+) -> Result<Value, Vec<SchemaError>> {
+    let mut errors = vec![];
+    let value = match param.data_type {
+        Some(DataType::Array) if param.collection_format == Some(CollectionFormat::Multi) => {
+            let items = param.items.as_ref();
+            let list = values
+                .iter()
+                .enumerate()
+                .map(|(i, raw)| coerce_items(items, raw, &format!("/{}", i), &mut errors))
+                .collect();
+            Value::Array(list)
+        }
+        data_type => {
+            let raw = values.first().copied().unwrap_or_default();
+            coerce(
+                data_type,
+                param.items.as_ref(),
+                param.collection_format,
+                raw,
+                "",
+                &mut errors,
+            )
+        }
+    };
+
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+fn coerce_items(
+    items: Option<&Items>,
+    raw: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) -> Value {
+    match items {
+        Some(i) => coerce(
+            i.data_type,
+            i.items.as_deref(),
+            i.collection_format,
+            raw,
+            path,
+            errors,
+        ),
+        None => Value::String(raw.into()),
+    }
+}
+
+fn coerce(
+    data_type: Option<DataType>,
+    items: Option<&Items>,
+    collection_format: Option<CollectionFormat>,
+    raw: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) -> Value {
+    let value = match data_type {
+        Some(DataType::Boolean) => match raw {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Some(DataType::Integer) => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| raw.parse::<u64>().map(Value::from))
+            .ok(),
+        Some(DataType::Number) => raw
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(Value::from),
+        Some(DataType::Array) => {
+            let separator = separator(collection_format);
+            let list = if raw.is_empty() {
+                vec![]
+            } else {
+                raw.split(separator)
+                    .enumerate()
+                    .map(|(i, raw)| coerce_items(items, raw, &format!("{}/{}", path, i), errors))
+                    .collect()
+            };
+            Some(Value::Array(list))
+        }
+        // Strings (including dates and date-times) are checked by their format during validation.
+        _ => Some(Value::String(raw.into())),
+    };
+
+    value.unwrap_or_else(|| {
+        let expected = data_type
+            .and_then(|t| serde_json::to_value(t).ok())
+            .and_then(|t| t.as_str().map(String::from))
+            .unwrap_or_default();
+        errors.push(SchemaError {
+            path: path.into(),
+            message: format!("expected {}, found {:?}", expected, raw),
+        });
+        Value::Null
+    })
+}
+
+/// Splits the values of a non-body parameter like `coerce_parameter`, but keeps them as strings.
+fn split_parameter(param: &DefaultParameterRaw, values: &[&str]) -> Value {
+    match param.data_type {
+        Some(DataType::Array) if param.collection_format == Some(CollectionFormat::Multi) => {
+            let items = param.items.as_ref();
+            Value::Array(values.iter().map(|raw| split_items(items, raw)).collect())
+        }
+        data_type => {
+            let raw = values.first().copied().unwrap_or_default();
+            split(
+                data_type,
+                param.items.as_ref(),
+                param.collection_format,
+                raw,
+            )
+        }
+    }
+}
+
+fn split_items(items: Option<&Items>, raw: &str) -> Value {
+    match items {
+        Some(i) => split(i.data_type, i.items.as_deref(), i.collection_format, raw),
+        None => Value::String(raw.into()),
+    }
+}
+
+fn split(
+    data_type: Option<DataType>,
+    items: Option<&Items>,
+    collection_format: Option<CollectionFormat>,
+    raw: &str,
+) -> Value {
+    if data_type != Some(DataType::Array) {
+        return Value::String(raw.into());
+    }
+
+    if raw.is_empty() {
+        return Value::Array(vec![]);
+    }
+
+    let separator = separator(collection_format);
+    Value::Array(
+        raw.split(separator)
+            .map(|raw| split_items(items, raw))
+            .collect(),
+    )
+}
+
+fn separator(collection_format: Option<CollectionFormat>) -> char {
+    match collection_format.unwrap_or(CollectionFormat::Csv) {
+        CollectionFormat::Ssv => ' ',
+        CollectionFormat::Tsv => '\t',
+        CollectionFormat::Pipes => '|',
+        _ => ',',
+    }
+}
+
+/// Returns the schema for validating the uncoerced (string) values of a parameter,
+/// i.e., with string types and the allowed values in their string forms.
+fn string_schema(mut schema: DefaultSchemaRaw) -> DefaultSchemaRaw {
+    if schema.data_type == Some(DataType::Array) {
+        schema.items = schema.items.map(|i| Box::new(string_schema(*i)));
+        return schema;
+    }
+
+    if schema.data_type.is_some() {
+        schema.data_type = Some(DataType::String);
+    }
+
+    for value in &mut schema.enum_ {
+        if !value.is_string() {
+            *value = Value::String(value.to_string());
+        }
+    }
+
+    schema
+}
+
+/// Returns the schema with the constraints of the (non-body) parameter.
+fn parameter_schema(param: &DefaultParameterRaw) -> DefaultSchemaRaw {
+    DefaultSchemaRaw {
+        data_type: param.data_type,
+        format: param.format.clone(),
+        items: param.items.as_ref().map(|i| Box::new(items_schema(i))),
+        enum_: param.enum_.clone(),
+        maximum: param.maximum,
+        exclusive_maximum: param.exclusive_maximum,
+        minimum: param.minimum,
+        exclusive_minimum: param.exclusive_minimum,
+        max_length: param.max_length,
+        min_length: param.min_length,
+        pattern: param.pattern.clone(),
+        max_items: param.max_items,
+        min_items: param.min_items,
+        unique_items: Some(param.unique_items),
+        multiple_of: param.multiple_of,
+        ..Default::default()
+    }
+}
+
+fn items_schema(items: &Items) -> DefaultSchemaRaw {
+    DefaultSchemaRaw {
+        data_type: items.data_type,
+        format: items.format.clone(),
+        items: items.items.as_ref().map(|i| Box::new(items_schema(i))),
+        enum_: items.enum_.clone(),
+        maximum: items.maximum,
+        exclusive_maximum: items.exclusive_maximum,
+        minimum: items.minimum,
+        exclusive_minimum: items.exclusive_minimum,
+        max_length: items.max_length,
+        min_length: items.min_length,
+        pattern: items.pattern.clone(),
+        max_items: items.max_items,
+        min_items: items.min_items,
+        unique_items: items.unique_items,
+        multiple_of: items.multiple_of,
+        ..Default::default()
+    }
+}
+
+/// Returns the errors for the value itself (i.e., excluding nested values).
+fn constraint_errors(schema: &DefaultSchemaRaw, value: &Value) -> Vec<String> {
+    let mut errors = vec![];
+    if let Some(data_type) = schema.data_type {
+        if !matches_type(data_type, value) {
+            let expected = serde_json::to_value(data_type).expect("data type");
+            errors.push(format!(
+                "expected {}, found {}",
+                expected.as_str().unwrap_or_default(),
+                type_name(value)
+            ));
+            return errors;
+        }
+    }
+
+    if !schema.enum_.is_empty() && !schema.enum_.contains(value) {
+        errors.push(format!("{} isn't one of the allowed values", value));
+    }
+
+    match value {
+        Value::Number(n) => {
+            let v = n.as_f64().unwrap_or_default();
+            // Limits are single-precision floats in the models.
+            if let Some(min) = schema.minimum {
+                let exclusive = schema.exclusive_minimum == Some(true);
+                if (v as f32) < min || (exclusive && v as f32 == min) {
+                    let limit = if exclusive {
+                        "exclusive minimum"
+                    } else {
+                        "minimum"
+                    };
+                    errors.push(format!("{} is below the {} {}", v, limit, min));
+                }
+            }
+
+            if let Some(max) = schema.maximum {
+                let exclusive = schema.exclusive_maximum == Some(true);
+                if (v as f32) > max || (exclusive && v as f32 == max) {
+                    let limit = if exclusive {
+                        "exclusive maximum"
+                    } else {
+                        "maximum"
+                    };
+                    errors.push(format!("{} is above the {} {}", v, limit, max));
+                }
+            }
+
+            if let Some(m) = schema.multiple_of {
+                let q = v / f64::from(m);
+                if (q - q.round()).abs() > 1e-6 {
+                    errors.push(format!("{} isn't a multiple of {}", v, m));
+                }
+            }
+
+            if schema.format == Some(DataTypeFormat::Int32)
+                && !matches!(n.as_i64(), Some(i) if i32::try_from(i).is_ok())
+            {
+                errors.push(format!("{} isn't a 32-bit integer", v));
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u32;
+            if let Some(min) = schema.min_length.filter(|min| len < *min) {
+                errors.push(format!("string is shorter than {} characters", min));
+            }
+
+            if let Some(max) = schema.max_length.filter(|max| len > *max) {
+                errors.push(format!("string is longer than {} characters", max));
+            }
+
+            if let Some(pattern) = &schema.pattern {
+                match compiled_pattern(pattern) {
+                    Some(r) if !r.is_match(s) => {
+                        errors.push(format!("string doesn't match pattern {:?}", pattern))
+                    }
+                    Some(_) => (),
+                    None => errors.push(format!("invalid pattern {:?}", pattern)),
+                }
+            }
+
+            let format = match schema.format {
+                Some(DataTypeFormat::Date) => Some((&*DATE_REGEX, "date")),
+                Some(DataTypeFormat::DateTime) => Some((&*DATE_TIME_REGEX, "date-time")),
+                Some(DataTypeFormat::Uuid) => Some((&*UUID_REGEX, "uuid")),
+                _ => None,
+            };
+
+            if let Some((regex, name)) = format {
+                if !regex.is_match(s) {
+                    errors.push(format!("{:?} isn't a valid {}", s, name));
+                }
+            }
+        }
+        Value::Array(list) => {
+            let len = list.len() as u32;
+            if let Some(min) = schema.min_items.filter(|min| len < *min) {
+                errors.push(format!("array has fewer than {} items", min));
+            }
+
+            if let Some(max) = schema.max_items.filter(|max| len > *max) {
+                errors.push(format!("array has more than {} items", max));
+            }
+
+            if schema.unique_items == Some(true)
+                && list.iter().enumerate().any(|(i, v)| list[..i].contains(v))
+            {
+                errors.push("array has duplicate items".into());
+            }
+        }
+        Value::Object(map) => {
+            for name in &schema.required {
+                if !map.contains_key(name) {
+                    errors.push(format!("missing required property {:?}", name));
+                }
+            }
+        }
+        _ => (),
+    }
+
+    errors
+}
+
+/// Returns the compiled pattern (or `None` if it's invalid), compiling it on first use.
+fn compiled_pattern(pattern: &str) -> Option<Regex> {
+    if let Some(regex) = PATTERNS.read().get(pattern) {
+        return regex.clone();
+    }
+
+    PATTERNS
+        .write()
+        .entry(pattern.into())
+        .or_insert_with(|| Regex::new(pattern).ok())
+        .clone()
+}
+
+fn matches_type(data_type: DataType, value: &Value) -> bool {
+    match data_type {
+        DataType::Integer => {
+            value.is_i64()
+                || value.is_u64()
+                || matches!(value.as_f64(), Some(f) if f.fract() == 0.0)
+        }
+        DataType::Number => value.is_number(),
+        DataType::String => value.is_string(),
+        DataType::Boolean => value.is_boolean(),
+        DataType::Array => value.is_array(),
+        DataType::Object => value.is_object(),
+        // Files can't be represented in JSON.
+        DataType::File => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escapes the name for use in a JSON pointer.
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn param(value: Value) -> DefaultParameterRaw {
+        serde_json::from_value(value).expect("parameter")
+    }
+
+    fn messages(errors: Vec<SchemaError>) -> Vec<String> {
+        errors.into_iter().map(|e| e.to_string()).collect()
+    }
+
+    fn schema(value: Value) -> DefaultSchemaRaw {
+        serde_json::from_value(value).expect("schema")
+    }
+
+    fn check(schema: &DefaultSchemaRaw, value: Value) -> Vec<String> {
+        let definitions = BTreeMap::new();
+        match Validator::new(&definitions).validate(schema, &value) {
+            Ok(()) => vec![],
+            Err(e) => messages(e),
+        }
+    }
+
+    #[test]
+    fn test_numeric_constraints() {
+        let count = schema(json!({"type": "integer", "minimum": 1, "maximum": 10}));
+        assert!(check(&count, json!(1)).is_empty());
+        assert!(check(&count, json!(10)).is_empty());
+        assert_eq!(check(&count, json!(0)), vec!["/: 0 is below the minimum 1"]);
+        assert_eq!(
+            check(&count, json!(11)),
+            vec!["/: 11 is above the maximum 10"]
+        );
+        assert_eq!(
+            check(&count, json!(1.5)),
+            vec!["/: expected integer, found number"]
+        );
+
+        let ratio = schema(json!({
+            "type": "number",
+            "minimum": 0,
+            "exclusiveMinimum": true,
+            "maximum": 1,
+            "exclusiveMaximum": true,
+            "multipleOf": 0.25
+        }));
+        assert!(check(&ratio, json!(0.5)).is_empty());
+        assert_eq!(
+            check(&ratio, json!(0)),
+            vec!["/: 0 is below the exclusive minimum 0"]
+        );
+        assert_eq!(
+            check(&ratio, json!(1)),
+            vec!["/: 1 is above the exclusive maximum 1"]
+        );
+        assert_eq!(
+            check(&ratio, json!(0.3)),
+            vec!["/: 0.3 isn't a multiple of 0.25"]
+        );
+
+        let small = schema(json!({"type": "integer", "format": "int32"}));
+        assert!(check(&small, json!(-2147483648i64)).is_empty());
+        assert_eq!(
+            check(&small, json!(2147483648i64)),
+            vec!["/: 2147483648 isn't a 32-bit integer"]
+        );
+    }
+
+    #[test]
+    fn test_string_and_array_constraints() {
+        let code = schema(json!({
+            "type": "string",
+            "minLength": 2,
+            "maxLength": 4,
+            "pattern": "^[a-z]+$"
+        }));
+        assert!(check(&code, json!("abc")).is_empty());
+        // Lengths are in characters rather than bytes.
+        assert_eq!(
+            check(&code, json!("é")),
+            vec![
+                "/: string is shorter than 2 characters",
+                "/: string doesn't match pattern \"^[a-z]+$\"",
+            ]
+        );
+        assert_eq!(
+            check(&code, json!("abcde")),
+            vec!["/: string is longer than 4 characters"]
+        );
+        // The compiled pattern is reused.
+        assert_eq!(
+            check(&code, json!("AB")),
+            vec!["/: string doesn't match pattern \"^[a-z]+$\""]
+        );
+
+        let invalid = schema(json!({"type": "string", "pattern": "("}));
+        assert_eq!(
+            check(&invalid, json!("a")),
+            vec!["/: invalid pattern \"(\""]
+        );
+
+        let tags = schema(json!({
+            "type": "array",
+            "items": { "type": "string", "maxLength": 3 },
+            "minItems": 1,
+            "maxItems": 3,
+            "uniqueItems": true
+        }));
+        assert!(check(&tags, json!(["a", "b"])).is_empty());
+        assert_eq!(
+            check(&tags, json!([])),
+            vec!["/: array has fewer than 1 items"]
+        );
+        assert_eq!(
+            check(&tags, json!(["a", "b", "c", "d"])),
+            vec!["/: array has more than 3 items"]
+        );
+        assert_eq!(
+            check(&tags, json!(["a", "a", "long"])),
+            vec![
+                "/: array has duplicate items",
+                "/2: string is longer than 3 characters",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        let kind = schema(json!({"type": "string", "enum": ["ball", "bone"]}));
+        assert!(check(&kind, json!("bone")).is_empty());
+        assert_eq!(
+            check(&kind, json!("stick")),
+            vec!["/: \"stick\" isn't one of the allowed values"]
+        );
+
+        let level = schema(json!({"type": "integer", "enum": [1, 2]}));
+        assert!(check(&level, json!(2)).is_empty());
+        assert_eq!(
+            check(&level, json!(3)),
+            vec!["/: 3 isn't one of the allowed values"]
+        );
+        // Type errors aren't reported twice.
+        assert_eq!(
+            check(&level, json!("1")),
+            vec!["/: expected integer, found string"]
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let date = schema(json!({"type": "string", "format": "date"}));
+        assert!(check(&date, json!("2021-04-13")).is_empty());
+        assert_eq!(
+            check(&date, json!("2021-4-13")),
+            vec!["/: \"2021-4-13\" isn't a valid date"]
+        );
+
+        let date_time = schema(json!({"type": "string", "format": "date-time"}));
+        for valid in &[
+            "2021-04-13T10:00:00Z",
+            "2021-04-13T10:00:00.123+05:30",
+            "2021-04-13 10:00:00",
+        ] {
+            assert!(check(&date_time, json!(valid)).is_empty(), "{}", valid);
+        }
+        assert_eq!(
+            check(&date_time, json!("2021-04-13T10:00")),
+            vec!["/: \"2021-04-13T10:00\" isn't a valid date-time"]
+        );
+
+        let uuid = schema(json!({"type": "string", "format": "uuid"}));
+        assert!(check(&uuid, json!("67e55044-10b1-426f-9247-bb680e5fe0c8")).is_empty());
+        assert_eq!(
+            check(&uuid, json!("67e55044")),
+            vec!["/: \"67e55044\" isn't a valid uuid"]
+        );
+    }
+
+    #[test]
+    fn test_objects_and_references() {
+        let mut definitions = BTreeMap::new();
+        definitions.insert(
+            "Toy".to_string(),
+            schema(json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "parts": { "type": "array", "items": { "$ref": "#/definitions/Toy" } }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            })),
+        );
+        let validator = Validator::new(&definitions);
+        let toy = schema(json!({"$ref": "#/definitions/Toy"}));
+
+        assert!(validator
+            .validate(&toy, &json!({"name": "ball", "parts": [{"name": "core"}]}))
+            .is_ok());
+        assert_eq!(
+            messages(
+                validator
+                    .validate(&toy, &json!({"parts": [{"name": 1}], "color": "red"}))
+                    .unwrap_err()
+            ),
+            vec![
+                "/: missing required property \"name\"",
+                "/color: unexpected property",
+                "/parts/0/name: expected string, found integer",
+            ]
+        );
+
+        let missing = schema(json!({"$ref": "#/definitions/Bone"}));
+        assert_eq!(
+            messages(validator.validate(&missing, &json!({})).unwrap_err()),
+            vec!["/: unresolved reference \"#/definitions/Bone\""]
+        );
+    }
+
+    #[test]
+    fn test_parameters_are_not_coerced_by_default() {
+        let definitions = BTreeMap::new();
+        let validator = Validator::new(&definitions);
+
+        let flag = param(json!({"in": "query", "name": "flag", "type": "boolean"}));
+        assert_eq!(validator.validate_parameter(&flag, &["1"]), Ok(json!("1")));
+
+        let ids = param(json!({
+            "in": "query",
+            "name": "ids",
+            "type": "array",
+            "items": { "type": "integer", "minimum": 1 },
+            "collectionFormat": "multi"
+        }));
+        assert_eq!(
+            validator.validate_parameter(&ids, &["0", "x"]),
+            Ok(json!(["0", "x"]))
+        );
+
+        let sort = param(json!({
+            "in": "query",
+            "name": "sort",
+            "type": "integer",
+            "enum": [1, -1]
+        }));
+        assert_eq!(
+            validator.validate_parameter(&sort, &["-1"]),
+            Ok(json!("-1"))
+        );
+        assert_eq!(
+            messages(validator.validate_parameter(&sort, &["2"]).unwrap_err()),
+            vec!["/: \"2\" isn't one of the allowed values"]
+        );
+
+        let since = param(json!({
+            "in": "query",
+            "name": "since",
+            "type": "string",
+            "format": "date",
+            "maxLength": 10
+        }));
+        assert_eq!(
+            messages(
+                validator
+                    .validate_parameter(&since, &["13/04/2021"])
+                    .unwrap_err()
+            ),
+            vec!["/: \"13/04/2021\" isn't a valid date"]
+        );
+
+        let required = param(json!({
+            "in": "path",
+            "name": "id",
+            "type": "string",
+            "required": true
+        }));
+        assert_eq!(
+            messages(validator.validate_parameter(&required, &[]).unwrap_err()),
+            vec!["/: missing required parameter \"id\""]
+        );
+        assert_eq!(validator.validate_parameter(&flag, &[]), Ok(Value::Null));
+    }
+
+    #[test]
+    fn test_coerced_parameters() {
+        let definitions = BTreeMap::new();
+        let validator = Validator::new(&definitions).coerce_parameters(true);
+
+        let flag = param(json!({"in": "query", "name": "flag", "type": "boolean"}));
+        assert_eq!(validator.validate_parameter(&flag, &["1"]), Ok(json!(true)));
+        assert_eq!(
+            validator.validate_parameter(&flag, &["false"]),
+            Ok(json!(false))
+        );
+        assert_eq!(
+            messages(validator.validate_parameter(&flag, &["yes"]).unwrap_err()),
+            vec!["/: expected boolean, found \"yes\""]
+        );
+
+        let limit = param(json!({
+            "in": "query",
+            "name": "limit",
+            "type": "integer",
+            "format": "int32",
+            "maximum": 100
+        }));
+        assert_eq!(validator.validate_parameter(&limit, &["10"]), Ok(json!(10)));
+        assert_eq!(
+            messages(validator.validate_parameter(&limit, &["1000"]).unwrap_err()),
+            vec!["/: 1000 is above the maximum 100"]
+        );
+        assert_eq!(
+            messages(
+                validator
+                    .validate_parameter(&limit, &["5000000000"])
+                    .unwrap_err()
+            ),
+            vec![
+                "/: 5000000000 is above the maximum 100",
+                "/: 5000000000 isn't a 32-bit integer"
+            ]
+        );
+
+        let ratio = param(json!({"in": "query", "name": "ratio", "type": "number"}));
+        assert_eq!(
+            validator.validate_parameter(&ratio, &["0.5"]),
+            Ok(json!(0.5))
+        );
+        assert!(validator.validate_parameter(&ratio, &["inf"]).is_err());
+
+        let ids = param(json!({
+            "in": "query",
+            "name": "ids",
+            "type": "array",
+            "items": { "type": "integer", "minimum": 1 },
+            "collectionFormat": "multi",
+            "uniqueItems": true
+        }));
+        assert_eq!(
+            validator.validate_parameter(&ids, &["1", "2"]),
+            Ok(json!([1, 2]))
+        );
+        assert_eq!(
+            messages(validator.validate_parameter(&ids, &["0", "x"]).unwrap_err()),
+            vec!["/1: expected integer, found \"x\""]
+        );
+        assert_eq!(
+            messages(validator.validate_parameter(&ids, &["1", "1"]).unwrap_err()),
+            vec!["/: array has duplicate items"]
+        );
+
+        let tags = param(json!({
+            "in": "query",
+            "name": "tags",
+            "type": "array",
+            "items": { "type": "string" },
+            "collectionFormat": "ssv"
+        }));
+        assert_eq!(
+            validator.validate_parameter(&tags, &["a b"]),
+            Ok(json!(["a", "b"]))
+        );
+        assert_eq!(validator.validate_parameter(&tags, &[""]), Ok(json!([])));
+
+        let since = param(json!({
+            "in": "query",
+            "name": "since",
+            "type": "string",
+            "format": "date-time"
+        }));
+        assert_eq!(
+            validator.validate_parameter(&since, &["2021-04-13T10:00:00"]),
+            Ok(json!("2021-04-13T10:00:00"))
+        );
+        assert_eq!(
+            messages(
+                validator
+                    .validate_parameter(&since, &["2021-04-13"])
+                    .unwrap_err()
+            ),
+            vec!["/: \"2021-04-13\" isn't a valid date-time"]
+        );
+    }
+}
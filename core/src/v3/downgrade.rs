@@ -0,0 +1,750 @@
+//! Best-effort conversion of OpenAPI v3 documents to v2.
+//!
+//! This works on the JSON representation of the v3 document (which is well-defined
+//! regardless of the types used for modeling it) and builds the v2 models from it.
+//! Constructs which can't be expressed in v2 are dropped and reported as warnings
+//! (prefixed with the JSON pointer to the offending node).
+
+use super::v2::{self, Either};
+use serde_json::{Map, Value};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+const URL_ENCODED_FORM: &str = "application/x-www-form-urlencoded";
+const MULTIPART_FORM: &str = "multipart/form-data";
+const METHODS: &[(&str, v2::HttpMethod)] = &[
+    ("get", v2::HttpMethod::Get),
+    ("put", v2::HttpMethod::Put),
+    ("post", v2::HttpMethod::Post),
+    ("delete", v2::HttpMethod::Delete),
+    ("options", v2::HttpMethod::Options),
+    ("head", v2::HttpMethod::Head),
+    ("patch", v2::HttpMethod::Patch),
+];
+
+/// State for converting a v3 document.
+pub(crate) struct Downgrade<'a> {
+    doc: &'a Value,
+    warnings: Vec<String>,
+}
+
+impl<'a> Downgrade<'a> {
+    pub(crate) fn new(doc: &'a Value) -> Self {
+        Downgrade {
+            doc,
+            warnings: vec![],
+        }
+    }
+
+    /// Converts the document and returns the v2 spec along with the warnings.
+    pub(crate) fn run(mut self) -> (v2::DefaultApiRaw, Vec<String>) {
+        let doc = self.doc;
+        let mut api = v2::DefaultApiRaw {
+            info: self.deserialize(&doc["info"], "/info"),
+            tags: self.deserialize(&doc["tags"], "/tags"),
+            external_docs: self.deserialize(&doc["externalDocs"], "/externalDocs"),
+            security: self.security(&doc["security"]),
+            ..Default::default()
+        };
+
+        self.servers(&doc["servers"], "/servers", &mut api);
+
+        let components = &doc["components"];
+        for (name, schema) in entries(&components["schemas"]) {
+            let loc = pointer(&["components", "schemas", name]);
+            let schema = self.schema(schema, &loc);
+            api.definitions.insert(name.clone(), schema);
+        }
+
+        for (name, param) in entries(&components["parameters"]) {
+            let loc = pointer(&["components", "parameters", name]);
+            if let Some(Either::Right(param)) = self.parameter(param, &loc) {
+                api.parameters.insert(name.clone(), param);
+            }
+        }
+
+        for (name, resp) in entries(&components["responses"]) {
+            let loc = pointer(&["components", "responses", name]);
+            let mut produces = BTreeSet::new();
+            if let Either::Right(resp) = self.response(resp, &loc, &mut produces) {
+                api.responses.insert(name.clone(), resp);
+                api.produces.extend(produces);
+            }
+        }
+
+        for (name, scheme) in entries(&components["securitySchemes"]) {
+            let loc = pointer(&["components", "securitySchemes", name]);
+            if let Some(scheme) = self.security_scheme(scheme, &loc) {
+                api.security_definitions.insert(name.clone(), scheme);
+            }
+        }
+
+        for (path, item) in entries(&doc["paths"]) {
+            let loc = pointer(&["paths", path]);
+            let item = self.path_item(item, &loc);
+            api.paths.insert(path.clone(), item);
+        }
+
+        (api, self.warnings)
+    }
+
+    fn warn(&mut self, loc: &str, message: &str) {
+        self.warnings.push(format!("{}: {}", loc, message));
+    }
+
+    /// Deserializes the (optional) node into a v2 model which has the same representation.
+    fn deserialize<T>(&mut self, node: &Value, loc: &str) -> T
+    where
+        T: serde::de::DeserializeOwned + Default,
+    {
+        if node.is_null() {
+            return T::default();
+        }
+
+        serde_json::from_value(node.clone()).unwrap_or_else(|e| {
+            self.warn(loc, &format!("invalid object ({})", e));
+            T::default()
+        })
+    }
+
+    /// Resolves local references to components (if the node is a reference).
+    fn resolve(&mut self, node: &'a Value, loc: &str) -> &'a Value {
+        let reference = match node.get("$ref").and_then(Value::as_str) {
+            Some(r) => r,
+            None => return node,
+        };
+
+        match reference
+            .strip_prefix('#')
+            .and_then(|p| self.doc.pointer(p))
+        {
+            Some(target) => self.resolve(target, loc),
+            None => {
+                self.warn(loc, &format!("unresolved reference {}", reference));
+                &Value::Null
+            }
+        }
+    }
+
+    /// Uses the host, base path and scheme from the first server. Multiple servers
+    /// (or servers with variables) are also kept in the `x-servers` extension.
+    fn servers(&mut self, servers: &Value, loc: &str, api: &mut v2::DefaultApiRaw) {
+        let list = servers.as_array().map(Vec::as_slice).unwrap_or_default();
+        let server = match list.first() {
+            Some(s) => s,
+            None => return,
+        };
+
+        if list.len() > 1 || list.iter().any(|s| s.get("variables").is_some()) {
+            api.servers = self.deserialize(servers, loc);
+        }
+
+        // Substitute the defaults for variables.
+        let mut url = server["url"].as_str().unwrap_or_default().to_owned();
+        for (name, var) in entries(&server["variables"]) {
+            let default = var["default"].as_str().unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", name), default);
+        }
+
+        let path = match url.split_once("://") {
+            Some((scheme, rest)) => {
+                match serde_json::from_value(Value::String(scheme.to_lowercase())) {
+                    Ok(s) => {
+                        api.schemes.insert(s);
+                    }
+                    Err(_) => self.warn(loc, &format!("unsupported scheme {}", scheme)),
+                }
+
+                let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                api.host = Some(host.into());
+                path
+            }
+            None => &url,
+        };
+
+        let path = path.trim_end_matches('/');
+        if !path.is_empty() {
+            api.base_path = Some(path.into());
+        }
+    }
+
+    fn security(&mut self, node: &Value) -> Vec<BTreeMap<String, BTreeSet<String>>> {
+        let mut requirements = vec![];
+        for req in node.as_array().into_iter().flatten() {
+            requirements.push(
+                entries(req)
+                    .map(|(name, scopes)| (name.clone(), string_list(scopes).collect()))
+                    .collect(),
+            );
+        }
+
+        requirements
+    }
+
+    fn security_scheme(&mut self, node: &'a Value, loc: &str) -> Option<v2::SecurityScheme> {
+        let node = self.resolve(node, loc);
+        let mut scheme = v2::SecurityScheme {
+            description: str_of(&node["description"]),
+            ..Default::default()
+        };
+
+        match node["type"].as_str().unwrap_or_default() {
+            "http" if node["scheme"].as_str() == Some("basic") => scheme.type_ = "basic".into(),
+            // Bearer tokens are passed in the header.
+            "http" if node["scheme"].as_str() == Some("bearer") => {
+                scheme.type_ = "apiKey".into();
+                scheme.in_ = Some("header".into());
+                scheme.name = Some("Authorization".into());
+            }
+            "apiKey" if node["in"].as_str() != Some("cookie") => {
+                scheme.type_ = "apiKey".into();
+                scheme.in_ = str_of(&node["in"]);
+                scheme.name = str_of(&node["name"]);
+            }
+            "oauth2" => {
+                let flows: Vec<_> = [
+                    "implicit",
+                    "password",
+                    "clientCredentials",
+                    "authorizationCode",
+                ]
+                .iter()
+                .filter(|f| node["flows"].get(**f).is_some())
+                .collect();
+                let flow = *flows.first()?;
+                if flows.len() > 1 {
+                    self.warn(loc, "only the first OAuth2 flow is used");
+                }
+
+                let data = &node["flows"][flow];
+                scheme.type_ = "oauth2".into();
+                scheme.flow = Some(
+                    match *flow {
+                        "clientCredentials" => "application",
+                        "authorizationCode" => "accessCode",
+                        f => f,
+                    }
+                    .into(),
+                );
+                scheme.auth_url = str_of(&data["authorizationUrl"]);
+                scheme.token_url = str_of(&data["tokenUrl"]);
+                scheme.scopes = entries(&data["scopes"])
+                    .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().into()))
+                    .collect();
+            }
+            _ => {
+                self.warn(loc, "security scheme isn't supported in v2");
+                return None;
+            }
+        }
+
+        Some(scheme)
+    }
+
+    fn path_item(&mut self, node: &'a Value, loc: &str) -> v2::DefaultPathItemRaw {
+        let node = self.resolve(node, loc);
+        let mut item = v2::DefaultPathItemRaw::default();
+        for (i, param) in node["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let loc = format!("{}/parameters/{}", loc, i);
+            item.parameters.extend(self.parameter(param, &loc));
+        }
+
+        for (name, method) in METHODS {
+            if let Some(op) = node.get(name) {
+                let loc = format!("{}/{}", loc, name);
+                item.methods.insert(*method, self.operation(op, &loc));
+            }
+        }
+
+        if node.get("trace").is_some() {
+            self.warn(
+                &format!("{}/trace", loc),
+                "TRACE operations aren't supported in v2",
+            );
+        }
+
+        if node.get("servers").is_some() {
+            self.warn(
+                &format!("{}/servers", loc),
+                "servers for paths aren't supported in v2",
+            );
+        }
+
+        item
+    }
+
+    fn operation(&mut self, node: &'a Value, loc: &str) -> v2::DefaultOperationRaw {
+        let mut op = v2::DefaultOperationRaw {
+            operation_id: str_of(&node["operationId"]),
+            summary: str_of(&node["summary"]),
+            description: str_of(&node["description"]),
+            tags: string_list(&node["tags"]).collect(),
+            external_docs: self
+                .deserialize(&node["externalDocs"], &format!("{}/externalDocs", loc)),
+            deprecated: node["deprecated"].as_bool().unwrap_or_default(),
+            required_roles: string_list(&node["x-required-roles"]).collect(),
+            extensions: entries(node)
+                .filter(|(k, _)| k.starts_with("x-") && *k != "x-required-roles")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            security: self
+                .security(&node["security"])
+                .into_iter()
+                .map(|r| {
+                    r.into_iter()
+                        .map(|(k, v)| (k, v.into_iter().collect()))
+                        .collect()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        for (i, param) in node["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let loc = format!("{}/parameters/{}", loc, i);
+            // Cookies are kept in the `x-cookies` extension of the operation.
+            if self.resolve(param, &loc)["in"] == "cookie" {
+                op.cookies.push(self.cookie(param, &loc));
+                continue;
+            }
+
+            op.parameters.extend(self.parameter(param, &loc));
+        }
+
+        if let Some(body) = node.get("requestBody") {
+            self.request_body(body, &format!("{}/requestBody", loc), &mut op);
+        }
+
+        let mut produces = BTreeSet::new();
+        for (code, resp) in entries(&node["responses"]) {
+            let resp_loc = format!("{}/responses/{}", loc, code);
+            if code != "default" && code.parse::<u16>().is_err() {
+                self.warn(&resp_loc, "ranges of status codes aren't supported in v2");
+                continue;
+            }
+
+            let resp = self.response(resp, &resp_loc, &mut produces);
+            op.responses.insert(code.clone(), resp);
+        }
+
+        if !produces.is_empty() {
+            op.produces = Some(produces);
+        }
+
+        for key in &["callbacks", "servers"] {
+            if node.get(key).is_some() {
+                self.warn(&format!("{}/{}", loc, key), "not supported in v2");
+            }
+        }
+
+        op
+    }
+
+    fn parameter(
+        &mut self,
+        node: &'a Value,
+        loc: &str,
+    ) -> Option<Either<v2::Reference, v2::DefaultParameterRaw>> {
+        if let Some(r) = node.get("$ref").and_then(Value::as_str) {
+            if r.starts_with("#/components/parameters/") {
+                return Some(Either::Left(v2::Reference {
+                    reference: rewrite_reference(r),
+                }));
+            }
+        }
+
+        let node = self.resolve(node, loc);
+        let in_ = node["in"].as_str().unwrap_or_default();
+        if in_ == "cookie" {
+            self.warn(loc, "cookie parameters aren't supported in v2");
+            return None;
+        }
+
+        let schema = match (node.get("schema"), node.get("content")) {
+            (Some(s), _) => s,
+            (None, Some(content)) => {
+                self.warn(loc, "parameter content is replaced by its schema");
+                match entries(content).next() {
+                    Some((_, media)) => &media["schema"],
+                    None => &Value::Null,
+                }
+            }
+            _ => &Value::Null,
+        };
+
+        let schema_loc = format!("{}/schema", loc);
+        let mut param = self.flat_schema(schema, &schema_loc);
+        // Objects in query are (free-form) `deepObject` parameters in v2.
+        let is_deep_object = in_ == "query" && node["style"].as_str() == Some("deepObject");
+        if param.get("type").and_then(Value::as_str) == Some("object") && !is_deep_object {
+            self.warn(&schema_loc, "object parameters aren't supported in v2");
+            param.insert("type".into(), "string".into());
+        }
+
+        if param.get("type").and_then(Value::as_str) == Some("array") {
+            if let Some(format) = self.collection_format(node, in_, loc) {
+                param.insert("collectionFormat".into(), format.into());
+            }
+        }
+
+        for key in &["name", "in", "description", "required", "allowEmptyValue"] {
+            if let Some(v) = node.get(*key) {
+                param.insert((*key).into(), v.clone());
+            }
+        }
+
+        Some(Either::Right(self.deserialize(&Value::Object(param), loc)))
+    }
+
+    /// Cookie (in the `x-cookies` extension of operations) for the given cookie parameter.
+    fn cookie(&mut self, node: &'a Value, loc: &str) -> v2::CookieParameter {
+        let node = self.resolve(node, loc);
+        let mut cookie = self.flat_schema(&node["schema"], &format!("{}/schema", loc));
+        for key in &["name", "description", "required"] {
+            if let Some(v) = node.get(*key) {
+                cookie.insert((*key).into(), v.clone());
+            }
+        }
+
+        self.deserialize(&Value::Object(cookie), loc)
+    }
+
+    /// Collection format for the style of an array parameter (if it's not the default).
+    fn collection_format(&mut self, node: &Value, in_: &str, loc: &str) -> Option<&'static str> {
+        // Formats which couldn't be expressed in v3 are kept in an extension.
+        if let Some(f) = node["x-collection-format"].as_str() {
+            return ["csv", "ssv", "tsv", "pipes", "multi"]
+                .iter()
+                .find(|v| **v == f)
+                .copied();
+        }
+
+        let style =
+            node["style"]
+                .as_str()
+                .unwrap_or(if in_ == "query" { "form" } else { "simple" });
+        let explode = node["explode"].as_bool().unwrap_or(style == "form");
+        match style {
+            "form" if explode => Some("multi"),
+            "spaceDelimited" => Some("ssv"),
+            "pipeDelimited" => Some("pipes"),
+            "form" | "simple" => None,
+            s => {
+                self.warn(loc, &format!("{} style isn't supported in v2", s));
+                None
+            }
+        }
+    }
+
+    /// Returns the fields of a (non-body) parameter, header or item for the given schema.
+    fn flat_schema(&mut self, node: &'a Value, loc: &str) -> Map<String, Value> {
+        let node = self.resolve(node, loc);
+        let mut flat = Map::new();
+        for key in &[
+            "type",
+            "format",
+            "default",
+            "maximum",
+            "exclusiveMaximum",
+            "minimum",
+            "exclusiveMinimum",
+            "maxLength",
+            "minLength",
+            "pattern",
+            "maxItems",
+            "minItems",
+            "uniqueItems",
+            "multipleOf",
+            "enum",
+        ] {
+            if let Some(v) = node.get(*key) {
+                flat.insert((*key).into(), v.clone());
+            }
+        }
+
+        if let Some(items) = node.get("items") {
+            let items = self.flat_schema(items, &format!("{}/items", loc));
+            flat.insert("items".into(), Value::Object(items));
+        }
+
+        flat
+    }
+
+    fn request_body(&mut self, node: &'a Value, loc: &str, op: &mut v2::DefaultOperationRaw) {
+        let node = self.resolve(node, loc);
+        let content = entries(&node["content"]).collect::<Vec<_>>();
+        let is_form = |m: &str| m == URL_ENCODED_FORM || m == MULTIPART_FORM;
+
+        op.consumes = Some(
+            content
+                .iter()
+                .filter_map(|(m, _)| m.parse().ok().map(v2::MediaRange))
+                .collect(),
+        );
+
+        // v2 doesn't allow both body and form parameters, so the body is preferred.
+        let body = content
+            .iter()
+            .find(|(m, _)| m.contains("json"))
+            .or_else(|| content.iter().find(|(m, _)| !is_form(m)));
+        if let Some((media, value)) = body {
+            if content.iter().any(|(m, _)| is_form(m)) {
+                self.warn(loc, "form data is ignored in favor of the request body");
+            }
+
+            let schema_loc = format!("{}/content/{}/schema", loc, escape(media));
+            let schema = self.schema(&value["schema"], &schema_loc);
+            op.parameters.push(Either::Right(v2::DefaultParameterRaw {
+                name: str_of(&node["x-codegen-request-body-name"]).unwrap_or_else(|| "body".into()),
+                in_: v2::ParameterIn::Body,
+                description: str_of(&node["description"]),
+                required: node["required"].as_bool().unwrap_or_default(),
+                schema: Some(schema),
+                ..Default::default()
+            }));
+            return;
+        }
+
+        let (media, value) = match content.first() {
+            Some(c) => *c,
+            None => return,
+        };
+
+        let schema_loc = format!("{}/content/{}/schema", loc, escape(media));
+        let schema = self.resolve(&value["schema"], &schema_loc);
+        let required: BTreeSet<_> = string_list(&schema["required"]).collect();
+        for (name, prop) in entries(&schema["properties"]) {
+            let prop_loc = format!("{}/properties/{}", schema_loc, escape(name));
+            let mut param = self.flat_schema(prop, &prop_loc);
+            // Binary strings are files.
+            if param.get("format").and_then(Value::as_str) == Some("binary") {
+                param.insert("type".into(), "file".into());
+                param.remove("format");
+            }
+
+            param.insert("name".into(), name.clone().into());
+            param.insert("in".into(), "formData".into());
+            param.insert("required".into(), required.contains(name).into());
+            if let Some(d) = self.resolve(prop, &prop_loc).get("description") {
+                param.insert("description".into(), d.clone());
+            }
+
+            let param = self.deserialize(&Value::Object(param), &prop_loc);
+            op.parameters.push(Either::Right(param));
+        }
+    }
+
+    fn response(
+        &mut self,
+        node: &'a Value,
+        loc: &str,
+        produces: &mut BTreeSet<v2::MediaRange>,
+    ) -> Either<v2::Reference, v2::DefaultResponseRaw> {
+        if let Some(r) = node.get("$ref").and_then(Value::as_str) {
+            if r.starts_with("#/components/responses/") {
+                return Either::Left(v2::Reference {
+                    reference: rewrite_reference(r),
+                });
+            }
+        }
+
+        let node = self.resolve(node, loc);
+        let mut resp = v2::DefaultResponseRaw {
+            description: str_of(&node["description"]),
+            ..Default::default()
+        };
+
+        let content = entries(&node["content"]).collect::<Vec<_>>();
+        produces.extend(
+            content
+                .iter()
+                .filter_map(|(m, _)| m.parse().ok().map(v2::MediaRange)),
+        );
+        for (m, v) in &content {
+            if let Some(example) = v.get("example") {
+                resp.examples.insert(m.to_string(), example.clone());
+            }
+        }
+
+        let media = content
+            .iter()
+            .find(|(m, _)| m.contains("json"))
+            .or_else(|| content.first());
+        if let Some((media, value)) = media {
+            if value.get("schema").is_some() {
+                let schema_loc = format!("{}/content/{}/schema", loc, escape(media));
+                resp.schema = Some(self.schema(&value["schema"], &schema_loc));
+            }
+
+            // Other media types with their own schemas are kept as extensions.
+            for (other, other_value) in &content {
+                if other == media || other_value.get("schema").is_none() {
+                    continue;
+                }
+
+                if other_value.get("schema") != value.get("schema") {
+                    let schema_loc = format!("{}/content/{}/schema", loc, escape(other));
+                    let schema = self.schema(&other_value["schema"], &schema_loc);
+                    resp.content_schemas.insert(other.to_string(), schema);
+                }
+            }
+        }
+
+        for (name, header) in entries(&node["headers"]) {
+            let header_loc = format!("{}/headers/{}", loc, escape(name));
+            let header = self.resolve(header, &header_loc);
+            let mut flat = self.flat_schema(&header["schema"], &format!("{}/schema", header_loc));
+            if let Some(d) = header.get("description") {
+                flat.insert("description".into(), d.clone());
+            }
+
+            let header = self.deserialize(&Value::Object(flat), &header_loc);
+            resp.headers.insert(name.clone(), header);
+        }
+
+        for (name, link) in entries(&node["links"]) {
+            match link["operationId"].as_str() {
+                // Links to the status of long-running operations are kept as an extension.
+                Some(id) if name == "status" => resp.status_operation = Some(id.into()),
+                _ => self.warn(
+                    &format!("{}/links/{}", loc, escape(name)),
+                    "links aren't supported in v2",
+                ),
+            }
+        }
+
+        Either::Right(resp)
+    }
+
+    fn schema(&mut self, node: &'a Value, loc: &str) -> v2::DefaultSchemaRaw {
+        let mut schema = v2::DefaultSchemaRaw::default();
+        if let Some(r) = node.get("$ref").and_then(Value::as_str) {
+            schema.reference = Some(rewrite_reference(r).into());
+            return schema;
+        }
+
+        if let Some(list) = node["allOf"].as_array() {
+            // Merge all the (resolved) schemas.
+            for (i, s) in list.iter().enumerate() {
+                let s_loc = format!("{}/allOf/{}", loc, i);
+                let s = self.resolve(s, &s_loc);
+                let s = self.schema(s, &s_loc);
+                schema.data_type = schema.data_type.or(s.data_type);
+                schema.description = schema.description.or(s.description);
+                schema.properties.extend(s.properties);
+                schema.required.extend(s.required);
+            }
+        }
+
+        for key in &["oneOf", "anyOf", "not", "discriminator"] {
+            if node.get(key).is_some() {
+                self.warn(&format!("{}/{}", loc, key), "not supported in v2");
+            }
+        }
+
+        if node["nullable"].as_bool() == Some(true) {
+            self.warn(&format!("{}/nullable", loc), "not supported in v2");
+        }
+
+        if let Some(t) = node.get("type") {
+            schema.data_type = self.deserialize::<Option<_>>(t, loc);
+        }
+
+        if let Some(f) = node.get("format") {
+            schema.format = self.deserialize::<Option<_>>(f, loc);
+        }
+
+        schema.title = str_of(&node["title"]).or(schema.title);
+        schema.description = str_of(&node["description"]).or(schema.description);
+        schema.example = match &node["example"] {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            v => Some(v.to_string()),
+        };
+        schema.deprecated = node["deprecated"].as_bool().unwrap_or_default();
+        schema.enum_ = node["enum"].as_array().cloned().unwrap_or_default();
+        schema.maximum = node["maximum"].as_f64().map(|v| v as f32);
+        schema.exclusive_maximum = node["exclusiveMaximum"].as_bool();
+        schema.minimum = node["minimum"].as_f64().map(|v| v as f32);
+        schema.exclusive_minimum = node["exclusiveMinimum"].as_bool();
+        schema.max_length = node["maxLength"].as_u64().map(|v| v as u32);
+        schema.min_length = node["minLength"].as_u64().map(|v| v as u32);
+        schema.pattern = str_of(&node["pattern"]);
+        schema.max_items = node["maxItems"].as_u64().map(|v| v as u32);
+        schema.min_items = node["minItems"].as_u64().map(|v| v as u32);
+        schema.unique_items = node["uniqueItems"].as_bool();
+        schema.multiple_of = node["multipleOf"].as_f64().map(|v| v as f32);
+        schema.required.extend(string_list(&node["required"]));
+
+        for (name, prop) in entries(&node["properties"]) {
+            let prop_loc = format!("{}/properties/{}", loc, escape(name));
+            let prop = self.schema(prop, &prop_loc);
+            schema.properties.insert(name.clone(), Box::new(prop));
+        }
+
+        if let Some(items) = node.get("items") {
+            let items = self.schema(items, &format!("{}/items", loc));
+            schema.items = Some(Box::new(items));
+        }
+
+        schema.extra_props = match node.get("additionalProperties") {
+            Some(Value::Bool(b)) => Some(Either::Left(*b)),
+            Some(s) => {
+                let s = self.schema(s, &format!("{}/additionalProperties", loc));
+                Some(Either::Right(Box::new(s)))
+            }
+            None => None,
+        };
+
+        schema
+    }
+}
+
+/// Iterates over the entries of an object node (if it's an object).
+fn entries(node: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    node.as_object().into_iter().flatten()
+}
+
+fn string_list(node: &Value) -> impl Iterator<Item = String> + '_ {
+    node.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(String::from)
+}
+
+fn str_of(node: &Value) -> Option<String> {
+    node.as_str().map(String::from)
+}
+
+/// Points references to components at their v2 counterparts.
+fn rewrite_reference(reference: &str) -> String {
+    [
+        ("#/components/schemas/", "#/definitions/"),
+        ("#/components/parameters/", "#/parameters/"),
+        ("#/components/responses/", "#/responses/"),
+    ]
+    .iter()
+    .find_map(|(v3, v2)| reference.strip_prefix(v3).map(|n| String::from(*v2) + n))
+    .unwrap_or_else(|| reference.into())
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn pointer(tokens: &[&str]) -> String {
+    tokens
+        .iter()
+        .map(|t| String::from("/") + &escape(t))
+        .collect()
+}
@@ -1,12 +1,15 @@
 #![cfg(feature = "v3")]
-//! Conversion traits and helps functions that help converting openapi v2 types to openapi v3.
+//! Conversion traits and helps functions that help converting openapi v2 types to openapi v3
+//! (and best-effort conversion of openapi v3 documents back to v2).
 //! For the OpenAPI v3 types the crate `openapiv3` is used.
 
 mod contact;
+mod downgrade;
 mod external_documentation;
 mod header;
 mod info;
 mod license;
+pub mod native;
 mod openapi;
 mod operation;
 mod parameter;
@@ -17,6 +20,9 @@ mod response;
 mod schema;
 mod security_scheme;
 mod tag;
+mod v31;
+
+pub use openapiv3;
 
 use super::v2::{models as v2, models::Either};
 
@@ -28,3 +34,22 @@ use response::OperationEitherResponse;
 pub fn openapiv2_to_v3(v2: v2::DefaultApiRaw) -> openapiv3::OpenAPI {
     openapiv3::OpenAPI::from(v2)
 }
+
+/// Convert `openapiv3::OpenAPI` to this crates openapi v2 (`DefaultApiRaw`).
+///
+/// This is best-effort: constructs which can't be expressed in v2 (cookie parameters,
+/// `oneOf`, links, callbacks, etc.) are dropped, and the returned warnings describe
+/// what was dropped along with its location in the v3 document.
+pub fn openapiv3_to_v2(v3: openapiv3::OpenAPI) -> (v2::DefaultApiRaw, Vec<String>) {
+    let doc = serde_json::to_value(&v3).expect("serializing v3 spec");
+    downgrade::Downgrade::new(&doc).run()
+}
+
+/// Convert `openapiv3::OpenAPI` to an OpenAPI 3.1 document (as JSON, since `openapiv3`
+/// doesn't model 3.1), whose schemas are JSON Schema compliant. For example, `nullable`
+/// schemas become `type: ["string", "null"]` and `example` becomes `examples`.
+pub fn openapiv3_to_v31(v3: &openapiv3::OpenAPI) -> serde_json::Value {
+    let mut doc = serde_json::to_value(v3).expect("serializing v3 spec");
+    v31::upgrade(&mut doc);
+    doc
+}
@@ -0,0 +1,254 @@
+//! Helpers for emitting OpenAPI v3 schemas natively (i.e., without converting them
+//! from v2), so that constructs which don't exist in v2 (like `nullable` and `oneOf`)
+//! can be used. These are used by the schemas derived through `#[derive(Apiv2Schema)]`
+//! (with the `v3` feature) and the `*_v3` methods of `Apiv2Schema`.
+
+use super::schema::add_constraints;
+use crate::v2::models::DefaultSchemaRaw;
+use openapiv3::{
+    AdditionalProperties, ArrayType, ObjectType, ReferenceOr, Schema, SchemaData, SchemaKind,
+    StringType, Type, VariantOrUnknownOrEmpty,
+};
+
+use std::collections::BTreeMap;
+
+/// Map of schema names and their (v3) schemas, which ends up in `components.schemas`.
+pub type SchemaMap = BTreeMap<String, ReferenceOr<Schema>>;
+
+/// Returns a reference to the named schema in `components.schemas`.
+pub fn reference(name: &str) -> ReferenceOr<Schema> {
+    ReferenceOr::ref_(&format!("#/components/schemas/{}", name))
+}
+
+/// Returns an (empty) object schema with the given description (if it's not empty).
+pub fn object(description: &str) -> Schema {
+    Schema {
+        schema_data: SchemaData {
+            description: Some(description.to_owned()).filter(|d| !d.is_empty()),
+            ..Default::default()
+        },
+        schema_kind: SchemaKind::Type(Type::Object(ObjectType::default())),
+    }
+}
+
+/// Returns an array schema with the given items.
+pub fn array(items: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    ReferenceOr::Item(Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::Type(Type::Array(ArrayType {
+            items: boxed(items),
+            min_items: None,
+            max_items: None,
+            unique_items: false,
+        })),
+    })
+}
+
+/// Returns an object schema whose values have the given schema.
+pub fn map(values: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    ReferenceOr::Item(Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::Type(Type::Object(ObjectType {
+            additional_properties: Some(AdditionalProperties::Schema(Box::new(values))),
+            ..Default::default()
+        })),
+    })
+}
+
+/// Adds a property to the given object schema. The description is only set
+/// for inline schemas, because siblings of `$ref` are ignored.
+pub fn add_property(
+    schema: &mut Schema,
+    name: &str,
+    mut property: ReferenceOr<Schema>,
+    description: &str,
+    required: bool,
+) {
+    if let ReferenceOr::Item(p) = &mut property {
+        if !description.is_empty() {
+            p.schema_data.description = Some(description.to_owned());
+        }
+    }
+
+    if let SchemaKind::Type(Type::Object(o)) = &mut schema.schema_kind {
+        o.properties.insert(name.into(), boxed(property));
+        if required {
+            o.required.push(name.into());
+        }
+    }
+}
+
+/// Marks the given (property) schema as write-only, since it's sensitive. References
+/// can't be marked, so they're wrapped in `allOf`.
+pub fn sensitive(schema: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    let mut schema = match schema {
+        ReferenceOr::Item(s) => s,
+        reference => Schema {
+            schema_data: Default::default(),
+            schema_kind: SchemaKind::AllOf {
+                all_of: vec![reference],
+            },
+        },
+    };
+
+    schema.schema_data.write_only = true;
+    ReferenceOr::Item(schema)
+}
+
+/// Marks the given (property) schema as deprecated. References can't be marked,
+/// so they're wrapped in `allOf`.
+pub fn deprecated(schema: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    let mut schema = match schema {
+        ReferenceOr::Item(s) => s,
+        reference => Schema {
+            schema_data: Default::default(),
+            schema_kind: SchemaKind::AllOf {
+                all_of: vec![reference],
+            },
+        },
+    };
+
+    schema.schema_data.deprecated = true;
+    ReferenceOr::Item(schema)
+}
+
+/// Sets the example of the given (property) schema, which is parsed as JSON (or taken
+/// as a string, if it isn't JSON). References can't have examples, so they're left alone.
+pub fn example(mut schema: ReferenceOr<Schema>, example: &str) -> ReferenceOr<Schema> {
+    if let ReferenceOr::Item(s) = &mut schema {
+        let value = serde_json::from_str(example).unwrap_or_else(|_| example.into());
+        s.schema_data.example = Some(value);
+    }
+
+    schema
+}
+
+/// Adds the properties of the given (flattened) object schema to the object
+/// schema, which are required only if the flattened object itself is required.
+pub fn flatten(schema: &mut Schema, other: ReferenceOr<Schema>, required: bool) {
+    let other = match other {
+        ReferenceOr::Item(Schema {
+            schema_kind: SchemaKind::Type(Type::Object(o)),
+            ..
+        }) => o,
+        _ => return,
+    };
+
+    if let SchemaKind::Type(Type::Object(o)) = &mut schema.schema_kind {
+        o.properties.extend(other.properties);
+        if required {
+            o.required.extend(other.required);
+        }
+    }
+}
+
+/// Disallows properties other than the ones in the given object schema.
+pub fn deny_unknown_fields(schema: &mut Schema) {
+    if let SchemaKind::Type(Type::Object(o)) = &mut schema.schema_kind {
+        o.additional_properties = Some(AdditionalProperties::Any(false));
+    }
+}
+
+/// Returns a schema which matches exactly one of the given schemas.
+pub fn one_of(description: &str, schemas: Vec<ReferenceOr<Schema>>) -> Schema {
+    Schema {
+        schema_data: SchemaData {
+            description: Some(description.to_owned()).filter(|d| !d.is_empty()),
+            ..Default::default()
+        },
+        schema_kind: SchemaKind::OneOf { one_of: schemas },
+    }
+}
+
+/// Returns a string schema with the given values (used for unit variants of enums).
+pub fn string_enum(values: &[&str]) -> ReferenceOr<Schema> {
+    ReferenceOr::Item(Schema {
+        schema_data: Default::default(),
+        schema_kind: SchemaKind::Type(Type::String(StringType {
+            enumeration: values
+                .iter()
+                .map(|v| serde_json::from_value(serde_json::json!(v)).unwrap_or_default())
+                .collect(),
+            ..Default::default()
+        })),
+    })
+}
+
+/// Returns a schema matching one of the given string values, each with its description
+/// (if it's not empty), which is how the values of enums are described in v3.
+pub fn described_enum(values: &[(&str, &str)]) -> Schema {
+    let values = values
+        .iter()
+        .map(|(value, description)| {
+            let mut schema = string_enum(&[*value]);
+            if let ReferenceOr::Item(s) = &mut schema {
+                s.schema_data.description =
+                    Some((*description).to_owned()).filter(|d| !d.is_empty());
+            }
+
+            schema
+        })
+        .collect();
+    one_of("", values)
+}
+
+/// Returns the schema of an externally tagged enum variant, which is an object
+/// with the name of the variant as its only property.
+pub fn tagged_variant(name: &str, schema: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    let mut variant = object("");
+    add_property(&mut variant, name, schema, "", true);
+    deny_unknown_fields(&mut variant);
+    ReferenceOr::Item(variant)
+}
+
+/// Makes the given schema nullable. References are wrapped in `allOf`, because
+/// siblings of `$ref` are ignored.
+pub fn nullable(schema: ReferenceOr<Schema>) -> ReferenceOr<Schema> {
+    let mut schema = match schema {
+        ReferenceOr::Item(s) => s,
+        r @ ReferenceOr::Reference { .. } => Schema {
+            schema_data: Default::default(),
+            schema_kind: SchemaKind::AllOf { all_of: vec![r] },
+        },
+    };
+
+    schema.schema_data.nullable = true;
+    ReferenceOr::Item(schema)
+}
+
+/// Copies the validation constraints (and the format of strings, if it's not set)
+/// of the given v2 schema to the schema. References are left as they are.
+pub fn constrain(schema: &mut ReferenceOr<Schema>, constraints: &DefaultSchemaRaw) {
+    let schema = match schema {
+        ReferenceOr::Item(s) => s,
+        ReferenceOr::Reference { .. } => return,
+    };
+
+    add_constraints(&mut schema.schema_kind, constraints);
+    if let SchemaKind::Type(Type::String(s)) = &mut schema.schema_kind {
+        if let (VariantOrUnknownOrEmpty::Empty, Some(f)) = (&s.format, &constraints.format) {
+            s.format = VariantOrUnknownOrEmpty::Unknown(f.to_string());
+        }
+    }
+}
+
+/// Boxes the given schema (for using it in properties and items).
+pub fn boxed(schema: ReferenceOr<Schema>) -> ReferenceOr<Box<Schema>> {
+    match schema {
+        ReferenceOr::Reference { reference } => ReferenceOr::Reference { reference },
+        ReferenceOr::Item(s) => ReferenceOr::Item(Box::new(s)),
+    }
+}
+
+/// Adds the given schemas to the components of the given spec, replacing the
+/// ones (with the same names) which have been converted from v2.
+pub fn extend_components(spec: &mut openapiv3::OpenAPI, schemas: SchemaMap) {
+    if schemas.is_empty() {
+        return;
+    }
+
+    spec.components
+        .get_or_insert_with(Default::default)
+        .schemas
+        .extend(schemas);
+}
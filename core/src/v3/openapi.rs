@@ -6,8 +6,25 @@ impl From<v2::DefaultApiRaw> for openapiv3::OpenAPI {
             openapi: "3.0.0".into(),
             tags: v2.tags.iter().cloned().map(From::from).collect(),
             info: v2.info.clone().into(),
-            servers: openapi3_server(v2.host, v2.base_path),
+            servers: if v2.servers.is_empty() {
+                openapi3_server(v2.host, v2.base_path)
+            } else {
+                // Both have the same representation.
+                serde_json::to_value(&v2.servers)
+                    .and_then(serde_json::from_value)
+                    .expect("converting servers")
+            },
             external_docs: v2.external_docs.map(From::from),
+            // Requirements refer to the schemes in the components by name.
+            security: v2
+                .security
+                .iter()
+                .map(|s| {
+                    s.iter()
+                        .map(|(k, v)| (k.clone(), v.iter().cloned().collect()))
+                        .collect()
+                })
+                .collect(),
             ..Default::default()
         };
 
@@ -41,6 +58,21 @@ impl From<v2::DefaultApiRaw> for openapiv3::OpenAPI {
             });
         spec.components = Some(components);
 
+        // `openapiv3` doesn't model OpenAPI 3.1, so webhooks are added as an extension
+        // (which gets flattened into the spec object).
+        if !v2.webhooks.is_empty() {
+            let webhooks = v2
+                .webhooks
+                .into_iter()
+                .map(|(name, item)| (name, openapiv3::PathItem::from(item)))
+                .collect::<indexmap::IndexMap<_, _>>();
+            spec.openapi = "3.1.0".into();
+            spec.extensions.insert(
+                "webhooks".into(),
+                serde_json::to_value(webhooks).expect("serializing webhooks"),
+            );
+        }
+
         spec
     }
 }
@@ -1,12 +1,16 @@
 use super::{v2, Either, OperationEitherResponse};
 
+const URL_ENCODED_FORM: &str = "application/x-www-form-urlencoded";
+const MULTIPART_FORM: &str = "multipart/form-data";
+
 impl From<v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>> for openapiv3::Operation {
     fn from(v2: v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>) -> Self {
         let mut request_body: Option<openapiv3::RequestBody> = None;
         let mut form_data: Option<openapiv3::AnySchema> = None;
+        let mut has_files = false;
         let v2v = v2.clone();
 
-        let parameters = v2
+        let mut parameters: Vec<_> = v2
             .parameters
             .iter()
             .filter_map(|p| match p {
@@ -23,22 +27,15 @@ impl From<v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>> for op
                                 None
                             }
                             Either::Right(Some(schema)) => {
-                                if let Some(any) = form_data.as_mut() {
-                                    any.properties.insert(
-                                        parameter.name.clone(),
-                                        openapiv3::ReferenceOr::Item(Box::new(schema)),
-                                    );
-                                } else {
-                                    let mut any = openapiv3::AnySchema::default();
-                                    if parameter.required {
-                                        any.required.push(parameter.name.clone());
-                                    }
-                                    any.properties.insert(
-                                        parameter.name.clone(),
-                                        openapiv3::ReferenceOr::Item(Box::new(schema)),
-                                    );
-                                    form_data = Some(any);
+                                let any = form_data.get_or_insert_with(Default::default);
+                                if parameter.required {
+                                    any.required.push(parameter.name.clone());
                                 }
+                                any.properties.insert(
+                                    parameter.name.clone(),
+                                    openapiv3::ReferenceOr::Item(Box::new(schema)),
+                                );
+                                has_files |= parameter.data_type == Some(v2::DataType::File);
                                 None
                             }
                             Either::Right(None) => None,
@@ -49,44 +46,96 @@ impl From<v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>> for op
             })
             .collect();
 
-        let request_body = if let Some(request_body) = request_body {
+        if v2.dynamic_query {
+            parameters.push(openapiv3::ReferenceOr::Item(dynamic_query_parameter()));
+        }
+
+        for cookie in &v2.cookies {
+            parameters.push(openapiv3::ReferenceOr::Item(cookie_parameter(cookie)));
+        }
+
+        let request_body = if let Some(mut request_body) = request_body {
+            // Bodies are sent in the media types consumed by the operation (other than
+            // the ones for forms), which is JSON by default.
+            let media_types: Vec<_> = v2
+                .consumes
+                .iter()
+                .flatten()
+                .map(|m| m.0.to_string())
+                .filter(|m| m != MULTIPART_FORM && m != URL_ENCODED_FORM)
+                .collect();
+            if !media_types.is_empty() {
+                if let Some((_, media)) = request_body.content.pop() {
+                    for mime in media_types {
+                        request_body.content.insert(mime, media.clone());
+                    }
+                }
+            }
+
             Some(openapiv3::ReferenceOr::Item(request_body))
         } else if let Some(form_data) = form_data {
-            let mut request_body = openapiv3::RequestBody::default();
-            match v2.consumes {
-                None => None,
-                Some(consumes) => {
-                    for media in consumes {
-                        request_body.content.insert(media.0.to_string(), {
-                            openapiv3::MediaType {
-                                schema: Some(openapiv3::ReferenceOr::Item(openapiv3::Schema {
-                                    schema_data: Default::default(),
-                                    schema_kind: openapiv3::SchemaKind::Any(form_data.clone()),
-                                })),
-                                example: None,
-                                examples: indexmap::IndexMap::new(),
-                                encoding: indexmap::IndexMap::new(),
-                            }
-                        });
-                    }
+            // Form parameters can only be sent as URL-encoded or multipart forms (and
+            // the latter is required for files).
+            let mut media_types: Vec<_> = v2
+                .consumes
+                .iter()
+                .flatten()
+                .map(|m| m.0.to_string())
+                .filter(|m| m == MULTIPART_FORM || (!has_files && m == URL_ENCODED_FORM))
+                .collect();
+            if media_types.is_empty() {
+                let default = if has_files {
+                    MULTIPART_FORM
+                } else {
+                    URL_ENCODED_FORM
+                };
+                media_types.push(default.into());
+            }
 
-                    Some(openapiv3::ReferenceOr::Item(request_body))
-                }
+            let mut request_body = openapiv3::RequestBody {
+                required: !form_data.required.is_empty(),
+                ..Default::default()
+            };
+            for media in media_types {
+                request_body.content.insert(media, {
+                    openapiv3::MediaType {
+                        schema: Some(openapiv3::ReferenceOr::Item(openapiv3::Schema {
+                            schema_data: Default::default(),
+                            schema_kind: openapiv3::SchemaKind::Any(form_data.clone()),
+                        })),
+                        example: None,
+                        examples: indexmap::IndexMap::new(),
+                        encoding: indexmap::IndexMap::new(),
+                    }
+                });
             }
+
+            Some(openapiv3::ReferenceOr::Item(request_body))
         } else {
             None
         };
 
+        let mut extensions: indexmap::IndexMap<_, _> = v2.extensions.clone().into_iter().collect();
+        if !v2.required_roles.is_empty() {
+            extensions.insert("x-required-roles".into(), v2.required_roles.clone().into());
+        }
+
         openapiv3::Operation {
             tags: v2.tags,
             summary: v2.summary,
             description: v2.description,
-            external_documentation: None,
+            external_documentation: v2.external_docs.map(From::from),
             operation_id: v2.operation_id,
             parameters,
             request_body,
             responses: openapiv3::Responses {
-                default: None,
+                default: v2.responses.get("default").map(|response| {
+                    OperationEitherResponse {
+                        operation: &v2v,
+                        response,
+                    }
+                    .into()
+                }),
                 responses: v2
                     .responses
                     .iter()
@@ -117,7 +166,60 @@ impl From<v2::Operation<v2::DefaultParameterRaw, v2::DefaultResponseRaw>> for op
                 })
                 .collect(),
             servers: vec![],
-            extensions: indexmap::IndexMap::new(),
+            extensions,
+        }
+    }
+}
+
+/// Cookie parameter for the cookie (in the `x-cookies` extension) of an operation.
+fn cookie_parameter(cookie: &v2::CookieParameter) -> openapiv3::Parameter {
+    let either: Either<
+        openapiv3::Parameter,
+        Either<openapiv3::RequestBody, Option<openapiv3::Schema>>,
+    > = cookie.to_parameter().into();
+    match either {
+        Either::Left(openapiv3::Parameter::Header { parameter_data, .. }) => {
+            openapiv3::Parameter::Cookie {
+                parameter_data,
+                style: openapiv3::CookieStyle::Form,
+            }
         }
+        _ => unreachable!("cookies are converted like header parameters"),
+    }
+}
+
+/// Free-form query object for operations accepting arbitrary (string) query parameters.
+fn dynamic_query_parameter() -> openapiv3::Parameter {
+    let value_schema = openapiv3::Schema {
+        schema_data: Default::default(),
+        schema_kind: openapiv3::SchemaKind::Type(openapiv3::Type::String(Default::default())),
+    };
+
+    openapiv3::Parameter::Query {
+        parameter_data: openapiv3::ParameterData {
+            name: "query".into(),
+            description: None,
+            required: false,
+            deprecated: None,
+            format: openapiv3::ParameterSchemaOrContent::Schema(openapiv3::ReferenceOr::Item(
+                openapiv3::Schema {
+                    schema_data: Default::default(),
+                    schema_kind: openapiv3::SchemaKind::Type(openapiv3::Type::Object(
+                        openapiv3::ObjectType {
+                            additional_properties: Some(openapiv3::AdditionalProperties::Schema(
+                                Box::new(openapiv3::ReferenceOr::Item(value_schema)),
+                            )),
+                            ..Default::default()
+                        },
+                    )),
+                },
+            )),
+            example: None,
+            examples: indexmap::IndexMap::new(),
+            extensions: indexmap::IndexMap::new(),
+        },
+        allow_reserved: false,
+        style: openapiv3::QueryStyle::Form,
+        allow_empty_value: None,
     }
 }
@@ -143,8 +143,23 @@ pub(crate) fn non_body_parameter_to_v3_parameter(
                     }))
                 }
                 v2::DataType::Object => {
-                    // objects comes from the parameter schema which would not trigger this call
-                    return None;
+                    // Objects are only allowed in query (as `deepObject`), where
+                    // they're free-form (i.e., their properties aren't documented).
+                    if form_data {
+                        return None;
+                    }
+
+                    openapiv3::SchemaKind::Type(openapiv3::Type::Object(openapiv3::ObjectType {
+                        additional_properties: Some(openapiv3::AdditionalProperties::Schema(
+                            Box::new(openapiv3::ReferenceOr::Item(openapiv3::Schema {
+                                schema_data: Default::default(),
+                                schema_kind: openapiv3::SchemaKind::Type(openapiv3::Type::String(
+                                    Default::default(),
+                                )),
+                            })),
+                        )),
+                        ..Default::default()
+                    }))
                 }
                 v2::DataType::File => {
                     if !form_data {
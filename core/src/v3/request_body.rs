@@ -23,21 +23,75 @@ impl From<v2::DefaultParameterRaw>
             extensions: indexmap::IndexMap::new(),
         };
 
+        // Array parameters are serialized as CSV by default in v2.
+        let collection_format = match v2.data_type {
+            Some(v2::DataType::Array) => {
+                Some(v2.collection_format.unwrap_or(v2::CollectionFormat::Csv))
+            }
+            _ => None,
+        };
+
         match v2.in_ {
-            v2::ParameterIn::Query => Either::Left(openapiv3::Parameter::Query {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                allow_reserved: false,
-                style: Default::default(),
-                allow_empty_value: None,
-            }),
-            v2::ParameterIn::Header => Either::Left(openapiv3::Parameter::Header {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                style: Default::default(),
-            }),
-            v2::ParameterIn::Path => Either::Left(openapiv3::Parameter::Path {
-                parameter_data: parameter_data(non_body_parameter_to_v3_parameter(false, &v2)),
-                style: Default::default(),
-            }),
+            v2::ParameterIn::Query => {
+                // `form` style is exploded by default in v3 (i.e., `multi`), and the
+                // delimited styles aren't. Objects are serialized as `deepObject`.
+                let (style, exact) = match collection_format {
+                    _ if v2.data_type == Some(v2::DataType::Object) => {
+                        (openapiv3::QueryStyle::DeepObject, true)
+                    }
+                    Some(v2::CollectionFormat::Ssv) => {
+                        (openapiv3::QueryStyle::SpaceDelimited, true)
+                    }
+                    Some(v2::CollectionFormat::Pipes) => {
+                        (openapiv3::QueryStyle::PipeDelimited, true)
+                    }
+                    Some(v2::CollectionFormat::Multi) | None => (openapiv3::QueryStyle::Form, true),
+                    Some(_) => (openapiv3::QueryStyle::Form, false),
+                };
+
+                let mut parameter_data =
+                    parameter_data(non_body_parameter_to_v3_parameter(false, &v2));
+                if !exact {
+                    preserve_collection_format(&mut parameter_data, collection_format);
+                }
+
+                Either::Left(openapiv3::Parameter::Query {
+                    parameter_data,
+                    allow_reserved: false,
+                    style,
+                    allow_empty_value: if v2.allow_empty_value {
+                        Some(true)
+                    } else {
+                        None
+                    },
+                })
+            }
+            v2::ParameterIn::Header => {
+                let mut parameter_data =
+                    parameter_data(non_body_parameter_to_v3_parameter(false, &v2));
+                // `simple` style (the only one for headers) is the same as `csv`.
+                if collection_format != Some(v2::CollectionFormat::Csv) {
+                    preserve_collection_format(&mut parameter_data, collection_format);
+                }
+
+                Either::Left(openapiv3::Parameter::Header {
+                    parameter_data,
+                    style: Default::default(),
+                })
+            }
+            v2::ParameterIn::Path => {
+                let mut parameter_data =
+                    parameter_data(non_body_parameter_to_v3_parameter(false, &v2));
+                // `simple` style (the default for paths) is the same as `csv`.
+                if collection_format != Some(v2::CollectionFormat::Csv) {
+                    preserve_collection_format(&mut parameter_data, collection_format);
+                }
+
+                Either::Left(openapiv3::Parameter::Path {
+                    parameter_data,
+                    style: Default::default(),
+                })
+            }
             v2::ParameterIn::FormData => {
                 Either::Right(Either::Right(non_body_parameter_to_v3_parameter(true, &v2)))
             }
@@ -60,3 +114,17 @@ impl From<v2::DefaultParameterRaw>
         }
     }
 }
+
+/// Keeps the v2 collection format (which can't be expressed through the style of
+/// the v3 parameter) in the `x-collection-format` extension.
+fn preserve_collection_format(
+    data: &mut openapiv3::ParameterData,
+    format: Option<v2::CollectionFormat>,
+) {
+    if let Some(format) = format {
+        data.extensions.insert(
+            "x-collection-format".into(),
+            serde_json::to_value(format).expect("serializing collection format"),
+        );
+    }
+}
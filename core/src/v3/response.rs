@@ -41,7 +41,16 @@ impl From<OperationResponse<'_>> for openapiv3::Response {
                         match v2.operation.produces.as_ref() {
                             Some(range) => {
                                 for mime in range {
-                                    map.insert(mime.0.to_string(), media.clone());
+                                    let mime = mime.0.to_string();
+                                    let mut media = match v2.response.content_schemas.get(&mime) {
+                                        Some(schema) => openapiv3::MediaType {
+                                            schema: Some(schema.clone().into()),
+                                            ..media.clone()
+                                        },
+                                        None => media.clone(),
+                                    };
+                                    media.example = v2.response.examples.get(&mime).cloned();
+                                    map.insert(mime, media);
                                 }
                             }
                             None => {
@@ -49,7 +58,9 @@ impl From<OperationResponse<'_>> for openapiv3::Response {
                                     // perhaps we should be conservative and use "*/*" instead?
                                     map.insert("multipart/form-data".to_string(), media);
                                 } else {
-                                    map.insert(v2::SpecFormat::Json.mime().0.to_string(), media);
+                                    let mime = v2::SpecFormat::Json.mime().0.to_string();
+                                    let example = v2.response.examples.get(&mime).cloned();
+                                    map.insert(mime, openapiv3::MediaType { example, ..media });
                                 }
                             }
                         }
@@ -59,11 +70,25 @@ impl From<OperationResponse<'_>> for openapiv3::Response {
                 }
             },
             extensions: indexmap::IndexMap::new(),
-            links: indexmap::IndexMap::new(),
+            links: v2
+                .response
+                .status_operation
+                .iter()
+                .map(|id| ("status".to_string(), status_link(id)))
+                .collect(),
         }
     }
 }
 
+/// Link to the operation for polling the status of a long-running operation.
+fn status_link(operation_id: &str) -> openapiv3::ReferenceOr<openapiv3::Link> {
+    let link = serde_json::json!({
+        "operationId": operation_id,
+        "description": "Status of the operation (at the URL in the `Location` header).",
+    });
+    openapiv3::ReferenceOr::Item(serde_json::from_value(link).expect("status link"))
+}
+
 pub(crate) struct OperationEitherResponse<'a> {
     pub(crate) operation: &'a v2::DefaultOperationRaw,
     pub(crate) response: &'a Either<v2::Reference, v2::DefaultResponseRaw>,
@@ -16,41 +16,55 @@ impl From<v2::DefaultSchemaRaw> for openapiv3::ReferenceOr<Box<openapiv3::Schema
 impl From<v2::DefaultSchemaRaw> for openapiv3::ReferenceOr<openapiv3::Schema> {
     fn from(v2: v2::DefaultSchemaRaw) -> Self {
         match v2.reference.clone() {
-            Some(reference) => v2::Reference { reference }.into(),
+            Some(reference) => v2::Reference {
+                reference: reference.into_owned(),
+            }
+            .into(),
             None => {
+                let mut schema_kind = if let Some(data_type) = v2.data_type {
+                    v2_data_type_to_v3(
+                        &data_type,
+                        &v2.format,
+                        &v2.enum_,
+                        &v2.items,
+                        &v2.properties,
+                        &v2.required,
+                    )
+                } else {
+                    openapiv3::SchemaKind::Type(openapiv3::Type::Object(
+                        openapiv3::ObjectType::default(),
+                    ))
+                };
+                add_constraints(&mut schema_kind, &v2);
+
+                // Described values of (string) enums are alternatives with descriptions.
+                if !v2.enum_descriptions.is_empty() {
+                    let values = v2
+                        .enum_
+                        .iter()
+                        .zip(&v2.enum_descriptions)
+                        .filter_map(|(v, d)| Some((v.as_str()?, d.as_str())))
+                        .collect::<Vec<_>>();
+                    schema_kind = super::native::described_enum(&values).schema_kind;
+                }
+
                 let item = openapiv3::Schema {
                     schema_data: openapiv3::SchemaData {
                         nullable: false,
                         read_only: false,
-                        write_only: false,
-                        deprecated: false,
+                        write_only: v2.sensitive,
+                        deprecated: v2.deprecated,
                         external_docs: None,
-                        example: if let Some(example) = v2.example {
-                            serde_json::from_str(&example).ok()
-                        } else {
-                            None
-                        },
+                        // Examples which aren't JSON (like `Felix` for strings) are strings.
+                        example: v2.example.map(|e| {
+                            serde_json::from_str(&e).unwrap_or(serde_json::Value::String(e))
+                        }),
                         title: v2.title,
                         description: v2.description,
                         discriminator: None,
                         default: None,
                     },
-                    schema_kind: {
-                        if let Some(data_type) = v2.data_type {
-                            v2_data_type_to_v3(
-                                &data_type,
-                                &v2.format,
-                                &v2.enum_,
-                                &v2.items,
-                                &v2.properties,
-                                &v2.required,
-                            )
-                        } else {
-                            openapiv3::SchemaKind::Type(openapiv3::Type::Object(
-                                openapiv3::ObjectType::default(),
-                            ))
-                        }
-                    },
+                    schema_kind,
                 };
                 openapiv3::ReferenceOr::Item(item)
             }
@@ -58,6 +72,42 @@ impl From<v2::DefaultSchemaRaw> for openapiv3::ReferenceOr<openapiv3::Schema> {
     }
 }
 
+/// Copies the validation constraints of the v2 schema to the (converted) v3 schema.
+pub(super) fn add_constraints(kind: &mut openapiv3::SchemaKind, v2: &v2::DefaultSchemaRaw) {
+    let t = match kind {
+        openapiv3::SchemaKind::Type(t) => t,
+        _ => return,
+    };
+
+    match t {
+        openapiv3::Type::Integer(i) => {
+            i.multiple_of = v2.multiple_of.map(|v| v as i64);
+            i.exclusive_minimum = v2.exclusive_minimum.unwrap_or_default();
+            i.exclusive_maximum = v2.exclusive_maximum.unwrap_or_default();
+            i.minimum = v2.minimum.map(|v| v as i64);
+            i.maximum = v2.maximum.map(|v| v as i64);
+        }
+        openapiv3::Type::Number(n) => {
+            n.multiple_of = v2.multiple_of.map(From::from);
+            n.exclusive_minimum = v2.exclusive_minimum.unwrap_or_default();
+            n.exclusive_maximum = v2.exclusive_maximum.unwrap_or_default();
+            n.minimum = v2.minimum.map(From::from);
+            n.maximum = v2.maximum.map(From::from);
+        }
+        openapiv3::Type::String(s) => {
+            s.pattern = v2.pattern.clone();
+            s.min_length = v2.min_length.map(|v| v as usize);
+            s.max_length = v2.max_length.map(|v| v as usize);
+        }
+        openapiv3::Type::Array(a) => {
+            a.min_items = v2.min_items.map(|v| v as usize);
+            a.max_items = v2.max_items.map(|v| v as usize);
+            a.unique_items = v2.unique_items.unwrap_or_default();
+        }
+        _ => (),
+    }
+}
+
 // helper function to convert a v2 DataType to v3, with explicit types making it more
 // rust-analyzer friendly as the DefaultSchemaRaw is autogenerated by a macro
 fn v2_data_type_to_v3(
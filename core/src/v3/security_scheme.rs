@@ -7,24 +7,24 @@ impl From<v2::SecurityScheme> for openapiv3::SecurityScheme {
                 scheme: "basic".to_string(),
                 bearer_format: None,
             },
-            "apiKey" => {
-                // how to determine when it should be JWT?
-                if v2.in_ == Some("header".into()) {
+            "apiKey" => match (v2.in_.as_deref(), v2.name.as_deref()) {
+                // Tokens in the authorization header are (most likely) bearer tokens.
+                (Some("header"), Some(name)) if name.eq_ignore_ascii_case("authorization") => {
                     openapiv3::SecurityScheme::HTTP {
                         scheme: "bearer".to_string(),
                         bearer_format: Some("JWT".into()),
                     }
-                } else {
-                    openapiv3::SecurityScheme::APIKey {
-                        location: match v2.in_.unwrap_or_default().as_str() {
-                            "query" => openapiv3::APIKeyLocation::Query,
-                            "header" => openapiv3::APIKeyLocation::Header,
-                            _ => openapiv3::APIKeyLocation::Query,
-                        },
-                        name: v2.name.unwrap_or_default(),
-                    }
                 }
-            }
+                (in_, name) => openapiv3::SecurityScheme::APIKey {
+                    location: match in_ {
+                        Some("header") => openapiv3::APIKeyLocation::Header,
+                        // Not allowed in v2, but used for documenting (session) cookies.
+                        Some("cookie") => openapiv3::APIKeyLocation::Cookie,
+                        _ => openapiv3::APIKeyLocation::Query,
+                    },
+                    name: name.unwrap_or_default().into(),
+                },
+            },
             "oauth2" => {
                 let scopes = v2
                     .scopes
@@ -33,10 +33,15 @@ impl From<v2::SecurityScheme> for openapiv3::SecurityScheme {
                         i.insert(k.clone(), v.clone());
                         i
                     });
-                let flow = v2.flow.unwrap_or_default();
+                // Accept the v3 names of flows as well.
+                let flow = match v2.flow.as_deref().unwrap_or_default() {
+                    "clientCredentials" => "application",
+                    "authorizationCode" => "accessCode",
+                    flow => flow,
+                };
                 openapiv3::SecurityScheme::OAuth2 {
                     flows: openapiv3::OAuth2Flows {
-                        implicit: match flow.as_str() {
+                        implicit: match flow {
                             "implicit" => Some(openapiv3::OAuth2Flow::Implicit {
                                 authorization_url: v2.auth_url.clone().unwrap_or_default(),
                                 refresh_url: None,
@@ -44,7 +49,7 @@ impl From<v2::SecurityScheme> for openapiv3::SecurityScheme {
                             }),
                             _ => None,
                         },
-                        password: match flow.as_str() {
+                        password: match flow {
                             "password" => Some(openapiv3::OAuth2Flow::Password {
                                 refresh_url: None,
                                 token_url: v2.token_url.clone().unwrap_or_default(),
@@ -52,7 +57,7 @@ impl From<v2::SecurityScheme> for openapiv3::SecurityScheme {
                             }),
                             _ => None,
                         },
-                        client_credentials: match flow.as_str() {
+                        client_credentials: match flow {
                             "application" => Some(openapiv3::OAuth2Flow::ClientCredentials {
                                 refresh_url: None,
                                 token_url: v2.token_url.clone().unwrap_or_default(),
@@ -60,7 +65,7 @@ impl From<v2::SecurityScheme> for openapiv3::SecurityScheme {
                             }),
                             _ => None,
                         },
-                        authorization_code: match flow.as_str() {
+                        authorization_code: match flow {
                             "accessCode" => Some(openapiv3::OAuth2Flow::AuthorizationCode {
                                 authorization_url: v2.auth_url.clone().unwrap_or_default(),
                                 token_url: v2.token_url.clone().unwrap_or_default(),
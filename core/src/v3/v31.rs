@@ -0,0 +1,144 @@
+//! Upgrading OpenAPI 3.0 documents to OpenAPI 3.1, whose schemas are
+//! JSON Schema (draft 2020-12) compliant.
+
+use serde_json::{Map, Value};
+
+/// Upgrades the given (JSON) OpenAPI 3.0 document to OpenAPI 3.1.
+pub(super) fn upgrade(doc: &mut Value) {
+    if let Some(map) = doc.as_object_mut() {
+        map.insert("openapi".into(), "3.1.0".into());
+    }
+
+    if let Some(schemas) = doc
+        .pointer_mut("/components/schemas")
+        .and_then(Value::as_object_mut)
+    {
+        schemas.values_mut().for_each(upgrade_schema);
+    }
+
+    // Webhooks (if any) are already in the document as an extension.
+    for key in &["paths", "webhooks", "components"] {
+        if let Some(value) = doc.get_mut(*key) {
+            upgrade_nested_schemas(value);
+        }
+    }
+}
+
+/// Upgrades the schemas (of parameters, media types and headers) in the given value.
+fn upgrade_nested_schemas(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match key.as_str() {
+                    "schema" => upgrade_schema(value),
+                    // Already handled (and may have properties named `schema`).
+                    "schemas" => (),
+                    _ => upgrade_nested_schemas(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(upgrade_nested_schemas),
+        _ => (),
+    }
+}
+
+/// Upgrades the given OpenAPI 3.0 schema (along with its subschemas) to a JSON schema.
+fn upgrade_schema(schema: &mut Value) {
+    let map = match schema.as_object_mut() {
+        Some(m) => m,
+        None => return,
+    };
+
+    if let Some(Value::Object(props)) = map.get_mut("properties") {
+        props.values_mut().for_each(upgrade_schema);
+    }
+
+    for key in &["items", "additionalProperties", "not"] {
+        if let Some(value) = map.get_mut(*key) {
+            upgrade_schema(value);
+        }
+    }
+
+    for key in &["allOf", "oneOf", "anyOf"] {
+        if let Some(Value::Array(values)) = map.get_mut(*key) {
+            values.iter_mut().for_each(upgrade_schema);
+        }
+    }
+
+    // Alternatives of single values (like the described values of enums) are constants.
+    if let Some(Value::Array(values)) = map.get_mut("oneOf") {
+        if values
+            .iter()
+            .all(|v| matches!(&v["enum"], Value::Array(e) if e.len() == 1))
+        {
+            for value in values.iter_mut().filter_map(Value::as_object_mut) {
+                if let Some(Value::Array(mut e)) = value.remove("enum") {
+                    value.insert("const".into(), e.remove(0));
+                }
+            }
+        }
+    }
+
+    // Exclusive bounds are numbers instead of flags.
+    for (flag, bound) in &[
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        match map.remove(*flag) {
+            Some(Value::Bool(true)) => {
+                if let Some(value) = map.remove(*bound) {
+                    map.insert((*flag).into(), value);
+                }
+            }
+            Some(v @ Value::Number(_)) => {
+                map.insert((*flag).into(), v);
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(example) = map.remove("example") {
+        map.entry("examples")
+            .or_insert_with(|| Value::Array(vec![example]));
+    }
+
+    if map.remove("nullable") == Some(Value::Bool(true)) {
+        make_nullable(map);
+    }
+}
+
+/// Allows `null` in the given schema, through its `type` (if any) or `anyOf`.
+fn make_nullable(map: &mut Map<String, Value>) {
+    if let Some(Value::Array(values)) = map.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+
+    match map.get_mut("type") {
+        Some(Value::String(t)) => {
+            let t = Value::String(t.clone());
+            map.insert("type".into(), Value::Array(vec![t, "null".into()]));
+        }
+        Some(Value::Array(types)) => {
+            if !types.contains(&"null".into()) {
+                types.push("null".into());
+            }
+        }
+        _ => {
+            // Schemas without types (like the `allOf` wrapping nullable references).
+            let schema = match map.remove("allOf") {
+                Some(Value::Array(mut all)) if all.len() == 1 && map.is_empty() => all.remove(0),
+                Some(all) => {
+                    let mut inner = std::mem::take(map);
+                    inner.insert("allOf".into(), all);
+                    Value::Object(inner)
+                }
+                None => Value::Object(std::mem::take(map)),
+            };
+
+            let null = serde_json::json!({ "type": "null" });
+            map.insert("anyOf".into(), Value::Array(vec![schema, null]));
+        }
+    }
+}
@@ -3,7 +3,6 @@
 
 use heck::*;
 use http::StatusCode;
-use lazy_static::lazy_static;
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use strum_macros::EnumString;
@@ -16,17 +15,9 @@ use syn::{
     ReturnType, Token, TraitBound, Type, TypeTraitObject,
 };
 
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Delimiter, TokenStream as TokenStream2, TokenTree};
 use std::collections::HashMap;
-
-const SCHEMA_MACRO_ATTR: &str = "openapi";
-
-lazy_static! {
-    static ref EMPTY_SCHEMA_HELP: String = format!(
-        "you can mark the struct with #[{}(empty)] to ignore this warning.",
-        SCHEMA_MACRO_ATTR
-    );
-}
+use syn::parse::{Parse, ParseStream, Parser};
 
 /// Actual parser and emitter for `api_v2_operation` macro.
 pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream {
@@ -39,16 +30,40 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
         }
     };
 
+    // Methods (and handlers referring to `Self`) get the unit struct (and its operation impl)
+    // defined inside their body, where `Self` can't be named. There, `Self` is replaced by
+    // a type parameter of the unit struct, which is then instantiated with `Self`.
+    let self_param = Ident::new("PaperclipSelf", default_span);
+    let has_receiver = item_ast
+        .sig
+        .inputs
+        .iter()
+        .any(|arg| matches!(arg, FnArg::Receiver(_)));
+    let uses_self = has_receiver || {
+        let (inputs, output) = (&item_ast.sig.inputs, &item_ast.sig.output);
+        mentions_ident(quote!(#inputs #output), "Self")
+    };
+    let returns_impl_trait = matches!(
+        &item_ast.sig.output,
+        ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_))
+    );
+
     // Unit struct
     let s_name = format!("paperclip_{}", item_ast.sig.ident);
     let unit_struct = Ident::new(&s_name, default_span);
-    let generics = &item_ast.sig.generics;
+    let mut generics = item_ast.sig.generics.clone();
+    let mut generics_params = extract_generics_params(&item_ast);
+    if uses_self {
+        generics.params.push(syn::parse_quote!(#self_param));
+        generics_params.push(self_param.clone());
+    }
+
     let mut generics_call = quote!();
     let mut struct_definition = quote!(struct #unit_struct;);
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = generics.split_for_impl();
     if !generics.params.is_empty() {
         let turbofish = ty_generics.as_turbofish();
-        let generics_params = extract_generics_params(&item_ast);
+        let turbofish = replace_ident(quote!(#turbofish), &self_param, &quote!(Self));
         generics_call = quote!(#turbofish { p: std::marker::PhantomData });
         struct_definition = quote!(struct #unit_struct #ty_generics { p: std::marker::PhantomData<(#generics_params)> } )
     }
@@ -58,7 +73,7 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
         item_ast.sig.asyncness = None;
     }
 
-    let mut wrapper = quote!(paperclip::actix::ResponseWrapper<actix_web::HttpResponse, #unit_struct #ty_generics>);
+    let mut future = quote!(paperclip::actix::ResponseWrapper<actix_web::HttpResponse, #unit_struct #ty_generics>);
     let mut is_impl_trait = false;
     let mut is_responder = false;
     match &mut item_ast.sig.output {
@@ -66,7 +81,7 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
             // Not particularly useful, but let's deal with it anyway
             *rt = ReturnType::Type(
                 Token![->](default_span),
-                Box::new(syn::parse2(wrapper.clone()).expect("parsing empty type")),
+                Box::new(syn::parse2(future.clone()).expect("parsing empty type")),
             );
         }
         ReturnType::Type(_, ty) => {
@@ -79,6 +94,9 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
                 // `impl Responder` is a special case because we have to add another wrapper.
                 // FIXME: Better way to deal with this?
                 is_responder = true;
+                future = quote!(
+                    std::future::Ready<paperclip::actix::ResponderWrapper<actix_web::HttpResponse>>
+                );
                 *ty = Box::new(
                     syn::parse2(quote!(
                         impl std::future::Future<Output=paperclip::actix::ResponderWrapper<#ty>>
@@ -94,9 +112,11 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
             }
 
             if let Type::ImplTrait(imp) = &**ty {
+                let mut bounds = imp.bounds.clone();
+                box_bounds(&mut bounds);
                 let obj = TypeTraitObject {
                     dyn_token: Some(Token![dyn](default_span)),
-                    bounds: imp.bounds.clone(),
+                    bounds,
                 };
                 *ty = Box::new(
                     syn::parse2(quote!(#ty + paperclip::v2::schema::Apiv2Operation))
@@ -105,38 +125,46 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
 
                 if !is_responder {
                     // NOTE: We're only using the box "type" to generate the operation data, we're not boxing
-                    // the handlers at runtime.
-                    wrapper = quote!(paperclip::actix::ResponseWrapper<Box<#obj + std::marker::Unpin>, #unit_struct #ty_generics>);
+                    // the handlers at runtime. The unit struct is left out, since its operation (which
+                    // needs the response type) would otherwise depend on itself.
+                    future = quote!(Box<#obj + std::marker::Unpin>);
                 }
             }
         }
     }
 
-    let block = item_ast.block;
-    // We need a function because devs should be able to use "return" keyword along the way.
-    let wrapped_fn_call = if is_responder {
-        quote!(paperclip::util::ready(paperclip::actix::ResponderWrapper((move || #block)())))
-    } else if is_impl_trait {
-        quote!((move || #block)())
-    } else {
-        quote!((move || async move #block)())
+    // Initialize operation parameters from macro attributes
+    let (attrs, extensions) = split_extensions(attrs.into());
+    let mut attrs = crate::parse_input_attrs(attrs.into());
+    let response_type = match extract_return_type(&mut attrs) {
+        Some(ty) => quote!(#ty),
+        None => quote!(<#future as std::future::Future>::Output),
     };
-
-    item_ast.block = Box::new(
-        syn::parse2(quote!(
-            {
-                let f = #wrapped_fn_call;
-                paperclip::actix::ResponseWrapper {
-                    0: f,
-                    1: #unit_struct #generics_call,
-                }
+    let body_limit = find_max_body_size(&attrs).map(|size| {
+        quote!(
+            fn max_body_size() -> Option<usize> {
+                Some(#size)
             }
-        ))
-        .expect("parsing wrapped block"),
-    );
-
-    // Initialize operation parameters from macro attributes
-    let (mut op_params, mut op_values) = parse_operation_attrs(attrs);
+        )
+    });
+    let response_headers = extract_response_headers(&mut attrs);
+    let parameters = extract_parameters(&mut attrs);
+    let bodies = extract_bodies(&mut attrs);
+    let response_example = extract_response_example(&mut attrs);
+    let skip = extract_operation_skip(&mut attrs);
+    let visibility = match extract_operation_cfg(&mut attrs) {
+        _ if skip => Some(quote!(false)),
+        Some(pred) => Some(quote!(cfg!(#pred))),
+        None => None,
+    }
+    .map(|visible| {
+        quote!(
+            fn is_visible() -> bool {
+                #visible
+            }
+        )
+    });
+    let (mut op_params, mut op_values) = parse_operation_attrs(attrs, extensions);
 
     // Optionally extract summary and description from doc comments
     if !op_params.iter().any(|i| *i == "summary") {
@@ -151,13 +179,50 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
         }
     }
 
-    let modifiers = extract_fn_arguments_types(&item_ast);
+    // The path of the handler is used for generating the operation ID (if needed).
+    let handler = item_ast
+        .sig
+        .ident
+        .to_string()
+        .trim_start_matches("r#")
+        .to_owned();
+
+    let mut modifiers = extract_fn_arguments_types(&item_ast);
+    let mut response_type = response_type;
+    if uses_self {
+        let self_ty = quote!(#self_param);
+        for ty in modifiers.iter_mut().chain(Some(&mut response_type)) {
+            *ty = replace_ident(ty.clone(), &Ident::new("Self", default_span), &self_ty);
+            if mentions_ident(ty.clone(), "PaperclipSelf") {
+                let ty = &*ty;
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote!(#ty: paperclip::actix::OperationModifier));
+            }
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let schemas_v3 = if cfg!(feature = "v3") {
+        quote!(
+            fn schemas_v3() -> paperclip::v3::native::SchemaMap {
+                use paperclip::actix::OperationModifier;
+                let mut map = std::collections::BTreeMap::new();
+                #(
+                    <#modifiers>::update_schemas_v3(&mut map);
+                )*
+                <#response_type>::update_schemas_v3(&mut map);
+                map
+            }
+        )
+    } else {
+        quote!()
+    };
 
-    quote!(
+    let operation_impl = quote!(
         #struct_definition
 
-        #item_ast
-
         impl #impl_generics paperclip::v2::schema::Apiv2Operation for #unit_struct #ty_generics #where_clause {
             fn operation() -> paperclip::v2::models::DefaultOperationRaw {
                 use paperclip::actix::OperationModifier;
@@ -171,7 +236,18 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
                     <#modifiers>::update_parameter(&mut op);
                     <#modifiers>::update_security(&mut op);
                 )*
-                <<#wrapper as std::future::Future>::Output>::update_response(&mut op);
+                #( #parameters )*
+                <#response_type>::update_response(&mut op);
+                #( #bodies )*
+                #( #response_headers )*
+                op.add_cache_headers();
+                if op.consumes.is_none() {
+                    op.consumes = paperclip::actix::infer_media_types(&[#(<#modifiers>::MEDIA_TYPE,)*]);
+                }
+                if op.produces.is_none() {
+                    op.produces = paperclip::actix::infer_media_types(&[<#response_type>::MEDIA_TYPE]);
+                }
+                #response_example
                 op
             }
 
@@ -191,10 +267,57 @@ pub fn emit_v2_operation(attrs: TokenStream, input: TokenStream) -> TokenStream
                 #(
                     <#modifiers>::update_definitions(&mut map);
                 )*
-                <<#wrapper as std::future::Future>::Output>::update_definitions(&mut map);
+                <#response_type>::update_definitions(&mut map);
                 map
             }
+
+            #schemas_v3
+
+            #visibility
+
+            #body_limit
+
+            fn handler() -> Option<&'static str> {
+                Some(concat!(module_path!(), "::", #handler))
+            }
         }
+    );
+
+    let (outer_impl, inner_impl) = if uses_self || returns_impl_trait {
+        (quote!(), operation_impl)
+    } else {
+        (operation_impl, quote!())
+    };
+
+    let block = item_ast.block;
+    // We need a function because devs should be able to use "return" keyword along the way.
+    let wrapped_fn_call = if is_responder {
+        quote!(paperclip::util::ready(paperclip::actix::ResponderWrapper((move || #block)())))
+    } else if is_impl_trait {
+        quote!((move || #block)())
+    } else {
+        quote!((move || async move #block)())
+    };
+
+    item_ast.block = Box::new(
+        syn::parse2(quote!(
+            {
+                #inner_impl
+
+                let f = #wrapped_fn_call;
+                paperclip::actix::ResponseWrapper {
+                    0: f,
+                    1: #unit_struct #generics_call,
+                }
+            }
+        ))
+        .expect("parsing wrapped block"),
+    );
+
+    quote!(
+        #outer_impl
+
+        #item_ast
     )
     .into()
 }
@@ -216,27 +339,488 @@ fn extract_generics_params(item_ast: &ItemFn) -> Punctuated<Ident, syn::token::C
         .collect()
 }
 
-/// Extract function arguments
-fn extract_fn_arguments_types(item_ast: &ItemFn) -> Vec<Type> {
+/// Extract function arguments (receivers taken by value are extractors of `Self`)
+fn extract_fn_arguments_types(item_ast: &ItemFn) -> Vec<TokenStream2> {
     item_ast
         .sig
         .inputs
         .iter()
         .filter_map(|inp| match inp {
+            FnArg::Receiver(r) if r.reference.is_none() => Some(quote!(Self)),
             FnArg::Receiver(_) => None,
-            FnArg::Typed(ref t) => Some(*t.ty.clone()),
+            FnArg::Typed(ref t) => {
+                let ty = &t.ty;
+                Some(quote!(#ty))
+            }
+        })
+        .collect()
+}
+
+/// Checks whether the given tokens contain the given identifier.
+fn mentions_ident(tokens: TokenStream2, ident: &str) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(i) => i == ident,
+        TokenTree::Group(g) => mentions_ident(g.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Replaces the given identifier (anywhere in the given tokens) with other tokens.
+fn replace_ident(tokens: TokenStream2, from: &Ident, to: &TokenStream2) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Ident(ref i) if i == from => to.clone(),
+            TokenTree::Group(g) => {
+                let mut group =
+                    proc_macro2::Group::new(g.delimiter(), replace_ident(g.stream(), from, to));
+                group.set_span(g.span());
+                TokenTree::Group(group).into()
+            }
+            tt => tt.into(),
         })
         .collect()
 }
 
+/// Replaces `impl Trait` types nested in the given type (say, in `Output = EventStream<impl Stream>`)
+/// with boxed trait objects, since the former are not allowed in paths.
+fn box_impl_traits(ty: &mut Type) {
+    match ty {
+        Type::ImplTrait(imp) => {
+            let mut bounds = imp.bounds.clone();
+            box_bounds(&mut bounds);
+            *ty = syn::parse2(quote!(Box<dyn #bounds + std::marker::Unpin>))
+                .expect("parsing boxed trait object");
+        }
+        Type::Path(p) => {
+            if let Some(q) = p.qself.as_mut() {
+                box_impl_traits(&mut q.ty);
+            }
+            box_path(&mut p.path);
+        }
+        Type::TraitObject(obj) => box_bounds(&mut obj.bounds),
+        Type::Reference(r) => box_impl_traits(&mut r.elem),
+        Type::Paren(p) => box_impl_traits(&mut p.elem),
+        Type::Group(g) => box_impl_traits(&mut g.elem),
+        Type::Slice(s) => box_impl_traits(&mut s.elem),
+        Type::Array(a) => box_impl_traits(&mut a.elem),
+        Type::Tuple(t) => t.elems.iter_mut().for_each(box_impl_traits),
+        _ => (),
+    }
+}
+
+fn box_bounds(bounds: &mut Punctuated<syn::TypeParamBound, Token![+]>) {
+    for bound in bounds.iter_mut() {
+        if let syn::TypeParamBound::Trait(t) = bound {
+            box_path(&mut t.path);
+        }
+    }
+}
+
+fn box_path(path: &mut Path) {
+    for segment in path.segments.iter_mut() {
+        match &mut segment.arguments {
+            PathArguments::AngleBracketed(args) => {
+                for arg in args.args.iter_mut() {
+                    match arg {
+                        syn::GenericArgument::Type(ty) => box_impl_traits(ty),
+                        syn::GenericArgument::Binding(b) => box_impl_traits(&mut b.ty),
+                        _ => (),
+                    }
+                }
+            }
+            PathArguments::Parenthesized(args) => {
+                args.inputs.iter_mut().for_each(box_impl_traits);
+                if let ReturnType::Type(_, ty) = &mut args.output {
+                    box_impl_traits(ty);
+                }
+            }
+            PathArguments::None => (),
+        }
+    }
+}
+
+/// Removes the first attribute with the given name (if any) from the list and returns it.
+fn take_operation_attr(attrs: &mut crate::MacroAttribute, name: &str) -> Option<NestedMeta> {
+    let mut taken = None;
+    attrs.0 = std::mem::take(&mut attrs.0)
+        .into_iter()
+        .filter_map(|attr| match &attr {
+            NestedMeta::Meta(meta) if taken.is_none() && meta.path().is_ident(name) => {
+                taken = Some(attr);
+                None
+            }
+            _ => Some(attr),
+        })
+        .collect();
+
+    taken
+}
+
+/// Removes the `return_type = "..."` attribute (if any) and parses its value.
+/// This declares the type used for documenting the response in cases where
+/// it can't be inferred from the signature (for example, `impl Responder`).
+fn extract_return_type(attrs: &mut crate::MacroAttribute) -> Option<Type> {
+    match take_operation_attr(attrs, "return_type")? {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(val), ..
+        })) => match val.parse::<Type>() {
+            Ok(ty) => Some(ty),
+            Err(err) => {
+                emit_error!(val.span(), "Invalid return type: {}", err);
+                None
+            }
+        },
+        attr => {
+            emit_error!(attr.span(), "Expected string literal: {:?}", attr);
+            None
+        }
+    }
+}
+
+/// Removes the `example = "..."` attribute (if any) and returns the statement for adding
+/// it to the successful responses of the operation. The example is usually JSON (and
+/// it's taken as a string otherwise).
+fn extract_response_example(attrs: &mut crate::MacroAttribute) -> Option<TokenStream2> {
+    match take_operation_attr(attrs, "example")? {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(val), ..
+        })) => Some(quote!(
+            op.add_response_example(
+                serde_json::from_str(#val).unwrap_or_else(|_| serde_json::Value::String(#val.into()))
+            );
+        )),
+        attr => {
+            emit_error!(attr.span(), "Expected string literal: {:?}", attr);
+            None
+        }
+    }
+}
+
+/// Removes the `cfg(...)` attribute (if any) and returns its predicate.
+fn extract_operation_cfg(attrs: &mut crate::MacroAttribute) -> Option<TokenStream2> {
+    match take_operation_attr(attrs, "cfg")? {
+        NestedMeta::Meta(Meta::List(MetaList { nested, .. })) if nested.len() == 1 => {
+            Some(quote!(#nested))
+        }
+        attr => {
+            emit_error!(attr.span(), "Expected a single cfg predicate: {:?}", attr);
+            None
+        }
+    }
+}
+
+/// Removes the `skip` attribute (if any), which leaves the operation (and its
+/// definitions) out of the spec while the handler is still mounted.
+fn extract_operation_skip(attrs: &mut crate::MacroAttribute) -> bool {
+    match take_operation_attr(attrs, "skip") {
+        Some(NestedMeta::Meta(Meta::Path(_))) => true,
+        Some(attr) => {
+            emit_error!(attr.span(), "Expected `skip` without a value: {:?}", attr);
+            false
+        }
+        None => false,
+    }
+}
+
+/// Removes the `response_header(...)` attributes (if any) and returns the statements
+/// for documenting those headers in the responses of the operation. Headers are added
+/// to the response with the given `code`, or to all the successful responses.
+fn extract_response_headers(attrs: &mut crate::MacroAttribute) -> Vec<TokenStream2> {
+    let mut headers = vec![];
+    while let Some(attr) = take_operation_attr(attrs, "response_header") {
+        let nested = match &attr {
+            NestedMeta::Meta(Meta::List(MetaList { nested, .. })) => nested,
+            _ => {
+                emit_error!(
+                    attr.span(),
+                    "Expected `response_header(name = \"...\", ...)`: {:?}",
+                    attr
+                );
+                continue;
+            }
+        };
+
+        let (mut name, mut code) = (None, quote!(None));
+        let mut fields = vec![];
+        for meta in nested {
+            let (key, lit) = match meta {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => {
+                    match path.get_ident() {
+                        Some(ident) => (ident.to_string(), lit),
+                        None => {
+                            emit_error!(path.span(), "Expected single identifier: {:?}", path);
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    emit_error!(meta.span(), "Expected `key = value`: {:?}", meta);
+                    continue;
+                }
+            };
+
+            match (key.as_str(), lit) {
+                ("name", Lit::Str(val)) => name = Some(val.value()),
+                ("code", Lit::Int(val)) => {
+                    let val = val.base10_digits();
+                    code = quote!(Some(#val));
+                }
+                ("description", Lit::Str(val)) => {
+                    fields.push(quote!(description: Some(#val.to_string())))
+                }
+                ("type", Lit::Str(val)) => match val.value().as_str() {
+                    "string" | "integer" | "number" | "boolean" | "array" => fields.push(
+                        quote!(data_type: serde_json::from_value(serde_json::json!(#val)).ok()),
+                    ),
+                    _ => emit_error!(
+                        val.span(),
+                        "Expected one of \"string\", \"integer\", \"number\", \"boolean\" or \"array\""
+                    ),
+                },
+                ("format", Lit::Str(val)) => fields
+                    .push(quote!(format: serde_json::from_value(serde_json::json!(#val)).ok())),
+                (key, lit) => emit_error!(
+                    lit.span(),
+                    "Unexpected value for `{}` in `response_header`: {:?}",
+                    key,
+                    lit
+                ),
+            }
+        }
+
+        // Headers are strings unless their type is given.
+        if !nested
+            .iter()
+            .any(|m| matches!(m, NestedMeta::Meta(m) if m.path().is_ident("type")))
+        {
+            fields.push(quote!(data_type: Some(paperclip::v2::models::DataType::String)));
+        }
+
+        match name {
+            Some(name) => headers.push(quote!(
+                op.add_response_header(#code, #name, paperclip::v2::models::Header {
+                    #( #fields, )*
+                    ..Default::default()
+                });
+            )),
+            None => emit_error!(attr.span(), "Expected `name` of the response header"),
+        }
+    }
+
+    headers
+}
+
+/// Removes the `params(...)` attributes (if any) and returns the statements for adding
+/// the declared parameters to the operation. This is for handlers whose parameters can't
+/// be inferred from their arguments (like the ones taking `HttpRequest` or `web::Payload`).
+/// Parameters are declared by their location (`query`, `path`, `header` or `form`), like
+/// `query("page", integer, required, format = "int32", description = "...")`. Types are
+/// `string` by default, and path parameters are always required.
+fn extract_parameters(attrs: &mut crate::MacroAttribute) -> Vec<TokenStream2> {
+    let mut params = vec![];
+    while let Some(attr) = take_operation_attr(attrs, "params") {
+        let nested = match &attr {
+            NestedMeta::Meta(Meta::List(MetaList { nested, .. })) => nested,
+            _ => {
+                emit_error!(
+                    attr.span(),
+                    "Expected `params(query(\"name\", type, ...), ...)`: {:?}",
+                    attr
+                );
+                continue;
+            }
+        };
+
+        for param in nested {
+            let (location, items) = match param {
+                NestedMeta::Meta(Meta::List(MetaList { path, nested, .. })) => {
+                    let location = match path.get_ident().map(|i| i.to_string()).as_deref() {
+                        Some("query") => "Query",
+                        Some("path") => "Path",
+                        Some("header") => "Header",
+                        Some("form") => "FormData",
+                        _ => {
+                            emit_error!(
+                                path.span(),
+                                "Expected one of `query`, `path`, `header` or `form`"
+                            );
+                            continue;
+                        }
+                    };
+
+                    (location, nested)
+                }
+                _ => {
+                    emit_error!(
+                        param.span(),
+                        "Expected `query(\"name\", type, ...)`: {:?}",
+                        param
+                    );
+                    continue;
+                }
+            };
+
+            let (mut name, mut required) = (None, location == "Path");
+            let mut data_type = quote!(String);
+            let mut fields = vec![];
+            for item in items {
+                match item {
+                    NestedMeta::Lit(Lit::Str(val)) if name.is_none() => name = Some(val.value()),
+                    NestedMeta::Meta(Meta::Path(path)) => {
+                        match path.get_ident().map(|i| i.to_string()).as_deref() {
+                            Some("required") => required = true,
+                            Some("string") => data_type = quote!(String),
+                            Some("integer") => data_type = quote!(Integer),
+                            Some("number") => data_type = quote!(Number),
+                            Some("boolean") => data_type = quote!(Boolean),
+                            Some("file") if location == "FormData" => data_type = quote!(File),
+                            _ => emit_error!(
+                                path.span(),
+                                "Expected `required` or one of `string`, `integer`, `number`, \
+                                 `boolean` or `file` (for `form` parameters)"
+                            ),
+                        }
+                    }
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Str(val),
+                        ..
+                    })) if path.is_ident("description") => {
+                        fields.push(quote!(description: Some(#val.to_string())))
+                    }
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Str(val),
+                        ..
+                    })) if path.is_ident("format") => fields
+                        .push(quote!(format: serde_json::from_value(serde_json::json!(#val)).ok())),
+                    _ => emit_error!(item.span(), "Unexpected item in parameter: {:?}", item),
+                }
+            }
+
+            let location = Ident::new(location, param.span());
+            match name {
+                Some(name) => params.push(quote!(
+                    op.parameters.push(paperclip::v2::models::Either::Right(
+                        paperclip::v2::models::DefaultParameterRaw {
+                            name: #name.into(),
+                            in_: paperclip::v2::models::ParameterIn::#location,
+                            required: #required,
+                            data_type: Some(paperclip::v2::models::DataType::#data_type),
+                            #( #fields, )*
+                            ..Default::default()
+                        },
+                    ));
+                )),
+                None => emit_error!(param.span(), "Expected the name of the parameter"),
+            }
+        }
+    }
+
+    params
+}
+
+/// Removes the `request_body(...)` and `response_body(...)` attributes (if any) and
+/// returns the statements for documenting the (raw) bodies of the operation in the given
+/// media types, like `request_body(media_type = "text/csv", description = "...")` or
+/// `response_body(media_type = "application/pdf", code = 200, description = "...")`.
+/// Responses are `200 OK` by default (with the reason phrase as their description).
+fn extract_bodies(attrs: &mut crate::MacroAttribute) -> Vec<TokenStream2> {
+    let mut bodies = vec![];
+    for kind in &["request_body", "response_body"] {
+        while let Some(attr) = take_operation_attr(attrs, kind) {
+            let nested = match &attr {
+                NestedMeta::Meta(Meta::List(MetaList { nested, .. })) => nested,
+                _ => {
+                    emit_error!(
+                        attr.span(),
+                        "Expected `{}(media_type = \"...\", ...)`: {:?}",
+                        kind,
+                        attr
+                    );
+                    continue;
+                }
+            };
+
+            let (mut media_type, mut code, mut description) = (None, 200, None);
+            for meta in nested {
+                match meta {
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Str(val),
+                        ..
+                    })) if path.is_ident("media_type") => match val.value().parse::<mime::Mime>() {
+                        Ok(_) => media_type = Some(val.value()),
+                        Err(err) => emit_error!(
+                            val.span(),
+                            "Value {} does not parse as mime type: {}",
+                            val.value(),
+                            err
+                        ),
+                    },
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Str(val),
+                        ..
+                    })) if path.is_ident("description") => description = Some(val.value()),
+                    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                        path,
+                        lit: Lit::Int(val),
+                        ..
+                    })) if path.is_ident("code") && *kind == "response_body" => {
+                        match val
+                            .base10_parse::<u16>()
+                            .ok()
+                            .and_then(|c| StatusCode::from_u16(c).ok())
+                        {
+                            Some(c) => code = c.as_u16(),
+                            None => emit_error!(val.span(), "Expected a valid status code"),
+                        }
+                    }
+                    _ => emit_error!(meta.span(), "Unexpected item in `{}`: {:?}", kind, meta),
+                }
+            }
+
+            let media_type = match media_type {
+                Some(m) => m,
+                None => {
+                    emit_error!(attr.span(), "Expected `media_type` of the body");
+                    continue;
+                }
+            };
+
+            if *kind == "request_body" {
+                let description = match description {
+                    Some(d) => quote!(Some(#d)),
+                    None => quote!(None),
+                };
+                bodies.push(quote!(op.set_request_body(#media_type, #description);));
+            } else {
+                let status = StatusCode::from_u16(code).expect("status code");
+                let description = description
+                    .unwrap_or_else(|| status.canonical_reason().unwrap_or_default().into());
+                let code = code.to_string();
+                bodies.push(quote!(op.set_response_body(#code, #media_type, #description);));
+            }
+        }
+    }
+
+    bodies
+}
+
 /// Parse macro attrs, matching to Operation fields
 /// Returning operation attribute identifier and value initialization arrays
 /// Note: Array likes initialized from string "val1, val2, val3", where "val1"
 /// would parse into destination item
-fn parse_operation_attrs(attrs: TokenStream) -> (Vec<Ident>, Vec<proc_macro2::TokenStream>) {
-    let attrs = crate::parse_input_attrs(attrs);
+fn parse_operation_attrs(
+    attrs: crate::MacroAttribute,
+    mut extensions: Vec<(String, proc_macro2::TokenStream)>,
+) -> (Vec<Ident>, Vec<proc_macro2::TokenStream>) {
     let mut params = Vec::new();
     let mut values = Vec::new();
+    let mut roles = Vec::new();
+    let mut cache = Vec::new();
     for attr in attrs.0 {
         if let NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) = &attr {
             if let Some(ident) = path.get_ident() {
@@ -249,6 +833,59 @@ fn parse_operation_attrs(attrs: TokenStream) -> (Vec<Ident>, Vec<proc_macro2::To
                             emit_error!(lit.span(), "Expected string literal: {:?}", lit)
                         }
                     }
+                    "required_role" => {
+                        if let Lit::Str(val) = lit {
+                            roles.push(val.value());
+                        } else {
+                            emit_error!(lit.span(), "Expected string literal: {:?}", lit)
+                        }
+                    }
+                    "cache_control" => {
+                        if let Lit::Str(val) = lit {
+                            cache.push(quote!("cacheControl": #val));
+                        } else {
+                            emit_error!(lit.span(), "Expected string literal: {:?}", lit)
+                        }
+                    }
+                    "etag" => {
+                        if let Lit::Bool(val) = lit {
+                            cache.push(quote!("etag": #val));
+                        } else {
+                            emit_error!(lit.span(), "Expected boolean literal: {:?}", lit)
+                        }
+                    }
+                    "max_body_size" => match parse_size(lit) {
+                        Some(size) => extensions.push((
+                            String::from("x-max-body-size"),
+                            quote!(serde_json::json!(#size)),
+                        )),
+                        None => emit_error!(
+                            lit.span(),
+                            "Expected size in bytes (or a string like \"1MB\"): {:?}",
+                            lit
+                        ),
+                    },
+                    "timeout" => match lit {
+                        Lit::Str(val) if is_duration(&val.value()) => extensions
+                            .push((String::from("x-timeout"), quote!(serde_json::json!(#val)))),
+                        _ => emit_error!(
+                            lit.span(),
+                            "Expected duration (a string like \"30s\" or \"500ms\"): {:?}",
+                            lit
+                        ),
+                    },
+                    "owner" => {
+                        if let Lit::Str(val) = lit {
+                            extensions
+                                .push((String::from("x-owner"), quote!(serde_json::json!(#val))));
+                        } else {
+                            emit_error!(lit.span(), "Expected string literal: {:?}", lit)
+                        }
+                    }
+                    "cost" => match extension_value(lit) {
+                        Some(value) => extensions.push((String::from("x-cost"), value)),
+                        None => emit_error!(lit.span(), "Expected string or number: {:?}", lit),
+                    },
                     "consumes" | "produces" => {
                         if let Lit::Str(mimes) = lit {
                             let mut mime_types = Vec::new();
@@ -292,6 +929,14 @@ fn parse_operation_attrs(attrs: TokenStream) -> (Vec<Ident>, Vec<proc_macro2::To
                     path
                 )
             }
+        } else if let NestedMeta::Meta(Meta::Path(path)) = &attr {
+            match path.get_ident().map(ToString::to_string).as_deref() {
+                Some("deprecated") => {
+                    params.push(Ident::new("deprecated", path.span()));
+                    values.push(quote!(true));
+                }
+                _ => emit_error!(path.span(), "Unknown attribute {:?}", path),
+            }
         } else if let NestedMeta::Meta(Meta::List(MetaList { path, nested, .. })) = &attr {
             if let Some(ident) = path.get_ident() {
                 match ident.to_string().as_str() {
@@ -315,6 +960,69 @@ fn parse_operation_attrs(attrs: TokenStream) -> (Vec<Ident>, Vec<proc_macro2::To
                             values.push(quote!(vec![ #( #tags.to_string() ),* ]));
                         }
                     }
+                    "external_docs" => {
+                        let (mut url, mut description) = (None, None);
+                        for meta in nested.pairs().map(|pair| pair.into_value()) {
+                            match meta {
+                                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                    path,
+                                    lit: Lit::Str(val),
+                                    ..
+                                })) if path.is_ident("url") => url = Some(val.clone()),
+                                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                    path,
+                                    lit: Lit::Str(val),
+                                    ..
+                                })) if path.is_ident("description") => {
+                                    description = Some(val.clone())
+                                }
+                                _ => emit_error!(
+                                    meta.span(),
+                                    "Expected `url = \"...\"` or `description = \"...\"`: {:?}",
+                                    meta
+                                ),
+                            }
+                        }
+
+                        match url {
+                            Some(url) => {
+                                let description = match description {
+                                    Some(d) => quote!(Some(#d)),
+                                    None => quote!(None),
+                                };
+                                params.push(ident.clone());
+                                values.push(quote!(Some(
+                                    paperclip::v2::models::ExternalDocs::new(#url, #description)
+                                )));
+                            }
+                            None => emit_error!(ident.span(), "Missing `url` for external_docs"),
+                        }
+                    }
+                    "extensions" => {
+                        for meta in nested.pairs().map(|pair| pair.into_value()) {
+                            let pair = match meta {
+                                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                    path,
+                                    lit,
+                                    ..
+                                })) => path
+                                    .get_ident()
+                                    .zip(extension_value(lit))
+                                    .map(|(i, v)| (i.to_string().replace('_', "-"), v)),
+                                _ => None,
+                            };
+                            match pair {
+                                Some((name, value)) if name.starts_with("x-") => {
+                                    extensions.push((name, value))
+                                }
+                                _ => emit_error!(
+                                    meta.span(),
+                                    "Expected extensions like `x_name = \"value\"`: {:?}",
+                                    meta
+                                ),
+                            }
+                        }
+                    }
                     x => emit_error!(ident.span(), "Unknown list ident {}", x),
                 }
             }
@@ -322,9 +1030,174 @@ fn parse_operation_attrs(attrs: TokenStream) -> (Vec<Ident>, Vec<proc_macro2::To
             emit_error!(attr.span(), "Not supported attribute type {:?}", attr)
         }
     }
+
+    // `required_role` can be repeated, so the roles are collected into a single field.
+    if !roles.is_empty() {
+        params.push(Ident::new("required_roles", proc_macro2::Span::call_site()));
+        values.push(quote!(vec![ #( #roles.to_string() ),* ]));
+    }
+
+    // Caching hints are collected into a single extension.
+    if !cache.is_empty() {
+        extensions.push((
+            String::from("x-cache"),
+            quote!(serde_json::json!({ #( #cache ),* })),
+        ));
+    }
+
+    if !extensions.is_empty() {
+        let (names, exts): (Vec<_>, Vec<_>) = extensions.into_iter().unzip();
+        params.push(Ident::new("extensions", proc_macro2::Span::call_site()));
+        values.push(quote!({
+            let mut tmp = std::collections::BTreeMap::new();
+            #(
+                tmp.insert(#names.to_string(), #exts);
+            )*
+            tmp
+        }));
+    }
+
     (params, values)
 }
 
+/// Returns the (valid) size from the `max_body_size = ...` attribute (if any).
+fn find_max_body_size(attrs: &crate::MacroAttribute) -> Option<usize> {
+    attrs.0.iter().find_map(|attr| match attr {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+            if path.is_ident("max_body_size") =>
+        {
+            parse_size(lit)
+        }
+        _ => None,
+    })
+}
+
+/// Parses a size in bytes from an integer or a string with an optional unit
+/// (`B`, `KB`, `MB` or `GB`, in multiples of 1024) such as `"1MB"`.
+fn parse_size(lit: &Lit) -> Option<usize> {
+    let value = match lit {
+        Lit::Int(i) => return i.base10_parse().ok(),
+        Lit::Str(s) => s.value().trim().to_ascii_uppercase(),
+        _ => return None,
+    };
+
+    let idx = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(idx);
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return None,
+    };
+
+    num.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+/// Returns whether the given string is a duration, i.e., a number with an optional
+/// unit (`ms`, `s`, `m` or `h`) such as `"30s"`.
+fn is_duration(value: &str) -> bool {
+    let value = value.trim();
+    let idx = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(idx);
+    !num.is_empty() && matches!(unit.trim(), "" | "ms" | "s" | "m" | "h")
+}
+
+/// Vendor extension in `extension("x-name" = value, ...)` attributes.
+struct ExtensionPair {
+    name: syn::LitStr,
+    value: Lit,
+}
+
+impl Parse for ExtensionPair {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(ExtensionPair {
+            name,
+            value: input.parse()?,
+        })
+    }
+}
+
+/// Splits the `extension("x-name" = value, ...)` items from the given attribute tokens,
+/// since their names (which aren't paths) can't be parsed as metas. Returns the rest of
+/// the tokens along with the names and (JSON) values of the extensions.
+fn split_extensions(tokens: TokenStream2) -> (TokenStream2, Vec<(String, TokenStream2)>) {
+    let mut rest = Vec::new();
+    let mut extensions = Vec::new();
+    let mut iter = tokens.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        let group = match (&tt, iter.peek()) {
+            (TokenTree::Ident(i), Some(TokenTree::Group(g)))
+                if i == "extension" && g.delimiter() == Delimiter::Parenthesis =>
+            {
+                g.clone()
+            }
+            _ => {
+                rest.push(tt);
+                continue;
+            }
+        };
+
+        iter.next();
+        if matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+            iter.next();
+        }
+
+        let pairs =
+            match Punctuated::<ExtensionPair, Token![,]>::parse_terminated.parse2(group.stream()) {
+                Ok(pairs) => pairs,
+                Err(e) => {
+                    emit_error!(e.span(), "Expected extensions like `\"x-name\" = value`");
+                    continue;
+                }
+            };
+
+        for pair in pairs {
+            let name = pair.name.value();
+            match extension_value(&pair.value) {
+                Some(value) if name.starts_with("x-") => extensions.push((name, value)),
+                Some(_) => emit_error!(pair.name.span(), "Extension names must start with `x-`"),
+                None => emit_error!(
+                    pair.value.span(),
+                    "Expected string, numeric or boolean value for extension"
+                ),
+            }
+        }
+    }
+
+    (rest.into_iter().collect(), extensions)
+}
+
+/// Returns the names and (JSON) values in the `#[openapi(extension("x-name" = value))]`
+/// attributes (if any).
+fn openapi_extensions(attrs: &[Attribute]) -> Vec<(String, TokenStream2)> {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("openapi"))
+        .filter_map(|a| match a.tokens.clone().into_iter().next() {
+            Some(TokenTree::Group(g)) => Some(split_extensions(g.stream()).1),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Returns the JSON value for the (string, numeric or boolean) value of an extension.
+fn extension_value(lit: &Lit) -> Option<proc_macro2::TokenStream> {
+    match lit {
+        Lit::Str(_) | Lit::Int(_) | Lit::Float(_) | Lit::Bool(_) => {
+            Some(quote!(serde_json::json!(#lit)))
+        }
+        _ => None,
+    }
+}
+
 /// Extracts summary from top line doc comment and description from the rest
 fn extract_fn_documentation(
     item_ast: &ItemFn,
@@ -501,8 +1374,8 @@ pub fn emit_v2_errors(attrs: TokenStream, input: TokenStream) -> TokenStream {
                 op.responses.insert(code.to_string(), paperclip::v2::models::Either::Right(paperclip::v2::models::Response {
                     description: Some(description.to_string()),
                     schema: Some(paperclip::v2::models::DefaultSchemaRaw {
-                        name: Some(schema.to_string()),
-                        reference: Some(format!("#/definitions/{}", schema)),
+                        name: Some(schema.to_string().into()),
+                        reference: Some(paperclip::v2::schema::definition_reference(schema)),
                         .. Default::default()
                     }),
                     ..Default::default()
@@ -670,6 +1543,10 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
         return empty;
     }
 
+    if let Some(external) = check_external_schema(&item_ast) {
+        return external;
+    }
+
     let docs = extract_documentation(&item_ast.attrs);
     let docs = docs.trim();
 
@@ -684,11 +1561,17 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
         param.bounds.push(bound.clone().into());
     });
 
-    let opt_impl = add_optional_impl(name, &generics);
+    let is_parameters = has_openapi_flag(&item_ast.attrs, "parameters");
+    let opt_impl = if is_parameters {
+        add_parameters_impl(name, &generics)
+    } else {
+        add_optional_impl(name, &generics)
+    };
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     // FIXME: Use attr path segments to find flattening, skipping, etc.
     let mut props_gen = quote! {};
+    let mut locations_gen = quote! {};
 
     match &item_ast.data {
         Data::Struct(ref s) => {
@@ -696,19 +1579,18 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
                 schema.data_type = Some(DataType::Object);
             ));
             match &s.fields {
-                Fields::Named(ref f) => {
-                    handle_field_struct(f, &item_ast.attrs, &props, &mut props_gen)
-                }
+                Fields::Named(ref f) => handle_field_struct(
+                    f,
+                    &item_ast.attrs,
+                    &props,
+                    &mut props_gen,
+                    &mut locations_gen,
+                ),
                 Fields::Unnamed(ref f) => {
                     handle_unnamed_field_struct(f, &item_ast.attrs, &mut props_gen)
                 }
-                Fields::Unit => {
-                    emit_warning!(
-                        s.struct_token.span().unwrap(),
-                        "unit structs do not have any fields and hence will have empty schema.";
-                        help = "{}", &*EMPTY_SCHEMA_HELP;
-                    );
-                }
+                // Unit structs (and marker types in general) get an empty object schema.
+                Fields::Unit => handle_unit_struct(&item_ast.attrs, &mut props_gen),
             }
         }
         Data::Enum(ref e) => handle_enum(e, &props, &mut props_gen),
@@ -718,8 +1600,40 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
         ),
     };
 
+    if is_parameters
+        && !matches!(&item_ast.data, Data::Struct(s) if matches!(s.fields, Fields::Named(_)))
+    {
+        emit_error!(
+            item_ast.span().unwrap(),
+            "only structs with named fields can be used as parameters"
+        );
+    }
+
+    let locations_fn = if locations_gen.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            fn parameter_locations() -> std::collections::BTreeMap<String, paperclip::v2::models::ParameterIn> {
+                use paperclip::v2::models::ParameterIn;
+
+                let mut locations = std::collections::BTreeMap::new();
+                #locations_gen
+                locations
+            }
+        }
+    };
+
+    let siblings = emit_sibling_structs(&item_ast);
+    let v3_fns = emit_v3_schema(&item_ast, &props);
     let schema_name = name.to_string();
     let props_gen_empty = props_gen.is_empty();
+    let owner = schema_owner(&item_ast.attrs).map(|owner| {
+        quote!(
+            schema.owner = Some(#owner.into());
+        )
+    });
+    let (ext_names, ext_values): (Vec<_>, Vec<_>) =
+        openapi_extensions(&item_ast.attrs).into_iter().unzip();
     let gen = quote! {
         impl #impl_generics paperclip::v2::schema::Apiv2Schema for #name #ty_generics #where_clause {
             const NAME: Option<&'static str> = Some(#schema_name);
@@ -741,11 +1655,21 @@ pub fn emit_v2_definition(input: TokenStream) -> TokenStream {
                 if !#props_gen_empty {
                     schema.name = Some(#schema_name.into());
                 }
+                #owner
+                #(
+                    schema.extensions.insert(#ext_names.into(), #ext_values);
+                )*
                 schema
             }
+
+            #locations_fn
+
+            #v3_fns
         }
 
         #opt_impl
+
+        #siblings
     };
 
     gen.into()
@@ -849,7 +1773,48 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
                             for nested in &list_attr.nested {
                                 match nested {
                                     NestedMeta::Lit(Lit::Str(value)) => {
-                                        scopes.push(value.value().to_string())
+                                        scopes.push((value.value(), value.value()))
+                                    }
+                                    // Scope with description, i.e., `scope(name = "...", description = "...")`.
+                                    NestedMeta::Meta(Meta::List(scope))
+                                        if scope.path.is_ident("scope") =>
+                                    {
+                                        let mut scope_name = None;
+                                        let mut description = None;
+                                        for nested in &scope.nested {
+                                            match nested {
+                                                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                                    path,
+                                                    lit: Lit::Str(value),
+                                                    ..
+                                                })) if path.is_ident("name") => {
+                                                    scope_name = Some(value.value())
+                                                }
+                                                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                                    path,
+                                                    lit: Lit::Str(value),
+                                                    ..
+                                                })) if path.is_ident("description") => {
+                                                    description = Some(value.value())
+                                                }
+                                                _ => emit_error!(
+                                                    nested.span().unwrap(),
+                                                    "Expected `name = \"...\"` or `description = \"...\"`"
+                                                ),
+                                            }
+                                        }
+
+                                        match scope_name {
+                                            Some(scope_name) => {
+                                                let description = description
+                                                    .unwrap_or_else(|| scope_name.clone());
+                                                scopes.push((scope_name, description))
+                                            }
+                                            None => emit_error!(
+                                                nested.span().unwrap(),
+                                                "Missing scope name"
+                                            ),
+                                        }
                                     }
                                     _ => emit_error!(
                                         nested.span().unwrap(),
@@ -877,14 +1842,15 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
         }
     }
 
-    let scopes_stream = scopes
-        .iter()
-        .fold(proc_macro2::TokenStream::new(), |mut stream, scope| {
+    let scopes_stream = scopes.iter().fold(
+        proc_macro2::TokenStream::new(),
+        |mut stream, (scope, description)| {
             stream.extend(quote! {
-                oauth2_scopes.insert(#scope.to_string(), #scope.to_string());
+                oauth2_scopes.insert(#scope.to_string(), #description.to_string());
             });
             stream
-        });
+        },
+    );
 
     let (security_def, security_def_name) = match (
         security_attrs.get("type"),
@@ -901,6 +1867,9 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
 
             (
                 Some(quote! {
+                    #[allow(unused_mut)]
+                    let mut oauth2_scopes = std::collections::BTreeMap::new();
+                    #scopes_stream
                     Some(paperclip::v2::models::SecurityScheme {
                         type_: #type_.to_string(),
                         name: #quoted_name,
@@ -908,7 +1877,7 @@ pub fn emit_v2_security(input: TokenStream) -> TokenStream {
                         flow: #quoted_flow,
                         auth_url: #quoted_auth_url,
                         token_url: #quoted_token_url,
-                        scopes: std::collections::BTreeMap::new(),
+                        scopes: oauth2_scopes,
                         description: #quoted_description,
                     })
                 }),
@@ -979,6 +1948,163 @@ fn add_optional_impl(name: &Ident, generics: &Generics) -> proc_macro2::TokenStr
     }
 }
 
+/// Implements `OperationModifier` for structs marked with `#[openapi(parameters)]`, so that
+/// their fields are documented as (query, by default) parameters when they're used as
+/// extractors. Their schema is still added to the definitions when they're used as bodies.
+fn add_parameters_impl(name: &Ident, generics: &Generics) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics paperclip::actix::OperationModifier for #name #ty_generics #where_clause {
+            fn update_parameter(op: &mut paperclip::v2::models::DefaultOperationRaw) {
+                paperclip::actix::add_struct_parameters::<Self>(
+                    op,
+                    paperclip::v2::models::ParameterIn::Query,
+                );
+            }
+
+            fn update_definitions(
+                _map: &mut std::collections::BTreeMap<String, paperclip::v2::models::DefaultSchemaRaw>,
+            ) {
+            }
+        }
+    }
+}
+
+/// Checks whether the bare flag (like `parameters`) is present in the `openapi` attributes.
+fn has_openapi_flag(attrs: &[Attribute], flag: &str) -> bool {
+    extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .any(|meta| matches!(meta, NestedMeta::Meta(Meta::Path(p)) if p.is_ident(flag)))
+}
+
+/// Returns the code for setting the constraints (from `garde` rules) on the schema `s`
+/// of the field with the given attributes (if there are any).
+#[cfg(feature = "garde")]
+fn field_constraints(attrs: &[Attribute]) -> Option<TokenStream2> {
+    crate::garde::constraints(attrs)
+}
+
+/// Returns the code for setting the constraints (from `garde` rules) on the schema `s`
+/// of the field with the given attributes (if there are any).
+#[cfg(not(feature = "garde"))]
+fn field_constraints(_: &[Attribute]) -> Option<TokenStream2> {
+    None
+}
+
+/// Returns the (external) schema in the `#[openapi(ref = "...")]` attribute (if any).
+fn external_reference(attrs: &[Attribute]) -> Option<String> {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("ref") => Some(nv.lit),
+            _ => None,
+        })?;
+
+    match &lit {
+        Lit::Str(s) if !s.value().starts_with('#') => Some(s.value()),
+        _ => {
+            emit_error!(
+                lit.span().unwrap(),
+                "expected string literal with the URL of an external schema"
+            );
+            None
+        }
+    }
+}
+
+/// Returns the owner in the `#[openapi(owner = "...")]` attribute (if any).
+fn schema_owner(attrs: &[Attribute]) -> Option<String> {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("owner") => Some(nv.lit),
+            _ => None,
+        })?;
+
+    match &lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => {
+            emit_error!(lit.span().unwrap(), "expected string literal for owner");
+            None
+        }
+    }
+}
+
+/// Returns the unit in the `#[openapi(unit = "...")]` attribute of the field (if any).
+fn field_unit(attrs: &[Attribute]) -> Option<String> {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("unit") => Some(nv.lit),
+            _ => None,
+        })?;
+
+    match &lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => {
+            emit_error!(lit.span().unwrap(), "expected string literal for unit");
+            None
+        }
+    }
+}
+
+/// Returns the example in the `#[openapi(example = ...)]` attribute of the field (if any),
+/// which is a string (usually JSON, like `"[1, 2]"`), a number or a boolean.
+fn field_example(attrs: &[Attribute]) -> Option<String> {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("example") => Some(nv.lit),
+            _ => None,
+        })?;
+
+    match &lit {
+        Lit::Str(s) => Some(s.value()),
+        Lit::Int(i) => Some(i.base10_digits().into()),
+        Lit::Float(f) => Some(f.base10_digits().into()),
+        Lit::Bool(b) => Some(b.value.to_string()),
+        _ => {
+            emit_error!(
+                lit.span().unwrap(),
+                "expected string, number or boolean literal for example"
+            );
+            None
+        }
+    }
+}
+
+/// Returns the parameter location in the `#[openapi(in = "...")]` attribute of the field (if any).
+fn parameter_location(attrs: &[Attribute]) -> Option<TokenStream2> {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("in") => Some(nv.lit),
+            _ => None,
+        })?;
+
+    let location = match &lit {
+        Lit::Str(s) => match s.value().as_str() {
+            "query" => quote!(ParameterIn::Query),
+            "header" => quote!(ParameterIn::Header),
+            "path" => quote!(ParameterIn::Path),
+            "formData" => quote!(ParameterIn::FormData),
+            _ => {
+                emit_error!(
+                    lit.span().unwrap(),
+                    "expected one of \"query\", \"header\", \"path\" or \"formData\""
+                );
+                return None;
+            }
+        },
+        _ => {
+            emit_error!(lit.span().unwrap(), "expected string literal for location");
+            return None;
+        }
+    };
+
+    Some(location)
+}
+
 fn get_field_type(field: &Field) -> Option<proc_macro2::TokenStream> {
     match field.ty {
         Type::Path(_) | Type::Reference(_) => Some(address_type_for_fn_call(&field.ty)),
@@ -992,6 +2118,32 @@ fn get_field_type(field: &Field) -> Option<proc_macro2::TokenStream> {
     }
 }
 
+/// Checks whether the field is a `PhantomData` marker, which doesn't
+/// show up in the serialized value and hence has to be skipped.
+fn is_phantom_data(field: &Field) -> bool {
+    match field.ty {
+        Type::Path(ref p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident == "PhantomData")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Generates code for a unit struct.
+fn handle_unit_struct(struct_attr: &[Attribute], props_gen: &mut proc_macro2::TokenStream) {
+    let docs = extract_documentation(struct_attr);
+    let docs = docs.trim();
+
+    props_gen.extend(quote!({
+        if !#docs.is_empty() {
+            schema.description = Some(#docs.to_string());
+        }
+    }));
+}
+
 /// Generates code for a tuple struct with fields.
 fn handle_unnamed_field_struct(
     fields: &FieldsUnnamed,
@@ -1005,7 +2157,7 @@ fn handle_unnamed_field_struct(
             let docs = extract_documentation(struct_attr);
             let docs = docs.trim();
 
-            if SerdeSkip::exists(&field.attrs) {
+            if SerdeSkip::exists(&field.attrs) || is_phantom_data(field) {
                 props_gen.extend(quote!({
                     let mut s: DefaultSchemaRaw = Default::default();
                     if !#docs.is_empty() {
@@ -1025,92 +2177,447 @@ fn handle_unnamed_field_struct(
         }
     } else {
         for (inner_field_id, field) in (&fields.unnamed).into_iter().enumerate() {
-            if SerdeSkip::exists(&field.attrs) {
+            if SerdeSkip::exists(&field.attrs) || is_phantom_data(field) {
                 continue;
             }
 
-            let ty_ref = get_field_type(field);
+            let ty_ref = get_field_type(field);
+
+            let docs = extract_documentation(&field.attrs);
+            let docs = docs.trim();
+
+            let mut gen = if !SerdeFlatten::exists(&field.attrs) {
+                // this is really not what we'd want to do because that's not how the
+                // deserialized struct will be like, ideally we want an actual tuple
+                // this type should therefore not be used for anything else than `Path`
+                quote!({
+                    let mut s = #ty_ref::raw_schema();
+                    if !#docs.is_empty() {
+                        s.description = Some(#docs.to_string());
+                    }
+                    schema.properties.insert(#inner_field_id.to_string(), s.into());
+                })
+            } else {
+                quote!({
+                    let s = #ty_ref::raw_schema();
+                    schema.properties.extend(s.properties);
+                })
+            };
+
+            gen.extend(quote! {
+                if #ty_ref::REQUIRED {
+                    schema.required.insert(#inner_field_id.to_string());
+                }
+            });
+
+            props_gen.extend(wrap_openapi_cfg(gen, &field.attrs));
+        }
+    }
+}
+
+/// Checks for `api_v2_empty` attributes and removes them.
+fn extract_openapi_attrs(
+    field_attrs: &'_ [Attribute],
+) -> impl Iterator<Item = Punctuated<syn::NestedMeta, syn::token::Comma>> + '_ {
+    field_attrs
+        .iter()
+        .filter(|a| a.path.is_ident("openapi"))
+        .filter_map(|a| match a.tokens.clone().into_iter().next() {
+            // Extensions are handled separately (see `openapi_extensions`).
+            Some(TokenTree::Group(g)) => {
+                let (rest, _) = split_extensions(g.stream());
+                Punctuated::parse_terminated.parse2(rest).ok()
+            }
+            _ => None,
+        })
+}
+
+/// Wraps the generated code for a field (or variant) so that it only takes effect
+/// when the predicate in its `#[openapi(cfg(...))]` attribute (if any) holds.
+fn wrap_openapi_cfg(gen: TokenStream2, attrs: &[Attribute]) -> TokenStream2 {
+    let pred = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("cfg") => Some(list.nested),
+            _ => None,
+        });
+
+    match pred {
+        Some(pred) => quote!(if cfg!(#pred) { #gen }),
+        None => gen,
+    }
+}
+
+/// Checks for `api_v2_empty` attributes and removes them.
+fn extract_documentation(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::NameValue(mnv)) if mnv.path.is_ident("doc") => match &mnv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Checks if an empty schema has been requested and generate if needed.
+fn check_empty_schema(item_ast: &DeriveInput) -> Option<TokenStream> {
+    let needs_empty_schema = extract_openapi_attrs(&item_ast.attrs).any(|nested| {
+        nested.len() == 1
+            && match &nested[0] {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("empty"),
+                _ => false,
+            }
+    });
+
+    if needs_empty_schema {
+        let name = &item_ast.ident;
+        let generics = item_ast.generics.clone();
+        let opt_impl = add_optional_impl(name, &generics);
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        return Some(quote!(
+            impl #impl_generics paperclip::v2::schema::Apiv2Schema for #name #ty_generics #where_clause {}
+
+            #opt_impl
+        ).into());
+    }
+
+    None
+}
+
+/// Checks if the type refers to an external schema (through `#[openapi(ref = "...")]`)
+/// and generates a schema with only that reference (and no definition) if needed.
+fn check_external_schema(item_ast: &DeriveInput) -> Option<TokenStream> {
+    let url = external_reference(&item_ast.attrs)?;
+    let name = &item_ast.ident;
+    let mut generics = item_ast.generics.clone();
+    let bound = syn::parse2::<TraitBound>(quote!(paperclip::v2::schema::Apiv2Schema))
+        .expect("expected to parse trait bound");
+    generics.type_params_mut().for_each(|param| {
+        param.bounds.push(bound.clone().into());
+    });
+
+    let opt_impl = add_optional_impl(name, &generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Some(
+        quote!(
+            impl #impl_generics paperclip::v2::schema::Apiv2Schema for #name #ty_generics #where_clause {
+                fn raw_schema() -> paperclip::v2::models::DefaultSchemaRaw {
+                    paperclip::v2::models::DefaultSchemaRaw {
+                        reference: Some(#url.into()),
+                        ..Default::default()
+                    }
+                }
+            }
+
+            #opt_impl
+        )
+        .into(),
+    )
+}
+
+/// Generates the sibling structs requested through the `#[openapi(...)]` attributes
+/// of the struct, along with their schemas:
+///
+/// - `omit(fields = "...", name = "...", derive = "...")` for structs without some
+///   of the fields (and conversions from the struct).
+/// - `generate_optional = "..."` for structs with all the fields wrapped in `Option`
+///   (and an `apply` method for updating the struct with the fields which are set).
+fn emit_sibling_structs(item_ast: &DeriveInput) -> TokenStream2 {
+    let mut gen = quote!();
+    for nested in extract_openapi_attrs(&item_ast.attrs).flat_map(|n| n.into_iter()) {
+        let span = nested.span().unwrap();
+        match nested {
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("omit") => {
+                if let Some(fields) = named_fields(item_ast, span) {
+                    gen.extend(emit_omitted_struct(item_ast, fields, &list));
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. }))
+                if path.is_ident("generate_optional") =>
+            {
+                let name = match lit {
+                    Lit::Str(s) => s.value(),
+                    _ => {
+                        emit_error!(span, "expected string literal with the name of the struct");
+                        continue;
+                    }
+                };
+
+                if let Some(fields) = named_fields(item_ast, span) {
+                    gen.extend(emit_optional_struct(item_ast, fields, &name));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    gen
+}
+
+/// Returns the named fields of the struct (or emits an error for other items).
+fn named_fields(item_ast: &DeriveInput, span: proc_macro::Span) -> Option<&FieldsNamed> {
+    if let Data::Struct(s) = &item_ast.data {
+        if let Fields::Named(f) = &s.fields {
+            return Some(f);
+        }
+    }
+
+    emit_error!(
+        span,
+        "only structs with named fields can generate sibling structs"
+    );
+    None
+}
+
+/// Generates a struct without the fields given in `omit(...)`.
+fn emit_omitted_struct(
+    item_ast: &DeriveInput,
+    fields: &FieldsNamed,
+    list: &MetaList,
+) -> TokenStream2 {
+    let mut omitted = vec![];
+    let mut name = None;
+    let mut derives = None;
+    for meta in &list.nested {
+        match meta {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(value),
+                ..
+            })) if path.is_ident("fields") => omitted.extend(
+                value
+                    .value()
+                    .split(',')
+                    .map(|f| f.trim().to_owned())
+                    .filter(|f| !f.is_empty()),
+            ),
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(value),
+                ..
+            })) if path.is_ident("name") => name = Some(value.value()),
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(value),
+                ..
+            })) if path.is_ident("derive") => derives = Some(value.clone()),
+            _ => emit_error!(
+                meta.span().unwrap(),
+                "expected `fields`, `name` or `derive` with a string literal"
+            ),
+        }
+    }
+
+    for field in &omitted {
+        if !fields
+            .named
+            .iter()
+            .any(|f| f.ident.as_ref().unwrap() == field)
+        {
+            emit_error!(list.span().unwrap(), "unknown field `{}`", field);
+        }
+    }
+
+    let name = name.unwrap_or_else(|| {
+        let suffix: String = omitted.iter().map(|f| f.to_camel_case()).collect();
+        format!("{}Without{}", item_ast.ident, suffix)
+    });
+    let kept = fields
+        .named
+        .iter()
+        .filter(|f| !omitted.contains(&f.ident.as_ref().unwrap().to_string()))
+        .cloned()
+        .collect();
+
+    let derives = sibling_derives(derives.as_ref());
+    let (sibling, schema) = sibling_struct(item_ast, &name, kept, &derives);
+    let kept: Vec<_> = sibling_fields(&sibling).map(|f| f.ident.clone()).collect();
+    let source = &item_ast.ident;
+    let ident = &sibling.ident;
+    let (impl_generics, ty_generics, where_clause) = item_ast.generics.split_for_impl();
+    quote! {
+        #[derive(#(#derives),*)]
+        #sibling
+
+        #schema
+
+        impl #impl_generics From<#source #ty_generics> for #ident #ty_generics #where_clause {
+            fn from(value: #source #ty_generics) -> Self {
+                #ident {
+                    #(#kept: value.#kept,)*
+                }
+            }
+        }
+    }
+}
+
+/// Generates a struct with all the fields wrapped in `Option` (unless they're already
+/// optional) for `generate_optional = "..."`.
+fn emit_optional_struct(item_ast: &DeriveInput, fields: &FieldsNamed, name: &str) -> TokenStream2 {
+    let derives = sibling_derives(None);
+    let mut updates = vec![];
+    let optional = fields
+        .named
+        .iter()
+        .map(|f| {
+            let mut field = f.clone();
+            let ident = &f.ident;
+            if is_option(&f.ty) {
+                updates.push(quote!(if self.#ident.is_some() {
+                    target.#ident = self.#ident;
+                }));
+            } else {
+                let ty = &f.ty;
+                field.ty = syn::parse_quote!(Option<#ty>);
+                updates.push(quote!(if let Some(value) = self.#ident {
+                    target.#ident = value;
+                }));
+            }
+
+            field
+                .attrs
+                .push(syn::parse_quote!(#[serde(skip_serializing_if = "Option::is_none")]));
+            field
+        })
+        .collect();
+
+    let (sibling, schema) = sibling_struct(item_ast, name, optional, &derives);
+    let source = &item_ast.ident;
+    let ident = &sibling.ident;
+    let vis = &item_ast.vis;
+    let (impl_generics, ty_generics, where_clause) = item_ast.generics.split_for_impl();
+    let doc = format!(
+        "Updates the given `{}` with the fields which have been set.",
+        source
+    );
+    quote! {
+        #[derive(#(#derives),*)]
+        #sibling
 
-            let docs = extract_documentation(&field.attrs);
-            let docs = docs.trim();
+        #schema
 
-            let mut gen = if !SerdeFlatten::exists(&field.attrs) {
-                // this is really not what we'd want to do because that's not how the
-                // deserialized struct will be like, ideally we want an actual tuple
-                // this type should therefore not be used for anything else than `Path`
-                quote!({
-                    let mut s = #ty_ref::raw_schema();
-                    if !#docs.is_empty() {
-                        s.description = Some(#docs.to_string());
-                    }
-                    schema.properties.insert(#inner_field_id.to_string(), s.into());
-                })
-            } else {
-                quote!({
-                    let s = #ty_ref::raw_schema();
-                    schema.properties.extend(s.properties);
-                })
-            };
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #[doc = #doc]
+            #vis fn apply(self, target: &mut #source #ty_generics) {
+                #(#updates)*
+            }
+        }
+    }
+}
 
-            gen.extend(quote! {
-                if #ty_ref::REQUIRED {
-                    schema.required.insert(#inner_field_id.to_string());
+/// Returns the traits in the given `derive = "..."` value (or `serde` traits by default).
+fn sibling_derives(derives: Option<&syn::LitStr>) -> Vec<Path> {
+    match derives {
+        Some(value) => value
+            .value()
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .filter_map(|d| match syn::parse_str(d) {
+                Ok(p) => Some(p),
+                Err(_) => {
+                    emit_error!(value.span().unwrap(), "invalid derive `{}`", d);
+                    None
                 }
-            });
+            })
+            .collect(),
+        None => vec![
+            syn::parse_quote!(serde::Serialize),
+            syn::parse_quote!(serde::Deserialize),
+        ],
+    }
+}
 
-            props_gen.extend(gen);
+/// Returns the sibling of the struct (with the given name and fields) along with its
+/// schema. The sibling only derives the given traits, so its `openapi` attributes are
+/// only used for generating the schema (and they're removed from the struct).
+fn sibling_struct(
+    item_ast: &DeriveInput,
+    name: &str,
+    fields: Punctuated<Field, Token![,]>,
+    derives: &[Path],
+) -> (DeriveInput, TokenStream2) {
+    let mut sibling = item_ast.clone();
+    sibling.ident = Ident::new(name, item_ast.ident.span());
+    sibling.attrs = item_ast
+        .attrs
+        .iter()
+        .filter(|a| !is_sibling_attr(a))
+        .cloned()
+        .collect();
+    if let Data::Struct(s) = &mut sibling.data {
+        if let Fields::Named(f) = &mut s.fields {
+            f.named = fields;
         }
     }
+
+    // `serde` attributes are only valid if some `serde` trait is derived.
+    let is_serde = derives.iter().any(|d| {
+        d.segments
+            .last()
+            .map(|s| s.ident == "Serialize" || s.ident == "Deserialize")
+            .unwrap_or(false)
+    });
+    let schema = emit_v2_definition(quote!(#sibling).into()).into();
+    strip_attrs(
+        &mut sibling,
+        if is_serde {
+            &["openapi"]
+        } else {
+            &["openapi", "serde"]
+        },
+    );
+    (sibling, schema)
 }
 
-/// Checks for `api_v2_empty` attributes and removes them.
-fn extract_openapi_attrs(
-    field_attrs: &'_ [Attribute],
-) -> impl Iterator<Item = Punctuated<syn::NestedMeta, syn::token::Comma>> + '_ {
-    field_attrs.iter().filter_map(|a| match a.parse_meta() {
-        Ok(Meta::List(list)) if list.path.is_ident("openapi") => Some(list.nested),
+/// Returns the fields of the (sibling) struct.
+fn sibling_fields(item: &DeriveInput) -> impl Iterator<Item = &Field> {
+    match &item.data {
+        Data::Struct(s) => Some(s.fields.iter()),
         _ => None,
-    })
+    }
+    .into_iter()
+    .flatten()
 }
 
-/// Checks for `api_v2_empty` attributes and removes them.
-fn extract_documentation(attrs: &[Attribute]) -> String {
-    attrs
-        .iter()
-        .filter_map(|a| match a.parse_meta() {
-            Ok(Meta::NameValue(mnv)) if mnv.path.is_ident("doc") => match &mnv.lit {
-                Lit::Str(s) => Some(s.value()),
-                _ => None,
-            },
-            _ => None,
+/// Checks whether the attribute is an `#[openapi(...)]` attribute for generating
+/// sibling structs.
+fn is_sibling_attr(attr: &Attribute) -> bool {
+    extract_openapi_attrs(std::slice::from_ref(attr))
+        .flat_map(|n| n.into_iter())
+        .any(|meta| match meta {
+            NestedMeta::Meta(Meta::List(l)) => l.path.is_ident("omit"),
+            NestedMeta::Meta(Meta::NameValue(nv)) => nv.path.is_ident("generate_optional"),
+            _ => false,
         })
-        .collect::<Vec<String>>()
-        .join("\n")
 }
 
-/// Checks if an empty schema has been requested and generate if needed.
-fn check_empty_schema(item_ast: &DeriveInput) -> Option<TokenStream> {
-    let needs_empty_schema = extract_openapi_attrs(&item_ast.attrs).any(|nested| {
-        nested.len() == 1
-            && match &nested[0] {
-                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("empty"),
-                _ => false,
-            }
-    });
-
-    if needs_empty_schema {
-        let name = &item_ast.ident;
-        let generics = item_ast.generics.clone();
-        let opt_impl = add_optional_impl(name, &generics);
-        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-        return Some(quote!(
-            impl #impl_generics paperclip::v2::schema::Apiv2Schema for #name #ty_generics #where_clause {}
-
-            #opt_impl
-        ).into());
+/// Checks whether the type is an `Option`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
     }
+}
 
-    None
+/// Removes the attributes (with the given names) of the struct and its fields.
+fn strip_attrs(item: &mut DeriveInput, names: &[&str]) {
+    let keep = |a: &Attribute| !names.iter().any(|n| a.path.is_ident(n));
+    item.attrs.retain(keep);
+    if let Data::Struct(s) = &mut item.data {
+        for field in s.fields.iter_mut() {
+            field.attrs.retain(keep);
+        }
+    }
 }
 
 /// Generates code for a struct with fields.
@@ -1119,6 +2626,7 @@ fn handle_field_struct(
     struct_attr: &[Attribute],
     serde: &SerdeProps,
     props_gen: &mut proc_macro2::TokenStream,
+    locations_gen: &mut proc_macro2::TokenStream,
 ) {
     let docs = extract_documentation(struct_attr);
     let docs = docs.trim();
@@ -1135,7 +2643,7 @@ fn handle_field_struct(
             .expect("missing field name?")
             .to_string();
 
-        if SerdeSkip::exists(&field.attrs) {
+        if SerdeSkip::exists(&field.attrs) || is_phantom_data(field) {
             continue;
         }
 
@@ -1150,28 +2658,89 @@ fn handle_field_struct(
         let docs = extract_documentation(&field.attrs);
         let docs = docs.trim();
 
-        let mut gen = if !SerdeFlatten::exists(&field.attrs) {
+        let gen = if !SerdeFlatten::exists(&field.attrs) {
+            if let Some(location) = parameter_location(&field.attrs) {
+                locations_gen.extend(wrap_openapi_cfg(
+                    quote!(locations.insert(#field_name.to_string(), #location);),
+                    &field.attrs,
+                ));
+            }
+
+            // Schemas of fields referring to external schemas only have the `$ref`.
+            let raw_schema = match external_reference(&field.attrs) {
+                Some(url) => quote!(DefaultSchemaRaw {
+                    reference: Some(#url.into()),
+                    ..Default::default()
+                }),
+                None => quote!(#ty_ref::raw_schema()),
+            };
+
+            let constraints = field_constraints(&field.attrs).map(|c| {
+                quote! {
+                    if s.reference.is_none() {
+                        #c
+                    }
+                }
+            });
+
+            let sensitive = if has_openapi_flag(&field.attrs, "sensitive") {
+                Some(quote!(s.sensitive = true;))
+            } else {
+                None
+            };
+            let deprecated = if has_openapi_flag(&field.attrs, "deprecated") {
+                Some(quote!(s.deprecated = true;))
+            } else {
+                None
+            };
+            let unit = field_unit(&field.attrs).map(|u| quote!(s.unit = Some(#u.into());));
+            let example = field_example(&field.attrs).map(|e| quote!(s.example = Some(#e.into());));
+            let (ext_names, ext_values): (Vec<_>, Vec<_>) =
+                openapi_extensions(&field.attrs).into_iter().unzip();
+
             quote!({
-                let mut s = #ty_ref::raw_schema();
-                if !#docs.is_empty() {
+                let mut s = #raw_schema;
+                if !#docs.is_empty() && s.reference.is_none() {
                     s.description = Some(#docs.to_string());
                 }
+                #constraints
+                #sensitive
+                #deprecated
+                #unit
+                #example
+                #(
+                    s.extensions.insert(#ext_names.into(), #ext_values);
+                )*
                 schema.properties.insert(#field_name.into(), s.into());
+
+                if #ty_ref::REQUIRED {
+                    schema.required.insert(#field_name.into());
+                }
             })
         } else {
+            locations_gen.extend(wrap_openapi_cfg(
+                quote!(locations.extend(#ty_ref::parameter_locations());),
+                &field.attrs,
+            ));
+
+            // Fields of flattened structs are required only if the struct itself is.
             quote!({
                 let s = #ty_ref::raw_schema();
                 schema.properties.extend(s.properties);
+
+                if #ty_ref::REQUIRED {
+                    schema.required.extend(s.required);
+                }
             })
         };
 
-        gen.extend(quote! {
-            if #ty_ref::REQUIRED {
-                schema.required.insert(#field_name.into());
-            }
-        });
+        props_gen.extend(wrap_openapi_cfg(gen, &field.attrs));
+    }
 
-        props_gen.extend(gen);
+    if serde.deny_unknown_fields {
+        props_gen.extend(quote!(
+            schema.extra_props = Some(paperclip::v2::models::Either::Left(false));
+        ));
     }
 }
 
@@ -1181,6 +2750,14 @@ fn handle_enum(e: &DataEnum, serde: &SerdeProps, props_gen: &mut proc_macro2::To
         schema.data_type = Some(DataType::String);
     ));
 
+    // Names and descriptions of the variants are documented only if any of them
+    // has a description.
+    let described = e
+        .variants
+        .iter()
+        .filter(|v| matches!(v.fields, Fields::Unit) && !SerdeSkip::exists(&v.attrs))
+        .any(|v| !variant_description(&v.attrs).is_empty());
+
     for var in &e.variants {
         let mut name = var.ident.to_string();
         match &var.fields {
@@ -1208,12 +2785,280 @@ fn handle_enum(e: &DataEnum, serde: &SerdeProps, props_gen: &mut proc_macro2::To
             name = prop.rename(&name);
         }
 
-        props_gen.extend(quote!(
-            schema.enum_.push(serde_json::json!(#name));
+        let described = if described {
+            let ident = var.ident.to_string();
+            let description = variant_description(&var.attrs);
+            Some(quote!(
+                schema.enum_names.push(#ident.into());
+                schema.enum_descriptions.push(#description.into());
+            ))
+        } else {
+            None
+        };
+
+        props_gen.extend(wrap_openapi_cfg(
+            quote!(
+                schema.enum_.push(serde_json::json!(#name));
+                #described
+            ),
+            &var.attrs,
         ));
     }
 }
 
+/// Returns the description of an enum variant from its `#[openapi(description = "...")]`
+/// attribute or its doc comments.
+fn variant_description(attrs: &[Attribute]) -> String {
+    let lit = extract_openapi_attrs(attrs)
+        .flat_map(|nested| nested.into_iter())
+        .find_map(|meta| match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("description") => {
+                Some(nv.lit)
+            }
+            _ => None,
+        });
+
+    match lit {
+        Some(Lit::Str(s)) => s.value(),
+        Some(lit) => {
+            emit_error!(
+                lit.span().unwrap(),
+                "expected string literal for description"
+            );
+            String::new()
+        }
+        None => extract_documentation(attrs).trim().to_owned(),
+    }
+}
+
+/// Generates the v3 schema functions of `Apiv2Schema` for structs with named fields
+/// and (externally tagged) enums with data variants, so that the v3 spec has their
+/// nullable fields and variants (as `oneOf`). Other types use the converted v2 schema.
+#[cfg(not(feature = "v3"))]
+fn emit_v3_schema(_: &DeriveInput, _: &SerdeProps) -> TokenStream2 {
+    quote!()
+}
+
+/// Generates the v3 schema functions of `Apiv2Schema` for structs with named fields
+/// and (externally tagged) enums with data variants, so that the v3 spec has their
+/// nullable fields and variants (as `oneOf`). Other types use the converted v2 schema.
+#[cfg(feature = "v3")]
+fn emit_v3_schema(item_ast: &DeriveInput, serde: &SerdeProps) -> TokenStream2 {
+    let docs = extract_documentation(&item_ast.attrs);
+    let docs = docs.trim();
+    let schema_name = item_ast.ident.to_string();
+
+    let mut props_gen = quote!();
+    let mut schemas_gen = quote!();
+    match &item_ast.data {
+        Data::Struct(s) => {
+            let fields = match &s.fields {
+                Fields::Named(f) => f,
+                _ => return quote!(),
+            };
+
+            handle_v3_fields(fields, serde.rename, &mut props_gen, &mut schemas_gen);
+            if serde.deny_unknown_fields {
+                props_gen.extend(quote!(native::deny_unknown_fields(&mut schema);));
+            }
+
+            props_gen = quote! {
+                let mut schema = native::object(#docs);
+                #props_gen
+            };
+        }
+        Data::Enum(e) => {
+            if serde.custom_tagging || e.variants.iter().all(|v| v.fields.is_empty()) {
+                return quote!();
+            }
+
+            handle_v3_enum(e, serde, &mut props_gen, &mut schemas_gen);
+            props_gen = quote! {
+                let mut values: Vec<&str> = vec![];
+                let mut variants = vec![];
+                #props_gen
+                if !values.is_empty() {
+                    variants.insert(0, native::string_enum(&values));
+                }
+
+                let schema = native::one_of(#docs, variants);
+            };
+        }
+        Data::Union(_) => return quote!(),
+    }
+
+    quote! {
+        #[allow(unused_mut)]
+        fn raw_schema_v3() -> paperclip::v3::openapiv3::ReferenceOr<paperclip::v3::openapiv3::Schema> {
+            use paperclip::v2::schema::Apiv2Schema;
+            use paperclip::v3::native;
+
+            #props_gen
+            paperclip::v3::openapiv3::ReferenceOr::Item(schema)
+        }
+
+        fn add_schemas_v3(map: &mut paperclip::v3::native::SchemaMap) {
+            use paperclip::v2::schema::Apiv2Schema;
+
+            if map.contains_key(#schema_name) {
+                return;
+            }
+
+            // Added before the schemas of fields, so that recursive types terminate.
+            map.insert(#schema_name.into(), Self::raw_schema_v3());
+            #schemas_gen
+        }
+    }
+}
+
+/// Generates code for adding the given fields to the (v3) object `schema`, and for
+/// adding the schemas of their types to the `map` of schemas.
+#[cfg(feature = "v3")]
+fn handle_v3_fields(
+    fields: &FieldsNamed,
+    rename: Option<SerdeRename>,
+    props_gen: &mut TokenStream2,
+    schemas_gen: &mut TokenStream2,
+) {
+    for field in &fields.named {
+        let mut field_name = field
+            .ident
+            .as_ref()
+            .expect("missing field name?")
+            .to_string();
+
+        if SerdeSkip::exists(&field.attrs) || is_phantom_data(field) {
+            continue;
+        }
+
+        if let Some(renamed) = SerdeRename::from_field_attrs(&field.attrs) {
+            field_name = renamed;
+        } else if let Some(prop) = rename {
+            field_name = prop.rename(&field_name);
+        }
+
+        // Unsupported types have already been reported for the v2 schema.
+        let ty_ref = match field.ty {
+            Type::Path(_) | Type::Reference(_) => address_type_for_fn_call(&field.ty),
+            _ => continue,
+        };
+
+        let docs = extract_documentation(&field.attrs);
+        let docs = docs.trim();
+
+        let gen = if SerdeFlatten::exists(&field.attrs) {
+            quote!(native::flatten(&mut schema, #ty_ref::raw_schema_v3(), #ty_ref::REQUIRED);)
+        } else {
+            let property = match (
+                external_reference(&field.attrs),
+                field_constraints(&field.attrs),
+            ) {
+                (Some(url), _) => quote!(paperclip::v3::openapiv3::ReferenceOr::ref_(#url)),
+                (None, Some(constraints)) => quote!({
+                    let mut property = #ty_ref::schema_with_ref_v3();
+                    let mut s = #ty_ref::raw_schema();
+                    #constraints
+                    native::constrain(&mut property, &s);
+                    property
+                }),
+                (None, None) => quote!(#ty_ref::schema_with_ref_v3()),
+            };
+
+            let property = match field_example(&field.attrs) {
+                Some(example) => quote!(native::example(#property, #example)),
+                None => property,
+            };
+
+            let property = if has_openapi_flag(&field.attrs, "sensitive") {
+                quote!(native::sensitive(#property))
+            } else {
+                property
+            };
+
+            let property = if has_openapi_flag(&field.attrs, "deprecated") {
+                quote!(native::deprecated(#property))
+            } else {
+                property
+            };
+
+            quote!(native::add_property(&mut schema, #field_name, #property, #docs, #ty_ref::REQUIRED);)
+        };
+
+        props_gen.extend(wrap_openapi_cfg(gen, &field.attrs));
+        if external_reference(&field.attrs).is_none() {
+            schemas_gen.extend(wrap_openapi_cfg(
+                quote!(#ty_ref::add_schemas_v3(map);),
+                &field.attrs,
+            ));
+        }
+    }
+}
+
+/// Generates code for adding the schemas of the variants of the given enum to the
+/// `variants` (for `oneOf`) and the names of its unit variants to the `values`.
+#[cfg(feature = "v3")]
+fn handle_v3_enum(
+    e: &DataEnum,
+    serde: &SerdeProps,
+    props_gen: &mut TokenStream2,
+    schemas_gen: &mut TokenStream2,
+) {
+    for var in &e.variants {
+        if SerdeSkip::exists(&var.attrs) {
+            continue;
+        }
+
+        let mut name = var.ident.to_string();
+        if let Some(renamed) = SerdeRename::from_field_attrs(&var.attrs) {
+            name = renamed;
+        } else if let Some(prop) = serde.rename {
+            name = prop.rename(&name);
+        }
+
+        let gen = match &var.fields {
+            Fields::Unit => quote!(values.push(#name);),
+            Fields::Named(f) => {
+                let mut fields_gen = quote!();
+                let mut fields_schemas_gen = quote!();
+                handle_v3_fields(f, None, &mut fields_gen, &mut fields_schemas_gen);
+                schemas_gen.extend(wrap_openapi_cfg(fields_schemas_gen, &var.attrs));
+
+                let docs = extract_documentation(&var.attrs);
+                let docs = docs.trim();
+                quote!({
+                    let mut schema = native::object(#docs);
+                    #fields_gen
+                    let schema = paperclip::v3::openapiv3::ReferenceOr::Item(schema);
+                    variants.push(native::tagged_variant(#name, schema));
+                })
+            }
+            Fields::Unnamed(f) => {
+                let types = f
+                    .unnamed
+                    .iter()
+                    .map(|f| address_type_for_fn_call(&f.ty))
+                    .collect::<Vec<_>>();
+                schemas_gen.extend(wrap_openapi_cfg(
+                    quote!(#(#types::add_schemas_v3(map);)*),
+                    &var.attrs,
+                ));
+
+                // Tuple variants are serialized as arrays.
+                let schema = if types.len() == 1 {
+                    quote!(#(#types::schema_with_ref_v3())*)
+                } else {
+                    quote!(native::array(paperclip::v3::openapiv3::ReferenceOr::Item(
+                        native::one_of("", vec![#(#types::schema_with_ref_v3()),*])
+                    )))
+                };
+                quote!(variants.push(native::tagged_variant(#name, #schema));)
+            }
+        };
+
+        props_gen.extend(wrap_openapi_cfg(gen, &var.attrs));
+    }
+}
+
 /// An associated function of a generic type, say, a vector cannot be called
 /// like `Vec::foo` as it doesn't have a default type. We should instead call
 /// `Vec::<T>::foo`. Something similar applies to `str`. This function takes
@@ -1358,6 +3203,9 @@ impl SerdeSkip {
 #[derive(Clone, Debug, Default)]
 struct SerdeProps {
     rename: Option<SerdeRename>,
+    deny_unknown_fields: bool,
+    /// Whether the enum isn't externally tagged (through `tag`, `content` or `untagged`).
+    custom_tagging: bool,
 }
 
 impl SerdeProps {
@@ -1380,6 +3228,22 @@ impl SerdeProps {
             };
 
             for meta in inner_meta {
+                if let NestedMeta::Meta(Meta::Path(path)) = meta {
+                    if path.is_ident("deny_unknown_fields") {
+                        props.deny_unknown_fields = true;
+                    } else if path.is_ident("untagged") {
+                        props.custom_tagging = true;
+                    }
+                    continue;
+                }
+
+                if let NestedMeta::Meta(Meta::NameValue(v)) = meta {
+                    if v.path.is_ident("tag") || v.path.is_ident("content") {
+                        props.custom_tagging = true;
+                        continue;
+                    }
+                }
+
                 let global_rename = match meta {
                     NestedMeta::Meta(Meta::NameValue(ref v))
                         if v.path
@@ -1465,6 +3329,26 @@ impl super::Method {
         let variant: proc_macro2::TokenStream = self.variant().parse()?;
         let handler_name_str = handler_name.to_string();
 
+        let registration = if cfg!(feature = "auto-routes") {
+            quote! {
+                paperclip::actix::inventory::submit! {
+                    paperclip::actix::AnnotatedRoute::new(#handler_name::resource)
+                }
+            }
+        } else {
+            quote!()
+        };
+
+        let schemas_v3_fn = if cfg!(feature = "v3") {
+            quote! {
+                fn schemas_v3(&mut self) -> paperclip::v3::native::SchemaMap {
+                    Self::resource().schemas_v3()
+                }
+            }
+        } else {
+            quote!()
+        };
+
         Ok(quote! {
             #[allow(non_camel_case_types, missing_docs)]
             pub struct #handler_name;
@@ -1499,6 +3383,12 @@ impl super::Method {
                     Self::resource().operations()
                 }
 
+                fn handlers(
+                    &mut self,
+                ) -> std::collections::BTreeMap<paperclip::v2::models::HttpMethod, &'static str> {
+                    Self::resource().handlers()
+                }
+
                 fn definitions(
                     &mut self,
                 ) -> std::collections::BTreeMap<
@@ -1514,7 +3404,11 @@ impl super::Method {
                 {
                     Self::resource().security_definitions()
                 }
+
+                #schemas_v3_fn
             }
+
+            #registration
         })
     }
 }
@@ -58,21 +58,35 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
         }
 
         impl #raw_struct_name {
-            /// Recursively removes all `$ref` values in this schema.
+            /// Recursively removes all local `$ref` values in this schema
+            /// (references to external schemas are retained, and so are the
+            /// bare references to schemas which aren't inlined, like recursive ones).
             pub fn remove_refs(&mut self) {
                 self.properties.values_mut().for_each(|s| s.remove_refs());
                 self.items.as_mut().map(|s| s.remove_refs());
                 self.extra_props.as_mut().and_then(|s| s.right_mut()).map(|s| s.remove_refs());
-                self.reference = None;
+                let is_bare = self.name.is_none()
+                    && self.data_type.is_none()
+                    && self.properties.is_empty()
+                    && self.items.is_none()
+                    && self.extra_props.is_none();
+                if !is_bare && matches!(&self.reference, Some(r) if r.starts_with('#')) {
+                    self.reference = None;
+                }
             }
 
             /// Recursively removes all properties other than `$ref` value
-            /// if the `$ref` is non-null.
+            /// (and the markers and extensions of the field) if the `$ref` is non-null.
             pub fn retain_ref(&mut self) {
                 if self.reference.is_some() {
                     let ref_ = self.reference.take();
+                    let (sensitive, deprecated) = (self.sensitive, self.deprecated);
+                    let extensions = std::mem::take(&mut self.extensions);
                     *self = Self::default();
                     self.reference = ref_;
+                    self.sensitive = sensitive;
+                    self.deprecated = deprecated;
+                    self.extensions = extensions;
                 } else {
                     self.properties.values_mut().for_each(|s| s.retain_ref());
                     self.items.as_mut().map(|s| s.retain_ref());
@@ -84,12 +98,12 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
         impl #impl_generics paperclip::v2::Schema for #name #ty_generics #where_clause {
             #[inline]
             fn name(&self) -> Option<&str> {
-                self.name.as_ref().map(String::as_str)
+                self.name.as_deref()
             }
 
             #[inline]
             fn set_name(&mut self, name: &str) {
-                self.name = Some(name.into());
+                self.name = Some(name.to_owned().into());
             }
 
             #[inline]
@@ -109,12 +123,12 @@ pub fn emit_v2_schema_struct(input: TokenStream) -> TokenStream {
 
             #[inline]
             fn reference(&self) -> Option<&str> {
-                self.reference.as_ref().map(String::as_str)
+                self.reference.as_deref()
             }
 
             #[inline]
             fn set_reference(&mut self, ref_: String) {
-                self.reference = Some(ref_);
+                self.reference = Some(ref_.into());
             }
 
             #[inline]
@@ -266,7 +280,7 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
 
     gen.extend(quote!(
         #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
-        pub reference: Option<String>,
+        pub reference: Option<std::borrow::Cow<'static, str>>,
     ));
     gen.extend(quote!(
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -308,6 +322,38 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
         pub enum_: Vec<serde_json::Value>,
     ));
 
+    gen.extend(quote!(
+        #[serde(default, rename = "x-enumNames", skip_serializing_if = "Vec::is_empty")]
+        pub enum_names: Vec<String>,
+        #[serde(default, rename = "x-enum-descriptions", skip_serializing_if = "Vec::is_empty")]
+        pub enum_descriptions: Vec<String>,
+    ));
+
+    gen.extend(quote!(
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub maximum: Option<f32>,
+        #[serde(rename = "exclusiveMaximum", skip_serializing_if = "Option::is_none")]
+        pub exclusive_maximum: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub minimum: Option<f32>,
+        #[serde(rename = "exclusiveMinimum", skip_serializing_if = "Option::is_none")]
+        pub exclusive_minimum: Option<bool>,
+        #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+        pub max_length: Option<u32>,
+        #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+        pub min_length: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub pattern: Option<String>,
+        #[serde(rename = "maxItems", skip_serializing_if = "Option::is_none")]
+        pub max_items: Option<u32>,
+        #[serde(rename = "minItems", skip_serializing_if = "Option::is_none")]
+        pub min_items: Option<u32>,
+        #[serde(rename = "uniqueItems", skip_serializing_if = "Option::is_none")]
+        pub unique_items: Option<bool>,
+        #[serde(rename = "multipleOf", skip_serializing_if = "Option::is_none")]
+        pub multiple_of: Option<f32>,
+    ));
+
     gen.extend(quote!(
         #[serde(rename = "additionalProperties", skip_serializing_if = "Option::is_none")]
         pub extra_props: Option<paperclip::v2::models::Either<bool,
@@ -320,6 +366,31 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
         pub required: std::collections::BTreeSet<String>,
     ));
 
+    gen.extend(quote!(
+        #[serde(rename = "x-sensitive", default, skip_serializing_if = "std::ops::Not::not")]
+        pub sensitive: bool,
+    ));
+
+    gen.extend(quote!(
+        #[serde(rename = "x-deprecated", default, skip_serializing_if = "std::ops::Not::not")]
+        pub deprecated: bool,
+    ));
+
+    gen.extend(quote!(
+        #[serde(rename = "x-owner", skip_serializing_if = "Option::is_none")]
+        pub owner: Option<String>,
+    ));
+
+    gen.extend(quote!(
+        #[serde(rename = "x-unit", skip_serializing_if = "Option::is_none")]
+        pub unit: Option<String>,
+    ));
+
+    gen.extend(quote!(
+        #[serde(flatten, deserialize_with = "paperclip::v2::models::deserialize_extensions")]
+        pub extensions: std::collections::BTreeMap<String, serde_json::Value>,
+    ));
+
     if is_ref {
         gen.extend(quote!(
             #[serde(skip)]
@@ -330,7 +401,7 @@ fn schema_fields(name: &Ident, is_ref: bool) -> proc_macro2::TokenStream {
     quote!({
         #[doc(hidden)]
         #[serde(skip)]
-        pub name: Option<String>,
+        pub name: Option<std::borrow::Cow<'static, str>>,
         #gen
     })
 }
@@ -0,0 +1,153 @@
+//! Support for reading the validation rules of the [garde](https://docs.rs/garde)
+//! crate, so that derived schemas have the matching constraints.
+
+use proc_macro2::TokenStream;
+use proc_macro_error::emit_warning;
+use quote::quote;
+use syn::{
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Expr, ExprLit, Ident, Lit, Token,
+};
+
+/// Validation rule, i.e., `name` or `name(args...)`.
+struct Rule {
+    name: Ident,
+    args: Vec<Arg>,
+}
+
+/// Argument of a rule, i.e., `name = expr` or `expr`.
+enum Arg {
+    Named(Ident, Expr),
+    Positional(Expr),
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.call(Ident::parse_any)?;
+        let mut args = vec![];
+        if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            args = Punctuated::<Arg, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect();
+        }
+
+        Ok(Rule { name, args })
+    }
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident::peek_any) && input.peek2(Token![=]) {
+            let name = input.call(Ident::parse_any)?;
+            input.parse::<Token![=]>()?;
+            return Ok(Arg::Named(name, input.parse()?));
+        }
+
+        Ok(Arg::Positional(input.parse()?))
+    }
+}
+
+impl Rule {
+    /// Returns the value of the named argument (if any).
+    fn arg(&self, name: &str) -> Option<&Expr> {
+        self.args.iter().find_map(|a| match a {
+            Arg::Named(n, e) if n == name => Some(e),
+            _ => None,
+        })
+    }
+}
+
+/// Returns the code for setting the constraints of the `garde` rules in the given
+/// (field) attributes on its (v2) schema `s`, if there are any such rules.
+///
+/// - `length(min = ..., max = ..., equal = ...)` sets `minLength`/`maxLength` (or
+///   `minItems`/`maxItems` for arrays).
+/// - `range(min = ..., max = ..., equal = ...)` sets `minimum`/`maximum`.
+/// - `pattern(...)` sets the `pattern` (for string literals and regexes).
+/// - `url` sets the `url` format.
+///
+/// Other rules don't affect the schema.
+pub fn constraints(attrs: &[Attribute]) -> Option<TokenStream> {
+    let mut gen = quote!();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("garde")) {
+        let rules = match attr.parse_args_with(Punctuated::<Rule, Token![,]>::parse_terminated) {
+            Ok(r) => r,
+            Err(e) => {
+                // Errors are reported by the `garde` derive itself.
+                emit_warning!(e.span(), "skipping unparseable garde rules in schema.");
+                continue;
+            }
+        };
+
+        for rule in rules {
+            gen.extend(rule_constraints(&rule));
+        }
+    }
+
+    if gen.is_empty() {
+        None
+    } else {
+        Some(gen)
+    }
+}
+
+/// Returns the code for setting the constraints of the given rule.
+fn rule_constraints(rule: &Rule) -> TokenStream {
+    let bounds = |min: &str, max: &str| {
+        let equal = rule.arg("equal");
+        (rule.arg(min).or(equal), rule.arg(max).or(equal))
+    };
+
+    match rule.name.to_string().as_str() {
+        "length" => {
+            let (min, max) = bounds("min", "max");
+            let min = min
+                .map(|e| quote!(Some((#e) as u32)))
+                .unwrap_or(quote!(None));
+            let max = max
+                .map(|e| quote!(Some((#e) as u32)))
+                .unwrap_or(quote!(None));
+            quote! {
+                if s.data_type == Some(paperclip::v2::models::DataType::Array) {
+                    s.min_items = #min.or(s.min_items);
+                    s.max_items = #max.or(s.max_items);
+                } else {
+                    s.min_length = #min.or(s.min_length);
+                    s.max_length = #max.or(s.max_length);
+                }
+            }
+        }
+        "range" => {
+            let (min, max) = bounds("min", "max");
+            let mut gen = quote!();
+            if let Some(e) = min {
+                gen.extend(quote!(s.minimum = Some((#e) as f32);));
+            }
+            if let Some(e) = max {
+                gen.extend(quote!(s.maximum = Some((#e) as f32);));
+            }
+            gen
+        }
+        "pattern" => match rule.args.first() {
+            Some(Arg::Positional(Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }))) => {
+                let pattern = s.value();
+                quote!(s.pattern = Some(#pattern.into());)
+            }
+            // Regexes (or lazily initialized ones).
+            Some(Arg::Positional(e)) => quote!(s.pattern = Some((#e).as_str().into());),
+            _ => {
+                emit_warning!(rule.name.span(), "skipping unsupported pattern in schema.");
+                quote!()
+            }
+        },
+        "url" => quote!(s.format = Some(paperclip::v2::models::DataTypeFormat::Url);),
+        _ => quote!(),
+    }
+}
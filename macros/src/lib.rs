@@ -14,6 +14,8 @@ extern crate proc_macro_error;
 mod actix;
 #[cfg(feature = "v2")]
 mod core;
+#[cfg(all(feature = "actix", feature = "garde"))]
+mod garde;
 
 use proc_macro::TokenStream;
 use quote::quote;
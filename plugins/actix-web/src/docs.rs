@@ -0,0 +1,189 @@
+//! Pages for browsing the spec with documentation viewers which are loaded
+//! from a CDN (or some other URL), so that they don't need to be bundled.
+
+use std::collections::BTreeMap;
+
+/// Options for the [RapiDoc](https://rapidocweb.com) page served by
+/// [`App::with_rapidoc_at`](../struct.App.html#method.with_rapidoc_at).
+#[derive(Clone, Debug)]
+pub struct RapiDoc {
+    title: String,
+    spec_url: Option<String>,
+    script_url: String,
+    attributes: BTreeMap<String, String>,
+}
+
+impl Default for RapiDoc {
+    fn default() -> Self {
+        RapiDoc {
+            title: "API documentation".into(),
+            spec_url: None,
+            script_url: "https://unpkg.com/rapidoc/dist/rapidoc-min.js".into(),
+            attributes: BTreeMap::new(),
+        }
+    }
+}
+
+impl RapiDoc {
+    /// Creates the default options, which point RapiDoc to the spec served
+    /// by `with_json_spec_at`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Title of the page.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// URL of the spec (instead of the path passed to `with_json_spec_at`).
+    pub fn spec_url(mut self, url: &str) -> Self {
+        self.spec_url = Some(url.into());
+        self
+    }
+
+    /// URL of the RapiDoc script (for hosting it elsewhere).
+    pub fn script_url(mut self, url: &str) -> Self {
+        self.script_url = url.into();
+        self
+    }
+
+    /// Theme of the page (`light` or `dark`).
+    pub fn theme(self, theme: &str) -> Self {
+        self.attribute("theme", theme)
+    }
+
+    /// Sets some [attribute](https://rapidocweb.com/api.html) of the `rapi-doc`
+    /// element (for example, `render-style` or `primary-color`).
+    pub fn attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the HTML of the page, using the given spec URL unless it's been overridden.
+    pub(crate) fn render(&self, spec_url: Option<&str>) -> String {
+        let spec_url = self.spec_url.as_deref().or(spec_url);
+        page(
+            &self.title,
+            "rapi-doc",
+            spec_url,
+            &self.attributes,
+            &format!(
+                "<script type=\"module\" src=\"{}\"></script>",
+                escape(&self.script_url)
+            ),
+        )
+    }
+}
+
+/// Options for the [ReDoc](https://github.com/Redocly/redoc) page served by
+/// [`App::with_redoc_at`](../struct.App.html#method.with_redoc_at).
+#[derive(Clone, Debug)]
+pub struct ReDoc {
+    title: String,
+    spec_url: Option<String>,
+    script_url: String,
+    attributes: BTreeMap<String, String>,
+}
+
+impl Default for ReDoc {
+    fn default() -> Self {
+        ReDoc {
+            title: "API documentation".into(),
+            spec_url: None,
+            script_url: "https://cdn.jsdelivr.net/npm/redoc/bundles/redoc.standalone.js".into(),
+            attributes: BTreeMap::new(),
+        }
+    }
+}
+
+impl ReDoc {
+    /// Creates the default options, which point ReDoc to the spec served
+    /// by `with_json_spec_at`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Title of the page.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// URL of the spec (instead of the path passed to `with_json_spec_at`).
+    pub fn spec_url(mut self, url: &str) -> Self {
+        self.spec_url = Some(url.into());
+        self
+    }
+
+    /// URL of the ReDoc (standalone) script (for hosting it elsewhere).
+    pub fn script_url(mut self, url: &str) -> Self {
+        self.script_url = url.into();
+        self
+    }
+
+    /// Sets some [option](https://github.com/Redocly/redoc#redoc-options-object)
+    /// of ReDoc as an attribute of the `redoc` element (for example,
+    /// `hide-download-button` or `expand-responses`).
+    pub fn attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the HTML of the page, using the given spec URL unless it's been overridden.
+    pub(crate) fn render(&self, spec_url: Option<&str>) -> String {
+        let spec_url = self.spec_url.as_deref().or(spec_url);
+        page(
+            &self.title,
+            "redoc",
+            spec_url,
+            &self.attributes,
+            &format!("<script src=\"{}\"></script>", escape(&self.script_url)),
+        )
+    }
+}
+
+/// Returns the HTML of a page with the given element (pointed at the spec) and script.
+fn page(
+    title: &str,
+    element: &str,
+    spec_url: Option<&str>,
+    attributes: &BTreeMap<String, String>,
+    script: &str,
+) -> String {
+    let spec_url = spec_url
+        .expect("Specification not set, be sure to call `with_json_spec_at` before this function");
+    let mut attrs = format!("spec-url=\"{}\"", escape(spec_url));
+    for (name, value) in attributes {
+        attrs.push_str(&format!(" {}=\"{}\"", escape(name), escape(value)));
+    }
+
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+</head>
+<body>
+<{element} {attrs}></{element}>
+{script}
+</body>
+</html>
+"#,
+        title = escape(title),
+        element = element,
+        attrs = attrs,
+        script = script
+    )
+}
+
+/// Escapes the given text for using it in HTML (content or quoted attributes).
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
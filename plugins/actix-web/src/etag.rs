@@ -0,0 +1,183 @@
+//! Middleware for conditional `GET` requests to the operations recorded in the spec
+//! (see [`App::with_conditional_get`](../struct.App.html#method.with_conditional_get)).
+
+use crate::validation::Operations;
+use actix_service::{Service, Transform};
+use actix_web::{
+    body::{to_bytes, EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::{
+        header::{self, HeaderValue},
+        Method, StatusCode,
+    },
+    web::Bytes,
+    Error,
+};
+use futures::future::{ok as fut_ok, LocalBoxFuture, Ready};
+use paperclip_core::v2::models::{
+    DataType, DefaultApiRaw, DefaultResponseRaw, Either, Header, HttpMethod,
+};
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use std::{rc::Rc, sync::Arc};
+
+/// Middleware (transform) adding weak `ETag`s (from the hashes of the canonicalized
+/// bodies) to the `200 OK` JSON responses of the documented `GET` operations, and
+/// responding with `304 Not Modified` to the requests whose `If-None-Match` header
+/// matches them. Responses which already have an `ETag` are left as they are.
+pub struct ConditionalGet {
+    spec: Arc<RwLock<DefaultApiRaw>>,
+}
+
+impl ConditionalGet {
+    /// Handles the `GET` operations of the given spec (which is read when the app
+    /// is started by the server, i.e., once it's been built).
+    pub fn new(spec: Arc<RwLock<DefaultApiRaw>>) -> Self {
+        ConditionalGet { spec }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalGet
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = ConditionalGetMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        fut_ok(ConditionalGetMiddleware {
+            service: Rc::new(service),
+            operations: Rc::new(Operations::from_spec(&self.spec.read())),
+        })
+    }
+}
+
+/// Service adding `ETag`s to the responses of the wrapped service.
+pub struct ConditionalGetMiddleware<S> {
+    service: Rc<S>,
+    operations: Rc<Operations>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalGetMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let documented = req.method() == Method::GET
+            && self.operations.find(HttpMethod::Get, req.path()).is_some();
+        let if_none_match = req
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            let is_json = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.contains("json"))
+                .unwrap_or(false);
+            if !documented
+                || !is_json
+                || res.status() != StatusCode::OK
+                || res.headers().contains_key(header::ETAG)
+            {
+                return Ok(res.map_into_left_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let (mut res, body) = res.into_parts();
+            let body = to_bytes(body).await.map_err(|e| {
+                let e: Box<dyn std::error::Error> = e.into();
+                ErrorInternalServerError(e.to_string())
+            })?;
+
+            let etag = match serde_json::from_slice::<Value>(&body) {
+                Ok(value) => weak_etag(&value),
+                Err(_) => {
+                    let res = ServiceResponse::new(req, res.set_body(body));
+                    return Ok(res.map_into_right_body());
+                }
+            };
+
+            res.headers_mut().insert(
+                header::ETAG,
+                HeaderValue::from_str(&etag).expect("hex-encoded etag"),
+            );
+            let body = match if_none_match {
+                Some(tags) if matches_etag(&tags, &etag) => {
+                    *res.status_mut() = StatusCode::NOT_MODIFIED;
+                    res.headers_mut().remove(header::CONTENT_TYPE);
+                    Bytes::new()
+                }
+                _ => body,
+            };
+
+            let res = ServiceResponse::new(req, res.set_body(body));
+            Ok(res.map_into_right_body())
+        })
+    }
+}
+
+/// Returns the weak `ETag` for the given (JSON) body, which is the hash of its
+/// canonicalized form (so that it doesn't depend on the order of the keys).
+fn weak_etag(body: &Value) -> String {
+    format!("W/\"{}\"", paperclip_core::integrity::fingerprint(body))
+}
+
+/// Checks whether the value of the `If-None-Match` header matches the given `ETag`
+/// (using the weak comparison, which ignores the `W/` prefixes).
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|t| t == "*" || t.trim_start_matches("W/") == etag)
+}
+
+/// Documents the `ETag` header in the `200 OK` responses of the `GET` operations
+/// in the given spec, along with their `304 Not Modified` responses.
+pub(crate) fn document_etags(api: &mut DefaultApiRaw) {
+    let etag = Header {
+        description: Some("Identifier for the version of the resource.".into()),
+        data_type: Some(DataType::String),
+        ..Default::default()
+    };
+
+    let ops = api
+        .paths
+        .values_mut()
+        .filter_map(|p| p.methods.get_mut(&HttpMethod::Get));
+    for op in ops {
+        if !op.responses.contains_key("200") {
+            continue;
+        }
+
+        op.responses.entry("304".into()).or_insert_with(|| {
+            let mut response = DefaultResponseRaw {
+                description: Some("Not Modified".into()),
+                ..Default::default()
+            };
+            response.headers.insert("ETag".into(), etag.clone());
+            Either::Right(response)
+        });
+        op.add_response_header(Some("200"), "ETag", etag.clone());
+    }
+}
@@ -12,47 +12,96 @@ use crate::web::Data;
 #[cfg(feature = "swagger-ui")]
 use include_dir::{include_dir, Dir};
 
+mod docs;
+pub mod etag;
+pub mod test;
+pub mod validation;
 #[cfg(feature = "actix4")]
 pub mod web;
 
+pub use self::docs::{RapiDoc, ReDoc};
 pub use self::web::{Resource, Route, Scope};
 pub use paperclip_macros::{
     api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, post, put, Apiv2Schema,
     Apiv2Security,
 };
 
+use self::etag::ConditionalGet;
+use self::validation::{MismatchAction, RequestValidation, ResponseValidation};
 use self::web::{RouteWrapper, ServiceConfig};
-use actix_service::ServiceFactory;
-#[cfg(feature = "swagger-ui")]
-use actix_web::web::HttpRequest;
+use actix_service::{Service, ServiceFactory};
 use actix_web::{
-    dev::{HttpServiceFactory, MessageBody, ServiceRequest, ServiceResponse, Transform},
-    Error, HttpResponse,
+    body::{EitherBody, MessageBody},
+    dev::{Handler, HttpServiceFactory, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web::Bytes,
+    Error, HttpRequest, HttpResponse,
 };
-use futures::future::{ok as fut_ok, Ready};
-use paperclip_core::v2::models::{
-    DefaultApiRaw, DefaultOperationRaw, DefaultPathItemRaw, DefaultSchemaRaw, HttpMethod,
-    SecurityScheme,
+use futures::future::{ok as fut_ok, ready, Ready};
+#[cfg(feature = "v3")]
+use paperclip_core::v3::native::SchemaMap;
+use paperclip_core::{
+    routes::OperationUrls,
+    v2::{
+        builder::OperationBuilder,
+        models::{
+            DefaultApiRaw, DefaultOperationRaw, DefaultPathItemRaw, DefaultResponseRaw,
+            DefaultSchemaRaw, Either, HttpMethod, OperationIdStrategy, Reference, SecurityScheme,
+            SpecFormat, Tag,
+        },
+        schema::Apiv2Errors,
+    },
 };
 use parking_lot::RwLock;
 
-use std::{collections::BTreeMap, fmt::Debug, future::Future, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    future::Future,
+    io::Write,
+    mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 /// Wrapper for [`actix_web::App`](https://docs.rs/actix-web/*/actix_web/struct.App.html).
-pub struct App<T, B> {
+pub struct App<T> {
     spec: Arc<RwLock<DefaultApiRaw>>,
+    spec_output: Arc<RwLock<SpecOutput>>,
     #[cfg(feature = "v3")]
     spec_v3: Option<Arc<RwLock<openapiv3::OpenAPI>>>,
-    #[cfg(feature = "swagger-ui")]
+    /// Schemas emitted natively for the v3 specification.
+    #[cfg(feature = "v3")]
+    schemas_v3: SchemaMap,
     spec_path: Option<String>,
-    inner: Option<actix_web::App<T, B>>,
+    /// Files to which the spec is written when the app is built.
+    spec_files: Vec<PathBuf>,
+    /// Configures the resources serving the spec and documentation pages.
+    spec_resource: Option<Rc<dyn Fn(actix_web::Resource) -> actix_web::Resource>>,
+    operation_urls: bool,
+    /// Strategy for generating the missing operation IDs when the app is built.
+    operation_ids: Option<OperationIdStrategy>,
+    /// Paths of the handlers of the operations in the spec (for generating their IDs).
+    handler_map: BTreeMap<String, BTreeMap<HttpMethod, &'static str>>,
+    /// Whether the `GET` operations are documented with `ETag`s (for conditional requests).
+    conditional_get: bool,
+    /// Whether parameters are coerced to their documented types by request validation
+    /// (shared with its middleware, which reads it when the app is started).
+    coerce_parameters: Arc<AtomicBool>,
+    /// Responses attached to all operations (unless they document the same status codes).
+    default_responses: BTreeMap<String, Either<Reference, DefaultResponseRaw>>,
+    inner: Option<actix_web::App<T>>,
 }
 
 #[cfg(feature = "swagger-ui")]
 static SWAGGER_DIST: Dir = include_dir!("./swagger-ui/dist");
 
 /// Extension trait for actix-web applications.
-pub trait OpenApiExt<T, B> {
+pub trait OpenApiExt<T> {
     type Wrapper;
 
     /// Consumes this app and produces its wrapper to start tracking
@@ -65,16 +114,26 @@ pub trait OpenApiExt<T, B> {
     fn wrap_api_with_spec(self, spec: DefaultApiRaw) -> Self::Wrapper;
 }
 
-impl<T, B> OpenApiExt<T, B> for actix_web::App<T, B> {
-    type Wrapper = App<T, B>;
+impl<T> OpenApiExt<T> for actix_web::App<T> {
+    type Wrapper = App<T>;
 
     fn wrap_api(self) -> Self::Wrapper {
         App {
             spec: Arc::new(RwLock::new(DefaultApiRaw::default())),
+            spec_output: Default::default(),
             #[cfg(feature = "v3")]
             spec_v3: None,
-            #[cfg(feature = "swagger-ui")]
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             spec_path: None,
+            spec_files: vec![],
+            spec_resource: None,
+            operation_urls: false,
+            operation_ids: None,
+            handler_map: BTreeMap::new(),
+            conditional_get: false,
+            coerce_parameters: Default::default(),
+            default_responses: BTreeMap::new(),
             inner: Some(self),
         }
     }
@@ -82,10 +141,20 @@ impl<T, B> OpenApiExt<T, B> for actix_web::App<T, B> {
     fn wrap_api_with_spec(self, spec: DefaultApiRaw) -> Self::Wrapper {
         App {
             spec: Arc::new(RwLock::new(spec)),
+            spec_output: Default::default(),
             #[cfg(feature = "v3")]
             spec_v3: None,
-            #[cfg(feature = "swagger-ui")]
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             spec_path: None,
+            spec_files: vec![],
+            spec_resource: None,
+            operation_urls: false,
+            operation_ids: None,
+            handler_map: BTreeMap::new(),
+            conditional_get: false,
+            coerce_parameters: Default::default(),
+            default_responses: BTreeMap::new(),
             inner: Some(self),
         }
     }
@@ -102,22 +171,81 @@ pub trait Mountable {
     /// The definitions recorded by this object.
     fn definitions(&mut self) -> BTreeMap<String, DefaultSchemaRaw>;
 
+    /// The (OpenAPI v3) schemas recorded by this object. The v3 specification
+    /// has the conversions of the definitions for the schemas missing here.
+    #[cfg(feature = "v3")]
+    fn schemas_v3(&mut self) -> SchemaMap {
+        Default::default()
+    }
+
     /// The security definitions recorded by this object.
     fn security_definitions(&mut self) -> BTreeMap<String, SecurityScheme>;
 
+    /// The tags (for the top-level `tags` of the spec) recorded by this object.
+    fn tags(&mut self) -> Vec<Tag> {
+        Vec::new()
+    }
+
     /// Updates the given map of operations with operations tracked by this object.
     ///
     /// **NOTE:** Overriding implementations must ensure that the `PathItem`
     /// is normalized before updating the input map.
     fn update_operations(&mut self, map: &mut BTreeMap<String, DefaultPathItemRaw>) {
+        let operations = self.operations();
+        // Paths without (documented) operations are left out of the spec.
+        if operations.is_empty() {
+            return;
+        }
+
         let op_map = map
             .entry(self.path().into())
             .or_insert_with(Default::default);
-        op_map.methods.extend(self.operations().into_iter());
+        op_map.methods.extend(operations.into_iter());
     }
+
+    /// Map of HTTP methods and the paths of the handlers of the associated API
+    /// operations (like `my_crate::pets::get_pet`), for generating operation IDs.
+    fn handlers(&mut self) -> BTreeMap<HttpMethod, &'static str> {
+        BTreeMap::new()
+    }
+
+    /// Updates the given map (of paths and HTTP methods) with the paths of the
+    /// handlers tracked by this object.
+    fn update_handlers(&mut self, map: &mut BTreeMap<String, BTreeMap<HttpMethod, &'static str>>) {
+        let handlers = self.handlers();
+        if handlers.is_empty() {
+            return;
+        }
+
+        map.entry(self.path().into())
+            .or_insert_with(Default::default)
+            .extend(handlers.into_iter());
+    }
+}
+
+/// Route registered by the `get`, `post`, `put` and `delete` macros for being
+/// collected by [`App::collect_annotated_routes`](struct.App.html#method.collect_annotated_routes).
+#[cfg(feature = "auto-routes")]
+pub struct AnnotatedRoute {
+    resource: fn() -> web::Resource,
 }
 
-impl<T, B> App<T, B>
+#[cfg(feature = "auto-routes")]
+impl AnnotatedRoute {
+    #[doc(hidden)]
+    pub const fn new(resource: fn() -> web::Resource) -> Self {
+        AnnotatedRoute { resource }
+    }
+}
+
+#[cfg(feature = "auto-routes")]
+inventory::collect!(AnnotatedRoute);
+
+#[cfg(feature = "auto-routes")]
+#[doc(hidden)]
+pub use inventory;
+
+impl<T, B> App<T>
 where
     B: MessageBody,
     T: ServiceFactory<
@@ -191,6 +319,84 @@ where
         self
     }
 
+    /// Mounts all the handlers annotated with one of the route macros (`get`, `post`, `put`
+    /// or `delete`) in this binary, along with their operations and definitions.
+    ///
+    /// **NOTE:** The order in which these routes are registered is unspecified, so handlers
+    /// with overlapping paths should still be registered manually.
+    #[cfg(feature = "auto-routes")]
+    pub fn collect_annotated_routes(mut self) -> Self {
+        for route in inventory::iter::<AnnotatedRoute> {
+            self = self.service((route.resource)());
+        }
+
+        self
+    }
+
+    /// Adds the operation for a route which is mounted through plain actix-web APIs
+    /// (for example, routes loaded from configuration at startup) to the spec.
+    ///
+    /// Definitions referenced by the operation (if any) should be added to the spec
+    /// separately (see `paperclip::v2::builder::OperationBuilder`).
+    pub fn document_route(
+        self,
+        path: &str,
+        method: HttpMethod,
+        mut operation: DefaultOperationRaw,
+    ) -> Self {
+        operation.set_parameter_names_from_path_template(path);
+        {
+            let mut api = self.spec.write();
+            let op_map = api.paths.entry(path.into()).or_default();
+            op_map.methods.insert(method, operation);
+            if cfg!(feature = "normalize") {
+                op_map.normalize();
+            }
+
+            add_default_responses(&mut api.paths, &self.default_responses);
+        }
+
+        self
+    }
+
+    /// Attaches the error responses (and their schemas) of the given type (usually
+    /// declared through `#[api_v2_errors]`) to all operations, including the ones added
+    /// before this call. This is useful for responses common to the whole API (like
+    /// `401`, `429` or `500`). Operations documenting the same status codes keep theirs.
+    pub fn with_default_responses<E: Apiv2Errors>(mut self) -> Self {
+        let mut op = DefaultOperationRaw::default();
+        E::update_error_definitions(&mut op);
+        self.default_responses.extend(op.responses);
+        {
+            let mut api = self.spec.write();
+            E::update_definitions(&mut api.definitions);
+            add_default_responses(&mut api.paths, &self.default_responses);
+        }
+
+        self
+    }
+
+    /// Documents a webhook (a request sent by this API to its subscribers) with the
+    /// given name (for example, `order.created`) through the given operation.
+    ///
+    /// The operation (along with its definitions) is recorded as a `POST` request
+    /// in the `x-webhooks` extension of the v2 spec, and it shows up in the
+    /// `webhooks` section of the v3 spec (which then uses OpenAPI 3.1).
+    pub fn with_webhook(self, name: &str, operation: OperationBuilder) -> Self {
+        let (operation, definitions) = operation.build();
+        {
+            let mut api = self.spec.write();
+            api.definitions.extend(definitions);
+            api.webhooks
+                .entry(name.into())
+                .or_default()
+                .methods
+                .insert(HttpMethod::Post, operation);
+        }
+
+        self
+    }
+
     /// Proxy for [`actix_web::App::default_service`](https://docs.rs/actix-web/*/actix_web/struct.App.html#method.default_service).
     ///
     /// **NOTE:** This doesn't affect spec generation.
@@ -236,24 +442,33 @@ where
             Error = Error,
             InitError = (),
         >,
-        B1,
     >
     where
         M: Transform<
-            T::Service,
-            ServiceRequest,
-            Response = ServiceResponse<B1>,
-            Error = Error,
-            InitError = (),
-        >,
+                T::Service,
+                ServiceRequest,
+                Response = ServiceResponse<B1>,
+                Error = Error,
+                InitError = (),
+            > + 'static,
         B1: MessageBody,
     {
         App {
             spec: self.spec,
+            spec_output: self.spec_output,
             #[cfg(feature = "v3")]
             spec_v3: self.spec_v3,
-            #[cfg(feature = "swagger-ui")]
-            spec_path: None,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
+            spec_path: self.spec_path,
+            spec_files: self.spec_files,
+            spec_resource: self.spec_resource,
+            operation_urls: self.operation_urls,
+            operation_ids: self.operation_ids,
+            handler_map: self.handler_map,
+            conditional_get: self.conditional_get,
+            coerce_parameters: self.coerce_parameters,
+            default_responses: self.default_responses,
             inner: self.inner.take().map(|a| a.wrap(mw)),
         }
     }
@@ -272,38 +487,191 @@ where
             Error = Error,
             InitError = (),
         >,
-        B1,
     >
     where
         B1: MessageBody,
-        F: Fn(ServiceRequest, &T::Service) -> R + Clone,
+        F: Fn(ServiceRequest, &T::Service) -> R + Clone + 'static,
         R: Future<Output = Result<ServiceResponse<B1>, Error>>,
     {
         App {
             spec: self.spec,
+            spec_output: self.spec_output,
             #[cfg(feature = "v3")]
             spec_v3: self.spec_v3,
-            #[cfg(feature = "swagger-ui")]
-            spec_path: None,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
+            spec_path: self.spec_path,
+            spec_files: self.spec_files,
+            spec_resource: self.spec_resource,
+            operation_urls: self.operation_urls,
+            operation_ids: self.operation_ids,
+            handler_map: self.handler_map,
+            conditional_get: self.conditional_get,
+            coerce_parameters: self.coerce_parameters,
+            default_responses: self.default_responses,
             inner: self.inner.take().map(|a| a.wrap_fn(mw)),
         }
     }
 
+    /// Validates the path, query and header parameters and the JSON bodies of
+    /// requests to the documented operations against the spec, and responds
+    /// with `400 Bad Request` (see [`RequestValidationError`](validation/struct.RequestValidationError.html))
+    /// for the ones which don't conform to it. Requests to other paths are left as they are.
+    /// Handlers of operations with timeouts (the `x-timeout` extension) which don't respond
+    /// in time are cancelled with `504 Gateway Timeout`.
+    ///
+    /// Parameters are validated as strings, unless `with_parameter_coercion` has been called
+    /// (before or after this).
+    ///
+    /// **NOTE:** This wraps the app (like `wrap`), but the spec is only read when the
+    /// app is started, so it covers all the operations regardless of when it's called.
+    pub fn with_request_validation(
+        self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        >,
+    >
+    where
+        B: 'static,
+        T::Service: 'static,
+        <T::Service as Service<ServiceRequest>>::Future: 'static,
+    {
+        let validation = RequestValidation::new(self.spec.clone())
+            .coerce_parameters_with(self.coerce_parameters.clone());
+        self.wrap(validation)
+    }
+
+    /// Coerces the path, query and header parameters to their documented types (like
+    /// `1` to `true` for booleans) before they're validated by `with_request_validation`,
+    /// so that values which aren't literals of those types are rejected.
+    pub fn with_parameter_coercion(self) -> Self {
+        self.coerce_parameters.store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Validates the JSON bodies of responses to the documented operations against
+    /// the schemas documented for their status codes, and logs (or panics on) the
+    /// ones which don't conform to them (see [`MismatchAction`](validation/enum.MismatchAction.html)).
+    /// This catches handlers whose actual serialization drifts from their schemas.
+    ///
+    /// **NOTE:** Responses are only validated in debug builds (since their bodies
+    /// are buffered for validation). Like `with_request_validation`, the spec is
+    /// read when the app is started.
+    pub fn with_response_validation(
+        self,
+        action: MismatchAction,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<EitherBody<B, Bytes>>,
+            Error = Error,
+            InitError = (),
+        >,
+    >
+    where
+        B: 'static,
+        T::Service: 'static,
+        <T::Service as Service<ServiceRequest>>::Future: 'static,
+    {
+        let validation = ResponseValidation::new(self.spec.clone(), action);
+        self.wrap(validation)
+    }
+
+    /// Adds weak `ETag`s (hashes of the canonicalized bodies) to the `200 OK` JSON
+    /// responses of the documented `GET` operations, and responds with `304 Not Modified`
+    /// to the requests whose `If-None-Match` header matches them (see
+    /// [`ConditionalGet`](etag/struct.ConditionalGet.html)). The `ETag` header and the
+    /// `304` response are documented on those operations when the app is built.
+    ///
+    /// **NOTE:** Like `with_request_validation`, this covers all the operations
+    /// regardless of when it's called. Responses are buffered for hashing, so this
+    /// isn't meant for streaming responses.
+    pub fn with_conditional_get(
+        mut self,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<EitherBody<B, Bytes>>,
+            Error = Error,
+            InitError = (),
+        >,
+    >
+    where
+        B: 'static,
+        T::Service: 'static,
+        <T::Service as Service<ServiceRequest>>::Future: 'static,
+    {
+        self.conditional_get = true;
+        let etags = ConditionalGet::new(self.spec.clone());
+        self.wrap(etags)
+    }
+
     /// Mounts the specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
-    /// as a JSON.
+    /// as a JSON. The spec is serialized once (when the app is built)
+    /// and served with an `ETag`, so that requests with a matching
+    /// `If-None-Match` header get `304 Not Modified` responses.
     pub fn with_json_spec_at(mut self, path: &str) -> Self {
-        #[cfg(feature = "swagger-ui")]
-        {
-            self.spec_path = Some(path.to_owned());
-        }
+        self.spec_path = Some(path.to_owned());
+        let resource = self.spec_resource(actix_web::web::resource(path).route(
+            actix_web::web::get().to(SpecHandler(self.spec.clone(), self.spec_output.clone())),
+        ));
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
 
-        self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandler(self.spec.clone()))),
-            )
-        });
+    /// Same as `with_json_spec_at`, but serves only the operations for which the given
+    /// function (called with their paths, methods and operations) returns `true`, along
+    /// with the definitions and tags used by them. This is useful for serving parts of
+    /// the API (say, the operations tagged `public`) to external partners, while serving
+    /// the whole API elsewhere. Like the whole spec, the filtered spec is serialized
+    /// once (when the app is built).
+    pub fn with_json_spec_at_filtered<F>(mut self, path: &str, filter: F) -> Self
+    where
+        F: Fn(&str, HttpMethod, &DefaultOperationRaw) -> bool + 'static,
+    {
+        let index = {
+            let mut output = self.spec_output.write();
+            output.filtered.push((Rc::new(filter), None));
+            output.filtered.len() - 1
+        };
+        let handler = FilteredSpecHandler(self.spec.clone(), self.spec_output.clone(), index);
+        let resource = self
+            .spec_resource(actix_web::web::resource(path).route(actix_web::web::get().to(handler)));
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    /// Same as `with_json_spec_at`, but serves the specification as YAML
+    /// (with `application/yaml` content type).
+    pub fn with_yaml_spec_at(mut self, path: &str) -> Self {
+        self.spec_output.write().yaml = true;
+        let resource = self.spec_resource(actix_web::web::resource(path).route(
+            actix_web::web::get().to(YamlSpecHandler(self.spec.clone(), self.spec_output.clone())),
+        ));
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    /// Serves the fingerprints (SHA-256 hashes of the canonicalized schemas) of all
+    /// definitions in the (v2) JSON specification as a JSON object in the given path,
+    /// so that consumers can detect changes to the models they depend on without
+    /// diffing the whole spec (see `paperclip::integrity`).
+    pub fn with_definition_fingerprints_at(mut self, path: &str) -> Self {
+        let resource = self.spec_resource(actix_web::web::resource(path).route(
+            actix_web::web::get().to(FingerprintsHandler(
+                self.spec.clone(),
+                self.spec_output.clone(),
+            )),
+        ));
+        self.inner = self.inner.take().map(|a| a.service(resource));
         self
     }
 
@@ -311,21 +679,112 @@ where
     /// Converts the generated v2 specification to v3 and then
     /// mounts the v3 specification for all operations and definitions
     /// recorded by the wrapper and serves them in the given path
-    /// as a JSON.
+    /// as a JSON. Schemas of derived types are emitted natively for v3
+    /// (with nullable fields and `oneOf` for enums with data variants).
     pub fn with_json_spec_v3_at(mut self, path: &str) -> Self {
-        let spec_v3 = if let Some(spec_v3) = &self.spec_v3 {
+        let spec_v3 = self.spec_v3();
+        let resource = self.spec_resource(
+            actix_web::web::resource(path).route(actix_web::web::get().to(SpecHandlerV3(spec_v3))),
+        );
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the v3 specification as YAML
+    /// (with `application/yaml` content type).
+    pub fn with_yaml_spec_v3_at(mut self, path: &str) -> Self {
+        let spec_v3 = self.spec_v3();
+        let resource = self.spec_resource(
+            actix_web::web::resource(path)
+                .route(actix_web::web::get().to(YamlSpecHandlerV3(spec_v3))),
+        );
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    #[cfg(feature = "v3")]
+    /// Same as `with_json_spec_v3_at`, but serves the specification as OpenAPI 3.1,
+    /// whose schemas are JSON Schema compliant (for example, nullable values have
+    /// `type: ["string", "null"]` instead of `nullable: true`).
+    pub fn with_json_spec_v31_at(mut self, path: &str) -> Self {
+        let spec_v3 = self.spec_v3();
+        let resource = self.spec_resource(
+            actix_web::web::resource(path).route(actix_web::web::get().to(SpecHandlerV31(spec_v3))),
+        );
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    #[cfg(feature = "v3")]
+    /// Returns the v3 specification built until now, which is converted from v2
+    /// along with the schemas emitted natively for v3 (replacing the converted ones).
+    fn build_spec_v3(&self) -> openapiv3::OpenAPI {
+        let mut spec = self.spec.read().clone();
+        for prefix in &self.spec_output.read().hidden_prefixes {
+            spec.strip_path_prefix(prefix);
+        }
+
+        let mut spec = paperclip_core::v3::openapiv2_to_v3(spec);
+        paperclip_core::v3::native::extend_components(&mut spec, self.schemas_v3.clone());
+        spec
+    }
+
+    #[cfg(feature = "v3")]
+    /// Returns the v3 specification (which is converted from v2 when the app is built).
+    fn spec_v3(&mut self) -> Arc<RwLock<openapiv3::OpenAPI>> {
+        if let Some(spec_v3) = &self.spec_v3 {
             spec_v3.clone()
         } else {
             let spec_v3 = Arc::new(RwLock::new(openapiv3::OpenAPI::default()));
             self.spec_v3 = Some(spec_v3.clone());
             spec_v3
-        };
-        self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource(path)
-                    .route(actix_web::web::get().to(SpecHandlerV3(spec_v3.clone()))),
-            )
-        });
+        }
+    }
+
+    /// Adds a [JSON merge patch](https://tools.ietf.org/html/rfc7386) which is applied
+    /// to the (v2) JSON specification whenever it's served or built. This is useful for
+    /// injecting environment-specific values (host, auth URLs, descriptions, etc.) from
+    /// configuration files. Overlays are applied in the order in which they were added.
+    pub fn with_spec_overlay(self, overlay: serde_json::Value) -> Self {
+        self.spec_output.write().overlays.push(overlay);
+        self
+    }
+
+    /// Embeds the SHA-256 hash of the (canonicalized) v2 JSON specification in the
+    /// `x-spec-sha256` extension whenever it's served or built (after applying the
+    /// overlays), so that consumers can verify it (see `paperclip::integrity`).
+    pub fn with_spec_sha256(self) -> Self {
+        self.spec_output.write().sha256 = true;
+        self
+    }
+
+    /// Hides the given prefix of the paths (like `/t/{tenant_id}`, along with its path
+    /// parameters) in the specifications whenever they're served or built, so that the
+    /// published spec only has the paths relative to it. Requests are still routed (and
+    /// validated) with the prefix.
+    pub fn with_hidden_path_prefix(self, prefix: &str) -> Self {
+        self.spec_output.write().hidden_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Makes the URLs of operations (by their IDs) available to handlers through the
+    /// `web::Data<OperationUrls>` extractor (see `paperclip::routes`), so that they can
+    /// build links and `Location` headers from the spec. These are collected from the
+    /// (v2) JSON specification when the app is built.
+    pub fn with_operation_urls(mut self) -> Self {
+        self.operation_urls = true;
+        self
+    }
+
+    /// Generates the IDs of the operations which don't have one (through `operation_id`
+    /// in `api_v2_operation`) from the paths of their handlers when the app is built.
+    ///
+    /// **NOTE:** Operation IDs used by more than one operation (which break generated
+    /// clients) are logged as warnings when the app is built, whether or not they've
+    /// been generated.
+    pub fn with_operation_ids(mut self, strategy: OperationIdStrategy) -> Self {
+        self.operation_ids = Some(strategy);
         self
     }
 
@@ -339,7 +798,7 @@ where
     where
         F: FnMut(Self, serde_json::Value) -> Self,
     {
-        let spec = serde_json::to_value(&*self.spec.read()).expect("generating json spec");
+        let spec = json_spec(&self.spec, &self.spec_output);
         call(self, spec)
     }
 
@@ -354,11 +813,24 @@ where
     where
         F: FnMut(Self, serde_json::Value) -> Self,
     {
-        let v3 = paperclip_core::v3::openapiv2_to_v3(self.spec.read().clone());
-        let spec = serde_json::to_value(v3).expect("generating json spec");
+        let spec = serde_json::to_value(self.build_spec_v3()).expect("generating json spec");
         call(self, spec)
     }
 
+    /// Writes the (v2) specification to the given file when the app is built, as YAML
+    /// if the file has a `.yaml` or `.yml` extension and JSON otherwise. This way, the
+    /// spec can be generated (say, for committing it or generating clients in CI)
+    /// by building the app, without starting the server.
+    ///
+    /// **NOTE:** Since the app is built in each worker of the server, the file is
+    /// written multiple times (with the same contents). Each write goes to a temporary
+    /// file which then replaces the given one, so the file is never partially written.
+    /// Building the app panics if the file can't be written.
+    pub fn write_spec_to_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.spec_files.push(path.into());
+        self
+    }
+
     /// Exposes the previously built JSON specification with Swagger UI at the given path
     ///
     /// **NOTE:** you **MUST** call with_json_spec_at before calling this function
@@ -373,46 +845,198 @@ where
         // E.g: js, html, svg and etc.
         let regex_path = format!("{}/{{filename:.*}}", path);
 
-        self.inner = self.inner.take().map(|a| {
-            a.service(
-                actix_web::web::resource([regex_path.to_owned(), path.clone()]).route(
-                    actix_web::web::get().to(move |request: HttpRequest| {
-                        let filename = request.match_info().query("filename");
+        let resource = self.spec_resource(
+            actix_web::web::resource([regex_path.to_owned(), path.clone()]).route(
+                actix_web::web::get().to(move |request: HttpRequest| {
+                    let filename = request.match_info().query("filename");
+                    ready(
                         if filename.is_empty() && request.query_string().is_empty() {
                             let redirect_url = format!("{}/index.html?url={}", path, spec_path);
                             HttpResponse::PermanentRedirect()
-                                .header("Location", redirect_url)
+                                .insert_header((header::LOCATION, redirect_url))
                                 .finish()
                         } else {
-                            HttpResponse::Ok().body(
-                                SWAGGER_DIST
-                                    .get_file(filename)
-                                    .expect(&format!("Failed to get file {}", filename))
-                                    .contents(),
-                            )
-                        }
-                    }),
-                ),
-            )
-        });
+                            match SWAGGER_DIST.get_file(filename) {
+                                Some(file) => HttpResponse::Ok()
+                                    .content_type(swagger_ui_content_type(filename))
+                                    .body(file.contents()),
+                                None => HttpResponse::NotFound().finish(),
+                            }
+                        },
+                    )
+                }),
+            ),
+        );
+        self.inner = self.inner.take().map(|a| a.service(resource));
+        self
+    }
+
+    /// Serves a [RapiDoc](https://rapidocweb.com) page for browsing the JSON
+    /// specification at the given path. The RapiDoc script is loaded from
+    /// [unpkg](https://unpkg.com) (see `with_rapidoc_config_at` for changing it).
+    ///
+    /// **NOTE:** you **MUST** call `with_json_spec_at` before calling this function
+    pub fn with_rapidoc_at(self, path: &str) -> Self {
+        self.with_rapidoc_config_at(path, RapiDoc::default())
+    }
+
+    /// Same as `with_rapidoc_at`, but with the given options (theme, title,
+    /// `rapi-doc` attributes, etc.).
+    ///
+    /// **NOTE:** you **MUST** call `with_json_spec_at` before calling this function,
+    /// unless the spec URL has been set in the options.
+    pub fn with_rapidoc_config_at(self, path: &str, options: RapiDoc) -> Self {
+        let html = options.render(self.spec_path.as_deref());
+        self.with_html_page_at(path, html)
+    }
+
+    /// Serves a [ReDoc](https://github.com/Redocly/redoc) page for browsing the
+    /// JSON specification at the given path. The ReDoc script is loaded from
+    /// [jsDelivr](https://www.jsdelivr.com) (see `with_redoc_config_at` for changing it).
+    ///
+    /// **NOTE:** you **MUST** call `with_json_spec_at` before calling this function
+    pub fn with_redoc_at(self, path: &str) -> Self {
+        self.with_redoc_config_at(path, ReDoc::default())
+    }
+
+    /// Same as `with_redoc_at`, but with the given options (title, spec URL,
+    /// ReDoc options, etc.).
+    ///
+    /// **NOTE:** you **MUST** call `with_json_spec_at` before calling this function,
+    /// unless the spec URL has been set in the options.
+    pub fn with_redoc_config_at(self, path: &str, options: ReDoc) -> Self {
+        let html = options.render(self.spec_path.as_deref());
+        self.with_html_page_at(path, html)
+    }
+
+    /// Serves the given HTML page at the given path.
+    fn with_html_page_at(mut self, path: &str, html: String) -> Self {
+        let resource = self.spec_resource(actix_web::web::resource(path).route(
+            actix_web::web::get().to(move || {
+                ready(
+                    HttpResponse::Ok()
+                        .content_type("text/html; charset=utf-8")
+                        .body(html.clone()),
+                )
+            }),
+        ));
+        self.inner = self.inner.take().map(|a| a.service(resource));
         self
     }
 
+    /// Configures the resources serving the specification and documentation pages
+    /// (like Swagger UI) which are mounted **after** this call, through the given
+    /// function. This way, they can be protected with guards (say, for checking
+    /// credentials or API keys in headers, or for limiting them to internal networks).
+    ///
+    /// ```ignore
+    /// App::new()
+    ///     .wrap_api()
+    ///     .configure_spec_resources(|r| r.guard(guard::Header("x-api-key", "secret")))
+    ///     .with_json_spec_at("/api/spec")
+    ///     .with_swagger_ui_at("/docs")
+    ///     .build()
+    /// ```
+    ///
+    /// **NOTE:** Requests failing the guards are handled by the app's default
+    /// service (`404 Not Found` unless it's been changed), like the ones for
+    /// other paths.
+    pub fn configure_spec_resources<F>(mut self, f: F) -> Self
+    where
+        F: Fn(actix_web::Resource) -> actix_web::Resource + 'static,
+    {
+        self.spec_resource = Some(Rc::new(f));
+        self
+    }
+
+    /// Returns the given resource (serving the spec or a documentation page)
+    /// after it's been configured with the function set by `configure_spec_resources`.
+    fn spec_resource(&self, resource: actix_web::Resource) -> actix_web::Resource {
+        match &self.spec_resource {
+            Some(f) => f(resource),
+            None => resource,
+        }
+    }
+
     /// Builds and returns the `actix_web::App`.
-    pub fn build(self) -> actix_web::App<T, B> {
+    pub fn build(self) -> actix_web::App<T> {
+        if let Some(strategy) = self.operation_ids {
+            self.spec
+                .write()
+                .generate_operation_ids(strategy, &self.handler_map);
+        }
+
+        // Clients generated from the spec break when operation IDs repeat.
+        for (id, ops) in self.spec.read().duplicate_operation_ids() {
+            let ops = ops
+                .iter()
+                .map(|(path, method)| format!("{} {}", method.to_string().to_uppercase(), path))
+                .collect::<Vec<_>>();
+            log::warn!("duplicate operation ID `{}` ({})", id, ops.join(", "));
+        }
+
+        if self.conditional_get {
+            etag::document_etags(&mut self.spec.write());
+        }
+
         #[cfg(feature = "v3")]
         self.spec_v3.clone().map(|v3| {
             let mut v3 = v3.write();
-            *v3 = paperclip_core::v3::openapiv2_to_v3(self.spec.read().clone());
+            *v3 = self.build_spec_v3();
         });
-        self.inner.expect("missing app?")
+        let spec = json_spec(&self.spec, &self.spec_output);
+        for path in &self.spec_files {
+            write_spec(&spec, path)
+                .unwrap_or_else(|e| panic!("failed to write spec to {}: {}", path.display(), e));
+        }
+
+        // The spec doesn't change once the app is built, so it's served as it is.
+        if self.spec_path.is_some() {
+            let body = serde_json::to_vec(&spec).expect("generating json spec");
+            let etag = format!("\"{}\"", paperclip_core::integrity::spec_sha256(&spec));
+            self.spec_output.write().cached = Some((body.into(), etag));
+        }
+
+        if self.spec_output.read().yaml {
+            let body = serde_yaml::to_string(&spec).expect("generating yaml spec");
+            self.spec_output.write().cached_yaml = Some(body.into());
+        }
+
+        // Likewise for the filtered specs.
+        let filtered = {
+            let (api, output) = (self.spec.read(), self.spec_output.read());
+            output
+                .filtered
+                .iter()
+                .map(|(filter, _)| {
+                    let spec = output_json_spec(&filter_spec(&api, filter), &output);
+                    Bytes::from(serde_json::to_vec(&spec).expect("generating json spec"))
+                })
+                .collect::<Vec<_>>()
+        };
+        for ((_, cached), body) in self.spec_output.write().filtered.iter_mut().zip(filtered) {
+            *cached = Some(body);
+        }
+
+        let app = self.inner.expect("missing app?");
+        if self.operation_urls {
+            // URLs are for the actual routes (which have the hidden prefixes).
+            let spec = if self.spec_output.read().hidden_prefixes.is_empty() {
+                spec
+            } else {
+                serde_json::to_value(&*self.spec.read()).expect("generating json spec")
+            };
+            app.app_data(Data::new(OperationUrls::from_spec(&spec)))
+        } else {
+            app
+        }
     }
 
     /// Trim's the Api base path from the start of all method paths.
     /// **NOTE:** much like `with_raw_json_spec` this only has the API spec built until
     /// this function call. Any route handler added after this call won't have the base path trimmed.
     /// So, it's important to call this function after adding all route handlers.
-    pub fn trim_base_path(self) -> Self {
+    pub fn trim_base_path(mut self) -> Self {
         {
             let mut spec = self.spec.write();
             let base_path = spec.base_path.clone().unwrap_or_default();
@@ -423,6 +1047,10 @@ where
                 );
                 i
             });
+            self.handler_map = mem::take(&mut self.handler_map)
+                .into_iter()
+                .map(|(k, v)| (k.trim_start_matches(base_path.as_str()).to_string(), v))
+                .collect();
         }
         self
     }
@@ -432,6 +1060,8 @@ where
     where
         F: Mountable,
     {
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(factory.schemas_v3().into_iter());
         let mut api = self.spec.write();
         api.definitions.extend(factory.definitions().into_iter());
         SecurityScheme::append_map(
@@ -439,20 +1069,239 @@ where
             &mut api.security_definitions,
         );
         factory.update_operations(&mut api.paths);
+        factory.update_handlers(&mut self.handler_map);
+        Tag::append_list(factory.tags(), &mut api.tags);
         if cfg!(feature = "normalize") {
             for map in api.paths.values_mut() {
                 map.normalize();
             }
         }
+
+        add_default_responses(&mut api.paths, &self.default_responses);
+    }
+}
+
+/// Adds the given responses to all operations which don't document their status codes.
+fn add_default_responses(
+    paths: &mut BTreeMap<String, DefaultPathItemRaw>,
+    responses: &BTreeMap<String, Either<Reference, DefaultResponseRaw>>,
+) {
+    if responses.is_empty() {
+        return;
+    }
+
+    for op in paths.values_mut().flat_map(|p| p.methods.values_mut()) {
+        for (code, response) in responses {
+            op.responses
+                .entry(code.clone())
+                .or_insert_with(|| response.clone());
+        }
+    }
+}
+
+/// Returns the JSON value of the given spec with the overlays applied (and its hash
+/// embedded, if needed).
+fn json_spec(spec: &RwLock<DefaultApiRaw>, output: &RwLock<SpecOutput>) -> serde_json::Value {
+    output_json_spec(&*spec.read(), &output.read())
+}
+
+fn output_json_spec(spec: &DefaultApiRaw, output: &SpecOutput) -> serde_json::Value {
+    let mut value = if output.hidden_prefixes.is_empty() {
+        serde_json::to_value(spec)
+    } else {
+        let mut spec = spec.clone();
+        for prefix in &output.hidden_prefixes {
+            spec.strip_path_prefix(prefix);
+        }
+
+        serde_json::to_value(&spec)
+    }
+    .expect("generating json spec");
+    for overlay in &output.overlays {
+        paperclip_core::util::merge_patch(&mut value, overlay);
+    }
+
+    if output.sha256 {
+        paperclip_core::integrity::embed_sha256(&mut value);
+    }
+
+    value
+}
+
+/// Writes the given (JSON) spec to the file, in the format for its extension.
+///
+/// Since the app is built in each worker, the spec is written to a temporary file
+/// (next to the given one) which then replaces the file, so that workers writing it
+/// at the same time don't leave it garbled.
+fn write_spec(spec: &serde_json::Value, path: &Path) -> std::io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = std::fs::File::create(&tmp_path)
+        .and_then(|file| {
+            // The file is closed before it's renamed.
+            let mut file = std::io::BufWriter::new(file);
+            SpecFormat::from_path(path).write(spec, &mut file)?;
+            file.flush()
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Changes applied to the (v2) JSON spec whenever it's served or built.
+#[derive(Default)]
+struct SpecOutput {
+    overlays: Vec<serde_json::Value>,
+    sha256: bool,
+    /// Prefixes of the paths hidden in the spec.
+    hidden_prefixes: Vec<String>,
+    /// JSON spec serialized when the app is built (along with its ETag), which is
+    /// served as it is (instead of serializing the spec for every request).
+    cached: Option<(Bytes, String)>,
+    /// Whether the spec is served as YAML.
+    yaml: bool,
+    /// YAML spec serialized when the app is built.
+    cached_yaml: Option<Bytes>,
+    /// Filters of the specs served by `with_json_spec_at_filtered`, along with
+    /// those specs (serialized when the app is built).
+    filtered: Vec<(OperationFilter, Option<Bytes>)>,
+}
+
+/// Returns the content type for a file of Swagger UI (based on its extension).
+#[cfg(feature = "swagger-ui")]
+fn swagger_ui_content_type(filename: &str) -> &'static str {
+    match filename.rsplit('.').next() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "application/javascript",
+        Some("css") => "text/css",
+        Some("json") | Some("map") => "application/json",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Clone)]
+struct SpecHandler(Arc<RwLock<DefaultApiRaw>>, Arc<RwLock<SpecOutput>>);
+
+impl Handler<(HttpRequest,)> for SpecHandler {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, (req,): (HttpRequest,)) -> Self::Future {
+        {
+            let output = self.1.read();
+            if let Some((body, etag)) = &output.cached {
+                let unchanged = req
+                    .headers()
+                    .get(header::IF_NONE_MATCH)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.split(',').any(|t| t.trim() == etag || t.trim() == "*"))
+                    .unwrap_or(false);
+                let mut resp = if unchanged {
+                    HttpResponse::NotModified()
+                } else {
+                    HttpResponse::Ok()
+                };
+                resp.insert_header((header::ETAG, etag.as_str()));
+                return fut_ok(if unchanged {
+                    resp.finish()
+                } else {
+                    resp.content_type("application/json").body(body.clone())
+                });
+            }
+
+            if output.overlays.is_empty() && !output.sha256 && output.hidden_prefixes.is_empty() {
+                return fut_ok(HttpResponse::Ok().json(&*self.0.read()));
+            }
+        }
+
+        fut_ok(HttpResponse::Ok().json(json_spec(&self.0, &self.1)))
     }
 }
 
+/// Filter of the operations in a spec.
+type OperationFilter = Rc<dyn Fn(&str, HttpMethod, &DefaultOperationRaw) -> bool>;
+
+/// Returns the given spec with only the operations retained by the filter.
+fn filter_spec(spec: &DefaultApiRaw, filter: &OperationFilter) -> DefaultApiRaw {
+    let mut spec = spec.clone();
+    spec.retain_operations(|path, method, op| filter(path, method, op));
+    spec
+}
+
+/// Handler serving the spec of the filter (in `SpecOutput::filtered`) at the given index.
 #[derive(Clone)]
-struct SpecHandler(Arc<RwLock<DefaultApiRaw>>);
+struct FilteredSpecHandler(Arc<RwLock<DefaultApiRaw>>, Arc<RwLock<SpecOutput>>, usize);
 
-impl actix_web::dev::Handler<(), Ready<Result<HttpResponse, Error>>> for SpecHandler {
-    fn call(&self, _: ()) -> Ready<Result<HttpResponse, Error>> {
-        fut_ok(HttpResponse::Ok().json(&*self.0.read()))
+impl Handler<()> for FilteredSpecHandler {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
+        let output = self.1.read();
+        let (filter, cached) = &output.filtered[self.2];
+        if let Some(body) = cached {
+            return fut_ok(
+                HttpResponse::Ok()
+                    .content_type("application/json")
+                    .body(body.clone()),
+            );
+        }
+
+        let spec = filter_spec(&self.0.read(), filter);
+        fut_ok(HttpResponse::Ok().json(output_json_spec(&spec, &output)))
+    }
+}
+
+#[derive(Clone)]
+struct YamlSpecHandler(Arc<RwLock<DefaultApiRaw>>, Arc<RwLock<SpecOutput>>);
+
+impl Handler<()> for YamlSpecHandler {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
+        if let Some(body) = &self.1.read().cached_yaml {
+            return fut_ok(
+                HttpResponse::Ok()
+                    .content_type("application/yaml")
+                    .body(body.clone()),
+            );
+        }
+
+        ready(yaml_response(&json_spec(&self.0, &self.1)))
+    }
+}
+
+/// Responds with the given (JSON) specification as YAML.
+fn yaml_response(spec: &serde_json::Value) -> Result<HttpResponse, Error> {
+    let body = serde_yaml::to_string(spec).map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/yaml")
+        .body(body))
+}
+
+#[derive(Clone)]
+struct FingerprintsHandler(Arc<RwLock<DefaultApiRaw>>, Arc<RwLock<SpecOutput>>);
+
+impl Handler<()> for FingerprintsHandler {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
+        let spec = json_spec(&self.0, &self.1);
+        fut_ok(HttpResponse::Ok().json(paperclip_core::integrity::definition_fingerprints(&spec)))
     }
 }
 
@@ -461,10 +1310,41 @@ impl actix_web::dev::Handler<(), Ready<Result<HttpResponse, Error>>> for SpecHan
 struct SpecHandlerV3(Arc<RwLock<openapiv3::OpenAPI>>);
 
 #[cfg(feature = "v3")]
-impl actix_web::dev::Factory<(), Ready<Result<HttpResponse, Error>>, Result<HttpResponse, Error>>
-    for SpecHandlerV3
-{
-    fn call(&self, _: ()) -> Ready<Result<HttpResponse, Error>> {
+impl Handler<()> for SpecHandlerV3 {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
         fut_ok(HttpResponse::Ok().json(&*self.0.read()))
     }
 }
+
+#[cfg(feature = "v3")]
+#[derive(Clone)]
+struct SpecHandlerV31(Arc<RwLock<openapiv3::OpenAPI>>);
+
+#[cfg(feature = "v3")]
+impl Handler<()> for SpecHandlerV31 {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
+        let spec = paperclip_core::v3::openapiv3_to_v31(&*self.0.read());
+        ready(Ok(HttpResponse::Ok().json(spec)))
+    }
+}
+
+#[cfg(feature = "v3")]
+#[derive(Clone)]
+struct YamlSpecHandlerV3(Arc<RwLock<openapiv3::OpenAPI>>);
+
+#[cfg(feature = "v3")]
+impl Handler<()> for YamlSpecHandlerV3 {
+    type Output = Result<HttpResponse, Error>;
+    type Future = Ready<Self::Output>;
+
+    fn call(&self, _: ()) -> Self::Future {
+        let spec = serde_json::to_value(&*self.0.read()).expect("generating json spec");
+        ready(yaml_response(&spec))
+    }
+}
@@ -0,0 +1,121 @@
+//! Helpers for checking requests and responses in tests against the spec, so that
+//! integration tests double as contract tests.
+
+use paperclip_core::v2::{
+    models::{
+        DefaultApiRaw, DefaultOperationRaw, DefaultSchemaRaw, Either, HttpMethod, ParameterIn,
+    },
+    validate::Validator,
+};
+use serde_json::Value;
+
+#[doc(inline)]
+pub use crate::assert_conforms;
+#[doc(hidden)]
+pub use actix_web::test::read_body;
+
+/// Asserts that the response (from an actix-web test service) has the given status
+/// and that its body conforms to the schema documented for that status in the
+/// operation (`"METHOD /path/template"`) of the given (JSON) spec.
+///
+/// This reads the body of the response, so it can only be used in async functions.
+#[macro_export]
+macro_rules! assert_conforms {
+    ($resp:expr, $spec:expr, $operation:expr, $status:expr) => {{
+        let resp = $resp;
+        let status = resp.status().as_u16();
+        assert_eq!(status, $status, "unexpected status for {}", $operation);
+        let body = $crate::test::read_body(resp).await;
+        if let Err(e) = $crate::test::check_response(&$spec, $operation, status, &body) {
+            panic!("response of {} doesn't conform to spec: {}", $operation, e);
+        }
+    }};
+}
+
+/// Checks that the (JSON) body of a response with the given status conforms to
+/// the schema documented for the operation (`"METHOD /path/template"`) in the spec.
+///
+/// Responses without a schema in the spec aren't checked.
+pub fn check_response(
+    spec: &Value,
+    operation: &str,
+    status: u16,
+    body: &[u8],
+) -> Result<(), String> {
+    let api = parse_spec(spec)?;
+    let op = find_operation(&api, operation)?;
+    let code = status.to_string();
+    let response = match op
+        .responses
+        .get(&code)
+        .or_else(|| op.responses.get("default"))
+    {
+        Some(Either::Right(r)) => r,
+        Some(Either::Left(r)) => {
+            let name = r.reference.trim_start_matches("#/responses/");
+            api.responses
+                .get(name)
+                .ok_or_else(|| format!("unresolved reference {:?}", r.reference))?
+        }
+        None => return Err(format!("status {} isn't documented", status)),
+    };
+
+    match &response.schema {
+        Some(schema) => check_body(&api, schema, body),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the (JSON) body of a request conforms to the schema of the body
+/// parameter of the operation (`"METHOD /path/template"`) in the spec.
+pub fn check_request(spec: &Value, operation: &str, body: &[u8]) -> Result<(), String> {
+    let api = parse_spec(spec)?;
+    let op = find_operation(&api, operation)?;
+    let param = op
+        .parameters
+        .iter()
+        .filter_map(Either::right)
+        .find(|p| p.in_ == ParameterIn::Body)
+        .ok_or_else(|| format!("{} doesn't have a body parameter", operation))?;
+
+    match &param.schema {
+        Some(schema) => check_body(&api, schema, body),
+        None => Ok(()),
+    }
+}
+
+fn parse_spec(spec: &Value) -> Result<DefaultApiRaw, String> {
+    serde_json::from_value(spec.clone()).map_err(|e| format!("invalid spec: {}", e))
+}
+
+fn find_operation<'a>(
+    api: &'a DefaultApiRaw,
+    operation: &str,
+) -> Result<&'a DefaultOperationRaw, String> {
+    let mut parts = operation.splitn(2, ' ');
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(m), Some(p)) => (m, p.trim()),
+        _ => return Err(format!("expected \"METHOD /path\", found {:?}", operation)),
+    };
+
+    let method: HttpMethod = serde_json::from_value(Value::String(method.to_lowercase()))
+        .map_err(|_| format!("unknown method in {:?}", operation))?;
+    api.paths
+        .get(path)
+        .and_then(|item| item.methods.get(&method))
+        .ok_or_else(|| format!("{} isn't documented", operation))
+}
+
+fn check_body(api: &DefaultApiRaw, schema: &DefaultSchemaRaw, body: &[u8]) -> Result<(), String> {
+    let value: Value =
+        serde_json::from_slice(body).map_err(|e| format!("body isn't valid JSON: {}", e))?;
+    Validator::new(&api.definitions)
+        .validate(schema, &value)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+}
@@ -0,0 +1,638 @@
+//! Middleware for validating requests and responses against the operations recorded
+//! in the spec (see [`App::with_request_validation`](../struct.App.html#method.with_request_validation)
+//! and [`App::with_response_validation`](../struct.App.html#method.with_response_validation)).
+
+use actix_service::{Service, Transform};
+use actix_web::{
+    body::{to_bytes, EitherBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::{ErrorGatewayTimeout, ErrorInternalServerError, ErrorPayloadTooLarge, ResponseError},
+    http::{header, StatusCode},
+    web::{Bytes, BytesMut, Query},
+    Error, HttpMessage, HttpResponse,
+};
+use futures::{
+    future::{ok as fut_ok, LocalBoxFuture, Ready},
+    StreamExt,
+};
+use paperclip_core::util::parse_duration;
+use paperclip_core::v2::{
+    models::{
+        DefaultApiRaw, DefaultParameterRaw, DefaultSchemaRaw, Either, HttpMethod, ParameterIn,
+        Reference,
+    },
+    validate::{SchemaError, Validator},
+};
+use parking_lot::RwLock;
+use serde_json::{json, Value};
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Requests with larger (JSON) bodies are rejected (unless their operations document
+/// another limit in `x-max-body-size`), like with the default limit of `web::Json`.
+const BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Part of a request which doesn't conform to its operation in the spec.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestError {
+    /// Location of the offending parameter.
+    pub location: ParameterIn,
+    /// Name of the parameter.
+    pub name: String,
+    /// Offending value (at the JSON pointer relative to the parameter).
+    pub error: SchemaError,
+}
+
+/// Error for requests which don't conform to the spec. This responds with
+/// `400 Bad Request` and the offending parameters:
+///
+/// ```json
+/// {
+///   "message": "request doesn't conform to the spec",
+///   "errors": [
+///     { "in": "query", "name": "limit", "path": "", "message": "expected integer, found \"ten\"" },
+///     { "in": "body", "name": "body", "path": "/name", "message": "expected string, found integer" }
+///   ]
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestValidationError(pub Vec<RequestError>);
+
+impl fmt::Display for RequestValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "request doesn't conform to the spec")?;
+        for (i, e) in self.0.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(
+                f,
+                "{}{} {:?} at {}",
+                sep,
+                location(e.location),
+                e.name,
+                e.error
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ResponseError for RequestValidationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let errors: Vec<_> = self
+            .0
+            .iter()
+            .map(|e| {
+                json!({
+                    "in": location(e.location),
+                    "name": e.name,
+                    "path": e.error.path,
+                    "message": e.error.message,
+                })
+            })
+            .collect();
+        HttpResponse::BadRequest().json(json!({
+            "message": "request doesn't conform to the spec",
+            "errors": errors,
+        }))
+    }
+}
+
+fn location(location: ParameterIn) -> &'static str {
+    match location {
+        ParameterIn::Query => "query",
+        ParameterIn::Header => "header",
+        ParameterIn::Path => "path",
+        ParameterIn::FormData => "formData",
+        ParameterIn::Body => "body",
+    }
+}
+
+/// Middleware (transform) validating requests against the spec.
+pub struct RequestValidation {
+    spec: Arc<RwLock<DefaultApiRaw>>,
+    /// Read along with the spec (so, it can be shared with the app).
+    coerce_parameters: Arc<AtomicBool>,
+}
+
+impl RequestValidation {
+    /// Validates requests against the given spec (which is read when the app
+    /// is started by the server, i.e., once it's been built).
+    pub fn new(spec: Arc<RwLock<DefaultApiRaw>>) -> Self {
+        RequestValidation {
+            spec,
+            coerce_parameters: Default::default(),
+        }
+    }
+
+    /// Sets whether parameters are coerced to their documented types before
+    /// they're validated (off by default).
+    pub fn coerce_parameters(mut self, coerce: bool) -> Self {
+        self.coerce_parameters = Arc::new(AtomicBool::new(coerce));
+        self
+    }
+
+    /// Uses the given flag for coercing parameters, which is read when the
+    /// app is started (like the spec).
+    pub(crate) fn coerce_parameters_with(mut self, coerce: Arc<AtomicBool>) -> Self {
+        self.coerce_parameters = coerce;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestValidation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestValidationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        fut_ok(RequestValidationMiddleware {
+            service: Rc::new(service),
+            operations: Rc::new(Operations::from_spec(&self.spec.read())),
+            coerce_parameters: self.coerce_parameters.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Service validating requests before passing them to the wrapped service.
+pub struct RequestValidationMiddleware<S> {
+    service: Rc<S>,
+    operations: Rc<Operations>,
+    coerce_parameters: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestValidationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let operations = self.operations.clone();
+        let coerce_parameters = self.coerce_parameters;
+        Box::pin(async move {
+            let method =
+                serde_json::from_value(Value::String(req.method().as_str().to_lowercase()));
+            let (op, path_values) = match method.ok().and_then(|m| operations.find(m, req.path())) {
+                Some(o) => o,
+                // Undocumented operations are left to the app.
+                None => return service.call(req).await,
+            };
+
+            let mut errors = op.check_parameters(
+                &operations.definitions,
+                &req,
+                &path_values,
+                coerce_parameters,
+            );
+            if let Some(param) = &op.body {
+                let is_json = req
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.contains("json"))
+                    .unwrap_or(true);
+                if is_json {
+                    let mut payload = req.take_payload();
+                    let mut body = BytesMut::new();
+                    while let Some(chunk) = payload.next().await {
+                        let chunk: Bytes = chunk?;
+                        body.extend_from_slice(&chunk);
+                        if body.len() > op.max_body_size.unwrap_or(BODY_LIMIT) {
+                            return Err(ErrorPayloadTooLarge("request body is too large"));
+                        }
+                    }
+
+                    let body = body.freeze();
+                    errors.extend(check_body(&operations.definitions, param, &body));
+                    req.set_payload(Payload::from(body));
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(RequestValidationError(errors).into());
+            }
+
+            match op.timeout {
+                // Handlers which don't respond within the timeout of their operations
+                // are cancelled.
+                Some(timeout) => {
+                    match actix_web::rt::time::timeout(timeout, service.call(req)).await {
+                        Ok(resp) => resp,
+                        Err(_) => Err(ErrorGatewayTimeout("operation timed out")),
+                    }
+                }
+                None => service.call(req).await,
+            }
+        })
+    }
+}
+
+/// What to do with responses which don't conform to the spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MismatchAction {
+    /// Log the offending values (as warnings).
+    Log,
+    /// Panic with the offending values (for failing tests).
+    Panic,
+}
+
+/// Middleware (transform) validating the JSON bodies of responses against the
+/// schemas documented for their status codes. This only validates responses in
+/// debug builds (i.e., with `debug_assertions`), because their bodies are buffered.
+pub struct ResponseValidation {
+    spec: Arc<RwLock<DefaultApiRaw>>,
+    action: MismatchAction,
+}
+
+impl ResponseValidation {
+    /// Validates responses against the given spec (which is read when the app
+    /// is started by the server, i.e., once it's been built).
+    pub fn new(spec: Arc<RwLock<DefaultApiRaw>>, action: MismatchAction) -> Self {
+        ResponseValidation { spec, action }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseValidation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = ResponseValidationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        fut_ok(ResponseValidationMiddleware {
+            service: Rc::new(service),
+            operations: Rc::new(Operations::from_spec(&self.spec.read())),
+            action: self.action,
+        })
+    }
+}
+
+/// Service validating the responses of the wrapped service.
+pub struct ResponseValidationMiddleware<S> {
+    service: Rc<S>,
+    operations: Rc<Operations>,
+    action: MismatchAction,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseValidationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let operations = self.operations.clone();
+        let action = self.action;
+        let method = serde_json::from_value(Value::String(req.method().as_str().to_lowercase()));
+        let path = req.path().to_owned();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            if !cfg!(debug_assertions) {
+                return Ok(res.map_into_left_body());
+            }
+
+            let op = match method.ok().and_then(|m| operations.find(m, &path)) {
+                Some((op, _)) => op,
+                None => return Ok(res.map_into_left_body()),
+            };
+
+            let status = res.status().as_u16();
+            let is_json = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.contains("json"))
+                .unwrap_or(false);
+            let schema = op
+                .responses
+                .get(&status.to_string())
+                .or_else(|| op.responses.get("default"));
+            let schema = match schema {
+                Some(s) if is_json => s,
+                _ => return Ok(res.map_into_left_body()),
+            };
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let body = to_bytes(body).await.map_err(|e| {
+                let e: Box<dyn std::error::Error> = e.into();
+                ErrorInternalServerError(e.to_string())
+            })?;
+
+            let errors = match serde_json::from_slice(&body) {
+                Ok(value) => Validator::new(&operations.definitions)
+                    .validate(schema, &value)
+                    .err()
+                    .map(|e| {
+                        e.iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    }),
+                Err(e) => Some(format!("invalid JSON: {}", e)),
+            };
+            if let Some(errors) = errors {
+                let message = format!(
+                    "response ({}) of {} doesn't conform to the spec: {}",
+                    status, op.name, errors
+                );
+                match action {
+                    MismatchAction::Log => log::warn!("{}", message),
+                    MismatchAction::Panic => panic!("{}", message),
+                }
+            }
+
+            let res = ServiceResponse::new(req, res.set_body(body));
+            Ok(res.map_into_right_body())
+        })
+    }
+}
+
+/// Documented operations (with their parameters resolved), for matching requests.
+pub(crate) struct Operations {
+    definitions: BTreeMap<String, DefaultSchemaRaw>,
+    /// Segments of the path templates (prefixed with the base path) and their operations.
+    paths: Vec<(Vec<String>, BTreeMap<HttpMethod, Operation>)>,
+}
+
+/// Parameters and response schemas of an operation.
+pub(crate) struct Operation {
+    /// Method and path template (for messages).
+    name: String,
+    parameters: Vec<DefaultParameterRaw>,
+    body: Option<DefaultParameterRaw>,
+    /// Schemas of the responses (by their status codes, or `default`).
+    responses: BTreeMap<String, DefaultSchemaRaw>,
+    /// Timeout of the handler (from the `x-timeout` extension).
+    timeout: Option<Duration>,
+    /// Maximum size of request bodies (from the `x-max-body-size` extension).
+    max_body_size: Option<usize>,
+}
+
+impl Operations {
+    pub(crate) fn from_spec(api: &DefaultApiRaw) -> Self {
+        let resolve =
+            |params: &[Either<Reference, DefaultParameterRaw>]| -> Vec<DefaultParameterRaw> {
+                params
+                    .iter()
+                    .filter_map(|p| match p {
+                        Either::Right(p) => Some(p.clone()),
+                        Either::Left(r) => api
+                            .parameters
+                            .get(r.reference.trim_start_matches("#/parameters/"))
+                            .cloned(),
+                    })
+                    .collect()
+            };
+
+        let base = api.base_path.as_deref().unwrap_or("").trim_end_matches('/');
+        let mut paths = vec![];
+        for (path, item) in &api.paths {
+            let shared = resolve(&item.parameters);
+            let mut methods = BTreeMap::new();
+            for (method, op) in &item.methods {
+                // Parameters of operations override the ones shared by the path.
+                let mut parameters = resolve(&op.parameters);
+                for param in &shared {
+                    if !parameters
+                        .iter()
+                        .any(|p| p.name == param.name && p.in_ == param.in_)
+                    {
+                        parameters.push(param.clone());
+                    }
+                }
+
+                let body = parameters
+                    .iter()
+                    .position(|p| p.in_ == ParameterIn::Body)
+                    .map(|i| parameters.remove(i));
+                let responses = op
+                    .responses
+                    .iter()
+                    .filter_map(|(code, r)| {
+                        let response = match r {
+                            Either::Right(r) => Some(r),
+                            Either::Left(r) => api
+                                .responses
+                                .get(r.reference.trim_start_matches("#/responses/")),
+                        };
+                        Some((code.clone(), response?.schema.clone()?))
+                    })
+                    .collect();
+                methods.insert(
+                    *method,
+                    Operation {
+                        name: format!("{} {}", method.to_string().to_uppercase(), path),
+                        parameters,
+                        body,
+                        responses,
+                        timeout: op
+                            .extensions
+                            .get("x-timeout")
+                            .and_then(|t| t.as_str())
+                            .and_then(parse_duration),
+                        max_body_size: op
+                            .extensions
+                            .get("x-max-body-size")
+                            .and_then(|s| s.as_u64())
+                            .map(|s| s as usize),
+                    },
+                );
+            }
+
+            paths.push((segments(&format!("{}{}", base, path)), methods));
+        }
+
+        // Static segments take precedence over templated ones (i.e., `/pets/mine`
+        // over `/pets/{id}`), like in most routers.
+        paths.sort_by_key(|(s, _)| s.iter().map(|s| s.starts_with('{')).collect::<Vec<_>>());
+        Operations {
+            definitions: api.definitions.clone(),
+            paths,
+        }
+    }
+
+    /// Returns the operation matching the given method and path, along with the
+    /// (percent-decoded) values of the path parameters.
+    pub(crate) fn find(
+        &self,
+        method: HttpMethod,
+        path: &str,
+    ) -> Option<(&Operation, BTreeMap<&str, String>)> {
+        let actual = segments(path);
+        self.paths.iter().find_map(|(template, methods)| {
+            if template.len() != actual.len() {
+                return None;
+            }
+
+            let mut values = BTreeMap::new();
+            for (t, a) in template.iter().zip(&actual) {
+                if t.starts_with('{') && t.ends_with('}') {
+                    values.insert(t.trim_start_matches('{').trim_end_matches('}'), decode(a));
+                } else if t != a {
+                    return None;
+                }
+            }
+
+            methods.get(&method).map(|op| (op, values))
+        })
+    }
+}
+
+impl Operation {
+    /// Checks the path, query and header parameters of the request.
+    fn check_parameters(
+        &self,
+        definitions: &BTreeMap<String, DefaultSchemaRaw>,
+        req: &ServiceRequest,
+        path_values: &BTreeMap<&str, String>,
+        coerce_parameters: bool,
+    ) -> Vec<RequestError> {
+        let validator = Validator::new(definitions).coerce_parameters(coerce_parameters);
+        let query = Query::<Vec<(String, String)>>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or_default();
+
+        let mut errors = vec![];
+        for param in &self.parameters {
+            let values: Vec<&str> = match param.in_ {
+                ParameterIn::Path => path_values
+                    .get(param.name.as_str())
+                    .map(String::as_str)
+                    .into_iter()
+                    .collect(),
+                ParameterIn::Query => query
+                    .iter()
+                    .filter(|(k, _)| *k == param.name)
+                    .map(|(_, v)| v.as_str())
+                    .collect(),
+                ParameterIn::Header => req
+                    .headers()
+                    .get_all(param.name.as_str())
+                    .filter_map(|v| v.to_str().ok())
+                    .collect(),
+                // Forms aren't buffered.
+                _ => continue,
+            };
+
+            if let Err(e) = validator.validate_parameter(param, &values) {
+                errors.extend(e.into_iter().map(|error| RequestError {
+                    location: param.in_,
+                    name: param.name.clone(),
+                    error,
+                }));
+            }
+        }
+
+        errors
+    }
+}
+
+/// Checks the (JSON) body against the schema of the body parameter.
+fn check_body(
+    definitions: &BTreeMap<String, DefaultSchemaRaw>,
+    param: &DefaultParameterRaw,
+    body: &[u8],
+) -> Vec<RequestError> {
+    let error = |path: String, message: String| RequestError {
+        location: ParameterIn::Body,
+        name: param.name.clone(),
+        error: SchemaError { path, message },
+    };
+
+    if body.is_empty() {
+        return if param.required {
+            vec![error("".into(), "missing required body".into())]
+        } else {
+            vec![]
+        };
+    }
+
+    let value: Value = match serde_json::from_slice(body) {
+        Ok(v) => v,
+        Err(e) => return vec![error("".into(), format!("invalid JSON: {}", e))],
+    };
+
+    match &param.schema {
+        Some(schema) => match Validator::new(definitions).validate(schema, &value) {
+            Ok(()) => vec![],
+            Err(e) => e.into_iter().map(|e| error(e.path, e.message)).collect(),
+        },
+        None => vec![],
+    }
+}
+
+/// Returns the (non-empty) segments of the given path.
+fn segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Decodes the percent-encoded bytes in the given path segment.
+fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
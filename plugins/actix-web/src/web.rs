@@ -1,29 +1,43 @@
 //! Proxy module for [`actix_web::web`](https://docs.rs/actix-web/*/actix_web/web/index.html).
 #![cfg(any(feature = "actix2", feature = "actix3", feature = "actix4"))]
 
-#[cfg(feature = "actix3")]
+#[cfg(any(feature = "actix3", feature = "actix4"))]
 pub use actix_web::web::ReqData;
 pub use actix_web::web::{
-    block, service, to, Bytes, BytesMut, Data, Form, FormConfig, HttpRequest, HttpResponse, Json,
-    JsonConfig, Path, PathConfig, Payload, PayloadConfig, Query, QueryConfig,
+    block, service, to, Bytes, BytesMut, Data, Form, FormConfig, Json, JsonConfig, Path,
+    PathConfig, Payload, PayloadConfig, Query, QueryConfig,
+};
+pub use actix_web::{HttpRequest, HttpResponse};
+pub use paperclip_core::v2::{
+    Cookie, FieldsParam, FilterParam, Header, QueryMap, SessionAuth, SessionCookie, SortParam,
 };
 
 use crate::Mountable;
-use actix_service::ServiceFactory;
+use actix_service::{Service, ServiceFactory};
 use actix_web::{
-    dev::{AppService, Handler, HttpServiceFactory, ServiceRequest, ServiceResponse, Transform},
+    dev::{
+        AppService, Extensions, Handler, HttpServiceFactory, ServiceRequest, ServiceResponse,
+        Transform,
+    },
     guard::Guard,
     http::Method,
     Error, FromRequest, Responder,
 };
 use paperclip_core::v2::{
+    add_struct_parameters,
     models::{
-        DefaultOperationRaw, DefaultPathItemRaw, DefaultSchemaRaw, HttpMethod, SecurityScheme,
+        DefaultApiRaw, DefaultOperationRaw, DefaultParameterRaw, DefaultPathItemRaw,
+        DefaultSchemaRaw, Either, HttpMethod, ParameterIn, SecurityScheme, Tag,
     },
-    schema::Apiv2Operation,
+    schema::{Apiv2Operation, Apiv2Schema},
+    OperationModifier,
 };
+#[cfg(feature = "v3")]
+use paperclip_core::v3::native::SchemaMap;
+
+use futures::future::{ok as fut_ok, Ready};
 
-use std::{collections::BTreeMap, fmt::Debug, future::Future, mem};
+use std::{collections::BTreeMap, fmt::Debug, future::Future, mem, rc::Rc};
 
 const METHODS: &[Method] = &[
     Method::GET,
@@ -40,9 +54,13 @@ const METHODS: &[Method] = &[
 /// Wrapper for [`actix_web::Resource`](https://docs.rs/actix-web/*/actix_web/struct.Resource.html)
 pub struct Resource<R = actix_web::Resource> {
     path: String,
+    hidden: bool,
     operations: BTreeMap<HttpMethod, DefaultOperationRaw>,
+    handlers: BTreeMap<HttpMethod, &'static str>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
+    #[cfg(feature = "v3")]
+    schemas_v3: SchemaMap,
     inner: R,
 }
 
@@ -51,9 +69,13 @@ impl Resource {
     pub fn new(path: &str) -> Resource {
         Resource {
             path: path.into(),
+            hidden: false,
             operations: BTreeMap::new(),
+            handlers: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             inner: actix_web::Resource::new(path),
         }
     }
@@ -83,10 +105,19 @@ impl<T> Mountable for Resource<T> {
         mem::take(&mut self.operations)
     }
 
+    fn handlers(&mut self) -> BTreeMap<HttpMethod, &'static str> {
+        mem::take(&mut self.handlers)
+    }
+
     fn definitions(&mut self) -> BTreeMap<String, DefaultSchemaRaw> {
         mem::take(&mut self.definitions)
     }
 
+    #[cfg(feature = "v3")]
+    fn schemas_v3(&mut self) -> SchemaMap {
+        mem::take(&mut self.schemas_v3)
+    }
+
     fn security_definitions(&mut self) -> BTreeMap<String, SecurityScheme> {
         mem::take(&mut self.security)
     }
@@ -118,11 +149,32 @@ where
         self
     }
 
+    /// Leaves the operations of this resource (including the ones added after
+    /// this call) out of the spec, while the resource is still mounted. This is
+    /// useful for internal endpoints (like health checks).
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self.operations.clear();
+        self.definitions.clear();
+        self.security.clear();
+        #[cfg(feature = "v3")]
+        self.schemas_v3.clear();
+        self
+    }
+
     /// Wrapper for [`actix_web::Resource::route`](https://docs.rs/actix-web/*/actix_web/struct.Resource.html#method.route).
     pub fn route(mut self, route: Route) -> Self {
         let w = RouteWrapper::from(&self.path, route);
+        if self.hidden {
+            self.inner = self.inner.route(w.inner);
+            return self;
+        }
+
         self.operations.extend(w.operations.into_iter());
+        self.handlers.extend(w.handlers.into_iter());
         self.definitions.extend(w.definitions.into_iter());
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(w.schemas_v3.into_iter());
         SecurityScheme::append_map(w.security, &mut self.security);
         self.inner = self.inner.route(w.inner);
         self
@@ -146,15 +198,18 @@ where
     }
 
     /// Wrapper for [`actix_web::Resource::to`](https://docs.rs/actix-web/*/actix_web/struct.Resource.html#method.to).
-    pub fn to<F, I, R, U>(mut self, handler: F) -> Self
+    pub fn to<F, Args>(mut self, handler: F) -> Self
     where
-        F: Handler<I, R> + 'static,
-        I: FromRequest + 'static,
-        R: Apiv2Operation + Future<Output = U> + 'static,
-        U: Responder + 'static,
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Future: Apiv2Operation,
+        F::Output: Responder + 'static,
     {
-        self.update_from_handler::<R>();
-        self.inner = self.inner.to(handler);
+        self.update_from_handler::<F::Future>();
+        let route = actix_web::Route::new().to(handler);
+        self.inner = self
+            .inner
+            .route(limit_body_size(route, F::Future::max_body_size()));
         self
     }
 
@@ -177,18 +232,22 @@ where
     >
     where
         M: Transform<
-            T::Service,
-            ServiceRequest,
-            Response = ServiceResponse,
-            Error = Error,
-            InitError = (),
-        >,
+                T::Service,
+                ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+                InitError = (),
+            > + 'static,
     {
         Resource {
             path: self.path,
+            hidden: self.hidden,
             operations: self.operations,
+            handlers: self.handlers,
             definitions: self.definitions,
             security: self.security,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
             inner: self.inner.wrap(mw),
         }
     }
@@ -211,14 +270,18 @@ where
         >,
     >
     where
-        F: Fn(ServiceRequest, &T::Service) -> R + Clone,
+        F: Fn(ServiceRequest, &T::Service) -> R + Clone + 'static,
         R: Future<Output = Result<ServiceResponse, Error>>,
     {
         Resource {
             path: self.path,
+            hidden: self.hidden,
             operations: self.operations,
+            handlers: self.handlers,
             definitions: self.definitions,
             security: self.security,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
             inner: self.inner.wrap_fn(mw),
         }
     }
@@ -247,13 +310,22 @@ where
     where
         U: Apiv2Operation,
     {
+        if self.hidden || !U::is_visible() {
+            return;
+        }
+
         let mut op = U::operation();
         op.set_parameter_names_from_path_template(&self.path);
         for method in METHODS {
             self.operations.insert(method.into(), op.clone());
+            if let Some(handler) = U::handler() {
+                self.handlers.insert(method.into(), handler);
+            }
         }
 
         self.definitions.extend(U::definitions().into_iter());
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(U::schemas_v3().into_iter());
         SecurityScheme::append_map(U::security_definitions(), &mut self.security);
     }
 }
@@ -269,8 +341,20 @@ pub fn resource(path: &str) -> Resource {
 pub struct Scope<S = actix_web::Scope> {
     path: String,
     path_map: BTreeMap<String, DefaultPathItemRaw>,
+    /// Paths of the handlers of the operations in `path_map`.
+    handler_map: BTreeMap<String, BTreeMap<HttpMethod, &'static str>>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
+    /// Tags of this scope (applied to its operations), and the tags of the nested scopes.
+    tags: Vec<Tag>,
+    /// Names of the tags of this scope.
+    scope_tags: Vec<String>,
+    /// Security requirements of this scope (added to its operations).
+    scope_security: Vec<BTreeMap<String, Vec<String>>>,
+    /// Path parameters of this scope (added to its operations).
+    scope_parameters: Vec<DefaultParameterRaw>,
+    #[cfg(feature = "v3")]
+    schemas_v3: SchemaMap,
     inner: Option<S>,
 }
 
@@ -280,8 +364,15 @@ impl Scope {
         Scope {
             path: path.into(),
             path_map: BTreeMap::new(),
+            handler_map: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
+            tags: Vec::new(),
+            scope_tags: Vec::new(),
+            scope_security: Vec::new(),
+            scope_parameters: Vec::new(),
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             inner: Some(actix_web::Scope::new(path)),
         }
     }
@@ -409,18 +500,25 @@ where
     >
     where
         M: Transform<
-            T::Service,
-            ServiceRequest,
-            Response = ServiceResponse,
-            Error = Error,
-            InitError = (),
-        >,
+                T::Service,
+                ServiceRequest,
+                Response = ServiceResponse,
+                Error = Error,
+                InitError = (),
+            > + 'static,
     {
         Scope {
             path: self.path,
             path_map: self.path_map,
+            handler_map: self.handler_map,
             definitions: self.definitions,
             security: self.security,
+            tags: self.tags,
+            scope_tags: self.scope_tags,
+            scope_security: self.scope_security,
+            scope_parameters: self.scope_parameters,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
             inner: self.inner.take().map(|s| s.wrap(mw)),
         }
     }
@@ -443,24 +541,193 @@ where
         >,
     >
     where
-        F: Fn(ServiceRequest, &T::Service) -> R + Clone,
+        F: Fn(ServiceRequest, &T::Service) -> R + Clone + 'static,
         R: Future<Output = Result<ServiceResponse, Error>>,
     {
         Scope {
             path: self.path,
             path_map: self.path_map,
+            handler_map: self.handler_map,
             definitions: self.definitions,
             security: self.security,
+            tags: self.tags,
+            scope_tags: self.scope_tags,
+            scope_security: self.scope_security,
+            scope_parameters: self.scope_parameters,
+            #[cfg(feature = "v3")]
+            schemas_v3: self.schemas_v3,
             inner: self.inner.take().map(|s| s.wrap_fn(mw)),
         }
     }
 
+    /// Tags all the operations in this scope (including the ones in nested scopes,
+    /// and the ones added after this call) with the given tags (like `&["users"]`).
+    /// The tags are also added to the top-level `tags` of the spec, so descriptions
+    /// can be given by passing `Tag` objects instead of names.
+    pub fn tags<G>(mut self, tags: &[G]) -> Self
+    where
+        G: Clone + Into<Tag>,
+    {
+        let tags = tags.iter().cloned().map(Into::into).collect::<Vec<Tag>>();
+        for tag in &tags {
+            if !self.scope_tags.contains(&tag.name) {
+                self.scope_tags.push(tag.name.clone());
+            }
+        }
+
+        Tag::append_list(tags, &mut self.tags);
+        self
+    }
+
+    /// Adds the security requirement of the given type (usually deriving `Apiv2Security`)
+    /// to all the operations in this scope (including the ones in nested scopes, and the
+    /// ones added after this call), for scopes whose routes are protected by middleware
+    /// instead of extractors. The security scheme is added to the spec.
+    pub fn security<S>(mut self) -> Self
+    where
+        S: OperationModifier,
+    {
+        let mut op = DefaultOperationRaw::default();
+        S::update_security(&mut op);
+        for requirement in op.security {
+            if !self.scope_security.contains(&requirement) {
+                self.scope_security.push(requirement);
+            }
+        }
+
+        S::update_security_definitions(&mut self.security);
+        self
+    }
+
+    /// Documents the fields of the given type (usually deriving `Apiv2Schema`) as path
+    /// parameters of all the operations in this scope (including the ones in nested scopes,
+    /// and the ones added after this call), for the parameters in the path of this scope
+    /// (like `/t/{tenant_id}`) which aren't extracted by all the handlers.
+    ///
+    /// The prefix (and its parameters) can be hidden from the published spec with
+    /// `App::hide_path_prefix`.
+    pub fn path_parameters<P>(mut self) -> Self
+    where
+        P: Apiv2Schema,
+    {
+        let mut op = DefaultOperationRaw::default();
+        add_struct_parameters::<P>(&mut op, ParameterIn::Path);
+        for param in op.parameters.iter().filter_map(Either::right) {
+            if !self.scope_parameters.iter().any(|p| p.name == param.name) {
+                self.scope_parameters.push(param.clone());
+            }
+        }
+
+        self
+    }
+
+    /// Returns a standalone spec with the operations and definitions recorded by
+    /// this scope (so far), so that the owners of a scope can publish their part of
+    /// the API separately. Only the definitions reachable from the operations are
+    /// included, and the paths include the path of this scope.
+    ///
+    /// **NOTE:** This must be called before the scope is mounted.
+    pub fn spec_fragment(&self) -> DefaultApiRaw {
+        let mut api = DefaultApiRaw {
+            paths: self.path_map.clone(),
+            definitions: self.definitions.clone(),
+            security_definitions: self.security.clone(),
+            tags: self.tags.clone(),
+            ..Default::default()
+        };
+
+        for map in api.paths.values_mut() {
+            self.apply_scope(map);
+        }
+
+        if cfg!(feature = "normalize") {
+            for map in api.paths.values_mut() {
+                map.normalize();
+            }
+        }
+
+        api.retain_reachable_definitions();
+        api
+    }
+
+    /// Documents the operations of another service (say, one that's proxied by this
+    /// service) under the given path in this scope, so that aggregating services
+    /// don't have to duplicate the annotations of the proxied routes.
+    ///
+    /// The paths of the given spec are relative to its `basePath`. Global parameters
+    /// and responses are inlined in the operations using them, and definitions which
+    /// conflict with the ones already recorded by this scope are renamed (with
+    /// numeric suffixes), along with their references.
+    ///
+    /// **NOTE:** This only affects spec generation, so routing the requests is still
+    /// up to the services added to this scope.
+    pub fn proxy_spec(mut self, spec: DefaultApiRaw, path: &str) -> Self {
+        let prefix = self.path.clone() + path.trim_end_matches('/');
+        let (paths, definitions, security) = import_spec(spec, &self.definitions);
+        for (path, item) in paths {
+            let op_map = self
+                .path_map
+                .entry(prefix.clone() + &path)
+                .or_insert_with(Default::default);
+            op_map.methods.extend(item.methods.into_iter());
+        }
+
+        self.definitions.extend(definitions.into_iter());
+        SecurityScheme::append_map(security, &mut self.security);
+        self
+    }
+
+    /// Documents the operations of an upstream service which is proxied through the
+    /// given catch-all route (like `/upstream/{tail:.*}`) in this scope. Operations
+    /// are documented under the path of the route without its last (templated)
+    /// segment, and their paths are relative to the `basePath` of the upstream unless
+    /// specified otherwise (see `UpstreamSpec`).
+    ///
+    /// **NOTE:** The handler of the catch-all route should be skipped from the spec
+    /// (with `#[api_v2_operation(skip)]`).
+    pub fn proxy_upstream(self, route: &str, upstream: UpstreamSpec) -> Self {
+        let prefix = match route.rfind('/') {
+            Some(i) if route[i + 1..].starts_with('{') && route.ends_with('}') => &route[..i],
+            _ => route,
+        };
+
+        let UpstreamSpec {
+            mut spec,
+            base_path,
+            strip_prefix,
+        } = upstream;
+        let base = match (base_path, &spec.base_path) {
+            (true, Some(p)) => p.trim_end_matches('/').to_owned(),
+            _ => String::new(),
+        };
+        let strip = strip_prefix
+            .as_deref()
+            .map(|p| p.trim_end_matches('/'))
+            .unwrap_or("");
+
+        spec.paths = mem::take(&mut spec.paths)
+            .into_iter()
+            .filter_map(|(path, item)| {
+                let path = base.clone() + &path;
+                match path.strip_prefix(strip) {
+                    Some("") => Some(("/".into(), item)),
+                    Some(p) if p.starts_with('/') => Some((p.into(), item)),
+                    _ => None,
+                }
+            })
+            .collect();
+        spec.retain_reachable_definitions();
+        self.proxy_spec(spec, prefix)
+    }
+
     /// Updates `self` using the given `Mountable` object.
     fn update_from_mountable<M>(&mut self, factory: &mut M)
     where
         M: Mountable,
     {
         self.definitions.extend(factory.definitions().into_iter());
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(factory.schemas_v3().into_iter());
         let mut path_map = BTreeMap::new();
         factory.update_operations(&mut path_map);
         for (path, mut map) in path_map {
@@ -472,7 +739,45 @@ where
             self.path_map.insert(p.clone(), map);
         }
 
+        let mut handler_map = BTreeMap::new();
+        factory.update_handlers(&mut handler_map);
+        for (path, handlers) in handler_map {
+            self.handler_map.insert(self.path.clone() + &path, handlers);
+        }
+
         SecurityScheme::append_map(factory.security_definitions(), &mut self.security);
+        Tag::append_list(factory.tags(), &mut self.tags);
+    }
+}
+
+impl<T> Scope<T> {
+    /// Adds the tags, security requirements and path parameters of this scope to the
+    /// operations in the given path item.
+    fn apply_scope(&self, item: &mut DefaultPathItemRaw) {
+        for op in item.methods.values_mut() {
+            // Parameters in the path of the scope come before the ones in the paths of
+            // its routes.
+            let params = self.scope_parameters.iter().filter(|p| {
+                !op.parameters
+                    .iter()
+                    .filter_map(Either::right)
+                    .any(|q| q.name == p.name && q.in_ == p.in_)
+            });
+            let params = params.cloned().map(Either::Right).collect::<Vec<_>>();
+            op.parameters.splice(0..0, params);
+
+            for tag in &self.scope_tags {
+                if !op.tags.contains(tag) {
+                    op.tags.push(tag.clone());
+                }
+            }
+
+            for requirement in &self.scope_security {
+                if !op.security.contains(requirement) {
+                    op.security.push(requirement.clone());
+                }
+            }
+        }
     }
 }
 
@@ -493,12 +798,146 @@ impl<T> Mountable for Scope<T> {
         mem::take(&mut self.definitions)
     }
 
+    fn tags(&mut self) -> Vec<Tag> {
+        mem::take(&mut self.tags)
+    }
+
+    #[cfg(feature = "v3")]
+    fn schemas_v3(&mut self) -> SchemaMap {
+        mem::take(&mut self.schemas_v3)
+    }
+
     fn update_operations(&mut self, map: &mut BTreeMap<String, DefaultPathItemRaw>) {
-        for (path, item) in mem::take(&mut self.path_map) {
+        for (path, mut item) in mem::take(&mut self.path_map) {
+            self.apply_scope(&mut item);
             let op_map = map.entry(path).or_insert_with(Default::default);
             op_map.methods.extend(item.methods.into_iter());
         }
     }
+
+    fn update_handlers(&mut self, map: &mut BTreeMap<String, BTreeMap<HttpMethod, &'static str>>) {
+        for (path, handlers) in mem::take(&mut self.handler_map) {
+            map.entry(path).or_default().extend(handlers);
+        }
+    }
+}
+
+/// Spec of an upstream service proxied through a catch-all route (see
+/// [`Scope::proxy_upstream`](struct.Scope.html#method.proxy_upstream)).
+pub struct UpstreamSpec {
+    spec: DefaultApiRaw,
+    base_path: bool,
+    strip_prefix: Option<String>,
+}
+
+impl UpstreamSpec {
+    /// Creates a new upstream spec. Its paths are documented relative to its
+    /// `basePath`, for proxies forwarding requests to the base URL of the upstream.
+    pub fn new(spec: DefaultApiRaw) -> Self {
+        UpstreamSpec {
+            spec,
+            base_path: false,
+            strip_prefix: None,
+        }
+    }
+
+    /// Includes the `basePath` of the upstream in the documented paths, for proxies
+    /// forwarding requests to the root of the upstream.
+    pub fn with_base_path(mut self) -> Self {
+        self.base_path = true;
+        self
+    }
+
+    /// Strips the given prefix from the paths of the upstream (after adding the
+    /// `basePath`, if needed), for proxies adding that prefix when forwarding requests.
+    /// Operations in other paths aren't documented.
+    pub fn strip_prefix(mut self, prefix: &str) -> Self {
+        self.strip_prefix = Some(prefix.into());
+        self
+    }
+}
+
+/// Returns the paths (with the global parameters and responses inlined), definitions
+/// and security definitions of the given spec, renaming the definitions which conflict
+/// with the existing ones (and updating their references).
+fn import_spec(
+    spec: DefaultApiRaw,
+    existing: &BTreeMap<String, DefaultSchemaRaw>,
+) -> (
+    BTreeMap<String, DefaultPathItemRaw>,
+    BTreeMap<String, DefaultSchemaRaw>,
+    BTreeMap<String, SecurityScheme>,
+) {
+    let mut renames = BTreeMap::new();
+    for (name, schema) in &spec.definitions {
+        match existing.get(name) {
+            Some(s) if s.fingerprint() != schema.fingerprint() => {
+                let new_name = (2..)
+                    .map(|i| format!("{}{}", name, i))
+                    .find(|n| !existing.contains_key(n) && !spec.definitions.contains_key(n))
+                    .expect("finding name for definition");
+                renames.insert(format!("#/definitions/{}", name), new_name);
+            }
+            _ => (),
+        }
+    }
+
+    let mut globals = serde_json::Map::new();
+    for (name, param) in &spec.parameters {
+        let value = serde_json::to_value(param).expect("serializing parameter");
+        globals.insert(format!("#/parameters/{}", name), value);
+    }
+    for (name, resp) in &spec.responses {
+        let value = serde_json::to_value(resp).expect("serializing response");
+        globals.insert(format!("#/responses/{}", name), value);
+    }
+
+    let mut paths = serde_json::to_value(&spec.paths).expect("serializing paths");
+    rewrite_refs(&mut paths, &renames, &globals);
+    let paths = serde_json::from_value(paths).expect("deserializing paths");
+
+    let definitions = spec
+        .definitions
+        .into_iter()
+        .map(|(name, schema)| {
+            let mut value = serde_json::to_value(&schema).expect("serializing schema");
+            rewrite_refs(&mut value, &renames, &globals);
+            let schema = serde_json::from_value(value).expect("deserializing schema");
+            let key = format!("#/definitions/{}", name);
+            (renames.get(&key).cloned().unwrap_or(name), schema)
+        })
+        .collect();
+
+    (paths, definitions, spec.security_definitions)
+}
+
+/// Recursively renames the references to definitions and inlines the references
+/// to global parameters and responses in the value.
+fn rewrite_refs(
+    value: &mut serde_json::Value,
+    renames: &BTreeMap<String, String>,
+    globals: &serde_json::Map<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::String(r)) = map.get("$ref") {
+                if let Some(global) = globals.get(r) {
+                    *value = global.clone();
+                    return rewrite_refs(value, renames, globals);
+                } else if let Some(name) = renames.get(r) {
+                    let r = format!("#/definitions/{}", name);
+                    map.insert("$ref".into(), r.into());
+                }
+            }
+
+            map.values_mut()
+                .for_each(|v| rewrite_refs(v, renames, globals));
+        }
+        serde_json::Value::Array(values) => values
+            .iter_mut()
+            .for_each(|v| rewrite_refs(v, renames, globals)),
+        _ => (),
+    }
 }
 
 /// Wrapper for [`actix_web::web::scope`](https://docs.rs/actix-web/*/actix_web/web/fn.scope.html).
@@ -511,9 +950,13 @@ pub fn scope(path: &str) -> Scope {
 /// Wrapper for [`actix_web::Route`](https://docs.rs/actix-web/*/actix_web/struct.Route.html)
 pub struct Route {
     method: Option<HttpMethod>,
+    hidden: bool,
     operation: Option<DefaultOperationRaw>,
+    handler: Option<&'static str>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
+    #[cfg(feature = "v3")]
+    schemas_v3: SchemaMap,
     inner: actix_web::Route,
 }
 
@@ -537,9 +980,13 @@ impl Route {
     pub fn new() -> Route {
         Route {
             method: None,
+            hidden: false,
             operation: None,
+            handler: None,
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             inner: actix_web::Route::new(),
         }
     }
@@ -559,22 +1006,107 @@ impl Route {
         self
     }
 
+    /// Leaves the operation of this route out of the spec, while the route is
+    /// still mounted. This is useful for internal endpoints (like health checks).
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self.operation = None;
+        self.handler = None;
+        self.definitions.clear();
+        self.security.clear();
+        #[cfg(feature = "v3")]
+        self.schemas_v3.clear();
+        self
+    }
+
     /// Wrapper for [`actix_web::Route::to`](https://docs.rs/actix-web/*/actix_web/struct.Route.html#method.to)
-    pub fn to<F, I, R, U>(mut self, handler: F) -> Self
+    pub fn to<F, Args>(mut self, handler: F) -> Self
     where
-        F: Handler<I, R>,
-        I: FromRequest + 'static,
-        R: Apiv2Operation + Future<Output = U> + 'static,
-        U: Responder + 'static,
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Future: Apiv2Operation,
+        F::Output: Responder + 'static,
     {
-        self.operation = Some(R::operation());
-        self.definitions = R::definitions();
-        self.security = R::security_definitions();
-        self.inner = self.inner.to(handler);
+        if !self.hidden && F::Future::is_visible() {
+            self.operation = Some(F::Future::operation());
+            self.handler = F::Future::handler();
+            self.definitions = F::Future::definitions();
+            #[cfg(feature = "v3")]
+            {
+                self.schemas_v3 = F::Future::schemas_v3();
+            }
+            self.security = F::Future::security_definitions();
+        }
+
+        self.inner = limit_body_size(self.inner.to(handler), F::Future::max_body_size());
         self
     }
 }
 
+/// Limits the size of request bodies (in bytes) accepted by the extractors
+/// (`Json`, `Form`, `Bytes`, etc.) of the given route.
+fn limit_body_size(route: actix_web::Route, limit: Option<usize>) -> actix_web::Route {
+    match limit {
+        Some(limit) => route.wrap(BodyLimit::new(limit)),
+        None => route,
+    }
+}
+
+/// Route middleware (transform) adding the extractor configs for a body size limit
+/// to the app data of requests. Unlike the app data of resources, this doesn't
+/// affect the other routes of the resource.
+struct BodyLimit(Rc<Extensions>);
+
+impl BodyLimit {
+    fn new(limit: usize) -> Self {
+        let mut data = Extensions::new();
+        data.insert(JsonConfig::default().limit(limit));
+        data.insert(FormConfig::default().limit(limit));
+        data.insert(PayloadConfig::new(limit));
+        BodyLimit(Rc::new(data))
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for BodyLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = BodyLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        fut_ok(BodyLimitMiddleware {
+            service,
+            data: self.0.clone(),
+        })
+    }
+}
+
+/// Service adding the extractor configs to the requests of the wrapped route.
+struct BodyLimitMiddleware<S> {
+    service: S,
+    data: Rc<Extensions>,
+}
+
+impl<S> Service<ServiceRequest> for BodyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = S::Future;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        req.add_data_container(self.data.clone());
+        self.service.call(req)
+    }
+}
+
 /// Wrapper for [`actix_web::web::method`](https://docs.rs/actix-web/*/actix_web/web/fn.method.html).
 pub fn method(method: Method) -> Route {
     Route::new().method(method)
@@ -625,8 +1157,11 @@ pub fn head() -> Route {
 pub(crate) struct RouteWrapper<S> {
     path: S,
     pub(crate) operations: BTreeMap<HttpMethod, DefaultOperationRaw>,
+    pub(crate) handlers: BTreeMap<HttpMethod, &'static str>,
     pub(crate) definitions: BTreeMap<String, DefaultSchemaRaw>,
     pub(crate) security: BTreeMap<String, SecurityScheme>,
+    #[cfg(feature = "v3")]
+    pub(crate) schemas_v3: SchemaMap,
     pub(crate) inner: actix_web::Route,
 }
 
@@ -636,14 +1171,18 @@ where
 {
     pub(crate) fn from(path: S, route: Route) -> Self {
         let mut operations = BTreeMap::new();
+        let mut handlers = BTreeMap::new();
         if let Some(mut op) = route.operation {
             op.set_parameter_names_from_path_template(path.as_ref());
 
-            if let Some(meth) = route.method {
-                operations.insert(meth, op);
-            } else {
-                for method in METHODS {
-                    operations.insert(method.into(), op.clone());
+            let methods = match route.method {
+                Some(meth) => vec![meth],
+                None => METHODS.iter().map(HttpMethod::from).collect(),
+            };
+            for method in methods {
+                operations.insert(method, op.clone());
+                if let Some(handler) = route.handler {
+                    handlers.insert(method, handler);
                 }
             }
         }
@@ -651,8 +1190,11 @@ where
         RouteWrapper {
             path,
             operations,
+            handlers,
             definitions: route.definitions,
             security: route.security,
+            #[cfg(feature = "v3")]
+            schemas_v3: route.schemas_v3,
             inner: route.inner,
         }
     }
@@ -670,6 +1212,10 @@ where
         mem::take(&mut self.operations)
     }
 
+    fn handlers(&mut self) -> BTreeMap<HttpMethod, &'static str> {
+        mem::take(&mut self.handlers)
+    }
+
     fn security_definitions(&mut self) -> BTreeMap<String, SecurityScheme> {
         mem::take(&mut self.security)
     }
@@ -677,6 +1223,11 @@ where
     fn definitions(&mut self) -> BTreeMap<String, DefaultSchemaRaw> {
         mem::take(&mut self.definitions)
     }
+
+    #[cfg(feature = "v3")]
+    fn schemas_v3(&mut self) -> SchemaMap {
+        mem::take(&mut self.schemas_v3)
+    }
 }
 
 /* Service config */
@@ -684,8 +1235,13 @@ where
 /// Wrapper for [`actix_web::web::ServiceConfig`](https://docs.rs/actix-web/*/actix_web/web/struct.ServiceConfig.html).
 pub struct ServiceConfig<'a> {
     path_map: BTreeMap<String, DefaultPathItemRaw>,
+    /// Paths of the handlers of the operations in `path_map`.
+    handler_map: BTreeMap<String, BTreeMap<HttpMethod, &'static str>>,
     definitions: BTreeMap<String, DefaultSchemaRaw>,
     security: BTreeMap<String, SecurityScheme>,
+    tags: Vec<Tag>,
+    #[cfg(feature = "v3")]
+    schemas_v3: SchemaMap,
     inner: &'a mut actix_web::web::ServiceConfig,
 }
 
@@ -693,8 +1249,12 @@ impl<'a> From<&'a mut actix_web::web::ServiceConfig> for ServiceConfig<'a> {
     fn from(cfg: &'a mut actix_web::web::ServiceConfig) -> Self {
         ServiceConfig {
             path_map: BTreeMap::new(),
+            handler_map: BTreeMap::new(),
             definitions: BTreeMap::new(),
             security: BTreeMap::new(),
+            tags: Vec::new(),
+            #[cfg(feature = "v3")]
+            schemas_v3: SchemaMap::new(),
             inner: cfg,
         }
     }
@@ -719,12 +1279,27 @@ impl<'a> Mountable for ServiceConfig<'a> {
         mem::take(&mut self.definitions)
     }
 
+    fn tags(&mut self) -> Vec<Tag> {
+        mem::take(&mut self.tags)
+    }
+
+    #[cfg(feature = "v3")]
+    fn schemas_v3(&mut self) -> SchemaMap {
+        mem::take(&mut self.schemas_v3)
+    }
+
     fn update_operations(&mut self, map: &mut BTreeMap<String, DefaultPathItemRaw>) {
         for (path, item) in mem::take(&mut self.path_map) {
             let op_map = map.entry(path).or_insert_with(Default::default);
             op_map.methods.extend(item.methods.into_iter());
         }
     }
+
+    fn update_handlers(&mut self, map: &mut BTreeMap<String, BTreeMap<HttpMethod, &'static str>>) {
+        for (path, handlers) in mem::take(&mut self.handler_map) {
+            map.entry(path).or_default().extend(handlers);
+        }
+    }
 }
 
 impl<'a> ServiceConfig<'a> {
@@ -732,7 +1307,10 @@ impl<'a> ServiceConfig<'a> {
     pub fn route(&mut self, path: &str, route: Route) -> &mut Self {
         let mut w = RouteWrapper::from(path, route);
         self.definitions.extend(w.definitions().into_iter());
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(w.schemas_v3().into_iter());
         w.update_operations(&mut self.path_map);
+        w.update_handlers(&mut self.handler_map);
         SecurityScheme::append_map(w.security, &mut self.security);
         self.inner.route(path, w.inner);
         self
@@ -744,8 +1322,12 @@ impl<'a> ServiceConfig<'a> {
         F: Mountable + HttpServiceFactory + 'static,
     {
         self.definitions.extend(factory.definitions().into_iter());
+        #[cfg(feature = "v3")]
+        self.schemas_v3.extend(factory.schemas_v3().into_iter());
         factory.update_operations(&mut self.path_map);
+        factory.update_handlers(&mut self.handler_map);
         SecurityScheme::append_map(factory.security_definitions(), &mut self.security);
+        Tag::append_list(factory.tags(), &mut self.tags);
         self.inner.service(factory);
         self
     }
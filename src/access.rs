@@ -0,0 +1,212 @@
+//! Access policies from the roles required by operations.
+//!
+//! Operations can declare the roles required for calling them (for example, through
+//! `#[api_v2_operation(required_role = "admin")]`), which end up in the `x-required-roles`
+//! extension. Callers need *any* of those roles. The rules collected from a spec can be
+//! exported as [OPA](https://www.openpolicyagent.org/) policies or
+//! [Envoy RBAC](https://www.envoyproxy.io/docs/envoy/latest/api-v3/config/rbac/v3/rbac.proto)
+//! policies, so that gateways enforce what the spec documents.
+//!
+//! ```rust
+//! use paperclip::access::{self, AccessRule};
+//! use serde_json::json;
+//!
+//! let spec = json!({
+//!     "basePath": "/api",
+//!     "paths": {
+//!         "/pets/{id}": {
+//!             "get": {},
+//!             "delete": { "x-required-roles": ["admin"] }
+//!         }
+//!     }
+//! });
+//!
+//! let rules = access::rules(&spec);
+//! assert_eq!(rules, vec![AccessRule {
+//!     method: "DELETE".into(),
+//!     path: "/api/pets/{id}".into(),
+//!     roles: vec!["admin".into()],
+//! }]);
+//! assert!(access::opa_policy(&rules, "paperclip.access").contains("/api/pets/*"));
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use std::fmt::Write;
+
+/// Extension for the roles required for calling an operation.
+pub const REQUIRED_ROLES_EXTENSION: &str = "x-required-roles";
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Roles required for calling an operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessRule {
+    /// HTTP method (in uppercase).
+    pub method: String,
+    /// Path template (including the base path, if any).
+    pub path: String,
+    /// Roles, any of which grants access to the operation.
+    pub roles: Vec<String>,
+}
+
+impl AccessRule {
+    /// Returns the path as a glob pattern (with `/` as the delimiter).
+    pub fn path_glob(&self) -> String {
+        self.path
+            .split('/')
+            .map(|s| if is_template(s) { "*" } else { s })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Returns the path as an (anchored) regular expression.
+    pub fn path_regex(&self) -> String {
+        let segments = self
+            .path
+            .split('/')
+            .map(|s| {
+                if is_template(s) {
+                    return "[^/]+".into();
+                }
+
+                s.chars().fold(String::new(), |mut r, c| {
+                    if "\\.+*?()|[]{}^$".contains(c) {
+                        r.push('\\');
+                    }
+
+                    r.push(c);
+                    r
+                })
+            })
+            .collect::<Vec<_>>();
+        format!("^{}$", segments.join("/"))
+    }
+}
+
+fn is_template(segment: &str) -> bool {
+    segment.starts_with('{') && segment.ends_with('}')
+}
+
+/// Collects the rules for operations (with required roles) in the given spec.
+///
+/// Paths are prefixed with the `basePath` of v2 specs.
+pub fn rules(spec: &Value) -> Vec<AccessRule> {
+    let base = spec["basePath"]
+        .as_str()
+        .unwrap_or("")
+        .trim_end_matches('/');
+    let mut rules = vec![];
+    for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+        for method in METHODS {
+            let roles = item[*method][REQUIRED_ROLES_EXTENSION]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|r| r.as_str().map(String::from))
+                .collect::<Vec<_>>();
+            if !roles.is_empty() {
+                rules.push(AccessRule {
+                    method: method.to_uppercase(),
+                    path: format!("{}{}", base, path),
+                    roles,
+                });
+            }
+        }
+    }
+
+    rules
+}
+
+/// Returns a Rego module (in the given package) for the rules.
+///
+/// `allow` holds for requests (with `method`, `path` and `roles` in the input) which
+/// either don't match any of the rules, or have one of the roles required by a
+/// matching rule.
+pub fn opa_policy(rules: &[AccessRule], package: &str) -> String {
+    let entries = rules
+        .iter()
+        .map(|rule| {
+            let value = json!({
+                "method": rule.method,
+                "path": rule.path_glob(),
+                "roles": rule.roles,
+            });
+            format!("    {}", value)
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "package {}\n", package);
+    out.push_str("rules := [\n");
+    if !entries.is_empty() {
+        out.push_str(&entries.join(",\n"));
+        out.push('\n');
+    }
+
+    out.push_str(
+        r#"]
+
+matching[rule] {
+    rule := rules[_]
+    input.method == rule.method
+    glob.match(rule.path, ["/"], input.path)
+}
+
+default allow = false
+
+allow {
+    count(matching) == 0
+}
+
+allow {
+    rule := matching[_]
+    input.roles[_] == rule.roles[_]
+}
+"#,
+    );
+    out
+}
+
+/// Returns an Envoy RBAC policy (for the HTTP RBAC filter) for the rules.
+///
+/// Requests matching a rule are denied unless the given claim of the JWT payload
+/// (stored by the JWT authentication filter in the `jwt_payload` metadata) has one
+/// of the required roles.
+pub fn envoy_rbac(rules: &[AccessRule], claim: &str) -> Value {
+    let mut policies = Map::new();
+    for rule in rules {
+        let ids = rule
+            .roles
+            .iter()
+            .map(|role| {
+                json!({
+                    "metadata": {
+                        "filter": "envoy.filters.http.jwt_authn",
+                        "path": [{ "key": "jwt_payload" }, { "key": claim }],
+                        "value": { "list_match": { "one_of": { "string_match": { "exact": role } } } }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let policy = json!({
+            "permissions": [{
+                "and_rules": {
+                    "rules": [
+                        { "header": { "name": ":method", "string_match": { "exact": rule.method } } },
+                        { "url_path": { "path": { "safe_regex": { "regex": rule.path_regex() } } } }
+                    ]
+                }
+            }],
+            "principals": [{ "not_id": { "or_ids": { "ids": ids } } }]
+        });
+        policies.insert(format!("{} {}", rule.method, rule.path), policy);
+    }
+
+    json!({
+        "rules": {
+            "action": "DENY",
+            "policies": policies,
+        }
+    })
+}
@@ -1,17 +1,29 @@
 use anyhow::Error;
 use paperclip::{
+    access, bundle, cost, fuzz, graphql, integrity,
+    overlay::Overlay,
+    owners, redact, units,
     v2::{
         self,
-        codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState},
+        codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState, ModuleLayout},
         models::{DefaultSchema, ResolvableApi},
+        LoadProgress, ResolveProgress,
     },
     PaperClipError,
 };
-use structopt::StructOpt;
+use structopt::{
+    clap::{Error as ClapError, ErrorKind},
+    StructOpt,
+};
+use url_dep::Url;
 
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
-    path::PathBuf,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
 };
 
 fn parse_version(s: &str) -> Result<OApiVersion, Error> {
@@ -22,9 +34,70 @@ fn parse_version(s: &str) -> Result<OApiVersion, Error> {
     }
 }
 
-fn parse_spec(s: &str) -> Result<ResolvableApi<DefaultSchema>, Error> {
-    let fd = File::open(s)?;
-    Ok(v2::from_reader(fd)?)
+fn parse_layout(s: &str) -> Result<ModuleLayout, Error> {
+    match s {
+        "path" => Ok(ModuleLayout::Path),
+        "flat" => Ok(ModuleLayout::Flat),
+        "tag" => Ok(ModuleLayout::Tag),
+        _ => Err(anyhow::anyhow!(
+            "expected one of path, flat or tag, found {:?}",
+            s
+        )),
+    }
+}
+
+/// Reads the spec from the given path or (HTTP) URL.
+fn read_spec_source(source: &str) -> Result<Vec<u8>, Error> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let resp = reqwest::blocking::get(source)?.error_for_status()?;
+        return Ok(resp.bytes()?.to_vec());
+    }
+
+    Ok(fs::read(source)?)
+}
+
+/// Opens the spec at the given path or (HTTP) URL for streaming its contents,
+/// along with its size (if it's known).
+fn open_spec_source(source: &str) -> Result<(Box<dyn Read>, Option<u64>), Error> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let resp = reqwest::blocking::get(source)?.error_for_status()?;
+        let len = resp.content_length();
+        return Ok((Box::new(resp), len));
+    }
+
+    let file = File::open(source)?;
+    let len = file.metadata()?.len();
+    Ok((Box::new(file), Some(len)))
+}
+
+/// Prints the progress of loading a spec (on stderr) in steps of 10%, or
+/// for every 10 MiB parsed from documents of unknown sizes.
+#[derive(Default)]
+struct ProgressPrinter {
+    last: String,
+}
+
+impl ProgressPrinter {
+    fn report(&mut self, progress: LoadProgress) {
+        let (stage, done, total) = match progress {
+            LoadProgress::Parsing { read, total } => ("Parsing spec", read, total),
+            LoadProgress::Resolving(ResolveProgress::Paths { done, total }) => {
+                ("Resolving paths", done as u64, Some(total as u64))
+            }
+            LoadProgress::Resolving(ResolveProgress::Definitions { done, total }) => {
+                ("Resolving definitions", done as u64, Some(total as u64))
+            }
+        };
+
+        let line = match total {
+            Some(t) => format!("{}: {}%", stage, done * 10 / t.max(1) * 10),
+            None => format!("{}: {} MiB", stage, (done >> 20) / 10 * 10),
+        };
+        if line != self.last {
+            eprintln!("{}", line);
+            self.last = line;
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -33,14 +106,43 @@ enum OApiVersion {
     V3,
 }
 
+/// Reads a JSON/YAML document from the given path as a JSON value.
+fn read_document(path: &Path) -> Result<(serde_json::Value, bool), Error> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    if contents.trim_start().starts_with('{') {
+        return Ok((v2::parse_json(contents.as_bytes())?, true));
+    }
+
+    // Go through YAML values so that non-string keys (like status codes) are stringified.
+    let value: serde_yaml::Value = serde_yaml::from_str(&contents)?;
+    Ok((serde_json::to_value(value)?, false))
+}
+
+/// Writes the JSON value as a JSON/YAML document to the given path (or stdout).
+fn write_document(value: &serde_json::Value, json: bool, path: Option<&Path>) -> Result<(), Error> {
+    let mut out: Box<dyn Write> = match path {
+        Some(p) => Box::new(File::create(p)?),
+        None => Box::new(io::stdout()),
+    };
+
+    if json {
+        serde_json::to_writer_pretty(&mut out, value)?;
+        writeln!(out)?;
+    } else {
+        serde_yaml::to_writer(&mut out, value)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, StructOpt)]
 struct Opt {
     /// Path to OpenAPI spec in JSON/YAML format (also supports publicly accessible URLs).
-    #[structopt(parse(try_from_str = parse_spec))]
-    spec: ResolvableApi<DefaultSchema>,
+    spec: Option<String>,
     /// OpenAPI version (e.g., v2).
     #[structopt(long = "api", parse(try_from_str = parse_version))]
-    api: OApiVersion,
+    api: Option<OApiVersion>,
     /// Output directory to write code (default: current working directory).
     #[structopt(short = "o", long = "out", parse(from_os_str))]
     output: Option<PathBuf>,
@@ -57,22 +159,500 @@ struct Opt {
     /// Version (defaults to 0.1.0)
     #[structopt(long = "version")]
     pub version: Option<String>,
+    /// Layout of the modules for definitions - nested modules based on the
+    /// namespaces in definition names (path), all in root (flat), or grouped
+    /// by the tags of the operations using them (tag).
+    #[structopt(long = "layout", default_value = "path", parse(try_from_str = parse_layout))]
+    layout: ModuleLayout,
+    /// Watch the spec for changes and regenerate code whenever it changes.
+    #[structopt(long = "watch")]
+    watch: bool,
+    /// Interval (in seconds) for polling the spec in watch mode.
+    #[structopt(long = "poll-interval", default_value = "1")]
+    poll_interval: u64,
+    /// Report the progress of parsing and resolving the spec (on stderr).
+    #[structopt(long = "progress")]
+    progress: bool,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Work with OpenAPI overlay documents.
+    Overlay(OverlayCommand),
+    /// Embed or verify the hash of specs (in the `x-spec-sha256` extension).
+    Integrity(IntegrityCommand),
+    /// Export access policies from the roles required by operations (in the
+    /// `x-required-roles` extension).
+    Access(AccessCommand),
+    /// Work with the cost classes of operations (in the `x-cost` extension).
+    Cost(CostCommand),
+    /// Work with the owners of operations and definitions (in the `x-owner` extension).
+    Owners(OwnersCommand),
+    /// Work with the units of numeric fields (in the `x-unit` extension).
+    Units(UnitsCommand),
+    /// Export the definitions of a spec as GraphQL SDL types (experimental).
+    ExportGraphql {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout).
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Generate cargo-fuzz targets sending (conforming and boundary-violating)
+    /// requests for the operations of a spec.
+    FuzzTargets {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output directory of the fuzz crate.
+        #[structopt(short = "o", long = "out", default_value = "fuzz", parse(from_os_str))]
+        output: PathBuf,
+        /// Name of the API (the crate is named `<name>-fuzz`).
+        #[structopt(long = "name", default_value = "api")]
+        name: String,
+        /// Base URL of the service (default: the scheme, host and base path of the spec).
+        /// This can be overridden with `FUZZ_BASE_URL` when running the targets.
+        #[structopt(long = "base-url")]
+        base_url: Option<String>,
+    },
+    /// Strip the examples and defaults of sensitive fields (marked with the
+    /// `x-sensitive` extension) before publishing a spec.
+    Redact {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Add the schemas referred to by external references (`$ref`) to the
+    /// definitions of a spec.
+    Bundle {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Mapping of a URL prefix to a local directory (`PREFIX=DIR`) for fetching
+        /// remote schemas (only local files are fetched otherwise).
+        #[structopt(long = "map", parse(try_from_str = parse_mapping))]
+        mappings: Vec<(String, PathBuf)>,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum OverlayCommand {
+    /// Apply the actions in an overlay to a spec.
+    Apply {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Path to the overlay in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        overlay: PathBuf,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum IntegrityCommand {
+    /// Embed the SHA-256 hash of the canonicalized spec in the spec.
+    Sign {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Also write the (hex-encoded) hash to this file.
+        #[structopt(long = "detached", parse(from_os_str))]
+        detached: Option<PathBuf>,
+    },
+    /// Check the hash embedded in the spec (or the one in a detached file).
+    Verify {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// File with the (hex-encoded) hash to check against.
+        #[structopt(long = "detached", parse(from_os_str))]
+        detached: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum AccessCommand {
+    /// Write OPA (Rego) or Envoy RBAC policies for the operations requiring roles.
+    Export {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Policy format (opa or envoy).
+        #[structopt(long = "format", parse(try_from_str = parse_policy_format))]
+        format: PolicyFormat,
+        /// Package of the Rego module.
+        #[structopt(long = "package", default_value = "paperclip.access")]
+        package: String,
+        /// JWT claim with the roles of the caller (for Envoy).
+        #[structopt(long = "claim", default_value = "roles")]
+        claim: String,
+        /// Output file (default: stdout). Envoy policies are written in JSON if
+        /// it ends with `.json` and YAML otherwise.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum CostCommand {
+    /// Embed the cost classes of operations for each tag in the spec (in the
+    /// `x-cost-by-tag` extension).
+    Aggregate {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum OwnersCommand {
+    /// Write the operations and definitions of the spec grouped by their owners.
+    Report {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout). The report is written in JSON if it
+        /// ends with `.json` and YAML otherwise.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum UnitsCommand {
+    /// List the numeric fields which look like durations or sizes (like `timeout`)
+    /// but don't have units, failing if there are any.
+    Lint {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+    },
+    /// Append the units of fields to their descriptions.
+    Describe {
+        /// Path to OpenAPI spec in JSON/YAML format.
+        #[structopt(parse(from_os_str))]
+        spec: PathBuf,
+        /// Output file (default: stdout). The format is inferred from its extension
+        /// and defaults to the format of the spec.
+        #[structopt(short = "o", long = "out", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug)]
+enum PolicyFormat {
+    Opa,
+    Envoy,
+}
+
+fn parse_policy_format(s: &str) -> Result<PolicyFormat, Error> {
+    match s {
+        "opa" => Ok(PolicyFormat::Opa),
+        "envoy" => Ok(PolicyFormat::Envoy),
+        _ => Err(anyhow::anyhow!("unknown policy format {:?}", s)),
+    }
+}
+
+fn parse_mapping(s: &str) -> Result<(String, PathBuf), Error> {
+    match s.find('=') {
+        Some(i) => Ok((s[..i].into(), PathBuf::from(&s[i + 1..]))),
+        None => Err(anyhow::anyhow!("expected PREFIX=DIR, found {:?}", s)),
+    }
+}
+
+/// Returns whether the output file (if any) should be in JSON.
+fn is_json_output(output: Option<&Path>, default: bool) -> bool {
+    match output.and_then(|p| p.extension()).and_then(|e| e.to_str()) {
+        Some("json") => true,
+        Some("yaml") | Some("yml") => false,
+        _ => default,
+    }
+}
+
+fn apply_overlay(spec: &Path, overlay: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    let overlay: Overlay = serde_json::from_value(read_document(overlay)?.0)?;
+    overlay.apply(&mut value)?;
+    write_document(&value, is_json_output(output, is_json), output)
+}
+
+fn sign_spec(spec: &Path, output: Option<&Path>, detached: Option<&Path>) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    let hash = integrity::embed_sha256(&mut value);
+    write_document(&value, is_json_output(output, is_json), output)?;
+    if let Some(path) = detached {
+        fs::write(path, hash + "\n")?;
+    }
+
+    Ok(())
+}
+
+fn verify_spec(spec: &Path, detached: Option<&Path>) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    let hash = integrity::spec_sha256(&value);
+    let expected = match detached {
+        Some(path) => fs::read_to_string(path)?.trim().to_owned(),
+        None => match value
+            .get(integrity::SPEC_SHA256_EXTENSION)
+            .and_then(|h| h.as_str())
+        {
+            Some(h) => h.to_owned(),
+            None => return Err(anyhow::anyhow!("spec doesn't have a hash")),
+        },
+    };
+
+    if hash != expected {
+        return Err(anyhow::anyhow!(
+            "hash mismatch (expected {}, found {})",
+            expected,
+            hash
+        ));
+    }
+
+    println!("{}", hash);
+    Ok(())
+}
+
+fn bundle_spec(
+    spec: &Path,
+    mappings: &[(String, PathBuf)],
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    let base = Url::from_file_path(fs::canonicalize(spec)?)
+        .map_err(|_| anyhow::anyhow!("invalid path {:?}", spec))?;
+    bundle::bundle(&mut value, Some(base.as_str()), |url| {
+        let path = match mappings.iter().find(|(p, _)| url.starts_with(p.as_str())) {
+            Some((prefix, dir)) => dir.join(url[prefix.len()..].trim_start_matches('/')),
+            None => Url::parse(url)
+                .ok()
+                .filter(|u| u.scheme() == "file")
+                .and_then(|u| u.to_file_path().ok())
+                .ok_or_else(|| {
+                    PaperClipError::UnresolvedReference(
+                        url.into(),
+                        "no mapping to a local directory".into(),
+                    )
+                })?,
+        };
+
+        read_document(&path)
+            .map(|(v, _)| v)
+            .map_err(|e| PaperClipError::UnresolvedReference(url.into(), e.to_string()))
+    })?;
+    write_document(&value, is_json_output(output, is_json), output)
+}
+
+fn aggregate_cost(spec: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    cost::embed_cost_by_tag(&mut value);
+    write_document(&value, is_json_output(output, is_json), output)
+}
+
+fn report_owners(spec: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    let report = serde_json::to_value(owners::surface_by_owner(&value))?;
+    write_document(&report, is_json_output(output, false), output)
+}
+
+fn lint_units(spec: &Path) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    let fields = units::unitless_fields(&value);
+    for field in &fields {
+        println!("{}", field);
+    }
+
+    if !fields.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} numeric field(s) without units",
+            fields.len()
+        ));
+    }
+
+    Ok(())
+}
+
+fn describe_units(spec: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    units::describe_units(&mut value);
+    write_document(&value, is_json_output(output, is_json), output)
+}
+
+fn export_access(
+    spec: &Path,
+    format: &PolicyFormat,
+    package: &str,
+    claim: &str,
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    let rules = access::rules(&value);
+    match format {
+        PolicyFormat::Opa => {
+            let policy = access::opa_policy(&rules, package);
+            match output {
+                Some(path) => fs::write(path, policy)?,
+                None => print!("{}", policy),
+            }
+
+            Ok(())
+        }
+        PolicyFormat::Envoy => {
+            let policy = access::envoy_rbac(&rules, claim);
+            write_document(&policy, is_json_output(output, false), output)
+        }
+    }
+}
+
+fn export_graphql(spec: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    let sdl = graphql::sdl(&value);
+    match output {
+        Some(path) => fs::write(path, sdl)?,
+        None => print!("{}", sdl),
+    }
+
+    Ok(())
+}
+
+fn generate_fuzz_targets(
+    spec: &Path,
+    output: &Path,
+    name: &str,
+    base_url: Option<&str>,
+) -> Result<(), Error> {
+    let (value, _) = read_document(spec)?;
+    for (path, contents) in fuzz::files(&value, name, base_url) {
+        let path = output.join(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&path, contents)?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+fn redact_spec(spec: &Path, output: Option<&Path>) -> Result<(), Error> {
+    let (mut value, is_json) = read_document(spec)?;
+    redact::strip_sensitive(&mut value);
+    write_document(&value, is_json_output(output, is_json), output)
 }
 
 fn parse_args_and_run() -> Result<(), Error> {
     let opt = Opt::from_args();
-    if let OApiVersion::V3 = opt.api {
+    match opt.cmd {
+        Some(Command::Overlay(OverlayCommand::Apply {
+            spec,
+            overlay,
+            output,
+        })) => return apply_overlay(&spec, &overlay, output.as_deref()),
+        Some(Command::Integrity(IntegrityCommand::Sign {
+            spec,
+            output,
+            detached,
+        })) => return sign_spec(&spec, output.as_deref(), detached.as_deref()),
+        Some(Command::Integrity(IntegrityCommand::Verify { spec, detached })) => {
+            return verify_spec(&spec, detached.as_deref())
+        }
+        Some(Command::Access(AccessCommand::Export {
+            spec,
+            format,
+            package,
+            claim,
+            output,
+        })) => return export_access(&spec, &format, &package, &claim, output.as_deref()),
+        Some(Command::Cost(CostCommand::Aggregate { spec, output })) => {
+            return aggregate_cost(&spec, output.as_deref())
+        }
+        Some(Command::Owners(OwnersCommand::Report { spec, output })) => {
+            return report_owners(&spec, output.as_deref())
+        }
+        Some(Command::Units(UnitsCommand::Lint { spec })) => return lint_units(&spec),
+        Some(Command::Units(UnitsCommand::Describe { spec, output })) => {
+            return describe_units(&spec, output.as_deref())
+        }
+        Some(Command::ExportGraphql { spec, output }) => {
+            return export_graphql(&spec, output.as_deref())
+        }
+        Some(Command::FuzzTargets {
+            spec,
+            output,
+            name,
+            base_url,
+        }) => return generate_fuzz_targets(&spec, &output, &name, base_url.as_deref()),
+        Some(Command::Redact { spec, output }) => return redact_spec(&spec, output.as_deref()),
+        Some(Command::Bundle {
+            spec,
+            mappings,
+            output,
+        }) => return bundle_spec(&spec, &mappings, output.as_deref()),
+        None => (),
+    }
+
+    let (spec, api) = match (&opt.spec, &opt.api) {
+        (Some(s), Some(a)) => (s, a),
+        _ => ClapError::with_description(
+            "The spec and its version (--api) are required for generating code.",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+
+    if let OApiVersion::V3 = api {
         return Err(PaperClipError::UnsupportedOpenAPIVersion.into());
     }
 
-    let spec = opt.spec.resolve()?;
+    if opt.watch {
+        return watch_and_generate(spec, &opt);
+    }
+
+    let (reader, len) = open_spec_source(spec)?;
+    generate(reader, len, &opt)
+}
+
+/// Generates code for the given (v2) spec of the given size (if known). The spec
+/// is parsed as it's read and then resolved, reporting the progress if needed.
+fn generate<R: Read>(spec: R, len: Option<u64>, opt: &Opt) -> Result<(), Error> {
+    let mut printer = ProgressPrinter::default();
+    let spec: ResolvableApi<DefaultSchema> = v2::load(spec, len, |p| {
+        if opt.progress {
+            printer.report(p)
+        }
+    })?;
     let mut state = EmitterState::default();
 
-    if let Some(o) = opt.output {
-        fs::create_dir_all(&o)?;
-        state.working_dir = o;
+    if let Some(o) = &opt.output {
+        fs::create_dir_all(o)?;
+        state.working_dir = o.clone();
     }
 
+    state.layout = opt.layout;
     let mut meta = CrateMeta::default();
     if opt.cli {
         meta.mode = EmitMode::App;
@@ -80,10 +660,10 @@ fn parse_args_and_run() -> Result<(), Error> {
         meta.mode = EmitMode::Crate;
     }
     if opt.name.is_some() {
-        meta.name = opt.name;
+        meta.name = opt.name.clone();
     }
     if opt.version.is_some() {
-        meta.version = opt.version;
+        meta.version = opt.version.clone();
     }
 
     meta.no_root = opt.no_root;
@@ -93,9 +673,82 @@ fn parse_args_and_run() -> Result<(), Error> {
     emitter.generate(&spec)
 }
 
+/// Polls the spec and regenerates code whenever its contents change, printing
+/// the files which have been added, changed or removed by each run.
+fn watch_and_generate(source: &str, opt: &Opt) -> Result<(), Error> {
+    let dir = match &opt.output {
+        Some(o) => o.clone(),
+        None => std::env::current_dir()?,
+    };
+
+    let mut last = None;
+    loop {
+        match read_spec_source(source) {
+            Ok(contents) if last.as_ref() != Some(&contents) => {
+                let before = snapshot_files(&dir);
+                match generate(&contents[..], Some(contents.len() as u64), opt) {
+                    Ok(()) => print_changes(&before, &snapshot_files(&dir)),
+                    Err(e) => eprintln!("{}", e),
+                }
+
+                last = Some(contents);
+            }
+            Ok(_) => (),
+            Err(e) => eprintln!("{}", e),
+        }
+
+        thread::sleep(Duration::from_secs(opt.poll_interval));
+    }
+}
+
+/// Returns the contents of the files in the given directory (skipping `target`
+/// and hidden directories).
+fn snapshot_files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(d) = dirs.pop() {
+        for entry in fs::read_dir(&d).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.is_dir() {
+                if name != "target" && !name.starts_with('.') {
+                    dirs.push(path);
+                }
+            } else if let Ok(contents) = fs::read(&path) {
+                let path = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+                files.insert(path, contents);
+            }
+        }
+    }
+
+    files
+}
+
+fn print_changes(before: &BTreeMap<PathBuf, Vec<u8>>, after: &BTreeMap<PathBuf, Vec<u8>>) {
+    let mut count = 0;
+    for (path, contents) in after {
+        match before.get(path) {
+            None => println!("added: {}", path.display()),
+            Some(c) if c != contents => println!("changed: {}", path.display()),
+            _ => continue,
+        }
+
+        count += 1;
+    }
+
+    for path in before.keys().filter(|p| !after.contains_key(*p)) {
+        println!("removed: {}", path.display());
+        count += 1;
+    }
+
+    println!("Regenerated code ({} file(s) affected).", count);
+}
+
 fn main() {
     env_logger::init();
     if let Err(e) = parse_args_and_run() {
         eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,387 @@
+//! Runtime for the fuzz targets generated by paperclip. Each target builds requests
+//! for an operation in `spec.json` from the fuzzer's input and sends them to the
+//! service at `FUZZ_BASE_URL` (or the URL the targets were generated with).
+//!
+//! Requests usually conform to the spec, but some of them have a value violating its
+//! schema (like numbers outside their bounds, strings longer than allowed, values of
+//! the wrong type or missing properties). Responses with `5xx` status codes are crashes.
+//! If `FUZZ_STRICT` is set, so are successful responses for requests violating the spec.
+
+use arbitrary::{Result, Unstructured};
+use once_cell::sync::Lazy;
+use reqwest::{
+    blocking::{multipart, Client},
+    header::HeaderValue,
+    Method, Url,
+};
+use serde_json::{json, Map, Value};
+
+use std::{env, time::Duration};
+
+// `DEFAULT_BASE_URL` (used if `FUZZ_BASE_URL` isn't set) is appended by paperclip.
+
+/// Maximum depth of generated values (beyond which optional values are left out).
+const MAX_DEPTH: usize = 6;
+
+static SPEC: Lazy<Value> =
+    Lazy::new(|| serde_json::from_str(include_str!("../spec.json")).expect("parsing spec"));
+
+static CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("building client")
+});
+
+/// Builds a request for the operation (with the given method and path template) from
+/// the given data, sends it and panics if the response indicates a bug.
+pub fn run(method: &str, path: &str, data: &[u8]) {
+    let mut u = Unstructured::new(data);
+    let request = match Request::build(method, path, &mut u) {
+        Ok(r) => r,
+        // Not enough data.
+        Err(_) => return,
+    };
+
+    request.send();
+}
+
+/// Request for an operation, with the values of its parameters.
+#[derive(Debug)]
+struct Request {
+    method: Method,
+    path: String,
+    multipart: bool,
+    /// Whether a value violates its schema.
+    violating: bool,
+    params: Vec<Param>,
+    body: Option<Value>,
+}
+
+#[derive(Debug)]
+struct Param {
+    location: String,
+    name: String,
+    value: Value,
+    /// Whether arrays are sent as multiple values (instead of comma-separated ones).
+    multi: bool,
+}
+
+impl Request {
+    fn build(method: &str, path: &str, u: &mut Unstructured) -> Result<Request> {
+        let item = &SPEC["paths"][path];
+        let op = &item[method.to_lowercase().as_str()];
+        let mut gen = Generator {
+            violate: u.ratio(1u8, 4)?,
+            violated: false,
+        };
+
+        let consumes = op
+            .get("consumes")
+            .or_else(|| SPEC.get("consumes"))
+            .and_then(|c| c.as_array());
+        let mut request = Request {
+            method: method.parse().expect("invalid method"),
+            path: path.into(),
+            multipart: consumes
+                .into_iter()
+                .flatten()
+                .any(|m| m == "multipart/form-data"),
+            violating: false,
+            params: vec![],
+            body: None,
+        };
+
+        let params = item["parameters"]
+            .as_array()
+            .into_iter()
+            .chain(op["parameters"].as_array())
+            .flatten()
+            .map(resolve)
+            .collect::<Vec<_>>();
+        for param in params {
+            let required = param["in"] == "path" || param["required"] == true;
+            if !required && !u.arbitrary::<bool>()? {
+                continue;
+            }
+
+            let location = param["in"].as_str().unwrap_or("query");
+            let schema = match location {
+                "body" => &param["schema"],
+                _ => param,
+            };
+            let value = gen.value(u, schema, 0)?;
+            match location {
+                "body" => request.body = Some(value),
+                _ => request.params.push(Param {
+                    location: location.into(),
+                    name: param["name"].as_str().unwrap_or_default().into(),
+                    value,
+                    multi: param["collectionFormat"] == "multi",
+                }),
+            }
+        }
+
+        request.violating = gen.violated;
+        Ok(request)
+    }
+
+    fn send(self) {
+        let base = env::var("FUZZ_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.into());
+        let mut url = Url::parse(&base).expect("invalid base URL");
+        {
+            let mut segments = url.path_segments_mut().expect("invalid base URL");
+            segments.pop_if_empty();
+            for segment in self.path.split('/').filter(|s| !s.is_empty()) {
+                let name = segment.trim_start_matches('{').trim_end_matches('}');
+                match self
+                    .params
+                    .iter()
+                    .find(|p| p.location == "path" && p.name == name)
+                {
+                    Some(p) => segments.push(&to_string(&p.value)),
+                    None => segments.push(segment),
+                };
+            }
+        }
+
+        let mut form = vec![];
+        for param in &self.params {
+            let values = match &param.value {
+                Value::Array(values) if param.multi => values.iter().map(to_string).collect(),
+                v => vec![to_string(v)],
+            };
+            for value in values {
+                match param.location.as_str() {
+                    "query" => {
+                        url.query_pairs_mut().append_pair(&param.name, &value);
+                    }
+                    "formData" => form.push((param.name.clone(), value)),
+                    _ => (),
+                }
+            }
+        }
+
+        let mut builder = CLIENT.request(self.method.clone(), url);
+        for param in self.params.iter().filter(|p| p.location == "header") {
+            // Values which can't be sent in headers are left out.
+            if let Ok(value) = HeaderValue::from_str(&to_string(&param.value)) {
+                builder = builder.header(param.name.as_str(), value);
+            }
+        }
+
+        if let Some(body) = &self.body {
+            builder = builder.json(body);
+        } else if self.multipart {
+            let parts = form
+                .into_iter()
+                .fold(multipart::Form::new(), |f, (k, v)| f.text(k, v));
+            builder = builder.multipart(parts);
+        } else if !form.is_empty() {
+            builder = builder.form(&form);
+        }
+
+        let status = match builder.send() {
+            Ok(resp) => resp.status(),
+            Err(e) => panic!("{} {} failed: {} ({:?})", self.method, self.path, e, self),
+        };
+
+        if status.is_server_error() {
+            panic!(
+                "{} {} responded with {} ({:?})",
+                self.method, self.path, status, self
+            );
+        }
+
+        if self.violating && status.is_success() && env::var_os("FUZZ_STRICT").is_some() {
+            panic!(
+                "{} {} accepted a request violating the spec ({:?})",
+                self.method, self.path, self
+            );
+        }
+    }
+}
+
+/// Generator of values for schemas.
+struct Generator {
+    /// Whether values (at most one of them) should violate their schemas.
+    violate: bool,
+    violated: bool,
+}
+
+impl Generator {
+    fn value(&mut self, u: &mut Unstructured, schema: &Value, depth: usize) -> Result<Value> {
+        let schema = resolve(schema);
+        // Required properties of recursive schemas can't be generated.
+        if depth > 2 * MAX_DEPTH {
+            return Ok(Value::Null);
+        }
+
+        if self.violate && !self.violated && u.ratio(1u8, 4)? {
+            self.violated = true;
+            return violation(u, schema);
+        }
+
+        if let Some(values) = schema["enum"].as_array().filter(|v| !v.is_empty()) {
+            return Ok(u.choose(values)?.clone());
+        }
+
+        let value = match schema["type"].as_str() {
+            Some("string") | Some("file") => string(u, schema)?,
+            Some("integer") => {
+                let min = schema["minimum"].as_i64().unwrap_or(i64::MIN);
+                let max = schema["maximum"].as_i64().unwrap_or(i64::MAX);
+                json!(u.int_in_range(min..=max.max(min))?)
+            }
+            Some("number") => {
+                let min = schema["minimum"].as_f64().unwrap_or(f64::MIN);
+                let max = schema["maximum"].as_f64().unwrap_or(f64::MAX);
+                let n = u.arbitrary::<f64>()?;
+                let n = if n.is_finite() { n } else { 0.0 };
+                json!(n.max(min).min(max))
+            }
+            Some("boolean") => json!(u.arbitrary::<bool>()?),
+            Some("array") => {
+                let min = schema["minItems"].as_u64().unwrap_or(0) as usize;
+                let max = schema["maxItems"].as_u64().unwrap_or(min as u64 + 4) as usize;
+                let max = if depth < MAX_DEPTH { max.max(min) } else { min };
+                let len = u.int_in_range(min..=max)?;
+                let items = (0..len)
+                    .map(|_| self.value(u, &schema["items"], depth + 1))
+                    .collect::<Result<_>>()?;
+                Value::Array(items)
+            }
+            Some("object") | None if schema.get("properties").is_some() => {
+                let required = schema["required"].as_array();
+                let mut map = Map::new();
+                for (name, prop) in schema["properties"].as_object().into_iter().flatten() {
+                    let is_required = required.into_iter().flatten().any(|r| r == name);
+                    if is_required || (depth < MAX_DEPTH && u.arbitrary::<bool>()?) {
+                        map.insert(name.clone(), self.value(u, prop, depth + 1)?);
+                    }
+                }
+
+                Value::Object(map)
+            }
+            Some("object") => json!({}),
+            _ => json!(u.arbitrary::<String>()?),
+        };
+
+        Ok(value)
+    }
+}
+
+/// Returns a string conforming to the length and format (if known) of the schema.
+fn string(u: &mut Unstructured, schema: &Value) -> Result<Value> {
+    let s = match schema["format"].as_str() {
+        Some("date-time") => format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            u.int_in_range(1970..=2100)?,
+            u.int_in_range(1..=12)?,
+            u.int_in_range(1..=28)?,
+            u.int_in_range(0..=23)?,
+            u.int_in_range(0..=59)?,
+            u.int_in_range(0..=59)?
+        ),
+        Some("date") => format!(
+            "{:04}-{:02}-{:02}",
+            u.int_in_range(1970..=2100)?,
+            u.int_in_range(1..=12)?,
+            u.int_in_range(1..=28)?
+        ),
+        Some("uuid") => {
+            let n = u.arbitrary::<u128>()?;
+            let hex = format!("{:032x}", n);
+            format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            )
+        }
+        _ => u.arbitrary::<String>()?,
+    };
+
+    let min = schema["minLength"].as_u64().unwrap_or(0) as usize;
+    let max = schema["maxLength"].as_u64().map(|m| m as usize);
+    let mut chars = s.chars().collect::<Vec<_>>();
+    if let Some(max) = max {
+        chars.truncate(max);
+    }
+
+    while chars.len() < min {
+        chars.push('a');
+    }
+
+    Ok(Value::String(chars.into_iter().collect()))
+}
+
+/// Returns a value violating the schema.
+fn violation(u: &mut Unstructured, schema: &Value) -> Result<Value> {
+    let mut values = vec![];
+    if let Some(max) = schema["maximum"].as_f64() {
+        values.push(json!(max + 1.0));
+    }
+
+    if let Some(min) = schema["minimum"].as_f64() {
+        values.push(json!(min - 1.0));
+    }
+
+    if let Some(max) = schema["maxLength"].as_u64() {
+        values.push(json!("a".repeat(max as usize + 1)));
+    }
+
+    if let Some(min) = schema["minLength"].as_u64().filter(|m| *m > 0) {
+        values.push(json!("a".repeat(min as usize - 1)));
+    }
+
+    if let Some(max) = schema["maxItems"].as_u64() {
+        values.push(Value::Array(vec![Value::Null; max as usize + 1]));
+    }
+
+    if schema.get("enum").is_some() {
+        values.push(json!("not-a-member"));
+    }
+
+    if matches!(schema["required"].as_array(), Some(r) if !r.is_empty()) {
+        // Objects without the required properties.
+        values.push(json!({}));
+    }
+
+    // Values of the wrong type.
+    values.push(match schema["type"].as_str() {
+        Some("string") => json!(u.arbitrary::<i64>()?),
+        _ => json!(u.arbitrary::<String>()?),
+    });
+
+    Ok(u.choose(&values)?.clone())
+}
+
+/// Resolves the references to the definitions (or parameters) of the spec.
+fn resolve(value: &Value) -> &Value {
+    let mut value = value;
+    for _ in 0..MAX_DEPTH {
+        let reference = match value["$ref"].as_str() {
+            Some(r) => r,
+            None => break,
+        };
+
+        let pointer = reference.trim_start_matches('#');
+        value = match SPEC.pointer(pointer) {
+            Some(v) => v,
+            None => break,
+        };
+    }
+
+    value
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(to_string).collect::<Vec<_>>().join(","),
+        v => v.to_string(),
+    }
+}
@@ -0,0 +1,220 @@
+//! Bundling of external schema references.
+//!
+//! Specs can refer to schemas outside the document (for example, in a centralized
+//! schema registry through `#[openapi(ref = "https://...")]`). Bundling fetches those
+//! schemas (along with the ones they refer to) and adds them to the definitions of
+//! the spec (`definitions` in v2 and `components.schemas` in v3), so that the spec
+//! can be used by tools which don't resolve external references.
+//!
+//! ```rust
+//! use paperclip::bundle;
+//! use serde_json::json;
+//!
+//! let mut spec = json!({
+//!     "definitions": {
+//!         "Customer": {
+//!             "type": "object",
+//!             "properties": {
+//!                 "address": { "$ref": "https://schemas.example.com/common/Address.json" }
+//!             }
+//!         }
+//!     }
+//! });
+//!
+//! bundle::bundle(&mut spec, None, |url| {
+//!     assert_eq!(url, "https://schemas.example.com/common/Address.json");
+//!     Ok(json!({ "type": "object", "properties": { "street": { "type": "string" } } }))
+//! }).unwrap();
+//!
+//! let definitions = &spec["definitions"];
+//! assert_eq!(definitions["Customer"]["properties"]["address"]["$ref"], "#/definitions/Address");
+//! assert_eq!(definitions["Address"]["properties"]["street"]["type"], "string");
+//! ```
+
+use crate::error::{PaperClipError, PaperClipResult};
+use serde_json::{Map, Value};
+use url_dep::Url;
+
+use std::collections::{HashMap, HashSet};
+
+/// Replaces the external references (i.e., the ones which don't start with `#`)
+/// in the spec with references to local definitions, adding the schemas fetched
+/// (by their URLs, without fragments) using the given function.
+///
+/// Relative references are resolved against the given base URL (usually the
+/// location of the spec), and references in the fetched documents are resolved
+/// against their own URLs. Definitions are named after the last segment of the
+/// fragment (if any) or the file name of the URL (without extension), with a
+/// numeric suffix if that name is already taken.
+pub fn bundle<F>(spec: &mut Value, base: Option<&str>, fetch: F) -> PaperClipResult<()>
+where
+    F: FnMut(&str) -> PaperClipResult<Value>,
+{
+    let base = match base {
+        Some(b) => Some(
+            Url::parse(b)
+                .map_err(|e| PaperClipError::UnresolvedReference(b.into(), e.to_string()))?,
+        ),
+        None => None,
+    };
+
+    let (pointer, prefix) = if spec.get("openapi").is_some() {
+        ("/components/schemas", "#/components/schemas/")
+    } else {
+        ("/definitions", "#/definitions/")
+    };
+
+    let mut bundler = Bundler {
+        prefix,
+        fetch,
+        documents: HashMap::new(),
+        names: HashMap::new(),
+        taken: spec
+            .pointer(pointer)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(k, _)| k.clone())
+            .collect(),
+        pending: vec![],
+    };
+
+    bundler.rewrite(spec, base.as_ref(), false)?;
+    let mut definitions = Map::new();
+    while let Some((name, mut schema, url)) = bundler.pending.pop() {
+        bundler.rewrite(&mut schema, Some(&url), true)?;
+        definitions.insert(name, schema);
+    }
+
+    if definitions.is_empty() {
+        return Ok(());
+    }
+
+    let mut target = spec;
+    for key in pointer.split('/').skip(1) {
+        if !target[key].is_object() {
+            target[key] = Value::Object(Map::new());
+        }
+
+        target = &mut target[key];
+    }
+
+    if let Value::Object(map) = target {
+        map.extend(definitions);
+    }
+
+    Ok(())
+}
+
+struct Bundler<F> {
+    prefix: &'static str,
+    fetch: F,
+    /// Fetched documents (by URL).
+    documents: HashMap<String, Value>,
+    /// Names of local definitions (by the URLs of external schemas).
+    names: HashMap<String, String>,
+    /// Names used by existing or added definitions.
+    taken: HashSet<String>,
+    /// Schemas (along with the URLs of their documents) yet to be rewritten.
+    pending: Vec<(String, Value, Url)>,
+}
+
+impl<F> Bundler<F>
+where
+    F: FnMut(&str) -> PaperClipResult<Value>,
+{
+    /// Recursively replaces external references in the value with local ones.
+    ///
+    /// Local references (starting with `#`) are retained in the spec, but they're
+    /// resolved against the base URL in external documents.
+    fn rewrite(
+        &mut self,
+        value: &mut Value,
+        base: Option<&Url>,
+        external: bool,
+    ) -> PaperClipResult<()> {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match value {
+                        Value::String(r) if key == "$ref" => {
+                            if r.starts_with('#') && !external {
+                                continue;
+                            }
+
+                            let url = match base {
+                                Some(b) => b.join(r),
+                                None => Url::parse(r),
+                            }
+                            .map_err(|e| {
+                                PaperClipError::UnresolvedReference(r.clone(), e.to_string())
+                            })?;
+
+                            let name = self.local_name(url)?;
+                            *r = format!("{}{}", self.prefix, name);
+                        }
+                        _ => self.rewrite(value, base, external)?,
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.rewrite(value, base, external)?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the local definition for the external schema in the
+    /// given URL, fetching it if needed.
+    fn local_name(&mut self, mut url: Url) -> PaperClipResult<String> {
+        if url.fragment() == Some("") {
+            url.set_fragment(None);
+        }
+
+        if let Some(name) = self.names.get(url.as_str()) {
+            return Ok(name.clone());
+        }
+
+        let mut doc_url = url.clone();
+        doc_url.set_fragment(None);
+        if !self.documents.contains_key(doc_url.as_str()) {
+            let document = (self.fetch)(doc_url.as_str())?;
+            self.documents.insert(doc_url.as_str().into(), document);
+        }
+
+        let fragment = url.fragment().unwrap_or("");
+        let schema = self.documents[doc_url.as_str()]
+            .pointer(fragment)
+            .cloned()
+            .ok_or_else(|| {
+                PaperClipError::UnresolvedReference(url.as_str().into(), "no such schema".into())
+            })?;
+
+        let stem = fragment
+            .rsplit('/')
+            .find(|s| !s.is_empty())
+            .or_else(|| {
+                url.path_segments()
+                    .and_then(|s| s.rev().find(|s| !s.is_empty()))
+            })
+            .map(|s| s.split('.').next().unwrap_or(s))
+            .filter(|s| !s.is_empty())
+            .unwrap_or("External")
+            .to_owned();
+        let mut name = stem.clone();
+        let mut suffix = 1;
+        while self.taken.contains(&name) {
+            suffix += 1;
+            name = format!("{}{}", stem, suffix);
+        }
+
+        self.taken.insert(name.clone());
+        self.names.insert(url.as_str().into(), name.clone());
+        self.pending.push((name.clone(), schema, doc_url));
+        Ok(name)
+    }
+}
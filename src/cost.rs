@@ -0,0 +1,87 @@
+//! Cost classes of operations.
+//!
+//! Operations can be marked with their expected cost (for example, through
+//! `#[api_v2_operation(cost = "heavy")]`), which ends up in the `x-cost` extension.
+//! Gateways usually rate limit by tags (or services) rather than individual
+//! operations, so the cost classes can be aggregated per tag and embedded in the
+//! `x-cost-by-tag` extension of the spec.
+//!
+//! ```rust
+//! use paperclip::cost;
+//! use serde_json::json;
+//!
+//! let mut spec = json!({
+//!     "paths": {
+//!         "/pets": {
+//!             "get": { "tags": ["pets"], "x-cost": "light" },
+//!             "post": { "tags": ["pets", "admin"], "x-cost": "heavy" }
+//!         }
+//!     }
+//! });
+//!
+//! cost::embed_cost_by_tag(&mut spec);
+//! assert_eq!(spec[cost::COST_BY_TAG_EXTENSION], json!({
+//!     "admin": { "heavy": ["POST /pets"] },
+//!     "pets": { "heavy": ["POST /pets"], "light": ["GET /pets"] }
+//! }));
+//! ```
+
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+
+/// Extension for the cost class of an operation.
+pub const COST_EXTENSION: &str = "x-cost";
+/// Extension for the operations in each cost class, grouped by tag.
+pub const COST_BY_TAG_EXTENSION: &str = "x-cost-by-tag";
+
+/// Operations (as `METHOD path`) for each cost class.
+pub type CostClasses = BTreeMap<String, Vec<String>>;
+
+/// Returns the cost classes of operations for each tag in the given spec.
+///
+/// Numeric costs are stringified, operations without a cost or tags are ignored,
+/// and paths are prefixed with the `basePath` of v2 specs.
+pub fn cost_by_tag(spec: &Value) -> BTreeMap<String, CostClasses> {
+    let base = spec["basePath"]
+        .as_str()
+        .unwrap_or("")
+        .trim_end_matches('/');
+    let mut tags = BTreeMap::<String, CostClasses>::new();
+    for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+        let methods = item.as_object().into_iter().flatten();
+        for (method, op) in methods.filter(|(k, _)| *k != "parameters" && !k.starts_with("x-")) {
+            let class = match &op[COST_EXTENSION] {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => continue,
+            };
+
+            let name = format!("{} {}{}", method.to_uppercase(), base, path);
+            for tag in op["tags"].as_array().into_iter().flatten() {
+                if let Some(tag) = tag.as_str() {
+                    tags.entry(tag.into())
+                        .or_default()
+                        .entry(class.clone())
+                        .or_default()
+                        .push(name.clone());
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+/// Embeds the cost classes for each tag in the `x-cost-by-tag` extension of the
+/// spec (replacing the existing one, if any).
+pub fn embed_cost_by_tag(spec: &mut Value) {
+    let tags = cost_by_tag(spec);
+    if let Value::Object(map) = spec {
+        map.remove(COST_BY_TAG_EXTENSION);
+        if !tags.is_empty() {
+            let value = serde_json::to_value(tags).expect("serializing cost classes");
+            map.insert(COST_BY_TAG_EXTENSION.into(), value);
+        }
+    }
+}
@@ -50,6 +50,12 @@ pub enum PaperClipError {
     /// A valid path cannot be obtained for the given definition.
     #[error("Invalid path for definition: {:?}", _0)]
     InvalidDefinitionPath(PathBuf),
+    /// Error encountered while querying a spec.
+    #[error("{}", _0)]
+    Query(paperclip_core::QueryError),
+    /// An external reference cannot be resolved.
+    #[error("Cannot resolve reference {:?}: {}", _0, _1)]
+    UnresolvedReference(String, String),
     /// I/O errors.
     #[error("I/O error: {}", _0)]
     Io(std::io::Error),
@@ -59,6 +65,10 @@ pub enum PaperClipError {
     /// YAML coding errors.
     #[error("YAML error: {}", _0)]
     Yaml(serde_yaml::Error),
+    #[cfg(feature = "simd-json")]
+    /// JSON parsing errors (with SIMD instructions).
+    #[error("JSON error: {}", _0)]
+    SimdJson(simd_json::Error),
     #[cfg(feature = "codegen-fmt")]
     /// Errors from rustfmt.
     #[error("Rustfmt formatting error: {}", _0)]
@@ -72,7 +82,10 @@ pub enum PaperClipError {
 impl_err_from!(PaperClipError::std::io::Error > Io);
 impl_err_from!(PaperClipError::serde_json::Error > Json);
 impl_err_from!(PaperClipError::serde_yaml::Error > Yaml);
+#[cfg(feature = "simd-json")]
+impl_err_from!(PaperClipError::simd_json::Error > SimdJson);
 impl_err_from!(PaperClipError::paperclip_core::ValidationError > Validation);
+impl_err_from!(PaperClipError::paperclip_core::QueryError > Query);
 #[cfg(feature = "codegen-fmt")]
 impl_err_from!(PaperClipError::rustfmt_nightly::ErrorKind > RustFmt);
 #[cfg(feature = "codegen")]
@@ -0,0 +1,208 @@
+//! Fuzz targets from the operations of (v2) specs.
+//!
+//! The targets are laid out as a [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz)
+//! crate, with a target for each operation. Each target builds requests for its operation
+//! from the fuzzer's input (usually conforming to the spec, and sometimes violating its
+//! schemas at their boundaries) and sends them to the service at a configurable base URL.
+//! Responses with `5xx` status codes are crashes.
+//!
+//! ```rust
+//! use paperclip::fuzz;
+//! use serde_json::json;
+//!
+//! let spec = json!({
+//!     "host": "localhost:8080",
+//!     "basePath": "/api",
+//!     "paths": {
+//!         "/pets": {
+//!             "get": { "operationId": "listPets" },
+//!             "post": {}
+//!         }
+//!     }
+//! });
+//!
+//! let targets = fuzz::targets(&spec);
+//! assert_eq!(targets[0].name, "list_pets");
+//! assert_eq!(targets[1].name, "post_pets");
+//! assert_eq!(fuzz::base_url(&spec), "http://localhost:8080/api");
+//!
+//! let files = fuzz::files(&spec, "pets", None);
+//! assert!(files.contains_key(std::path::Path::new("fuzz_targets/list_pets.rs")));
+//! ```
+
+use serde_json::Value;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+/// Runtime (the library of the crate) used by the targets.
+const RUNTIME: &str = include_str!("build/fuzz_runtime.rs");
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Fuzz target for an operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzTarget {
+    /// Name of the target (the operation ID in snake case, or the method and path).
+    pub name: String,
+    /// HTTP method (in uppercase).
+    pub method: String,
+    /// Path template (without the base path).
+    pub path: String,
+}
+
+/// Collects the targets for the operations in the given spec.
+pub fn targets(spec: &Value) -> Vec<FuzzTarget> {
+    let mut names = BTreeSet::new();
+    let mut targets = vec![];
+    for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+        for method in METHODS.iter().filter(|m| item.get(**m).is_some()) {
+            let name = match item[*method]["operationId"].as_str() {
+                Some(id) => snake_case(id),
+                None => snake_case(&format!("{}{}", method, path)),
+            };
+
+            let mut unique = name.clone();
+            let mut i = 1;
+            while !names.insert(unique.clone()) {
+                i += 1;
+                unique = format!("{}_{}", name, i);
+            }
+
+            targets.push(FuzzTarget {
+                name: unique,
+                method: method.to_uppercase(),
+                path: path.clone(),
+            });
+        }
+    }
+
+    targets
+}
+
+/// Returns the base URL of the service from the (first) scheme, host and base path
+/// of the spec (defaulting to `http` and `localhost`).
+pub fn base_url(spec: &Value) -> String {
+    let scheme = spec["schemes"][0].as_str().unwrap_or("http");
+    let host = spec["host"].as_str().unwrap_or("localhost");
+    let base = spec["basePath"].as_str().unwrap_or("");
+    format!("{}://{}{}", scheme, host, base.trim_end_matches('/'))
+}
+
+/// Returns the files (by their paths relative to the crate) of the cargo-fuzz crate
+/// (named `<name>-fuzz`) for the given spec. Requests are sent to the given base URL
+/// (or the one in the spec) unless `FUZZ_BASE_URL` is set when running the targets.
+pub fn files(spec: &Value, name: &str, base_url: Option<&str>) -> BTreeMap<PathBuf, String> {
+    let mut files = BTreeMap::new();
+    let targets = targets(spec);
+    let lib = format!("{}_fuzz", snake_case(name));
+
+    let mut manifest = format!(
+        r#"[package]
+name = "{}-fuzz"
+version = "0.0.0"
+publish = false
+edition = "2018"
+
+[package.metadata]
+cargo-fuzz = true
+
+[lib]
+name = "{}"
+path = "src/lib.rs"
+
+[dependencies]
+arbitrary = "1"
+libfuzzer-sys = "0.4"
+once_cell = "1"
+reqwest = {{ version = "0.11", features = ["blocking", "json", "multipart"] }}
+serde_json = "1"
+
+# Prevent this from interfering with workspaces
+[workspace]
+members = ["."]
+"#,
+        name, lib
+    );
+
+    for target in &targets {
+        manifest.push_str(&format!(
+            r#"
+[[bin]]
+name = "{0}"
+path = "fuzz_targets/{0}.rs"
+test = false
+doc = false
+"#,
+            target.name
+        ));
+
+        files.insert(
+            PathBuf::from(format!("fuzz_targets/{}.rs", target.name)),
+            format!(
+                r#"#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {{
+    {}::run({:?}, {:?}, data);
+}});
+"#,
+                lib, target.method, target.path
+            ),
+        );
+    }
+
+    let base_url = base_url
+        .map(String::from)
+        .unwrap_or_else(|| self::base_url(spec));
+    files.insert(PathBuf::from("Cargo.toml"), manifest);
+    files.insert(
+        PathBuf::from("src/lib.rs"),
+        format!(
+            "{}\n/// Base URL used if `FUZZ_BASE_URL` isn't set.\nconst DEFAULT_BASE_URL: &str = {:?};\n",
+            RUNTIME, base_url
+        ),
+    );
+    files.insert(
+        PathBuf::from("spec.json"),
+        serde_json::to_string_pretty(spec).expect("serializing spec") + "\n",
+    );
+    files.insert(
+        PathBuf::from(".gitignore"),
+        "target\ncorpus\nartifacts\ncoverage\n".into(),
+    );
+
+    files
+}
+
+/// Converts the given identifier (or path) to snake case.
+fn snake_case(s: &str) -> String {
+    let mut name = String::new();
+    let mut prev = '_';
+    for c in s.chars() {
+        if c.is_ascii_uppercase() && (prev.is_ascii_lowercase() || prev.is_ascii_digit()) {
+            name.push('_');
+        }
+
+        let c = if c.is_ascii_alphanumeric() {
+            c.to_ascii_lowercase()
+        } else {
+            '_'
+        };
+        if c != '_' || !name.ends_with('_') {
+            name.push(c);
+        }
+
+        prev = c;
+    }
+
+    let name = name.trim_matches('_');
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("op_{}", name)
+    } else {
+        name.into()
+    }
+}
@@ -0,0 +1,253 @@
+//! GraphQL SDL types from the definitions of specs (experimental).
+//!
+//! Teams maintaining a GraphQL facade over a REST API can keep the types of both in
+//! sync by exporting the definitions (`definitions` of v2 specs, or `components.schemas`
+//! of v3 specs) as GraphQL types. Objects (with properties) become object types, string
+//! enums become enums, and other definitions are inlined where they're referred to.
+//!
+//! ```rust
+//! use paperclip::graphql;
+//! use serde_json::json;
+//!
+//! let spec = json!({
+//!     "definitions": {
+//!         "Pet": {
+//!             "description": "A pet in the store.",
+//!             "type": "object",
+//!             "properties": {
+//!                 "name": { "type": "string" },
+//!                 "status": { "$ref": "#/definitions/Status" },
+//!                 "tags": { "type": "array", "items": { "type": "string" } }
+//!             },
+//!             "required": ["name"]
+//!         },
+//!         "Status": { "type": "string", "enum": ["available", "sold"] }
+//!     }
+//! });
+//!
+//! assert_eq!(graphql::sdl(&spec), r#""""
+//! A pet in the store.
+//! """
+//! type Pet {
+//!   name: String!
+//!   status: Status
+//!   tags: [String!]
+//! }
+//!
+//! enum Status {
+//!   available
+//!   sold
+//! }
+//! "#);
+//! ```
+//!
+//! GraphQL types are more restrictive than schemas, so some constructs are approximated:
+//! names are sanitized (invalid characters are replaced with `_`), enums whose values
+//! aren't names are strings, `date-time` strings are `DateTime` scalars, and free-form
+//! objects (like maps) or unknown schemas are `JSON` scalars.
+
+use serde_json::{Map, Value};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+/// Prefixes of references to definitions in v2 and v3 specs.
+const REF_PREFIXES: &[&str] = &["#/definitions/", "#/components/schemas/"];
+
+/// Returns the GraphQL SDL for the definitions in the given spec.
+///
+/// The types are sorted by name, with the custom scalars (if any) before them.
+pub fn sdl(spec: &Value) -> String {
+    let empty = Map::new();
+    let definitions = spec["definitions"]
+        .as_object()
+        .or_else(|| spec["components"]["schemas"].as_object())
+        .unwrap_or(&empty);
+
+    let mut exporter = Exporter {
+        definitions,
+        types: BTreeMap::new(),
+        scalars: BTreeSet::new(),
+    };
+
+    for (name, schema) in definitions {
+        if is_type(schema) {
+            exporter.add_type(&sanitize(name), schema);
+        }
+    }
+
+    let mut out = String::new();
+    for scalar in &exporter.scalars {
+        let _ = writeln!(out, "scalar {}\n", scalar);
+    }
+
+    let types = exporter.types.values().cloned().collect::<Vec<_>>();
+    out.push_str(&types.join("\n"));
+    out
+}
+
+/// Returns whether the schema is exported as a type (i.e., an object with properties
+/// or an enum of names).
+fn is_type(schema: &Value) -> bool {
+    if let Some(values) = schema["enum"].as_array() {
+        return schema["type"] == "string" && values.iter().all(is_name);
+    }
+
+    matches!(schema["properties"].as_object(), Some(p) if !p.is_empty())
+}
+
+fn is_name(value: &Value) -> bool {
+    matches!(value.as_str(), Some(s) if sanitize(s) == s)
+}
+
+/// Replaces characters which aren't allowed in GraphQL names with `_`.
+fn sanitize(name: &str) -> String {
+    let mut name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+
+    name
+}
+
+struct Exporter<'a> {
+    definitions: &'a Map<String, Value>,
+    /// SDL of the types (by name).
+    types: BTreeMap<String, String>,
+    /// Custom scalars used by the types.
+    scalars: BTreeSet<&'static str>,
+}
+
+impl<'a> Exporter<'a> {
+    /// Adds the (object or enum) type with the given name for the schema.
+    fn add_type(&mut self, name: &str, schema: &Value) {
+        if self.types.contains_key(name) {
+            return;
+        }
+
+        // Reserve the name for recursive references.
+        self.types.insert(name.into(), String::new());
+        let mut out = String::new();
+        write_description(&mut out, schema, "");
+        if let Some(values) = schema["enum"].as_array() {
+            let _ = writeln!(out, "enum {} {{", name);
+            for value in values.iter().filter_map(|v| v.as_str()) {
+                let _ = writeln!(out, "  {}", value);
+            }
+        } else {
+            let required = schema["required"].as_array();
+            let is_required = |k: &str| required.into_iter().flatten().any(|n| n == k);
+            let _ = writeln!(out, "type {} {{", name);
+            for (key, prop) in schema["properties"].as_object().into_iter().flatten() {
+                let field = sanitize(key);
+                let hint = format!("{}{}", name, pascal_case(&field));
+                let mut ty = self.type_of(prop, &hint, &mut vec![]);
+                if is_required(key) {
+                    ty.push('!');
+                }
+
+                write_description(&mut out, prop, "  ");
+                let _ = writeln!(out, "  {}: {}", field, ty);
+            }
+        }
+
+        out.push_str("}\n");
+        self.types.insert(name.into(), out);
+    }
+
+    /// Returns the GraphQL type (without `!`) for the schema, adding the types of
+    /// inline objects (named after the given hint) along the way. The stack has the
+    /// definitions being inlined, so that recursive aliases don't overflow.
+    fn type_of(&mut self, schema: &Value, hint: &str, stack: &mut Vec<String>) -> String {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = REF_PREFIXES.iter().find_map(|p| reference.strip_prefix(p));
+            let def = name.and_then(|n| self.definitions.get(n).map(|d| (n, d)));
+            return match def {
+                Some((name, def)) if is_type(def) => sanitize(name),
+                Some((name, def)) if !stack.iter().any(|n| n == name) => {
+                    stack.push(name.into());
+                    let ty = self.type_of(def, &sanitize(name), stack);
+                    stack.pop();
+                    ty
+                }
+                _ => self.scalar("JSON"),
+            };
+        }
+
+        if is_type(schema) {
+            let name = self.unique_name(hint);
+            self.add_type(&name, schema);
+            return name;
+        }
+
+        // Single compositions are the schemas themselves.
+        for key in &["allOf", "anyOf", "oneOf"] {
+            if let Some([schema]) = schema[*key].as_array().map(Vec::as_slice) {
+                return self.type_of(schema, hint, stack);
+            }
+        }
+
+        match schema["type"].as_str() {
+            Some("string") if schema["format"] == "date-time" => self.scalar("DateTime"),
+            Some("string") => "String".into(),
+            Some("integer") => "Int".into(),
+            Some("number") => "Float".into(),
+            Some("boolean") => "Boolean".into(),
+            Some("array") => {
+                let item = self.type_of(&schema["items"], &format!("{}Item", hint), stack);
+                format!("[{}!]", item)
+            }
+            _ => self.scalar("JSON"),
+        }
+    }
+
+    /// Returns the given name for inline types, suffixed (with a number) if it's taken.
+    fn unique_name(&self, name: &str) -> String {
+        let is_taken = |n: &str| {
+            self.types.contains_key(n) || self.definitions.keys().any(|k| sanitize(k) == n)
+        };
+
+        let mut unique = name.to_owned();
+        let mut i = 1;
+        while is_taken(&unique) {
+            i += 1;
+            unique = format!("{}{}", name, i);
+        }
+
+        unique
+    }
+
+    fn scalar(&mut self, name: &'static str) -> String {
+        self.scalars.insert(name);
+        name.into()
+    }
+}
+
+fn write_description(out: &mut String, schema: &Value, indent: &str) {
+    if let Some(desc) = schema["description"].as_str() {
+        let _ = writeln!(out, "{}\"\"\"", indent);
+        for line in desc.trim().lines() {
+            let line = line.replace("\"\"\"", "\\\"\"\"");
+            let _ = writeln!(out, "{}", format!("{}{}", indent, line).trim_end());
+        }
+
+        let _ = writeln!(out, "{}\"\"\"", indent);
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|s| {
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
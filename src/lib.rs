@@ -8,12 +8,23 @@
 #[cfg(feature = "codegen")]
 extern crate log;
 
+pub mod access;
+pub mod bundle;
+pub mod cost;
 mod error;
+pub mod fuzz;
+pub mod graphql;
+pub mod overlay;
+pub mod owners;
+pub mod redact;
+pub mod units;
 #[cfg(feature = "v2")]
 pub mod v2;
 
 pub use error::{PaperClipError, PaperClipResult};
-pub use paperclip_core::util;
+#[cfg(feature = "v3")]
+pub use paperclip_core::v3;
+pub use paperclip_core::{integrity, query, routes, util};
 #[cfg(feature = "v2")]
 pub use paperclip_macros::api_v2_schema_struct as api_v2_schema;
 
@@ -22,10 +33,17 @@ pub mod actix {
     //! Plugin types, traits and macros for actix-web framework.
 
     pub use paperclip_actix::{
-        api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, post, put, web,
-        Apiv2Schema, Apiv2Security, App, Mountable, OpenApiExt,
+        api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, post, put, test,
+        validation, web, Apiv2Schema, Apiv2Security, App, Mountable, OpenApiExt, RapiDoc, ReDoc,
     };
+    #[cfg(feature = "auto-routes")]
+    pub use paperclip_actix::{inventory, AnnotatedRoute};
+    #[doc(hidden)]
+    pub use paperclip_core::v2::{add_struct_parameters, infer_media_types};
     pub use paperclip_core::v2::{
-        AcceptedJson, CreatedJson, NoContent, OperationModifier, ResponderWrapper, ResponseWrapper,
+        Accepted, AcceptedJson, Batch, BatchItem, BatchResult, CreatedJson, EventStream, NdJson,
+        Negotiate, NoContent, OperationModifier, ResponderWrapper, ResponseWrapper, StatusRef,
     };
+    #[cfg(feature = "actix-multipart")]
+    pub use paperclip_core::v2::{FilePart, MultipartForm};
 }
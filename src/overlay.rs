@@ -0,0 +1,139 @@
+//! Support for the [OpenAPI Overlay](https://github.com/OAI/Overlay-Specification)
+//! specification.
+//!
+//! An overlay is a document with a list of actions, each of which targets some
+//! nodes in a spec (through a JSONPath expression) and either updates or removes
+//! them. This allows maintaining textual improvements (descriptions, examples, etc.)
+//! outside of the codebase which emits the spec.
+//!
+//! ```rust
+//! use paperclip::overlay::Overlay;
+//! use serde_json::json;
+//!
+//! let overlay: Overlay = serde_json::from_value(json!({
+//!     "overlay": "1.0.0",
+//!     "info": { "title": "Descriptions", "version": "1.0.0" },
+//!     "actions": [{
+//!         "target": "$.info",
+//!         "update": { "description": "Pet store" }
+//!     }, {
+//!         "target": "$.paths['/pets'].delete",
+//!         "remove": true
+//!     }]
+//! })).unwrap();
+//!
+//! let mut spec = json!({
+//!     "info": { "title": "Pets", "version": "1.0" },
+//!     "paths": { "/pets": { "get": {}, "delete": {} } }
+//! });
+//! overlay.apply(&mut spec).unwrap();
+//!
+//! assert_eq!(spec["info"]["description"], "Pet store");
+//! assert!(spec["paths"]["/pets"].get("delete").is_none());
+//! ```
+
+use crate::error::PaperClipResult;
+use paperclip_core::query::Query;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Overlay document.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Overlay {
+    /// Version of the overlay specification.
+    pub overlay: String,
+    pub info: OverlayInfo,
+    /// URL of the spec this overlay was meant for (if any).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    pub actions: Vec<OverlayAction>,
+}
+
+/// Metadata for the overlay.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OverlayInfo {
+    pub title: String,
+    pub version: String,
+}
+
+/// Action to be applied to the nodes matching the target.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OverlayAction {
+    /// JSONPath expression for selecting the nodes.
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value to be merged with the matching nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<Value>,
+    /// Whether the matching nodes should be removed.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub remove: bool,
+}
+
+impl Overlay {
+    /// Applies the actions in this overlay (in order) to the given spec.
+    ///
+    /// Targets are parsed as [queries](../query/struct.Query.html#method.parse).
+    /// Actions which don't match any node are ignored.
+    pub fn apply(&self, spec: &mut Value) -> PaperClipResult<()> {
+        for action in &self.actions {
+            action.apply(spec)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OverlayAction {
+    /// Applies this action to the given spec.
+    pub fn apply(&self, spec: &mut Value) -> PaperClipResult<()> {
+        let mut paths = Query::parse(&self.target)?.locate(spec);
+
+        if self.remove {
+            // Remove from the end so that array indices of other matches stay valid.
+            paths.sort();
+            for path in paths.iter().rev() {
+                path.remove(spec);
+            }
+        } else if let Some(update) = &self.update {
+            for path in &paths {
+                if let Some(node) = path.get_mut(spec) {
+                    merge_update(node, update);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Merges the update into the target node. Objects are merged recursively, values are
+/// appended to arrays and everything else gets replaced.
+fn merge_update(target: &mut Value, update: &Value) {
+    match (target, update) {
+        (Value::Object(map), Value::Object(update)) => {
+            for (k, v) in update {
+                match map.get_mut(k) {
+                    Some(existing) if existing.is_object() && v.is_object() => {
+                        merge_update(existing, v)
+                    }
+                    Some(Value::Array(list)) if v.is_array() => {
+                        list.extend(v.as_array().into_iter().flatten().cloned())
+                    }
+                    _ => {
+                        map.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(list), Value::Array(update)) => list.extend(update.iter().cloned()),
+        (Value::Array(list), update) => list.push(update.clone()),
+        (target, update) => *target = update.clone(),
+    }
+}
+
+#[inline]
+fn is_false(val: &bool) -> bool {
+    !*val
+}
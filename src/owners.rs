@@ -0,0 +1,92 @@
+//! Owners of the spec surface.
+//!
+//! Operations and definitions can be marked with the team owning them (for example,
+//! through `#[api_v2_operation(owner = "team-payments")]` or `#[openapi(owner = "...")]`
+//! on schemas), which ends up in the `x-owner` extension. The surface of the spec can
+//! then be grouped by owner, so that reviews of API changes can be routed to them.
+//!
+//! ```rust
+//! use paperclip::owners;
+//! use serde_json::json;
+//!
+//! let spec = json!({
+//!     "basePath": "/api",
+//!     "paths": {
+//!         "/charges": {
+//!             "get": { "x-owner": "team-payments" },
+//!             "post": { "x-owner": "team-payments" }
+//!         },
+//!         "/pets": {
+//!             "get": { "x-owner": "team-pets" }
+//!         }
+//!     },
+//!     "definitions": {
+//!         "Charge": { "type": "object", "x-owner": "team-payments" },
+//!         "Pet": { "type": "object" }
+//!     }
+//! });
+//!
+//! let surface = owners::surface_by_owner(&spec);
+//! assert_eq!(surface["team-payments"].operations, vec!["GET /api/charges", "POST /api/charges"]);
+//! assert_eq!(surface["team-payments"].definitions, vec!["Charge"]);
+//! assert!(surface["team-pets"].definitions.is_empty());
+//! ```
+
+use serde_derive::Serialize;
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+
+/// Extension for the owner of an operation or a definition.
+pub const OWNER_EXTENSION: &str = "x-owner";
+
+/// Surface of the spec owned by someone.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct OwnedSurface {
+    /// Operations (as `METHOD path`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<String>,
+    /// Names of the definitions (or the schemas of v3 components).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub definitions: Vec<String>,
+}
+
+/// Returns the surface (operations and definitions) of the given spec for each owner.
+///
+/// Operations and definitions without owners are ignored, and paths are prefixed
+/// with the `basePath` of v2 specs.
+pub fn surface_by_owner(spec: &Value) -> BTreeMap<String, OwnedSurface> {
+    let base = spec["basePath"]
+        .as_str()
+        .unwrap_or("")
+        .trim_end_matches('/');
+    let mut owners = BTreeMap::<String, OwnedSurface>::new();
+    for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+        let methods = item.as_object().into_iter().flatten();
+        for (method, op) in methods.filter(|(k, _)| *k != "parameters" && !k.starts_with("x-")) {
+            if let Some(owner) = op[OWNER_EXTENSION].as_str() {
+                let name = format!("{} {}{}", method.to_uppercase(), base, path);
+                owners
+                    .entry(owner.into())
+                    .or_default()
+                    .operations
+                    .push(name);
+            }
+        }
+    }
+
+    let definitions = spec["definitions"]
+        .as_object()
+        .or_else(|| spec["components"]["schemas"].as_object());
+    for (name, schema) in definitions.into_iter().flatten() {
+        if let Some(owner) = schema[OWNER_EXTENSION].as_str() {
+            owners
+                .entry(owner.into())
+                .or_default()
+                .definitions
+                .push(name.clone());
+        }
+    }
+
+    owners
+}
@@ -0,0 +1,89 @@
+//! Redaction of sensitive fields.
+//!
+//! Fields (like tokens or personal information) can be marked as sensitive (for example,
+//! through `#[openapi(sensitive)]`), which ends up in the `x-sensitive` extension of their
+//! schemas. Before publishing the spec externally, the examples and defaults of those
+//! fields (including their values in the examples and defaults of the objects having
+//! them) can be stripped.
+//!
+//! ```rust
+//! use paperclip::redact;
+//! use serde_json::json;
+//!
+//! let mut spec = json!({
+//!     "definitions": {
+//!         "Login": {
+//!             "type": "object",
+//!             "properties": {
+//!                 "user": { "type": "string", "example": "alice" },
+//!                 "password": { "type": "string", "example": "hunter2", "x-sensitive": true }
+//!             },
+//!             "example": { "user": "alice", "password": "hunter2" }
+//!         }
+//!     }
+//! });
+//!
+//! redact::strip_sensitive(&mut spec);
+//! assert_eq!(spec["definitions"]["Login"], json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "user": { "type": "string", "example": "alice" },
+//!         "password": { "type": "string", "x-sensitive": true }
+//!     },
+//!     "example": { "user": "alice" }
+//! }));
+//! ```
+
+use serde_json::Value;
+
+/// Extension marking sensitive fields.
+pub const SENSITIVE_EXTENSION: &str = "x-sensitive";
+
+/// Keys of the examples and defaults of schemas (and parameters).
+const EXAMPLE_KEYS: &[&str] = &["example", "examples", "x-example", "default"];
+
+/// Returns whether the given schema (or parameter) is marked as sensitive.
+pub fn is_sensitive(schema: &Value) -> bool {
+    schema[SENSITIVE_EXTENSION] == true
+}
+
+/// Strips the examples and defaults of sensitive fields (anywhere in the given spec),
+/// along with their values in the examples and defaults of the objects having them.
+pub fn strip_sensitive(spec: &mut Value) {
+    let sensitive = is_sensitive(spec);
+    let fields = spec["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, s)| is_sensitive(s))
+        .map(|(k, _)| k.clone())
+        .collect::<Vec<_>>();
+
+    match spec {
+        Value::Object(map) => {
+            for key in EXAMPLE_KEYS {
+                if sensitive {
+                    map.remove(*key);
+                    continue;
+                }
+
+                let values = match map.get_mut(*key) {
+                    // `examples` of schemas are lists of examples.
+                    Some(Value::Array(values)) if *key == "examples" => values.iter_mut().collect(),
+                    Some(v) if *key != "examples" => vec![v],
+                    _ => continue,
+                };
+
+                for value in values.into_iter().filter_map(|v| v.as_object_mut()) {
+                    for name in &fields {
+                        value.remove(name);
+                    }
+                }
+            }
+
+            map.values_mut().for_each(strip_sensitive);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_sensitive),
+        _ => (),
+    }
+}
@@ -0,0 +1,185 @@
+//! Units of measurement of numeric fields.
+//!
+//! Fields can be annotated with their units (for example, through
+//! `#[openapi(unit = "milliseconds")]`), which ends up in the `x-unit` extension of
+//! their schemas. Since most tools ignore extensions, the units can also be appended
+//! to the descriptions of the fields, and numeric fields which look like durations
+//! or sizes (like `timeout` or `max_size`) without units can be flagged.
+//!
+//! ```rust
+//! use paperclip::units;
+//! use serde_json::json;
+//!
+//! let mut spec = json!({
+//!     "definitions": {
+//!         "Config": {
+//!             "type": "object",
+//!             "properties": {
+//!                 "timeout": { "type": "integer", "description": "Timeout of requests.", "x-unit": "milliseconds" },
+//!                 "retry_delay": { "type": "integer" },
+//!                 "max_size_bytes": { "type": "integer" }
+//!             }
+//!         }
+//!     }
+//! });
+//!
+//! assert_eq!(units::unitless_fields(&spec), vec!["#/definitions/Config/properties/retry_delay"]);
+//!
+//! units::describe_units(&mut spec);
+//! assert_eq!(
+//!     spec["definitions"]["Config"]["properties"]["timeout"]["description"],
+//!     "Timeout of requests (in milliseconds)."
+//! );
+//! ```
+
+use serde_json::Value;
+
+/// Extension for the unit of a numeric field.
+pub const UNIT_EXTENSION: &str = "x-unit";
+
+/// Words in the names of fields which are usually durations or sizes.
+const QUANTITY_WORDS: &[&str] = &[
+    "age", "capacity", "delay", "duration", "elapsed", "interval", "latency", "period", "quota",
+    "size", "timeout", "ttl", "wait",
+];
+
+/// Words (usually suffixes) in the names of fields which are units (or which
+/// indicate counts, like `page_size`).
+const UNIT_WORDS: &[&str] = &[
+    "b",
+    "bytes",
+    "count",
+    "d",
+    "days",
+    "gb",
+    "gib",
+    "h",
+    "hours",
+    "items",
+    "kb",
+    "kib",
+    "mb",
+    "mib",
+    "millis",
+    "milliseconds",
+    "min",
+    "mins",
+    "minutes",
+    "ms",
+    "nanos",
+    "nanoseconds",
+    "ns",
+    "page",
+    "s",
+    "sec",
+    "seconds",
+    "secs",
+    "us",
+];
+
+/// Appends the units (in the `x-unit` extension) of the schemas (anywhere in the
+/// given spec) to their descriptions, unless they already mention them.
+pub fn describe_units(spec: &mut Value) {
+    match spec {
+        Value::Object(map) => {
+            let unit = map
+                .get(UNIT_EXTENSION)
+                .and_then(|u| u.as_str())
+                .map(String::from);
+            if let Some(unit) = unit {
+                let description = match map.get("description").and_then(|d| d.as_str()) {
+                    Some(d) if d.contains(unit.as_str()) => None,
+                    Some(d) if d.ends_with('.') => {
+                        Some(format!("{} (in {}).", d.trim_end_matches('.'), unit))
+                    }
+                    Some(d) => Some(format!("{} (in {})", d, unit)),
+                    None => Some(format!("In {}.", unit)),
+                };
+
+                if let Some(d) = description {
+                    map.insert("description".into(), Value::String(d));
+                }
+            }
+
+            map.values_mut().for_each(describe_units);
+        }
+        Value::Array(values) => values.iter_mut().for_each(describe_units),
+        _ => (),
+    }
+}
+
+/// Returns the locations (as JSON pointers) of numeric properties (anywhere in the
+/// given spec) whose names look like durations or sizes, but which don't have units
+/// (neither in the `x-unit` extension nor in their names, like `timeout_ms`).
+pub fn unitless_fields(spec: &Value) -> Vec<String> {
+    let mut fields = vec![];
+    collect_unitless_fields(spec, &mut String::from("#"), &mut fields);
+    fields
+}
+
+fn collect_unitless_fields(value: &Value, pointer: &mut String, fields: &mut Vec<String>) {
+    let len = pointer.len();
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                collect_unitless_fields(value, pointer, fields);
+                pointer.truncate(len);
+            }
+
+            let properties = map.get("properties").and_then(|p| p.as_object());
+            for (name, schema) in properties.into_iter().flatten() {
+                let numeric = matches!(schema["type"].as_str(), Some("integer") | Some("number"));
+                if numeric && schema.get(UNIT_EXTENSION).is_none() && is_unitless_quantity(name) {
+                    fields.push(format!(
+                        "{}/properties/{}",
+                        pointer,
+                        name.replace('~', "~0").replace('/', "~1")
+                    ));
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                pointer.push_str(&format!("/{}", i));
+                collect_unitless_fields(value, pointer, fields);
+                pointer.truncate(len);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Returns whether the given field name looks like a duration or a size without a unit.
+fn is_unitless_quantity(name: &str) -> bool {
+    let words = words(name);
+    words.iter().any(|w| QUANTITY_WORDS.contains(&w.as_str()))
+        && !words.iter().any(|w| UNIT_WORDS.contains(&w.as_str()))
+}
+
+/// Splits the given name (in snake, kebab or camel case) into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut prev = '_';
+    for c in name.chars() {
+        let boundary = !c.is_ascii_alphanumeric()
+            || (c.is_ascii_uppercase() && (prev.is_ascii_lowercase() || prev.is_ascii_digit()));
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+        }
+
+        prev = c;
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
@@ -3,16 +3,16 @@ use super::{
         ApiObject, ObjectContainer, ObjectField, ObjectVariant, OpRequirement, Parameter, Response,
     },
     state::{ChildModule, EmitterState},
-    CrateMeta,
+    CrateMeta, ModuleLayout,
 };
 use crate::{
     error::PaperClipError,
     v2::{
         models::{
             Coder, CollectionFormat, DataType, DataTypeFormat, Either, HttpMethod, Items,
-            MediaRange, ParameterIn, Reference, ResolvableApi, ResolvableOperation,
-            ResolvableParameter, ResolvablePathItem, ResolvableResponse, JSON_CODER, JSON_MIME,
-            YAML_CODER, YAML_MIME,
+            MediaRange, OperationProtocol, ParameterIn, Reference, ResolvableApi,
+            ResolvableOperation, ResolvableParameter, ResolvablePathItem, ResolvableResponse,
+            Server, JSON_CODER, JSON_MIME, YAML_CODER, YAML_MIME,
         },
         Schema,
     },
@@ -21,9 +21,10 @@ use anyhow::Error;
 use heck::{CamelCase, SnakeCase};
 use http::{header::HeaderName, HeaderMap};
 use itertools::Itertools;
+use paperclip_core::util::parse_duration;
 use parking_lot::RwLock;
 use std::{
-    collections::{BTreeMap, BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::Debug,
     fs,
     ops::Deref,
@@ -36,6 +37,9 @@ use url_dep::Host;
 pub(super) const ANY_GENERIC_PARAMETER: &str = "Any";
 /// Identifier used for file types in schema. This will be replaced with `ResponseStream`.
 pub(super) const FILE_MARKER: &str = "--FILE--";
+/// Type of object query parameters, which are serialized as `deepObject`
+/// (i.e., `name[key]=value`).
+pub(super) const DEEP_OBJECT_TYPE: &str = "std::collections::BTreeMap<String, String>";
 /// Field that collects all properties when "additionalProperties" is set to "true"
 pub(super) const EXTRA_PROPS_FIELD: &str = "other_fields";
 
@@ -119,12 +123,20 @@ pub trait Emitter: Sized {
     /// Returns an iterator of path components for the given definition.
     ///
     /// **NOTE:** All components are [snake_cased](https://docs.rs/heck/*/heck/trait.SnakeCase.html)
-    /// (including the definition name).
+    /// (including the definition name). The components are based on the
+    /// configured `ModuleLayout` (namespaces in the definition name, by default).
     fn def_ns_name<'a>(
         &self,
         def: &'a Self::Definition,
     ) -> Result<Box<dyn Iterator<Item = String> + 'a>, Error> {
         let state = self.state();
+        if let Some(c) = def
+            .name()
+            .and_then(|n| state.def_modules.borrow().get(n).cloned())
+        {
+            return Ok(Box::new(c.into_iter()));
+        }
+
         def.name()
             .map(|n| n.split(state.ns_sep).map(SnakeCase::to_snake_case))
             .ok_or_else(|| {
@@ -297,7 +309,32 @@ pub trait Emitter: Sized {
             state.base_url.borrow_mut().set_path(p);
         }
 
+        // Servers are used for selecting the base URL in clients. If the spec
+        // doesn't have them, then we have a server for each (HTTP) scheme.
+        let mut servers = api.servers.clone();
+        if servers.is_empty() && api.schemes.len() > 1 {
+            for scheme in &api.schemes {
+                let (name, desc) = match scheme {
+                    OperationProtocol::Http => ("http", "HTTP"),
+                    OperationProtocol::Https => ("https", "HTTPS"),
+                    _ => continue,
+                };
+
+                let mut url = state.base_url.borrow().clone();
+                if url.set_scheme(name).is_ok() {
+                    servers.push(Server {
+                        url: url.into(),
+                        description: Some(desc.into()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        *state.servers.borrow_mut() = servers;
+
         let gen = CodegenEmitter(self);
+        gen.assign_def_modules(api);
         // Generate file contents by accumulating definitions.
         for (name, schema) in &api.definitions {
             debug!("Creating definition {}", name);
@@ -316,6 +353,16 @@ pub trait Emitter: Sized {
             .collect()?;
         }
 
+        *state.operation_paths.borrow_mut() = api
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                item.methods
+                    .values()
+                    .filter_map(move |op| op.operation_id.clone().map(|id| (id, path.clone())))
+            })
+            .collect();
+
         state.declare_modules()?;
         state.write_definitions()?;
         state.add_builders()?;
@@ -383,6 +430,79 @@ where
     E: Emitter,
     E::Definition: Debug,
 {
+    /// Assigns the module components of definitions based on the configured
+    /// layout. Nothing's assigned for the (default) path layout.
+    fn assign_def_modules(&self, api: &ResolvableApi<E::Definition>) {
+        let state = self.state();
+        let components = |name: &str| {
+            name.split(state.ns_sep)
+                .map(SnakeCase::to_snake_case)
+                .collect::<Vec<_>>()
+        };
+
+        let mut modules = state.def_modules.borrow_mut();
+        match state.layout {
+            ModuleLayout::Path => (),
+            ModuleLayout::Flat => {
+                let mut counts = HashMap::new();
+                for name in api.definitions.keys() {
+                    let c = components(name);
+                    *counts.entry(c.last().cloned()).or_insert(0) += 1;
+                }
+
+                for name in api.definitions.keys() {
+                    let c = components(name);
+                    let leaf = match counts.get(&c.last().cloned()) {
+                        Some(1) => c
+                            .last()
+                            .cloned()
+                            .expect("last item always exists for split?"),
+                        _ => c.join("_"),
+                    };
+
+                    modules.insert(name.clone(), vec![leaf]);
+                }
+            }
+            ModuleLayout::Tag => {
+                let mut tags = HashMap::<String, BTreeSet<String>>::new();
+                for op in api.paths.values().flat_map(|item| item.methods.values()) {
+                    let tag = match op.tags.first() {
+                        Some(t) => t.to_snake_case(),
+                        None => continue,
+                    };
+
+                    let params = op.parameters.iter().filter_map(|p| p.read().schema.clone());
+                    let responses = op
+                        .responses
+                        .values()
+                        .filter_map(|r| r.read().schema.clone());
+                    for schema in params.chain(responses) {
+                        let schema = schema.read();
+                        let name = match schema.items() {
+                            Some(s) => s.read().name().map(String::from),
+                            None => schema.name().map(String::from),
+                        };
+
+                        if let Some(n) = name {
+                            tags.entry(n).or_default().insert(tag.clone());
+                        }
+                    }
+                }
+
+                for name in api.definitions.keys() {
+                    let module = match tags.get(name) {
+                        Some(t) if t.len() == 1 => t.iter().next().cloned(),
+                        _ => None,
+                    };
+
+                    let mut c = vec![module.unwrap_or_else(|| "shared".into())];
+                    c.extend(components(name));
+                    modules.insert(name.clone(), c);
+                }
+            }
+        }
+    }
+
     /// Given a schema definition, generate the corresponding Rust definitions and
     /// add them to `EmitterState`.
     fn generate_from_definition(&self, def: &E::Definition) -> Result<(), Error> {
@@ -596,9 +716,22 @@ where
             // If the object has an anonymous name, then it would definitely
             // be in its own module, which is identified by the initial parent name.
             if let Some(name) = self.def_anon_name(def, &ctx.parents) {
-                ty_path.push_str("::");
                 let parent = ctx.parents.get(0).expect("expected first parent name");
-                ty_path.push_str(&parent.to_snake_case());
+                let modules = self.state().def_modules.borrow();
+                let components = modules
+                    .values()
+                    .find(|c| matches!(c.last(), Some(s) if s.to_camel_case() == *parent));
+                match components {
+                    Some(c) => c.iter().for_each(|c| {
+                        ty_path.push_str("::");
+                        ty_path.push_str(c);
+                    }),
+                    None => {
+                        ty_path.push_str("::");
+                        ty_path.push_str(&parent.to_snake_case());
+                    }
+                }
+
                 ty_path.push_str("::");
                 ty_path.push_str(&name);
                 return Ok(EmittedUnit::KnownButAnonymous(ty_path, objects));
@@ -933,6 +1066,11 @@ where
             let (ty, mut it_fmts) =
                 match resolve_parameter_type(p.data_type, p.format.as_ref(), p.items.as_ref()) {
                     Some(t) => t,
+                    None if p.data_type == Some(DataType::Object)
+                        && p.in_ == ParameterIn::Query =>
+                    {
+                        (DEEP_OBJECT_TYPE.into(), vec![])
+                    }
                     None => {
                         warn!(
                             "Skipping parameter {:?} with unknown type {:?} in path {:?}",
@@ -1004,6 +1142,12 @@ where
             meth,
             OpRequirement {
                 listable: false,
+                cacheable: op.extensions.contains_key("x-cache"),
+                timeout: op
+                    .extensions
+                    .get("x-timeout")
+                    .and_then(|t| t.as_str())
+                    .and_then(parse_duration),
                 id: op.operation_id.clone(),
                 description: op.description.clone(),
                 deprecated: op.deprecated,
@@ -1115,6 +1259,12 @@ where
                 params,
                 body_required: false,
                 listable,
+                cacheable: op.extensions.contains_key("x-cache"),
+                timeout: op
+                    .extensions
+                    .get("x-timeout")
+                    .and_then(|t| t.as_str())
+                    .and_then(parse_duration),
                 response: Response {
                     ty_path: response_ty_path,
                     contains_any: schema.contains_any(),
@@ -1,10 +1,10 @@
 use super::{
-    emitter::ANY_GENERIC_PARAMETER,
+    emitter::{ANY_GENERIC_PARAMETER, DEEP_OBJECT_TYPE},
     object,
     object::{ApiObject, ApiObjectBuilder, Response, StructField, TypeParameters},
     RUST_KEYWORDS,
 };
-use crate::v2::models::{CollectionFormat, ParameterIn, JSON_CODER, JSON_MIME};
+use crate::v2::models::{CollectionFormat, HttpMethod, ParameterIn, JSON_CODER, JSON_MIME};
 use heck::{KebabCase, SnakeCase};
 
 use std::{
@@ -76,6 +76,8 @@ impl ApiObject {
                     .map(move |(&method, req)| ApiObjectBuilder {
                         idx,
                         is_list_op: req.listable,
+                        cacheable: req.cacheable,
+                        timeout: req.timeout,
                         multiple_builders_exist: {
                             let mut iter =
                                 self.paths.values().flat_map(|path_ops| path_ops.req.iter());
@@ -438,6 +440,22 @@ where
                 ty = "std::path::PathBuf".into();
             }
 
+            // Objects are passed as `key=value` pairs delimited by ampersand.
+            if field.ty == DEEP_OBJECT_TYPE {
+                return writeln!(
+                    f,
+                    ": matches.and_then(|m| {{
+                    m.value_of(\"{arg}\").map(|v| {{
+                        v.split('&').filter(|p| !p.is_empty()).map(|p| {{
+                            let mut kv = p.splitn(2, '=');
+                            (kv.next().unwrap_or_default().into(), kv.next().unwrap_or_default().into())
+                        }}).collect()
+                    }})
+                }}),",
+                    arg = kk
+                );
+            }
+
             // We're enforcing requirements in the CLI. We can relax here.
             writeln!(
                 f,
@@ -708,25 +726,24 @@ impl<'a, 'b> SendableCodegen<'a, 'b> {
             f.write_str("#[async_trait::async_trait]\n")?;
         }
 
-        f.write_str("impl<Client: ")?;
-        f.write_str(self.builder.helper_module_prefix)?;
-        f.write_str("client::ApiClient + Sync + 'static")?;
-
-        if self.builder.needs_any {
-            f.write_str(", Any: serde::Serialize")?;
-        }
-
-        f.write_str("> ")?;
-        f.write_str(self.builder.helper_module_prefix)?;
-        f.write_str("client::Sendable<Client> for ")?;
-        self.builder.write_name(f)?;
-        self.builder
-            .write_generics_if_necessary(f, None, TypeParameters::ChangeAll)?;
+        self.write_impl_header(f, "Sendable")?;
         f.write_str(" {\n    type Output = ")?;
         let accepted_range = self.write_output_ty(f)?;
 
         f.write_str(";\n\n    const METHOD: http::Method = http::Method::")?;
         f.write_str(&method.to_string().to_uppercase())?;
+        if self.builder.cacheable && method == HttpMethod::Get && !self.builder.response.is_file() {
+            f.write_str(";\n\n    const CACHEABLE: bool = true")?;
+        }
+
+        if let Some(timeout) = self.builder.timeout {
+            write!(
+                f,
+                ";\n\n    const TIMEOUT: Option<std::time::Duration> = Some(std::time::Duration::from_millis({}))",
+                timeout.as_millis()
+            )?;
+        }
+
         f.write_str(";\n\n    fn rel_path(&self) -> std::borrow::Cow<'static, str> {\n        ")?;
 
         self.builder
@@ -763,9 +780,34 @@ impl<'a, 'b> SendableCodegen<'a, 'b> {
         }
 
         f.write_str("\n}\n")?;
+        if method == HttpMethod::Get && self.builder.response.has_validators() {
+            f.write_str("\n")?;
+            self.write_impl_header(f, "ConditionalSendable")?;
+            f.write_str(" {}\n")?;
+        }
+
         self.write_response_headers_impl(f)
     }
 
+    /// Writes the header of an impl (of the given client trait) for this builder.
+    fn write_impl_header<F: Write>(&self, f: &mut F, trait_name: &str) -> fmt::Result {
+        f.write_str("impl<Client: ")?;
+        f.write_str(self.builder.helper_module_prefix)?;
+        f.write_str("client::ApiClient + Sync + 'static")?;
+
+        if self.builder.needs_any {
+            f.write_str(", Any: serde::Serialize")?;
+        }
+
+        f.write_str("> ")?;
+        f.write_str(self.builder.helper_module_prefix)?;
+        write!(f, "client::{}<Client> for ", trait_name)?;
+        self.builder.write_name(f)?;
+        self.builder
+            .write_generics_if_necessary(f, None, TypeParameters::ChangeAll)?;
+        Ok(())
+    }
+
     fn write_response_headers_impl<F: Write>(&mut self, f: &mut F) -> fmt::Result {
         if self.builder.response.headers.is_empty() {
             return Ok(());
@@ -988,6 +1030,20 @@ impl<'a, 'b> SendableCodegen<'a, 'b> {
     /// Handle field for an URL query parameter.
     fn handle_query_param(&mut self, field: StructField) {
         let name = object::to_snake_case(&field.name);
+        if field.ty == DEEP_OBJECT_TYPE {
+            self.multi_value_query.push(format!(
+                "
+            &self.{}param_{}.as_ref().map(|v| {{
+                v.iter().map(|(k, v)| (format!(\"{{}}[{{}}]\", {:?}, k), v.to_string())).collect::<Vec<_>>()
+            }}).unwrap_or_default()",
+                if self.needs_container { "inner." } else { "" },
+                name,
+                &field.name,
+            ));
+
+            return;
+        }
+
         if let Some(CollectionFormat::Multi) = field.delimiting.get(0) {
             self.multi_value_query.push(format!(
                 "
@@ -84,3 +84,24 @@ impl Default for EmitMode {
         EmitMode::Module
     }
 }
+
+/// Layout of the modules in which definitions are emitted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ModuleLayout {
+    /// Nested modules based on the namespaces in definition names
+    /// (separated by `EmitterState::ns_sep`).
+    Path,
+    /// All definitions in root. Names of definitions that clash after
+    /// stripping namespaces retain their namespaces (joined by underscores).
+    Flat,
+    /// Definitions in modules named after the tag of the operations using
+    /// them directly (through body parameters or responses). Definitions used
+    /// by operations with different tags (or by none) go in the `shared` module.
+    Tag,
+}
+
+impl Default for ModuleLayout {
+    fn default() -> Self {
+        ModuleLayout::Path
+    }
+}
@@ -19,6 +19,7 @@ use std::{
     fmt::{self, Display, Write},
     iter,
     sync::Arc,
+    time::Duration,
 };
 
 /// Regex for appropriate escaping in docs.
@@ -144,6 +145,11 @@ pub struct OpRequirement {
     pub body_required: bool,
     /// Whether this operation returns a list of the associated `ApiObject`.
     pub listable: bool,
+    /// Whether the responses of this operation can be cached by clients (i.e., it's
+    /// documented with the `x-cache` extension).
+    pub cacheable: bool,
+    /// Default timeout of this operation for clients (i.e., its `x-timeout` extension).
+    pub timeout: Option<Duration>,
     /// Response information for this operation.
     pub response: Response<String, Vec<Parameter>>,
     /// Preferred media range and encoder for the client. This is ignored for
@@ -181,6 +187,21 @@ where
     }
 }
 
+impl<S, H> Response<S, H>
+where
+    S: AsRef<str>,
+    H: AsRef<[Parameter]>,
+{
+    /// Returns whether this response (which isn't a file) has validators for
+    /// conditional requests (i.e., `ETag` or `Last-Modified` headers).
+    pub fn has_validators(&self) -> bool {
+        !self.is_file()
+            && self.headers.as_ref().iter().any(|h| {
+                h.name.eq_ignore_ascii_case("etag") || h.name.eq_ignore_ascii_case("last-modified")
+            })
+    }
+}
+
 /// Represents some parameter somewhere (header, path, query, etc.).
 #[derive(Debug, Clone)]
 pub struct Parameter {
@@ -361,6 +382,10 @@ pub(super) struct ApiObjectBuilder<'a> {
     pub rel_path: Option<&'a str>,
     /// Whether this operation returns a list object.
     pub is_list_op: bool,
+    /// Whether the responses of this operation can be cached.
+    pub cacheable: bool,
+    /// Default timeout of this operation, if any.
+    pub timeout: Option<Duration>,
     /// Response for this operation, if any.
     pub response: Response<&'a str, &'a [Parameter]>,
     /// Object to which this builder belongs to.
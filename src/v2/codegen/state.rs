@@ -2,11 +2,11 @@ use super::{
     object,
     object::ApiObject,
     template::{self, TEMPLATE},
-    CrateMeta, EmitMode,
+    CrateMeta, EmitMode, ModuleLayout, RUST_KEYWORDS,
 };
 use crate::{
     error::PaperClipError,
-    v2::models::{Coders, SpecFormat},
+    v2::models::{Coders, Server, SpecFormat},
 };
 use anyhow::Error;
 use heck::CamelCase;
@@ -37,6 +37,8 @@ pub struct EmitterState {
     pub ns_sep: &'static str,
     /// Module prefix for using in generated code.
     pub mod_prefix: &'static str,
+    /// Layout of the modules in which definitions are emitted.
+    pub layout: ModuleLayout,
 
     /* MARK: Private fields. */
     /// Base URL for the API.
@@ -53,10 +55,16 @@ pub struct EmitterState {
     pub(super) mod_children: RefCell<HashMap<PathBuf, HashSet<ChildModule>>>,
     /// Holds generated struct definitions for leaf modules.
     pub(super) def_mods: RefCell<BTreeMap<PathBuf, Vec<ApiObject>>>,
+    /// Module components of definitions (by name) based on the layout.
+    pub(super) def_modules: RefCell<HashMap<String, Vec<String>>>,
     /// Relative paths
     pub(super) rel_paths: RefCell<HashSet<String>>,
     /// Media ranges and the corresponding decoders we've registered.
     media_coders: RefCell<Vec<MediaCoder>>,
+    /// IDs of operations and their path templates.
+    pub(super) operation_paths: RefCell<BTreeMap<String, String>>,
+    /// Servers of the API (if there are multiple base URLs or server variables).
+    pub(super) servers: RefCell<Vec<Server>>,
     /// Unit types used by builders.
     unit_types: RefCell<BTreeSet<String>>,
     /// Generated CLI YAML for clap.
@@ -97,11 +105,14 @@ impl EmitterState {
     pub(crate) fn reset_internal_fields(&self) {
         *self.mod_children.borrow_mut() = Default::default();
         *self.def_mods.borrow_mut() = Default::default();
+        *self.def_modules.borrow_mut() = Default::default();
         *self.rel_paths.borrow_mut() = Default::default();
         *self.unit_types.borrow_mut() = Default::default();
         *self.cli_yaml.borrow_mut() = Default::default();
         *self.cli_match_arms.borrow_mut() = Default::default();
         *self.media_coders.borrow_mut() = Default::default();
+        *self.operation_paths.borrow_mut() = Default::default();
+        *self.servers.borrow_mut() = Default::default();
     }
 
     /// Sets the media type information for encoder/decoders.
@@ -283,7 +294,18 @@ pub mod util {
         self.write_contents(&content, &module)?;
 
         module.set_file_name("util.rs");
-        let contents = template::render(TEMPLATE::UTIL_MOD, &EmptyContext {})?;
+        let operation_paths = self
+            .operation_paths
+            .borrow()
+            .iter()
+            .map(|(id, path)| format!("({:?}, {:?})", id, path))
+            .collect::<Vec<_>>();
+        let contents = template::render(
+            TEMPLATE::UTIL_MOD,
+            &UtilModContext {
+                operation_paths: &operation_paths,
+            },
+        )?;
         self.write_contents(&contents, &module)?;
 
         self.add_cli_deps_if_needed()?;
@@ -293,18 +315,189 @@ pub mod util {
     /// Once the builders have been added, we can add API client dependencies.
     pub(crate) fn add_client_deps(&self) -> Result<(), Error> {
         let module = self.root_module_path();
+        let mut base_url = self.base_url.borrow().to_string();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+
         let contents = template::render(
             TEMPLATE::CLIENT_MOD,
             &ClientModContext {
                 mod_prefix: &self.normalized_mod_prefix(),
                 media_coders: &*self.media_coders.borrow(),
-                base_url: self.base_url.borrow().as_str(),
+                base_url: &base_url,
+                servers: &self.servers_code(&base_url)?,
             },
         )?;
 
         self.append_contents(&contents, &module)
     }
 
+    /// Returns the code for the `Server` enum (in the client module), which has a
+    /// variant (with fields for variables) for each server of the API. If the API
+    /// doesn't have servers, then the base URL is the only (default) server.
+    fn servers_code(&self, base_url: &str) -> Result<String, Error> {
+        let mut servers = self.servers.borrow().clone();
+        if servers.is_empty() {
+            servers.push(Server {
+                url: base_url.into(),
+                description: Some("Default".into()),
+                ..Default::default()
+            });
+        }
+
+        let origin = self.base_url.borrow().origin().ascii_serialization();
+        let mut names = HashSet::new();
+        let servers = servers
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut s)| {
+                // Relative URLs are relative to the host.
+                if s.url.starts_with('/') {
+                    s.url = origin.clone() + &s.url;
+                }
+
+                if !s.url.ends_with('/') {
+                    s.url.push('/');
+                }
+
+                let name = s
+                    .description
+                    .as_deref()
+                    .map(|d| d.to_camel_case())
+                    .filter(|n| n.starts_with(|c: char| c.is_ascii_alphabetic()))
+                    .filter(|n| n.chars().all(|c| c.is_ascii_alphanumeric()))
+                    .filter(|n| !names.contains(n))
+                    .unwrap_or_else(|| format!("Server{}", i + 1));
+                names.insert(name.clone());
+                (name, s)
+            })
+            .collect::<Vec<_>>();
+
+        let fields = |s: &Server| {
+            s.variables
+                .keys()
+                .map(|k| {
+                    let field = object::to_snake_case(k);
+                    if RUST_KEYWORDS.contains(&field.as_str()) {
+                        field + "_"
+                    } else {
+                        field
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut code = String::from(
+            "
+    /// Servers of this API. Variables in server URLs are fields of the variants.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Server {",
+        );
+        for (name, s) in &servers {
+            let mut doc = format!("`{}`", s.url);
+            if let Some(d) = &s.description {
+                let _ = write!(doc, "\n\n{}", d);
+            }
+
+            ApiObject::write_docs(Some(doc), &mut code, 2)?;
+            let _ = write!(code, "        {}", name);
+            if !s.variables.is_empty() {
+                code.push_str(" {");
+                for ((_, var), field) in s.variables.iter().zip(fields(s)) {
+                    let mut doc = var.description.clone().unwrap_or_default();
+                    if !var.enum_.is_empty() {
+                        if !doc.is_empty() {
+                            doc.push_str("\n\n");
+                        }
+
+                        let values = var.enum_.iter().map(|v| format!("`{}`", v)).join(", ");
+                        let _ = write!(doc, "One of {}.", values);
+                    }
+
+                    if doc.is_empty() {
+                        code.push('\n');
+                    } else {
+                        ApiObject::write_docs(Some(doc), &mut code, 3)?;
+                    }
+
+                    let _ = write!(code, "            {}: String,", field);
+                }
+
+                code.push_str("\n        }");
+            }
+
+            code.push(',');
+        }
+
+        code.push_str(
+            "
+    }
+
+    impl Server {
+        /// Base URL (ending with `/`) of this server, with its variables substituted.
+        pub fn url(&self) -> String {
+            match self {",
+        );
+        for (name, s) in &servers {
+            let fields = fields(s);
+            let _ = write!(code, "\n                Server::{}", name);
+            if !fields.is_empty() {
+                let _ = write!(code, " {{ {} }}", fields.join(", "));
+            }
+
+            let _ = write!(code, " => String::from({:?})", s.url);
+            for (var, field) in s.variables.keys().zip(&fields) {
+                let _ = write!(code, ".replace({:?}, {})", format!("{{{}}}", var), field);
+            }
+
+            code.push(',');
+        }
+
+        code.push_str(
+            "
+            }
+        }
+
+        /// All servers (in the order of the spec) with the default values of their variables.
+        pub fn all() -> Vec<Self> {
+            vec![",
+        );
+        for (name, s) in &servers {
+            let _ = write!(code, "\n                Server::{}", name);
+            if !s.variables.is_empty() {
+                code.push_str(" {");
+                for (var, field) in s.variables.values().zip(fields(s)) {
+                    let _ = write!(
+                        code,
+                        "\n                    {}: {:?}.into(),",
+                        field, var.default
+                    );
+                }
+
+                code.push_str("\n                }");
+            }
+
+            code.push(',');
+        }
+
+        code.push_str(
+            "
+            ]
+        }
+    }
+
+    impl Default for Server {
+        /// First server of the API.
+        fn default() -> Self {
+            Server::all().remove(0)
+        }
+    }",
+        );
+
+        Ok(code)
+    }
+
     /// Writes the given contents to a file at the given path (truncating the file if it exists).
     fn write_contents(&self, contents: &str, path: &Path) -> Result<(), Error> {
         let mut fd = OpenOptions::new()
@@ -548,16 +741,20 @@ impl Default for EmitterState {
             working_dir: PathBuf::from("."),
             mod_prefix: "crate::",
             ns_sep: ".",
+            layout: ModuleLayout::default(),
             #[cfg(feature = "cli")]
             crate_meta: Rc::new(RefCell::new(None)),
             base_url: RefCell::new("https://example.com".parse().expect("invalid URL?")),
             def_mods: RefCell::new(BTreeMap::new()),
+            def_modules: RefCell::new(HashMap::new()),
             rel_paths: RefCell::new(HashSet::new()),
             mod_children: RefCell::new(HashMap::new()),
             unit_types: RefCell::new(BTreeSet::new()),
             cli_yaml: RefCell::new(String::new()),
             cli_match_arms: RefCell::new(String::new()),
             media_coders: RefCell::new(vec![]),
+            operation_paths: RefCell::new(BTreeMap::new()),
+            servers: RefCell::new(vec![]),
             default_encoding: RefCell::new(SpecFormat::Json),
         }
     }
@@ -593,9 +790,15 @@ struct CliUtilContext<'a> {
     media_coders: &'a [MediaCoder],
 }
 
+#[derive(serde::Serialize)]
+struct UtilModContext<'a> {
+    operation_paths: &'a [String],
+}
+
 #[derive(serde::Serialize)]
 struct ClientModContext<'a> {
     base_url: &'a str,
+    servers: &'a str,
     mod_prefix: &'a str,
     media_coders: &'a [MediaCoder],
 }
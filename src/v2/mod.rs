@@ -94,15 +94,30 @@ use crate::error::PaperClipError;
 use paperclip_core::v2::models::SpecFormat;
 use serde::Deserialize;
 
+#[cfg(feature = "codegen")]
+use std::io::{BufRead, BufReader};
 use std::io::{Read, Seek, SeekFrom};
 
 #[cfg(feature = "codegen")]
 pub use self::codegen::{DefaultEmitter, Emitter, EmitterState};
+pub use paperclip_core::api_string_type;
+#[cfg(feature = "schemars")]
+pub use paperclip_core::impl_apiv2_schema_for_json_schema;
+#[cfg(feature = "utoipa")]
+pub use paperclip_core::impl_apiv2_schema_for_to_schema;
+#[cfg(any(feature = "schemars", feature = "utoipa"))]
+pub use paperclip_core::v2::json_schema;
+#[cfg(feature = "test-util")]
+pub use paperclip_core::v2::test_util;
+#[cfg(feature = "codegen")]
+pub use paperclip_core::v2::ResolveProgress;
 pub use paperclip_core::{
     im,
     v2::{
+        builder, compose,
         models::{self, DefaultSchema, ResolvableApi},
         schema::{self, Schema},
+        string_type,
     },
 };
 
@@ -133,3 +148,176 @@ where
     api.spec_format = fmt;
     Ok(api)
 }
+
+/// Deserialize the schema from the given JSON/YAML document (like `from_reader`).
+/// With the `simd-json` feature, JSON documents are parsed with SIMD instructions
+/// (see the `parse` benchmarks for comparing the parsers).
+pub fn from_slice<S>(bytes: &[u8]) -> Result<ResolvableApi<S>, PaperClipError>
+where
+    for<'de> S: Deserialize<'de> + Schema,
+{
+    let (mut api, fmt) = match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => (parse_json(bytes)?, SpecFormat::Json),
+        _ => (
+            serde_yaml::from_slice::<ResolvableApi<S>>(bytes)?,
+            SpecFormat::Yaml,
+        ),
+    };
+
+    api.spec_format = fmt;
+    Ok(api)
+}
+
+/// Parses the given JSON document (with SIMD instructions with the `simd-json` feature).
+#[cfg(feature = "simd-json")]
+pub fn parse_json<T>(bytes: &[u8]) -> Result<T, PaperClipError>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    // The parser needs a mutable buffer (which is reused for unescaping strings).
+    let mut bytes = bytes.to_vec();
+    Ok(simd_json::serde::from_slice(&mut bytes)?)
+}
+
+/// Parses the given JSON document (with SIMD instructions with the `simd-json` feature).
+#[cfg(not(feature = "simd-json"))]
+pub fn parse_json<T>(bytes: &[u8]) -> Result<T, PaperClipError>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    Ok(serde_json::from_slice(bytes)?)
+}
+
+/// Progress of loading a spec (reported by `load`).
+#[cfg(feature = "codegen")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadProgress {
+    /// Number of bytes of the document which have been parsed (out of the total, if known).
+    Parsing { read: u64, total: Option<u64> },
+    /// Progress of resolving the parsed schema.
+    Resolving(ResolveProgress),
+}
+
+/// Parses and resolves the spec from the given reader in stages, reporting the
+/// progress of each stage to the given function (along with the size of the
+/// document, if known).
+///
+/// Unlike `from_slice`, JSON documents are deserialized as they're read, so the
+/// whole document is never in memory along with the schema, which considerably
+/// cuts the peak memory for very large specs. YAML documents (and JSON documents
+/// with the `simd-json` feature, which needs the whole document for parsing it
+/// with SIMD instructions) are still read entirely before parsing them.
+#[cfg(feature = "codegen")]
+pub fn load<R, S, F>(
+    reader: R,
+    total: Option<u64>,
+    mut progress: F,
+) -> Result<ResolvableApi<S>, PaperClipError>
+where
+    R: Read,
+    for<'de> S: Deserialize<'de> + Schema + Default,
+    F: FnMut(LoadProgress),
+{
+    let mut reader = BufReader::new(ProgressReader {
+        inner: reader,
+        read: 0,
+        total,
+        progress: &mut progress,
+        quiet: false,
+    });
+
+    // Peek the first (non-whitespace) byte to find the format.
+    let is_json = loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break false;
+        }
+
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => break buf[i] == b'{',
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    };
+
+    let (mut api, fmt) = if is_json {
+        (load_json(reader)?, SpecFormat::Json)
+    } else {
+        (
+            serde_yaml::from_reader::<_, ResolvableApi<S>>(reader)?,
+            SpecFormat::Yaml,
+        )
+    };
+
+    api.spec_format = fmt;
+    Ok(api.resolve_with_progress(|p| progress(LoadProgress::Resolving(p)))?)
+}
+
+/// Deserializes the JSON document as it's read.
+#[cfg(all(feature = "codegen", not(feature = "simd-json")))]
+fn load_json<R, T>(reader: R) -> Result<T, PaperClipError>
+where
+    R: Read,
+    for<'de> T: Deserialize<'de>,
+{
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Reads the whole JSON document and parses it with SIMD instructions.
+///
+/// **NOTE:** Since the document is in memory along with the parsed schema, this
+/// roughly doubles the peak memory of loading the spec. The bytes read aren't
+/// reported as parsed until the whole document has been parsed.
+#[cfg(all(feature = "codegen", feature = "simd-json"))]
+fn load_json<R, T, F>(mut reader: BufReader<ProgressReader<'_, R, F>>) -> Result<T, PaperClipError>
+where
+    R: Read,
+    for<'de> T: Deserialize<'de>,
+    F: FnMut(LoadProgress),
+{
+    reader.get_mut().quiet = true;
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    // Unlike `parse_json`, the buffer is ours to modify, so it's not copied.
+    let value = simd_json::serde::from_slice(&mut bytes)?;
+
+    let reader = reader.get_mut();
+    (reader.progress)(LoadProgress::Parsing {
+        read: reader.read,
+        total: reader.total,
+    });
+    Ok(value)
+}
+
+/// Reader which reports the number of bytes read so far.
+#[cfg(feature = "codegen")]
+struct ProgressReader<'a, R, F> {
+    inner: R,
+    read: u64,
+    total: Option<u64>,
+    progress: &'a mut F,
+    /// Whether the progress shouldn't be reported while reading.
+    quiet: bool,
+}
+
+#[cfg(feature = "codegen")]
+impl<R, F> Read for ProgressReader<'_, R, F>
+where
+    R: Read,
+    F: FnMut(LoadProgress),
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.read += len as u64;
+        if !self.quiet {
+            (self.progress)(LoadProgress::Parsing {
+                read: self.read,
+                total: self.total,
+            });
+        }
+
+        Ok(len)
+    }
+}
@@ -1,22 +1,35 @@
+extern crate actix_web4 as actix_web;
+#[cfg(feature = "garde")]
+extern crate garde_dev as garde;
 #[macro_use]
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
 
-use actix_rt::System;
 use actix_service::ServiceFactory;
 use actix_web::{
-    dev::{MessageBody, Payload, ServiceRequest, ServiceResponse},
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    rt::System,
     App, Error, FromRequest, HttpRequest, HttpServer, Responder,
 };
 use futures::future::{ok as fut_ok, ready, Future, Ready};
 use once_cell::sync::Lazy;
 use paperclip::{
     actix::{
-        api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, post, put, web,
-        Apiv2Schema, Apiv2Security, CreatedJson, NoContent, OpenApiExt,
+        api_v2_errors, api_v2_errors_overlay, api_v2_operation, delete, get, post, put, test,
+        web::{self, UpstreamSpec},
+        Accepted, Apiv2Schema, Apiv2Security, Batch, BatchItem, BatchResult, CreatedJson,
+        EventStream, NdJson, Negotiate, NoContent, OpenApiExt, RapiDoc, ReDoc, StatusRef,
+    },
+    bundle, integrity, owners, redact,
+    routes::OperationUrls,
+    units,
+    v2::{
+        builder::{OperationBuilder, ParameterBuilder, SchemaBuilder},
+        compose::{Merged, Partial},
+        models::{DefaultApiRaw, ExternalDocs, HttpMethod, Info, OperationIdStrategy, Tag},
     },
-    v2::models::{DefaultApiRaw, Info, Tag},
 };
 use parking_lot::Mutex;
 
@@ -69,16 +82,15 @@ impl Default for Pet {
 }
 
 impl Responder for Pet {
-    type Error = Error;
-    type Future = Ready<Result<actix_web::HttpResponse, Error>>;
+    type Body = actix_web::body::BoxBody;
 
-    fn respond_to(self, _req: &HttpRequest) -> Self::Future {
+    fn respond_to(self, _req: &HttpRequest) -> actix_web::HttpResponse {
         let body = serde_json::to_string(&self).unwrap();
 
         // Create response and set content type
-        ready(Ok(actix_web::HttpResponse::Ok()
+        actix_web::HttpResponse::Ok()
             .content_type("application/json")
-            .body(body)))
+            .body(body)
     }
 }
 
@@ -111,7 +123,7 @@ fn test_simple_app() {
     }
 
     #[api_v2_operation]
-    async fn adopt_pet() -> Result<CreatedJson<Pet>, ()> {
+    async fn adopt_pet() -> Result<CreatedJson<Pet>, Error> {
         let pet: Pet = Pet::default();
         Ok(CreatedJson(pet))
     }
@@ -435,7 +447,7 @@ fn test_params() {
     #[api_v2_operation]
     fn get_known_badge_3(
         _p: web::Path<KnownBadgeId>,
-    ) -> impl Future<Output = Result<web::Json<KnownBadgeId>, ()>> {
+    ) -> impl Future<Output = Result<web::Json<KnownBadgeId>, Error>> {
         futures::future::ok(web::Json(KnownBadgeId("id".into())))
     }
 
@@ -729,6 +741,7 @@ fn test_params() {
                                 }
                             },
                             "post": {
+                                "consumes": ["application/x-www-form-urlencoded"],
                                 "parameters": [
                                     {
                                         "in": "path",
@@ -1222,7 +1235,7 @@ fn test_serde_flatten() {
     }
 
     #[api_v2_operation]
-    async fn some_images(_filter: web::Query<ImagesQuery>) -> Result<web::Json<Images>, ()> {
+    async fn some_images(_filter: web::Query<ImagesQuery>) -> Result<web::Json<Images>, Error> {
         #[allow(unreachable_code)]
         if _filter.paging.offset.is_some() && _filter.name.is_some() {
             unimplemented!()
@@ -1293,7 +1306,9 @@ fn test_serde_flatten() {
                           },
                           "required": [
                             "data",
-                            "paging"
+                            "offset",
+                            "size",
+                            "total"
                           ],
                           "type":"object"
                         }
@@ -1374,7 +1389,7 @@ fn test_serde_skip() {
 
     #[post("/v0/pets")]
     #[api_v2_operation]
-    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, ()>> {
+    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
         futures::future::ready(Ok(pet))
     }
 
@@ -1456,23 +1471,18 @@ fn test_serde_skip() {
 }
 
 #[test]
-fn test_list_in_out() {
-    #[derive(Serialize, Deserialize, Apiv2Schema)]
-    enum Sort {
-        Asc,
-        Desc,
-    }
-
-    #[derive(Serialize, Deserialize, Apiv2Schema)]
-    struct Params {
-        sort: Option<Sort>,
-        limit: Option<u16>,
+fn test_serde_deny_unknown_fields() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(deny_unknown_fields)]
+    struct Pet {
+        name: String,
+        id: Option<u64>,
     }
 
+    #[post("/v0/pets")]
     #[api_v2_operation]
-    fn get_pets(_q: web::Query<Params>) -> impl Future<Output = web::Json<Vec<Pet>>> {
-        #[allow(unreachable_code)]
-        ready(unimplemented!())
+    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::ready(Ok(pet))
     }
 
     run_and_check_app(
@@ -1480,7 +1490,7 @@ fn test_list_in_out() {
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .service(post_pet)
                 .build()
         },
         |addr| {
@@ -1492,69 +1502,51 @@ fn test_list_in_out() {
             check_json(
                 resp,
                 json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {
-                    "Pet": {
-                      "description": "Pets are awesome!",
-                      "properties": {
-                        "class": {
-                          "enum": ["dog", "cat", "other"],
-                          "type": "string"
-                        },
-                        "id": {
-                          "format": "int64",
-                          "type": "integer"
-                        },
-                        "name": {
-                          "description": "Pick a good one.",
-                          "type": "string"
-                        },
-                        "birthday": {
-                          "format": "date",
-                          "type": "string"
-                        },
-                        "updatedOn": {
-                          "format": "date-time",
-                          "type": "string"
-                        },
-                        "uuid": {
-                          "format": "uuid",
-                          "type": "string"
+                    "definitions": {
+                        "Pet": {
+                            "additionalProperties": false,
+                            "properties": {
+                                "id": {
+                                    "format": "int64",
+                                    "type": "integer"
+                                },
+                                "name": {
+                                    "type": "string"
+                                }
+                            },
+                            "required": ["name"],
+                            "type": "object"
                         }
-                      },
-                      "required":["birthday", "class", "name"],
-                      "type":"object"
-                    }
-                  },
-                  "paths": {
-                    "/pets": {
-                      "get": {
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "type": "array",
-                              "items": {
-                                "$ref": "#/definitions/Pet"
-                              }
+                    },
+                    "info": {
+                        "title": "",
+                        "version": ""
+                    },
+                    "paths": {
+                        "/v0/pets": {
+                            "post": {
+                                "parameters": [
+                                    {
+                                        "in": "body",
+                                        "name": "body",
+                                        "required": true,
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
+                                    }
+                                ],
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
+                                    }
+                                }
                             }
-                          }
-                        },
-                        "parameters": [{
-                            "format": "int32",
-                            "in": "query",
-                            "name": "limit",
-                            "type": "integer"
-                          }, {
-                            "enum": ["Asc", "Desc"],
-                            "in": "query",
-                            "name": "sort",
-                            "type": "string"
-                        }],
-                      },
-                    }
-                  },
-                  "swagger": "2.0"
+                        }
+                    },
+                    "swagger": "2.0"
                 }),
             );
         },
@@ -1562,56 +1554,36 @@ fn test_list_in_out() {
 }
 
 #[test]
-fn test_tags() {
-    #[derive(Serialize, Apiv2Schema)]
-    struct Image {
-        data: String,
+fn test_unit_struct_and_phantom_data() {
+    /// Marker for pets.
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct PetMarker;
+
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Id<T> {
         id: u64,
+        #[serde(skip)]
+        kind: std::marker::PhantomData<T>,
     }
 
-    #[api_v2_operation(tags(Cats, Dogs))]
-    fn some_pets_images() -> impl Future<Output = web::Json<Vec<Image>>> {
-        ready(web::Json(Vec::new()))
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Pet {
+        id: Id<PetMarker>,
+        marker: PetMarker,
     }
 
-    #[api_v2_operation(tags(Cats, "Nice cars"))]
-    fn some_cats_cars_images() -> impl Future<Output = web::Json<Vec<Image>>> {
-        ready(web::Json(Vec::new()))
+    #[post("/v0/pets")]
+    #[api_v2_operation]
+    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::ready(Ok(pet))
     }
 
     run_and_check_app(
         || {
-            let mut spec = DefaultApiRaw::default();
-            spec.tags = vec![
-                Tag {
-                    name: "Dogs".to_string(),
-                    description: Some("Images of dogs".to_string()),
-                    external_docs: None,
-                },
-                Tag {
-                    name: "Cats".to_string(),
-                    description: Some("Images of cats".to_string()),
-                    external_docs: None,
-                },
-                Tag {
-                    name: "Nice cars".to_string(),
-                    description: Some("Images of nice cars".to_string()),
-                    external_docs: None,
-                },
-            ];
-            spec.info = Info {
-                version: "0.1".into(),
-                title: "Image server".into(),
-                ..Default::default()
-            };
-
             App::new()
-                .wrap_api_with_spec(spec)
+                .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/images/pets").route(web::get().to(some_pets_images)))
-                .service(
-                    web::resource("/images/cats/cars").route(web::get().to(some_cats_cars_images)),
-                )
+                .service(post_pet)
                 .build()
         },
         |addr| {
@@ -1623,77 +1595,57 @@ fn test_tags() {
             check_json(
                 resp,
                 json!({
-                    "definitions":{
-                        "Image":{
-                            "properties":{
-                                "data":{
-                                "type":"string"
+                    "definitions": {
+                        "Pet": {
+                            "properties": {
+                                "id": {
+                                    "properties": {
+                                        "id": {
+                                            "format": "int64",
+                                            "type": "integer"
+                                        }
+                                    },
+                                    "required": ["id"],
+                                    "type": "object"
                                 },
-                                "id":{
-                                "format":"int64",
-                                "type":"integer"
+                                "marker": {
+                                    "description": "Marker for pets.",
+                                    "type": "object"
                                 }
                             },
-                            "required":[
-                                "data",
-                                "id"
-                            ],
-                            "type":"object"
+                            "required": ["id", "marker"],
+                            "type": "object"
                         }
                     },
-                    "info":{
-                        "title":"Image server",
-                        "version":"0.1"
+                    "info": {
+                        "title": "",
+                        "version": ""
                     },
-                    "paths":{
-                        "/images/pets":{
-                            "get":{
-                                "responses":{
-                                "200":{
-                                    "description":"OK",
-                                    "schema":{
-                                        "items":{
-                                            "$ref":"#/definitions/Image"
-                                        },
-                                        "type":"array"
+                    "paths": {
+                        "/v0/pets": {
+                            "post": {
+                                "parameters": [
+                                    {
+                                        "in": "body",
+                                        "name": "body",
+                                        "required": true,
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
                                     }
-                                }
-                                },
-                                "tags":[ "Cats", "Dogs" ]
-                            }
-                        },
-                        "/images/cats/cars":{
-                            "get":{
-                                "responses":{
-                                "200":{
-                                    "description":"OK",
-                                    "schema":{
-                                        "items":{
-                                            "$ref":"#/definitions/Image"
-                                        },
-                                        "type":"array"
+                                ],
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
                                     }
                                 }
-                                },
-                                "tags":[ "Cats", "Nice cars" ]
                             }
                         }
                     },
-                    "swagger":"2.0",
-                    "tags":[
-                        {
-                            "description":"Images of dogs",
-                            "name":"Dogs"
-                        },
-                        {
-                            "description":"Images of cats",
-                            "name":"Cats"
-                        },
-                        {
-                            "description":"Images of nice cars",
-                            "name":"Nice cars"
-                        }
-                    ]
+                    "swagger": "2.0"
                 }),
             );
         },
@@ -1701,39 +1653,28 @@ fn test_tags() {
 }
 
 #[test]
-#[allow(unreachable_code)]
-fn test_impl_traits() {
-    #[api_v2_operation]
-    fn index() -> impl Responder {
-        ""
-    }
-
-    #[derive(Serialize, Deserialize, Apiv2Schema)]
-    struct Params {
-        limit: Option<u16>,
-    }
-
-    #[api_v2_operation]
-    fn get_pets(
-        _data: web::Data<String>,
-        _q: web::Query<Params>,
-    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, ()>> {
-        if true {
-            // test for return in wrapper blocks (#75)
-            return futures::future::err(());
-        }
-
-        futures::future::err(())
+fn test_conditional_documentation() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Pet {
+        name: String,
+        #[openapi(cfg(test))]
+        nickname: Option<String>,
+        #[openapi(cfg(not(test)))]
+        internal_id: Option<u64>,
     }
 
     #[api_v2_operation]
-    async fn get_pet_async() -> impl Responder {
-        Pet::default()
+    fn get_pet() -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::ready(Ok(web::Json(Pet {
+            name: "".into(),
+            nickname: None,
+            internal_id: None,
+        })))
     }
 
-    #[api_v2_operation]
-    fn get_pet() -> impl Responder {
-        Pet::default()
+    #[api_v2_operation(cfg(not(test)))]
+    fn delete_pet() -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::err(actix_web::error::ErrorInternalServerError(""))
     }
 
     run_and_check_app(
@@ -1741,10 +1682,11 @@ fn test_impl_traits() {
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/").route(web::get().to(index)))
-                .service(web::resource("/pets").route(web::get().to(get_pets)))
-                .service(web::resource("/pet").route(web::get().to(get_pet)))
-                .service(web::resource("/pet_async").route(web::get().to(get_pet_async)))
+                .service(
+                    web::resource("/v0/pet")
+                        .route(web::get().to(get_pet))
+                        .route(web::delete().to(delete_pet)),
+                )
                 .build()
         },
         |addr| {
@@ -1756,79 +1698,39 @@ fn test_impl_traits() {
             check_json(
                 resp,
                 json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {
-                    "Pet": {
-                      "description": "Pets are awesome!",
-                      "properties": {
-                        "class": {
-                          "enum": ["dog", "cat", "other"],
-                          "type": "string"
-                        },
-                        "id": {
-                          "format": "int64",
-                          "type": "integer"
-                        },
-                        "name": {
-                          "description": "Pick a good one.",
-                          "type": "string"
-                        },
-                        "birthday": {
-                          "format": "date",
-                          "type": "string"
-                        },
-                        "updatedOn": {
-                          "format": "date-time",
-                          "type": "string"
-                        },
-                        "uuid": {
-                          "format": "uuid",
-                          "type": "string"
+                    "definitions": {
+                        "Pet": {
+                            "properties": {
+                                "name": {
+                                    "type": "string"
+                                },
+                                "nickname": {
+                                    "type": "string"
+                                }
+                            },
+                            "required": ["name"],
+                            "type": "object"
                         }
-                      },
-                      "required":["birthday", "class", "name"],
-                      "type":"object"
-                    }
-                  },
-                  "paths": {
-                    "/": {
-                      "get": {
-                        "responses": {}
-                      }
                     },
-                    "/pets": {
-                      "get": {
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "type": "array",
-                              "items": {
-                                "$ref": "#/definitions/Pet"
-                              }
-                            }
-                          }
-                        },
-                        "parameters": [{
-                            "format": "int32",
-                            "in": "query",
-                            "name": "limit",
-                            "type": "integer"
-                        }]
-                      },
+                    "info": {
+                        "title": "",
+                        "version": ""
                     },
-                    "/pet": {
-                      "get": {
-                        "responses": {}
-                      }
+                    "paths": {
+                        "/v0/pet": {
+                            "get": {
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     },
-                    "/pet_async": {
-                      "get": {
-                        "responses": {}
-                      }
-                    }
-                  },
-                  "swagger": "2.0"
+                    "swagger": "2.0"
                 }),
             );
         },
@@ -1836,44 +1738,71 @@ fn test_impl_traits() {
 }
 
 #[test]
-#[allow(unreachable_code)]
-fn test_operation_with_generics() {
+fn test_yaml_spec() {
     #[api_v2_operation]
-    fn get_pet_by_id<I: paperclip::v2::schema::Apiv2Schema>(
-        _path: web::Path<I>,
-    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, ()>> {
-        futures::future::ok(web::Json(vec![Pet::default()]))
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
     }
 
-    #[api_v2_operation]
-    async fn get_pet_by_name<S: paperclip::v2::schema::Apiv2Schema + ToString>(
-        _path: web::Path<S>,
-    ) -> Result<web::Json<Vec<Pet>>, ()> {
-        Ok(web::Json(vec![Pet::default()]))
-    }
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_yaml_spec_at("/api/spec.yaml")
+                .service(web::resource("/echo").route(web::post().to(echo_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let json: serde_json::Value = resp.json().unwrap();
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec.yaml", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "application/yaml"
+            );
+            let yaml: serde_json::Value = serde_yaml::from_str(&resp.text().unwrap()).unwrap();
+            assert_eq!(yaml, json);
+            assert_eq!(
+                yaml["paths"]["/echo"]["post"]["responses"]["200"]["schema"]["$ref"],
+                "#/definitions/Pet"
+            );
+        },
+    );
+}
 
+#[test]
+fn test_spec_overlay() {
     #[api_v2_operation]
-    async fn get_pet_by_type<S>(_path: web::Path<S>) -> Result<web::Json<Vec<Pet>>, ()>
-    where
-        S: paperclip::v2::schema::Apiv2Schema + ToString,
-    {
-        Ok(web::Json(vec![Pet::default()]))
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
     }
 
     run_and_check_app(
         || {
+            let mut spec = DefaultApiRaw::default();
+            spec.host = Some("localhost:8080".into());
+            spec.info.title = "Pets".into();
+
             App::new()
-                .wrap_api()
+                .wrap_api_with_spec(spec)
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/pet/id/{id}").route(web::get().to(get_pet_by_id::<u64>)))
-                .service(
-                    web::resource("/pet/name/{name}")
-                        .route(web::get().to(get_pet_by_name::<String>)),
-                )
-                .service(
-                    web::resource("/pet/type/{type}")
-                        .route(web::get().to(get_pet_by_type::<String>)),
-                )
+                .with_spec_overlay(json!({
+                    "host": "pets.example.com",
+                    "info": {
+                        "description": "Production API"
+                    },
+                    "definitions": null,
+                    "x-environment": "production"
+                }))
+                .service(web::resource("/echo").route(web::post().to(echo_pet)))
                 .build()
         },
         |addr| {
@@ -1885,124 +1814,36 @@ fn test_operation_with_generics() {
             check_json(
                 resp,
                 json!({
-                    "definitions":{
-                        "Pet":{
-                           "description":"Pets are awesome!",
-                           "properties":{
-                              "class":{
-                                 "enum":[
-                                    "dog",
-                                    "cat",
-                                    "other"
-                                 ],
-                                 "type":"string"
-                              },
-                              "id":{
-                                 "format":"int64",
-                                 "type":"integer"
-                              },
-                              "name":{
-                                 "description":"Pick a good one.",
-                                 "type":"string"
-                              },
-                              "birthday": {
-                                "format": "date",
-                                "type": "string"
-                              },
-                              "updatedOn":{
-                                 "format":"date-time",
-                                 "type":"string"
-                              },
-                              "uuid":{
-                                 "format":"uuid",
-                                 "type":"string"
-                              }
-                           },
-                           "required":[
-                             "birthday",
-                              "class",
-                              "name"
-                           ],
-                           "type":"object"
-                        }
-                     },
-                     "info":{
-                        "title":"",
-                        "version":""
-                     },
-                     "paths":{
-                        "/pet/id/{id}":{
-                           "get":{
-                              "responses":{
-                                 "200":{
-                                    "description":"OK",
-                                    "schema":{
-                                       "items":{
-                                          "$ref":"#/definitions/Pet"
-                                       },
-                                       "type":"array"
+                    "host": "pets.example.com",
+                    "info": {
+                        "description": "Production API",
+                        "title": "Pets",
+                        "version": ""
+                    },
+                    "paths": {
+                        "/echo": {
+                            "post": {
+                                "parameters": [{
+                                    "in": "body",
+                                    "name": "body",
+                                    "required": true,
+                                    "schema": {
+                                        "$ref": "#/definitions/Pet"
                                     }
-                                 }
-                              },
-                              "parameters":[
-                                {
-                                   "format":"int64",
-                                   "in":"path",
-                                   "name":"id",
-                                   "required":true,
-                                   "type":"integer"
-                                }
-                             ]
-                          },
-                        },
-                        "/pet/name/{name}":{
-                           "get":{
-                              "responses":{
-                                 "200":{
-                                    "description":"OK",
-                                    "schema":{
-                                       "items":{
-                                          "$ref":"#/definitions/Pet"
-                                       },
-                                       "type":"array"
-                                    }
-                                 }
-                              },
-                              "parameters":[
-                                {
-                                   "in":"path",
-                                   "name":"name",
-                                   "required":true,
-                                   "type":"string"
-                                }
-                             ]
-                          },
-                        },
-                        "/pet/type/{type}":{
-                           "get":{
-                              "responses":{
-                                 "200":{
-                                    "description":"OK",
-                                    "schema":{
-                                       "items":{
-                                          "$ref":"#/definitions/Pet"
-                                       },
-                                       "type":"array"
+                                }],
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
                                     }
-                                 }
-                              },
-                              "parameters":[
-                                {
-                                   "in":"path",
-                                   "name":"type",
-                                   "required":true,
-                                   "type":"string"
                                 }
-                             ]
-                          },
+                            }
                         }
-                     },
-                     "swagger":"2.0"
+                    },
+                    "swagger": "2.0",
+                    "x-environment": "production"
                 }),
             );
         },
@@ -2010,48 +1851,41 @@ fn test_operation_with_generics() {
 }
 
 #[test]
-#[allow(unreachable_code)]
-fn test_operations_documentation() {
-    /// Index call
-    #[api_v2_operation]
-    fn index() -> impl Responder {
-        ""
-    }
-
-    #[derive(Serialize, Deserialize, Apiv2Schema)]
-    struct Params {
-        limit: Option<u16>,
-    }
-
-    /// List all pets
-    ///
-    /// Will provide list of all pets available for sale
-    #[api_v2_operation]
-    fn get_pets(
-        _data: web::Data<String>,
-        _q: web::Query<Params>,
-    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, ()>> {
-        if true {
-            // test for return in wrapper blocks (#75)
-            return futures::future::err(());
-        }
+fn test_spec_sha256() {
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_spec_sha256()
+                .with_spec_overlay(json!({
+                    "host": "pets.example.com"
+                }))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
 
-        futures::future::err(())
-    }
+            assert_eq!(spec["host"], "pets.example.com");
+            assert_eq!(integrity::verify_sha256(&spec), Some(true));
 
-    /// Get pet info
-    ///
-    /// Will provide details on a pet
-    #[api_v2_operation]
-    async fn get_pet_async() -> impl Responder {
-        Pet::default()
-    }
+            // The hash doesn't depend on the format or the order of fields.
+            let yaml = serde_yaml::to_string(&spec).unwrap();
+            let spec: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(integrity::verify_sha256(&spec), Some(true));
+        },
+    );
+}
 
-    /// Get pet info
-    /// sync version
+#[test]
+fn test_definition_fingerprints() {
     #[api_v2_operation]
-    fn get_pet() -> impl Responder {
-        Pet::default()
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
     }
 
     run_and_check_app(
@@ -2059,10 +1893,8 @@ fn test_operations_documentation() {
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/").route(web::get().to(index)))
-                .service(web::resource("/pets").route(web::get().to(get_pets)))
-                .service(web::resource("/pet").route(web::get().to(get_pet)))
-                .service(web::resource("/pet_async").route(web::get().to(get_pet_async)))
+                .with_definition_fingerprints_at("/api/fingerprints")
+                .service(web::resource("/echo").route(web::post().to(echo_pet)))
                 .build()
         },
         |addr| {
@@ -2070,131 +1902,49 @@ fn test_operations_documentation() {
                 .get(&format!("http://{}/api/spec", addr))
                 .send()
                 .expect("request failed?");
+            let spec: DefaultApiRaw = resp.json().unwrap();
 
-            check_json(
-                resp,
-                json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {
-                    "Pet": {
-                      "description": "Pets are awesome!",
-                      "properties": {
-                        "class": {
-                          "enum": ["dog", "cat", "other"],
-                          "type": "string"
-                        },
-                        "id": {
-                          "format": "int64",
-                          "type": "integer"
-                        },
-                        "name": {
-                          "description": "Pick a good one.",
-                          "type": "string"
-                        },
-                        "birthday": {
-                          "format": "date",
-                          "type": "string"
-                        },
-                        "updatedOn": {
-                          "format": "date-time",
-                          "type": "string"
-                        },
-                        "uuid": {
-                          "format": "uuid",
-                          "type": "string"
-                        }
-                      },
-                      "required":["birthday", "class", "name"],
-                      "type":"object"
-                    }
-                  },
-                  "paths": {
-                    "/": {
-                      "get": {
-                        "responses": {},
-                        "summary":"Index call"
-                      }
-                    },
-                    "/pets": {
-                      "get": {
-                        "description":"Will provide list of all pets available for sale",
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "type": "array",
-                              "items": {
-                                "$ref": "#/definitions/Pet"
-                              }
-                            }
-                          }
-                        },
-                        "summary":"List all pets",
-                        "parameters": [{
-                            "format": "int32",
-                            "in": "query",
-                            "name": "limit",
-                            "type": "integer"
-                        }]
-                      },
-                    },
-                    "/pet": {
-                      "get": {
-                        "responses": {},
-                        "summary":"Get pet info sync version"
-                      }
-                    },
-                    "/pet_async": {
-                      "get": {
-                        "responses": {},
-                        "description":"Will provide details on a pet",
-                        "summary":"Get pet info"
-                      }
-                    }
-                  },
-                  "swagger": "2.0"
-                }),
+            let resp = CLIENT
+                .get(&format!("http://{}/api/fingerprints", addr))
+                .send()
+                .expect("request failed?");
+            let fingerprints: BTreeMap<String, String> = resp.json().unwrap();
+
+            assert_eq!(
+                fingerprints.keys().collect::<Vec<_>>(),
+                spec.definitions.keys().collect::<Vec<_>>()
             );
+            assert_eq!(fingerprints["Pet"], spec.definitions["Pet"].fingerprint());
+            assert_eq!(fingerprints["Pet"].len(), 64);
         },
     );
 }
 
 #[test]
-#[allow(unreachable_code)]
-fn test_operations_macro_attributes() {
-    /// Index operation
-    ///
-    /// This doc comment will be overriden by macro attrs
-    #[api_v2_operation(
-        summary = "Root",
-        description = "Provides an empty value in response",
-        operation_id = "getIndex",
-        consumes = "application/json, text/plain",
-        produces = "text/plain"
-    )]
-    fn index() -> impl Responder {
-        ""
+fn test_external_references() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[openapi(ref = "https://schemas.example.com/common/Address.json")]
+    struct Address {
+        street: String,
     }
 
-    #[derive(Serialize, Deserialize, Apiv2Schema)]
-    struct Params {
-        limit: Option<u16>,
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Customer {
+        name: String,
+        billing: Address,
+        shipping: Option<Address>,
+        #[openapi(ref = "https://schemas.example.com/common/Phone.json")]
+        phone: Option<String>,
     }
 
-    /// List all pets (in summary)
-    ///
-    /// This doc comment will be used in description
-    #[api_v2_operation(operation_id = "getPets")]
-    fn get_pets(
-        _data: web::Data<String>,
-        _q: web::Query<Params>,
-    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, ()>> {
-        if true {
-            // test for return in wrapper blocks (#75)
-            return futures::future::err(());
-        }
+    #[api_v2_operation]
+    async fn echo_customer(body: web::Json<Customer>) -> Result<web::Json<Customer>, Error> {
+        Ok(body)
+    }
 
-        futures::future::err(())
+    #[api_v2_operation]
+    async fn customer_address(body: web::Json<Customer>) -> Result<web::Json<Address>, Error> {
+        Ok(web::Json(body.into_inner().billing))
     }
 
     run_and_check_app(
@@ -2202,8 +1952,8 @@ fn test_operations_macro_attributes() {
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::resource("/").route(web::get().to(index)))
-                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .service(web::resource("/customers").route(web::post().to(echo_customer)))
+                .service(web::resource("/addresses").route(web::post().to(customer_address)))
                 .build()
         },
         |addr| {
@@ -2211,238 +1961,94 @@ fn test_operations_macro_attributes() {
                 .get(&format!("http://{}/api/spec", addr))
                 .send()
                 .expect("request failed?");
+            let mut spec: serde_json::Value = resp.json().unwrap();
 
-            check_json(
-                resp,
+            // External schemas don't have definitions.
+            assert_eq!(
+                spec["definitions"],
                 json!({
-                    "definitions": {
-                        "Pet": {
-                            "description": "Pets are awesome!",
-                            "properties": {
-                                "class": {
-                                "enum": ["dog", "cat", "other"],
-                                    "type":"string"
-                                },
-                                "id": {
-                                    "format": "int64",
-                                    "type": "integer"
-                                },
-                                "name": {
-                                    "description": "Pick a good one.",
-                                    "type": "string"
-                                },
-                                "birthday": {
-                                  "format": "date",
-                                  "type": "string"
-                                },
-                                "updatedOn": {
-                                    "format": "date-time",
-                                    "type": "string"
-                                },
-                                "uuid":{
-                                    "format": "uuid",
-                                    "type": "string"
-                                }
+                    "Customer": {
+                        "properties": {
+                            "billing": {
+                                "$ref": "https://schemas.example.com/common/Address.json"
                             },
-                            "required":[
-                                "birthday",
-                                "class",
-                                "name"
-                            ],
-                            "type":"object"
-                        }
-                    },
-                    "info": {
-                        "title":"",
-                        "version":""
-                    },
-                    "paths": {
-                        "/": {
-                            "get": {
-                                "consumes": [
-                                    "application/json",
-                                    "text/plain"
-                                ],
-                                "description": "Provides an empty value in response",
-                                "operationId": "getIndex",
-                                "produces": [ "text/plain" ],
-                                "responses": {},
-                                "summary": "Root"
+                            "name": {
+                                "type": "string"
+                            },
+                            "phone": {
+                                "$ref": "https://schemas.example.com/common/Phone.json"
+                            },
+                            "shipping": {
+                                "$ref": "https://schemas.example.com/common/Address.json"
                             }
                         },
-                        "/pets": {
-                            "get": {
-                                "description": "This doc comment will be used in description",
-                                "operationId": "getPets",
-                                "parameters":[{
-                                    "format":"int32",
-                                    "in":"query",
-                                    "name":"limit",
-                                    "type":"integer"
-                                }],
-                                "responses": {
-                                "200": {
-                                    "description": "OK",
-                                    "schema": {
-                                        "items": {
-                                            "$ref": "#/definitions/Pet"
-                                        },
-                                        "type": "array"
-                                    }
-                                }
-                                },
-                                "summary": "List all pets (in summary)"
-                            }
+                        "required": ["billing", "name"],
+                        "type": "object"
+                    }
+                })
+            );
+            assert_eq!(
+                spec["paths"]["/addresses"]["post"]["responses"]["200"]["schema"],
+                json!({ "$ref": "https://schemas.example.com/common/Address.json" })
+            );
+
+            bundle::bundle(&mut spec, None, |url| {
+                Ok(match url {
+                    "https://schemas.example.com/common/Address.json" => json!({
+                        "type": "object",
+                        "properties": {
+                            "street": { "type": "string" },
+                            "phone": { "$ref": "Phone.json" }
                         }
-                    },
-                    "swagger": "2.0"
-                }),
+                    }),
+                    _ => json!({ "type": "string" }),
+                })
+            })
+            .unwrap();
+
+            assert_eq!(
+                spec["paths"]["/addresses"]["post"]["responses"]["200"]["schema"],
+                json!({ "$ref": "#/definitions/Address" })
+            );
+            assert_eq!(
+                spec["definitions"]["Address"]["properties"]["phone"],
+                json!({ "$ref": "#/definitions/Phone" })
             );
+            assert_eq!(
+                spec["definitions"]["Customer"]["properties"]["phone"],
+                json!({ "$ref": "#/definitions/Phone" })
+            );
+            assert_eq!(spec["definitions"]["Phone"], json!({ "type": "string" }));
         },
     );
 }
 
-// #[test] // issue #71
-// fn test_multiple_method_routes() {
-//     #[api_v2_operation]
-//     fn test_get() -> impl Future<Output = String> {
-//         ready("get".into())
-//     }
-//
-//     #[api_v2_operation]
-//     fn test_post() -> impl Future<Output = String> {
-//         ready("post".into())
-//     }
-//
-//     fn test_app<F, T, B>(f: F)
-//     where
-//         F: Fn() -> App<T, B> + Clone + Send + Sync + 'static,
-//         B: MessageBody + 'static,
-//         T: ServiceFactory<
-//                 Config = (),
-//                 Request = ServiceRequest,
-//                 Response = ServiceResponse<B>,
-//                 Error = Error,
-//                 InitError = (),
-//             > + 'static,
-//     {
-//         run_and_check_app(f, |addr| {
-//             let resp = CLIENT
-//                 .get(&format!("http://{}/v1/foo", addr))
-//                 .send()
-//                 .expect("request failed?");
-//             assert_eq!(resp.status().as_u16(), 200);
-//             assert_eq!(resp.text().unwrap(), "get");
-//
-//             let resp = CLIENT
-//                 .post(&format!("http://{}/v1/foo", addr))
-//                 .send()
-//                 .expect("request failed?");
-//             assert_eq!(resp.status().as_u16(), 200);
-//             assert_eq!(resp.text().unwrap(), "post");
-//
-//             let resp = CLIENT
-//                 .get(&format!("http://{}/api/spec", addr))
-//                 .send()
-//                 .expect("request failed?");
-//
-//             check_json(
-//                 resp,
-//                 json!({
-//                   "info":{"title":"","version":""},
-//                   "definitions": {},
-//                   "paths": {
-//                     "/v1/foo": {
-//                       "get": {
-//                         "responses": {},
-//                       },
-//                       "post": {
-//                         "responses": {},
-//                       },
-//                     }
-//                   },
-//                   "swagger": "2.0",
-//                 }),
-//             );
-//         });
-//     }
-//
-//     test_app(|| {
-//         App::new()
-//             .wrap_api()
-//             .with_json_spec_at("/api/spec")
-//             .route("/v1/foo", web::get().to(test_get))
-//             .route("/v1/foo", web::post().to(test_post))
-//             .build()
-//     });
-//
-//     fn config(cfg: &mut web::ServiceConfig) {
-//         cfg.route("/foo", web::get().to(test_get))
-//             .route("/foo", web::post().to(test_post));
-//     }
-//
-//     test_app(|| {
-//         App::new()
-//             .wrap_api()
-//             .with_json_spec_at("/api/spec")
-//             .service(web::scope("/v1").configure(config))
-//             .build()
-//     });
-//
-//     fn config_1(cfg: &mut web::ServiceConfig) {
-//         cfg.route("/v1/foo", web::get().to(test_get));
-//     }
-//
-//     fn config_2(cfg: &mut web::ServiceConfig) {
-//         cfg.route("/v1/foo", web::post().to(test_post));
-//     }
-//
-//     test_app(|| {
-//         App::new()
-//             .wrap_api()
-//             .with_json_spec_at("/api/spec")
-//             .configure(config_1)
-//             .configure(config_2)
-//             .build()
-//     });
-// }
-
 #[test]
-fn test_custom_extractor_empty_schema() {
-    #[derive(Apiv2Schema)]
-    #[openapi(empty)]
-    struct SomeUselessThing<T>(T);
-
-    impl FromRequest for SomeUselessThing<String> {
-        type Error = Error;
-        type Future = Ready<Result<Self, Self::Error>>;
-        type Config = ();
+fn test_owners() {
+    /// A charge.
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[openapi(owner = "team-payments")]
+    struct Charge {
+        amount: u64,
+    }
 
-        fn from_request(_req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-            fut_ok(SomeUselessThing(String::from("booya")))
-        }
+    #[api_v2_operation(owner = "team-payments")]
+    async fn create_charge(body: web::Json<Charge>) -> Result<web::Json<Charge>, Error> {
+        Ok(body)
     }
 
-    #[api_v2_operation]
-    fn index(
-        _req: HttpRequest,
-        _payload: String,
-        _thing: SomeUselessThing<String>,
-    ) -> impl Future<Output = &'static str> {
-        ready("")
+    #[api_v2_operation(owner = "team-pets")]
+    async fn get_pet() -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
     }
 
     run_and_check_app(
         || {
             App::new()
                 .wrap_api()
-                .service(web::resource("/").route(web::get().to(index)))
-                .with_raw_json_spec(|app, spec| {
-                    app.route(
-                        "/api/spec",
-                        web::get().to(move || actix_web::HttpResponse::Ok().json(&spec)),
-                    )
-                })
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/charges").route(web::post().to(create_charge)))
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
                 .build()
         },
         |addr| {
@@ -2450,118 +2056,106 @@ fn test_custom_extractor_empty_schema() {
                 .get(&format!("http://{}/api/spec", addr))
                 .send()
                 .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
 
-            check_json(
-                resp,
-                json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {},
-                  "paths": {
-                    "/": {
-                      "get": {
-                        "responses": {}
-                      }
-                    }
-                  },
-                  "swagger": "2.0"
-                }),
+            assert_eq!(spec["definitions"]["Charge"]["x-owner"], "team-payments");
+            assert_eq!(
+                spec["paths"]["/charges"]["post"]["x-owner"],
+                "team-payments"
             );
+            assert_eq!(spec["paths"]["/pet"]["get"]["x-owner"], "team-pets");
+
+            let surface = owners::surface_by_owner(&spec);
+            assert_eq!(surface.len(), 2);
+            assert_eq!(surface["team-payments"].operations, vec!["POST /charges"]);
+            assert_eq!(surface["team-payments"].definitions, vec!["Charge"]);
+            assert_eq!(surface["team-pets"].operations, vec!["GET /pet"]);
+            assert!(surface["team-pets"].definitions.is_empty());
         },
     );
 }
 
 #[test]
-fn test_errors_app() {
-    use actix_web::{
-        error::{ErrorBadRequest, ResponseError},
-        HttpResponse,
-    };
-    use std::fmt;
+fn test_units() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Config {
+        /// Timeout of requests.
+        #[openapi(unit = "milliseconds")]
+        timeout: u64,
+        retry_delay: u64,
+        max_size_bytes: u64,
+    }
 
-    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
-    struct PetErrorScheme1 {}
-    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
-    struct PetErrorScheme2 {}
-    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
-    struct PetErrorScheme3 {}
+    #[api_v2_operation]
+    async fn get_config() -> Result<web::Json<Config>, Error> {
+        Ok(web::Json(Config {
+            timeout: 1000,
+            retry_delay: 10,
+            max_size_bytes: 1024,
+        }))
+    }
 
-    #[api_v2_errors(
-        400,
-        description = "Sorry, bad request",
-        code = 401,
-        code = 403,
-        schema = "PetErrorScheme1",
-        description = "Forbidden, go away",
-        500,
-        description = "Internal Server Error",
-        schema = "PetErrorScheme2"
-    )]
-    #[derive(Debug)]
-    struct PetError {}
-
-    #[api_v2_errors(
-        400,
-        description = "Sorry, bad request",
-        code = 401,
-        code = 403,
-        schema = "PetErrorScheme1",
-        description = "Forbidden, go away",
-        500,
-        description = "Internal Server Error",
-        default_schema = "PetErrorScheme2"
-    )]
-    #[derive(Debug)]
-    struct PetError2 {}
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/config").route(web::get().to(get_config)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let mut spec: serde_json::Value = resp.json().unwrap();
 
-    #[api_v2_errors_overlay(401)]
-    #[derive(Debug)]
-    struct PetErrorOverlay(pub PetError2);
+            assert_eq!(
+                spec["definitions"]["Config"]["properties"]["timeout"]["x-unit"],
+                "milliseconds"
+            );
+            assert_eq!(
+                units::unitless_fields(&spec),
+                vec!["#/definitions/Config/properties/retry_delay"]
+            );
 
-    impl fmt::Display for PetError {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "Bad Request")
-        }
-    }
-    impl fmt::Display for PetError2 {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "Bad Request")
-        }
-    }
+            units::describe_units(&mut spec);
+            assert_eq!(
+                spec["definitions"]["Config"]["properties"]["timeout"]["description"],
+                "Timeout of requests (in milliseconds)."
+            );
+        },
+    );
+}
 
-    impl ResponseError for PetError {
-        fn error_response(&self) -> HttpResponse {
-            HttpResponse::from_error(ErrorBadRequest("Bad Request"))
-        }
-    }
-    impl ResponseError for PetError2 {
-        fn error_response(&self) -> HttpResponse {
-            HttpResponse::from_error(ErrorBadRequest("Bad Request"))
-        }
+#[test]
+fn test_sensitive_fields() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Token {
+        value: String,
     }
 
-    #[api_v2_operation]
-    async fn echo_pet_with_errors(body: web::Json<Pet>) -> Result<web::Json<Pet>, PetError> {
-        Ok(body)
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Login {
+        user: String,
+        /// The password.
+        #[openapi(sensitive)]
+        password: String,
+        #[openapi(sensitive)]
+        token: Option<Token>,
     }
 
     #[api_v2_operation]
-    async fn echo_pet_with_errors2(
-        body: web::Json<Pet>,
-    ) -> Result<web::Json<Pet>, PetErrorOverlay> {
+    async fn login(body: web::Json<Login>) -> Result<web::Json<Login>, Error> {
         Ok(body)
     }
 
-    fn config(cfg: &mut web::ServiceConfig) {
-        cfg.service(web::resource("/echo").route(web::post().to(echo_pet_with_errors)));
-        cfg.service(web::resource("/echo2").route(web::post().to(echo_pet_with_errors2)));
-    }
-
     run_and_check_app(
         || {
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(web::scope("/api").configure(config))
+                .service(web::resource("/login").route(web::post().to(login)))
                 .build()
         },
         |addr| {
@@ -2569,328 +2163,284 @@ fn test_errors_app() {
                 .get(&format!("http://{}/api/spec", addr))
                 .send()
                 .expect("request failed?");
+            let mut spec: serde_json::Value = resp.json().unwrap();
 
-            check_json(
-                resp,
+            let props = &spec["definitions"]["Login"]["properties"];
+            assert_eq!(
+                props["password"],
                 json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {
-                    "Pet": {
-                      "description": "Pets are awesome!",
-                      "properties": {
-                        "class": {
-                          "enum": ["dog", "cat", "other"],
-                          "type": "string"
-                        },
-                        "id": {
-                          "format": "int64",
-                          "type": "integer"
-                        },
-                        "name": {
-                          "description": "Pick a good one.",
-                          "type": "string"
-                        },
-                        "birthday": {
-                          "format": "date",
-                          "type": "string"
-                        },
-                        "updatedOn": {
-                          "format": "date-time",
-                          "type": "string"
-                        },
-                        "uuid": {
-                          "format": "uuid",
-                          "type": "string"
-                        }
-                      },
-                      "required":["birthday", "class", "name"],
-                      "type":"object"
-                    },
-                    "PetErrorScheme1": {
-                      "type": "object"
-                    },
-                    "PetErrorScheme2": {
-                      "type": "object"
-                    },
-                  },
-                  "paths": {
-                    "/api/echo": {
-                      "post": {
-                        "parameters": [{
-                            "in": "body",
-                            "name": "body",
-                            "required": true,
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          }],
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          },
-                          "400": {
-                            "description": "Sorry, bad request"
-                          },
-                          "401": {
-                            "description": "Unauthorized"
-                          },
-                          "403":{
-                            "description": "Forbidden, go away",
-                            "schema": {
-                              "$ref": "#/definitions/PetErrorScheme1"
-                            }
-                          },
-                          "500": {
-                            "description": "Internal Server Error",
-                            "schema": {
-                              "$ref": "#/definitions/PetErrorScheme2"
-                            }
-                          }
-                        }
-                      }
-                    },
-                    "/api/echo2": {
-                      "post": {
-                        "parameters": [{
-                            "in": "body",
-                            "name": "body",
-                            "required": true,
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          }],
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          },
-                          "400": {
-                            "description": "Sorry, bad request",
-                            "schema": {
-                              "$ref": "#/definitions/PetErrorScheme2"
-                            }
-                          },
-                          "403":{
-                            "description": "Forbidden, go away",
-                            "schema": {
-                              "$ref": "#/definitions/PetErrorScheme1"
-                            }
-                          },
-                          "500": {
-                            "description": "Internal Server Error",
-                            "schema": {
-                              "$ref": "#/definitions/PetErrorScheme2"
-                            }
-                          }
-                        }
-                      }
-                    },
-                  },
-                  "swagger": "2.0"
-                }),
+                    "description": "The password.",
+                    "type": "string",
+                    "x-sensitive": true
+                })
             );
+            assert_eq!(props["token"]["x-sensitive"], true);
+            assert_eq!(props["user"], json!({ "type": "string" }));
+
+            let login = &mut spec["definitions"]["Login"];
+            login["example"] = json!({ "user": "alice", "password": "hunter2" });
+            login["properties"]["password"]["example"] = json!("hunter2");
+            login["properties"]["user"]["example"] = json!("alice");
+
+            redact::strip_sensitive(&mut spec);
+            let login = &spec["definitions"]["Login"];
+            assert_eq!(login["example"], json!({ "user": "alice" }));
+            assert!(login["properties"]["password"].get("example").is_none());
+            assert_eq!(login["properties"]["user"]["example"], "alice");
         },
     );
 }
 
 #[test]
-fn test_security_app() {
-    #[derive(Apiv2Security, Deserialize)]
-    #[openapi(
-        apiKey,
-        alias = "JWT",
-        in = "header",
-        name = "Authorization",
-        description = "Use format 'Bearer TOKEN'"
-    )]
-    struct AccessToken;
-
-    impl FromRequest for AccessToken {
-        type Error = Error;
-        type Future = Ready<Result<Self, Self::Error>>;
-        type Config = ();
-
-        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
-            ready(Ok(Self {}))
-        }
+fn test_scope_spec_fragment() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Invoice {
+        id: u64,
     }
 
-    #[derive(Apiv2Security, Deserialize)]
-    #[openapi(
-        oauth2,
-        alias = "MyOAuth2",
-        auth_url = "http://example.com/",
-        token_url = "http://example.com/token",
-        flow = "password"
-    )]
-    struct OAuth2Access;
-
-    impl FromRequest for OAuth2Access {
-        type Error = Error;
-        type Future = Ready<Result<Self, Self::Error>>;
-        type Config = ();
+    #[api_v2_operation]
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
+    }
 
-        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
-            ready(Ok(Self {}))
-        }
+    #[api_v2_operation]
+    async fn get_invoice(id: web::Path<u64>) -> Result<web::Json<Invoice>, Error> {
+        Ok(web::Json(Invoice {
+            id: id.into_inner(),
+        }))
     }
 
-    #[derive(Apiv2Security, Deserialize)]
-    #[openapi(parent = "OAuth2Access", scopes("pets.read", "pets.write"))]
-    struct PetScope;
+    let pets = web::scope("/pets").service(web::resource("/echo").route(web::post().to(echo_pet)));
+    let billing = web::scope("/billing")
+        .service(web::resource("/invoices/{id}").route(web::get().to(get_invoice)));
 
-    impl FromRequest for PetScope {
-        type Error = Error;
-        type Future = Ready<Result<Self, Self::Error>>;
-        type Config = ();
+    let fragment = billing.spec_fragment();
+    assert_eq!(
+        fragment.paths.keys().collect::<Vec<_>>(),
+        vec!["/billing/invoices/{id}"]
+    );
+    assert_eq!(
+        fragment.definitions.keys().collect::<Vec<_>>(),
+        vec!["Invoice"]
+    );
 
-        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
-            ready(Ok(Self {}))
-        }
-    }
+    let fragment = serde_json::to_value(pets.spec_fragment()).unwrap();
+    assert_eq!(
+        fragment["paths"]["/pets/echo"]["post"]["parameters"][0]["schema"],
+        json!({ "$ref": "#/definitions/Pet" })
+    );
+    assert_eq!(fragment["definitions"]["Pet"]["type"], "object");
+    assert!(fragment["definitions"].get("Invoice").is_none());
+}
 
+#[test]
+fn test_scope_proxy_spec() {
     #[api_v2_operation]
-    async fn echo_pet_with_jwt(_: AccessToken, body: web::Json<Pet>) -> web::Json<Pet> {
-        body
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
     }
 
-    #[api_v2_operation]
-    async fn echo_pet_with_petstore(_: PetScope, body: web::Json<Pet>) -> web::Json<Pet> {
-        body
-    }
+    let payments: DefaultApiRaw = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": { "title": "Payments", "version": "1.0" },
+        "basePath": "/v1",
+        "parameters": {
+            "chargeId": { "in": "path", "name": "id", "required": true, "type": "string" }
+        },
+        "responses": {
+            "NotFound": { "description": "Not found", "schema": { "$ref": "#/definitions/Error" } }
+        },
+        "paths": {
+            "/charges/{id}": {
+                "get": {
+                    "operationId": "getCharge",
+                    "parameters": [{ "$ref": "#/parameters/chargeId" }],
+                    "responses": {
+                        "200": { "description": "Charge", "schema": { "$ref": "#/definitions/Charge" } },
+                        "404": { "$ref": "#/responses/NotFound" }
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "Charge": {
+                "type": "object",
+                "properties": { "pet": { "$ref": "#/definitions/Pet" } }
+            },
+            "Error": { "type": "string" },
+            "Pet": { "type": "string" }
+        }
+    }))
+    .unwrap();
+
+    let scope = web::scope("/api")
+        .service(web::resource("/echo").route(web::post().to(echo_pet)))
+        .proxy_spec(payments, "/payments");
+    let fragment = serde_json::to_value(scope.spec_fragment()).unwrap();
+
+    assert_eq!(
+        fragment["paths"]["/api/payments/charges/{id}"]["get"],
+        json!({
+            "operationId": "getCharge",
+            "parameters": [{
+                "in": "path",
+                "name": "id",
+                "required": true,
+                "type": "string"
+            }],
+            "responses": {
+                "200": {
+                    "description": "Charge",
+                    "schema": { "$ref": "#/definitions/Charge" }
+                },
+                "404": {
+                    "description": "Not found",
+                    "schema": { "$ref": "#/definitions/Error" }
+                }
+            }
+        })
+    );
 
-    fn config(cfg: &mut web::ServiceConfig) {
-        cfg.service(web::resource("/echo1").route(web::post().to(echo_pet_with_jwt)))
-            .service(web::resource("/echo2").route(web::post().to(echo_pet_with_petstore)));
+    // The definition conflicting with the one in this service is renamed.
+    assert_eq!(fragment["definitions"]["Pet"]["type"], "object");
+    assert_eq!(fragment["definitions"]["Pet2"], json!({ "type": "string" }));
+    assert_eq!(
+        fragment["definitions"]["Charge"]["properties"]["pet"],
+        json!({ "$ref": "#/definitions/Pet2" })
+    );
+}
+
+#[test]
+fn test_scope_proxy_upstream() {
+    #[api_v2_operation(skip)]
+    async fn forward(tail: web::Path<String>) -> String {
+        tail.into_inner()
+    }
+
+    let upstream: DefaultApiRaw = serde_json::from_value(json!({
+        "swagger": "2.0",
+        "info": { "title": "Upstream", "version": "1.0" },
+        "basePath": "/v1",
+        "paths": {
+            "/internal/stats": {
+                "get": {
+                    "responses": {
+                        "200": { "description": "Stats", "schema": { "$ref": "#/definitions/Stats" } }
+                    }
+                }
+            },
+            "/charges": {
+                "get": {
+                    "responses": {
+                        "200": { "description": "Charges", "schema": { "$ref": "#/definitions/Charges" } }
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "Charges": { "type": "array", "items": { "type": "string" } },
+            "Stats": { "type": "object" }
+        }
+    }))
+    .unwrap();
+
+    // Proxy forwarding requests to the root of the upstream.
+    let scope = web::scope("/api")
+        .route("/upstream/{tail:.*}", web::get().to(forward))
+        .proxy_upstream(
+            "/upstream/{tail:.*}",
+            UpstreamSpec::new(upstream.clone()).with_base_path(),
+        );
+    let fragment = scope.spec_fragment();
+    assert_eq!(
+        fragment.paths.keys().collect::<Vec<_>>(),
+        vec![
+            "/api/upstream/v1/charges",
+            "/api/upstream/v1/internal/stats"
+        ]
+    );
+
+    // Proxy forwarding requests to `/v1/internal` in the upstream.
+    let scope = web::scope("/api").proxy_upstream(
+        "/stats/{tail:.*}",
+        UpstreamSpec::new(upstream)
+            .with_base_path()
+            .strip_prefix("/v1/internal"),
+    );
+    let fragment = scope.spec_fragment();
+    assert_eq!(
+        fragment.paths.keys().collect::<Vec<_>>(),
+        vec!["/api/stats/stats"]
+    );
+    assert_eq!(
+        fragment.definitions.keys().collect::<Vec<_>>(),
+        vec!["Stats"]
+    );
+}
+
+#[test]
+fn test_document_route() {
+    async fn tenant_status(path: actix_web::web::Path<String>) -> String {
+        path.into_inner()
     }
 
     run_and_check_app(
-        move || {
+        || {
+            let (op, _) = OperationBuilder::new()
+                .operation_id("tenantStatus")
+                .parameter(ParameterBuilder::path("tenant"))
+                .response(200, "Status of the tenant", None)
+                .build();
+
             App::new()
                 .wrap_api()
-                .service(web::scope("/api").configure(config))
-                .with_json_spec_at("/spec")
+                .with_json_spec_at("/api/spec")
+                .document_route("/tenants/{tenant}/status", HttpMethod::Get, op)
                 .build()
+                .route(
+                    "/tenants/{tenant}/status",
+                    actix_web::web::get().to(tenant_status),
+                )
         },
         |addr| {
             let resp = CLIENT
-                .get(&format!("http://{}/spec", addr))
+                .get(&format!("http://{}/tenants/acme/status", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.text().unwrap(), "acme");
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
                 .send()
                 .expect("request failed?");
 
             check_json(
                 resp,
                 json!({
-                  "info":{"title":"","version":""},
-                  "definitions": {
-                    "Pet": {
-                      "description": "Pets are awesome!",
-                      "properties": {
-                        "class": {
-                          "enum": ["dog", "cat", "other"],
-                          "type": "string"
-                        },
-                        "id": {
-                          "format": "int64",
-                          "type": "integer"
-                        },
-                        "name": {
-                          "description": "Pick a good one.",
-                          "type": "string"
-                        },
-                        "birthday": {
-                          "format": "date",
-                          "type": "string"
-                        },
-                        "updatedOn": {
-                          "format": "date-time",
-                          "type": "string"
-                        },
-                        "uuid": {
-                          "format": "uuid",
-                          "type": "string"
-                        }
-                      },
-                      "required":["birthday", "class", "name"],
-                      "type":"object"
-                    }
-                  },
-                  "paths": {
-                    "/api/echo1": {
-                      "post": {
-                        "parameters": [{
-                            "in": "body",
-                            "name": "body",
-                            "required": true,
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          }],
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          },
-                        },
-                        "security": [
-                          {
-                            "JWT": []
-                          }
-                        ]
-                      }
+                    "definitions": {},
+                    "info": {
+                        "title": "",
+                        "version": ""
                     },
-                    "/api/echo2": {
-                      "post": {
-                        "parameters": [{
-                            "in": "body",
-                            "name": "body",
-                            "required": true,
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
-                            }
-                          }],
-                        "responses": {
-                          "200": {
-                            "description": "OK",
-                            "schema": {
-                              "$ref": "#/definitions/Pet"
+                    "paths": {
+                        "/tenants/{tenant}/status": {
+                            "get": {
+                                "operationId": "tenantStatus",
+                                "parameters": [{
+                                    "in": "path",
+                                    "name": "tenant",
+                                    "required": true,
+                                    "type": "string"
+                                }],
+                                "responses": {
+                                    "200": {
+                                        "description": "Status of the tenant"
+                                    }
+                                }
                             }
-                          },
-                        },
-                        "security": [
-                          {
-                            "MyOAuth2": ["pets.read", "pets.write"]
-                          }
-                        ]
-                      }
-                    },
-                  },
-                  "securityDefinitions": {
-                    "JWT": {
-                        "description":"Use format 'Bearer TOKEN'",
-                        "in": "header",
-                        "name": "Authorization",
-                        "type": "apiKey"
+                        }
                     },
-                    "MyOAuth2": {
-                        "scopes": {
-                          "pets.read": "pets.read",
-                          "pets.write": "pets.write"
-                        },
-                        "type": "oauth2",
-                        "authorizationUrl": "http://example.com/",
-                        "tokenUrl": "http://example.com/token",
-                        "flow": "password"
-                    }
-                  },
-                  "swagger": "2.0"
+                    "swagger": "2.0"
                 }),
             );
         },
@@ -2898,40 +2448,24 @@ fn test_security_app() {
 }
 
 #[test]
-fn test_method_macro() {
-    #[get("/v0/pets")]
-    #[api_v2_operation]
-    fn get_pets() -> impl Future<Output = Result<web::Json<Vec<Pet>>, ()>> {
-        futures::future::ready(Ok(web::Json(Default::default())))
-    }
-    #[put("/v0/pets/{name}")]
-    #[api_v2_operation]
-    fn put_pet(
-        _name: web::Path<String>,
-        pet: web::Json<Pet>,
-    ) -> impl Future<Output = Result<web::Json<Pet>, ()>> {
-        futures::future::ready(Ok(pet))
-    }
-    #[post("/v0/pets")]
-    #[api_v2_operation]
-    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, ()>> {
-        futures::future::ready(Ok(pet))
-    }
-    #[delete("/v0/pets/{name}")]
-    #[api_v2_operation]
-    fn delete_pet(_name: web::Path<String>) -> impl Future<Output = Result<web::Json<()>, ()>> {
-        futures::future::ready(Ok(web::Json(())))
+fn test_webhooks() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct OrderCreated {
+        id: String,
+        paid: bool,
     }
 
     run_and_check_app(
         || {
+            let op = OperationBuilder::new()
+                .summary("Sent when an order is created")
+                .parameter(ParameterBuilder::body(SchemaBuilder::of::<OrderCreated>()))
+                .response(200, "Webhook was received", None);
+
             App::new()
                 .wrap_api()
                 .with_json_spec_at("/api/spec")
-                .service(get_pets)
-                .service(put_pet)
-                .service(post_pet)
-                .service(delete_pet)
+                .with_webhook("order.created", op)
                 .build()
         },
         |addr| {
@@ -2944,87 +2478,5122 @@ fn test_method_macro() {
                 resp,
                 json!({
                     "definitions": {
-                        "Pet": {
-                            "description": "Pets are awesome!",
+                        "OrderCreated": {
                             "properties": {
-                                "class": {
-                                "enum": ["dog", "cat", "other"],
-                                    "type":"string"
-                                },
                                 "id": {
-                                    "format": "int64",
-                                    "type": "integer"
-                                },
-                                "name": {
-                                    "description": "Pick a good one.",
-                                    "type": "string"
-                                },
-                                "birthday": {
-                                  "format": "date",
-                                  "type": "string"
-                                },
-                                "updatedOn": {
-                                    "format": "date-time",
                                     "type": "string"
                                 },
-                                "uuid":{
-                                    "format": "uuid",
-                                    "type": "string"
+                                "paid": {
+                                    "type": "boolean"
                                 }
                             },
-                            "required":[
-                                "birthday",
-                                "class",
-                                "name"
-                            ],
-                            "type":"object"
+                            "required": ["id", "paid"],
+                            "type": "object"
                         }
                     },
                     "info": {
-                        "title":"",
-                        "version":""
+                        "title": "",
+                        "version": ""
                     },
-                    "paths": {
-                        "/v0/pets": {
-                            "get": {
-                                "responses": {
-                                    "200": {
-                                        "description": "OK",
-                                        "schema": {
-                                            "items": {
-                                                "$ref": "#/definitions/Pet"
-                                            },
-                                            "type": "array"
-                                        }
-                                    }
-                                },
-                            },
+                    "paths": {},
+                    "swagger": "2.0",
+                    "x-webhooks": {
+                        "order.created": {
                             "post": {
-                                "parameters": [
-                                    {
-                                        "in": "body",
-                                        "name": "body",
-                                        "required": true,
-                                        "schema": {
-                                            "$ref": "#/definitions/Pet"
-                                        }
+                                "parameters": [{
+                                    "in": "body",
+                                    "name": "body",
+                                    "required": true,
+                                    "schema": {
+                                        "$ref": "#/definitions/OrderCreated"
                                     }
-                                ],
+                                }],
                                 "responses": {
                                     "200": {
-                                        "description": "OK",
-                                        "schema": {
-                                            "$ref": "#/definitions/Pet"
-                                        }
+                                        "description": "Webhook was received"
                                     }
                                 },
+                                "summary": "Sent when an order is created"
                             }
-                        },
-                        "/v0/pets/{name}": {
-                            "delete": {
-                                "parameters": [
-                                    {
-                                        "in": "path",
+                        }
+                    }
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_required_roles() {
+    #[api_v2_operation(required_role = "admin", required_role = "owner")]
+    async fn delete_pet(_path: web::Path<u64>) -> NoContent {
+        NoContent
+    }
+
+    #[api_v2_operation]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(
+                    web::resource("/pets/{id}")
+                        .route(web::get().to(get_pet))
+                        .route(web::delete().to(delete_pet)),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let item = &spec["paths"]["/pets/{id}"];
+            assert_eq!(
+                item["delete"]["x-required-roles"],
+                json!(["admin", "owner"])
+            );
+            assert!(item["get"].get("x-required-roles").is_none());
+
+            let rules = paperclip::access::rules(&spec);
+            assert_eq!(
+                rules,
+                vec![paperclip::access::AccessRule {
+                    method: "DELETE".into(),
+                    path: "/pets/{id}".into(),
+                    roles: vec!["admin".into(), "owner".into()],
+                }]
+            );
+            assert_eq!(rules[0].path_regex(), "^/pets/[^/]+$");
+
+            let policy = paperclip::access::envoy_rbac(&rules, "roles");
+            assert_eq!(policy["rules"]["action"], "DENY");
+            assert!(policy["rules"]["policies"]
+                .get("DELETE /pets/{id}")
+                .is_some());
+        },
+    );
+}
+
+#[test]
+fn test_operation_cost() {
+    #[api_v2_operation(tags(pets), cost = "heavy", extensions(x_weight = 5))]
+    async fn delete_pet(_path: web::Path<u64>) -> NoContent {
+        NoContent
+    }
+
+    #[api_v2_operation(tags(pets), cost = 1)]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(
+                    web::resource("/pets/{id}")
+                        .route(web::get().to(get_pet))
+                        .route(web::delete().to(delete_pet)),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let mut spec: serde_json::Value = resp.json().unwrap();
+            let item = &spec["paths"]["/pets/{id}"];
+            assert_eq!(item["delete"]["x-cost"], "heavy");
+            assert_eq!(item["delete"]["x-weight"], 5);
+            assert_eq!(item["get"]["x-cost"], 1);
+
+            paperclip::cost::embed_cost_by_tag(&mut spec);
+            assert_eq!(
+                spec["x-cost-by-tag"],
+                json!({
+                    "pets": {
+                        "1": ["GET /pets/{id}"],
+                        "heavy": ["DELETE /pets/{id}"]
+                    }
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_operation_cache() {
+    #[api_v2_operation(cache_control = "max-age=60", etag = true)]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/pets/{id}"]["get"];
+            assert_eq!(
+                op["x-cache"],
+                json!({ "cacheControl": "max-age=60", "etag": true })
+            );
+            assert_eq!(
+                op["responses"]["200"]["headers"],
+                json!({
+                    "Cache-Control": {
+                        "description": "Caching directives (`max-age=60`).",
+                        "type": "string"
+                    },
+                    "ETag": {
+                        "description": "Identifier for the version of the resource.",
+                        "type": "string"
+                    }
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_response_headers() {
+    #[api_v2_operation(
+        response_header(name = "X-Request-Id", type = "string", format = "uuid"),
+        response_header(
+            name = "X-RateLimit-Remaining",
+            type = "integer",
+            description = "Number of requests left in the current window."
+        ),
+        response_header(name = "Location", code = 201, description = "URL of the pet.")
+    )]
+    async fn add_pet(body: web::Json<Pet>) -> Result<CreatedJson<Pet>, Error> {
+        Ok(CreatedJson(body.into_inner()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::post().to(add_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets"]["post"]["responses"]["201"]["headers"],
+                json!({
+                    "Location": {
+                        "description": "URL of the pet.",
+                        "type": "string"
+                    },
+                    "X-RateLimit-Remaining": {
+                        "description": "Number of requests left in the current window.",
+                        "type": "integer"
+                    },
+                    "X-Request-Id": {
+                        "type": "string",
+                        "format": "uuid"
+                    }
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_conditional_get() {
+    use paperclip::actix::validation::MismatchAction;
+
+    #[api_v2_operation]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_conditional_get()
+                // The (either) bodies of the middleware are accepted by other middleware.
+                .with_response_validation(MismatchAction::Panic)
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let etag = resp.headers()["etag"].to_str().unwrap().to_owned();
+            assert!(etag.starts_with("W/\""));
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1", addr))
+                .header("If-None-Match", etag.trim_start_matches("W/"))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 304);
+            assert_eq!(resp.headers()["etag"], etag.as_str());
+            assert!(resp.text().unwrap().is_empty());
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1", addr))
+                .header("If-None-Match", "W/\"stale\"")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            // Undocumented responses are passed through.
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 404);
+            assert!(resp.headers().get("etag").is_none());
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/pets/{id}"]["get"];
+            let etag = json!({
+                "description": "Identifier for the version of the resource.",
+                "type": "string"
+            });
+            assert_eq!(op["responses"]["200"]["headers"]["ETag"], etag);
+            assert_eq!(
+                op["responses"]["304"],
+                json!({
+                    "description": "Not Modified",
+                    "headers": { "ETag": etag }
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_examples() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Toy {
+        #[openapi(example = "Ball")]
+        name: String,
+        #[openapi(example = 3)]
+        count: u32,
+    }
+
+    #[api_v2_operation(example = r#"{"name": "Ball", "count": 3}"#)]
+    async fn get_toy() -> Result<web::Json<Toy>, Error> {
+        Ok(web::Json(Toy {
+            name: "Ball".into(),
+            count: 3,
+        }))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/toy").route(web::get().to(get_toy)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/toy"]["get"]["responses"]["200"]["examples"],
+                json!({ "application/json": { "name": "Ball", "count": 3 } })
+            );
+            let props = &spec["definitions"]["Toy"]["properties"];
+            assert_eq!(props["name"]["example"], "Ball");
+            assert_eq!(props["count"]["example"], "3");
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                let resp = &spec["paths"]["/toy"]["get"]["responses"]["200"];
+                assert_eq!(
+                    resp["content"]["application/json"]["example"],
+                    json!({ "name": "Ball", "count": 3 })
+                );
+                let props = &spec["components"]["schemas"]["Toy"]["properties"];
+                assert_eq!(props["name"]["example"], "Ball");
+                assert_eq!(props["count"]["example"], 3);
+            }
+        },
+    );
+}
+
+#[test]
+fn test_deprecated() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Toy {
+        name: String,
+        #[openapi(deprecated)]
+        color: Option<String>,
+    }
+
+    #[api_v2_operation(deprecated)]
+    async fn get_toy() -> Result<web::Json<Toy>, Error> {
+        Ok(web::Json(Toy {
+            name: "Ball".into(),
+            color: None,
+        }))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/toy").route(web::get().to(get_toy)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(spec["paths"]["/toy"]["get"]["deprecated"], true);
+            let props = &spec["definitions"]["Toy"]["properties"];
+            assert_eq!(props["color"]["x-deprecated"], true);
+            assert!(props["name"].get("x-deprecated").is_none());
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                assert_eq!(spec["paths"]["/toy"]["get"]["deprecated"], true);
+                let props = &spec["components"]["schemas"]["Toy"]["properties"];
+                assert_eq!(props["color"]["deprecated"], true);
+                assert!(props["name"].get("deprecated").is_none());
+            }
+        },
+    );
+}
+
+#[test]
+fn test_external_docs() {
+    #[api_v2_operation(external_docs(
+        url = "https://docs.example.com/guides/pets",
+        description = "Guide to pets"
+    ))]
+    async fn get_pets() -> Result<web::Json<Vec<Pet>>, Error> {
+        Ok(web::Json(vec![]))
+    }
+
+    run_and_check_app(
+        || {
+            let mut spec = DefaultApiRaw::default();
+            spec.tags = vec![Tag {
+                name: "pets".into(),
+                description: None,
+                external_docs: Some(ExternalDocs::new("https://docs.example.com/pets", None)),
+            }];
+            let app = App::new()
+                .wrap_api_with_spec(spec)
+                .with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets"]["get"]["externalDocs"],
+                json!({
+                    "url": "https://docs.example.com/guides/pets",
+                    "description": "Guide to pets"
+                })
+            );
+            assert_eq!(
+                spec["tags"][0]["externalDocs"],
+                json!({ "url": "https://docs.example.com/pets" })
+            );
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                assert_eq!(
+                    spec["paths"]["/pets"]["get"]["externalDocs"]["url"],
+                    "https://docs.example.com/guides/pets"
+                );
+                assert_eq!(
+                    spec["tags"][0]["externalDocs"]["url"],
+                    "https://docs.example.com/pets"
+                );
+            }
+        },
+    );
+}
+
+#[test]
+fn test_vendor_extensions() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[openapi(extension("x-internal" = true))]
+    struct Toy {
+        #[openapi(extension("x-pii" = false, "x-max-chars" = 40))]
+        name: String,
+    }
+
+    #[api_v2_operation(
+        extension("x-internal" = true, "x-gateway-route" = "toys"),
+        extensions(x_weight = 2)
+    )]
+    async fn get_toy() -> Result<web::Json<Toy>, Error> {
+        Ok(web::Json(Toy {
+            name: "Ball".into(),
+        }))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/toy").route(web::get().to(get_toy)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/toy"]["get"];
+            assert_eq!(op["x-internal"], true);
+            assert_eq!(op["x-gateway-route"], "toys");
+            assert_eq!(op["x-weight"], 2);
+            let toy = &spec["definitions"]["Toy"];
+            assert_eq!(toy["x-internal"], true);
+            assert_eq!(toy["properties"]["name"]["x-pii"], false);
+            assert_eq!(toy["properties"]["name"]["x-max-chars"], 40);
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                let op = &spec["paths"]["/toy"]["get"];
+                assert_eq!(op["x-internal"], true);
+                assert_eq!(op["x-gateway-route"], "toys");
+            }
+        },
+    );
+}
+
+#[test]
+fn test_max_body_size() {
+    #[api_v2_operation(max_body_size = "1KB")]
+    async fn add_pet(body: web::Json<Pet>) -> Result<web::Json<Pet>, Error> {
+        Ok(body)
+    }
+
+    #[api_v2_operation(max_body_size = "4KB")]
+    async fn update_pet(body: web::Json<Pet>) -> Result<web::Json<Pet>, Error> {
+        Ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(
+                    web::resource("/pets")
+                        .route(web::post().to(add_pet))
+                        .route(web::put().to(update_pet)),
+                )
+                .route("/app/pets", web::post().to(add_pet))
+                .service(web::scope("/scope").route("/pets", web::post().to(add_pet)))
+                .configure(|cfg| {
+                    cfg.route("/config/pets", web::post().to(add_pet));
+                })
+                .service(web::resource("/resource/pets").to(add_pet))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(spec["paths"]["/pets"]["post"]["x-max-body-size"], 1024);
+            assert_eq!(spec["paths"]["/pets"]["put"]["x-max-body-size"], 4096);
+
+            let pet = Pet {
+                name: "a".repeat(2048),
+                ..Default::default()
+            };
+            for path in &[
+                "/pets",
+                "/app/pets",
+                "/scope/pets",
+                "/config/pets",
+                "/resource/pets",
+            ] {
+                let resp = CLIENT
+                    .post(&format!("http://{}{}", addr, path))
+                    .json(&Pet::default())
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 200, "{}", path);
+
+                let resp = CLIENT
+                    .post(&format!("http://{}{}", addr, path))
+                    .json(&pet)
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 413, "{}", path);
+            }
+
+            // The limit of one route doesn't leak into the other routes of the resource.
+            let resp = CLIENT
+                .put(&format!("http://{}/pets", addr))
+                .json(&pet)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let pet = Pet {
+                name: "a".repeat(8192),
+                ..Default::default()
+            };
+            let resp = CLIENT
+                .put(&format!("http://{}/pets", addr))
+                .json(&pet)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 413);
+        },
+    );
+}
+
+#[test]
+fn test_max_body_size_with_request_validation() {
+    #[api_v2_operation(max_body_size = "3MB")]
+    async fn add_pet(body: web::Json<Pet>) -> Result<web::Json<Pet>, Error> {
+        Ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_request_validation()
+                .service(web::resource("/pets").route(web::post().to(add_pet)))
+                .build()
+        },
+        |addr| {
+            // Larger than the default limit of the middleware, but within that of the operation.
+            let pet = Pet {
+                name: "a".repeat(5 * 1024 * 1024 / 2),
+                ..Default::default()
+            };
+            let resp = CLIENT
+                .post(&format!("http://{}/pets", addr))
+                .json(&pet)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let pet = Pet {
+                name: "a".repeat(4 * 1024 * 1024),
+                ..Default::default()
+            };
+            let resp = CLIENT
+                .post(&format!("http://{}/pets", addr))
+                .json(&pet)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 413);
+        },
+    );
+}
+
+#[test]
+fn test_operation_timeout() {
+    #[api_v2_operation(timeout = "500ms")]
+    async fn get_pet(id: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        if *id == 0 {
+            actix_web::rt::time::sleep(std::time::Duration::from_secs(2)).await;
+        }
+
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_request_validation()
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .with_json_spec_at("/api/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(spec["paths"]["/pets/{id}"]["get"]["x-timeout"], "500ms");
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/0", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 504);
+        },
+    );
+}
+
+#[test]
+fn test_long_running_operation() {
+    #[derive(Serialize, Apiv2Schema)]
+    struct JobRef {
+        id: u64,
+    }
+
+    impl StatusRef for JobRef {
+        const STATUS_OPERATION: &'static str = "getJob";
+
+        fn status_url(&self) -> String {
+            format!("/jobs/{}", self.id)
+        }
+    }
+
+    #[api_v2_operation]
+    async fn export_pets() -> Accepted<JobRef> {
+        Accepted(JobRef { id: 7 })
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/pets/export").route(web::post().to(export_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .post(&format!("http://{}/pets/export", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 202);
+            assert_eq!(resp.headers()["location"], "/jobs/7");
+            assert_eq!(resp.headers()["operation-location"], "/jobs/7");
+            assert_eq!(resp.json::<serde_json::Value>().unwrap(), json!({"id": 7}));
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets/export"]["post"]["responses"]["202"],
+                json!({
+                    "description": "Accepted",
+                    "schema": { "$ref": "#/definitions/JobRef" },
+                    "headers": {
+                        "Location": {
+                            "description": "URL of the status.",
+                            "type": "string"
+                        },
+                        "Operation-Location": {
+                            "description": "URL of the status (same as `Location`).",
+                            "type": "string"
+                        }
+                    },
+                    "x-status-operation": "getJob"
+                })
+            );
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                let resp = &spec["paths"]["/pets/export"]["post"]["responses"]["202"];
+                assert_eq!(resp["links"]["status"]["operationId"], "getJob");
+            }
+        },
+    );
+}
+
+#[test]
+fn test_batch() {
+    use actix_web::http::StatusCode;
+
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Tag {
+        name: String,
+    }
+
+    #[derive(Serialize, Apiv2Schema)]
+    struct TagError {
+        message: String,
+    }
+
+    #[api_v2_operation]
+    async fn create_tags(body: web::Json<Batch<Tag>>) -> BatchResult<Tag, TagError> {
+        body.into_inner()
+            .into_inner()
+            .into_iter()
+            .map(|tag| {
+                if tag.name.is_empty() {
+                    let message = "name is empty".into();
+                    BatchItem::err(StatusCode::BAD_REQUEST, TagError { message })
+                } else {
+                    BatchItem::ok(StatusCode::CREATED, tag)
+                }
+            })
+            .collect()
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/tags/bulk").route(web::post().to(create_tags)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .post(&format!("http://{}/tags/bulk", addr))
+                .json(&json!({"items": [{"name": "cute"}, {"name": ""}]}))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 207);
+            assert_eq!(
+                resp.json::<serde_json::Value>().unwrap(),
+                json!({
+                    "items": [
+                        {"status": 201, "data": {"name": "cute"}},
+                        {"status": 400, "error": {"message": "name is empty"}}
+                    ]
+                })
+            );
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/tags/bulk"]["post"];
+            assert_eq!(
+                op["parameters"][0]["schema"],
+                json!({"$ref": "#/definitions/TagBatch"})
+            );
+            assert_eq!(
+                op["responses"]["207"],
+                json!({
+                    "description": "Multi-Status",
+                    "schema": {"$ref": "#/definitions/TagTagErrorBatchResult"}
+                })
+            );
+            assert_eq!(
+                spec["definitions"]["TagBatch"]["properties"]["items"]["items"]["properties"],
+                json!({"name": {"type": "string"}})
+            );
+            assert_eq!(
+                spec["definitions"]["TagTagErrorBatchResult"]["properties"]["items"]["items"],
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "status": {
+                            "type": "integer",
+                            "description": "Status code of the item."
+                        },
+                        "data": {
+                            "type": "object",
+                            "properties": {"name": {"type": "string"}},
+                            "required": ["name"]
+                        },
+                        "error": {
+                            "type": "object",
+                            "properties": {"message": {"type": "string"}},
+                            "required": ["message"]
+                        }
+                    },
+                    "required": ["status"]
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_negotiate() {
+    #[api_v2_operation]
+    async fn get_pets() -> Result<Negotiate<Vec<Pet>>, Error> {
+        let pet = Pet {
+            name: "Felix, Jr.".into(),
+            class: PetClass::Cat,
+            id: Some(1),
+            ..Default::default()
+        };
+        Ok(Negotiate(vec![pet]))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/pets"]["get"];
+            assert_eq!(
+                op["produces"],
+                json!(["application/json", "application/yaml", "text/csv"])
+            );
+            assert_eq!(
+                op["responses"]["200"]["schema"]["items"]["$ref"],
+                "#/definitions/Pet"
+            );
+            assert_eq!(
+                op["responses"]["200"]["x-content-schemas"],
+                json!({ "text/csv": { "type": "string" } })
+            );
+
+            let get = |accept: &str| {
+                CLIENT
+                    .get(&format!("http://{}/pets", addr))
+                    .header("Accept", accept)
+                    .send()
+                    .expect("request failed?")
+            };
+
+            let resp = get("application/json");
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "application/json"
+            );
+            let json: serde_json::Value = resp.json().unwrap();
+            assert_eq!(json[0]["name"], "Felix, Jr.");
+
+            let resp = get("application/yaml");
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "application/yaml"
+            );
+            let yaml: serde_json::Value = serde_yaml::from_str(&resp.text().unwrap()).unwrap();
+            assert_eq!(yaml, json);
+
+            let resp = get("application/json;q=0.5, text/csv");
+            assert_eq!(resp.headers().get("content-type").unwrap(), "text/csv");
+            assert_eq!(
+                resp.text().unwrap(),
+                "birthday,class,id,name,updatedOn,uuid\r\n2012-03-10,cat,1,\"Felix, Jr.\",,\r\n"
+            );
+
+            let resp = get("image/png");
+            assert_eq!(resp.status().as_u16(), 406);
+        },
+    );
+}
+
+#[test]
+fn test_streaming_responses() {
+    use futures::stream::{self, Stream};
+
+    fn pets() -> impl Stream<Item = Result<Pet, Error>> {
+        stream::iter((1..=2).map(|id| {
+            Ok(Pet {
+                name: format!("Pet {}", id),
+                id: Some(id),
+                ..Default::default()
+            })
+        }))
+    }
+
+    #[api_v2_operation]
+    async fn watch_pets() -> EventStream<impl Stream<Item = Result<Pet, Error>>> {
+        EventStream(pets())
+    }
+
+    #[api_v2_operation]
+    async fn export_pets() -> NdJson<impl Stream<Item = Result<Pet, Error>>> {
+        NdJson(pets())
+    }
+
+    #[api_v2_operation(produces = "application/json-seq")]
+    async fn export_pet_sequence() -> NdJson<impl Stream<Item = Result<Pet, Error>>> {
+        NdJson(pets())
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/events").route(web::get().to(watch_pets)))
+                .service(web::resource("/pets/export").route(web::get().to(export_pets)))
+                .service(web::resource("/pets/sequence").route(web::get().to(export_pet_sequence)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let paths = &spec["paths"];
+            // Responses are documented with the schemas of their items.
+            for path in &["/pets/events", "/pets/export", "/pets/sequence"] {
+                assert_eq!(
+                    paths[*path]["get"]["responses"]["200"]["schema"]["$ref"],
+                    "#/definitions/Pet"
+                );
+            }
+
+            assert_eq!(
+                paths["/pets/events"]["get"]["produces"],
+                json!(["text/event-stream"])
+            );
+            assert_eq!(
+                paths["/pets/export"]["get"]["produces"],
+                json!(["application/x-ndjson"])
+            );
+            assert_eq!(
+                paths["/pets/sequence"]["get"]["produces"],
+                json!(["application/json-seq"])
+            );
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/events", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "text/event-stream"
+            );
+            let body = resp.text().unwrap();
+            let events = body
+                .split_terminator("\n\n")
+                .map(|e| serde_json::from_str(e.trim_start_matches("data: ")).unwrap())
+                .collect::<Vec<serde_json::Value>>();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[1]["name"], "Pet 2");
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/export", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "application/x-ndjson"
+            );
+            let body = resp.text().unwrap();
+            let ids = body
+                .lines()
+                .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["id"].clone())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, vec![json!(1), json!(2)]);
+        },
+    );
+}
+
+#[test]
+fn test_rapidoc() {
+    #[api_v2_operation]
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_rapidoc_at("/docs")
+                .with_rapidoc_config_at(
+                    "/docs/dark",
+                    RapiDoc::new()
+                        .title("Pets & friends")
+                        .theme("dark")
+                        .attribute("render-style", "read"),
+                )
+                .service(web::resource("/echo").route(web::post().to(echo_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/docs", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "text/html; charset=utf-8"
+            );
+            let html = resp.text().unwrap();
+            assert!(html.contains("<rapi-doc spec-url=\"/api/spec\"></rapi-doc>"));
+            assert!(html.contains("https://unpkg.com/rapidoc/dist/rapidoc-min.js"));
+
+            let resp = CLIENT
+                .get(&format!("http://{}/docs/dark", addr))
+                .send()
+                .expect("request failed?");
+            let html = resp.text().unwrap();
+            assert!(html.contains("<title>Pets &amp; friends</title>"));
+            assert!(html.contains(
+                "<rapi-doc spec-url=\"/api/spec\" render-style=\"read\" theme=\"dark\"></rapi-doc>"
+            ));
+        },
+    );
+}
+
+#[test]
+fn test_redoc() {
+    #[api_v2_operation]
+    fn echo_pet(body: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        fut_ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_redoc_at("/redoc")
+                .with_redoc_config_at(
+                    "/redoc/public",
+                    ReDoc::new()
+                        .title("Pets API")
+                        .spec_url("https://pets.example.com/api/spec"),
+                )
+                .service(web::resource("/echo").route(web::post().to(echo_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/redoc", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "text/html; charset=utf-8"
+            );
+            let html = resp.text().unwrap();
+            assert!(html.contains("<redoc spec-url=\"/api/spec\"></redoc>"));
+            assert!(html.contains("redoc.standalone.js"));
+
+            let resp = CLIENT
+                .get(&format!("http://{}/redoc/public", addr))
+                .send()
+                .expect("request failed?");
+            let html = resp.text().unwrap();
+            assert!(html.contains("<title>Pets API</title>"));
+            assert!(html.contains("<redoc spec-url=\"https://pets.example.com/api/spec\"></redoc>"));
+        },
+    );
+}
+
+#[test]
+fn test_schema_composition() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(rename_all = "camelCase")]
+    #[openapi(omit(fields = "password"))]
+    #[openapi(omit(name = "NewUser", fields = "id"))]
+    struct User {
+        id: u64,
+        user_name: String,
+        password: String,
+    }
+
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Address {
+        street: String,
+    }
+
+    #[api_v2_operation]
+    async fn add_user(
+        body: web::Json<Merged<NewUser, Address>>,
+    ) -> Result<web::Json<UserWithoutPassword>, Error> {
+        let body = body.into_inner();
+        let user = User {
+            id: 1,
+            user_name: body.first.user_name,
+            password: body.first.password,
+        };
+        Ok(web::Json(user.into()))
+    }
+
+    #[api_v2_operation]
+    async fn update_user(
+        body: web::Json<Partial<User>>,
+    ) -> Result<web::Json<UserWithoutPassword>, Error> {
+        let mut user = User {
+            id: 1,
+            user_name: "alice".into(),
+            password: "secret".into(),
+        };
+        body.apply(&mut user)
+            .map_err(actix_web::error::ErrorBadRequest)?;
+        Ok(web::Json(user.into()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/users").route(web::post().to(add_user)))
+                .service(web::resource("/users/1").route(web::patch().to(update_user)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let defs = &spec["definitions"];
+            assert_eq!(
+                defs["UserWithoutPassword"]["required"],
+                json!(["id", "userName"])
+            );
+            assert!(defs["UserWithoutPassword"]["properties"]["password"].is_null());
+            assert_eq!(
+                defs["NewUserAndAddress"]["required"],
+                json!(["password", "street", "userName"])
+            );
+            assert!(defs["PartialUser"]["required"].is_null());
+            assert_eq!(
+                defs["PartialUser"]["properties"]["password"]["type"],
+                "string"
+            );
+            assert_eq!(
+                spec["paths"]["/users/1"]["patch"]["parameters"][0]["schema"]["$ref"],
+                "#/definitions/PartialUser"
+            );
+
+            let resp = CLIENT
+                .post(&format!("http://{}/users", addr))
+                .json(&json!({ "userName": "bob", "password": "pw", "street": "Main St" }))
+                .send()
+                .expect("request failed?");
+            let user: serde_json::Value = resp.json().unwrap();
+            assert_eq!(user, json!({ "id": 1, "userName": "bob" }));
+
+            let resp = CLIENT
+                .patch(&format!("http://{}/users/1", addr))
+                .json(&json!({ "userName": "carol" }))
+                .send()
+                .expect("request failed?");
+            let user: serde_json::Value = resp.json().unwrap();
+            assert_eq!(user, json!({ "id": 1, "userName": "carol" }));
+        },
+    );
+}
+
+#[test]
+fn test_generate_optional() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(rename_all = "camelCase")]
+    #[openapi(generate_optional = "UpdatePet")]
+    struct Pet {
+        name: String,
+        class: PetClass,
+        nickname: Option<String>,
+    }
+
+    #[api_v2_operation]
+    async fn update_pet(body: web::Json<UpdatePet>) -> Result<web::Json<Pet>, Error> {
+        let mut pet = Pet {
+            name: "Felix".into(),
+            class: PetClass::Cat,
+            nickname: None,
+        };
+        body.into_inner().apply(&mut pet);
+        Ok(web::Json(pet))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/1").route(web::patch().to(update_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let update = &spec["definitions"]["UpdatePet"];
+            assert!(update["required"].is_null());
+            assert_eq!(update["properties"]["name"]["type"], "string");
+            assert_eq!(update["properties"]["nickname"]["type"], "string");
+            assert_eq!(
+                spec["definitions"]["Pet"]["required"],
+                json!(["class", "name"])
+            );
+
+            let resp = CLIENT
+                .patch(&format!("http://{}/pets/1", addr))
+                .json(&json!({ "nickname": "Kitty" }))
+                .send()
+                .expect("request failed?");
+            let pet: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                pet,
+                json!({ "name": "Felix", "class": "cat", "nickname": "Kitty" })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_contract_checks() {
+    #[api_v2_operation]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1", addr))
+                .send()
+                .expect("request failed?");
+            let body = resp.bytes().unwrap();
+            test::check_response(&spec, "GET /pets/{id}", 200, &body).unwrap();
+
+            assert_eq!(
+                test::check_response(&spec, "GET /pets/{id}", 404, &body),
+                Err("status 404 isn't documented".into())
+            );
+            assert_eq!(
+                test::check_response(&spec, "DELETE /pets/{id}", 200, &body),
+                Err("DELETE /pets/{id} isn't documented".into())
+            );
+            assert_eq!(
+                test::check_response(&spec, "GET /pets/{id}", 200, br#"{"name": 1}"#),
+                Err("/: missing required property \"birthday\"; \
+                     /: missing required property \"class\"; \
+                     /name: expected string, found integer"
+                    .into())
+            );
+        },
+    );
+}
+
+#[test]
+fn test_parameters_struct() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Paging {
+        limit: Option<u32>,
+        #[openapi(in = "header")]
+        #[serde(rename = "X-Cursor")]
+        cursor: Option<String>,
+    }
+
+    /// Metadata of pet requests.
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[openapi(parameters)]
+    struct PetRequestMeta {
+        /// Tenant of the pet.
+        #[openapi(in = "header")]
+        #[serde(rename = "X-Tenant")]
+        tenant: String,
+        verbose: Option<bool>,
+        #[serde(flatten)]
+        paging: Paging,
+    }
+
+    impl FromRequest for PetRequestMeta {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(req: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            let header = |name| {
+                req.headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(String::from)
+            };
+            ready(Ok(PetRequestMeta {
+                tenant: header("X-Tenant").unwrap_or_default(),
+                verbose: None,
+                paging: Paging {
+                    limit: None,
+                    cursor: header("X-Cursor"),
+                },
+            }))
+        }
+    }
+
+    #[api_v2_operation]
+    async fn list_pets(meta: PetRequestMeta) -> Result<web::Json<Vec<Pet>>, Error> {
+        assert_eq!(meta.tenant, "acme");
+        Ok(web::Json(vec![]))
+    }
+
+    #[api_v2_operation]
+    async fn replay_request(
+        body: web::Json<PetRequestMeta>,
+    ) -> Result<web::Json<PetRequestMeta>, Error> {
+        Ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(list_pets)))
+                .service(web::resource("/requests").route(web::post().to(replay_request)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("X-Tenant", "acme")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets"]["get"]["parameters"],
+                json!([
+                    {
+                        "in": "header",
+                        "name": "X-Cursor",
+                        "type": "string"
+                    },
+                    {
+                        "description": "Tenant of the pet.",
+                        "in": "header",
+                        "name": "X-Tenant",
+                        "required": true,
+                        "type": "string"
+                    },
+                    {
+                        "format": "int32",
+                        "in": "query",
+                        "name": "limit",
+                        "type": "integer"
+                    },
+                    {
+                        "in": "query",
+                        "name": "verbose",
+                        "type": "boolean"
+                    }
+                ])
+            );
+
+            // The same struct is still a regular schema when it's used as a body.
+            assert_eq!(
+                spec["paths"]["/requests"]["post"]["parameters"][0]["schema"],
+                json!({ "$ref": "#/definitions/PetRequestMeta" })
+            );
+            assert_eq!(
+                spec["definitions"]["PetRequestMeta"]["required"],
+                json!(["X-Tenant"])
+            );
+        },
+    );
+}
+
+#[test]
+fn test_parameter_location_override() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct PetFilters {
+        color: Option<String>,
+        #[openapi(in = "header")]
+        #[serde(rename = "X-Request-Id")]
+        request_id: Option<String>,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct PetPath {
+        id: u64,
+        #[openapi(in = "query")]
+        revision: Option<u32>,
+    }
+
+    #[api_v2_operation]
+    async fn get_pet(
+        _path: web::Path<PetPath>,
+        _filters: web::Query<PetFilters>,
+    ) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets/{id}"]["get"]["parameters"],
+                json!([
+                    {
+                        "format": "int64",
+                        "in": "path",
+                        "name": "id",
+                        "required": true,
+                        "type": "integer"
+                    },
+                    {
+                        "format": "int32",
+                        "in": "query",
+                        "name": "revision",
+                        "type": "integer"
+                    },
+                    {
+                        "in": "header",
+                        "name": "X-Request-Id",
+                        "type": "string"
+                    },
+                    {
+                        "in": "query",
+                        "name": "color",
+                        "type": "string"
+                    }
+                ])
+            );
+        },
+    );
+}
+
+#[test]
+fn test_operation_urls() {
+    #[api_v2_operation(operation_id = "getPet")]
+    async fn get_pet(_path: web::Path<u64>) -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    #[api_v2_operation(operation_id = "addPet")]
+    async fn add_pet(
+        urls: web::Data<OperationUrls>,
+        _body: web::Json<Pet>,
+    ) -> Result<web::Json<String>, Error> {
+        let url = urls
+            .operation_url("getPet", &[("id", "42"), ("fields", "name")])
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        Ok(web::Json(url))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api_with_spec(DefaultApiRaw {
+                    base_path: Some("/api".into()),
+                    ..Default::default()
+                })
+                .with_operation_urls()
+                .service(
+                    web::scope("/api")
+                        .service(web::resource("/pets").route(web::post().to(add_pet)))
+                        .service(web::resource("/pets/{id}").route(web::get().to(get_pet))),
+                )
+                .trim_base_path()
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .post(&format!("http://{}/api/pets", addr))
+                .json(&Pet::default())
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let url: String = resp.json().unwrap();
+            assert_eq!(url, "/api/pets/42?fields=name");
+        },
+    );
+}
+
+#[test]
+fn test_operation_links() {
+    #[derive(Serialize, Apiv2Schema)]
+    struct PetResource {
+        name: String,
+        #[serde(rename = "_links")]
+        links: serde_json::Value,
+    }
+
+    #[api_v2_operation(operation_id = "getPet")]
+    async fn get_pet(
+        urls: web::Data<OperationUrls>,
+        path: web::Path<String>,
+    ) -> Result<web::Json<PetResource>, Error> {
+        let id = path.into_inner();
+        let links = urls
+            .links()
+            .link("self", "getPet", &[("id", &id)])
+            .and_then(|l| l.link("toys", "listToys", &[("id", &id), ("limit", "10")]))
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        assert_eq!(
+            links.header_value(),
+            r#"</pets/42>; rel="self", </pets/42/toys?limit=10>; rel="toys""#
+        );
+        Ok(web::Json(PetResource {
+            name: "Milo".into(),
+            links: links.to_json(),
+        }))
+    }
+
+    #[api_v2_operation(operation_id = "listToys")]
+    async fn list_toys(_path: web::Path<String>) -> Result<web::Json<Vec<String>>, Error> {
+        Ok(web::Json(vec![]))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_operation_urls()
+                .service(web::resource("/pets/{id}").route(web::get().to(get_pet)))
+                .service(web::resource("/pets/{id}/toys").route(web::get().to(list_toys)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/42", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let body: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                body["_links"],
+                json!({
+                    "self": { "href": "/pets/42" },
+                    "toys": { "href": "/pets/42/toys?limit=10" }
+                })
+            );
+        },
+    );
+}
+
+#[test]
+fn test_declared_parameters() {
+    #[api_v2_operation(params(
+        path("id", integer, format = "int64"),
+        query("page", integer, required, description = "Page of the results."),
+        header("X-Tenant")
+    ))]
+    async fn get_pet_toys(req: HttpRequest) -> Result<web::Json<String>, Error> {
+        Ok(web::Json(req.query_string().into()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/{id}/toys").route(web::get().to(get_pet_toys)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets/{id}/toys"]["get"]["parameters"],
+                json!([
+                    {
+                        "format": "int64",
+                        "in": "path",
+                        "name": "id",
+                        "required": true,
+                        "type": "integer"
+                    },
+                    {
+                        "description": "Page of the results.",
+                        "in": "query",
+                        "name": "page",
+                        "required": true,
+                        "type": "integer"
+                    },
+                    {
+                        "in": "header",
+                        "name": "X-Tenant",
+                        "type": "string"
+                    }
+                ])
+            );
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets/1/toys?page=2", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.json::<String>().unwrap(), "page=2");
+        },
+    );
+}
+
+#[test]
+fn test_raw_bodies() {
+    #[api_v2_operation(request_body(media_type = "text/csv", description = "Pets as CSV."))]
+    async fn import_pets(body: web::Bytes) -> Result<NoContent, Error> {
+        assert_eq!(&body[..], b"name\nMilo\n");
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation(response_body(media_type = "application/pdf"))]
+    async fn get_report() -> Result<actix_web::HttpResponse, Error> {
+        Ok(actix_web::HttpResponse::Ok()
+            .content_type("application/pdf")
+            .body("%PDF-1.4"))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/pets/import").route(web::post().to(import_pets)))
+                .service(web::resource("/report").route(web::get().to(get_report)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let import = &spec["paths"]["/pets/import"]["post"];
+            assert_eq!(import["consumes"], json!(["text/csv"]));
+            assert_eq!(
+                import["parameters"],
+                json!([{
+                    "description": "Pets as CSV.",
+                    "in": "body",
+                    "name": "body",
+                    "required": true,
+                    "schema": { "type": "string" }
+                }])
+            );
+
+            let report = &spec["paths"]["/report"]["get"];
+            assert_eq!(report["produces"], json!(["application/pdf"]));
+            assert_eq!(
+                report["responses"]["200"],
+                json!({ "description": "OK", "schema": { "type": "file" } })
+            );
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                let body = &spec["paths"]["/pets/import"]["post"]["requestBody"]["content"];
+                assert_eq!(
+                    body,
+                    &json!({ "text/csv": { "schema": { "type": "string" } } })
+                );
+                let content = &spec["paths"]["/report"]["get"]["responses"]["200"]["content"];
+                assert_eq!(
+                    content,
+                    &json!({ "application/pdf": { "schema": { "type": "string", "format": "binary" } } })
+                );
+            }
+
+            let resp = CLIENT
+                .post(&format!("http://{}/pets/import", addr))
+                .header("content-type", "text/csv")
+                .body("name\nMilo\n")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 204);
+        },
+    );
+}
+
+#[test]
+fn test_operation_ids() {
+    mod v1 {
+        use super::*;
+
+        #[api_v2_operation]
+        pub async fn get_pet() -> Result<web::Json<Pet>, Error> {
+            Ok(web::Json(Pet::default()))
+        }
+    }
+
+    mod v2 {
+        use super::*;
+
+        #[api_v2_operation]
+        pub async fn get_pet() -> Result<web::Json<Pet>, Error> {
+            Ok(web::Json(Pet::default()))
+        }
+    }
+
+    #[api_v2_operation(operation_id = "listPets")]
+    async fn list_pets() -> Result<web::Json<Vec<Pet>>, Error> {
+        Ok(web::Json(vec![]))
+    }
+
+    #[api_v2_operation(operation_id = "listPets")]
+    async fn list_cats() -> Result<web::Json<Vec<Pet>>, Error> {
+        Ok(web::Json(vec![]))
+    }
+
+    #[api_v2_operation]
+    async fn echo() -> Result<web::Json<String>, Error> {
+        Ok(web::Json("echo".into()))
+    }
+
+    // Duplicate IDs (generated or not) are logged when the app is built.
+    let (app, warnings) = capture_logs(|| {
+        App::new()
+            .wrap_api()
+            .with_json_spec_at("/api/spec")
+            .with_operation_ids(OperationIdStrategy::FnName)
+            .service(web::resource("/v1/pet").route(web::get().to(v1::get_pet)))
+            .service(web::resource("/v2/pet").route(web::get().to(v2::get_pet)))
+            .service(web::resource("/pets").route(web::get().to(list_pets)))
+            .service(
+                web::resource("/echo")
+                    .route(web::get().to(echo))
+                    .route(web::post().to(echo)),
+            )
+            .service(web::resource("/cats").route(web::get().to(list_cats)))
+            .build()
+    });
+    drop(app);
+    assert_eq!(
+        warnings,
+        vec![
+            "duplicate operation ID `get_pet` (GET /v1/pet, GET /v2/pet)",
+            "duplicate operation ID `listPets` (GET /cats, GET /pets)",
+        ]
+    );
+
+    let (app, warnings) = capture_logs(|| {
+        App::new()
+            .wrap_api()
+            .service(web::resource("/pets").route(web::get().to(list_pets)))
+            .service(web::resource("/cats").route(web::get().to(list_cats)))
+            .build()
+    });
+    drop(app);
+    assert_eq!(
+        warnings,
+        vec!["duplicate operation ID `listPets` (GET /cats, GET /pets)"]
+    );
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_operation_ids(OperationIdStrategy::ModulePath)
+                .service(web::resource("/v1/pet").route(web::get().to(v1::get_pet)))
+                .service(web::scope("/v2").route("/pet", web::get().to(v2::get_pet)))
+                .service(web::resource("/pets").route(web::get().to(list_pets)))
+                .service(
+                    web::resource("/echo")
+                        .route(web::get().to(echo))
+                        .route(web::post().to(echo)),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(spec["paths"]["/v1/pet"]["get"]["operationId"], "v1_get_pet");
+            assert_eq!(spec["paths"]["/v2/pet"]["get"]["operationId"], "v2_get_pet");
+            assert_eq!(spec["paths"]["/pets"]["get"]["operationId"], "listPets");
+            assert_eq!(spec["paths"]["/echo"]["get"]["operationId"], "echo_get");
+            assert_eq!(spec["paths"]["/echo"]["post"]["operationId"], "echo_post");
+        },
+    );
+}
+
+#[test]
+fn test_query_map() {
+    #[api_v2_operation]
+    async fn search_pets(filters: web::QueryMap) -> Result<web::Json<Vec<Pet>>, Error> {
+        assert_eq!(filters.get("color").map(String::as_str), Some("black"));
+        Ok(web::Json(vec![]))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(search_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets?color=black&size=small", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions": {
+                        "Pet": {
+                            "description": "Pets are awesome!",
+                            "properties": {
+                                "birthday": {
+                                    "format": "date",
+                                    "type": "string"
+                                },
+                                "class": {
+                                    "enum": ["dog", "cat", "other"],
+                                    "type": "string"
+                                },
+                                "id": {
+                                    "format": "int64",
+                                    "type": "integer"
+                                },
+                                "name": {
+                                    "description": "Pick a good one.",
+                                    "type": "string"
+                                },
+                                "updatedOn": {
+                                    "format": "date-time",
+                                    "type": "string"
+                                },
+                                "uuid": {
+                                    "format": "uuid",
+                                    "type": "string"
+                                }
+                            },
+                            "required": ["birthday", "class", "name"],
+                            "type": "object"
+                        }
+                    },
+                    "info": {
+                        "title": "",
+                        "version": ""
+                    },
+                    "paths": {
+                        "/pets": {
+                            "get": {
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "items": {
+                                                "$ref": "#/definitions/Pet"
+                                            },
+                                            "type": "array"
+                                        }
+                                    }
+                                },
+                                "x-dynamic-query": true
+                            }
+                        }
+                    },
+                    "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_filter_param() {
+    #[api_v2_operation]
+    async fn list_pets(filter: web::FilterParam<Pet>) -> Result<web::Json<Vec<Pet>>, Error> {
+        let pets = vec![
+            Pet {
+                name: "Felix".into(),
+                class: PetClass::Cat,
+                id: Some(4),
+                ..Default::default()
+            },
+            Pet {
+                name: "Rex".into(),
+                class: PetClass::Dog,
+                id: Some(5),
+                ..Default::default()
+            },
+        ];
+        Ok(web::Json(
+            pets.into_iter().filter(|p| filter.matches(p)).collect(),
+        ))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(list_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!(
+                    "http://{}/pets?filter=class:in:cat|other,id:gt:3",
+                    addr
+                ))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let pets: serde_json::Value = resp.json().unwrap();
+            assert_eq!(pets.as_array().unwrap().len(), 1);
+            assert_eq!(pets[0]["name"], "Felix");
+
+            for filter in &["owner:eq:me", "class:eq:fish", "id:gt:many", "name"] {
+                let resp = CLIENT
+                    .get(&format!("http://{}/pets?filter={}", addr, filter))
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 400);
+            }
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let param = &spec["paths"]["/pets"]["get"]["parameters"][0];
+            assert_eq!(param["name"], "filter");
+            assert_eq!(param["in"], "query");
+            assert_eq!(param["type"], "string");
+            let description = param["description"].as_str().unwrap();
+            assert!(description.contains("`field:op:value`"));
+            assert!(description
+                .contains("Fields: `birthday`, `class`, `id`, `name`, `updatedOn`, `uuid`."));
+        },
+    );
+}
+
+#[test]
+fn test_sort_and_fields_params() {
+    #[api_v2_operation]
+    async fn list_pets(
+        sort: web::SortParam<Pet>,
+        fields: web::FieldsParam<Pet>,
+    ) -> Result<web::Json<Vec<serde_json::Value>>, Error> {
+        let mut pets = vec![
+            Pet {
+                name: "Felix".into(),
+                id: Some(4),
+                ..Default::default()
+            },
+            Pet {
+                name: "Rex".into(),
+                id: Some(5),
+                ..Default::default()
+            },
+        ];
+        sort.sort(&mut pets);
+        Ok(web::Json(pets.iter().map(|p| fields.project(p)).collect()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(list_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets?sort=-id&fields=name,id", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let pets: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                pets,
+                json!([{ "id": 5, "name": "Rex" }, { "id": 4, "name": "Felix" }])
+            );
+
+            for query in &["sort=owner", "sort=-", "fields=name,owner"] {
+                let resp = CLIENT
+                    .get(&format!("http://{}/pets?{}", addr, query))
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 400);
+            }
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let params = &spec["paths"]["/pets"]["get"]["parameters"];
+            assert_eq!(params[0]["name"], "sort");
+            assert_eq!(params[0]["type"], "array");
+            assert_eq!(params[0]["items"]["enum"][0], "birthday");
+            assert_eq!(params[0]["items"]["enum"][1], "-birthday");
+            assert_eq!(params[1]["name"], "fields");
+            assert_eq!(
+                params[1]["items"]["enum"],
+                json!(["birthday", "class", "id", "name", "updatedOn", "uuid"])
+            );
+        },
+    );
+}
+
+#[test]
+fn test_header_extractor() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct RequestHeaders {
+        /// ID of the request (for tracing).
+        #[serde(rename = "X-Request-Id")]
+        request_id: String,
+        /// Version of the API.
+        #[serde(rename = "X-Api-Version")]
+        api_version: Option<u32>,
+    }
+
+    #[api_v2_operation]
+    async fn get_pets(headers: web::Header<RequestHeaders>) -> Result<web::Json<Vec<Pet>>, Error> {
+        assert_eq!(headers.request_id, "abc");
+        assert_eq!(headers.api_version, Some(2));
+        Ok(web::Json(vec![]))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("x-request-id", "abc")
+                .header("X-API-VERSION", "2")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            // Missing required headers and invalid values are rejected.
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("X-Api-Version", "2")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 400);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("X-Request-Id", "abc")
+                .header("X-Api-Version", "two")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 400);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pets"]["get"]["parameters"],
+                json!([
+                    {
+                        "description": "Version of the API.",
+                        "format": "int32",
+                        "in": "header",
+                        "name": "X-Api-Version",
+                        "type": "integer"
+                    },
+                    {
+                        "description": "ID of the request (for tracing).",
+                        "in": "header",
+                        "name": "X-Request-Id",
+                        "required": true,
+                        "type": "string"
+                    }
+                ])
+            );
+        },
+    );
+}
+
+#[test]
+fn test_cookie_extractor() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct SessionCookies {
+        /// ID of the session.
+        session: String,
+        /// Token for CSRF protection.
+        csrf_token: Option<String>,
+    }
+
+    #[api_v2_operation]
+    async fn get_pets(cookies: web::Cookie<SessionCookies>) -> Result<web::Json<Vec<Pet>>, Error> {
+        assert_eq!(cookies.session, "abc");
+        assert_eq!(cookies.csrf_token.as_deref(), Some("xyz"));
+        Ok(web::Json(vec![]))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("Cookie", "theme=dark; session=abc; csrf_token=xyz")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/pets", addr))
+                .header("Cookie", "csrf_token=xyz")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 400);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let op = &spec["paths"]["/pets"]["get"];
+            assert!(op.get("parameters").is_none());
+            assert_eq!(
+                op["x-cookies"],
+                json!([
+                    {
+                        "description": "Token for CSRF protection.",
+                        "name": "csrf_token",
+                        "type": "string"
+                    },
+                    {
+                        "description": "ID of the session.",
+                        "name": "session",
+                        "required": true,
+                        "type": "string"
+                    }
+                ])
+            );
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec_v3: serde_json::Value = resp.json().unwrap();
+                let params = &spec_v3["paths"]["/pets"]["get"]["parameters"];
+                assert_eq!(params[1]["in"], "cookie");
+                assert_eq!(params[1]["name"], "session");
+                assert_eq!(params[1]["required"], true);
+                assert_eq!(params[1]["schema"]["type"], "string");
+                assert!(spec_v3["paths"]["/pets"]["get"].get("x-cookies").is_none());
+
+                // Cookies are kept when converting back to v2.
+                let (spec_v2, _) =
+                    paperclip::v3::openapiv3_to_v2(serde_json::from_value(spec_v3).unwrap());
+                let op = &spec_v2.paths["/pets"].methods[&HttpMethod::Get];
+                assert_eq!(
+                    serde_json::to_value(&op.cookies).unwrap(),
+                    spec["paths"]["/pets"]["get"]["x-cookies"]
+                );
+            }
+        },
+    );
+}
+
+#[test]
+fn test_list_in_out() {
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    enum Sort {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    struct Params {
+        sort: Option<Sort>,
+        limit: Option<u16>,
+    }
+
+    #[api_v2_operation]
+    fn get_pets(_q: web::Query<Params>) -> impl Future<Output = web::Json<Vec<Pet>>> {
+        #[allow(unreachable_code)]
+        ready(unimplemented!())
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    }
+                  },
+                  "paths": {
+                    "/pets": {
+                      "get": {
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "type": "array",
+                              "items": {
+                                "$ref": "#/definitions/Pet"
+                              }
+                            }
+                          }
+                        },
+                        "parameters": [{
+                            "format": "int32",
+                            "in": "query",
+                            "name": "limit",
+                            "type": "integer"
+                          }, {
+                            "enum": ["Asc", "Desc"],
+                            "in": "query",
+                            "name": "sort",
+                            "type": "string"
+                        }],
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_enum_query_parameters() {
+    #[derive(Deserialize, Apiv2Schema)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Paging {
+        order: Option<Order>,
+        page: Option<u32>,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Filters {
+        class: PetClass,
+        classes: Vec<PetClass>,
+        #[serde(flatten)]
+        paging: Paging,
+    }
+
+    #[api_v2_operation]
+    async fn filter_pets(
+        _path: web::Path<Order>,
+        _filters: web::Query<Filters>,
+    ) -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets/{order}").route(web::get().to(filter_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {},
+                  "paths": {
+                    "/pets/{order}": {
+                      "get": {
+                        "responses": {
+                          "204": {
+                            "description": "No Content"
+                          }
+                        },
+                        "parameters": [{
+                            "enum": ["asc", "desc"],
+                            "in": "path",
+                            "name": "order",
+                            "required": true,
+                            "type": "string"
+                          }, {
+                            "enum": ["dog", "cat", "other"],
+                            "in": "query",
+                            "name": "class",
+                            "required": true,
+                            "type": "string"
+                          }, {
+                            "in": "query",
+                            "items": {
+                              "enum": ["dog", "cat", "other"],
+                              "type": "string"
+                            },
+                            "name": "classes",
+                            "required": true,
+                            "type": "array"
+                          }, {
+                            "enum": ["asc", "desc"],
+                            "in": "query",
+                            "name": "order",
+                            "type": "string"
+                          }, {
+                            "format": "int32",
+                            "in": "query",
+                            "name": "page",
+                            "type": "integer"
+                        }],
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_enum_descriptions() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        /// Available for adoption.
+        Available,
+        #[openapi(description = "Adopted by someone.")]
+        Sold,
+        Pending,
+    }
+
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Listing {
+        status: Status,
+    }
+
+    #[api_v2_operation]
+    async fn get_listing() -> Result<web::Json<Listing>, Error> {
+        Ok(web::Json(Listing {
+            status: Status::Available,
+        }))
+    }
+
+    run_and_check_app(
+        || {
+            let app = App::new().wrap_api().with_json_spec_at("/api/spec");
+            #[cfg(feature = "v3")]
+            let app = app.with_json_spec_v3_at("/api/spec/v3");
+            app.service(web::resource("/listing").route(web::get().to(get_listing)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["definitions"]["Listing"]["properties"]["status"],
+                json!({
+                    "type": "string",
+                    "enum": ["available", "sold", "pending"],
+                    "x-enumNames": ["Available", "Sold", "Pending"],
+                    "x-enum-descriptions": ["Available for adoption.", "Adopted by someone.", ""]
+                })
+            );
+
+            #[cfg(feature = "v3")]
+            {
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec/v3", addr))
+                    .send()
+                    .expect("request failed?");
+                let spec: serde_json::Value = resp.json().unwrap();
+                assert_eq!(
+                    spec["components"]["schemas"]["Status"]["oneOf"],
+                    json!([
+                        { "type": "string", "enum": ["available"], "description": "Available for adoption." },
+                        { "type": "string", "enum": ["sold"], "description": "Adopted by someone." },
+                        { "type": "string", "enum": ["pending"] }
+                    ])
+                );
+            }
+        },
+    );
+}
+
+#[test]
+fn test_multiple_query_extractors() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Pagination {
+        limit: u32,
+        offset: Option<u32>,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Filters {
+        name: String,
+        /// Duplicate of the field in `Pagination`.
+        limit: Option<u32>,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Sorting {
+        sort: String,
+    }
+
+    #[api_v2_operation]
+    async fn search_pets(
+        _paging: web::Query<Pagination>,
+        _filters: web::Query<Filters>,
+        _sorting: Option<web::Query<Sorting>>,
+    ) -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(search_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {},
+                  "paths": {
+                    "/pets": {
+                      "get": {
+                        "responses": {
+                          "204": {
+                            "description": "No Content"
+                          }
+                        },
+                        "parameters": [{
+                            "format": "int32",
+                            "in": "query",
+                            "name": "limit",
+                            "required": true,
+                            "type": "integer"
+                          }, {
+                            "format": "int32",
+                            "in": "query",
+                            "name": "offset",
+                            "type": "integer"
+                          }, {
+                            "in": "query",
+                            "name": "name",
+                            "required": true,
+                            "type": "string"
+                          }, {
+                            "in": "query",
+                            "name": "sort",
+                            "type": "string"
+                        }],
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_path_struct_descriptions() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct PetPath {
+        /// Kind of the pet
+        kind: String,
+        /// ID of the pet
+        id: u64,
+        /// Revision of the pet (if any)
+        rev: Option<u32>,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Verbosity {
+        verbose: Option<bool>,
+    }
+
+    #[api_v2_operation]
+    async fn get_pet(
+        _q: web::Query<Verbosity>,
+        _p: web::Path<PetPath>,
+    ) -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(
+                    web::scope("/pets/{kind}")
+                        .service(web::resource("/{id}/{rev}").route(web::get().to(get_pet))),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {},
+                  "paths": {
+                    "/pets/{kind}/{id}/{rev}": {
+                      "get": {
+                        "responses": {
+                          "204": {
+                            "description": "No Content"
+                          }
+                        },
+                        "parameters": [{
+                            "in": "query",
+                            "name": "verbose",
+                            "type": "boolean"
+                          }, {
+                            "description": "Kind of the pet",
+                            "in": "path",
+                            "name": "kind",
+                            "required": true,
+                            "type": "string"
+                          }, {
+                            "description": "ID of the pet",
+                            "format": "int64",
+                            "in": "path",
+                            "name": "id",
+                            "required": true,
+                            "type": "integer"
+                          }, {
+                            "description": "Revision of the pet (if any)",
+                            "format": "int32",
+                            "in": "path",
+                            "name": "rev",
+                            "required": true,
+                            "type": "integer"
+                        }],
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_request_local_data() {
+    // Neither of these implement `Apiv2Schema`.
+    #[derive(Clone)]
+    struct CurrentUser {
+        _name: String,
+    }
+
+    struct Pool;
+
+    #[api_v2_operation]
+    async fn get_profile(
+        _pool: web::Data<Pool>,
+        _user: web::ReqData<CurrentUser>,
+        _session: Option<web::ReqData<CurrentUser>>,
+    ) -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .app_data(web::Data::new(Pool))
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/profile").route(web::get().to(get_profile)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {},
+                  "paths": {
+                    "/profile": {
+                      "get": {
+                        "responses": {
+                          "204": {
+                            "description": "No Content"
+                          }
+                        },
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_tags() {
+    #[derive(Serialize, Apiv2Schema)]
+    struct Image {
+        data: String,
+        id: u64,
+    }
+
+    #[api_v2_operation(tags(Cats, Dogs))]
+    fn some_pets_images() -> impl Future<Output = web::Json<Vec<Image>>> {
+        ready(web::Json(Vec::new()))
+    }
+
+    #[api_v2_operation(tags(Cats, "Nice cars"))]
+    fn some_cats_cars_images() -> impl Future<Output = web::Json<Vec<Image>>> {
+        ready(web::Json(Vec::new()))
+    }
+
+    run_and_check_app(
+        || {
+            let mut spec = DefaultApiRaw::default();
+            spec.tags = vec![
+                Tag {
+                    name: "Dogs".to_string(),
+                    description: Some("Images of dogs".to_string()),
+                    external_docs: None,
+                },
+                Tag {
+                    name: "Cats".to_string(),
+                    description: Some("Images of cats".to_string()),
+                    external_docs: None,
+                },
+                Tag {
+                    name: "Nice cars".to_string(),
+                    description: Some("Images of nice cars".to_string()),
+                    external_docs: None,
+                },
+            ];
+            spec.info = Info {
+                version: "0.1".into(),
+                title: "Image server".into(),
+                ..Default::default()
+            };
+
+            App::new()
+                .wrap_api_with_spec(spec)
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/images/pets").route(web::get().to(some_pets_images)))
+                .service(
+                    web::resource("/images/cats/cars").route(web::get().to(some_cats_cars_images)),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions":{
+                        "Image":{
+                            "properties":{
+                                "data":{
+                                "type":"string"
+                                },
+                                "id":{
+                                "format":"int64",
+                                "type":"integer"
+                                }
+                            },
+                            "required":[
+                                "data",
+                                "id"
+                            ],
+                            "type":"object"
+                        }
+                    },
+                    "info":{
+                        "title":"Image server",
+                        "version":"0.1"
+                    },
+                    "paths":{
+                        "/images/pets":{
+                            "get":{
+                                "responses":{
+                                "200":{
+                                    "description":"OK",
+                                    "schema":{
+                                        "items":{
+                                            "$ref":"#/definitions/Image"
+                                        },
+                                        "type":"array"
+                                    }
+                                }
+                                },
+                                "tags":[ "Cats", "Dogs" ]
+                            }
+                        },
+                        "/images/cats/cars":{
+                            "get":{
+                                "responses":{
+                                "200":{
+                                    "description":"OK",
+                                    "schema":{
+                                        "items":{
+                                            "$ref":"#/definitions/Image"
+                                        },
+                                        "type":"array"
+                                    }
+                                }
+                                },
+                                "tags":[ "Cats", "Nice cars" ]
+                            }
+                        }
+                    },
+                    "swagger":"2.0",
+                    "tags":[
+                        {
+                            "description":"Images of dogs",
+                            "name":"Dogs"
+                        },
+                        {
+                            "description":"Images of cats",
+                            "name":"Cats"
+                        },
+                        {
+                            "description":"Images of nice cars",
+                            "name":"Nice cars"
+                        }
+                    ]
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_scope_tags() {
+    #[api_v2_operation(tags(Admin))]
+    async fn delete_user() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation]
+    async fn get_user() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation]
+    async fn get_avatar() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(
+                    web::scope("/users")
+                        .tags(&[Tag {
+                            name: "users".into(),
+                            description: Some("Operations on users".into()),
+                            external_docs: None,
+                        }])
+                        .service(
+                            web::resource("/{id}")
+                                .route(web::get().to(get_user))
+                                .route(web::delete().to(delete_user)),
+                        )
+                        .service(
+                            web::scope("/{id}/avatar")
+                                .tags(&["avatars"])
+                                .route("", web::get().to(get_avatar)),
+                        ),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(
+                spec["paths"]["/users/{id}"]["get"]["tags"],
+                json!(["users"])
+            );
+            assert_eq!(
+                spec["paths"]["/users/{id}"]["delete"]["tags"],
+                json!(["Admin", "users"])
+            );
+            assert_eq!(
+                spec["paths"]["/users/{id}/avatar"]["get"]["tags"],
+                json!(["avatars", "users"])
+            );
+            assert_eq!(
+                spec["tags"],
+                json!([
+                    { "name": "users", "description": "Operations on users" },
+                    { "name": "avatars" }
+                ])
+            );
+        },
+    );
+}
+
+#[test]
+fn test_scope_security() {
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(apiKey, alias = "BearerAuth", in = "header", name = "Authorization")]
+    struct BearerAuth;
+
+    #[api_v2_operation]
+    async fn get_user() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation]
+    async fn get_avatar() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation]
+    async fn health() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/health").route(web::get().to(health)))
+                .service(
+                    web::scope("/users")
+                        .security::<BearerAuth>()
+                        .service(web::resource("/{id}").route(web::get().to(get_user)))
+                        .service(
+                            web::scope("/{id}/avatar")
+                                .security::<BearerAuth>()
+                                .route("", web::get().to(get_avatar)),
+                        ),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(
+                spec["securityDefinitions"],
+                json!({
+                    "BearerAuth": { "type": "apiKey", "in": "header", "name": "Authorization" }
+                })
+            );
+            assert_eq!(
+                spec["paths"]["/users/{id}"]["get"]["security"],
+                json!([{ "BearerAuth": [] }])
+            );
+            assert_eq!(
+                spec["paths"]["/users/{id}/avatar"]["get"]["security"],
+                json!([{ "BearerAuth": [] }])
+            );
+            assert!(spec["paths"]["/health"]["get"].get("security").is_none());
+        },
+    );
+}
+
+#[test]
+fn test_scope_path_parameters() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct TenantPath {
+        /// ID of the tenant.
+        #[allow(dead_code)]
+        tenant_id: String,
+    }
+
+    #[derive(Deserialize, Apiv2Schema)]
+    struct OrderPath {
+        tenant_id: String,
+        id: u64,
+    }
+
+    #[api_v2_operation]
+    async fn get_pets() -> Result<web::Json<Vec<Pet>>, Error> {
+        Ok(web::Json(vec![]))
+    }
+
+    #[api_v2_operation]
+    async fn get_order(path: web::Path<OrderPath>) -> Result<String, Error> {
+        Ok(format!("{}/{}", path.tenant_id, path.id))
+    }
+
+    fn tenant_scope() -> web::Scope {
+        web::scope("/t/{tenant_id}")
+            .path_parameters::<TenantPath>()
+            .service(web::resource("/pets").route(web::get().to(get_pets)))
+            .service(
+                web::scope("/orders")
+                    .service(web::resource("/{id}").route(web::get().to(get_order))),
+            )
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(tenant_scope())
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/t/{tenant_id}/pets"]["get"]["parameters"],
+                json!([{
+                    "description": "ID of the tenant.",
+                    "in": "path",
+                    "name": "tenant_id",
+                    "required": true,
+                    "type": "string"
+                }])
+            );
+
+            let params = &spec["paths"]["/t/{tenant_id}/orders/{id}"]["get"]["parameters"];
+            let names = params
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["name"].as_str().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(names, vec!["tenant_id", "id"]);
+        },
+    );
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_hidden_path_prefix("/t/{tenant_id}")
+                .service(tenant_scope())
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let paths = spec["paths"].as_object().unwrap();
+            assert_eq!(
+                paths.keys().collect::<Vec<_>>(),
+                vec!["/orders/{id}", "/pets"]
+            );
+            assert!(spec["paths"]["/pets"]["get"].get("parameters").is_none());
+            assert_eq!(
+                spec["paths"]["/orders/{id}"]["get"]["parameters"][0]["name"],
+                "id"
+            );
+            assert_eq!(
+                spec["paths"]["/orders/{id}"]["get"]["parameters"]
+                    .as_array()
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            // Requests are still routed with the prefix.
+            let resp = CLIENT
+                .get(&format!("http://{}/t/acme/orders/7", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.text().unwrap(), "acme/7");
+        },
+    );
+}
+
+#[test]
+fn test_filtered_spec() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Audit {
+        action: String,
+    }
+
+    #[api_v2_operation(tags(public))]
+    async fn get_pet() -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    #[api_v2_operation(tags(internal))]
+    async fn get_audit() -> Result<web::Json<Audit>, Error> {
+        Ok(web::Json(Audit {
+            action: "get".into(),
+        }))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api_with_spec(DefaultApiRaw {
+                    tags: vec![Tag::from("public"), Tag::from("internal")],
+                    ..Default::default()
+                })
+                .with_json_spec_at("/spec/internal.json")
+                .with_json_spec_at_filtered("/spec/public.json", |_, _, op| {
+                    op.tags.iter().any(|t| t == "public")
+                })
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .service(web::resource("/audit").route(web::get().to(get_audit)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec/public.json", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(
+                spec["paths"]
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .collect::<Vec<_>>(),
+                vec!["/pet"]
+            );
+            assert!(spec["definitions"].get("Audit").is_none());
+            assert!(spec["definitions"].get("Pet").is_some());
+            assert_eq!(spec["tags"], json!([{ "name": "public" }]));
+
+            let resp = CLIENT
+                .get(&format!("http://{}/spec/internal.json", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(spec["paths"].as_object().unwrap().len(), 2);
+            assert!(spec["definitions"].get("Audit").is_some());
+        },
+    );
+}
+
+#[test]
+fn test_hidden_routes() {
+    #[api_v2_operation]
+    async fn get_pet() -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    #[api_v2_operation(skip)]
+    async fn health() -> Result<NoContent, Error> {
+        Ok(NoContent)
+    }
+
+    #[api_v2_operation]
+    async fn debug_info() -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .service(web::resource("/health").route(web::get().to(health)))
+                .service(
+                    web::resource("/debug")
+                        .hidden()
+                        .route(web::get().to(debug_info)),
+                )
+                .route("/debug/pet", web::get().to(debug_info).hidden())
+                .build()
+        },
+        |addr| {
+            // Hidden routes are still mounted.
+            for path in &["/health", "/debug", "/debug/pet"] {
+                let resp = CLIENT
+                    .get(&format!("http://{}{}", addr, path))
+                    .send()
+                    .expect("request failed?");
+                assert!(resp.status().is_success());
+            }
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let paths = spec["paths"].as_object().unwrap();
+            assert_eq!(paths.keys().collect::<Vec<_>>(), vec!["/pet"]);
+        },
+    );
+}
+
+#[test]
+#[allow(unreachable_code)]
+fn test_impl_traits() {
+    #[api_v2_operation]
+    fn index() -> impl Responder {
+        ""
+    }
+
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    struct Params {
+        limit: Option<u16>,
+    }
+
+    #[api_v2_operation]
+    fn get_pets(
+        _data: web::Data<String>,
+        _q: web::Query<Params>,
+    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        if true {
+            // test for return in wrapper blocks (#75)
+            return futures::future::err(actix_web::error::ErrorInternalServerError(""));
+        }
+
+        futures::future::err(actix_web::error::ErrorInternalServerError(""))
+    }
+
+    #[api_v2_operation]
+    async fn get_pet_async() -> impl Responder {
+        Pet::default()
+    }
+
+    #[api_v2_operation]
+    fn get_pet() -> impl Responder {
+        Pet::default()
+    }
+
+    #[api_v2_operation(return_type = "web::Json<Pet>")]
+    fn get_pet_documented() -> impl Responder {
+        Pet::default()
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/").route(web::get().to(index)))
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .service(web::resource("/pet_async").route(web::get().to(get_pet_async)))
+                .service(web::resource("/pet_documented").route(web::get().to(get_pet_documented)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    }
+                  },
+                  "paths": {
+                    "/": {
+                      "get": {
+                        "responses": {}
+                      }
+                    },
+                    "/pets": {
+                      "get": {
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "type": "array",
+                              "items": {
+                                "$ref": "#/definitions/Pet"
+                              }
+                            }
+                          }
+                        },
+                        "parameters": [{
+                            "format": "int32",
+                            "in": "query",
+                            "name": "limit",
+                            "type": "integer"
+                        }]
+                      },
+                    },
+                    "/pet": {
+                      "get": {
+                        "responses": {}
+                      }
+                    },
+                    "/pet_async": {
+                      "get": {
+                        "responses": {}
+                      }
+                    },
+                    "/pet_documented": {
+                      "get": {
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }
+                        }
+                      }
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_method_handlers() {
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    struct Params {
+        limit: Option<u16>,
+    }
+
+    #[derive(Apiv2Schema)]
+    #[openapi(empty)]
+    struct Pets;
+
+    impl FromRequest for Pets {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            fut_ok(Pets)
+        }
+    }
+
+    impl Pets {
+        /// List pets
+        #[api_v2_operation]
+        async fn list(self, _q: web::Query<Params>) -> Result<web::Json<Vec<Pet>>, Error> {
+            Ok(web::Json(vec![Pet::default()]))
+        }
+
+        #[api_v2_operation]
+        fn get(_id: web::Path<u64>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+            fut_ok(web::Json(Pet::default()))
+        }
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pets").route(web::get().to(Pets::list)))
+                .service(web::resource("/pets/{id}").route(web::get().to(Pets::get)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    }
+                  },
+                  "paths": {
+                    "/pets": {
+                      "get": {
+                        "summary": "List pets",
+                        "parameters": [{
+                            "format": "int32",
+                            "in": "query",
+                            "name": "limit",
+                            "type": "integer"
+                        }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "type": "array",
+                              "items": {
+                                "$ref": "#/definitions/Pet"
+                              }
+                            }
+                          }
+                        }
+                      }
+                    },
+                    "/pets/{id}": {
+                      "get": {
+                        "parameters": [{
+                            "format": "int64",
+                            "in": "path",
+                            "name": "id",
+                            "required": true,
+                            "type": "integer"
+                        }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }
+                        }
+                      },
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+#[allow(unreachable_code)]
+fn test_operation_with_generics() {
+    #[api_v2_operation]
+    fn get_pet_by_id<I: paperclip::v2::schema::Apiv2Schema>(
+        _path: web::Path<I>,
+    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        futures::future::ok(web::Json(vec![Pet::default()]))
+    }
+
+    #[api_v2_operation]
+    async fn get_pet_by_name<S: paperclip::v2::schema::Apiv2Schema + ToString>(
+        _path: web::Path<S>,
+    ) -> Result<web::Json<Vec<Pet>>, Error> {
+        Ok(web::Json(vec![Pet::default()]))
+    }
+
+    #[api_v2_operation]
+    async fn get_pet_by_type<S>(_path: web::Path<S>) -> Result<web::Json<Vec<Pet>>, Error>
+    where
+        S: paperclip::v2::schema::Apiv2Schema + ToString,
+    {
+        Ok(web::Json(vec![Pet::default()]))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pet/id/{id}").route(web::get().to(get_pet_by_id::<u64>)))
+                .service(
+                    web::resource("/pet/name/{name}")
+                        .route(web::get().to(get_pet_by_name::<String>)),
+                )
+                .service(
+                    web::resource("/pet/type/{type}")
+                        .route(web::get().to(get_pet_by_type::<String>)),
+                )
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions":{
+                        "Pet":{
+                           "description":"Pets are awesome!",
+                           "properties":{
+                              "class":{
+                                 "enum":[
+                                    "dog",
+                                    "cat",
+                                    "other"
+                                 ],
+                                 "type":"string"
+                              },
+                              "id":{
+                                 "format":"int64",
+                                 "type":"integer"
+                              },
+                              "name":{
+                                 "description":"Pick a good one.",
+                                 "type":"string"
+                              },
+                              "birthday": {
+                                "format": "date",
+                                "type": "string"
+                              },
+                              "updatedOn":{
+                                 "format":"date-time",
+                                 "type":"string"
+                              },
+                              "uuid":{
+                                 "format":"uuid",
+                                 "type":"string"
+                              }
+                           },
+                           "required":[
+                             "birthday",
+                              "class",
+                              "name"
+                           ],
+                           "type":"object"
+                        }
+                     },
+                     "info":{
+                        "title":"",
+                        "version":""
+                     },
+                     "paths":{
+                        "/pet/id/{id}":{
+                           "get":{
+                              "responses":{
+                                 "200":{
+                                    "description":"OK",
+                                    "schema":{
+                                       "items":{
+                                          "$ref":"#/definitions/Pet"
+                                       },
+                                       "type":"array"
+                                    }
+                                 }
+                              },
+                              "parameters":[
+                                {
+                                   "format":"int64",
+                                   "in":"path",
+                                   "name":"id",
+                                   "required":true,
+                                   "type":"integer"
+                                }
+                             ]
+                          },
+                        },
+                        "/pet/name/{name}":{
+                           "get":{
+                              "responses":{
+                                 "200":{
+                                    "description":"OK",
+                                    "schema":{
+                                       "items":{
+                                          "$ref":"#/definitions/Pet"
+                                       },
+                                       "type":"array"
+                                    }
+                                 }
+                              },
+                              "parameters":[
+                                {
+                                   "in":"path",
+                                   "name":"name",
+                                   "required":true,
+                                   "type":"string"
+                                }
+                             ]
+                          },
+                        },
+                        "/pet/type/{type}":{
+                           "get":{
+                              "responses":{
+                                 "200":{
+                                    "description":"OK",
+                                    "schema":{
+                                       "items":{
+                                          "$ref":"#/definitions/Pet"
+                                       },
+                                       "type":"array"
+                                    }
+                                 }
+                              },
+                              "parameters":[
+                                {
+                                   "in":"path",
+                                   "name":"type",
+                                   "required":true,
+                                   "type":"string"
+                                }
+                             ]
+                          },
+                        }
+                     },
+                     "swagger":"2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+#[allow(unreachable_code)]
+fn test_operations_documentation() {
+    /// Index call
+    #[api_v2_operation]
+    fn index() -> impl Responder {
+        ""
+    }
+
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    struct Params {
+        limit: Option<u16>,
+    }
+
+    /// List all pets
+    ///
+    /// Will provide list of all pets available for sale
+    #[api_v2_operation]
+    fn get_pets(
+        _data: web::Data<String>,
+        _q: web::Query<Params>,
+    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        if true {
+            // test for return in wrapper blocks (#75)
+            return futures::future::err(actix_web::error::ErrorInternalServerError(""));
+        }
+
+        futures::future::err(actix_web::error::ErrorInternalServerError(""))
+    }
+
+    /// Get pet info
+    ///
+    /// Will provide details on a pet
+    #[api_v2_operation]
+    async fn get_pet_async() -> impl Responder {
+        Pet::default()
+    }
+
+    /// Get pet info
+    /// sync version
+    #[api_v2_operation]
+    fn get_pet() -> impl Responder {
+        Pet::default()
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/").route(web::get().to(index)))
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .service(web::resource("/pet_async").route(web::get().to(get_pet_async)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    }
+                  },
+                  "paths": {
+                    "/": {
+                      "get": {
+                        "responses": {},
+                        "summary":"Index call"
+                      }
+                    },
+                    "/pets": {
+                      "get": {
+                        "description":"Will provide list of all pets available for sale",
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "type": "array",
+                              "items": {
+                                "$ref": "#/definitions/Pet"
+                              }
+                            }
+                          }
+                        },
+                        "summary":"List all pets",
+                        "parameters": [{
+                            "format": "int32",
+                            "in": "query",
+                            "name": "limit",
+                            "type": "integer"
+                        }]
+                      },
+                    },
+                    "/pet": {
+                      "get": {
+                        "responses": {},
+                        "summary":"Get pet info sync version"
+                      }
+                    },
+                    "/pet_async": {
+                      "get": {
+                        "responses": {},
+                        "description":"Will provide details on a pet",
+                        "summary":"Get pet info"
+                      }
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+#[allow(unreachable_code)]
+fn test_operations_macro_attributes() {
+    /// Index operation
+    ///
+    /// This doc comment will be overriden by macro attrs
+    #[api_v2_operation(
+        summary = "Root",
+        description = "Provides an empty value in response",
+        operation_id = "getIndex",
+        consumes = "application/json, text/plain",
+        produces = "text/plain"
+    )]
+    fn index() -> impl Responder {
+        ""
+    }
+
+    #[derive(Serialize, Deserialize, Apiv2Schema)]
+    struct Params {
+        limit: Option<u16>,
+    }
+
+    /// List all pets (in summary)
+    ///
+    /// This doc comment will be used in description
+    #[api_v2_operation(operation_id = "getPets")]
+    fn get_pets(
+        _data: web::Data<String>,
+        _q: web::Query<Params>,
+    ) -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        if true {
+            // test for return in wrapper blocks (#75)
+            return futures::future::err(actix_web::error::ErrorInternalServerError(""));
+        }
+
+        futures::future::err(actix_web::error::ErrorInternalServerError(""))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/").route(web::get().to(index)))
+                .service(web::resource("/pets").route(web::get().to(get_pets)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions": {
+                        "Pet": {
+                            "description": "Pets are awesome!",
+                            "properties": {
+                                "class": {
+                                "enum": ["dog", "cat", "other"],
+                                    "type":"string"
+                                },
+                                "id": {
+                                    "format": "int64",
+                                    "type": "integer"
+                                },
+                                "name": {
+                                    "description": "Pick a good one.",
+                                    "type": "string"
+                                },
+                                "birthday": {
+                                  "format": "date",
+                                  "type": "string"
+                                },
+                                "updatedOn": {
+                                    "format": "date-time",
+                                    "type": "string"
+                                },
+                                "uuid":{
+                                    "format": "uuid",
+                                    "type": "string"
+                                }
+                            },
+                            "required":[
+                                "birthday",
+                                "class",
+                                "name"
+                            ],
+                            "type":"object"
+                        }
+                    },
+                    "info": {
+                        "title":"",
+                        "version":""
+                    },
+                    "paths": {
+                        "/": {
+                            "get": {
+                                "consumes": [
+                                    "application/json",
+                                    "text/plain"
+                                ],
+                                "description": "Provides an empty value in response",
+                                "operationId": "getIndex",
+                                "produces": [ "text/plain" ],
+                                "responses": {},
+                                "summary": "Root"
+                            }
+                        },
+                        "/pets": {
+                            "get": {
+                                "description": "This doc comment will be used in description",
+                                "operationId": "getPets",
+                                "parameters":[{
+                                    "format":"int32",
+                                    "in":"query",
+                                    "name":"limit",
+                                    "type":"integer"
+                                }],
+                                "responses": {
+                                "200": {
+                                    "description": "OK",
+                                    "schema": {
+                                        "items": {
+                                            "$ref": "#/definitions/Pet"
+                                        },
+                                        "type": "array"
+                                    }
+                                }
+                                },
+                                "summary": "List all pets (in summary)"
+                            }
+                        }
+                    },
+                    "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+// #[test] // issue #71
+// fn test_multiple_method_routes() {
+//     #[api_v2_operation]
+//     fn test_get() -> impl Future<Output = String> {
+//         ready("get".into())
+//     }
+//
+//     #[api_v2_operation]
+//     fn test_post() -> impl Future<Output = String> {
+//         ready("post".into())
+//     }
+//
+//     fn test_app<F, T, B>(f: F)
+//     where
+//         F: Fn() -> App<T, B> + Clone + Send + Sync + 'static,
+//         B: MessageBody + 'static,
+//         T: ServiceFactory<
+//                 Config = (),
+//                 Request = ServiceRequest,
+//                 Response = ServiceResponse<B>,
+//                 Error = Error,
+//                 InitError = (),
+//             > + 'static,
+//     {
+//         run_and_check_app(f, |addr| {
+//             let resp = CLIENT
+//                 .get(&format!("http://{}/v1/foo", addr))
+//                 .send()
+//                 .expect("request failed?");
+//             assert_eq!(resp.status().as_u16(), 200);
+//             assert_eq!(resp.text().unwrap(), "get");
+//
+//             let resp = CLIENT
+//                 .post(&format!("http://{}/v1/foo", addr))
+//                 .send()
+//                 .expect("request failed?");
+//             assert_eq!(resp.status().as_u16(), 200);
+//             assert_eq!(resp.text().unwrap(), "post");
+//
+//             let resp = CLIENT
+//                 .get(&format!("http://{}/api/spec", addr))
+//                 .send()
+//                 .expect("request failed?");
+//
+//             check_json(
+//                 resp,
+//                 json!({
+//                   "info":{"title":"","version":""},
+//                   "definitions": {},
+//                   "paths": {
+//                     "/v1/foo": {
+//                       "get": {
+//                         "responses": {},
+//                       },
+//                       "post": {
+//                         "responses": {},
+//                       },
+//                     }
+//                   },
+//                   "swagger": "2.0",
+//                 }),
+//             );
+//         });
+//     }
+//
+//     test_app(|| {
+//         App::new()
+//             .wrap_api()
+//             .with_json_spec_at("/api/spec")
+//             .route("/v1/foo", web::get().to(test_get))
+//             .route("/v1/foo", web::post().to(test_post))
+//             .build()
+//     });
+//
+//     fn config(cfg: &mut web::ServiceConfig) {
+//         cfg.route("/foo", web::get().to(test_get))
+//             .route("/foo", web::post().to(test_post));
+//     }
+//
+//     test_app(|| {
+//         App::new()
+//             .wrap_api()
+//             .with_json_spec_at("/api/spec")
+//             .service(web::scope("/v1").configure(config))
+//             .build()
+//     });
+//
+//     fn config_1(cfg: &mut web::ServiceConfig) {
+//         cfg.route("/v1/foo", web::get().to(test_get));
+//     }
+//
+//     fn config_2(cfg: &mut web::ServiceConfig) {
+//         cfg.route("/v1/foo", web::post().to(test_post));
+//     }
+//
+//     test_app(|| {
+//         App::new()
+//             .wrap_api()
+//             .with_json_spec_at("/api/spec")
+//             .configure(config_1)
+//             .configure(config_2)
+//             .build()
+//     });
+// }
+
+#[test]
+fn test_custom_extractor_empty_schema() {
+    #[derive(Apiv2Schema)]
+    #[openapi(empty)]
+    struct SomeUselessThing<T>(T);
+
+    impl FromRequest for SomeUselessThing<String> {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+            fut_ok(SomeUselessThing(String::from("booya")))
+        }
+    }
+
+    #[api_v2_operation]
+    fn index(
+        _req: HttpRequest,
+        _payload: String,
+        _thing: SomeUselessThing<String>,
+    ) -> impl Future<Output = &'static str> {
+        ready("")
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/").route(web::get().to(index)))
+                .with_raw_json_spec(|app, spec| {
+                    app.route(
+                        "/api/spec",
+                        web::get().to(move || {
+                            paperclip::util::ready(actix_web::HttpResponse::Ok().json(&spec))
+                        }),
+                    )
+                })
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {},
+                  "paths": {
+                    "/": {
+                      "get": {
+                        "responses": {}
+                      }
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_errors_app() {
+    use actix_web::{
+        error::{ErrorBadRequest, ResponseError},
+        HttpResponse,
+    };
+    use std::fmt;
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    struct PetErrorScheme1 {}
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    struct PetErrorScheme2 {}
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    struct PetErrorScheme3 {}
+
+    #[api_v2_errors(
+        400,
+        description = "Sorry, bad request",
+        code = 401,
+        code = 403,
+        schema = "PetErrorScheme1",
+        description = "Forbidden, go away",
+        500,
+        description = "Internal Server Error",
+        schema = "PetErrorScheme2"
+    )]
+    #[derive(Debug)]
+    struct PetError {}
+
+    #[api_v2_errors(
+        400,
+        description = "Sorry, bad request",
+        code = 401,
+        code = 403,
+        schema = "PetErrorScheme1",
+        description = "Forbidden, go away",
+        500,
+        description = "Internal Server Error",
+        default_schema = "PetErrorScheme2"
+    )]
+    #[derive(Debug)]
+    struct PetError2 {}
+
+    #[api_v2_errors_overlay(401)]
+    #[derive(Debug)]
+    struct PetErrorOverlay(pub PetError2);
+
+    impl fmt::Display for PetError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Bad Request")
+        }
+    }
+    impl fmt::Display for PetError2 {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Bad Request")
+        }
+    }
+
+    impl ResponseError for PetError {
+        fn error_response(&self) -> HttpResponse {
+            HttpResponse::from_error(ErrorBadRequest("Bad Request"))
+        }
+    }
+    impl ResponseError for PetError2 {
+        fn error_response(&self) -> HttpResponse {
+            HttpResponse::from_error(ErrorBadRequest("Bad Request"))
+        }
+    }
+
+    #[api_v2_operation]
+    async fn echo_pet_with_errors(body: web::Json<Pet>) -> Result<web::Json<Pet>, PetError> {
+        Ok(body)
+    }
+
+    #[api_v2_operation]
+    async fn echo_pet_with_errors2(
+        body: web::Json<Pet>,
+    ) -> Result<web::Json<Pet>, PetErrorOverlay> {
+        Ok(body)
+    }
+
+    fn config(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/echo").route(web::post().to(echo_pet_with_errors)));
+        cfg.service(web::resource("/echo2").route(web::post().to(echo_pet_with_errors2)));
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::scope("/api").configure(config))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    },
+                    "PetErrorScheme1": {
+                      "type": "object"
+                    },
+                    "PetErrorScheme2": {
+                      "type": "object"
+                    },
+                  },
+                  "paths": {
+                    "/api/echo": {
+                      "post": {
+                        "parameters": [{
+                            "in": "body",
+                            "name": "body",
+                            "required": true,
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          },
+                          "400": {
+                            "description": "Sorry, bad request"
+                          },
+                          "401": {
+                            "description": "Unauthorized"
+                          },
+                          "403":{
+                            "description": "Forbidden, go away",
+                            "schema": {
+                              "$ref": "#/definitions/PetErrorScheme1"
+                            }
+                          },
+                          "500": {
+                            "description": "Internal Server Error",
+                            "schema": {
+                              "$ref": "#/definitions/PetErrorScheme2"
+                            }
+                          }
+                        }
+                      }
+                    },
+                    "/api/echo2": {
+                      "post": {
+                        "parameters": [{
+                            "in": "body",
+                            "name": "body",
+                            "required": true,
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          },
+                          "400": {
+                            "description": "Sorry, bad request",
+                            "schema": {
+                              "$ref": "#/definitions/PetErrorScheme2"
+                            }
+                          },
+                          "403":{
+                            "description": "Forbidden, go away",
+                            "schema": {
+                              "$ref": "#/definitions/PetErrorScheme1"
+                            }
+                          },
+                          "500": {
+                            "description": "Internal Server Error",
+                            "schema": {
+                              "$ref": "#/definitions/PetErrorScheme2"
+                            }
+                          }
+                        }
+                      }
+                    },
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_default_responses() {
+    use actix_web::{
+        error::{ErrorInternalServerError, ResponseError},
+        HttpResponse,
+    };
+    use std::fmt;
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    struct ApiErrorBody {
+        message: String,
+    }
+
+    #[api_v2_errors(
+        code = 401,
+        description = "Missing or invalid credentials",
+        code = 429,
+        description = "Too many requests",
+        code = 500,
+        description = "Internal Server Error",
+        schema = "ApiErrorBody"
+    )]
+    #[derive(Debug)]
+    struct ApiError;
+
+    impl fmt::Display for ApiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Internal Server Error")
+        }
+    }
+
+    impl ResponseError for ApiError {
+        fn error_response(&self) -> HttpResponse {
+            HttpResponse::from_error(ErrorInternalServerError("Internal Server Error"))
+        }
+    }
+
+    #[api_v2_errors(code = 401, description = "Login failed")]
+    #[derive(Debug)]
+    struct LoginError;
+
+    impl fmt::Display for LoginError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Login failed")
+        }
+    }
+
+    impl ResponseError for LoginError {}
+
+    #[api_v2_operation]
+    async fn get_pet() -> Result<web::Json<Pet>, Error> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    #[api_v2_operation]
+    async fn login() -> Result<web::Json<Pet>, LoginError> {
+        Ok(web::Json(Pet::default()))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .with_default_responses::<ApiError>()
+                .service(web::resource("/login").route(web::post().to(login)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(
+                spec["definitions"]["ApiErrorBody"]["properties"]["message"]["type"],
+                "string"
+            );
+            for path in &["/pet", "/login"] {
+                let op = spec["paths"][path]
+                    .as_object()
+                    .and_then(|m| m.values().next())
+                    .unwrap();
+                assert_eq!(op["responses"]["200"]["description"], "OK");
+                assert_eq!(op["responses"]["429"]["description"], "Too many requests");
+                assert_eq!(
+                    op["responses"]["500"]["schema"],
+                    json!({"$ref": "#/definitions/ApiErrorBody"})
+                );
+            }
+
+            assert_eq!(
+                spec["paths"]["/pet"]["get"]["responses"]["401"]["description"],
+                "Missing or invalid credentials"
+            );
+            // Responses of operations take precedence.
+            assert_eq!(
+                spec["paths"]["/login"]["post"]["responses"]["401"]["description"],
+                "Login failed"
+            );
+        },
+    );
+}
+
+#[test]
+fn test_security_app() {
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(
+        apiKey,
+        alias = "JWT",
+        in = "header",
+        name = "Authorization",
+        description = "Use format 'Bearer TOKEN'"
+    )]
+    struct AccessToken;
+
+    impl FromRequest for AccessToken {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            ready(Ok(Self {}))
+        }
+    }
+
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(
+        oauth2,
+        alias = "MyOAuth2",
+        auth_url = "http://example.com/",
+        token_url = "http://example.com/token",
+        flow = "password"
+    )]
+    struct OAuth2Access;
+
+    impl FromRequest for OAuth2Access {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            ready(Ok(Self {}))
+        }
+    }
+
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(parent = "OAuth2Access", scopes("pets.read", "pets.write"))]
+    struct PetScope;
+
+    impl FromRequest for PetScope {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            ready(Ok(Self {}))
+        }
+    }
+
+    #[api_v2_operation]
+    async fn echo_pet_with_jwt(_: AccessToken, body: web::Json<Pet>) -> web::Json<Pet> {
+        body
+    }
+
+    #[api_v2_operation]
+    async fn echo_pet_with_petstore(_: PetScope, body: web::Json<Pet>) -> web::Json<Pet> {
+        body
+    }
+
+    fn config(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/echo1").route(web::post().to(echo_pet_with_jwt)))
+            .service(web::resource("/echo2").route(web::post().to(echo_pet_with_petstore)));
+    }
+
+    run_and_check_app(
+        move || {
+            App::new()
+                .wrap_api()
+                .service(web::scope("/api").configure(config))
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                  "info":{"title":"","version":""},
+                  "definitions": {
+                    "Pet": {
+                      "description": "Pets are awesome!",
+                      "properties": {
+                        "class": {
+                          "enum": ["dog", "cat", "other"],
+                          "type": "string"
+                        },
+                        "id": {
+                          "format": "int64",
+                          "type": "integer"
+                        },
+                        "name": {
+                          "description": "Pick a good one.",
+                          "type": "string"
+                        },
+                        "birthday": {
+                          "format": "date",
+                          "type": "string"
+                        },
+                        "updatedOn": {
+                          "format": "date-time",
+                          "type": "string"
+                        },
+                        "uuid": {
+                          "format": "uuid",
+                          "type": "string"
+                        }
+                      },
+                      "required":["birthday", "class", "name"],
+                      "type":"object"
+                    }
+                  },
+                  "paths": {
+                    "/api/echo1": {
+                      "post": {
+                        "parameters": [{
+                            "in": "body",
+                            "name": "body",
+                            "required": true,
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          },
+                        },
+                        "security": [
+                          {
+                            "JWT": []
+                          }
+                        ]
+                      }
+                    },
+                    "/api/echo2": {
+                      "post": {
+                        "parameters": [{
+                            "in": "body",
+                            "name": "body",
+                            "required": true,
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          }],
+                        "responses": {
+                          "200": {
+                            "description": "OK",
+                            "schema": {
+                              "$ref": "#/definitions/Pet"
+                            }
+                          },
+                        },
+                        "security": [
+                          {
+                            "MyOAuth2": ["pets.read", "pets.write"]
+                          }
+                        ]
+                      }
+                    },
+                  },
+                  "securityDefinitions": {
+                    "JWT": {
+                        "description":"Use format 'Bearer TOKEN'",
+                        "in": "header",
+                        "name": "Authorization",
+                        "type": "apiKey"
+                    },
+                    "MyOAuth2": {
+                        "scopes": {
+                          "pets.read": "pets.read",
+                          "pets.write": "pets.write"
+                        },
+                        "type": "oauth2",
+                        "authorizationUrl": "http://example.com/",
+                        "tokenUrl": "http://example.com/token",
+                        "flow": "password"
+                    }
+                  },
+                  "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[test]
+fn test_security_scope_descriptions() {
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(
+        oauth2,
+        alias = "PetStoreAuth",
+        auth_url = "http://example.com/",
+        token_url = "http://example.com/token",
+        flow = "password",
+        description = "OAuth2 with the pet store"
+    )]
+    struct PetStoreAuth;
+
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(
+        parent = "PetStoreAuth",
+        scopes(
+            scope(name = "pets.read", description = "Read your pets"),
+            "pets.write"
+        )
+    )]
+    struct ReadPets;
+
+    #[derive(Apiv2Security, Deserialize)]
+    #[openapi(
+        parent = "PetStoreAuth",
+        scopes(
+            "pets.read",
+            scope(name = "pets.write", description = "Modify your pets")
+        )
+    )]
+    struct WritePets;
+
+    impl FromRequest for ReadPets {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            ready(Ok(Self {}))
+        }
+    }
+
+    impl FromRequest for WritePets {
+        type Error = Error;
+        type Future = Ready<Result<Self, Self::Error>>;
+
+        fn from_request(_: &HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+            ready(Ok(Self {}))
+        }
+    }
+
+    #[api_v2_operation]
+    async fn list_pets(_: ReadPets) -> web::Json<Vec<String>> {
+        web::Json(vec![])
+    }
+
+    #[api_v2_operation]
+    async fn add_pet(_: WritePets, body: web::Json<String>) -> web::Json<String> {
+        body
+    }
+
+    run_and_check_app(
+        move || {
+            App::new()
+                .wrap_api()
+                .service(
+                    web::resource("/pets")
+                        .route(web::get().to(list_pets))
+                        .route(web::post().to(add_pet)),
+                )
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            // Descriptions of scopes are kept regardless of the order in which
+            // the operations (and their requirements) are recorded.
+            assert_eq!(
+                spec["securityDefinitions"]["PetStoreAuth"],
+                json!({
+                    "type": "oauth2",
+                    "flow": "password",
+                    "authorizationUrl": "http://example.com/",
+                    "tokenUrl": "http://example.com/token",
+                    "description": "OAuth2 with the pet store",
+                    "scopes": {
+                        "pets.read": "Read your pets",
+                        "pets.write": "Modify your pets"
+                    }
+                })
+            );
+        },
+    );
+}
+
+#[cfg(feature = "actix-session")]
+#[test]
+fn test_session_auth() {
+    use actix_session_dev::Session;
+    use paperclip::actix::web::SessionAuth;
+
+    #[derive(Apiv2Security)]
+    #[openapi(
+        apiKey,
+        alias = "adminCookie",
+        in = "cookie",
+        name = "admin",
+        description = "Admin session cookie"
+    )]
+    struct AdminCookie;
+
+    #[api_v2_operation]
+    async fn get_profile(session: SessionAuth<Session>) -> Result<web::Json<String>, Error> {
+        let name = session.get::<String>("name")?;
+        Ok(web::Json(name.unwrap_or_default()))
+    }
+
+    #[api_v2_operation]
+    async fn list_users(_: SessionAuth<Session, AdminCookie>) -> web::Json<Vec<String>> {
+        web::Json(vec![])
+    }
+
+    #[api_v2_operation]
+    async fn get_cart(_: Session) -> web::Json<Vec<String>> {
+        web::Json(vec![])
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/profile").route(web::get().to(get_profile)))
+                .service(web::resource("/users").route(web::get().to(list_users)))
+                .service(web::resource("/cart").route(web::get().to(get_cart)))
+                .with_json_spec_at("/api/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/profile", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["securityDefinitions"],
+                json!({
+                    "adminCookie": {
+                        "type": "apiKey",
+                        "in": "cookie",
+                        "name": "admin",
+                        "description": "Admin session cookie"
+                    },
+                    "sessionCookie": {
+                        "type": "apiKey",
+                        "in": "cookie",
+                        "name": "id",
+                        "description": "Session cookie"
+                    }
+                })
+            );
+            assert_eq!(
+                spec["paths"]["/profile"]["get"]["security"],
+                json!([{ "sessionCookie": [] }])
+            );
+            assert_eq!(
+                spec["paths"]["/users"]["get"]["security"],
+                json!([{ "adminCookie": [] }])
+            );
+            // Sessions without authentication aren't secured.
+            assert!(spec["paths"]["/cart"]["get"].get("security").is_none());
+        },
+    );
+}
+
+#[test]
+fn test_method_macro() {
+    #[get("/v0/pets")]
+    #[api_v2_operation]
+    fn get_pets() -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        futures::future::ready(Ok(web::Json(Default::default())))
+    }
+    #[put("/v0/pets/{name}")]
+    #[api_v2_operation]
+    fn put_pet(
+        _name: web::Path<String>,
+        pet: web::Json<Pet>,
+    ) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::ready(Ok(pet))
+    }
+    #[post("/v0/pets")]
+    #[api_v2_operation]
+    fn post_pet(pet: web::Json<Pet>) -> impl Future<Output = Result<web::Json<Pet>, Error>> {
+        futures::future::ready(Ok(pet))
+    }
+    #[delete("/v0/pets/{name}")]
+    #[api_v2_operation]
+    fn delete_pet(_name: web::Path<String>) -> impl Future<Output = Result<web::Json<()>, Error>> {
+        futures::future::ready(Ok(web::Json(())))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(get_pets)
+                .service(put_pet)
+                .service(post_pet)
+                .service(delete_pet)
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            check_json(
+                resp,
+                json!({
+                    "definitions": {
+                        "Pet": {
+                            "description": "Pets are awesome!",
+                            "properties": {
+                                "class": {
+                                "enum": ["dog", "cat", "other"],
+                                    "type":"string"
+                                },
+                                "id": {
+                                    "format": "int64",
+                                    "type": "integer"
+                                },
+                                "name": {
+                                    "description": "Pick a good one.",
+                                    "type": "string"
+                                },
+                                "birthday": {
+                                  "format": "date",
+                                  "type": "string"
+                                },
+                                "updatedOn": {
+                                    "format": "date-time",
+                                    "type": "string"
+                                },
+                                "uuid":{
+                                    "format": "uuid",
+                                    "type": "string"
+                                }
+                            },
+                            "required":[
+                                "birthday",
+                                "class",
+                                "name"
+                            ],
+                            "type":"object"
+                        }
+                    },
+                    "info": {
+                        "title":"",
+                        "version":""
+                    },
+                    "paths": {
+                        "/v0/pets": {
+                            "get": {
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "items": {
+                                                "$ref": "#/definitions/Pet"
+                                            },
+                                            "type": "array"
+                                        }
+                                    }
+                                },
+                            },
+                            "post": {
+                                "parameters": [
+                                    {
+                                        "in": "body",
+                                        "name": "body",
+                                        "required": true,
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
+                                    }
+                                ],
+                                "responses": {
+                                    "200": {
+                                        "description": "OK",
+                                        "schema": {
+                                            "$ref": "#/definitions/Pet"
+                                        }
+                                    }
+                                },
+                            }
+                        },
+                        "/v0/pets/{name}": {
+                            "delete": {
+                                "parameters": [
+                                    {
+                                        "in": "path",
                                         "name": "name",
                                         "required": true,
                                         "type": "string"
@@ -3066,80 +7635,1162 @@ fn test_method_macro() {
                             }
                         }
                     },
-                    "swagger": "2.0"
-                }),
+                    "swagger": "2.0"
+                }),
+            );
+        },
+    );
+}
+
+#[cfg(feature = "auto-routes")]
+#[test]
+fn test_collect_annotated_routes() {
+    #[get("/v0/auto/pets")]
+    #[api_v2_operation]
+    fn get_auto_pets() -> impl Future<Output = Result<web::Json<Vec<Pet>>, Error>> {
+        futures::future::ready(Ok(web::Json(Default::default())))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .collect_annotated_routes()
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+
+            assert_eq!(resp.status().as_u16(), 200);
+            let json = resp.json::<serde_json::Value>().expect("json error");
+            // Routes from other tests get collected as well, so only check ours.
+            assert_eq!(
+                json["paths"]["/v0/auto/pets"]["get"]["responses"]["200"]["schema"],
+                json!({
+                    "items": {
+                        "$ref": "#/definitions/Pet"
+                    },
+                    "type": "array"
+                })
+            );
+            assert!(json["definitions"]["Pet"].is_object());
+        },
+    );
+}
+
+fn run_and_check_app<F, G, T, B, U>(factory: F, check: G) -> U
+where
+    F: Fn() -> App<T> + Clone + Send + Sync + 'static,
+    B: MessageBody + 'static,
+    T: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<B>,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    G: Fn(String) -> U,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let _ = thread::spawn(move || {
+        let sys = System::new();
+        for port in 3000..30000 {
+            if !PORTS.lock().insert(port) {
+                continue;
+            }
+
+            let addr = format!("127.0.0.1:{}", port);
+            let server = match HttpServer::new(factory.clone()).bind(&addr) {
+                Ok(srv) => {
+                    println!("Bound to {}", addr);
+                    srv
+                }
+                Err(_) => continue,
+            };
+
+            let s = sys.block_on(async { server.run() });
+            tx.send((s.handle(), addr)).unwrap();
+            sys.block_on(s).expect("system error?");
+            return;
+        }
+
+        unreachable!("No ports???");
+    });
+
+    let (_server, addr) = rx.recv().unwrap();
+    let ret = check(addr);
+    let _ = _server.stop(true);
+    ret
+}
+
+/// Logger collecting the warnings logged by paperclip in the threads capturing them.
+struct WarningCollector;
+
+thread_local! {
+    static WARNINGS: std::cell::RefCell<Option<Vec<String>>> = std::cell::RefCell::new(None);
+}
+
+impl log::Log for WarningCollector {
+    fn enabled(&self, meta: &log::Metadata) -> bool {
+        meta.level() <= log::Level::Warn && meta.target().starts_with("paperclip")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            WARNINGS.with(|w| {
+                if let Some(w) = w.borrow_mut().as_mut() {
+                    w.push(record.args().to_string());
+                }
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Calls the given function and returns its result along with the warnings
+/// logged by paperclip in the meantime.
+fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    static LOGGER: Lazy<()> = Lazy::new(|| {
+        log::set_logger(&WarningCollector).expect("setting logger");
+        log::set_max_level(log::LevelFilter::Warn);
+    });
+
+    Lazy::force(&LOGGER);
+    WARNINGS.with(|w| *w.borrow_mut() = Some(vec![]));
+    let ret = f();
+    let warnings = WARNINGS.with(|w| w.borrow_mut().take().unwrap_or_default());
+    (ret, warnings)
+}
+
+fn check_json(resp: reqwest::blocking::Response, expected: serde_json::Value) {
+    assert_eq!(resp.status().as_u16(), 200);
+    let json = resp.json::<serde_json::Value>().expect("json error");
+
+    if json != expected {
+        panic!(
+            "assertion failed:
+  left: {}
+
+ right: {}
+",
+            json.to_string(),
+            expected.to_string()
+        )
+    }
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_openapi3() {
+    let spec = std::fs::File::open("tests/pet-v2.yaml").unwrap();
+    let spec: DefaultApiRaw = serde_yaml::from_reader(spec).unwrap();
+    let _spec_v3: openapiv3::OpenAPI = spec.into();
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_openapi3_round_trip() {
+    let pet = std::fs::File::open("tests/pet-v2.yaml").unwrap();
+    let pet: DefaultApiRaw = serde_yaml::from_reader(pet).unwrap();
+    let k8s = std::fs::File::open("tests/k8s-v1.16.0-alpha.0-openapi-v2.json").unwrap();
+    let k8s: DefaultApiRaw = serde_json::from_reader(k8s).unwrap();
+
+    for spec in vec![pet.clone(), k8s] {
+        let spec_v3: openapiv3::OpenAPI = spec.into();
+        let json = serde_json::to_value(&spec_v3).unwrap();
+        let parsed: openapiv3::OpenAPI = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+
+    let spec: openapiv3::OpenAPI = pet.into();
+    let operation =
+        |path: &str, method: fn(&openapiv3::PathItem) -> &Option<openapiv3::Operation>| match &spec
+            .paths[path]
+        {
+            openapiv3::ReferenceOr::Item(item) => method(item).clone().unwrap(),
+            _ => unreachable!(),
+        };
+
+    // Files (in form data) are sent through multipart forms.
+    let op = operation("/test/file", |p| &p.put);
+    match op.request_body {
+        Some(openapiv3::ReferenceOr::Item(body)) => {
+            assert!(body.required);
+            assert_eq!(
+                body.content.keys().collect::<Vec<_>>(),
+                vec!["multipart/form-data"]
+            );
+            match &body.content["multipart/form-data"].schema {
+                Some(openapiv3::ReferenceOr::Item(openapiv3::Schema {
+                    schema_kind: openapiv3::SchemaKind::Any(any),
+                    ..
+                })) => {
+                    assert_eq!(any.required, vec!["someDataFile", "foobar"]);
+                    assert!(any.properties.contains_key("someOtherFile"));
+                }
+                s => panic!("unexpected schema: {:?}", s),
+            }
+        }
+        b => panic!("unexpected request body: {:?}", b),
+    }
+
+    // Collection formats are mapped to styles (or kept in an extension).
+    let op = operation("/test/parameter/{values}", |p| &p.post);
+    for param in op.parameters {
+        match param {
+            openapiv3::ReferenceOr::Item(openapiv3::Parameter::Path { parameter_data, .. }) => {
+                assert_eq!(parameter_data.extensions["x-collection-format"], "tsv");
+            }
+            openapiv3::ReferenceOr::Item(openapiv3::Parameter::Header {
+                parameter_data, ..
+            }) => {
+                assert_eq!(parameter_data.extensions["x-collection-format"], "pipes");
+            }
+            openapiv3::ReferenceOr::Item(openapiv3::Parameter::Query {
+                parameter_data,
+                style,
+                ..
+            }) => {
+                if parameter_data.name == "filter" {
+                    assert!(matches!(style, openapiv3::QueryStyle::DeepObject));
+                } else {
+                    assert!(matches!(style, openapiv3::QueryStyle::Form));
+                }
+
+                assert!(parameter_data.extensions.is_empty());
+            }
+            p => panic!("unexpected parameter: {:?}", p),
+        }
+    }
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_openapi3_to_v2() {
+    use paperclip::v2::models::{CollectionFormat, DataType};
+
+    let pet = std::fs::File::open("tests/pet-v2.yaml").unwrap();
+    let pet: DefaultApiRaw = serde_yaml::from_reader(pet).unwrap();
+    let (spec, _warnings) = paperclip::v3::openapiv3_to_v2(pet.clone().into());
+
+    assert_eq!(
+        spec.paths.keys().collect::<Vec<_>>(),
+        pet.paths.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        spec.definitions.keys().collect::<Vec<_>>(),
+        pet.definitions.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(spec.base_path, pet.base_path);
+
+    // Files are back in form data.
+    let op = &spec.paths["/test/file"].methods[&HttpMethod::Put];
+    let files = op
+        .parameters
+        .iter()
+        .filter(|p| p.data_type == Some(DataType::File))
+        .map(|p| (p.name.as_str(), p.required))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        files,
+        vec![("someDataFile", true), ("someOtherFile", false)]
+    );
+
+    // Collection formats survive the round trip.
+    let op = &spec.paths["/test/parameter/{values}"].methods[&HttpMethod::Post];
+    let formats = op
+        .parameters
+        .iter()
+        .map(|p| (p.name.as_str(), p.collection_format))
+        .collect::<Vec<_>>();
+    assert!(formats.contains(&("values", Some(CollectionFormat::Tsv))));
+    assert!(formats.contains(&("X-foobar", Some(CollectionFormat::Pipes))));
+
+    // Object query parameters are still `deepObject` parameters.
+    let filter = op.parameters.iter().find(|p| p.name == "filter").unwrap();
+    assert_eq!(filter.data_type, Some(DataType::Object));
+}
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_native_v3_schemas() {
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Owner {
+        name: String,
+    }
+
+    /// Shape of a toy.
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    #[serde(rename_all = "lowercase")]
+    enum Shape {
+        Blob,
+        Circle { radius: f64 },
+        Named(Owner),
+    }
+
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Toy {
+        label: Option<String>,
+        owner: Option<Owner>,
+        shape: Shape,
+    }
+
+    #[api_v2_operation]
+    async fn add_toy(body: web::Json<Toy>) -> Result<web::Json<Toy>, Error> {
+        Ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .with_json_spec_v3_at("/api/spec/v3")
+                .service(web::resource("/toys").route(web::post().to(add_toy)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec/v3", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let schemas = &spec["components"]["schemas"];
+
+            let toy = &schemas["Toy"];
+            assert_eq!(toy["required"], json!(["shape"]));
+            assert_eq!(
+                toy["properties"]["label"],
+                json!({ "type": "string", "nullable": true })
+            );
+            assert_eq!(
+                toy["properties"]["owner"],
+                json!({
+                    "allOf": [{ "$ref": "#/components/schemas/Owner" }],
+                    "nullable": true
+                })
+            );
+            assert_eq!(
+                toy["properties"]["shape"]["$ref"],
+                "#/components/schemas/Shape"
+            );
+
+            // Data variants (which v2 can't describe) show up as `oneOf`.
+            let shape = &schemas["Shape"];
+            assert_eq!(shape["description"], "Shape of a toy.");
+            assert_eq!(
+                shape["oneOf"],
+                json!([
+                    { "type": "string", "enum": ["blob"] },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "circle": {
+                                "type": "object",
+                                "properties": {
+                                    "radius": { "type": "number", "format": "double" }
+                                },
+                                "required": ["radius"]
+                            }
+                        },
+                        "required": ["circle"],
+                        "additionalProperties": false
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "named": { "$ref": "#/components/schemas/Owner" }
+                        },
+                        "required": ["named"],
+                        "additionalProperties": false
+                    }
+                ])
+            );
+            assert_eq!(schemas["Owner"]["properties"]["name"]["type"], "string");
+
+            // The v2 spec is unaffected.
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(spec["definitions"]["Shape"]["enum"], json!(["blob"]));
+        },
+    );
+}
+
+#[test]
+fn test_write_spec_to_file() {
+    #[api_v2_operation]
+    async fn get_pet() -> web::Json<Pet> {
+        unimplemented!();
+    }
+
+    let dir = std::env::temp_dir().join(format!("paperclip-spec-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (json_path, yaml_path) = (dir.join("spec.json"), dir.join("spec.yaml"));
+
+    // The spec is written when the app is built, without starting a server.
+    let _ = App::new()
+        .wrap_api()
+        .write_spec_to_file(&json_path)
+        .write_spec_to_file(&yaml_path)
+        .service(web::resource("/pet").route(web::get().to(get_pet)))
+        .build();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    let yaml: serde_json::Value =
+        serde_yaml::from_str(&std::fs::read_to_string(&yaml_path).unwrap()).unwrap();
+    assert_eq!(json, yaml);
+    assert_eq!(json["swagger"], "2.0");
+    assert_eq!(
+        json["paths"]["/pet"]["get"]["responses"]["200"]["schema"]["$ref"],
+        "#/definitions/Pet"
+    );
+    assert!(json["definitions"]["Pet"].is_object());
+
+    // Workers build the app (and write the file) at the same time.
+    let workers = (0..4)
+        .map(|_| {
+            let path = json_path.clone();
+            std::thread::spawn(move || {
+                let _ = App::new()
+                    .wrap_api()
+                    .write_spec_to_file(&path)
+                    .service(web::resource("/pet").route(web::get().to(get_pet)))
+                    .build();
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(written, json);
+    // No temporary files are left behind.
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_spec_etag() {
+    #[api_v2_operation]
+    async fn get_pet() -> web::Json<Pet> {
+        unimplemented!();
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .with_json_spec_at("/api/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            let etag = resp.headers()["ETag"].to_str().unwrap().to_owned();
+            assert!(etag.starts_with('"') && etag.ends_with('"'));
+            let spec: serde_json::Value = resp.json().unwrap();
+            assert_eq!(
+                spec["paths"]["/pet"]["get"]["responses"]["200"]["description"],
+                "OK"
+            );
+
+            // Clients which already have the spec don't get it again.
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .header("If-None-Match", etag.as_str())
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 304);
+            assert_eq!(resp.headers()["ETag"], etag.as_str());
+            assert!(resp.text().unwrap().is_empty());
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .header("If-None-Match", "\"outdated\"")
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+        },
+    );
+}
+
+#[test]
+fn test_spec_resource_guards() {
+    #[api_v2_operation]
+    async fn get_pet() -> web::Json<Pet> {
+        unimplemented!();
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .with_json_spec_at("/api/public-spec")
+                .configure_spec_resources(|r| {
+                    r.guard(actix_web::guard::Header("x-api-key", "secret"))
+                })
+                .with_json_spec_at("/api/spec")
+                .with_rapidoc_at("/docs")
+                .build()
+        },
+        |addr| {
+            let get = |path: &str, key: Option<&str>| {
+                let mut req = CLIENT.get(&format!("http://{}{}", addr, path));
+                if let Some(key) = key {
+                    req = req.header("x-api-key", key);
+                }
+                req.send().expect("request failed?").status().as_u16()
+            };
+
+            // Resources mounted before the call aren't affected.
+            assert_eq!(get("/api/public-spec", None), 200);
+            for path in &["/api/spec", "/docs"] {
+                assert_eq!(get(path, None), 404);
+                assert_eq!(get(path, Some("wrong")), 404);
+                assert_eq!(get(path, Some("secret")), 200);
+            }
+        },
+    );
+}
+
+#[cfg(feature = "garde")]
+#[test]
+fn test_garde_constraints() {
+    use garde::Validate;
+    use regex::Regex;
+
+    static NICKNAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z]+$").unwrap());
+
+    #[derive(Deserialize, Serialize, Apiv2Schema, Validate)]
+    struct NewPet {
+        #[garde(length(min = 1, max = 64), pattern(r"^[A-Za-z ]+$"))]
+        name: String,
+        #[garde(pattern(NICKNAME))]
+        nickname: String,
+        #[garde(range(min = 0, max = 30))]
+        age: u8,
+        #[garde(range(min = 0.5))]
+        weight: f64,
+        #[garde(length(min = 2, max = 2))]
+        country: String,
+        #[garde(length(max = 5), inner(length(min = 1)))]
+        tags: Vec<String>,
+        #[garde(url)]
+        homepage: Option<String>,
+        #[garde(skip)]
+        note: String,
+    }
+
+    #[api_v2_operation]
+    async fn add_pet(body: web::Json<NewPet>) -> Result<web::Json<NewPet>, Error> {
+        body.0
+            .validate()
+            .map_err(|e| actix_web::error::ErrorBadRequest(e.to_string()))?;
+        Ok(body)
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/pets").route(web::post().to(add_pet)))
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+            let props = &spec["definitions"]["NewPet"]["properties"];
+            assert_eq!(
+                props["name"],
+                json!({
+                    "type": "string",
+                    "minLength": 1,
+                    "maxLength": 64,
+                    "pattern": "^[A-Za-z ]+$"
+                })
+            );
+            assert_eq!(
+                props["nickname"],
+                json!({ "type": "string", "pattern": "^[a-z]+$" })
+            );
+            assert_eq!(
+                props["age"],
+                json!({
+                    "type": "integer",
+                    "format": "int32",
+                    "minimum": 0.0,
+                    "maximum": 30.0
+                })
+            );
+            assert_eq!(
+                props["weight"],
+                json!({ "type": "number", "format": "double", "minimum": 0.5 })
+            );
+            assert_eq!(
+                props["country"],
+                json!({ "type": "string", "minLength": 2, "maxLength": 2 })
+            );
+            assert_eq!(
+                props["tags"],
+                json!({
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "maxItems": 5
+                })
+            );
+            assert_eq!(
+                props["homepage"],
+                json!({ "type": "string", "format": "url" })
+            );
+            assert_eq!(props["note"], json!({ "type": "string" }));
+
+            // Bodies conforming to the documented constraints pass the validation.
+            let pet = json!({
+                "name": "Milo",
+                "nickname": "milo",
+                "age": 3,
+                "weight": 4.2,
+                "country": "IN",
+                "tags": ["cat"],
+                "homepage": "https://example.com/milo",
+                "note": ""
+            });
+            let resp = CLIENT
+                .post(&format!("http://{}/pets", addr))
+                .json(&pet)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            for (key, value) in &[
+                ("name", json!("")),
+                ("nickname", json!("Milo")),
+                ("age", json!(31)),
+                ("weight", json!(0.1)),
+                ("country", json!("IND")),
+                ("tags", json!(["a", "b", "c", "d", "e", "f"])),
+                ("homepage", json!("not a url")),
+            ] {
+                let mut pet = pet.clone();
+                pet[*key] = value.clone();
+                let resp = CLIENT
+                    .post(&format!("http://{}/pets", addr))
+                    .json(&pet)
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 400, "{}", key);
+            }
+        },
+    );
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_json_schema_bridge() {
+    /// Toys for your pets.
+    #[derive(Deserialize, Serialize, schemars_dev::JsonSchema)]
+    #[schemars(crate = "schemars_dev")]
+    struct Toy {
+        /// Name of the toy.
+        name: String,
+        price: Option<u32>,
+        kind: ToyKind,
+        parts: Vec<Toy>,
+    }
+
+    #[derive(Deserialize, Serialize, schemars_dev::JsonSchema)]
+    #[schemars(crate = "schemars_dev")]
+    #[serde(rename_all = "lowercase")]
+    enum ToyKind {
+        Ball,
+        Bone,
+    }
+
+    paperclip::v2::impl_apiv2_schema_for_json_schema!(Toy);
+
+    #[api_v2_operation]
+    async fn add_toy(body: web::Json<Toy>) -> web::Json<Toy> {
+        body
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/toys").route(web::post().to(add_toy)))
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            // Definitions are inlined (and named), recursive references aren't.
+            assert_eq!(
+                spec["definitions"]["Toy"],
+                json!({
+                    "description": "Toys for your pets.",
+                    "type": "object",
+                    "properties": {
+                        "kind": {
+                            "type": "string",
+                            "enum": ["ball", "bone"]
+                        },
+                        "name": {
+                            "description": "Name of the toy.",
+                            "type": "string"
+                        },
+                        "parts": {
+                            "type": "array",
+                            "items": {
+                                "$ref": "#/definitions/Toy"
+                            }
+                        },
+                        "price": {
+                            "type": "integer",
+                            "minimum": 0.0
+                        }
+                    },
+                    "required": ["kind", "name", "parts"]
+                })
             );
         },
     );
 }
 
-fn run_and_check_app<F, G, T, B, U>(factory: F, check: G) -> U
-where
-    F: Fn() -> App<T, B> + Clone + Send + Sync + 'static,
-    B: MessageBody + 'static,
-    T: ServiceFactory<
-            Config = (),
-            Request = ServiceRequest,
-            Response = ServiceResponse<B>,
-            Error = Error,
-            InitError = (),
-        > + 'static,
-    G: Fn(String) -> U,
-{
-    let (tx, rx) = mpsc::channel();
+#[test]
+fn test_request_validation() {
+    #[derive(Deserialize, Apiv2Schema)]
+    struct Limits {
+        limit: Option<u32>,
+    }
 
-    let _ = thread::spawn(move || {
-        let sys = System::new("test");
-        for port in 3000..30000 {
-            if !PORTS.lock().insert(port) {
-                continue;
-            }
+    #[api_v2_operation]
+    async fn update_pet(
+        _id: web::Path<u64>,
+        _limits: web::Query<Limits>,
+        body: web::Json<Pet>,
+    ) -> web::Json<Pet> {
+        body
+    }
 
-            let addr = format!("127.0.0.1:{}", port);
-            let server = match HttpServer::new(factory.clone()).bind(&addr) {
-                Ok(srv) => {
-                    println!("Bound to {}", addr);
-                    srv
-                }
-                Err(_) => continue,
-            };
+    // Parameters are coerced regardless of the order of calls.
+    for &coerce_first in &[true, false] {
+        run_and_check_app(
+            move || {
+                let app = App::new().wrap_api();
+                let app = if coerce_first {
+                    app.with_parameter_coercion().with_request_validation()
+                } else {
+                    app.with_request_validation().with_parameter_coercion()
+                };
+
+                app.service(web::resource("/pets/{id}").route(web::post().to(update_pet)))
+                    .with_json_spec_at("/api/spec")
+                    .build()
+            },
+            |addr| {
+                let post = |path: &str, body: serde_json::Value| {
+                    CLIENT
+                        .post(&format!("http://{}{}", addr, path))
+                        .json(&body)
+                        .send()
+                        .expect("request failed?")
+                };
+
+                let pet = json!({"name": "Milo", "class": "dog", "birthday": "2012-03-10"});
+                let resp = post("/pets/1?limit=2", pet.clone());
+                assert_eq!(resp.status().as_u16(), 200);
+                assert_eq!(resp.json::<serde_json::Value>().unwrap()["name"], "Milo");
+
+                // All the offending parameters are reported.
+                let resp = post(
+                    "/pets/x?limit=ten",
+                    json!({"name": 5, "class": "fish", "birthday": "2012-03-10"}),
+                );
+                assert_eq!(resp.status().as_u16(), 400);
+                let body: serde_json::Value = resp.json().unwrap();
+                assert_eq!(body["message"], "request doesn't conform to the spec");
+                let mut errors: Vec<_> = body["errors"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| {
+                        format!(
+                            "{} {} {}",
+                            e["in"].as_str().unwrap(),
+                            e["name"].as_str().unwrap(),
+                            e["path"].as_str().unwrap()
+                        )
+                    })
+                    .collect();
+                errors.sort();
+                assert_eq!(
+                    errors,
+                    vec![
+                        "body body /class",
+                        "body body /name",
+                        "path id ",
+                        "query limit ",
+                    ]
+                );
+
+                // Other paths aren't affected.
+                let resp = CLIENT
+                    .get(&format!("http://{}/api/spec", addr))
+                    .send()
+                    .expect("request failed?");
+                assert_eq!(resp.status().as_u16(), 200);
+            },
+        );
+    }
+}
 
-            let s = server.run();
-            tx.send((s, addr)).unwrap();
-            sys.run().expect("system error?");
-            return;
-        }
+#[cfg(feature = "utoipa")]
+#[test]
+fn test_to_schema_bridge() {
+    use utoipa_dev as utoipa;
 
-        unreachable!("No ports???");
-    });
+    /// Treats for your pets.
+    #[derive(Deserialize, Serialize, utoipa::ToSchema)]
+    struct Treat {
+        /// Name of the treat.
+        name: String,
+        calories: Option<u32>,
+        flavor: Option<Flavor>,
+    }
 
-    let (_server, addr) = rx.recv().unwrap();
-    let ret = check(addr);
-    let _ = _server.stop(true);
-    ret
+    #[derive(Deserialize, Serialize, utoipa::ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    enum Flavor {
+        Chicken,
+        Beef,
+    }
+
+    paperclip::v2::impl_apiv2_schema_for_to_schema!(Treat);
+
+    #[api_v2_operation]
+    async fn add_treat(body: web::Json<Treat>) -> web::Json<Treat> {
+        body
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/treats").route(web::post().to(add_treat)))
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            // References to other schemas (including nullable ones) are inlined and named.
+            assert_eq!(
+                spec["definitions"]["Treat"],
+                json!({
+                    "description": "Treats for your pets.",
+                    "type": "object",
+                    "properties": {
+                        "calories": {
+                            "type": "integer",
+                            "format": "int32",
+                            "minimum": 0.0
+                        },
+                        "flavor": {
+                            "type": "string",
+                            "enum": ["chicken", "beef"]
+                        },
+                        "name": {
+                            "description": "Name of the treat.",
+                            "type": "string"
+                        }
+                    },
+                    "required": ["name"]
+                })
+            );
+        },
+    );
 }
 
-fn check_json(resp: reqwest::blocking::Response, expected: serde_json::Value) {
-    assert_eq!(resp.status().as_u16(), 200);
-    let json = resp.json::<serde_json::Value>().expect("json error");
+#[test]
+fn test_string_types() {
+    paperclip::v2::api_string_type!(
+        /// ID of an order.
+        OrderId, pattern = "^ord_[a-z0-9]{12}$"
+    );
 
-    if json != expected {
-        panic!(
-            "assertion failed:
-  left: {}
+    #[derive(Deserialize, Serialize, Apiv2Schema)]
+    struct Order {
+        id: OrderId,
+        quantity: u32,
+    }
 
- right: {}
-",
-            json.to_string(),
-            expected.to_string()
-        )
+    #[api_v2_operation]
+    async fn get_order(id: web::Path<OrderId>) -> Result<web::Json<Order>, Error> {
+        Ok(web::Json(Order {
+            id: id.into_inner(),
+            quantity: 1,
+        }))
     }
+
+    assert!("ord_0123456789ab".parse::<OrderId>().is_ok());
+    assert!(OrderId::new("order-1").is_err());
+    assert!(serde_json::from_value::<Order>(json!({"id": "1", "quantity": 1})).is_err());
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_json_spec_at("/api/spec")
+                .service(web::resource("/orders/{id}").route(web::get().to(get_order)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/orders/ord_0123456789ab", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/orders/order-1", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 404);
+
+            let resp = CLIENT
+                .get(&format!("http://{}/api/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            assert_eq!(
+                spec["definitions"]["Order"]["properties"]["id"],
+                json!({ "type": "string", "pattern": "^ord_[a-z0-9]{12}$" })
+            );
+            assert_eq!(
+                spec["paths"]["/orders/{id}"]["get"]["parameters"][0]["pattern"],
+                "^ord_[a-z0-9]{12}$"
+            );
+        },
+    );
 }
 
-#[cfg(feature = "v3")]
+#[cfg(feature = "prost-types")]
 #[test]
-fn test_openapi3() {
-    let spec = std::fs::File::open("tests/pet-v2.yaml").unwrap();
-    let spec: DefaultApiRaw = serde_yaml::from_reader(spec).unwrap();
-    let _spec_v3: openapiv3::OpenAPI = spec.into();
+fn test_prost_well_known_types() {
+    use paperclip::v2::schema::Apiv2Schema as _;
+
+    #[derive(Apiv2Schema)]
+    #[allow(dead_code)]
+    struct Event {
+        created_at: prost_types_dev::Timestamp,
+        timeout: prost_types_dev::Duration,
+        labels: prost_types_dev::Struct,
+        payload: Option<prost_types_dev::Any>,
+    }
+
+    // Schemas match the canonical JSON mappings of the types.
+    let schema = serde_json::to_value(Event::raw_schema()).unwrap();
+    assert_eq!(
+        schema["properties"],
+        json!({
+            "created_at": {
+                "type": "string",
+                "format": "date-time"
+            },
+            "timeout": {
+                "type": "string",
+                "pattern": r"^-?\d+(\.\d{1,9})?s$"
+            },
+            "labels": {
+                "type": "object",
+                "additionalProperties": true
+            },
+            "payload": {
+                "type": "object",
+                "properties": {
+                    "@type": {
+                        "type": "string"
+                    }
+                },
+                "required": ["@type"],
+                "additionalProperties": true
+            }
+        })
+    );
+    assert_eq!(
+        schema["required"],
+        json!(["created_at", "labels", "timeout"])
+    );
+}
+
+#[test]
+fn test_response_validation() {
+    use paperclip::actix::validation::MismatchAction;
+
+    fn as_string<S: serde::Serializer>(value: &u32, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&value.to_string())
+    }
+
+    /// Serialization of this drifts from its schema (which has an integer).
+    #[derive(Serialize, Apiv2Schema)]
+    struct Counter {
+        #[serde(serialize_with = "as_string")]
+        count: u32,
+    }
+
+    #[api_v2_operation]
+    async fn get_pet() -> web::Json<Pet> {
+        web::Json(Pet::default())
+    }
+
+    #[api_v2_operation]
+    async fn get_counter() -> web::Json<Counter> {
+        web::Json(Counter { count: 1 })
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .with_response_validation(MismatchAction::Panic)
+                .service(web::resource("/pet").route(web::get().to(get_pet)))
+                .service(web::resource("/counter").route(web::get().to(get_counter)))
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/pet", addr))
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            assert_eq!(resp.json::<serde_json::Value>().unwrap()["name"], "");
+
+            // The worker panics (and drops the connection) for the response
+            // which doesn't conform to its schema.
+            assert!(CLIENT
+                .get(&format!("http://{}/counter", addr))
+                .send()
+                .is_err());
+        },
+    );
+}
+
+#[cfg(feature = "actix-multipart")]
+#[test]
+fn test_multipart_form() {
+    use futures::TryStreamExt;
+    use paperclip::actix::{FilePart, MultipartForm};
+
+    #[derive(Apiv2Schema)]
+    #[allow(dead_code)]
+    struct PetPhoto {
+        /// Caption of the photo.
+        caption: Option<String>,
+        pet_id: u64,
+        photo: FilePart,
+    }
+
+    #[api_v2_operation]
+    async fn upload_photo(mut form: MultipartForm<PetPhoto>) -> Result<web::Json<usize>, Error> {
+        let mut count = 0;
+        while let Some(mut field) = form.try_next().await? {
+            while field.try_next().await?.is_some() {}
+            count += 1;
+        }
+
+        Ok(web::Json(count))
+    }
+
+    run_and_check_app(
+        || {
+            App::new()
+                .wrap_api()
+                .service(web::resource("/photos").route(web::post().to(upload_photo)))
+                .with_json_spec_at("/spec")
+                .build()
+        },
+        |addr| {
+            let resp = CLIENT
+                .get(&format!("http://{}/spec", addr))
+                .send()
+                .expect("request failed?");
+            let spec: serde_json::Value = resp.json().unwrap();
+
+            let op = &spec["paths"]["/photos"]["post"];
+            assert_eq!(op["consumes"], json!(["multipart/form-data"]));
+            assert_eq!(
+                op["parameters"],
+                json!([
+                    {
+                        "description": "Caption of the photo.",
+                        "in": "formData",
+                        "name": "caption",
+                        "type": "string"
+                    },
+                    {
+                        "format": "int64",
+                        "in": "formData",
+                        "name": "pet_id",
+                        "required": true,
+                        "type": "integer"
+                    },
+                    {
+                        "format": "binary",
+                        "in": "formData",
+                        "name": "photo",
+                        "required": true,
+                        "type": "file"
+                    }
+                ])
+            );
+            // The form itself isn't a definition.
+            assert!(spec["definitions"].get("PetPhoto").is_none());
+
+            let form = reqwest::blocking::multipart::Form::new()
+                .text("pet_id", "1")
+                .part(
+                    "photo",
+                    reqwest::blocking::multipart::Part::bytes(vec![0u8; 4]).file_name("pet.png"),
+                );
+            let resp = CLIENT
+                .post(&format!("http://{}/photos", addr))
+                .multipart(form)
+                .send()
+                .expect("request failed?");
+            assert_eq!(resp.status().as_u16(), 200);
+            assert_eq!(resp.json::<usize>().unwrap(), 2);
+        },
+    );
 }
@@ -0,0 +1,178 @@
+use paperclip::{bundle, PaperClipError, PaperClipResult};
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+
+/// Documents in a (fake) schema registry.
+fn registry() -> HashMap<&'static str, Value> {
+    let mut docs = HashMap::new();
+    docs.insert(
+        "https://schemas.example.com/common/Address.json",
+        json!({
+            "type": "object",
+            "properties": {
+                "country": { "$ref": "Country.json" },
+                "geo": { "$ref": "#/definitions/Geo" }
+            },
+            "definitions": {
+                "Geo": { "type": "array", "items": { "type": "number" } }
+            }
+        }),
+    );
+    docs.insert(
+        "https://schemas.example.com/common/Country.json",
+        json!({ "type": "string", "enum": ["DE", "IN", "US"] }),
+    );
+    docs.insert(
+        "https://schemas.example.com/common/types.json",
+        json!({
+            "definitions": {
+                "Money": { "type": "string", "pattern": "^[0-9]+\\.[0-9]{2}$" },
+                "Tree": {
+                    "type": "object",
+                    "properties": {
+                        "children": { "type": "array", "items": { "$ref": "#/definitions/Tree" } }
+                    }
+                }
+            }
+        }),
+    );
+
+    docs
+}
+
+/// Bundles the spec with the documents of the registry, and returns the fetched URLs.
+fn bundle(spec: &mut Value, base: Option<&str>) -> PaperClipResult<Vec<String>> {
+    let docs = registry();
+    let mut fetched = vec![];
+    bundle::bundle(spec, base, |url| {
+        fetched.push(url.to_owned());
+        docs.get(url).cloned().ok_or_else(|| {
+            PaperClipError::UnresolvedReference(url.into(), "not in registry".into())
+        })
+    })?;
+
+    fetched.sort();
+    Ok(fetched)
+}
+
+#[test]
+fn test_bundle_v2() {
+    let mut spec = json!({
+        "swagger": "2.0",
+        "definitions": {
+            "Address": { "type": "string" },
+            "Customer": {
+                "type": "object",
+                "properties": {
+                    "address": { "$ref": "../common/Address.json" },
+                    "billing": { "$ref": "https://schemas.example.com/common/Address.json#" },
+                    "balance": { "$ref": "../common/types.json#/definitions/Money" },
+                    "tree": { "$ref": "../common/types.json#/definitions/Tree" },
+                    "label": { "$ref": "#/definitions/Address" }
+                }
+            }
+        }
+    });
+
+    let fetched = bundle(&mut spec, Some("https://schemas.example.com/api/spec.json")).unwrap();
+    // Documents are fetched once.
+    assert_eq!(
+        fetched,
+        vec![
+            "https://schemas.example.com/common/Address.json",
+            "https://schemas.example.com/common/Country.json",
+            "https://schemas.example.com/common/types.json",
+        ]
+    );
+
+    let defs = &spec["definitions"];
+    let props = &defs["Customer"]["properties"];
+    // Existing names aren't overwritten.
+    assert_eq!(defs["Address"], json!({ "type": "string" }));
+    assert_eq!(props["label"]["$ref"], "#/definitions/Address");
+    assert_eq!(props["address"]["$ref"], "#/definitions/Address2");
+    assert_eq!(props["billing"]["$ref"], "#/definitions/Address2");
+    assert_eq!(props["balance"]["$ref"], "#/definitions/Money");
+    assert_eq!(props["tree"]["$ref"], "#/definitions/Tree");
+
+    // References in the fetched documents are resolved against their URLs.
+    let address = &defs["Address2"]["properties"];
+    assert_eq!(address["country"]["$ref"], "#/definitions/Country");
+    assert_eq!(address["geo"]["$ref"], "#/definitions/Geo");
+    assert_eq!(defs["Country"]["enum"], json!(["DE", "IN", "US"]));
+    assert_eq!(defs["Geo"]["items"]["type"], "number");
+    assert_eq!(defs["Money"]["type"], "string");
+    assert_eq!(
+        defs["Tree"]["properties"]["children"]["items"]["$ref"],
+        "#/definitions/Tree"
+    );
+}
+
+#[test]
+fn test_bundle_v3() {
+    let mut spec = json!({
+        "openapi": "3.0.0",
+        "paths": {
+            "/customers": {
+                "get": {
+                    "responses": {
+                        "200": {
+                            "description": "Customers",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": { "$ref": "https://schemas.example.com/common/types.json#/definitions/Money" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    bundle(&mut spec, None).unwrap();
+    let schema = &spec["paths"]["/customers"]["get"]["responses"]["200"]["content"]
+        ["application/json"]["schema"];
+    assert_eq!(schema["items"]["$ref"], "#/components/schemas/Money");
+    assert_eq!(spec["components"]["schemas"]["Money"]["type"], "string");
+}
+
+#[test]
+fn test_bundle_without_external_references() {
+    let mut spec = json!({
+        "definitions": {
+            "Pet": { "properties": { "owner": { "$ref": "#/definitions/Owner" } } },
+            "Owner": { "type": "object" }
+        }
+    });
+    let expected = spec.clone();
+
+    assert!(bundle(&mut spec, None).unwrap().is_empty());
+    assert_eq!(spec, expected);
+}
+
+#[test]
+fn test_bundle_errors() {
+    // Relative references need a base URL.
+    let mut spec = json!({ "definitions": { "Pet": { "$ref": "Pet.json" } } });
+    assert!(bundle(&mut spec, None).is_err());
+
+    // Missing documents and schemas.
+    let mut spec = json!({
+        "definitions": { "Pet": { "$ref": "https://schemas.example.com/Pet.json" } }
+    });
+    assert!(bundle(&mut spec, None).is_err());
+
+    let mut spec = json!({
+        "definitions": {
+            "Pet": { "$ref": "https://schemas.example.com/common/types.json#/definitions/Pet" }
+        }
+    });
+    assert!(bundle(&mut spec, None).is_err());
+
+    assert!(bundle(&mut json!({}), Some("not a URL")).is_err());
+}
@@ -0,0 +1,178 @@
+use serde_json::{json, Value};
+
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// Returns a fresh directory (in the target directory) for the given test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn paperclip(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_paperclip"))
+        .args(args)
+        .output()
+        .expect("running paperclip")
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn spec() -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": { "title": "Pets", "version": "1.0" },
+        "paths": {
+            "/pets": { "get": { "responses": { "200": { "description": "Pets" } } } }
+        },
+        "definitions": {
+            "Pet": { "type": "object", "properties": { "name": { "type": "string" } } }
+        }
+    })
+}
+
+#[test]
+fn test_integrity_verify() {
+    let dir = test_dir("integrity");
+    let spec_path = dir.join("spec.json");
+    let signed_path = dir.join("signed.json");
+    let hash_path = dir.join("spec.sha256");
+    fs::write(&spec_path, spec().to_string()).unwrap();
+
+    let out = paperclip(&[
+        "integrity".as_ref(),
+        "sign".as_ref(),
+        &spec_path,
+        "-o".as_ref(),
+        &signed_path,
+        "--detached".as_ref(),
+        &hash_path,
+    ]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let hash = fs::read_to_string(&hash_path).unwrap().trim().to_owned();
+
+    let out = paperclip(&["integrity".as_ref(), "verify".as_ref(), &signed_path]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), hash);
+
+    // The formatting of the spec doesn't matter.
+    let signed: Value = serde_json::from_slice(&fs::read(&signed_path).unwrap()).unwrap();
+    fs::write(&signed_path, serde_json::to_string_pretty(&signed).unwrap()).unwrap();
+    let out = paperclip(&["integrity".as_ref(), "verify".as_ref(), &signed_path]);
+    assert!(out.status.success(), "{}", stderr(&out));
+
+    // Tampered specs are rejected (with the embedded or the detached hash).
+    let mut tampered = signed;
+    tampered["definitions"]["Pet"]["properties"]["name"]["type"] = json!("integer");
+    let tampered_path = dir.join("tampered.json");
+    fs::write(&tampered_path, tampered.to_string()).unwrap();
+
+    let out = paperclip(&["integrity".as_ref(), "verify".as_ref(), &tampered_path]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert!(
+        stderr(&out).starts_with(&format!("hash mismatch (expected {}, found ", hash)),
+        "{}",
+        stderr(&out)
+    );
+
+    let out = paperclip(&[
+        "integrity".as_ref(),
+        "verify".as_ref(),
+        &tampered_path,
+        "--detached".as_ref(),
+        &hash_path,
+    ]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).contains("hash mismatch"), "{}", stderr(&out));
+
+    // So are specs without hashes.
+    let out = paperclip(&["integrity".as_ref(), "verify".as_ref(), &spec_path]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(stderr(&out).trim(), "spec doesn't have a hash");
+}
+
+/// Child process which is killed when it's dropped (i.e., even if the test fails).
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Returns the lines printed by the watcher (up to and including the summary of
+/// the next run).
+fn next_run(lines: &Receiver<String>) -> Vec<String> {
+    let mut run = vec![];
+    loop {
+        let line = lines
+            .recv_timeout(Duration::from_secs(60))
+            .expect("waiting for regeneration");
+        let done = line.starts_with("Regenerated code");
+        run.push(line);
+        if done {
+            return run;
+        }
+    }
+}
+
+#[test]
+fn test_watch_regenerates_on_change() {
+    let dir = test_dir("watch");
+    let spec_path = dir.join("spec.json");
+    let out_dir = dir.join("pets");
+    let mut spec = spec();
+    fs::write(&spec_path, spec.to_string()).unwrap();
+
+    let mut child = KillOnDrop(
+        Command::new(env!("CARGO_BIN_EXE_paperclip"))
+            .args(["--api", "v2", "--watch", "--poll-interval", "1", "-o"])
+            .arg(&out_dir)
+            .arg(&spec_path)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("running paperclip"),
+    );
+
+    let (tx, lines) = mpsc::channel();
+    let stdout = child.0.stdout.take().unwrap();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let run = next_run(&lines);
+    assert!(run.contains(&"added: pet.rs".to_owned()), "{:?}", run);
+    assert!(out_dir.join("pet.rs").is_file());
+    assert!(!out_dir.join("toy.rs").exists());
+
+    spec["definitions"]["Toy"] = json!({
+        "type": "object",
+        "properties": { "name": { "type": "string" } }
+    });
+    fs::write(&spec_path, spec.to_string()).unwrap();
+
+    let run = next_run(&lines);
+    assert!(run.contains(&"added: toy.rs".to_owned()), "{:?}", run);
+    assert!(!run.iter().any(|l| l == "changed: pet.rs"), "{:?}", run);
+    assert!(out_dir.join("toy.rs").is_file());
+
+    // Code isn't regenerated while the spec stays the same.
+    assert!(lines.recv_timeout(Duration::from_secs(3)).is_err());
+}
@@ -1,7 +1,8 @@
 use paperclip::v2::{
     self,
-    codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState},
+    codegen::{CrateMeta, DefaultEmitter, EmitMode, Emitter, EmitterState, ModuleLayout},
     models::{DefaultSchema, ResolvableApi},
+    LoadProgress, ResolveProgress,
 };
 
 use once_cell::sync::Lazy;
@@ -81,6 +82,15 @@ static CODEGEN_K8S_CLI: Lazy<()> = Lazy::new(|| {
     let emitter = DefaultEmitter::from(state);
     emitter.generate(&K8S_SCHEMA).expect("codegen");
 });
+static CODEGEN_K8S_FLAT: Lazy<()> = Lazy::new(|| {
+    let mut state = EmitterState::default();
+    state.working_dir = (&*ROOT).into();
+    state.working_dir.push("tests/test_k8s/flat");
+    state.layout = ModuleLayout::Flat;
+
+    let emitter = DefaultEmitter::from(state);
+    emitter.generate(&K8S_SCHEMA).expect("codegen");
+});
 
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -108,6 +118,7 @@ static CODEGEN: Lazy<()> = Lazy::new(|| {
     Lazy::force(&CODEGEN_PET_CLI);
     Lazy::force(&CODEGEN_K8S_LIB);
     Lazy::force(&CODEGEN_K8S_CLI);
+    Lazy::force(&CODEGEN_K8S_FLAT);
 });
 
 fn assert_file(path: &str) {
@@ -187,6 +198,39 @@ mod tests_pet {
     fn test_simple_any_enum() {
         assert_file("tests/test_pet/test_enum.rs");
     }
+
+    #[test]
+    fn test_load_in_stages() {
+        let path = ROOT.join("tests/pet-v2.yaml");
+        let len = std::fs::metadata(&path).expect("file?").len();
+        let mut events = vec![];
+        let fd = File::open(&path).expect("file?");
+        let api: ResolvableApi<DefaultSchema> =
+            v2::load(fd, Some(len), |p| events.push(p)).expect("loading spec");
+        assert_eq!(
+            api.definitions.keys().collect::<Vec<_>>(),
+            PET_SCHEMA.definitions.keys().collect::<Vec<_>>()
+        );
+
+        let (paths, defs) = (api.paths.len(), api.definitions.len());
+        assert!(events.contains(&LoadProgress::Parsing {
+            read: len,
+            total: Some(len)
+        }));
+        assert!(
+            events.contains(&LoadProgress::Resolving(ResolveProgress::Paths {
+                done: paths,
+                total: paths
+            }))
+        );
+        assert_eq!(
+            events.last(),
+            Some(&LoadProgress::Resolving(ResolveProgress::Definitions {
+                done: defs,
+                total: defs
+            }))
+        );
+    }
 }
 
 #[cfg(test)]
@@ -287,6 +331,11 @@ mod tests_k8s {
         assert_file("tests/test_k8s/io/k8s/apimachinery/pkg/apis/meta/v1/patch.rs");
     }
 
+    #[test]
+    fn test_flat_layout() {
+        assert_file("tests/test_k8s/flat/pod.rs");
+    }
+
     #[test]
     fn test_cli_manifest() {
         assert_file("tests/test_k8s/cli/Cargo.toml");
@@ -0,0 +1,171 @@
+use once_cell::sync::Lazy;
+use paperclip::fuzz::{self, FuzzTarget};
+use serde_json::{json, Value};
+
+use std::{fs, path::PathBuf};
+
+static ROOT: Lazy<PathBuf> = Lazy::new(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+
+/// Small spec with operations (with and without IDs) and parameters in every location.
+fn spec() -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": { "title": "Pets", "version": "1.0" },
+        "schemes": ["https", "http"],
+        "host": "pets.example.com",
+        "basePath": "/api/",
+        "paths": {
+            "/pets": {
+                "get": {
+                    "operationId": "listPets",
+                    "parameters": [{
+                        "name": "tags",
+                        "in": "query",
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "collectionFormat": "multi"
+                    }, {
+                        "name": "limit",
+                        "in": "query",
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 100
+                    }]
+                },
+                "post": {
+                    "operationId": "addPet",
+                    "parameters": [{
+                        "name": "body",
+                        "in": "body",
+                        "required": true,
+                        "schema": { "$ref": "#/definitions/Pet" }
+                    }]
+                }
+            },
+            "/pets/{id}": {
+                "parameters": [{ "name": "id", "in": "path", "type": "integer" }],
+                "get": { "operationId": "get_pet" },
+                "put": {
+                    "consumes": ["multipart/form-data"],
+                    "parameters": [
+                        { "name": "name", "in": "formData", "type": "string", "maxLength": 32 },
+                        { "name": "X-Request-Id", "in": "header", "type": "string", "format": "uuid" }
+                    ]
+                },
+                "delete": { "operationId": "GetPet" }
+            },
+            "/2fa": {
+                "post": { "operationId": "2fa" }
+            }
+        },
+        "definitions": {
+            "Pet": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "minLength": 1 },
+                    "status": { "type": "string", "enum": ["available", "sold"] },
+                    "born": { "type": "string", "format": "date-time" },
+                    "friends": { "type": "array", "items": { "$ref": "#/definitions/Pet" } }
+                },
+                "required": ["name"]
+            }
+        }
+    })
+}
+
+fn target(name: &str, method: &str, path: &str) -> FuzzTarget {
+    FuzzTarget {
+        name: name.into(),
+        method: method.into(),
+        path: path.into(),
+    }
+}
+
+#[test]
+fn test_targets() {
+    assert_eq!(
+        fuzz::targets(&spec()),
+        vec![
+            target("op_2fa", "POST", "/2fa"),
+            target("list_pets", "GET", "/pets"),
+            target("add_pet", "POST", "/pets"),
+            target("get_pet", "GET", "/pets/{id}"),
+            // Operations without IDs are named after their methods and paths.
+            target("put_pets_id", "PUT", "/pets/{id}"),
+            // Names are unique, even if the IDs are the same in snake case.
+            target("get_pet_2", "DELETE", "/pets/{id}"),
+        ]
+    );
+
+    assert!(fuzz::targets(&json!({ "paths": {} })).is_empty());
+}
+
+#[test]
+fn test_base_url() {
+    assert_eq!(fuzz::base_url(&spec()), "https://pets.example.com/api");
+    assert_eq!(fuzz::base_url(&json!({})), "http://localhost");
+    assert_eq!(
+        fuzz::base_url(&json!({ "host": "localhost:8080", "basePath": "/" })),
+        "http://localhost:8080"
+    );
+}
+
+#[test]
+fn test_files() {
+    let spec = spec();
+    let files = fuzz::files(&spec, "Pet Store", None);
+    let paths = files
+        .keys()
+        .map(|p| p.to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            ".gitignore",
+            "Cargo.toml",
+            "fuzz_targets/add_pet.rs",
+            "fuzz_targets/get_pet.rs",
+            "fuzz_targets/get_pet_2.rs",
+            "fuzz_targets/list_pets.rs",
+            "fuzz_targets/op_2fa.rs",
+            "fuzz_targets/put_pets_id.rs",
+            "spec.json",
+            "src/lib.rs",
+        ]
+    );
+
+    let manifest = &files[&PathBuf::from("Cargo.toml")];
+    assert!(manifest.contains("name = \"Pet Store-fuzz\""));
+    assert!(manifest.contains("[lib]\nname = \"pet_store_fuzz\""));
+    assert!(manifest.contains(
+        "[[bin]]\nname = \"get_pet_2\"\npath = \"fuzz_targets/get_pet_2.rs\"\ntest = false"
+    ));
+
+    let target = &files[&PathBuf::from("fuzz_targets/get_pet_2.rs")];
+    assert!(target.contains("pet_store_fuzz::run(\"DELETE\", \"/pets/{id}\", data);"));
+
+    let lib = &files[&PathBuf::from("src/lib.rs")];
+    assert!(lib.starts_with("//! Runtime for the fuzz targets"));
+    assert!(lib.ends_with("const DEFAULT_BASE_URL: &str = \"https://pets.example.com/api\";\n"));
+    let lib =
+        &fuzz::files(&spec, "pets", Some("http://localhost:8080"))[&PathBuf::from("src/lib.rs")];
+    assert!(lib.ends_with("const DEFAULT_BASE_URL: &str = \"http://localhost:8080\";\n"));
+
+    let written: Value = serde_json::from_str(&files[&PathBuf::from("spec.json")]).unwrap();
+    assert_eq!(written, spec);
+}
+
+/// Writes the fuzz crate for the spec, so that the runtime (and the targets) can be
+/// compiled (see `make test`).
+#[test]
+fn test_write_crate() {
+    let dir = ROOT.join("tests/test_fuzz");
+    let _ = fs::remove_dir_all(&dir);
+    for (path, contents) in fuzz::files(&spec(), "test-pet", Some("http://localhost:8080")) {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    assert!(dir.join("fuzz_targets/list_pets.rs").is_file());
+}
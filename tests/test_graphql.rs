@@ -0,0 +1,154 @@
+use paperclip::graphql;
+use serde_json::{json, Value};
+
+/// Small v2 spec with the constructs which are approximated in GraphQL.
+fn spec() -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": { "title": "Pets", "version": "1.0" },
+        "paths": {},
+        "definitions": {
+            "Pet": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "format": "int64" },
+                    "name": { "type": "string" },
+                    "weight": { "type": "number" },
+                    "vaccinated": { "type": "boolean" },
+                    "born_at": { "type": "string", "format": "date-time" },
+                    "owner": { "$ref": "#/definitions/Owner" },
+                    "size": { "$ref": "#/definitions/Size" },
+                    "nick-name": { "$ref": "#/definitions/Name" },
+                    "attributes": { "type": "object", "additionalProperties": { "type": "string" } },
+                    "location": {
+                        "type": "object",
+                        "properties": {
+                            "lat": { "type": "number" },
+                            "lng": { "type": "number" }
+                        },
+                        "required": ["lat", "lng"]
+                    },
+                    "grade": { "type": "string", "enum": ["A+", "B"] }
+                },
+                "required": ["id", "name", "nick-name"]
+            },
+            "Owner": {
+                "description": "Owner of pets.",
+                "type": "object",
+                "properties": {
+                    "pets": { "type": "array", "items": { "$ref": "#/definitions/Pet" } },
+                    "manager": { "allOf": [{ "$ref": "#/definitions/Owner" }] }
+                }
+            },
+            "Size": { "type": "string", "enum": ["small", "large"] },
+            "Name": { "type": "string", "minLength": 1 },
+            "Loop": { "$ref": "#/definitions/Loop" },
+            "PetLocation": {
+                "type": "object",
+                "properties": { "address": { "$ref": "#/definitions/Loop" } }
+            }
+        }
+    })
+}
+
+#[test]
+fn test_v2_definitions() {
+    assert_eq!(
+        graphql::sdl(&spec()),
+        r#"scalar DateTime
+
+scalar JSON
+
+"""
+Owner of pets.
+"""
+type Owner {
+  manager: Owner
+  pets: [Pet!]
+}
+
+type Pet {
+  attributes: JSON
+  born_at: DateTime
+  grade: String
+  id: Int!
+  location: PetLocation2
+  name: String!
+  nick_name: String!
+  owner: Owner
+  size: Size
+  vaccinated: Boolean
+  weight: Float
+}
+
+type PetLocation {
+  address: JSON
+}
+
+type PetLocation2 {
+  lat: Float!
+  lng: Float!
+}
+
+enum Size {
+  small
+  large
+}
+"#
+    );
+}
+
+#[test]
+fn test_v3_components() {
+    let spec = json!({
+        "openapi": "3.0.0",
+        "info": { "title": "Pets", "version": "1.0" },
+        "paths": {},
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "properties": {
+                        "tags": { "type": "array", "items": { "$ref": "#/components/schemas/Tag" } }
+                    }
+                },
+                "Tag": {
+                    "description": "Tag with \"\"\"quotes\"\"\".",
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"]
+                }
+            }
+        }
+    });
+
+    assert_eq!(
+        graphql::sdl(&spec),
+        r#"type Pet {
+  tags: [Tag!]
+}
+
+"""
+Tag with \"""quotes\""".
+"""
+type Tag {
+  name: String!
+}
+"#
+    );
+}
+
+#[test]
+fn test_specs_without_types() {
+    assert_eq!(graphql::sdl(&json!({ "paths": {} })), "");
+    assert_eq!(
+        graphql::sdl(&json!({
+            "definitions": {
+                "Name": { "type": "string" },
+                "Empty": { "type": "object" },
+                "Codes": { "type": "string", "enum": ["1xx", "2xx"] }
+            }
+        })),
+        ""
+    );
+}
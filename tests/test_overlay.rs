@@ -0,0 +1,219 @@
+use paperclip::overlay::Overlay;
+use serde_json::{json, Value};
+
+fn spec() -> Value {
+    json!({
+        "info": { "title": "Pets", "version": "1.0" },
+        "tags": [{ "name": "pets" }],
+        "paths": {
+            "/pets": {
+                "get": {
+                    "operationId": "listPets",
+                    "tags": ["pets"],
+                    "parameters": [
+                        { "name": "limit", "in": "query", "type": "integer" },
+                        { "name": "offset", "in": "query", "type": "integer" },
+                        { "name": "sort", "in": "query", "type": "string" },
+                        { "name": "filter", "in": "query", "type": "string" }
+                    ]
+                },
+                "post": { "operationId": "addPet" }
+            },
+            "/pets/{id}": {
+                "get": { "operationId": "getPet" },
+                "delete": { "operationId": "deletePet" }
+            }
+        }
+    })
+}
+
+/// Returns an overlay with the given actions.
+fn overlay(actions: Value) -> Overlay {
+    serde_json::from_value(json!({
+        "overlay": "1.0.0",
+        "info": { "title": "Test", "version": "1.0.0" },
+        "actions": actions
+    }))
+    .expect("invalid overlay")
+}
+
+#[test]
+fn test_wildcard_targets() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.paths.*.get",
+        "update": { "x-internal": false }
+    }, {
+        "target": "$.paths['/pets/{id}'].*",
+        "update": { "security": [] }
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    assert_eq!(spec["paths"]["/pets"]["get"]["x-internal"], false);
+    assert_eq!(spec["paths"]["/pets/{id}"]["get"]["x-internal"], false);
+    assert!(spec["paths"]["/pets"]["post"].get("x-internal").is_none());
+
+    assert_eq!(spec["paths"]["/pets/{id}"]["get"]["security"], json!([]));
+    assert_eq!(spec["paths"]["/pets/{id}"]["delete"]["security"], json!([]));
+    assert!(spec["paths"]["/pets"]["get"].get("security").is_none());
+}
+
+#[test]
+fn test_remove_array_elements() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.paths['/pets'].get.parameters[1]",
+        "remove": true
+    }, {
+        "target": "$.paths['/pets'].get.parameters[2]",
+        "remove": true
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    let names = |spec: &Value| {
+        spec["paths"]["/pets"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    // Actions are applied in order, so the second one targets the shifted array.
+    assert_eq!(names(&spec), vec!["limit", "sort"]);
+
+    // The matches of an action are removed from the end, so that the indices
+    // of the other matches are still valid.
+    let mut spec = self::spec();
+    overlay(json!([{
+        "target": "$.paths['/pets'].get.parameters[*]",
+        "remove": true
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+    assert!(names(&spec).is_empty());
+
+    let mut spec = self::spec();
+    overlay(json!([{
+        "target": "$.paths.*.*",
+        "remove": true
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+    assert_eq!(spec["paths"], json!({ "/pets": {}, "/pets/{id}": {} }));
+}
+
+#[test]
+fn test_update_appends_to_arrays() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.paths['/pets'].get",
+        "update": {
+            "tags": ["public"],
+            "parameters": [{ "name": "q", "in": "query", "type": "string" }]
+        }
+    }, {
+        "target": "$.tags",
+        "update": { "name": "public" }
+    }, {
+        "target": "$.paths['/pets'].post",
+        "update": { "tags": ["pets"] }
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    let op = &spec["paths"]["/pets"]["get"];
+    assert_eq!(op["tags"], json!(["pets", "public"]));
+    assert_eq!(op["parameters"].as_array().unwrap().len(), 5);
+    assert_eq!(op["parameters"][4]["name"], "q");
+    // Single values are pushed to the targeted arrays.
+    assert_eq!(
+        spec["tags"],
+        json!([{ "name": "pets" }, { "name": "public" }])
+    );
+    // Missing arrays are added.
+    assert_eq!(spec["paths"]["/pets"]["post"]["tags"], json!(["pets"]));
+}
+
+#[test]
+fn test_update_merges_objects() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.info",
+        "update": {
+            "title": "Pet store",
+            "contact": { "name": "API team" }
+        }
+    }, {
+        "target": "$.info",
+        "update": { "contact": { "email": "api@example.com" } }
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    assert_eq!(
+        spec["info"],
+        json!({
+            "title": "Pet store",
+            "version": "1.0",
+            "contact": { "name": "API team", "email": "api@example.com" }
+        })
+    );
+}
+
+#[test]
+fn test_targets_without_matches() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.paths['/toys'].get",
+        "update": { "description": "Toys" }
+    }, {
+        "target": "$.paths['/pets'].get.parameters[10]",
+        "remove": true
+    }, {
+        "target": "/definitions/Pet",
+        "remove": true
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    assert_eq!(spec, self::spec());
+
+    // Invalid targets are errors, though.
+    let res = overlay(json!([{ "target": "$.paths[", "remove": true }])).apply(&mut spec);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_update_and_remove() {
+    let mut spec = spec();
+    overlay(json!([{
+        "target": "$.paths['/pets/{id}'].get",
+        "update": { "summary": "Get a pet" }
+    }, {
+        "target": "$.paths['/pets/{id}'].delete",
+        "remove": true
+    }, {
+        "target": "$.paths['/pets/{id}'].delete",
+        "update": { "summary": "Delete a pet" }
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+
+    let item = &spec["paths"]["/pets/{id}"];
+    assert_eq!(item["get"]["summary"], "Get a pet");
+    // Removed nodes aren't matched by the later actions (so they're not added again).
+    assert!(item.get("delete").is_none());
+
+    // Actions with both `update` and `remove` remove the nodes.
+    let mut spec = self::spec();
+    overlay(json!([{
+        "target": "$.paths['/pets'].post",
+        "update": { "summary": "Add a pet" },
+        "remove": true
+    }]))
+    .apply(&mut spec)
+    .unwrap();
+    assert!(spec["paths"]["/pets"].get("post").is_none());
+}
@@ -0,0 +1,102 @@
+use paperclip::owners::{self, OwnedSurface};
+use serde_json::json;
+
+fn surface(operations: &[&str], definitions: &[&str]) -> OwnedSurface {
+    OwnedSurface {
+        operations: operations.iter().map(|s| s.to_string()).collect(),
+        definitions: definitions.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_v2_surface() {
+    let spec = json!({
+        "swagger": "2.0",
+        "basePath": "/api/",
+        "paths": {
+            "/charges": {
+                "parameters": [{ "name": "X-Tenant", "in": "header", "type": "string" }],
+                "x-owner": "team-platform",
+                "get": { "x-owner": "team-payments" },
+                "post": { "x-owner": "team-payments" },
+                "delete": {}
+            },
+            "/pets/{id}": {
+                "get": { "x-owner": "team-pets" },
+                "patch": { "x-owner": "team-payments" }
+            }
+        },
+        "definitions": {
+            "Charge": { "type": "object", "x-owner": "team-payments" },
+            "Pet": { "type": "object", "x-owner": "team-pets" },
+            "Error": { "type": "object" },
+            "Legacy": { "type": "object", "x-owner": ["team-pets"] }
+        }
+    });
+
+    let surface_by_owner = owners::surface_by_owner(&spec);
+    // Operations and definitions without (string) owners are left out, along with
+    // extensions of path items.
+    assert_eq!(
+        surface_by_owner.keys().collect::<Vec<_>>(),
+        vec!["team-payments", "team-pets"]
+    );
+    assert_eq!(
+        surface_by_owner["team-payments"],
+        surface(
+            &[
+                "GET /api/charges",
+                "POST /api/charges",
+                "PATCH /api/pets/{id}"
+            ],
+            &["Charge"]
+        )
+    );
+    assert_eq!(
+        surface_by_owner["team-pets"],
+        surface(&["GET /api/pets/{id}"], &["Pet"])
+    );
+}
+
+#[test]
+fn test_v3_surface() {
+    let spec = json!({
+        "openapi": "3.0.0",
+        "servers": [{ "url": "https://example.com/api" }],
+        "paths": {
+            "/charges": { "get": { "x-owner": "team-payments" } }
+        },
+        "components": {
+            "schemas": {
+                "Charge": { "type": "object", "x-owner": "team-payments" },
+                "Refund": { "type": "object", "x-owner": "team-refunds" }
+            }
+        }
+    });
+
+    let surface_by_owner = owners::surface_by_owner(&spec);
+    assert_eq!(
+        surface_by_owner["team-payments"],
+        surface(&["GET /charges"], &["Charge"])
+    );
+    assert_eq!(surface_by_owner["team-refunds"], surface(&[], &["Refund"]));
+
+    // Empty lists are left out when serialized.
+    assert_eq!(
+        serde_json::to_value(&surface_by_owner).unwrap(),
+        json!({
+            "team-payments": { "operations": ["GET /charges"], "definitions": ["Charge"] },
+            "team-refunds": { "definitions": ["Refund"] }
+        })
+    );
+}
+
+#[test]
+fn test_spec_without_owners() {
+    assert!(owners::surface_by_owner(&json!({})).is_empty());
+    assert!(owners::surface_by_owner(&json!({
+        "paths": { "/pets": { "get": {} } },
+        "definitions": { "Pet": { "type": "object" } }
+    }))
+    .is_empty());
+}
@@ -0,0 +1,292 @@
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use test_pet::{
+    client::{self, ConditionalSendable, Sendable, ServerClient, Validators},
+    miscellaneous::Miscellaneous,
+    pet::Pet,
+};
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    iter,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Request received by the fake server.
+#[derive(Debug, Clone)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    /// Headers (with lowercase names).
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Response sent by the fake server.
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(body: &str) -> Self {
+        Response {
+            status: 200,
+            headers: vec![("Content-Type", "application/json".into())],
+            body: body.into(),
+        }
+    }
+
+    fn status(status: u16) -> Self {
+        Response {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+}
+
+/// Requests received by the fake server (in the order they were received).
+type Requests = Arc<Mutex<Vec<Request>>>;
+
+/// Starts a server (which handles one connection at a time) responding with the given
+/// handler, and returns a client for it along with the requests received by it.
+fn serve<F>(handler: F) -> (ServerClient, Requests)
+where
+    F: Fn(&Request) -> Response + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").expect("binding server");
+    let addr = listener.local_addr().unwrap();
+    let requests = Requests::default();
+    let received = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+
+            if let Ok(req) = read_request(&stream) {
+                let resp = handler(&req);
+                received.lock().unwrap().push(req);
+                let _ = write_response(&stream, resp);
+            }
+        }
+    });
+
+    let client =
+        ServerClient::with_base_url(reqwest::Client::new(), format!("http://{}/api", addr));
+    (client, requests)
+}
+
+fn read_request(stream: &TcpStream) -> std::io::Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let url = url::Url::parse("http://localhost")
+        .unwrap()
+        .join(parts.next().unwrap_or_default())
+        .expect("invalid URL?");
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(i) = header.find(':') {
+            headers.insert(
+                header[..i].to_ascii_lowercase(),
+                header[i + 1..].trim().to_owned(),
+            );
+        }
+    }
+
+    let len = headers
+        .get("content-length")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path: url.path().into(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, resp: Response) -> std::io::Result<()> {
+    write!(stream, "HTTP/1.1 {} Fake\r\n", resp.status)?;
+    for (name, value) in &resp.headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        resp.body.len()
+    )?;
+    stream.write_all(&resp.body)?;
+    stream.flush()
+}
+
+fn pet_json(id: &str) -> String {
+    format!(r#"{{"id": {}, "name": "Pet {}"}}"#, id, id)
+}
+
+#[tokio::test]
+async fn test_deep_object_query() {
+    let (client, requests) = serve(|_| Response::json(r#""ok""#));
+
+    let resp = Miscellaneous::post_2()
+        .values(iter::once(iter::once(iter::once(iter::once("a")))))
+        .filter(vec![("name".to_owned(), "Milo"), ("age".to_owned(), "3")].into_iter())
+        .send(&client)
+        .await
+        .unwrap();
+    assert_eq!(resp.object, "ok");
+
+    let req = requests.lock().unwrap()[0].clone();
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.path, "/api/test/parameter/a");
+    assert_eq!(
+        req.query,
+        vec![
+            ("filter[age]".to_owned(), "3".to_owned()),
+            ("filter[name]".to_owned(), "Milo".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_gzip() {
+    let (client, requests) = serve(|req| {
+        let body = pet_json("1");
+        if !req
+            .header("accept-encoding")
+            .unwrap_or_default()
+            .contains("gzip")
+        {
+            return Response::json(&body);
+        }
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let mut resp = Response::json("");
+        resp.headers.push(("Content-Encoding", "gzip".into()));
+        resp.body = encoder.finish().unwrap();
+        resp
+    });
+
+    let add_pet = || Pet::<()>::add_pet().x_auth("secret").id(1).name("Milo");
+    // Compressed responses are decompressed.
+    let pet = add_pet().send(&client).await.unwrap();
+    assert_eq!(pet.name, "Pet 1");
+
+    let client = client.gzip_requests(true);
+    let pet = add_pet().send(&client).await.unwrap();
+    assert_eq!(pet.name, "Pet 1");
+
+    let requests = requests.lock().unwrap();
+    // Request bodies are compressed only when it's enabled.
+    assert_eq!(requests[0].header("content-encoding"), None);
+    let plain = String::from_utf8(requests[0].body.clone()).unwrap();
+    assert!(plain.contains("name: Milo"), "{}", plain);
+
+    assert_eq!(requests[1].header("content-encoding"), Some("gzip"));
+    let mut body = String::new();
+    GzDecoder::new(&requests[1].body[..])
+        .read_to_string(&mut body)
+        .unwrap();
+    assert_eq!(body, plain);
+}
+
+#[tokio::test]
+async fn test_batch() {
+    let (client, _) = serve(|req| {
+        let id = req.path.trim_start_matches("/api/pets/");
+        if id == "3" {
+            Response::status(404)
+        } else {
+            Response::json(&pet_json(id))
+        }
+    });
+
+    let results = client::batch(
+        &client,
+        (1..=5).map(|id| Pet::<()>::get_pet_by_id().pet_id(id)),
+        2,
+    )
+    .await;
+    assert_eq!(results.len(), 5);
+    // Results are in the same order as the requests.
+    for (i, result) in results.iter().enumerate() {
+        match result {
+            Ok(pet) => assert_eq!(pet.name, format!("Pet {}", i + 1)),
+            Err(client::ApiError::Failure(path, status, _)) => {
+                assert_eq!((path.as_str(), status.as_u16()), ("/pets/3", 404))
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    assert!(results[2].is_err());
+}
+
+#[tokio::test]
+async fn test_conditional_requests() {
+    let (client, requests) = serve(|req| {
+        if req.header("if-none-match") == Some("\"v1\"") {
+            return Response::status(304);
+        }
+
+        let mut resp = Response::json(&pet_json("1"));
+        resp.headers.push(("ETag", "\"v1\"".into()));
+        resp
+    });
+
+    let get_pet = || Pet::<()>::get_pet_by_id().pet_id(1);
+    let pet = get_pet().send(&client).await.unwrap();
+    assert_eq!(pet.name, "Pet 1");
+    let validators = pet.validators();
+    assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(validators.last_modified, None);
+
+    // Unmodified responses aren't returned.
+    let resp = get_pet()
+        .send_if_modified(&client, &validators)
+        .await
+        .unwrap();
+    assert!(resp.is_none());
+
+    // Requests without validators aren't conditional.
+    let pet = get_pet()
+        .send_if_modified(&client, &Validators::default())
+        .await
+        .unwrap()
+        .expect("modified response");
+    assert_eq!(pet.name, "Pet 1");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[1].header("if-none-match"), Some("\"v1\""));
+    assert_eq!(requests[2].header("if-none-match"), None);
+}
@@ -0,0 +1,138 @@
+use paperclip::redact;
+use serde_json::{json, Value};
+
+/// Small spec with sensitive fields and parameters (and examples which have them).
+fn spec() -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": { "title": "Users", "version": "1.0" },
+        "paths": {
+            "/login": {
+                "post": {
+                    "parameters": [{
+                        "name": "user",
+                        "in": "formData",
+                        "type": "string",
+                        "x-example": "alice"
+                    }, {
+                        "name": "password",
+                        "in": "formData",
+                        "type": "string",
+                        "x-example": "hunter2",
+                        "default": "changeme",
+                        "x-sensitive": true
+                    }],
+                    "responses": {
+                        "200": {
+                            "description": "Session",
+                            "schema": {
+                                "type": "object",
+                                "properties": {
+                                    "token": { "type": "string", "x-sensitive": true },
+                                    "expires_in": { "type": "integer" }
+                                },
+                                "example": { "token": "abc", "expires_in": 3600 }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "User": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "example": "Alice", "default": "" },
+                    "ssn": {
+                        "type": "string",
+                        "example": "078-05-1120",
+                        "default": "000-00-0000",
+                        "x-sensitive": true
+                    },
+                    "credentials": {
+                        "type": "object",
+                        "properties": { "key": { "type": "string" } },
+                        "example": { "key": "secret" },
+                        "x-sensitive": true
+                    },
+                    "roles": { "type": "array", "items": { "type": "string" } }
+                },
+                "example": { "name": "Alice", "ssn": "078-05-1120", "roles": ["admin"] },
+                "default": { "name": "", "ssn": "000-00-0000" },
+                "examples": [
+                    { "name": "Alice", "ssn": "078-05-1120" },
+                    { "name": "Bob", "credentials": { "key": "secret" } }
+                ]
+            }
+        }
+    })
+}
+
+#[test]
+fn test_strip_sensitive() {
+    let mut spec = spec();
+    redact::strip_sensitive(&mut spec);
+
+    let params = &spec["paths"]["/login"]["post"]["parameters"];
+    assert_eq!(params[0]["x-example"], "alice");
+    assert_eq!(
+        params[1],
+        json!({
+            "name": "password",
+            "in": "formData",
+            "type": "string",
+            "x-sensitive": true
+        })
+    );
+
+    let session = &spec["paths"]["/login"]["post"]["responses"]["200"]["schema"];
+    assert_eq!(session["example"], json!({ "expires_in": 3600 }));
+
+    let user = &spec["definitions"]["User"];
+    assert_eq!(user["properties"]["name"]["example"], "Alice");
+    assert_eq!(user["properties"]["name"]["default"], "");
+    assert_eq!(
+        user["properties"]["ssn"],
+        json!({ "type": "string", "x-sensitive": true })
+    );
+    assert!(user["properties"]["credentials"].get("example").is_none());
+    assert_eq!(
+        user["example"],
+        json!({ "name": "Alice", "roles": ["admin"] })
+    );
+    assert_eq!(user["default"], json!({ "name": "" }));
+    assert_eq!(
+        user["examples"],
+        json!([{ "name": "Alice" }, { "name": "Bob" }])
+    );
+}
+
+#[test]
+fn test_strip_without_sensitive_fields() {
+    let mut spec = spec();
+    // Values which aren't `true` don't mark fields as sensitive.
+    for pointer in &[
+        "/paths/~1login/post/parameters/1",
+        "/paths/~1login/post/responses/200/schema/properties/token",
+        "/definitions/User/properties/ssn",
+        "/definitions/User/properties/credentials",
+    ] {
+        spec.pointer_mut(pointer).unwrap()["x-sensitive"] = json!("false");
+    }
+
+    let expected = spec.clone();
+    redact::strip_sensitive(&mut spec);
+    assert_eq!(spec, expected);
+}
+
+#[test]
+fn test_is_sensitive() {
+    let spec = spec();
+    assert!(redact::is_sensitive(
+        &spec["definitions"]["User"]["properties"]["ssn"]
+    ));
+    assert!(!redact::is_sensitive(
+        &spec["definitions"]["User"]["properties"]["name"]
+    ));
+    assert!(!redact::is_sensitive(&json!({ "x-sensitive": false })));
+}
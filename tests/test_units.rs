@@ -0,0 +1,102 @@
+use paperclip::units;
+use serde_json::{json, Value};
+
+/// Small spec with numeric fields (with and without units).
+fn spec() -> Value {
+    json!({
+        "swagger": "2.0",
+        "info": { "title": "Jobs", "version": "1.0" },
+        "paths": {
+            "/jobs": {
+                "get": {
+                    "parameters": [{
+                        "name": "timeout",
+                        "in": "query",
+                        "type": "integer",
+                        "description": "Timeout",
+                        "x-unit": "seconds"
+                    }],
+                    "responses": {
+                        "200": {
+                            "description": "Job",
+                            "schema": {
+                                "type": "object",
+                                "properties": { "elapsed": { "type": "number" } }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "definitions": {
+            "Config": {
+                "type": "object",
+                "properties": {
+                    "timeout": {
+                        "type": "integer",
+                        "description": "Timeout of requests.",
+                        "x-unit": "milliseconds"
+                    },
+                    "age": { "type": "number", "x-unit": "years" },
+                    "max_size": {
+                        "type": "integer",
+                        "description": "Maximum size in bytes",
+                        "x-unit": "bytes"
+                    },
+                    "retryDelay": { "type": "integer" },
+                    "cacheTTL": { "type": "integer" },
+                    "read/timeout": { "type": "integer" },
+                    "max_size_bytes": { "type": "integer" },
+                    "pollIntervalMs": { "type": "integer" },
+                    "page_size": { "type": "integer" },
+                    "retry_count": { "type": "integer" },
+                    "wait": { "type": "string" },
+                    "limits": {
+                        "type": "object",
+                        "properties": { "max-size": { "type": "number" } }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[test]
+fn test_unitless_fields() {
+    assert_eq!(
+        units::unitless_fields(&spec()),
+        vec![
+            "#/definitions/Config/properties/limits/properties/max-size",
+            "#/definitions/Config/properties/cacheTTL",
+            "#/definitions/Config/properties/read~1timeout",
+            "#/definitions/Config/properties/retryDelay",
+            "#/paths/~1jobs/get/responses/200/schema/properties/elapsed",
+        ]
+    );
+
+    assert!(units::unitless_fields(&json!({})).is_empty());
+}
+
+#[test]
+fn test_describe_units() {
+    let mut spec = spec();
+    units::describe_units(&mut spec);
+
+    let param = &spec["paths"]["/jobs"]["get"]["parameters"][0];
+    assert_eq!(param["description"], "Timeout (in seconds)");
+
+    let props = &spec["definitions"]["Config"]["properties"];
+    assert_eq!(
+        props["timeout"]["description"],
+        "Timeout of requests (in milliseconds)."
+    );
+    assert_eq!(props["age"]["description"], "In years.");
+    // Descriptions mentioning the units are left as they are.
+    assert_eq!(props["max_size"]["description"], "Maximum size in bytes");
+    assert!(props["retryDelay"].get("description").is_none());
+
+    // Units are described once.
+    let described = spec.clone();
+    units::describe_units(&mut spec);
+    assert_eq!(spec, described);
+}
@@ -0,0 +1,377 @@
+use paperclip::v2::{
+    builder::{OperationBuilder, ParameterBuilder, SchemaBuilder},
+    models::{
+        DataType, DefaultApiRaw, DefaultSchemaRaw, Either, HttpMethod, ParameterIn, SecurityScheme,
+        Server, ServerVariable,
+    },
+    test_util,
+};
+use proptest::prelude::*;
+use serde_json::Value;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Checks that the (JSON) v3 schema has the same content as the v2 schema.
+fn assert_schema(v2: &DefaultSchemaRaw, v3: &Value, loc: &str) {
+    if let Some(reference) = &v2.reference {
+        let reference = reference.replace("#/definitions/", "#/components/schemas/");
+        assert_eq!(v3["$ref"], reference, "reference at {}", loc);
+        return;
+    }
+
+    let data_type = match v2.data_type {
+        Some(DataType::File) => Some("string".into()),
+        Some(t) => serde_json::to_value(t).ok(),
+        None => None,
+    };
+    assert_eq!(v3.get("type"), data_type.as_ref(), "type at {}", loc);
+
+    if let Some(items) = &v2.items {
+        assert_schema(items, &v3["items"], &format!("{}/items", loc));
+    }
+
+    let properties = v3["properties"].as_object().cloned().unwrap_or_default();
+    assert_eq!(
+        properties.keys().collect::<BTreeSet<_>>(),
+        v2.properties.keys().collect::<BTreeSet<_>>(),
+        "properties at {}",
+        loc
+    );
+    for (name, schema) in &v2.properties {
+        assert_schema(schema, &properties[name], &format!("{}/{}", loc, name));
+    }
+
+    assert_eq!(
+        names(&v3["required"]),
+        v2.required.iter().cloned().collect(),
+        "required properties at {}",
+        loc
+    );
+}
+
+fn names(v: &Value) -> BTreeSet<String> {
+    v.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.as_str().map(String::from))
+        .collect()
+}
+
+fn assert_v3_content(v2: &DefaultApiRaw, v3: &Value) {
+    let schemas = v3["components"]["schemas"].as_object().unwrap();
+    assert_eq!(
+        schemas.keys().collect::<BTreeSet<_>>(),
+        v2.definitions.keys().collect::<BTreeSet<_>>()
+    );
+    for (name, schema) in &v2.definitions {
+        assert_schema(schema, &schemas[name], name);
+    }
+
+    assert_eq!(
+        v3["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<BTreeSet<_>>(),
+        v2.paths.keys().collect::<BTreeSet<_>>()
+    );
+
+    for (path, item) in &v2.paths {
+        for (method, op) in &item.methods {
+            let method = serde_json::to_value(method).unwrap();
+            let method = method.as_str().unwrap();
+            let v3_op = &v3["paths"][path][method];
+            let loc = format!("{} {}", method, path);
+
+            for param in op.parameters.iter().filter_map(Either::right) {
+                let loc = format!("{} ({:?} parameter {})", loc, param.in_, param.name);
+                let body = &v3_op["requestBody"];
+                match param.in_ {
+                    ParameterIn::Body => {
+                        assert_eq!(
+                            body["required"].as_bool().unwrap_or_default(),
+                            param.required
+                        );
+                        let media = body["content"].as_object().unwrap().values().next();
+                        assert_schema(
+                            param.schema.as_ref().unwrap(),
+                            &media.unwrap()["schema"],
+                            &loc,
+                        );
+                    }
+                    ParameterIn::FormData => {
+                        for media in body["content"].as_object().unwrap().values() {
+                            let schema = &media["schema"];
+                            assert!(schema["properties"].get(&param.name).is_some(), "{}", loc);
+                            assert_eq!(
+                                names(&schema["required"]).contains(&param.name),
+                                param.required,
+                                "{}",
+                                loc
+                            );
+                        }
+                    }
+                    in_ => {
+                        let in_ = serde_json::to_value(in_).unwrap();
+                        let v3_param = v3_op["parameters"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .find(|p| p["name"] == param.name.as_str() && p["in"] == in_)
+                            .unwrap_or_else(|| panic!("missing {}", loc));
+                        assert_eq!(
+                            v3_param["required"].as_bool().unwrap_or_default(),
+                            param.required,
+                            "{}",
+                            loc
+                        );
+                        assert_eq!(
+                            v3_param["schema"]["type"],
+                            serde_json::to_value(param.data_type).unwrap(),
+                            "{}",
+                            loc
+                        );
+                    }
+                }
+            }
+
+            let responses = v3_op["responses"].as_object().unwrap();
+            assert_eq!(
+                responses.keys().collect::<BTreeSet<_>>(),
+                op.responses.keys().collect::<BTreeSet<_>>(),
+                "responses of {}",
+                loc
+            );
+            for (code, resp) in &op.responses {
+                let resp = resp.right().unwrap();
+                let v3_resp = &responses[code];
+                assert_eq!(v3_resp["description"], resp.description.as_deref().unwrap());
+                if let Some(schema) = &resp.schema {
+                    let media = v3_resp["content"].as_object().unwrap().values().next();
+                    assert_schema(schema, &media.unwrap()["schema"], &loc);
+                }
+            }
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_v2_to_v3_preserves_content(api in test_util::api()) {
+        let spec: openapiv3::OpenAPI = api.clone().into();
+        assert_v3_content(&api, &serde_json::to_value(&spec).unwrap());
+    }
+}
+
+#[test]
+fn test_v3_security_schemes() {
+    let mut api = DefaultApiRaw::default();
+    let scheme =
+        |type_: &str, in_: Option<&str>, name: Option<&str>, flow: Option<&str>| SecurityScheme {
+            type_: type_.into(),
+            in_: in_.map(Into::into),
+            name: name.map(Into::into),
+            flow: flow.map(Into::into),
+            auth_url: Some("https://example.com/auth".into()),
+            token_url: Some("https://example.com/token".into()),
+            ..Default::default()
+        };
+    let schemes = vec![
+        (
+            "bearer",
+            scheme("apiKey", Some("header"), Some("Authorization"), None),
+        ),
+        (
+            "key",
+            scheme("apiKey", Some("header"), Some("X-API-Key"), None),
+        ),
+        (
+            "session",
+            scheme("apiKey", Some("cookie"), Some("id"), None),
+        ),
+        ("app", scheme("oauth2", None, None, Some("application"))),
+        ("code", scheme("oauth2", None, None, Some("accessCode"))),
+    ];
+    for (name, scheme) in schemes {
+        api.security_definitions.insert(name.into(), scheme);
+    }
+
+    let mut requirement = BTreeMap::new();
+    requirement.insert(
+        "code".into(),
+        vec!["pets.read".into()].into_iter().collect(),
+    );
+    api.security.push(requirement);
+
+    let spec: openapiv3::OpenAPI = api.into();
+    let spec = serde_json::to_value(&spec).unwrap();
+    let schemes = &spec["components"]["securitySchemes"];
+    assert_eq!(schemes["bearer"]["type"], "http");
+    assert_eq!(schemes["key"]["type"], "apiKey");
+    assert_eq!(schemes["key"]["in"], "header");
+    assert_eq!(schemes["key"]["name"], "X-API-Key");
+    assert_eq!(schemes["session"]["in"], "cookie");
+    assert_eq!(schemes["session"]["name"], "id");
+    assert_eq!(
+        schemes["app"]["flows"]["clientCredentials"]["tokenUrl"],
+        "https://example.com/token"
+    );
+    assert_eq!(
+        schemes["code"]["flows"]["authorizationCode"]["authorizationUrl"],
+        "https://example.com/auth"
+    );
+    assert_eq!(
+        spec["security"],
+        serde_json::json!([{"code": ["pets.read"]}])
+    );
+}
+
+#[test]
+fn test_v3_webhooks() {
+    let mut api = DefaultApiRaw::default();
+    OperationBuilder::new()
+        .summary("Sent when an order is created")
+        .parameter(ParameterBuilder::body(SchemaBuilder::reference("Order")))
+        .response(200, "Webhook was received", None)
+        .add_to(&mut api, "/orders", HttpMethod::Post);
+    let spec: openapiv3::OpenAPI = api.clone().into();
+    let spec = serde_json::to_value(&spec).unwrap();
+    assert_eq!(spec["openapi"], "3.0.0");
+    assert!(spec.get("webhooks").is_none());
+
+    // Webhooks are path items keyed by their names.
+    api.webhooks = std::mem::take(&mut api.paths)
+        .into_iter()
+        .map(|(_, item)| ("order.created".into(), item))
+        .collect();
+    let spec: openapiv3::OpenAPI = api.into();
+    let spec = serde_json::to_value(&spec).unwrap();
+    assert_eq!(spec["openapi"], "3.1.0");
+    assert_eq!(
+        spec["webhooks"]["order.created"]["post"]["requestBody"]["content"]["application/json"]
+            ["schema"]["$ref"],
+        "#/components/schemas/Order"
+    );
+}
+
+#[test]
+fn test_v3_servers() {
+    let mut api = DefaultApiRaw::default();
+    api.base_path = Some("/api".into());
+    let spec: openapiv3::OpenAPI = api.clone().into();
+    let spec = serde_json::to_value(&spec).unwrap();
+    assert_eq!(spec["servers"][0]["url"], "/api");
+
+    let mut region = ServerVariable {
+        default: "eu".into(),
+        ..Default::default()
+    };
+    region.enum_ = vec!["eu".into(), "us".into()];
+    let mut server = Server {
+        url: "https://{region}.example.com/api".into(),
+        description: Some("Production".into()),
+        ..Default::default()
+    };
+    server.variables.insert("region".into(), region);
+    api.servers = vec![
+        server,
+        Server {
+            url: "http://localhost:8080/api".into(),
+            description: Some("Local".into()),
+            ..Default::default()
+        },
+    ];
+
+    let spec: openapiv3::OpenAPI = api.into();
+    let spec = serde_json::to_value(&spec).unwrap();
+    assert_eq!(
+        spec["servers"],
+        serde_json::json!([
+            {
+                "url": "https://{region}.example.com/api",
+                "description": "Production",
+                "variables": {
+                    "region": { "enum": ["eu", "us"], "default": "eu" }
+                }
+            },
+            {
+                "url": "http://localhost:8080/api",
+                "description": "Local"
+            }
+        ])
+    );
+}
+
+#[test]
+fn test_v31_schemas() {
+    let spec: openapiv3::OpenAPI = serde_json::from_value(serde_json::json!({
+        "openapi": "3.0.0",
+        "info": { "title": "Pets", "version": "1.0" },
+        "paths": {
+            "/pets": {
+                "get": {
+                    "parameters": [{
+                        "name": "limit",
+                        "in": "query",
+                        "schema": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "exclusiveMinimum": true,
+                            "nullable": true
+                        }
+                    }],
+                    "responses": { "200": { "description": "OK" } }
+                }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "example": "Tom" },
+                        "kind": { "type": "string", "enum": ["cat", "dog"], "nullable": true },
+                        "owner": {
+                            "allOf": [{ "$ref": "#/components/schemas/Owner" }],
+                            "nullable": true
+                        }
+                    }
+                },
+                "Owner": { "type": "object" },
+                "Status": {
+                    "oneOf": [
+                        { "type": "string", "enum": ["available"], "description": "Available for adoption." },
+                        { "type": "string", "enum": ["sold"] }
+                    ]
+                }
+            }
+        }
+    }))
+    .unwrap();
+
+    let spec = paperclip::v3::openapiv3_to_v31(&spec);
+    assert_eq!(spec["openapi"], "3.1.0");
+    assert_eq!(
+        spec["components"]["schemas"]["Pet"]["properties"],
+        serde_json::json!({
+            "name": { "type": "string", "examples": ["Tom"] },
+            "kind": { "type": ["string", "null"], "enum": ["cat", "dog", null] },
+            "owner": {
+                "anyOf": [{ "$ref": "#/components/schemas/Owner" }, { "type": "null" }]
+            }
+        })
+    );
+    assert_eq!(
+        spec["paths"]["/pets"]["get"]["parameters"][0]["schema"],
+        serde_json::json!({ "type": ["integer", "null"], "exclusiveMinimum": 0 })
+    );
+    assert_eq!(
+        spec["components"]["schemas"]["Status"],
+        serde_json::json!({
+            "oneOf": [
+                { "type": "string", "const": "available", "description": "Available for adoption." },
+                { "type": "string", "const": "sold" }
+            ]
+        })
+    );
+}